@@ -0,0 +1,54 @@
+// every chapter module has its own FrameLoop, so the chapter passes in the one of its module.
+// render gets a Frame every animation frame, its time, delta time, frame index, resolution and mouse are
+// also in the frame uniforms of the shaders
+export function startFrameLoop(FrameLoop, canvas, render) {
+    const frameLoop = FrameLoop.start(canvas, render);
+
+    // p pauses and resumes, t switches between a fixed timestep of 1/60 s and a variable one. space is taken
+    // by the fly camera and shift+p by the downloads of the chapters
+    let fixedTimestep = false;
+    function onKeyDown(event) {
+        if (event.code === "KeyP" && !event.shiftKey) {
+            if (frameLoop.is_paused()) {
+                frameLoop.resume();
+            } else {
+                frameLoop.pause();
+            }
+            console.log(frameLoop.is_paused() ? "paused" : "resumed");
+        } else if (event.code === "KeyT") {
+            fixedTimestep = !fixedTimestep;
+            if (fixedTimestep) {
+                frameLoop.set_fixed_timestep(1 / 60);
+            } else {
+                frameLoop.set_variable_timestep();
+            }
+            console.log(fixedTimestep ? "fixed timestep" : "variable timestep");
+        }
+    }
+    window.addEventListener("keydown", onKeyDown);
+
+    // stops the loop for good, e.g. before another chapter takes over the canvas
+    return () => {
+        window.removeEventListener("keydown", onKeyDown);
+        frameLoop.free();
+    };
+}
+
+// , and . scrub the keyframe animation of the scene back and forth by a tenth of a second, / plays and pauses it
+export function addTimelineKeys(scene) {
+    function onKeyDown(event) {
+        if (event.key === "," || event.key === ".") {
+            scene.pause_animation();
+            scene.set_animation_time(Math.max(0, scene.animation_time() + (event.key === "," ? -0.1 : 0.1)));
+            console.log(`animation at ${scene.animation_time().toFixed(1)} of ${scene.animation_duration()} s`);
+        } else if (event.key === "/") {
+            if (scene.is_animation_playing()) {
+                scene.pause_animation();
+            } else {
+                scene.play_animation();
+            }
+        }
+    }
+    window.addEventListener("keydown", onKeyDown);
+    return () => window.removeEventListener("keydown", onKeyDown);
+}
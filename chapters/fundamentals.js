@@ -1,4 +1,5 @@
-import { initFundamentals } from "../wasm_modules_initialization/fundamentals_init.js";
+import { initFundamentals, FrameLoop } from "../wasm_modules_initialization/fundamentals_init.js";
+import { startFrameLoop } from "./animation.js";
 
 
 export async function mainFundamentals(canvas) {
@@ -44,4 +45,7 @@ export async function mainFundamentals(canvas) {
         }
     });
     observer.observe(canvas);
+
+    // renders every animation frame, returns what stops it
+    return startFrameLoop(FrameLoop, canvas, (frame) => scene.render_frame(frame));
 }
@@ -1,4 +1,5 @@
-import { initInterStageVariables } from "../wasm_modules_initialization/inter_stage_variables_init.js";
+import { initInterStageVariables, FrameLoop } from "../wasm_modules_initialization/inter_stage_variables_init.js";
+import { startFrameLoop } from "./animation.js";
 
 
 function fail(msg) {
@@ -55,4 +56,7 @@ export async function mainInterStageVariables(canvas) {
         }
     });
     observer.observe(canvas);
+
+    // renders every animation frame, returns what stops it
+    return startFrameLoop(FrameLoop, canvas, (frame) => scene.render_frame(frame));
 }
@@ -1,4 +1,5 @@
-import { initLoadingImages } from "../wasm_modules_initialization/loading_images_init.js";
+import { initLoadingImages, FrameLoop } from "../wasm_modules_initialization/loading_images_init.js";
+import { startFrameLoop } from "./animation.js";
 import * as dat from "dat.gui";
 
 const settings = {
@@ -76,11 +77,14 @@ export async function mainLoadingImages(canvas) {
 
     const scene = await initLoadingImages(device, context, gpuTextureFormat, imageBitmap);
 
-    function render() {
-        const ndx = (settings.addressModeU === "repeat" ? 1 : 0) +
+    function samplerNdx() {
+        return (settings.addressModeU === "repeat" ? 1 : 0) +
             (settings.addressModeV === "repeat" ? 2 : 0) +
             (settings.magFilter === "linear" ? 4 : 0);
-        scene.render(ndx);
+    }
+
+    function render() {
+        scene.render(samplerNdx());
     };
 
     addGUI(render);
@@ -96,4 +100,7 @@ export async function mainLoadingImages(canvas) {
         }
     });
     observer.observe(canvas);
+
+    // renders every animation frame, returns what stops it
+    return startFrameLoop(FrameLoop, canvas, (frame) => scene.render_frame(frame, samplerNdx()));
 }
@@ -1,10 +1,21 @@
-import { initStorageBuffers } from "../wasm_modules_initialization/storage_buffers_init.js";
+import {
+    initStorageBuffers, FrameLoop, InstanceProperty, Interpolation, Easing, Repeat,
+} from "../wasm_modules_initialization/storage_buffers_init.js";
+import { startFrameLoop, addTimelineKeys } from "./animation.js";
 
 
 function fail(msg) {
     alert(msg);
 }
 
+function downloadJson(json, fileName) {
+    const link = document.createElement("a");
+    link.href = URL.createObjectURL(new Blob([json], { type: "application/json" }));
+    link.download = fileName;
+    link.click();
+    URL.revokeObjectURL(link.href);
+}
+
 export async function mainStorageBuffers(canvas) {
     if (!navigator.gpu) {
         fail("this browser does not support WebGPU");
@@ -41,18 +52,71 @@ export async function mainStorageBuffers(canvas) {
         format: gpuTextureFormat,
     });
 
-    const scene = await initStorageBuffers(device, context, gpuTextureFormat);
+    // ?seed=42 in the url recreates the scene that seed generated
+    const seedParam = new URLSearchParams(window.location.search).get("seed");
+    const scene = await initStorageBuffers(device, context, gpuTextureFormat, seedParam === null ? null : Number(seedParam));
+    console.log(`scene seed ${scene.seed()}`);
+
+    // the first circles swell, drift across and swap their color for the opposite one, then play it backwards
+    for (let id = 0; id < Math.min(10, scene.instance_count()); id++) {
+        const [r, g, b, a, offsetX, offsetY, scale] = scene.instance(id);
+        scene.animate_instance(
+            id, InstanceProperty.Scale, Interpolation.CubicSpline, Easing.Linear,
+            [0, 1, 2], [0, scale, 0, 0, scale * 1.5, 0, 0, scale, 0],
+        );
+        scene.animate_instance(
+            id, InstanceProperty.Offset, Interpolation.Linear, Easing.SineInOut,
+            [0, 2], [offsetX, offsetY, -offsetX, offsetY],
+        );
+        scene.animate_instance(
+            id, InstanceProperty.Color, Interpolation.Step, Easing.Linear,
+            [0, 1], [r, g, b, a, 1 - r, 1 - g, 1 - b, a],
+        );
+    }
+    scene.set_animation_repeat(Repeat.PingPong);
 
     const observer = new ResizeObserver(entries => {
         for (const entry of entries) {
-            const canvas = entry.target;
             const width = entry.contentBoxSize[0].inlineSize;
             const height = entry.contentBoxSize[0].blockSize;
-            canvas.width = Math.min(width, device.limits.maxTextureDimension2D);
-            canvas.height = Math.min(height, device.limits.maxTextureDimension2D);
+            scene.resize(width, height, window.devicePixelRatio);
             // re-render
             scene.render();
         }
     });
     observer.observe(canvas);
+
+    // shift+p downloads the seed and the color, offset and scale of every object, to attach to bug reports
+    window.addEventListener("keydown", (event) => {
+        if (event.shiftKey && event.key === "P") {
+            const parameters = Array.from(scene.object_parameters());
+            const objects = [];
+            for (let i = 0; i < parameters.length; i += 7) {
+                const [r, g, b, a, offsetX, offsetY, scale] = parameters.slice(i, i + 7);
+                objects.push({ color: [r, g, b, a], offset: [offsetX, offsetY], scale });
+            }
+            downloadJson(JSON.stringify({ seed: scene.seed(), objects }, null, 2), "storage_buffers_scene.json");
+        }
+    });
+
+    // + doubles the circles, 0 removes them all, to find out how many the device can draw
+    window.addEventListener("keydown", (event) => {
+        if (event.key === "+") {
+            scene.add_random_instances(Math.max(scene.instance_count(), 100));
+        } else if (event.key === "0") {
+            scene.clear_instances();
+        } else {
+            return;
+        }
+        scene.render();
+        console.log(`${scene.instance_count()} circles`);
+    });
+
+    // renders every animation frame, returns what stops it
+    const removeTimelineKeys = addTimelineKeys(scene);
+    const stopFrameLoop = startFrameLoop(FrameLoop, canvas, (frame) => scene.render_frame(frame));
+    return () => {
+        removeTimelineKeys();
+        stopFrameLoop();
+    };
 }
@@ -1,4 +1,7 @@
-import { initTextures } from "../wasm_modules_initialization/textures_init.js";
+import {
+    initTextures, Camera, CameraController, Projection, FrameLoop, TransformProperty, Interpolation, Easing, Repeat,
+} from "../wasm_modules_initialization/textures_init.js";
+import { startFrameLoop, addTimelineKeys } from "./animation.js";
 
 
 function fail(msg) {
@@ -43,22 +46,85 @@ export async function mainTextures(canvas) {
 
     const scene = await initTextures(device, context, gpuTextureFormat);
 
+    // the quads bob up one after the other and the bottom row rocks from side to side, then back again
+    for (let i = 0; i < 8; i++) {
+        scene.animate_quad(
+            i, TransformProperty.Translation, Interpolation.Linear, Easing.SineInOut,
+            [0, 1 + (i % 4) * 0.25], [0, 0, 0, 0, 0.2, 0],
+        );
+        if (i >= 4) {
+            const angle = 0.3;
+            scene.animate_quad(
+                i, TransformProperty.Rotation, Interpolation.Linear, Easing.QuadInOut,
+                [0, 2], [0, 0, -Math.sin(angle / 2), Math.cos(angle / 2), 0, 0, Math.sin(angle / 2), Math.cos(angle / 2)],
+            );
+        }
+    }
+    scene.set_animation_repeat(Repeat.PingPong);
+
     let texNdx = 0;
+    const camera = new Camera();
 
     const observer = new ResizeObserver(entries => {
         for (const entry of entries) {
-            const canvas = entry.target;
             const width = entry.contentBoxSize[0].inlineSize;
             const height = entry.contentBoxSize[0].blockSize;
-            canvas.width = Math.max(1, Math.min(width, device.limits.maxTextureDimension2D));
-            canvas.height = Math.max(1, Math.min(height, device.limits.maxTextureDimension2D));
+            scene.resize(width, height, window.devicePixelRatio);
+            camera.resize(width, height);
             scene.render(texNdx);
         }
       });
     observer.observe(canvas);
 
-    canvas.addEventListener("click", () => {
+    // a drag moves the camera, only a click without one switches the texture
+    let downAt = null;
+    canvas.addEventListener("click", (event) => {
+        if (downAt && Math.hypot(event.offsetX - downAt[0], event.offsetY - downAt[1]) > 3) {
+            return;
+        }
         texNdx = (texNdx + 1) % 2;
         scene.render(texNdx);
     });
+
+    // 1, 2 and 3 pick the orbit, pan and zoom or fly controller, o toggles the orthographic projection
+    canvas.tabIndex = 0;
+    canvas.addEventListener("contextmenu", (event) => event.preventDefault());
+    canvas.addEventListener("pointerdown", (event) => {
+        canvas.setPointerCapture(event.pointerId);
+        downAt = [event.offsetX, event.offsetY];
+        camera.pointer_down(event.offsetX, event.offsetY, event.button);
+    });
+    canvas.addEventListener("pointermove", (event) => {
+        camera.pointer_move(event.offsetX, event.offsetY);
+    });
+    canvas.addEventListener("pointerup", () => camera.pointer_up());
+    canvas.addEventListener("pointercancel", () => camera.pointer_up());
+    canvas.addEventListener("wheel", (event) => {
+        event.preventDefault();
+        camera.wheel(event.deltaY);
+    }, { passive: false });
+    canvas.addEventListener("keydown", (event) => {
+        const controllers = { Digit1: CameraController.Orbit, Digit2: CameraController.PanZoom, Digit3: CameraController.Fly };
+        if (event.code in controllers) {
+            camera.set_controller(controllers[event.code]);
+        } else if (event.code === "KeyO") {
+            const orthographic = camera.projection() === Projection.Orthographic;
+            camera.set_projection(orthographic ? Projection.Perspective : Projection.Orthographic);
+        } else {
+            camera.key_down(event.code);
+        }
+    });
+    canvas.addEventListener("keyup", (event) => camera.key_up(event.code));
+
+    // the camera eases towards where the input left it by the time of every animation frame
+    const removeTimelineKeys = addTimelineKeys(scene);
+    const stopFrameLoop = startFrameLoop(FrameLoop, canvas, (frame) => {
+        camera.update(frame.delta_time());
+        scene.set_camera(camera);
+        scene.render_frame(frame, texNdx);
+    });
+    return () => {
+        removeTimelineKeys();
+        stopFrameLoop();
+    };
 }
@@ -17,6 +17,8 @@ export class CustomApp extends HTMLElement {
 
         this.state = {
             canvas: null,
+            // stops the frame loop of the chapter on the canvas
+            stopChapter: null,
         };
     }
 
@@ -52,38 +54,44 @@ export class CustomApp extends HTMLElement {
     }
 
     async renderChapter(selectedChapter) {
+        // the chapters share the canvas, only the selected one may keep rendering to it
+        this.state.stopChapter?.();
+        this.state.stopChapter = null;
+
+        let stopChapter;
         switch (selectedChapter) {
             case "fundamentals":
                 destroyLoadingImagesGUI();
-                await mainFundamentals(this.state.canvas);
+                stopChapter = await mainFundamentals(this.state.canvas);
                 break;
             case "inter_stage_variables":
                 destroyLoadingImagesGUI();
-                await mainInterStageVariables(this.state.canvas);
+                stopChapter = await mainInterStageVariables(this.state.canvas);
                 break;
             case "uniforms":
                 destroyLoadingImagesGUI();
-                await mainUniforms(this.state.canvas);
+                stopChapter = await mainUniforms(this.state.canvas);
                 break;
             case "storage_buffers":
                 destroyLoadingImagesGUI();
-                await mainStorageBuffers(this.state.canvas);
+                stopChapter = await mainStorageBuffers(this.state.canvas);
                 break;
             case "vertex_buffers":
                 destroyLoadingImagesGUI();
-                await mainVertexBuffers(this.state.canvas);
+                stopChapter = await mainVertexBuffers(this.state.canvas);
                 break;
             case "textures":
                 destroyLoadingImagesGUI();
-                await mainTextures(this.state.canvas);
+                stopChapter = await mainTextures(this.state.canvas);
                 break;
             case "loading_images":
-                await mainLoadingImages(this.state.canvas);
+                stopChapter = await mainLoadingImages(this.state.canvas);
                 break;
             default:
                 destroyLoadingImagesGUI();
-                await mainFundamentals(this.state.canvas);
+                stopChapter = await mainFundamentals(this.state.canvas);
         }
+        this.state.stopChapter = stopChapter ?? null;
     }
 
     disconnectedCallback() {
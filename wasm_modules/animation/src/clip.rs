@@ -0,0 +1,105 @@
+use scene_graph::Transform;
+
+use crate::easing::Easing;
+use crate::skeleton::Skeleton;
+use crate::track::Interpolation;
+use crate::transform_tracks::{TransformProperty, TransformTracks};
+
+
+// the keyframes of a skeleton's joints, like a gltf animation targeting the nodes of a skin
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip
+{
+    name: String,
+    joints: Vec<TransformTracks>,
+}
+
+
+impl Clip
+{
+    pub fn new(name: &str, skeleton: &Skeleton) -> Self
+    {
+        Clip { name: name.to_owned(), joints: vec![TransformTracks::new(); skeleton.len()] }
+    }
+
+
+    pub fn name(&self) -> &str
+    {
+        &self.name
+    }
+
+
+    // replaces the track of a property of a joint, see Track::from_components for the components
+    pub fn set_track(
+        &mut self,
+        joint: usize,
+        property: TransformProperty,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        components: &[f32],
+    )
+        -> Result<(), String>
+    {
+        let joint_count = self.joints.len();
+        self.joints.get_mut(joint)
+            .ok_or_else(|| format!("joint {} is out of the {} joints of the clip", joint, joint_count))?
+            .set_track(property, interpolation, easing, times, components)
+    }
+
+
+    pub fn joint_tracks(&self, joint: usize) -> Option<&TransformTracks>
+    {
+        self.joints.get(joint)
+    }
+
+
+    pub fn duration(&self) -> f32
+    {
+        self.joints.iter().map(TransformTracks::end_time).fold(0.0, f32::max)
+    }
+
+
+    // the local transforms of the joints at time, the parts without a track stay in the rest pose
+    pub fn sample(&self, time: f32, skeleton: &Skeleton) -> Vec<Transform>
+    {
+        assert_eq!(self.joints.len(), skeleton.len(), "the clip {} is for another skeleton", self.name);
+        self.joints.iter()
+            .zip(skeleton.joints())
+            .map(|(tracks, joint)| tracks.sample(time, &joint.rest))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use math::{Quat, Vec3};
+
+    use crate::skeleton::Joint;
+
+    use super::*;
+
+
+    #[test]
+    fn untracked_joints_stay_in_the_rest_pose()
+    {
+        let rest = Transform::from_translation(Vec3::new(0.0, 1.0, 0.0));
+        let skeleton = Skeleton::new(vec![Joint::new("root", None, rest), Joint::new("tip", Some(0), rest)]).unwrap();
+
+        let mut clip = Clip::new("wave", &skeleton);
+        let quarter_turn = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2).to_array();
+        let rotations = [Quat::IDENTITY.to_array(), quarter_turn].concat();
+        clip.set_track(1, TransformProperty::Rotation, Interpolation::Linear, Easing::Linear, vec![0.0, 2.0], &rotations)
+            .unwrap();
+        assert!(clip.set_track(2, TransformProperty::Scale, Interpolation::Step, Easing::Linear, vec![0.0], &[1.0; 3])
+            .is_err());
+        assert_eq!(clip.duration(), 2.0);
+
+        let pose = clip.sample(1.0, &skeleton);
+        assert_eq!(pose[0], rest);
+        assert_eq!(pose[1].translation, rest.translation);
+        assert!(pose[1].rotation.abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4), 1e-5));
+    }
+}
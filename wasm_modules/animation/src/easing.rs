@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+
+// remaps the progress between two keyframes, the curves are the ones of easings.net
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing
+{
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    BackOut,
+    ElasticOut,
+    BounceOut,
+}
+
+
+impl Easing
+{
+    // t is clamped to [0, 1], 0 and 1 map to themselves. back and elastic overshoot in between
+    pub fn apply(self, t: f32) -> f32
+    {
+        let t = t.clamp(0.0, 1.0);
+        match self
+        {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (2.0 - 2.0 * t).powi(2) / 2.0 },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0 },
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => (1.0 - (t * PI).cos()) / 2.0,
+            Easing::BackOut =>
+            {
+                let overshoot = 1.70158;
+                1.0 + (overshoot + 1.0) * (t - 1.0).powi(3) + overshoot * (t - 1.0).powi(2)
+            },
+            Easing::ElasticOut =>
+            {
+                if t == 0.0 || t == 1.0
+                {
+                    return t;
+                }
+                2f32.powf(-10.0 * t) * ((10.0 * t - 0.75) * 2.0 * PI / 3.0).sin() + 1.0
+            },
+            Easing::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+
+fn bounce_out(t: f32) -> f32
+{
+    let (n, d) = (7.5625, 2.75);
+    if t < 1.0 / d
+    {
+        n * t * t
+    }
+    else if t < 2.0 / d
+    {
+        n * (t - 1.5 / d).powi(2) + 0.75
+    }
+    else if t < 2.5 / d
+    {
+        n * (t - 2.25 / d).powi(2) + 0.9375
+    }
+    else
+    {
+        n * (t - 2.625 / d).powi(2) + 0.984375
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    const EASINGS: [Easing; 13] = [
+        Easing::Linear, Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut, Easing::CubicIn, Easing::CubicOut,
+        Easing::CubicInOut, Easing::SineIn, Easing::SineOut, Easing::SineInOut, Easing::BackOut, Easing::ElasticOut,
+        Easing::BounceOut,
+    ];
+
+
+    #[test]
+    fn easings_start_at_0_and_end_at_1()
+    {
+        for easing in EASINGS
+        {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0));
+            assert_eq!(easing.apply(2.0), easing.apply(1.0));
+        }
+
+        // the in and out halves of in-out easings meet in the middle
+        for easing in [Easing::QuadInOut, Easing::CubicInOut, Easing::SineInOut]
+        {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-6);
+        }
+        assert!(Easing::QuadIn.apply(0.25) < 0.25 && Easing::QuadOut.apply(0.25) > 0.25);
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+    }
+}
@@ -0,0 +1,23 @@
+mod easing;
+pub use easing::Easing;
+
+mod track;
+pub use track::{Animatable, Interpolation, Track};
+
+mod timeline;
+pub use timeline::{Repeat, Timeline};
+
+mod transform_tracks;
+pub use transform_tracks::{TransformProperty, TransformTracks};
+
+mod skeleton;
+pub use skeleton::{Joint, Skeleton};
+
+mod clip;
+pub use clip::Clip;
+
+mod skinning;
+pub use skinning::{skin, skin_matrix, SkinnedVertex};
+
+mod morph;
+pub use morph::{MorphTarget, MorphTargets, MorphWeightTracks};
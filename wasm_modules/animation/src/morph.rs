@@ -0,0 +1,205 @@
+use math::Vec3;
+
+use crate::easing::Easing;
+use crate::track::{Interpolation, Track};
+
+
+// how far a target moves every vertex of a mesh and turns its normal, like the POSITION and NORMAL of a gltf target
+#[derive(Clone, Debug, PartialEq)]
+pub struct MorphTarget
+{
+    pub name: String,
+    pub position_deltas: Vec<Vec3>,
+    pub normal_deltas: Vec<Vec3>,
+}
+
+
+// the targets of a mesh, weights blend them onto its vertices
+#[derive(Clone, Debug, PartialEq)]
+pub struct MorphTargets
+{
+    vertex_count: usize,
+    targets: Vec<MorphTarget>,
+}
+
+
+impl MorphTargets
+{
+    pub fn new(vertex_count: usize) -> Self
+    {
+        MorphTargets { vertex_count, targets: Vec::new() }
+    }
+
+
+    // returns the index of the target, the weights of it go there
+    pub fn add(&mut self, target: MorphTarget) -> Result<usize, String>
+    {
+        if target.position_deltas.len() != self.vertex_count || target.normal_deltas.len() != self.vertex_count
+        {
+            return Err(format!(
+                "the target {} has {} position and {} normal deltas for {} vertices",
+                target.name, target.position_deltas.len(), target.normal_deltas.len(), self.vertex_count,
+            ));
+        }
+        self.targets.push(target);
+        Ok(self.targets.len() - 1)
+    }
+
+
+    pub fn targets(&self) -> &[MorphTarget]
+    {
+        &self.targets
+    }
+
+
+    pub fn len(&self) -> usize
+    {
+        self.targets.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.targets.is_empty()
+    }
+
+
+    pub fn vertex_count(&self) -> usize
+    {
+        self.vertex_count
+    }
+
+
+    // the reference for the vertex shaders: the weighted deltas of every target are added to the vertex in the order
+    // of the targets, then the normal is normalized again. targets without a weight have weight 0
+    pub fn morph(&self, vertex: usize, position: Vec3, normal: Vec3, weights: &[f32]) -> (Vec3, Vec3)
+    {
+        let (mut position, mut normal) = (position, normal);
+        for (target, weight) in self.targets.iter().zip(weights)
+        {
+            position += target.position_deltas[vertex] * *weight;
+            normal += target.normal_deltas[vertex] * *weight;
+        }
+        (position, normal.normalize())
+    }
+}
+
+
+// a track for the weight of every target, like a gltf animation of the weights of a mesh. the weights without a
+// track keep the values they are sampled over
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MorphWeightTracks
+{
+    tracks: Vec<Option<Track<f32>>>,
+}
+
+
+impl MorphWeightTracks
+{
+    pub fn new(target_count: usize) -> Self
+    {
+        MorphWeightTracks { tracks: vec![None; target_count] }
+    }
+
+
+    // replaces the track of the weight of a target, with cubic-spline interpolation values holds an in-tangent,
+    // the value and an out-tangent for every keyframe
+    pub fn set_track(
+        &mut self,
+        target: usize,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        values: Vec<f32>,
+    )
+        -> Result<(), String>
+    {
+        let target_count = self.tracks.len();
+        let slot = self.tracks.get_mut(target)
+            .ok_or_else(|| format!("target {} is out of the {} targets", target, target_count))?;
+        let mut track = Track::new(interpolation, times, values)?;
+        *slot = Some(track.easing(easing).clone());
+        Ok(())
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.tracks.iter().all(Option::is_none)
+    }
+
+
+    pub fn end_time(&self) -> f32
+    {
+        self.tracks.iter().flatten().map(Track::end_time).fold(0.0, f32::max)
+    }
+
+
+    pub fn sample(&self, time: f32, weights: &[f32]) -> Vec<f32>
+    {
+        self.tracks.iter()
+            .enumerate()
+            .map(|(target, track)| match track
+            {
+                Some(track) => track.sample(time),
+                None => weights.get(target).copied().unwrap_or(0.0),
+            })
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    fn targets() -> MorphTargets
+    {
+        let mut targets = MorphTargets::new(2);
+        targets.add(MorphTarget
+        {
+            name: "stretch".to_owned(),
+            position_deltas: vec![Vec3::ZERO, Vec3::new(0.0, 2.0, 0.0)],
+            normal_deltas: vec![Vec3::ZERO; 2],
+        }).unwrap();
+        targets.add(MorphTarget
+        {
+            name: "tilt".to_owned(),
+            position_deltas: vec![Vec3::new(1.0, 0.0, 0.0); 2],
+            normal_deltas: vec![Vec3::new(1.0, 0.0, -1.0); 2],
+        }).unwrap();
+        targets
+    }
+
+
+    #[test]
+    fn weights_blend_the_targets()
+    {
+        let targets = targets();
+        assert_eq!(targets.morph(1, Vec3::Y, Vec3::Z, &[]), (Vec3::Y, Vec3::Z));
+        assert_eq!(targets.morph(1, Vec3::Y, Vec3::Z, &[0.5]), (Vec3::new(0.0, 2.0, 0.0), Vec3::Z));
+
+        let (position, normal) = targets.morph(1, Vec3::Y, Vec3::Z, &[0.5, 1.0]);
+        assert_eq!(position, Vec3::new(1.0, 2.0, 0.0));
+        assert_eq!(normal, Vec3::X);
+
+        let mut mismatched = MorphTargets::new(3);
+        assert!(mismatched.add(targets.targets()[0].clone()).is_err());
+        assert!(mismatched.is_empty());
+    }
+
+
+    #[test]
+    fn untracked_weights_keep_their_value()
+    {
+        let mut tracks = MorphWeightTracks::new(2);
+        assert!(tracks.is_empty());
+        tracks.set_track(1, Interpolation::Linear, Easing::Linear, vec![0.0, 2.0], vec![0.0, 1.0]).unwrap();
+        assert!(tracks.set_track(2, Interpolation::Linear, Easing::Linear, vec![0.0], vec![1.0]).is_err());
+        assert_eq!(tracks.end_time(), 2.0);
+        assert_eq!(tracks.sample(1.0, &[0.25, 0.75]), [0.25, 0.5]);
+        assert_eq!(tracks.sample(1.0, &[]), [0.0, 0.5]);
+    }
+}
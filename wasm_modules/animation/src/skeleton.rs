@@ -0,0 +1,200 @@
+use math::Mat4;
+
+use scene_graph::Transform;
+
+
+// a bone of a skeleton like a gltf skin joint. the inverse bind matrix takes the mesh from model space into the
+// space of the joint in the pose the mesh was bound in
+#[derive(Clone, Debug, PartialEq)]
+pub struct Joint
+{
+    pub name: String,
+    pub parent: Option<usize>,
+    pub rest: Transform,
+    pub inverse_bind_matrix: Mat4,
+}
+
+
+impl Joint
+{
+    // the inverse bind matrix is left to Skeleton::bind_rest_pose for skeletons built in code
+    pub fn new(name: &str, parent: Option<usize>, rest: Transform) -> Self
+    {
+        Joint { name: name.to_owned(), parent, rest, inverse_bind_matrix: Mat4::IDENTITY }
+    }
+
+
+    pub fn inverse_bind_matrix(&mut self, inverse_bind_matrix: Mat4) -> &mut Self
+    {
+        self.inverse_bind_matrix = inverse_bind_matrix;
+        self
+    }
+}
+
+
+// a joint hierarchy, the joints can come in any order as long as they form a forest. vertices refer to the joints
+// by their index
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skeleton
+{
+    joints: Vec<Joint>,
+    // the joint indices with every parent before its children
+    order: Vec<usize>,
+}
+
+
+impl Skeleton
+{
+    pub fn new(joints: Vec<Joint>) -> Result<Self, String>
+    {
+        if let Some((index, joint)) = joints.iter().enumerate()
+            .find(|(_, joint)| joint.parent.is_some_and(|parent| parent >= joints.len()))
+        {
+            return Err(format!("the parent {:?} of joint {} doesn't exist", joint.parent, index));
+        }
+
+        let mut depths = vec![None; joints.len()];
+        for index in 0 .. joints.len()
+        {
+            // walks up to a joint of known depth, a walk longer than the skeleton went around a cycle
+            let mut chain = vec![index];
+            while let Some(parent) = joints[*chain.last().unwrap()].parent.filter(|parent| depths[*parent].is_none())
+            {
+                if chain.len() > joints.len()
+                {
+                    return Err(format!("joint {} is its own ancestor", index));
+                }
+                chain.push(parent);
+            }
+            let top = *chain.last().unwrap();
+            let top_depth = joints[top].parent.map_or(0, |parent| depths[parent].unwrap() + 1);
+            for (depth, joint) in (top_depth ..).zip(chain.into_iter().rev())
+            {
+                depths[joint].get_or_insert(depth);
+            }
+        }
+        let mut order: Vec<usize> = (0 .. joints.len()).collect();
+        order.sort_by_key(|index| depths[*index]);
+
+        Ok(Skeleton { joints, order })
+    }
+
+
+    pub fn joints(&self) -> &[Joint]
+    {
+        &self.joints
+    }
+
+
+    pub fn len(&self) -> usize
+    {
+        self.joints.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.joints.is_empty()
+    }
+
+
+    pub fn find(&self, name: &str) -> Option<usize>
+    {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+
+    pub fn rest_pose(&self) -> Vec<Transform>
+    {
+        self.joints.iter().map(|joint| joint.rest).collect()
+    }
+
+
+    // the model space matrices of the joints for the local transforms of a pose
+    pub fn world_matrices(&self, pose: &[Transform]) -> Vec<Mat4>
+    {
+        assert_eq!(pose.len(), self.joints.len(), "a pose needs a transform for every joint");
+        let mut matrices = vec![Mat4::IDENTITY; self.joints.len()];
+        for &index in &self.order
+        {
+            let local = pose[index].matrix();
+            matrices[index] = match self.joints[index].parent
+            {
+                Some(parent) => matrices[parent] * local,
+                None => local,
+            };
+        }
+        matrices
+    }
+
+
+    // what the vertices are skinned with, the identity for every joint in the bind pose
+    pub fn joint_matrices(&self, pose: &[Transform]) -> Vec<Mat4>
+    {
+        self.world_matrices(pose).into_iter()
+            .zip(&self.joints)
+            .map(|(world, joint)| world * joint.inverse_bind_matrix)
+            .collect()
+    }
+
+
+    // binds the mesh in the rest pose, imported skins come with their inverse bind matrices instead
+    pub fn bind_rest_pose(&mut self) -> Result<(), String>
+    {
+        let world = self.world_matrices(&self.rest_pose());
+        for (joint, matrix) in self.joints.iter_mut().zip(world)
+        {
+            joint.inverse_bind_matrix = matrix.inverse()
+                .ok_or_else(|| format!("the rest pose of joint {} can't be inverted", joint.name))?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use math::{Quat, Vec3};
+
+    use super::*;
+
+
+    #[test]
+    fn children_follow_their_parents()
+    {
+        // listed child first, like gltf allows
+        let joints = vec![
+            Joint::new("tip", Some(1), Transform::from_translation(Vec3::new(0.0, 1.0, 0.0))),
+            Joint::new("root", None, Transform::from_translation(Vec3::new(2.0, 0.0, 0.0))),
+        ];
+        let mut skeleton = Skeleton::new(joints).unwrap();
+        assert_eq!(skeleton.find("root"), Some(1));
+
+        let world = skeleton.world_matrices(&skeleton.rest_pose());
+        assert_eq!(world[0].translation(), Vec3::new(2.0, 1.0, 0.0));
+
+        skeleton.bind_rest_pose().unwrap();
+        for matrix in skeleton.joint_matrices(&skeleton.rest_pose())
+        {
+            assert!(matrix.abs_diff_eq(&Mat4::IDENTITY, 1e-6));
+        }
+
+        // turning the root swings the tip around it
+        let mut pose = skeleton.rest_pose();
+        pose[1].rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        let tip = skeleton.joint_matrices(&pose)[0].transform_point3(Vec3::new(2.0, 1.0, 0.0));
+        assert!(tip.abs_diff_eq(Vec3::new(1.0, 0.0, 0.0), 1e-6));
+    }
+
+
+    #[test]
+    fn malformed_hierarchies_are_rejected()
+    {
+        let joint = |parent| Joint::new("joint", parent, Transform::IDENTITY);
+        assert!(Skeleton::new(vec![joint(None), joint(Some(2))]).is_err());
+        assert!(Skeleton::new(vec![joint(Some(1)), joint(Some(0))]).is_err());
+        assert!(Skeleton::new(vec![joint(Some(0))]).is_err());
+        assert!(Skeleton::new(vec![joint(None), joint(Some(0)), joint(Some(1))]).is_ok());
+    }
+}
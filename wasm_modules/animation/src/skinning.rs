@@ -0,0 +1,70 @@
+use math::{Mat4, Vec3};
+
+
+// a vertex bound to up to four joints like the JOINTS_0 and WEIGHTS_0 attributes of gltf, the weights add up to 1
+// and unused joints have weight 0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkinnedVertex
+{
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+
+
+// the reference for the vertex shaders, they blend the same way
+pub fn skin_matrix(joints: [u32; 4], weights: [f32; 4], joint_matrices: &[Mat4]) -> Mat4
+{
+    let mut blended = [0.0; 16];
+    for (joint, weight) in joints.into_iter().zip(weights)
+    {
+        for (value, joint_value) in blended.iter_mut().zip(joint_matrices[joint as usize].as_array())
+        {
+            *value += joint_value * weight;
+        }
+    }
+    Mat4::from_cols_array(&blended)
+}
+
+
+// the skinned position and normal of a vertex on the cpu, e.g. for picking or to test the gpu path against. like the
+// vertex shaders it turns the normals with the blended matrix, which holds as long as the joints don't scale
+pub fn skin(vertex: &SkinnedVertex, joint_matrices: &[Mat4]) -> (Vec3, Vec3)
+{
+    let matrix = skin_matrix(vertex.joints, vertex.weights, joint_matrices);
+    (matrix.transform_point3(vertex.position), matrix.transform_vector3(vertex.normal).normalize())
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn weights_blend_the_joint_matrices()
+    {
+        let vertex = SkinnedVertex
+        {
+            position: Vec3::new(1.0, 0.0, 0.0),
+            normal: Vec3::Y,
+            joints: [0, 1, 0, 0],
+            weights: [0.5, 0.5, 0.0, 0.0],
+        };
+        let still = [Mat4::IDENTITY, Mat4::IDENTITY];
+        assert_eq!(skin(&vertex, &still), (vertex.position, vertex.normal));
+
+        let moved = [Mat4::IDENTITY, Mat4::from_translation(Vec3::new(0.0, 2.0, 0.0))];
+        let (position, normal) = skin(&vertex, &moved);
+        assert_eq!(position, Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(normal, Vec3::Y);
+
+        // normals turn with the joints and stay unit length
+        let turned = [Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2); 2];
+        let (position, normal) = skin(&vertex, &turned);
+        assert!(position.abs_diff_eq(Vec3::Y, 1e-6));
+        assert!(normal.abs_diff_eq(-Vec3::X, 1e-6));
+    }
+}
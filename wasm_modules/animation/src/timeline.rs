@@ -0,0 +1,185 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+
+// what happens once the time passes the duration
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Repeat
+{
+    // stays at the end
+    Once,
+    // starts over
+    Loop,
+    // plays backwards to the start, then forwards again
+    PingPong,
+}
+
+
+// the time of an animation. the frame loop advances it while it plays, setting it scrubs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timeline
+{
+    time: f32,
+    duration: f32,
+    repeat: Repeat,
+    speed: f32,
+    playing: bool,
+}
+
+
+impl Timeline
+{
+    pub fn new(duration: f32, repeat: Repeat) -> Self
+    {
+        Timeline { time: 0.0, duration, repeat, speed: 1.0, playing: true }
+    }
+
+
+    // seconds since the start, it keeps counting past the duration
+    pub fn time(&self) -> f32
+    {
+        self.time
+    }
+
+
+    pub fn set_time(&mut self, time: f32)
+    {
+        self.time = time.max(0.0);
+    }
+
+
+    pub fn duration(&self) -> f32
+    {
+        self.duration
+    }
+
+
+    pub fn set_duration(&mut self, duration: f32)
+    {
+        self.duration = duration.max(0.0);
+    }
+
+
+    pub fn repeat(&self) -> Repeat
+    {
+        self.repeat
+    }
+
+
+    pub fn set_repeat(&mut self, repeat: Repeat)
+    {
+        self.repeat = repeat;
+    }
+
+
+    pub fn speed(&self) -> f32
+    {
+        self.speed
+    }
+
+
+    // negative speeds play backwards
+    pub fn set_speed(&mut self, speed: f32)
+    {
+        self.speed = speed;
+    }
+
+
+    pub fn is_playing(&self) -> bool
+    {
+        self.playing
+    }
+
+
+    pub fn play(&mut self)
+    {
+        self.playing = true;
+    }
+
+
+    pub fn pause(&mut self)
+    {
+        self.playing = false;
+    }
+
+
+    pub fn advance(&mut self, delta_time: f32)
+    {
+        if self.playing
+        {
+            self.set_time(self.time + delta_time * self.speed);
+        }
+    }
+
+
+    // the time within [0, duration] to sample the tracks at
+    pub fn local_time(&self) -> f32
+    {
+        if self.duration <= 0.0
+        {
+            return 0.0;
+        }
+        match self.repeat
+        {
+            Repeat::Once => self.time.min(self.duration),
+            Repeat::Loop => self.time % self.duration,
+            Repeat::PingPong =>
+            {
+                let time = self.time % (2.0 * self.duration);
+                if time > self.duration { 2.0 * self.duration - time } else { time }
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    fn local_times(repeat: Repeat) -> [f32; 6]
+    {
+        let mut timeline = Timeline::new(2.0, repeat);
+        [0.5, 1.5, 2.5, 3.5, 4.5, 7.0].map(|time|
+        {
+            timeline.set_time(time);
+            timeline.local_time()
+        })
+    }
+
+
+    #[test]
+    fn repeat_modes_map_the_time_into_the_duration()
+    {
+        assert_eq!(local_times(Repeat::Once), [0.5, 1.5, 2.0, 2.0, 2.0, 2.0]);
+        assert_eq!(local_times(Repeat::Loop), [0.5, 1.5, 0.5, 1.5, 0.5, 1.0]);
+        assert_eq!(local_times(Repeat::PingPong), [0.5, 1.5, 1.5, 0.5, 0.5, 1.0]);
+        assert_eq!(Timeline::new(0.0, Repeat::Loop).local_time(), 0.0);
+    }
+
+
+    #[test]
+    fn only_a_playing_timeline_advances()
+    {
+        let mut timeline = Timeline::new(2.0, Repeat::Loop);
+        timeline.advance(0.5);
+        timeline.set_speed(2.0);
+        timeline.advance(0.5);
+        assert_eq!(timeline.time(), 1.5);
+
+        timeline.pause();
+        timeline.advance(0.5);
+        assert!(!timeline.is_playing());
+        assert_eq!(timeline.time(), 1.5);
+
+        // scrubbing works while paused, the time never goes below 0
+        timeline.set_time(0.25);
+        assert_eq!(timeline.local_time(), 0.25);
+        timeline.play();
+        timeline.set_speed(-1.0);
+        timeline.advance(1.0);
+        assert_eq!(timeline.time(), 0.0);
+    }
+}
@@ -0,0 +1,273 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use math::{Quat, Vec2, Vec3, Vec4};
+
+use crate::easing::Easing;
+
+
+// how values between two keyframes are found, like the interpolation of gltf animation samplers
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation
+{
+    // the value of the keyframe before, until the next one
+    Step,
+    Linear,
+    // a cubic hermite spline through the values, every keyframe has an in-tangent and an out-tangent
+    CubicSpline,
+}
+
+
+// a value a track can animate
+pub trait Animatable: Copy
+{
+    // how many floats make up the value, e.g. when they come flattened from js
+    const COMPONENTS: usize;
+
+    // components has to hold COMPONENTS floats
+    fn from_components(components: &[f32]) -> Self;
+
+    fn lerp(self, other: Self, t: f32) -> Self;
+
+    // the tangents are per second, so they are scaled by the duration of the segment
+    fn hermite(self, out_tangent: Self, other: Self, in_tangent: Self, t: f32, duration: f32) -> Self;
+}
+
+
+fn hermite_weights(t: f32) -> [f32; 4]
+{
+    let (t2, t3) = (t * t, t * t * t);
+    [2.0 * t3 - 3.0 * t2 + 1.0, t3 - 2.0 * t2 + t, -2.0 * t3 + 3.0 * t2, t3 - t2]
+}
+
+
+macro_rules! animatable
+{
+    ($type:ty, $components:expr, $from_components:expr) =>
+    {
+        impl Animatable for $type
+        {
+            const COMPONENTS: usize = $components;
+
+            fn from_components(components: &[f32]) -> Self
+            {
+                $from_components(components)
+            }
+
+            fn lerp(self, other: Self, t: f32) -> Self
+            {
+                self + (other - self) * t
+            }
+
+            fn hermite(self, out_tangent: Self, other: Self, in_tangent: Self, t: f32, duration: f32) -> Self
+            {
+                let [a, b, c, d] = hermite_weights(t);
+                self * a + out_tangent * (b * duration) + other * c + in_tangent * (d * duration)
+            }
+        }
+    };
+}
+
+animatable!(f32, 1, |components: &[f32]| components[0]);
+animatable!(Vec2, 2, |components: &[f32]| Vec2::new(components[0], components[1]));
+animatable!(Vec3, 3, |components: &[f32]| Vec3::new(components[0], components[1], components[2]));
+animatable!(Vec4, 4, |components: &[f32]| Vec4::new(components[0], components[1], components[2], components[3]));
+
+
+// rotations take the shorter way, splines are evaluated on the components and normalized again like gltf does
+impl Animatable for Quat
+{
+    const COMPONENTS: usize = 4;
+
+    fn from_components(components: &[f32]) -> Self
+    {
+        Quat::from_vec4(Vec4::from_components(components)).normalize()
+    }
+
+    fn lerp(self, other: Self, t: f32) -> Self
+    {
+        self.slerp(other, t)
+    }
+
+    fn hermite(self, out_tangent: Self, other: Self, in_tangent: Self, t: f32, duration: f32) -> Self
+    {
+        let vector = self.to_vec4().hermite(out_tangent.to_vec4(), other.to_vec4(), in_tangent.to_vec4(), t, duration);
+        Quat::from_vec4(vector).normalize()
+    }
+}
+
+
+// keyframes of one value at increasing times in seconds. before the first keyframe the track holds its first
+// value and after the last its last value
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T>
+{
+    interpolation: Interpolation,
+    easing: Easing,
+    times: Vec<f32>,
+    // with cubic-spline interpolation the in-tangent, the value and the out-tangent of every keyframe like gltf
+    values: Vec<T>,
+}
+
+
+impl<T: Animatable> Track<T>
+{
+    pub fn new(interpolation: Interpolation, times: Vec<f32>, values: Vec<T>) -> Result<Self, String>
+    {
+        if times.is_empty()
+        {
+            return Err("a track needs at least one keyframe".to_owned());
+        }
+        if times.iter().any(|time| !time.is_finite()) || times.windows(2).any(|pair| pair[0] >= pair[1])
+        {
+            return Err(format!("the keyframe times {:?} have to be finite and increasing", times));
+        }
+        let values_per_keyframe = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+        if values.len() != times.len() * values_per_keyframe
+        {
+            return Err(format!(
+                "{} keyframes with {:?} interpolation take {} values, not {}",
+                times.len(), interpolation, times.len() * values_per_keyframe, values.len(),
+            ));
+        }
+        Ok(Track { interpolation, easing: Easing::Linear, times, values })
+    }
+
+
+    // the values flattened into their components, e.g. as they come from js
+    pub fn from_components(interpolation: Interpolation, times: Vec<f32>, components: &[f32]) -> Result<Self, String>
+    {
+        if !components.len().is_multiple_of(T::COMPONENTS)
+        {
+            return Err(format!("{} floats aren't values of {} components", components.len(), T::COMPONENTS));
+        }
+        let values = components.chunks(T::COMPONENTS).map(T::from_components).collect();
+        Track::new(interpolation, times, values)
+    }
+
+
+    // eases the progress between every two keyframes
+    pub fn easing(&mut self, easing: Easing) -> &mut Self
+    {
+        self.easing = easing;
+        self
+    }
+
+
+    pub fn start_time(&self) -> f32
+    {
+        self.times[0]
+    }
+
+
+    pub fn end_time(&self) -> f32
+    {
+        *self.times.last().unwrap()
+    }
+
+
+    fn value(&self, keyframe: usize) -> T
+    {
+        match self.interpolation
+        {
+            Interpolation::CubicSpline => self.values[3 * keyframe + 1],
+            _ => self.values[keyframe],
+        }
+    }
+
+
+    pub fn sample(&self, time: f32) -> T
+    {
+        // the last keyframe at or before time
+        let keyframe = self.times.partition_point(|keyframe_time| *keyframe_time <= time);
+        if keyframe == 0
+        {
+            return self.value(0);
+        }
+        if keyframe == self.times.len()
+        {
+            return self.value(keyframe - 1);
+        }
+
+        let (start, end) = (keyframe - 1, keyframe);
+        let duration = self.times[end] - self.times[start];
+        let t = self.easing.apply((time - self.times[start]) / duration);
+        match self.interpolation
+        {
+            Interpolation::Step => self.value(start),
+            Interpolation::Linear => self.value(start).lerp(self.value(end), t),
+            Interpolation::CubicSpline =>
+            {
+                let out_tangent = self.values[3 * start + 2];
+                let in_tangent = self.values[3 * end];
+                self.value(start).hermite(out_tangent, self.value(end), in_tangent, t, duration)
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn tracks_interpolate_between_keyframes()
+    {
+        let times = vec![1.0, 2.0, 4.0];
+        let linear = Track::new(Interpolation::Linear, times.clone(), vec![0.0, 10.0, 20.0]).unwrap();
+        assert_eq!([0.0, 1.0, 1.5, 3.0, 4.0, 9.0].map(|time| linear.sample(time)), [0.0, 0.0, 5.0, 15.0, 20.0, 20.0]);
+        assert_eq!((linear.start_time(), linear.end_time()), (1.0, 4.0));
+
+        let step = Track::new(Interpolation::Step, times, vec![0.0, 10.0, 20.0]).unwrap();
+        assert_eq!([1.0, 1.99, 2.0, 3.5, 4.0].map(|time| step.sample(time)), [0.0, 0.0, 10.0, 10.0, 20.0]);
+
+        let mut eased = linear.clone();
+        eased.easing(Easing::QuadIn);
+        assert_eq!(eased.sample(1.5), 2.5);
+        assert_eq!(eased.sample(2.0), 10.0);
+    }
+
+
+    #[test]
+    fn cubic_splines_follow_their_tangents()
+    {
+        // with tangents matching the slope the spline is the straight line, tangents are per second
+        let components = [2.0, 2.0, 0.0, 0.0, 2.0, 2.0, 2.0, 2.0, 4.0, 4.0, 2.0, 2.0];
+        let track = Track::<Vec2>::from_components(Interpolation::CubicSpline, vec![0.0, 2.0], &components).unwrap();
+        for time in [0.0, 0.5, 1.0, 1.5, 2.0]
+        {
+            assert!(track.sample(time).abs_diff_eq(Vec2::new(2.0 * time, 2.0 * time), 1e-5));
+        }
+
+        // flat tangents ease in and out
+        let flat = Track::new(Interpolation::CubicSpline, vec![0.0, 1.0], vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0]).unwrap();
+        assert_eq!(flat.sample(0.5), 0.5);
+        assert!(flat.sample(0.25) < 0.25 && flat.sample(0.75) > 0.75);
+    }
+
+
+    #[test]
+    fn rotations_take_the_shorter_way()
+    {
+        let quarter = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let track = Track::new(Interpolation::Linear, vec![0.0, 1.0], vec![Quat::IDENTITY, -quarter]).unwrap();
+        let halfway = track.sample(0.5);
+        assert!(halfway.abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4), 1e-5));
+        assert!((halfway.length() - 1.0).abs() < 1e-5);
+    }
+
+
+    #[test]
+    fn malformed_tracks_are_rejected()
+    {
+        assert!(Track::<f32>::new(Interpolation::Linear, vec![], vec![]).is_err());
+        assert!(Track::new(Interpolation::Linear, vec![1.0, 1.0], vec![0.0, 1.0]).is_err());
+        assert!(Track::new(Interpolation::Linear, vec![0.0, f32::NAN], vec![0.0, 1.0]).is_err());
+        assert!(Track::new(Interpolation::CubicSpline, vec![0.0, 1.0], vec![0.0, 1.0]).is_err());
+        assert!(Track::<Vec3>::from_components(Interpolation::Step, vec![0.0], &[1.0, 2.0]).is_err());
+        assert!(Track::<Vec3>::from_components(Interpolation::Step, vec![0.0], &[1.0, 2.0, 3.0]).is_ok());
+    }
+}
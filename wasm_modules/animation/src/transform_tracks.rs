@@ -0,0 +1,140 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use math::{Quat, Vec3};
+
+use scene_graph::Transform;
+
+use crate::easing::Easing;
+use crate::track::{Interpolation, Track};
+
+
+// the part of a transform a track animates, translation and scale take 3 floats and rotation 4, xyzw
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransformProperty
+{
+    Translation,
+    Rotation,
+    Scale,
+}
+
+
+// the parts of a transform without a track keep their value of the transform they are sampled over
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformTracks
+{
+    pub translation: Option<Track<Vec3>>,
+    pub rotation: Option<Track<Quat>>,
+    pub scale: Option<Track<Vec3>>,
+}
+
+
+impl TransformTracks
+{
+    pub fn new() -> Self
+    {
+        TransformTracks::default()
+    }
+
+
+    // replaces the track of a property with keyframes flattened into their components, see Track::from_components
+    pub fn set_track(
+        &mut self,
+        property: TransformProperty,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        components: &[f32],
+    )
+        -> Result<(), String>
+    {
+        match property
+        {
+            TransformProperty::Translation =>
+            {
+                let mut track = Track::from_components(interpolation, times, components)?;
+                self.translation = Some(track.easing(easing).clone());
+            },
+            TransformProperty::Rotation =>
+            {
+                let mut track = Track::from_components(interpolation, times, components)?;
+                self.rotation = Some(track.easing(easing).clone());
+            },
+            TransformProperty::Scale =>
+            {
+                let mut track = Track::from_components(interpolation, times, components)?;
+                self.scale = Some(track.easing(easing).clone());
+            },
+        }
+        Ok(())
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.translation.is_none() && self.rotation.is_none() && self.scale.is_none()
+    }
+
+
+    // when the last keyframe of any track is reached, 0 without tracks
+    pub fn end_time(&self) -> f32
+    {
+        [
+            self.translation.as_ref().map(Track::end_time),
+            self.rotation.as_ref().map(Track::end_time),
+            self.scale.as_ref().map(Track::end_time),
+        ]
+            .into_iter()
+            .flatten()
+            .fold(0.0, f32::max)
+    }
+
+
+    pub fn sample(&self, time: f32, base: &Transform) -> Transform
+    {
+        Transform
+        {
+            translation: self.translation.as_ref().map_or(base.translation, |track| track.sample(time)),
+            rotation: self.rotation.as_ref().map_or(base.rotation, |track| track.sample(time)),
+            scale: self.scale.as_ref().map_or(base.scale, |track| track.sample(time)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn untracked_parts_keep_the_base_transform()
+    {
+        let mut tracks = TransformTracks::new();
+        assert!(tracks.is_empty());
+        assert_eq!(tracks.end_time(), 0.0);
+
+        tracks.set_track(
+            TransformProperty::Translation, Interpolation::Linear, Easing::Linear, vec![0.0, 2.0],
+            &[0.0, 0.0, 0.0, 2.0, 4.0, 6.0],
+        ).unwrap();
+        tracks.set_track(
+            TransformProperty::Scale, Interpolation::Step, Easing::Linear, vec![0.0, 3.0], &[1.0; 6],
+        ).unwrap();
+        assert_eq!(tracks.end_time(), 3.0);
+
+        let base = Transform::from_rotation(Quat::from_rotation_y(1.0));
+        let transform = tracks.sample(1.0, &base);
+        assert_eq!(transform.translation, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(transform.rotation, base.rotation);
+        assert_eq!(transform.scale, Vec3::ONE);
+
+        // a rotation needs 4 floats
+        let rotation = tracks.set_track(
+            TransformProperty::Rotation, Interpolation::Linear, Easing::Linear, vec![0.0], &[0.0, 0.0, 1.0],
+        );
+        assert!(rotation.is_err());
+        assert!(tracks.rotation.is_none());
+    }
+}
@@ -0,0 +1,12 @@
+// prepended to the render shaders of every chapter, frame is bound only if a shader uses it
+struct Frame {
+  // the size of the canvas and the position of the pointer on it in pixels, from the top left corner
+  resolution: vec2f,
+  mouse: vec2f,
+  // seconds since the animation started, without the time it was paused
+  time: f32,
+  delta_time: f32,
+  frame_index: u32,
+}
+
+@group(1) @binding(0) var<uniform> frame: Frame;
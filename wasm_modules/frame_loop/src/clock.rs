@@ -0,0 +1,169 @@
+// a frame after a long gap, e.g. when the tab was hidden, advances by this much at most instead of
+// jumping ahead or running every fixed step it missed
+const MAX_DELTA_TIME: f64 = 0.25;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Timestep
+{
+    // every frame advances by the time since the last one
+    Variable,
+    // time advances in whole steps of this many seconds, the rest carries over to the next frame
+    Fixed(f64),
+}
+
+
+// what a frame advances the animation by
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTime
+{
+    pub time: f64,
+    pub delta_time: f64,
+    pub frame_index: u32,
+    // how many fixed steps delta_time is made of, 1 with a variable timestep
+    pub steps: u32,
+}
+
+
+#[derive(Debug)]
+pub struct FrameClock
+{
+    timestep: Timestep,
+    paused: bool,
+    last_timestamp: Option<f64>,
+    accumulator: f64,
+    time: f64,
+    frame_index: u32,
+}
+
+
+impl FrameClock
+{
+    pub fn new(timestep: Timestep) -> Self
+    {
+        FrameClock { timestep, paused: false, last_timestamp: None, accumulator: 0.0, time: 0.0, frame_index: 0 }
+    }
+
+
+    pub fn timestep(&self) -> Timestep
+    {
+        self.timestep
+    }
+
+
+    pub fn set_timestep(&mut self, timestep: Timestep)
+    {
+        self.timestep = timestep;
+        self.accumulator = 0.0;
+    }
+
+
+    pub fn is_paused(&self) -> bool
+    {
+        self.paused
+    }
+
+
+    pub fn pause(&mut self)
+    {
+        self.paused = true;
+    }
+
+
+    // the time spent paused doesn't count
+    pub fn resume(&mut self)
+    {
+        self.paused = false;
+        self.last_timestamp = None;
+    }
+
+
+    // takes the timestamp of an animation frame in milliseconds, None while paused.
+    // the first frame after starting or resuming doesn't advance
+    pub fn tick(&mut self, timestamp: f64) -> Option<FrameTime>
+    {
+        if self.paused
+        {
+            return None;
+        }
+
+        let elapsed = match self.last_timestamp
+        {
+            Some(last_timestamp) => ((timestamp - last_timestamp) / 1000.0).clamp(0.0, MAX_DELTA_TIME),
+            None => 0.0,
+        };
+        self.last_timestamp = Some(timestamp);
+
+        let (delta_time, steps) = match self.timestep
+        {
+            Timestep::Variable => (elapsed, 1),
+            Timestep::Fixed(step) =>
+            {
+                self.accumulator += elapsed;
+                let steps = (self.accumulator / step).floor();
+                self.accumulator -= steps * step;
+                (steps * step, steps as u32)
+            },
+        };
+        self.time += delta_time;
+
+        let frame_time = FrameTime { time: self.time, delta_time, frame_index: self.frame_index, steps };
+        self.frame_index = self.frame_index.wrapping_add(1);
+        Some(frame_time)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn a_variable_timestep_follows_the_frames()
+    {
+        let mut clock = FrameClock::new(Timestep::Variable);
+        assert_eq!(clock.tick(1000.0), Some(FrameTime { time: 0.0, delta_time: 0.0, frame_index: 0, steps: 1 }));
+        assert_eq!(clock.tick(1016.0), Some(FrameTime { time: 0.016, delta_time: 0.016, frame_index: 1, steps: 1 }));
+
+        // a hidden tab comes back without jumping ahead
+        let frame_time = clock.tick(60_000.0).unwrap();
+        assert_eq!(frame_time.delta_time, MAX_DELTA_TIME);
+        assert_eq!(frame_time.time, 0.016 + MAX_DELTA_TIME);
+        assert_eq!(frame_time.frame_index, 2);
+    }
+
+
+    #[test]
+    fn a_fixed_timestep_carries_the_rest_over()
+    {
+        let mut clock = FrameClock::new(Timestep::Fixed(0.0625));
+        clock.tick(0.0);
+        let frame_times = [150.0, 180.0, 260.0].map(|timestamp| clock.tick(timestamp).unwrap());
+        assert_eq!(frame_times.map(|frame_time| frame_time.steps), [2, 0, 2]);
+        assert_eq!(frame_times.map(|frame_time| frame_time.delta_time), [0.125, 0.0, 0.125]);
+        assert_eq!(frame_times[2].time, 0.25);
+
+        // changing the timestep drops what was left of a step
+        clock.set_timestep(Timestep::Variable);
+        assert_eq!(clock.timestep(), Timestep::Variable);
+        assert_eq!(clock.tick(270.0).unwrap().steps, 1);
+    }
+
+
+    #[test]
+    fn time_stands_still_while_paused()
+    {
+        let mut clock = FrameClock::new(Timestep::Variable);
+        clock.tick(0.0);
+        clock.tick(100.0);
+        clock.pause();
+        assert!(clock.is_paused());
+        assert_eq!(clock.tick(200.0), None);
+
+        clock.resume();
+        assert_eq!(clock.tick(5000.0), Some(FrameTime { time: 0.1, delta_time: 0.0, frame_index: 2, steps: 1 }));
+        assert!((clock.tick(5050.0).unwrap().time - 0.15).abs() < 1e-9);
+    }
+}
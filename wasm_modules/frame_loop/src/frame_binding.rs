@@ -0,0 +1,127 @@
+use gpu_backend::
+{
+    Backend, BufferDescriptor, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+    BufferBindingType, RenderPassEncoder,
+};
+use gpu_backend::buffer_usage::{COPY_DST, UNIFORM};
+use gpu_backend::shader_stage::{VERTEX, FRAGMENT};
+
+use crate::frame_uniforms::{FrameUniforms, FRAME_GROUP, FRAME_UNIFORMS_SIZE, uses_frame};
+
+
+// the frame uniforms of one render pipeline
+pub struct FrameBinding<B: Backend>
+{
+    buffer: B::Buffer,
+    bind_group: Option<B::BindGroup>,
+}
+
+
+impl<B: Backend> FrameBinding<B>
+{
+    fn create_buffer(backend: &B) -> B::Buffer
+    {
+        let mut buffer_descriptor = BufferDescriptor::new(FRAME_UNIFORMS_SIZE, UNIFORM | COPY_DST);
+        buffer_descriptor.label("frame uniforms");
+        backend.create_buffer(&buffer_descriptor)
+    }
+
+
+    fn create_bind_group(backend: &B, buffer: &B::Buffer, layout: &B::BindGroupLayout) -> B::BindGroup
+    {
+        let mut bind_group_descriptor = BindGroupDescriptor::new(vec![BindGroupEntry::buffer(0, buffer)], layout);
+        bind_group_descriptor.label("bind group for the frame uniforms");
+        backend.create_bind_group(&bind_group_descriptor)
+    }
+
+
+    // for pipelines with an "auto" layout, the shader sources are the ones the pipeline was created from
+    pub fn new(backend: &B, render_pipeline: &B::RenderPipeline, sources: &[&str]) -> Self
+    {
+        let buffer = FrameBinding::create_buffer(backend);
+        let bind_group = sources.iter().any(|source| uses_frame(source)).then(|| FrameBinding::create_bind_group(
+            backend, &buffer, &backend.render_bind_group_layout(render_pipeline, FRAME_GROUP),
+        ));
+        FrameBinding { buffer, bind_group }
+    }
+
+
+    // for pipelines with an explicit layout, which has bind_group_layout at FRAME_GROUP
+    pub fn with_layout(backend: &B, layout: &B::BindGroupLayout) -> Self
+    {
+        let buffer = FrameBinding::create_buffer(backend);
+        let bind_group = Some(FrameBinding::create_bind_group(backend, &buffer, layout));
+        FrameBinding { buffer, bind_group }
+    }
+
+
+    pub fn bind_group_layout(backend: &B) -> B::BindGroupLayout
+    {
+        let mut bind_group_layout_descriptor = BindGroupLayoutDescriptor::new(&[
+            BindGroupLayoutEntry::buffer(0, VERTEX | FRAGMENT, BufferBindingType::Uniform),
+        ]);
+        bind_group_layout_descriptor.label("frame uniforms");
+        backend.create_bind_group_layout(&bind_group_layout_descriptor)
+    }
+
+
+    pub fn buffer(&self) -> &B::Buffer
+    {
+        &self.buffer
+    }
+
+
+    // None when the pipeline doesn't use the frame uniforms
+    pub fn bind_group(&self) -> Option<&B::BindGroup>
+    {
+        self.bind_group.as_ref()
+    }
+
+
+    pub fn write(&self, backend: &B, uniforms: &FrameUniforms)
+    {
+        backend.write_buffer(&self.buffer, 0, &uniforms.to_bytes());
+    }
+
+
+    pub fn set_bind_group(&self, render_pass_encoder: &mut RenderPassEncoder<'_, B>)
+    {
+        if let Some(bind_group) = &self.bind_group
+        {
+            render_pass_encoder.set_bind_group(FRAME_GROUP, bind_group);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{ShaderModuleDescriptor, VertexState, RenderPipelineDescriptor};
+    use gpu_backend::recording::{Call, RecordingBackend};
+
+    use super::*;
+    use crate::frame_uniforms::with_frame_prelude;
+
+
+    #[test]
+    fn only_pipelines_using_the_frame_get_a_bind_group()
+    {
+        let backend = RecordingBackend::new();
+        let module = backend.create_shader_module(&ShaderModuleDescriptor::new(""));
+        let pipeline = backend.create_render_pipeline(&RenderPipelineDescriptor::new(VertexState::new("vs", &module)));
+
+        let unused = with_frame_prelude("@vertex fn vs() -> @builtin(position) vec4f { return vec4f(1.0); }");
+        let used = with_frame_prelude("@fragment fn fs() -> @location(0) vec4f { return vec4f(frame.time); }");
+        assert!(FrameBinding::new(&backend, &pipeline, &[&unused]).bind_group().is_none());
+        let frame_binding = FrameBinding::new(&backend, &pipeline, &[&unused, &used]);
+        assert!(frame_binding.bind_group().is_some());
+        assert!(backend.calls().iter().any(|call| matches!(
+            call, Call::GetBindGroupLayout { pipeline: queried, index: FRAME_GROUP, .. } if *queried == pipeline
+        )));
+
+        let uniforms = FrameUniforms { time: 1.5, ..Default::default() };
+        frame_binding.write(&backend, &uniforms);
+        assert_eq!(backend.buffer_contents(frame_binding.buffer()).unwrap(), uniforms.to_bytes());
+    }
+}
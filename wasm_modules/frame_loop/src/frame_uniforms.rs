@@ -0,0 +1,109 @@
+use crate::clock::FrameTime;
+
+
+// the bind group the frame uniforms take in render pipelines, the chapters only use group 0
+pub const FRAME_GROUP: u32 = 1;
+
+// resolution and mouse are vec2fs, then time, delta_time and frame_index padded to the alignment of a vec2f
+pub const FRAME_UNIFORMS_SIZE: u64 = 4 * 4 + 3 * 4 + 4;
+
+
+// the values of shader/frame.wgsl
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameUniforms
+{
+    pub resolution: [f32; 2],
+    pub mouse: [f32; 2],
+    pub time: f32,
+    pub delta_time: f32,
+    pub frame_index: u32,
+}
+
+
+impl FrameUniforms
+{
+    pub fn new(frame_time: &FrameTime, resolution: [f32; 2], mouse: [f32; 2]) -> Self
+    {
+        FrameUniforms
+        {
+            resolution,
+            mouse,
+            time: frame_time.time as f32,
+            delta_time: frame_time.delta_time as f32,
+            frame_index: frame_time.frame_index,
+        }
+    }
+
+
+    pub fn to_bytes(&self) -> [u8; FRAME_UNIFORMS_SIZE as usize]
+    {
+        let mut bytes = [0; FRAME_UNIFORMS_SIZE as usize];
+        let values = [
+            self.resolution[0].to_bits(), self.resolution[1].to_bits(),
+            self.mouse[0].to_bits(), self.mouse[1].to_bits(),
+            self.time.to_bits(), self.delta_time.to_bits(), self.frame_index,
+        ];
+        for (chunk, value) in bytes.chunks_mut(4).zip(values)
+        {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+
+const FRAME_PRELUDE: &str = include_str!("../shader/frame.wgsl");
+
+
+// puts the declaration of frame in front of a shader
+pub fn with_frame_prelude(source: &str) -> String
+{
+    format!("{}\n{}", FRAME_PRELUDE, source)
+}
+
+
+// whether a shader refers to frame, the declaration in the prelude doesn't count. pipelines with an "auto"
+// layout leave out bind groups their shaders don't use, so only then is there a frame group to bind
+pub fn uses_frame(source: &str) -> bool
+{
+    let source = source.strip_prefix(FRAME_PRELUDE).unwrap_or(source);
+    source.lines()
+        .map(|line| line.split("//").next().unwrap())
+        .flat_map(|line| line.split(|c: char| !(c.is_alphanumeric() || c == '_')))
+        .any(|word| word == "frame")
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn uniforms_are_laid_out_like_the_wgsl_struct()
+    {
+        let frame_time = FrameTime { time: 2.5, delta_time: 0.5, frame_index: 7, steps: 1 };
+        let bytes = FrameUniforms::new(&frame_time, [640.0, 480.0], [10.0, 20.0]).to_bytes();
+        let words = bytes.chunks(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())).collect::<Vec<_>>();
+        let floats = words.iter().map(|word| f32::from_bits(*word)).collect::<Vec<_>>();
+        assert_eq!(floats[..6], [640.0, 480.0, 10.0, 20.0, 2.5, 0.5]);
+        assert_eq!(words[6..], [7, 0]);
+    }
+
+
+    #[test]
+    fn only_shaders_naming_frame_use_it()
+    {
+        assert!(uses_frame("let t = frame.time;"));
+        assert!(uses_frame("fn f() -> f32 { return sin(frame.time); }"));
+        assert!(!uses_frame("let frame_count = 3u; // frame.time"));
+        assert!(!uses_frame("var<uniform> keyframe: f32;"));
+
+        // the prelude alone doesn't count, it only declares frame
+        let source = with_frame_prelude("@fragment fn main() -> @location(0) vec4f { return vec4f(frame.time); }");
+        assert!(source.starts_with("// prepended"));
+        assert!(uses_frame(&source));
+        assert!(!uses_frame(&with_frame_prelude("@fragment fn main() -> @location(0) vec4f { return vec4f(1.0); }")));
+    }
+}
@@ -0,0 +1,11 @@
+mod clock;
+pub use clock::{FrameClock, FrameTime, Timestep};
+
+mod frame_binding;
+pub use frame_binding::FrameBinding;
+
+mod frame_uniforms;
+pub use frame_uniforms::{FrameUniforms, FRAME_GROUP, FRAME_UNIFORMS_SIZE, with_frame_prelude, uses_frame};
+
+mod web;
+pub use web::{Frame, FrameLoop};
@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::{prelude::{wasm_bindgen, Closure}, JsCast, JsValue};
+
+use web_sys::{HtmlCanvasElement, PointerEvent};
+
+use crate::clock::{FrameClock, FrameTime, Timestep};
+use crate::frame_uniforms::FrameUniforms;
+
+
+// what the frame loop hands to the scene every animation frame
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct Frame
+{
+    uniforms: FrameUniforms,
+    steps: u32,
+}
+
+
+#[wasm_bindgen]
+impl Frame
+{
+    pub fn time(&self) -> f32
+    {
+        self.uniforms.time
+    }
+
+
+    pub fn delta_time(&self) -> f32
+    {
+        self.uniforms.delta_time
+    }
+
+
+    pub fn frame_index(&self) -> u32
+    {
+        self.uniforms.frame_index
+    }
+
+
+    // how many fixed steps to simulate, 1 with a variable timestep
+    pub fn steps(&self) -> u32
+    {
+        self.steps
+    }
+}
+
+
+impl Frame
+{
+    pub fn new(frame_time: &FrameTime, resolution: [f32; 2], mouse: [f32; 2]) -> Self
+    {
+        Frame { uniforms: FrameUniforms::new(frame_time, resolution, mouse), steps: frame_time.steps }
+    }
+
+
+    pub fn uniforms(&self) -> &FrameUniforms
+    {
+        &self.uniforms
+    }
+}
+
+
+struct LoopState
+{
+    clock: FrameClock,
+    canvas: HtmlCanvasElement,
+    // in canvas pixels
+    mouse: [f32; 2],
+    callback: js_sys::Function,
+    request_id: Option<i32>,
+    on_animation_frame: Option<Closure<dyn FnMut(f64)>>,
+}
+
+
+// nothing is requested once the loop was freed, even from within its own callback
+fn request_animation_frame(state: &mut LoopState)
+{
+    if let (None, Some(on_animation_frame)) = (state.request_id, &state.on_animation_frame)
+    {
+        let on_animation_frame = on_animation_frame.as_ref().unchecked_ref();
+        state.request_id = web_sys::window().unwrap().request_animation_frame(on_animation_frame).ok();
+    }
+}
+
+
+fn cancel_animation_frame(state: &mut LoopState)
+{
+    if let Some(request_id) = state.request_id.take()
+    {
+        web_sys::window().unwrap().cancel_animation_frame(request_id).unwrap();
+    }
+}
+
+
+fn on_animation_frame(state: &Weak<RefCell<LoopState>>, timestamp: f64)
+{
+    let Some(state) = state.upgrade()
+    else
+    {
+        return;
+    };
+
+    let (frame, callback) =
+    {
+        let mut state = state.borrow_mut();
+        state.request_id = None;
+        let Some(frame_time) = state.clock.tick(timestamp)
+        else
+        {
+            return;
+        };
+        let resolution = [state.canvas.width() as f32, state.canvas.height() as f32];
+        (Frame::new(&frame_time, resolution, state.mouse), state.callback.clone())
+    };
+
+    // the callback may pause the loop, so the state isn't borrowed while it runs
+    if let Err(error) = callback.call1(&JsValue::NULL, &JsValue::from(frame))
+    {
+        web_sys::console::error_1(&error);
+    }
+
+    let mut state = state.borrow_mut();
+    if !state.clock.is_paused()
+    {
+        request_animation_frame(&mut state);
+    }
+}
+
+
+// calls back with a Frame on every animation frame of the browser, until paused or freed
+#[wasm_bindgen]
+pub struct FrameLoop
+{
+    state: Rc<RefCell<LoopState>>,
+    on_pointer_move: Closure<dyn FnMut(PointerEvent)>,
+}
+
+
+#[wasm_bindgen]
+impl FrameLoop
+{
+    // starts right away with a variable timestep, the mouse is followed over the canvas
+    pub fn start(canvas: HtmlCanvasElement, callback: js_sys::Function) -> Self
+    {
+        let state = Rc::new(RefCell::new(LoopState
+        {
+            clock: FrameClock::new(Timestep::Variable),
+            canvas: canvas.clone(),
+            mouse: [0.0; 2],
+            callback,
+            request_id: None,
+            on_animation_frame: None,
+        }));
+
+        let weak_state = Rc::downgrade(&state);
+        let on_animation_frame = Closure::<dyn FnMut(f64)>::new(move |timestamp|
+            {
+                on_animation_frame(&weak_state, timestamp)
+            });
+
+        // css pixels to the pixels of the drawing buffer, like @builtin(position)
+        let weak_state = Rc::downgrade(&state);
+        let on_pointer_move = Closure::<dyn FnMut(PointerEvent)>::new(move |event: PointerEvent|
+            {
+                if let Some(state) = weak_state.upgrade()
+                {
+                    let mut state = state.borrow_mut();
+                    let scale_x = state.canvas.width() as f32 / state.canvas.client_width().max(1) as f32;
+                    let scale_y = state.canvas.height() as f32 / state.canvas.client_height().max(1) as f32;
+                    state.mouse = [event.offset_x() as f32 * scale_x, event.offset_y() as f32 * scale_y];
+                }
+            });
+        canvas.add_event_listener_with_callback("pointermove", on_pointer_move.as_ref().unchecked_ref()).unwrap();
+
+        {
+            let mut state = state.borrow_mut();
+            state.on_animation_frame = Some(on_animation_frame);
+            request_animation_frame(&mut state);
+        }
+
+        FrameLoop { state, on_pointer_move }
+    }
+
+
+    pub fn pause(&self)
+    {
+        let mut state = self.state.borrow_mut();
+        state.clock.pause();
+        cancel_animation_frame(&mut state);
+    }
+
+
+    pub fn resume(&self)
+    {
+        let mut state = self.state.borrow_mut();
+        if state.clock.is_paused()
+        {
+            state.clock.resume();
+            request_animation_frame(&mut state);
+        }
+    }
+
+
+    pub fn is_paused(&self) -> bool
+    {
+        self.state.borrow().clock.is_paused()
+    }
+
+
+    // time advances in steps of step seconds, see Frame::steps
+    pub fn set_fixed_timestep(&self, step: f64) -> Result<(), String>
+    {
+        if step.is_nan() || step <= 0.0
+        {
+            return Err(format!("a fixed timestep has to be positive, not {}", step));
+        }
+        self.state.borrow_mut().clock.set_timestep(Timestep::Fixed(step));
+        Ok(())
+    }
+
+
+    pub fn set_variable_timestep(&self)
+    {
+        self.state.borrow_mut().clock.set_timestep(Timestep::Variable);
+    }
+}
+
+
+impl Drop for FrameLoop
+{
+    fn drop(&mut self)
+    {
+        let mut state = self.state.borrow_mut();
+        cancel_animation_frame(&mut state);
+        state.canvas
+            .remove_event_listener_with_callback("pointermove", self.on_pointer_move.as_ref().unchecked_ref())
+            .unwrap();
+        // the closure refers to the state only weakly, dropping it here ends the loop
+        state.on_animation_frame = None;
+    }
+}
@@ -1,9 +1,10 @@
 @group(0) @binding(0) var<storage, read_write> data: array<f32>;
  
 
-@compute @workgroup_size(1)
+@compute @workgroup_size(workgroup_size)
 fn compute_main(@builtin(global_invocation_id) id: vec3<u32>) 
 {
-    let i = id.x;
+    let i = element_index(id);
+    if (i >= dispatch_info.element_count) { return; }
     data[i] = data[i] * 2.0;
 }
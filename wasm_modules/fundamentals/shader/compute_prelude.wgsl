@@ -0,0 +1,23 @@
+// prepended to every compute kernel, workgroup_size is filled in by the kernel
+const workgroup_size: u32 = {{workgroup_size}}u;
+
+struct DispatchInfo
+{
+    element_count: u32,
+    element_offset: u32,
+    invocations_per_row: u32,
+    invocations_per_slice: u32,
+};
+
+@group(1) @binding(0) var<uniform> dispatch_info: DispatchInfo;
+
+
+// large dispatches are spread over a 3d grid and several passes,
+// this maps the invocation back to the index of the element it processes
+fn element_index(global_id: vec3u) -> u32
+{
+    return dispatch_info.element_offset +
+        global_id.x +
+        global_id.y * dispatch_info.invocations_per_row +
+        global_id.z * dispatch_info.invocations_per_slice;
+}
@@ -0,0 +1,12 @@
+// adds the exclusive scan of the block totals to every element of its block
+@group(0) @binding(0) var<storage, read_write> data: array<{{element_type}}>;
+@group(0) @binding(1) var<storage, read> block_offsets: array<{{element_type}}>;
+
+
+@compute @workgroup_size(workgroup_size)
+fn add_offsets_main(@builtin(global_invocation_id) id: vec3<u32>)
+{
+    let i = element_index(id);
+    if (i >= dispatch_info.element_count) { return; }
+    data[i] += block_offsets[i / workgroup_size];
+}
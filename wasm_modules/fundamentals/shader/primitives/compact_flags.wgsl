@@ -0,0 +1,12 @@
+// turns any non zero flag into 1, so the scan of the flags gives the output positions
+@group(0) @binding(0) var<storage, read> flags: array<u32>;
+@group(0) @binding(1) var<storage, read_write> keep: array<u32>;
+
+
+@compute @workgroup_size(workgroup_size)
+fn compact_flags_main(@builtin(global_invocation_id) id: vec3<u32>)
+{
+    let i = element_index(id);
+    if (i >= dispatch_info.element_count) { return; }
+    keep[i] = select(0u, 1u, flags[i] != 0u);
+}
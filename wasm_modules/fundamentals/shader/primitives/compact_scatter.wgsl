@@ -0,0 +1,17 @@
+// moves every kept element to its position given by the exclusive scan of the keep flags
+@group(0) @binding(0) var<storage, read> values: array<{{element_type}}>;
+@group(0) @binding(1) var<storage, read> keep: array<u32>;
+@group(0) @binding(2) var<storage, read> positions: array<u32>;
+@group(0) @binding(3) var<storage, read_write> output: array<{{element_type}}>;
+
+
+@compute @workgroup_size(workgroup_size)
+fn compact_scatter_main(@builtin(global_invocation_id) id: vec3<u32>)
+{
+    let i = element_index(id);
+    if (i >= dispatch_info.element_count) { return; }
+    if (keep[i] != 0u)
+    {
+        output[positions[i]] = values[i];
+    }
+}
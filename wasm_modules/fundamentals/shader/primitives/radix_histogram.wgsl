@@ -0,0 +1,32 @@
+// counts the digits params[0] bits up the keys of every workgroup sized block, the counts are
+// stored digit major, so the exclusive scan of them is where every block puts each digit
+@group(0) @binding(0) var<storage, read> keys: array<u32>;
+@group(0) @binding(1) var<storage, read_write> histograms: array<u32>;
+@group(0) @binding(2) var<storage, read> params: array<u32>;
+
+const radix_size: u32 = 16u;
+
+var<workgroup> digit_counts: array<atomic<u32>, radix_size>;
+
+
+@compute @workgroup_size(workgroup_size)
+fn radix_histogram_main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local_index: u32)
+{
+    let i = element_index(id);
+    let count = dispatch_info.element_count;
+    if (i < count)
+    {
+        atomicAdd(&digit_counts[(keys[i] >> params[0]) & (radix_size - 1u)], 1u);
+    }
+    workgroupBarrier();
+
+    let block_count = (count + workgroup_size - 1u) / workgroup_size;
+    let block = i / workgroup_size;
+    if (block >= block_count) { return; }
+
+    // workgroups can be smaller than the radix
+    for (var digit = local_index; digit < radix_size; digit += workgroup_size)
+    {
+        histograms[digit * block_count + block] = atomicLoad(&digit_counts[digit]);
+    }
+}
@@ -0,0 +1,39 @@
+// moves every key to where its block puts its digit, after the keys of the same digit earlier
+// in the block, so every pass is stable. values move along with their keys
+@group(0) @binding(0) var<storage, read> keys_in: array<u32>;
+@group(0) @binding(1) var<storage, read> values_in: array<u32>;
+@group(0) @binding(2) var<storage, read> digit_offsets: array<u32>;
+@group(0) @binding(3) var<storage, read> params: array<u32>;
+@group(0) @binding(4) var<storage, read_write> keys_out: array<u32>;
+@group(0) @binding(5) var<storage, read_write> values_out: array<u32>;
+
+const radix_size: u32 = 16u;
+
+var<workgroup> block_digits: array<u32, workgroup_size>;
+
+
+@compute @workgroup_size(workgroup_size)
+fn radix_scatter_main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local_index: u32)
+{
+    let i = element_index(id);
+    let count = dispatch_info.element_count;
+
+    // elements past the end get a digit no key has
+    var digit = radix_size;
+    if (i < count) { digit = (keys_in[i] >> params[0]) & (radix_size - 1u); }
+    block_digits[local_index] = digit;
+    workgroupBarrier();
+
+    if (i >= count) { return; }
+
+    var rank = 0u;
+    for (var j = 0u; j < local_index; j++)
+    {
+        rank += select(0u, 1u, block_digits[j] == digit);
+    }
+
+    let block_count = (count + workgroup_size - 1u) / workgroup_size;
+    let destination = digit_offsets[digit * block_count + i / workgroup_size] + rank;
+    keys_out[destination] = keys_in[i];
+    values_out[destination] = values_in[i];
+}
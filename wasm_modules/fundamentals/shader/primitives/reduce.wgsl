@@ -0,0 +1,41 @@
+// every workgroup combines its elements into one partial result,
+// the kernel runs again over the partials until a single value is left
+@group(0) @binding(0) var<storage, read> input: array<{{element_type}}>;
+@group(0) @binding(1) var<storage, read_write> partials: array<{{element_type}}>;
+
+var<workgroup> shared_values: array<{{element_type}}, workgroup_size>;
+
+
+fn combine(a: {{element_type}}, b: {{element_type}}) -> {{element_type}}
+{
+    return {{combine}};
+}
+
+
+@compute @workgroup_size(workgroup_size)
+fn reduce_main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local_index: u32)
+{
+    let i = element_index(id);
+
+    var value = {{identity}};
+    if (i < dispatch_info.element_count) { value = input[i]; }
+    shared_values[local_index] = value;
+    workgroupBarrier();
+
+    // every step folds the upper half of the remaining values onto the lower half, rounding the
+    // half up keeps workgroup sizes that aren't a power of two from dropping the odd value
+    for (var remaining = workgroup_size; remaining > 1u; remaining = (remaining + 1u) / 2u)
+    {
+        let stride = (remaining + 1u) / 2u;
+        if (local_index + stride < remaining)
+        {
+            shared_values[local_index] = combine(shared_values[local_index], shared_values[local_index + stride]);
+        }
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u && i < dispatch_info.element_count)
+    {
+        partials[i / workgroup_size] = shared_values[0];
+    }
+}
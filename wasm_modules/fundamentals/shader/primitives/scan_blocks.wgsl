@@ -0,0 +1,46 @@
+// scans every workgroup sized block on its own and stores the total of each block,
+// the scanned block totals are added back by add_offsets.wgsl
+// params[0] - 1 for an inclusive scan, 0 for an exclusive one
+@group(0) @binding(0) var<storage, read> input: array<{{element_type}}>;
+@group(0) @binding(1) var<storage, read_write> output: array<{{element_type}}>;
+@group(0) @binding(2) var<storage, read_write> block_sums: array<{{element_type}}>;
+@group(0) @binding(3) var<storage, read> params: array<u32>;
+
+var<workgroup> shared_values: array<{{element_type}}, workgroup_size>;
+
+
+@compute @workgroup_size(workgroup_size)
+fn scan_blocks_main(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local_index: u32)
+{
+    let i = element_index(id);
+
+    var value = {{identity}};
+    if (i < dispatch_info.element_count) { value = input[i]; }
+    shared_values[local_index] = value;
+    workgroupBarrier();
+
+    // hillis-steele scan, every step reads before any invocation writes
+    for (var offset = 1u; offset < workgroup_size; offset *= 2u)
+    {
+        var addend = {{identity}};
+        if (local_index >= offset) { addend = shared_values[local_index - offset]; }
+        workgroupBarrier();
+        shared_values[local_index] += addend;
+        workgroupBarrier();
+    }
+
+    var exclusive = {{identity}};
+    if (local_index > 0u) { exclusive = shared_values[local_index - 1u]; }
+    let inclusive = shared_values[local_index];
+
+    if (i < dispatch_info.element_count)
+    {
+        output[i] = select(exclusive, inclusive, params[0] != 0u);
+    }
+
+    let block_start = i - local_index;
+    if (local_index == workgroup_size - 1u && block_start < dispatch_info.element_count)
+    {
+        block_sums[block_start / workgroup_size] = inclusive;
+    }
+}
@@ -0,0 +1,438 @@
+use std::cell::RefCell;
+
+use gpu_backend::
+{
+    Backend, ShaderModuleDescriptor, ComputePipelineDescriptor, BufferDescriptor, BindGroupDescriptor,
+    BindGroupEntry, BindingResource, CommandEncoder, GrowableBuffer, AsBytes,
+};
+use gpu_backend::buffer_usage::{COPY_SRC, COPY_DST, STORAGE, MAP_READ, UNIFORM};
+
+use crate::gpu_array::{GpuElement, StorageBinding, decode_elements, encode_elements, round_up};
+
+
+const PREFERRED_WORKGROUP_SIZE: u32 = 64;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BindingKind
+{
+    Input,          // uploaded before the dispatch, var<storage, read>
+    Output,         // read back after the dispatch, var<storage, read_write>
+    InputOutput,    // uploaded and read back, var<storage, read_write>
+}
+
+
+impl BindingKind
+{
+    fn is_uploaded(&self) -> bool
+    {
+        matches!(self, BindingKind::Input | BindingKind::InputOutput)
+    }
+
+
+    fn is_read_back(&self) -> bool
+    {
+        matches!(self, BindingKind::Output | BindingKind::InputOutput)
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DispatchPass
+{
+    pub element_offset: u32,
+    pub workgroups: [u32; 3],
+    pub invocations_per_row: u32,
+    pub invocations_per_slice: u32,
+}
+
+
+// splits a dispatch of element_count invocations into passes whose workgroup counts stay
+// within max_workgroups_per_dimension, filling x first, then y and z
+pub fn plan_dispatch(element_count: u32, workgroup_size: u32, max_workgroups_per_dimension: u32) -> Vec<DispatchPass>
+{
+    let max = max_workgroups_per_dimension.max(1) as u64;
+    let mut remaining_workgroups = (element_count as u64).div_ceil(workgroup_size as u64);
+    let mut element_offset = 0u64;
+    let mut passes = Vec::new();
+
+    while remaining_workgroups > 0
+    {
+        let x = remaining_workgroups.min(max);
+        let y = remaining_workgroups.div_ceil(x).min(max);
+        let z = remaining_workgroups.div_ceil(x * y).min(max);
+
+        let invocations_per_row = x * workgroup_size as u64;
+        passes.push(DispatchPass
+            {
+                element_offset: element_offset as u32,
+                workgroups: [x as u32, y as u32, z as u32],
+                invocations_per_row: invocations_per_row as u32,
+                invocations_per_slice: (invocations_per_row * y) as u32,
+            });
+
+        let covered_workgroups = (x * y * z).min(remaining_workgroups);
+        remaining_workgroups -= covered_workgroups;
+        element_offset += covered_workgroups * workgroup_size as u64;
+    }
+
+    passes
+}
+
+
+// puts the prelude in front of the kernel source, enable directives have to stay
+// at the very top of the module so they are moved in front of the prelude
+pub fn module_source(source: &str, workgroup_size: u32) -> String
+{
+    let prelude = include_str!("../shader/compute_prelude.wgsl")
+        .replace("{{workgroup_size}}", &workgroup_size.to_string());
+    let (directives, body): (Vec<&str>, Vec<&str>) = source.lines()
+        .partition(|line| line.trim_start().starts_with("enable "));
+
+    format!("{}\n{}\n{}", directives.join("\n"), prelude, body.join("\n"))
+}
+
+
+pub struct ComputeKernel<B: Backend>
+{
+    backend: B,
+    label: String,
+    compute_pipeline: B::ComputePipeline,
+    workgroup_size: u32,
+    max_workgroups_per_dimension: u32,
+    bindings: Vec<BindingKind>,
+    storage_buffers: RefCell<Vec<Option<GrowableBuffer<B>>>>,
+    // the dispatch info uniform buffer of every pass and the bind group of it, rewritten by every dispatch
+    dispatch_infos: RefCell<Vec<(B::Buffer, B::BindGroup)>>,
+}
+
+
+impl<B: Backend> ComputeKernel<B>
+{
+    // the source has to use workgroup_size in its @workgroup_size attribute and can use
+    // element_index() and dispatch_info from shader/compute_prelude.wgsl
+    pub fn create(backend: &B, label: &str, source: &str, entry_point: &str) -> Self
+    {
+        let limits = backend.limits();
+        let workgroup_size = PREFERRED_WORKGROUP_SIZE
+            .min(limits.max_compute_invocations_per_workgroup)
+            .min(limits.max_compute_workgroup_size_x);
+
+        let module_source = module_source(source, workgroup_size);
+        let mut compute_shader_module_descriptor = ShaderModuleDescriptor::new(&module_source);
+        compute_shader_module_descriptor.label(&format!("{} module", label));
+        let compute_shader_module = backend.create_shader_module(&compute_shader_module_descriptor);
+
+        let mut compute_pipeline_descriptor = ComputePipelineDescriptor::new(entry_point, &compute_shader_module);
+        compute_pipeline_descriptor.label(&format!("{} pipeline", label));
+        let compute_pipeline = backend.create_compute_pipeline(&compute_pipeline_descriptor);
+
+        ComputeKernel
+        {
+            backend: backend.clone(),
+            label: label.to_owned(),
+            compute_pipeline,
+            workgroup_size,
+            max_workgroups_per_dimension: limits.max_compute_workgroups_per_dimension,
+            bindings: Vec::new(),
+            storage_buffers: RefCell::new(Vec::new()),
+            dispatch_infos: RefCell::new(Vec::new()),
+        }
+    }
+
+
+    // bindings are assigned to @group(0) in the order they are added, starting at @binding(0)
+    pub fn add_binding(&mut self, kind: BindingKind)
+    {
+        self.bindings.push(kind);
+        self.storage_buffers.borrow_mut().push(None);
+    }
+
+
+    pub fn workgroup_size(&self) -> u32
+    {
+        self.workgroup_size
+    }
+
+
+    fn storage_buffer(&self, binding: usize, size: u64) -> B::Buffer
+    {
+        let mut storage_buffers = self.storage_buffers.borrow_mut();
+        let storage_buffer = storage_buffers[binding].get_or_insert_with(||
+            {
+                let mut storage_buffer_descriptor = BufferDescriptor::new(size, STORAGE | COPY_DST | COPY_SRC);
+                storage_buffer_descriptor.label(&format!("{} storage buffer: {}", self.label, binding));
+                GrowableBuffer::new(&self.backend, &storage_buffer_descriptor)
+            });
+        storage_buffer.reserve(&self.backend, size);
+        storage_buffer.buffer().clone()
+    }
+
+
+    fn bind_group_0(&self, entries: Vec<BindGroupEntry<B>>) -> B::BindGroup
+    {
+        let bind_group_0_descriptor = BindGroupDescriptor::new(
+            entries, &self.backend.compute_bind_group_layout(&self.compute_pipeline, 0),
+        );
+        self.backend.create_bind_group(&bind_group_0_descriptor)
+    }
+
+
+    // the bind group of the dispatch info of a pass, the buffers are created by the first dispatch with that many
+    // passes. the writes land before the submit that follows them, so every dispatch can reuse them
+    fn bind_group_1(&self, pass: usize, dispatch_info: [u32; 4]) -> B::BindGroup
+    {
+        let mut dispatch_infos = self.dispatch_infos.borrow_mut();
+        while dispatch_infos.len() <= pass
+        {
+            let mut dispatch_info_buffer_descriptor = BufferDescriptor::new(
+                4 * 4,  // 4 32bit unsigned integers (4bytes each)
+                UNIFORM | COPY_DST,
+            );
+            dispatch_info_buffer_descriptor.label(&format!("{} dispatch info: {}", self.label, dispatch_infos.len()));
+            let dispatch_info_buffer = self.backend.create_buffer(&dispatch_info_buffer_descriptor);
+
+            let bind_group_1_descriptor = BindGroupDescriptor::new(
+                vec![BindGroupEntry::buffer(0, &dispatch_info_buffer)],
+                &self.backend.compute_bind_group_layout(&self.compute_pipeline, 1),
+            );
+            let bind_group_1 = self.backend.create_bind_group(&bind_group_1_descriptor);
+            dispatch_infos.push((dispatch_info_buffer, bind_group_1));
+        }
+
+        let (dispatch_info_buffer, bind_group_1) = &dispatch_infos[pass];
+        self.backend.write_buffer(dispatch_info_buffer, 0, dispatch_info.as_bytes());
+        bind_group_1.clone()
+    }
+
+
+    fn encode_passes(&self, command_encoder: &mut CommandEncoder<B>, bind_group_0: &B::BindGroup, element_count: u32)
+    {
+        let passes = plan_dispatch(element_count, self.workgroup_size, self.max_workgroups_per_dimension);
+        for (i, pass) in passes.iter().enumerate()
+        {
+            let bind_group_1 = self.bind_group_1(i, [
+                element_count, pass.element_offset, pass.invocations_per_row, pass.invocations_per_slice,
+            ]);
+
+            let pass_label = format!("{} pass: {}", self.label, i);
+            let mut compute_pass = command_encoder.begin_compute_pass(Some(&pass_label));
+            compute_pass.set_pipeline(&self.compute_pipeline);
+            compute_pass.set_bind_group(0, bind_group_0);
+            compute_pass.set_bind_group(1, &bind_group_1);
+            let [x, y, z] = pass.workgroups;
+            compute_pass.dispatch_workgroups(x, y, z);
+            compute_pass.end();
+        }
+    }
+
+
+    // runs the kernel over arrays the caller owns, one per binding in binding order, the results
+    // stay on the gpu and can be read back from the arrays once they are needed
+    pub fn dispatch_arrays(&self, element_count: u32, arrays: &[&dyn StorageBinding<B>]) -> Result<(), String>
+    {
+        if arrays.len() != self.bindings.len()
+        {
+            return Err(format!("{}: expected {} arrays, got {}", self.label, self.bindings.len(), arrays.len()));
+        }
+
+        let bind_group_0_entries = arrays.iter().enumerate()
+            .map(|(binding, array)| BindGroupEntry::new(binding as u32, BindingResource::Buffer
+                {
+                    buffer: array.storage_buffer().clone(), offset: 0, size: Some(array.byte_length()),
+                }))
+            .collect::<Vec<BindGroupEntry<B>>>();
+        let bind_group_0 = self.bind_group_0(bind_group_0_entries);
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label(&format!("{} encoder", self.label));
+        self.encode_passes(&mut command_encoder, &bind_group_0, element_count);
+
+        self.backend.submit(vec![command_encoder.finish()]);
+        Ok(())
+    }
+
+
+    // uploads the inputs to the bindings that are uploaded and reads back the ones that are read back, every binding
+    // holds elements of T
+    pub async fn dispatch<T: GpuElement>(&self, element_count: u32, inputs: &[&[T]]) -> Result<Vec<Vec<T>>, String>
+    {
+        let uploaded_count = self.bindings.iter().filter(|kind| kind.is_uploaded()).count();
+        if inputs.len() != uploaded_count
+        {
+            return Err(format!("{}: expected {} inputs, got {}", self.label, uploaded_count, inputs.len()));
+        }
+
+        let mut inputs = inputs.iter();
+        let mut bind_group_0_entries = Vec::new();
+        let mut read_backs = Vec::new();
+
+        for (binding, kind) in self.bindings.iter().enumerate()
+        {
+            let input = if kind.is_uploaded() { inputs.next().copied() } else { None };
+            let length = input.map(|input| input.len() as u64).unwrap_or(element_count.into());
+            // zero sized bindings are not allowed, keep at least one element
+            let size = round_up(length.max(1) as usize * T::STRIDE, 4) as u64;
+
+            let storage_buffer = self.storage_buffer(binding, size);
+            if let Some(input) = input
+            {
+                self.backend.write_buffer(&storage_buffer, 0, &encode_elements(input));
+            }
+
+            bind_group_0_entries.push(BindGroupEntry::new(binding as u32, BindingResource::Buffer
+                {
+                    buffer: storage_buffer.clone(), offset: 0, size: Some(size),
+                }));
+
+            if kind.is_read_back()
+            {
+                read_backs.push((storage_buffer, length));
+            }
+        }
+
+        let bind_group_0 = self.bind_group_0(bind_group_0_entries);
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label(&format!("{} encoder", self.label));
+        self.encode_passes(&mut command_encoder, &bind_group_0, element_count);
+
+        let result_buffers = read_backs.iter().enumerate().map(|(i, (storage_buffer, length))|
+            {
+                let size = round_up((*length).max(1) as usize * T::STRIDE, 4) as u64;
+                let mut result_buffer_descriptor = BufferDescriptor::new(size, MAP_READ | COPY_DST);
+                result_buffer_descriptor.label(&format!("{} result buffer: {}", self.label, i));
+                let result_buffer = self.backend.create_buffer(&result_buffer_descriptor);
+                command_encoder.copy_buffer_to_buffer(storage_buffer, 0, &result_buffer, 0, size);
+                (result_buffer, size, *length)
+            })
+            .collect::<Vec<(B::Buffer, u64, u64)>>();
+
+        self.backend.submit(vec![command_encoder.finish()]);
+
+        let mut outputs = Vec::new();
+        for (result_buffer, size, length) in result_buffers
+        {
+            let bytes = self.backend.read_buffer(&result_buffer, 0, size).await;
+            self.backend.destroy_buffer(&result_buffer);
+            outputs.push(decode_elements(&bytes?, length as usize));
+        }
+
+        Ok(outputs)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::Limits;
+
+    use software_gpu::{SoftwareBackend, block_on};
+
+    use super::*;
+
+
+    #[test]
+    fn dispatches_reuse_the_dispatch_info_of_every_pass()
+    {
+        let backend = SoftwareBackend::new();
+        backend.set_limits(Limits { max_compute_workgroups_per_dimension: 2, ..Limits::default() });
+        let mut kernel = ComputeKernel::create(
+            &backend, "doubling", include_str!("../shader/compute.wgsl"), "compute_main",
+        );
+        kernel.add_binding(BindingKind::InputOutput);
+
+        // 2x2x2 workgroups a pass, 1000 values take two of them and 100 values one
+        for count in [1000, 600, 100, 1000]
+        {
+            let values = (0..count).map(|i| i as f32).collect::<Vec<f32>>();
+            let doubled = block_on(kernel.dispatch(count, &[&values])).unwrap();
+            assert_eq!(doubled[0], values.iter().map(|value| value * 2.0).collect::<Vec<f32>>());
+        }
+        assert_eq!(kernel.dispatch_infos.borrow().len(), 2);
+    }
+
+
+    #[test]
+    fn dispatches_read_back_the_element_type_of_the_kernel()
+    {
+        let backend = SoftwareBackend::new();
+        let source = "
+            @group(0) @binding(0) var<storage, read> input: array<u32>;
+            @group(0) @binding(1) var<storage, read_write> output: array<u32>;
+
+            @compute @workgroup_size(workgroup_size)
+            fn main(@builtin(global_invocation_id) id: vec3<u32>)
+            {
+                let i = element_index(id);
+                if (i >= dispatch_info.element_count) { return; }
+                output[i] = input[i] * 3u + 1u;
+            }
+        ";
+        let mut kernel = ComputeKernel::create(&backend, "triple", source, "main");
+        kernel.add_binding(BindingKind::Input);
+        kernel.add_binding(BindingKind::Output);
+
+        let values = (0..100).map(|i| 4_000_000_000 - i).collect::<Vec<u32>>();
+        let outputs = block_on(kernel.dispatch(values.len() as u32, &[&values])).unwrap();
+        let expected = values.iter().map(|value| value.wrapping_mul(3).wrapping_add(1)).collect::<Vec<u32>>();
+        assert_eq!(outputs, vec![expected]);
+    }
+
+
+    #[test]
+    fn dispatches_of_exact_multiples_of_the_workgroup_size_take_whole_workgroups()
+    {
+        assert_eq!(plan_dispatch(256, 64, 65535), vec![DispatchPass
+            {
+                element_offset: 0, workgroups: [4, 1, 1], invocations_per_row: 256, invocations_per_slice: 256,
+            }]);
+        assert_eq!(plan_dispatch(0, 64, 65535), Vec::new());
+    }
+
+
+    #[test]
+    fn dispatches_with_a_remainder_take_one_more_workgroup()
+    {
+        assert_eq!(plan_dispatch(130, 64, 65535), vec![DispatchPass
+            {
+                element_offset: 0, workgroups: [3, 1, 1], invocations_per_row: 192, invocations_per_slice: 192,
+            }]);
+    }
+
+
+    #[test]
+    fn dispatches_beyond_the_workgroup_limit_spill_into_y_and_further_passes()
+    {
+        // one element more than a row of 65535 workgroups holds
+        let element_count = 65535 * 64 + 1;
+        assert_eq!(plan_dispatch(element_count, 64, 65535), vec![DispatchPass
+            {
+                element_offset: 0,
+                workgroups: [65535, 2, 1],
+                invocations_per_row: 65535 * 64,
+                invocations_per_slice: 65535 * 64 * 2,
+            }]);
+
+        // 16 workgroups with at most 2 a dimension take two 2x2x2 passes
+        let passes = plan_dispatch(1000, 64, 2);
+        assert_eq!(passes.len(), 2);
+        assert_eq!(passes.iter().map(|pass| pass.element_offset).collect::<Vec<u32>>(), vec![0, 512]);
+        assert!(passes.iter().all(|pass| pass.workgroups == [2, 2, 2]));
+        assert!(passes.iter().all(|pass| pass.invocations_per_row == 128 && pass.invocations_per_slice == 256));
+    }
+
+
+    #[test]
+    fn module_sources_fill_in_the_workgroup_size_and_keep_enable_directives_at_the_top()
+    {
+        let source = module_source("enable f16;\n@compute @workgroup_size(workgroup_size)\nfn main() {}", 32);
+
+        assert!(source.starts_with("enable f16;\n"));
+        assert!(source.contains("const workgroup_size: u32 = 32u;"));
+        assert!(!source.contains("{{workgroup_size}}"));
+        assert_eq!(source.matches("enable f16;").count(), 1);
+        assert!(source.find("fn element_index").unwrap() < source.find("fn main").unwrap());
+    }
+}
@@ -0,0 +1,549 @@
+use std::marker::PhantomData;
+
+use half::f16;
+
+use gpu_backend::{Backend, BufferDescriptor, CommandEncoder};
+use gpu_backend::buffer_usage::{COPY_SRC, COPY_DST, STORAGE, MAP_READ};
+
+
+pub const fn round_up(value: usize, alignment: usize) -> usize
+{
+    value.div_ceil(alignment) * alignment
+}
+
+
+// a value that can live in a WGSL storage buffer, SIZE and ALIGN follow the WGSL
+// memory layout rules, so arrays of elements are laid out with STRIDE bytes apart
+pub trait GpuElement: Copy + Default
+{
+    const SIZE: usize;
+    const ALIGN: usize;
+    const STRIDE: usize = round_up(Self::SIZE, Self::ALIGN);
+
+    fn wgsl_type() -> String;
+
+    // the declarations kernels need in front of them to use the type, empty for builtin types
+    fn wgsl_declaration() -> String
+    {
+        String::new()
+    }
+
+    // the device feature the element type needs, like "shader-f16"
+    fn required_feature() -> Option<&'static str>
+    {
+        None
+    }
+
+    fn write_bytes(&self, bytes: &mut [u8]);
+
+    fn read_bytes(bytes: &[u8]) -> Self;
+}
+
+
+// scalars are the only elements vectors can be made of
+pub trait GpuScalar: GpuElement {}
+
+
+macro_rules! impl_gpu_scalar
+{
+    ($ty:ty, $wgsl_type:literal, $feature:expr) =>
+    {
+        impl GpuElement for $ty
+        {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+            const ALIGN: usize = std::mem::size_of::<$ty>();
+
+            fn wgsl_type() -> String
+            {
+                $wgsl_type.to_owned()
+            }
+
+            fn required_feature() -> Option<&'static str>
+            {
+                $feature
+            }
+
+            fn write_bytes(&self, bytes: &mut [u8])
+            {
+                bytes[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_bytes(bytes: &[u8]) -> Self
+            {
+                <$ty>::from_le_bytes(bytes[..Self::SIZE].try_into().unwrap())
+            }
+        }
+
+        impl GpuScalar for $ty {}
+    };
+}
+
+
+impl_gpu_scalar!(u32, "u32", None);
+impl_gpu_scalar!(i32, "i32", None);
+impl_gpu_scalar!(f32, "f32", None);
+impl_gpu_scalar!(f16, "f16", Some("shader-f16"));
+
+
+// [S; 2] and [S; 4] map to vec2<S> and vec4<S>, vec3 is left out because its 16 byte
+// alignment makes a [S; 3] silently disagree with the WGSL layout
+macro_rules! impl_gpu_vector
+{
+    ($n:literal) =>
+    {
+        impl<S: GpuScalar> GpuElement for [S; $n]
+        {
+            const SIZE: usize = S::SIZE * $n;
+            const ALIGN: usize = S::SIZE * $n;
+
+            fn wgsl_type() -> String
+            {
+                format!("vec{}<{}>", $n, S::wgsl_type())
+            }
+
+            fn required_feature() -> Option<&'static str>
+            {
+                S::required_feature()
+            }
+
+            fn write_bytes(&self, bytes: &mut [u8])
+            {
+                for (i, component) in self.iter().enumerate()
+                {
+                    component.write_bytes(&mut bytes[i * S::SIZE..]);
+                }
+            }
+
+            fn read_bytes(bytes: &[u8]) -> Self
+            {
+                std::array::from_fn(|i| S::read_bytes(&bytes[i * S::SIZE..]))
+            }
+        }
+    };
+}
+
+
+impl_gpu_vector!(2);
+impl_gpu_vector!(4);
+
+
+// declares a struct together with its WGSL memory layout, every field has to be a GpuElement
+//
+// gpu_struct!
+// {
+//     pub struct Particle
+//     {
+//         pub position: [f32; 4],
+//         pub velocity: [f32; 2],
+//         pub mass: f32,
+//     }
+// }
+#[macro_export]
+macro_rules! gpu_struct
+{
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident
+        {
+            $($field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) =>
+    {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq)]
+        $vis struct $name
+        {
+            $($field_vis $field: $ty),*
+        }
+
+        impl $crate::gpu_array::GpuElement for $name
+        {
+            const ALIGN: usize =
+            {
+                let mut align = 1;
+                $(
+                    if <$ty as $crate::gpu_array::GpuElement>::ALIGN > align
+                    {
+                        align = <$ty as $crate::gpu_array::GpuElement>::ALIGN;
+                    }
+                )*
+                align
+            };
+
+            const SIZE: usize =
+            {
+                let mut offset = 0;
+                $(
+                    offset = $crate::gpu_array::round_up(offset, <$ty as $crate::gpu_array::GpuElement>::ALIGN);
+                    offset += <$ty as $crate::gpu_array::GpuElement>::SIZE;
+                )*
+                $crate::gpu_array::round_up(offset, Self::ALIGN)
+            };
+
+            fn wgsl_type() -> String
+            {
+                stringify!($name).to_owned()
+            }
+
+            fn wgsl_declaration() -> String
+            {
+                let fields = [$(format!("    {}: {},", stringify!($field), <$ty as $crate::gpu_array::GpuElement>::wgsl_type())),*];
+                format!("struct {}\n{{\n{}\n}};\n", stringify!($name), fields.join("\n"))
+            }
+
+            fn required_feature() -> Option<&'static str>
+            {
+                [$(<$ty as $crate::gpu_array::GpuElement>::required_feature()),*].into_iter().flatten().next()
+            }
+
+            fn write_bytes(&self, bytes: &mut [u8])
+            {
+                let mut offset = 0;
+                $(
+                    offset = $crate::gpu_array::round_up(offset, <$ty as $crate::gpu_array::GpuElement>::ALIGN);
+                    $crate::gpu_array::GpuElement::write_bytes(&self.$field, &mut bytes[offset..]);
+                    offset += <$ty as $crate::gpu_array::GpuElement>::SIZE;
+                )*
+                let _ = offset;
+            }
+
+            fn read_bytes(bytes: &[u8]) -> Self
+            {
+                let mut offset = 0;
+                $(
+                    offset = $crate::gpu_array::round_up(offset, <$ty as $crate::gpu_array::GpuElement>::ALIGN);
+                    let $field = <$ty as $crate::gpu_array::GpuElement>::read_bytes(&bytes[offset..]);
+                    offset += <$ty as $crate::gpu_array::GpuElement>::SIZE;
+                )*
+                let _ = offset;
+                $name { $($field),* }
+            }
+        }
+    };
+}
+
+
+// the bytes of a storage buffer holding the values, padded to whole 4 byte words
+pub fn encode_elements<T: GpuElement>(values: &[T]) -> Vec<u8>
+{
+    let mut bytes = vec![0u8; round_up(values.len() * T::STRIDE, 4)];
+    for (i, value) in values.iter().enumerate()
+    {
+        value.write_bytes(&mut bytes[i * T::STRIDE..]);
+    }
+    bytes
+}
+
+
+pub fn decode_elements<T: GpuElement>(bytes: &[u8], len: usize) -> Vec<T>
+{
+    (0..len).map(|i| T::read_bytes(&bytes[i * T::STRIDE..])).collect()
+}
+
+
+// anything that can be bound as a storage buffer of a ComputeKernel
+pub trait StorageBinding<B: Backend>
+{
+    fn storage_buffer(&self) -> &B::Buffer;
+
+    fn byte_length(&self) -> u64;
+}
+
+
+pub struct GpuArray<B: Backend, T: GpuElement>
+{
+    backend: B,
+    label: String,
+    buffer: B::Buffer,
+    len: usize,
+    element: PhantomData<T>,
+}
+
+
+impl<B: Backend, T: GpuElement> GpuArray<B, T>
+{
+    // a zero initialized array of len elements
+    pub fn new(backend: &B, label: &str, len: usize) -> Result<Self, String>
+    {
+        if let Some(feature) = T::required_feature()
+        {
+            if !backend.has_feature(feature)
+            {
+                return Err(format!("{}: {} needs the \"{}\" device feature", label, T::wgsl_type(), feature));
+            }
+        }
+
+        // buffers are written in multiples of 4 bytes and zero sized bindings are not allowed
+        let size = round_up((len * T::STRIDE).max(1), 4);
+        let mut buffer_descriptor = BufferDescriptor::new(size as u64, STORAGE | COPY_DST | COPY_SRC);
+        buffer_descriptor.label(label);
+        let buffer = backend.create_buffer(&buffer_descriptor);
+
+        Ok(GpuArray { backend: backend.clone(), label: label.to_owned(), buffer, len, element: PhantomData })
+    }
+
+
+    pub fn from_slice(backend: &B, label: &str, values: &[T]) -> Result<Self, String>
+    {
+        let array = GpuArray::new(backend, label, values.len())?;
+        array.write(0, values)?;
+        Ok(array)
+    }
+
+
+    pub fn len(&self) -> usize
+    {
+        self.len
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.len == 0
+    }
+
+
+    pub fn buffer(&self) -> &B::Buffer
+    {
+        &self.buffer
+    }
+
+
+    // overwrites the elements starting at offset, the rest of the array is kept
+    pub fn write(&self, offset: usize, values: &[T]) -> Result<(), String>
+    {
+        if offset + values.len() > self.len
+        {
+            return Err(format!(
+                "{}: writing {} elements at {} overflows the array of {}", self.label, values.len(), offset, self.len,
+            ));
+        }
+
+        // writes have to be 4 byte aligned, a padded tail is only safe at the end of the array
+        // where it lands in the padding of the buffer
+        let byte_offset = offset * T::STRIDE;
+        let byte_length = values.len() * T::STRIDE;
+        let reaches_end = offset + values.len() == self.len;
+        if !byte_offset.is_multiple_of(4) || (!byte_length.is_multiple_of(4) && !reaches_end)
+        {
+            return Err(format!("{}: writes of {} have to cover whole 4 byte words", self.label, T::wgsl_type()));
+        }
+        if values.is_empty()
+        {
+            return Ok(());
+        }
+
+        self.backend.write_buffer(&self.buffer, byte_offset as u64, &encode_elements(values));
+        Ok(())
+    }
+
+
+    // shortens the array, the buffer keeps its size but only the first len elements are
+    // bound and read back
+    pub fn truncate(&mut self, len: usize)
+    {
+        self.len = self.len.min(len);
+    }
+
+
+    // copies the array into a mappable buffer after all submitted work and reads it back
+    pub async fn read(&self) -> Result<Vec<T>, String>
+    {
+        self.read_range(0, self.len).await
+    }
+
+
+    pub async fn read_range(&self, start: usize, len: usize) -> Result<Vec<T>, String>
+    {
+        if start + len > self.len
+        {
+            return Err(format!(
+                "{}: reading {} elements at {} overflows the array of {}", self.label, len, start, self.len,
+            ));
+        }
+        if len == 0
+        {
+            return Ok(Vec::new());
+        }
+
+        // copies have to be 4 byte aligned, so the range is widened to whole words
+        let byte_start = start * T::STRIDE;
+        let copy_start = byte_start / 4 * 4;
+        let copy_end = round_up((start + len) * T::STRIDE, 4);
+        let size = (copy_end - copy_start) as u64;
+
+        let mut result_buffer_descriptor = BufferDescriptor::new(size, MAP_READ | COPY_DST);
+        result_buffer_descriptor.label(&format!("{} result buffer", self.label));
+        let result_buffer = self.backend.create_buffer(&result_buffer_descriptor);
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label(&format!("{} read back encoder", self.label));
+        command_encoder.copy_buffer_to_buffer(&self.buffer, copy_start as u64, &result_buffer, 0, size);
+        self.backend.submit(vec![command_encoder.finish()]);
+
+        let bytes = self.backend.read_buffer(&result_buffer, 0, size).await;
+        self.backend.destroy_buffer(&result_buffer);
+
+        Ok(decode_elements(&bytes?[byte_start - copy_start..], len))
+    }
+}
+
+
+impl<B: Backend, T: GpuElement> StorageBinding<B> for GpuArray<B, T>
+{
+    fn storage_buffer(&self) -> &B::Buffer
+    {
+        &self.buffer
+    }
+
+    fn byte_length(&self) -> u64
+    {
+        round_up((self.len * T::STRIDE).max(1), 4) as u64
+    }
+}
+
+
+impl<B: Backend, T: GpuElement> Drop for GpuArray<B, T>
+{
+    fn drop(&mut self)
+    {
+        self.backend.destroy_buffer(&self.buffer);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::recording::{Call, RecordingBackend};
+
+    use software_gpu::{SoftwareBackend, block_on};
+    use software_gpu::interpreter::Interpreter;
+
+    use crate::compute::{BindingKind, ComputeKernel};
+
+    use super::*;
+
+
+    gpu_struct!
+    {
+        struct Particle
+        {
+            mass: f32,
+            position: [f32; 4],
+            velocity: [f32; 2],
+            steps: u32,
+        }
+    }
+
+
+    gpu_struct!
+    {
+        struct Emitter
+        {
+            particle: Particle,
+            rate: f32,
+        }
+    }
+
+
+    gpu_struct!
+    {
+        struct HalfVertex
+        {
+            weight: f16,
+            uv: [f16; 2],
+        }
+    }
+
+
+    fn particle(i: u32) -> Particle
+    {
+        Particle { mass: i as f32, position: [i as f32, 0.0, 0.0, 1.0], velocity: [1.0, 2.0], steps: i }
+    }
+
+
+    #[test]
+    fn struct_layouts_match_the_wgsl_layout()
+    {
+        // vec3s are left out, the vec4 after the leading f32 is where the 16 byte alignment shows
+        assert_eq!((Particle::SIZE, Particle::ALIGN, Particle::STRIDE), (48, 16, 48));
+        assert_eq!((Emitter::SIZE, Emitter::ALIGN), (64, 16));
+        assert_eq!((HalfVertex::SIZE, HalfVertex::ALIGN), (8, 4));
+        assert_eq!(HalfVertex::required_feature(), Some("shader-f16"));
+
+        let interpreter = Interpreter::new(&(Particle::wgsl_declaration() + &Emitter::wgsl_declaration())).unwrap();
+        assert_eq!(interpreter.type_size("Particle"), Some(Particle::SIZE));
+        assert_eq!(interpreter.type_size("Emitter"), Some(Emitter::SIZE));
+
+        let bytes = encode_elements(&[particle(3), particle(7)]);
+        assert_eq!(bytes.len(), 96);
+        assert_eq!(bytes[4..16], [0; 12]);
+        assert_eq!(bytes[64..68], 7f32.to_le_bytes());
+        assert_eq!(bytes[88..92], 7u32.to_le_bytes());
+        assert_eq!(decode_elements::<Particle>(&bytes, 2), [particle(3), particle(7)]);
+    }
+
+
+    #[test]
+    fn kernels_find_the_fields_where_the_struct_puts_them()
+    {
+        let backend = SoftwareBackend::new();
+        let source = Particle::wgsl_declaration() + "
+            @group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+
+            @compute @workgroup_size(workgroup_size)
+            fn step(@builtin(global_invocation_id) id: vec3<u32>)
+            {
+                let i = element_index(id);
+                if (i >= dispatch_info.element_count) { return; }
+                particles[i].position += vec4<f32>(particles[i].velocity * particles[i].mass, 0.0, 0.0);
+                particles[i].steps += 1u;
+            }
+        ";
+        let mut kernel = ComputeKernel::create(&backend, "step", &source, "step");
+        kernel.add_binding(BindingKind::InputOutput);
+
+        let particles = (0..100).map(particle).collect::<Vec<_>>();
+        let array = GpuArray::from_slice(&backend, "particles", &particles).unwrap();
+        kernel.dispatch_arrays(100, &[&array]).unwrap();
+
+        let stepped = particles.iter()
+            .map(|particle| Particle
+            {
+                position: [particle.position[0] + particle.mass, 2.0 * particle.mass, 0.0, 1.0],
+                steps: particle.steps + 1,
+                ..*particle
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(block_on(array.read()).unwrap(), stepped);
+        assert_eq!(block_on(array.read_range(97, 3)).unwrap(), stepped[97..]);
+    }
+
+
+    #[test]
+    fn elements_sharing_words_are_written_and_read_back()
+    {
+        let backend = RecordingBackend::new();
+        assert!(GpuArray::<_, f16>::new(&backend, "halves", 5).is_err());
+        backend.enable_feature("shader-f16");
+
+        let values = (0..5).map(|i| f16::from_f32(i as f32 * 0.5)).collect::<Vec<_>>();
+        let array = GpuArray::from_slice(&backend, "halves", &values).unwrap();
+        assert_eq!(array.byte_length(), 12);
+        assert_eq!(block_on(array.read()).unwrap(), values);
+        assert_eq!(block_on(array.read_range(1, 3)).unwrap(), values[1..4]);
+
+        // half a word can only be written into the padding at the end
+        assert!(array.write(1, &values[..1]).is_err());
+        assert!(array.write(3, &values[..2]).is_err());
+        array.write(4, &values[..1]).unwrap();
+        assert_eq!(block_on(array.read_range(3, 2)).unwrap(), [values[3], values[0]]);
+        assert!(block_on(array.read_range(4, 2)).is_err());
+
+        let buffer = *array.buffer();
+        drop(array);
+        let calls = backend.calls();
+        assert!(calls.iter().any(|call| matches!(call, Call::DestroyBuffer(destroyed) if *destroyed == buffer)));
+    }
+}
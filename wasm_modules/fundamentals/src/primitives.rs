@@ -0,0 +1,670 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Add;
+use std::rc::Rc;
+
+use gpu_backend::Backend;
+
+use crate::compute::{ComputeKernel, BindingKind};
+use crate::gpu_array::{GpuArray, GpuElement, GpuScalar};
+
+
+// numbers the reduction and scan kernels can combine
+pub trait GpuNumber: GpuScalar + PartialOrd + Add<Output = Self>
+{
+    const MIN: Self;
+    const MAX: Self;
+
+    // an exact WGSL expression for the value, going through the bits keeps
+    // extreme values like i32::MIN and f32::MAX representable
+    fn wgsl_literal(&self) -> String;
+}
+
+
+impl GpuNumber for u32
+{
+    const MIN: Self = u32::MIN;
+    const MAX: Self = u32::MAX;
+
+    fn wgsl_literal(&self) -> String
+    {
+        format!("{}u", self)
+    }
+}
+
+
+impl GpuNumber for i32
+{
+    const MIN: Self = i32::MIN;
+    const MAX: Self = i32::MAX;
+
+    fn wgsl_literal(&self) -> String
+    {
+        format!("bitcast<i32>({}u)", *self as u32)
+    }
+}
+
+
+impl GpuNumber for f32
+{
+    const MIN: Self = f32::MIN;
+    const MAX: Self = f32::MAX;
+
+    fn wgsl_literal(&self) -> String
+    {
+        format!("bitcast<f32>({}u)", self.to_bits())
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReduceOp
+{
+    Sum,
+    Min,
+    Max,
+}
+
+
+impl ReduceOp
+{
+    pub fn identity<T: GpuNumber>(&self) -> T
+    {
+        match self
+        {
+            ReduceOp::Sum => T::default(),
+            ReduceOp::Min => T::MAX,
+            ReduceOp::Max => T::MIN,
+        }
+    }
+
+
+    pub fn combine<T: GpuNumber>(&self, a: T, b: T) -> T
+    {
+        match self
+        {
+            ReduceOp::Sum => a + b,
+            ReduceOp::Min => if b < a { b } else { a },
+            ReduceOp::Max => if b > a { b } else { a },
+        }
+    }
+
+
+    fn wgsl_combine(&self) -> &'static str
+    {
+        match self
+        {
+            ReduceOp::Sum => "a + b",
+            ReduceOp::Min => "min(a, b)",
+            ReduceOp::Max => "max(a, b)",
+        }
+    }
+}
+
+
+pub struct KernelTemplate
+{
+    pub name: &'static str,
+    pub source: &'static str,
+    pub entry_point: &'static str,
+    pub bindings: &'static [BindingKind],
+}
+
+
+pub const REDUCE: KernelTemplate = KernelTemplate
+{
+    name: "reduce",
+    source: include_str!("../shader/primitives/reduce.wgsl"),
+    entry_point: "reduce_main",
+    bindings: &[BindingKind::Input, BindingKind::Output],
+};
+
+pub const SCAN_BLOCKS: KernelTemplate = KernelTemplate
+{
+    name: "scan blocks",
+    source: include_str!("../shader/primitives/scan_blocks.wgsl"),
+    entry_point: "scan_blocks_main",
+    bindings: &[BindingKind::Input, BindingKind::Output, BindingKind::Output, BindingKind::Input],
+};
+
+pub const ADD_OFFSETS: KernelTemplate = KernelTemplate
+{
+    name: "add offsets",
+    source: include_str!("../shader/primitives/add_offsets.wgsl"),
+    entry_point: "add_offsets_main",
+    bindings: &[BindingKind::InputOutput, BindingKind::Input],
+};
+
+pub const COMPACT_FLAGS: KernelTemplate = KernelTemplate
+{
+    name: "compact flags",
+    source: include_str!("../shader/primitives/compact_flags.wgsl"),
+    entry_point: "compact_flags_main",
+    bindings: &[BindingKind::Input, BindingKind::Output],
+};
+
+pub const COMPACT_SCATTER: KernelTemplate = KernelTemplate
+{
+    name: "compact scatter",
+    source: include_str!("../shader/primitives/compact_scatter.wgsl"),
+    entry_point: "compact_scatter_main",
+    bindings: &[BindingKind::Input, BindingKind::Input, BindingKind::Input, BindingKind::Output],
+};
+
+pub const RADIX_HISTOGRAM: KernelTemplate = KernelTemplate
+{
+    name: "radix histogram",
+    source: include_str!("../shader/primitives/radix_histogram.wgsl"),
+    entry_point: "radix_histogram_main",
+    bindings: &[BindingKind::Input, BindingKind::Output, BindingKind::Input],
+};
+
+pub const RADIX_SCATTER: KernelTemplate = KernelTemplate
+{
+    name: "radix scatter",
+    source: include_str!("../shader/primitives/radix_scatter.wgsl"),
+    entry_point: "radix_scatter_main",
+    bindings: &[
+        BindingKind::Input, BindingKind::Input, BindingKind::Input, BindingKind::Input,
+        BindingKind::Output, BindingKind::Output,
+    ],
+};
+
+
+impl KernelTemplate
+{
+    // fills in the {{placeholders}} of the template, the element type brings its own
+    // declarations and enable directives along
+    pub fn source<T: GpuElement>(&self, identity: &str, combine: &str) -> String
+    {
+        let enable = match T::required_feature()
+        {
+            Some("shader-f16") => "enable f16;\n",
+            _ => "",
+        };
+        let source = self.source
+            .replace("{{element_type}}", &T::wgsl_type())
+            .replace("{{identity}}", identity)
+            .replace("{{combine}}", combine);
+
+        format!("{}{}\n{}", enable, T::wgsl_declaration(), source)
+    }
+}
+
+
+// the keys and the values they were sorted with
+pub type SortedArrays<B> = (GpuArray<B, u32>, GpuArray<B, u32>);
+
+
+// radix sort passes sort by 4 bits of the keys at a time, the digit size of the kernels
+const RADIX_BITS: u32 = 4;
+const RADIX_SIZE: usize = 1 << RADIX_BITS;
+
+
+// the block sums and offsets of every level of a scan, allocated once for an input length
+// so that repeated scans, like the ones of the radix sort passes, don't allocate
+struct ScanScratch<B: Backend, T: GpuElement>
+{
+    // indexed by the inclusive flag
+    params: [GpuArray<B, u32>; 2],
+    block_sums: Vec<GpuArray<B, T>>,
+    block_offsets: Vec<GpuArray<B, T>>,
+}
+
+
+// reduction, prefix scan, stream compaction and radix sort over GpuArrays,
+// kernels are compiled on first use for every element type and cached
+pub struct Primitives<B: Backend>
+{
+    backend: B,
+    kernels: RefCell<HashMap<String, Rc<ComputeKernel<B>>>>,
+}
+
+
+impl<B: Backend> Primitives<B>
+{
+    pub fn new(backend: &B) -> Self
+    {
+        Primitives { backend: backend.clone(), kernels: RefCell::new(HashMap::new()) }
+    }
+
+
+    fn kernel<T: GpuElement>(&self, template: &KernelTemplate, identity: &str, combine: &str) -> Rc<ComputeKernel<B>>
+    {
+        let key = format!("{}<{}> {} {}", template.name, T::wgsl_type(), identity, combine);
+        if let Some(kernel) = self.kernels.borrow().get(&key)
+        {
+            return kernel.clone();
+        }
+
+        let mut kernel = ComputeKernel::create(
+            &self.backend, &key, &template.source::<T>(identity, combine), template.entry_point,
+        );
+        for kind in template.bindings
+        {
+            kernel.add_binding(*kind);
+        }
+
+        let kernel = Rc::new(kernel);
+        self.kernels.borrow_mut().insert(key, kernel.clone());
+        kernel
+    }
+
+
+    // a single element array holding the combination of all the elements
+    pub fn reduce<T: GpuNumber>(&self, input: &GpuArray<B, T>, op: ReduceOp) -> Result<GpuArray<B, T>, String>
+    {
+        let identity = op.identity::<T>();
+        if input.is_empty()
+        {
+            return GpuArray::from_slice(&self.backend, "reduce result", &[identity]);
+        }
+
+        let kernel = self.kernel::<T>(&REDUCE, &identity.wgsl_literal(), op.wgsl_combine());
+        let workgroup_size = kernel.workgroup_size() as usize;
+
+        let mut count = input.len();
+        let mut level: Option<GpuArray<B, T>> = None;
+        while level.is_none() || count > 1
+        {
+            let partial_count = count.div_ceil(workgroup_size);
+            let partials = GpuArray::new(&self.backend, "reduce partials", partial_count)?;
+            let source = level.as_ref().unwrap_or(input);
+            kernel.dispatch_arrays(count as u32, &[source, &partials])?;
+
+            level = Some(partials);
+            count = partial_count;
+        }
+
+        Ok(level.unwrap())
+    }
+
+
+    pub async fn reduce_value<T: GpuNumber>(&self, input: &GpuArray<B, T>, op: ReduceOp) -> Result<T, String>
+    {
+        Ok(self.reduce(input, op)?.read().await?[0])
+    }
+
+
+    // the running sums of the input, an inclusive scan counts every element in
+    // its own sum, an exclusive one starts at zero
+    pub fn scan<T: GpuNumber>(&self, input: &GpuArray<B, T>, inclusive: bool) -> Result<GpuArray<B, T>, String>
+    {
+        let output = GpuArray::new(&self.backend, "scan output", input.len())?;
+        let scratch = self.scan_scratch::<T>(input.len())?;
+        self.scan_into(input, &output, inclusive, &scratch, 0)?;
+        Ok(output)
+    }
+
+
+    fn scan_scratch<T: GpuNumber>(&self, len: usize) -> Result<ScanScratch<B, T>, String>
+    {
+        let zero = T::default().wgsl_literal();
+        let workgroup_size = self.kernel::<T>(&SCAN_BLOCKS, &zero, "").workgroup_size() as usize;
+
+        let mut scratch = ScanScratch
+        {
+            params: [
+                GpuArray::from_slice(&self.backend, "scan params", &[0])?,
+                GpuArray::from_slice(&self.backend, "scan params", &[1])?,
+            ],
+            block_sums: Vec::new(),
+            block_offsets: Vec::new(),
+        };
+
+        // every level scans the block totals of the level above it, until they fit in one block
+        let mut len = len;
+        while len > 0
+        {
+            let block_count = len.div_ceil(workgroup_size);
+            scratch.block_sums.push(GpuArray::new(&self.backend, "scan block sums", block_count)?);
+            if block_count == 1
+            {
+                break;
+            }
+            scratch.block_offsets.push(GpuArray::new(&self.backend, "scan block offsets", block_count)?);
+            len = block_count;
+        }
+
+        Ok(scratch)
+    }
+
+
+    fn scan_into<T: GpuNumber>(
+        &self,
+        input: &GpuArray<B, T>,
+        output: &GpuArray<B, T>,
+        inclusive: bool,
+        scratch: &ScanScratch<B, T>,
+        level: usize,
+    )
+        -> Result<(), String>
+    {
+        if input.is_empty()
+        {
+            return Ok(());
+        }
+
+        let zero = T::default().wgsl_literal();
+        let scan_blocks_kernel = self.kernel::<T>(&SCAN_BLOCKS, &zero, "");
+        let block_sums = &scratch.block_sums[level];
+        let params = &scratch.params[inclusive as usize];
+        scan_blocks_kernel.dispatch_arrays(input.len() as u32, &[input, output, block_sums, params])?;
+
+        // longer inputs scan the block totals recursively and add them back to every block
+        if let Some(block_offsets) = scratch.block_offsets.get(level)
+        {
+            self.scan_into(block_sums, block_offsets, false, scratch, level + 1)?;
+
+            let add_offsets_kernel = self.kernel::<T>(&ADD_OFFSETS, &zero, "");
+            add_offsets_kernel.dispatch_arrays(input.len() as u32, &[output, block_offsets])?;
+        }
+
+        Ok(())
+    }
+
+
+    // keeps the values whose flag is not zero, in their original order
+    pub async fn compact<T: GpuElement>(&self, values: &GpuArray<B, T>, flags: &GpuArray<B, u32>)
+        -> Result<GpuArray<B, T>, String>
+    {
+        if values.len() != flags.len()
+        {
+            return Err(format!("compact: {} values but {} flags", values.len(), flags.len()));
+        }
+
+        let count = values.len() as u32;
+        let mut output = GpuArray::new(&self.backend, "compact output", values.len())?;
+        if values.is_empty()
+        {
+            return Ok(output);
+        }
+
+        let keep = GpuArray::<B, u32>::new(&self.backend, "compact keep flags", values.len())?;
+        self.kernel::<u32>(&COMPACT_FLAGS, "", "").dispatch_arrays(count, &[flags, &keep])?;
+
+        let positions = self.scan(&keep, false)?;
+        self.kernel::<T>(&COMPACT_SCATTER, "", "").dispatch_arrays(count, &[values, &keep, &positions, &output])?;
+
+        let last = values.len() - 1;
+        let kept = positions.read_range(last, 1).await?[0] + keep.read_range(last, 1).await?[0];
+        output.truncate(kept as usize);
+        Ok(output)
+    }
+
+
+    // a stable least significant digit first radix sort, 4 bits per pass. every pass counts the digits of
+    // every block into a histogram, scans the histograms into the offset of every digit of every block
+    // and scatters the keys to them. the arrays are allocated once and reused by all 8 passes
+    pub fn radix_sort(&self, keys: &GpuArray<B, u32>, values: &GpuArray<B, u32>)
+        -> Result<SortedArrays<B>, String>
+    {
+        if keys.len() != values.len()
+        {
+            return Err(format!("radix sort: {} keys but {} values", keys.len(), values.len()));
+        }
+
+        let count = keys.len() as u32;
+        let new_array = |label: &str| GpuArray::<B, u32>::new(&self.backend, label, keys.len());
+        let targets = [
+            (new_array("radix sort ping keys")?, new_array("radix sort ping values")?),
+            (new_array("radix sort pong keys")?, new_array("radix sort pong values")?),
+        ];
+
+        let radix_histogram_kernel = self.kernel::<u32>(&RADIX_HISTOGRAM, "", "");
+        let radix_scatter_kernel = self.kernel::<u32>(&RADIX_SCATTER, "", "");
+
+        let block_count = keys.len().div_ceil(radix_histogram_kernel.workgroup_size() as usize);
+        let histograms = GpuArray::<B, u32>::new(&self.backend, "radix sort histograms", RADIX_SIZE * block_count)?;
+        let digit_offsets = GpuArray::<B, u32>::new(&self.backend, "radix sort digit offsets", histograms.len())?;
+        let scan_scratch = self.scan_scratch::<u32>(histograms.len())?;
+        let params = GpuArray::<B, u32>::new(&self.backend, "radix sort params", 1)?;
+
+        let passes = (u32::BITS / RADIX_BITS) as usize;
+        for pass in 0..passes
+        {
+            let (keys_in, values_in) = match pass
+            {
+                0 => (keys, values),
+                _ => (&targets[(pass + 1) % 2].0, &targets[(pass + 1) % 2].1),
+            };
+            let (keys_out, values_out) = &targets[pass % 2];
+
+            params.write(0, &[pass as u32 * RADIX_BITS])?;
+            radix_histogram_kernel.dispatch_arrays(count, &[keys_in, &histograms, &params])?;
+            self.scan_into(&histograms, &digit_offsets, false, &scan_scratch, 0)?;
+            radix_scatter_kernel.dispatch_arrays(
+                count, &[keys_in, values_in, &digit_offsets, &params, keys_out, values_out],
+            )?;
+        }
+
+        // an even number of passes leaves the result in the pong arrays
+        let [_, sorted] = targets;
+        Ok(sorted)
+    }
+}
+
+
+// cpu reference implementations, the gpu versions are checked against these
+
+pub fn reduce_cpu<T: GpuNumber>(values: &[T], op: ReduceOp) -> T
+{
+    values.iter().fold(op.identity(), |acc, value| op.combine(acc, *value))
+}
+
+
+pub fn scan_cpu<T: GpuNumber>(values: &[T], inclusive: bool) -> Vec<T>
+{
+    let mut sum = T::default();
+    values.iter()
+        .map(|value|
+        {
+            let exclusive = sum;
+            sum = sum + *value;
+            if inclusive { sum } else { exclusive }
+        })
+        .collect()
+}
+
+
+pub fn compact_cpu<T: Copy>(values: &[T], flags: &[u32]) -> Vec<T>
+{
+    values.iter().zip(flags).filter(|(_, flag)| **flag != 0).map(|(value, _)| *value).collect()
+}
+
+
+pub fn radix_sort_cpu(keys: &[u32], values: &[u32]) -> (Vec<u32>, Vec<u32>)
+{
+    let mut pairs = keys.iter().copied().zip(values.iter().copied()).collect::<Vec<(u32, u32)>>();
+    pairs.sort_by_key(|(key, _)| *key);
+    pairs.into_iter().unzip()
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::Limits;
+
+    use software_gpu::{SoftwareBackend, block_on};
+    use software_gpu::interpreter::Interpreter;
+
+    use super::*;
+    use crate::compute::module_source;
+
+
+    // parsing validates the module
+    fn validate(source: &str)
+    {
+        Interpreter::new(source).unwrap_or_else(|error| panic!("{}\n{}", error, source));
+    }
+
+
+    fn validate_numeric<T: GpuNumber>()
+    {
+        for op in [ReduceOp::Sum, ReduceOp::Min, ReduceOp::Max]
+        {
+            let identity = op.identity::<T>().wgsl_literal();
+            validate(&module_source(&REDUCE.source::<T>(&identity, op.wgsl_combine()), 64));
+        }
+
+        let zero = T::default().wgsl_literal();
+        validate(&module_source(&SCAN_BLOCKS.source::<T>(&zero, ""), 64));
+        validate(&module_source(&ADD_OFFSETS.source::<T>(&zero, ""), 64));
+        validate(&module_source(&COMPACT_SCATTER.source::<T>("", ""), 64));
+    }
+
+
+    #[test]
+    fn kernels_are_valid_wgsl()
+    {
+        validate_numeric::<u32>();
+        validate_numeric::<i32>();
+        validate_numeric::<f32>();
+
+        validate(&module_source(&COMPACT_SCATTER.source::<[f32; 4]>("", ""), 64));
+        validate(&module_source(&COMPACT_FLAGS.source::<u32>("", ""), 64));
+        validate(&module_source(&RADIX_HISTOGRAM.source::<u32>("", ""), 64));
+        validate(&module_source(&RADIX_SCATTER.source::<u32>("", ""), 64));
+    }
+
+
+    #[test]
+    fn reduce_cpu_combines_every_element()
+    {
+        let values = [3, -7, 12, 0, 5];
+        assert_eq!(reduce_cpu(&values, ReduceOp::Sum), 13);
+        assert_eq!(reduce_cpu(&values, ReduceOp::Min), -7);
+        assert_eq!(reduce_cpu(&values, ReduceOp::Max), 12);
+        assert_eq!(reduce_cpu::<u32>(&[], ReduceOp::Min), u32::MAX);
+    }
+
+
+    #[test]
+    fn scan_cpu_is_inclusive_or_exclusive()
+    {
+        let values = [1.0f32, 2.0, 3.0, 4.0];
+        assert_eq!(scan_cpu(&values, true), [1.0, 3.0, 6.0, 10.0]);
+        assert_eq!(scan_cpu(&values, false), [0.0, 1.0, 3.0, 6.0]);
+    }
+
+
+    #[test]
+    fn compact_cpu_keeps_flagged_values_in_order()
+    {
+        assert_eq!(compact_cpu(&[10u32, 11, 12, 13, 14], &[1, 0, 7, 0, 1]), [10, 12, 14]);
+    }
+
+
+    #[test]
+    fn radix_sort_cpu_is_stable()
+    {
+        let (keys, values) = radix_sort_cpu(&[5, 1, 5, 0, 1], &[0, 1, 2, 3, 4]);
+        assert_eq!(keys, [0, 1, 1, 5, 5]);
+        assert_eq!(values, [3, 1, 4, 0, 2]);
+    }
+
+
+    // a workgroup size of 8 and at most 4 workgroups a dimension make every multi level and multi pass path run
+    fn primitives() -> (SoftwareBackend, Primitives<SoftwareBackend>)
+    {
+        let backend = SoftwareBackend::new();
+        backend.set_limits(Limits
+        {
+            max_compute_workgroups_per_dimension: 4,
+            max_compute_invocations_per_workgroup: 8,
+            max_compute_workgroup_size_x: 8,
+            ..Limits::default()
+        });
+        let primitives = Primitives::new(&backend);
+        (backend, primitives)
+    }
+
+
+    #[test]
+    fn reduce_matches_cpu()
+    {
+        let (backend, primitives) = primitives();
+        let values = (0..300).map(|i| (i * 37 % 101) - 50).collect::<Vec<i32>>();
+        let array = GpuArray::from_slice(&backend, "values", &values).unwrap();
+        for op in [ReduceOp::Sum, ReduceOp::Min, ReduceOp::Max]
+        {
+            assert_eq!(block_on(primitives.reduce_value(&array, op)).unwrap(), reduce_cpu(&values, op));
+        }
+
+        let values = (0..77).map(|i| i as f32 * 0.5).collect::<Vec<f32>>();
+        let array = GpuArray::from_slice(&backend, "values", &values).unwrap();
+        let sum = block_on(primitives.reduce_value(&array, ReduceOp::Sum)).unwrap();
+        assert_eq!(sum, reduce_cpu(&values, ReduceOp::Sum));
+
+        let empty = GpuArray::<_, u32>::new(&backend, "empty", 0).unwrap();
+        assert_eq!(block_on(primitives.reduce_value(&empty, ReduceOp::Min)).unwrap(), u32::MAX);
+    }
+
+
+    #[test]
+    fn reduce_handles_workgroup_sizes_that_are_not_a_power_of_two()
+    {
+        let backend = SoftwareBackend::new();
+        backend.set_limits(Limits { max_compute_invocations_per_workgroup: 6, ..Limits::default() });
+        let primitives = Primitives::new(&backend);
+        let values = (0..50).map(|i| (i * 13 % 29) - 14).collect::<Vec<i32>>();
+        let array = GpuArray::from_slice(&backend, "values", &values).unwrap();
+        for op in [ReduceOp::Sum, ReduceOp::Min, ReduceOp::Max]
+        {
+            assert_eq!(block_on(primitives.reduce_value(&array, op)).unwrap(), reduce_cpu(&values, op));
+        }
+    }
+
+
+    #[test]
+    fn scan_matches_cpu()
+    {
+        let (backend, primitives) = primitives();
+        let values = (0..150).map(|i| i * 3 % 11).collect::<Vec<u32>>();
+        let array = GpuArray::from_slice(&backend, "values", &values).unwrap();
+        for inclusive in [true, false]
+        {
+            let scanned = primitives.scan(&array, inclusive).unwrap();
+            assert_eq!(block_on(scanned.read()).unwrap(), scan_cpu(&values, inclusive));
+        }
+    }
+
+
+    #[test]
+    fn compact_matches_cpu()
+    {
+        let (backend, primitives) = primitives();
+        let values = (0..90).map(|i| [i as f32, 0.0, 1.0, -(i as f32)]).collect::<Vec<[f32; 4]>>();
+        let flags = (0..90).map(|i| (i % 3 == 0 || i % 7 == 0) as u32 * i).collect::<Vec<u32>>();
+        let values_array = GpuArray::from_slice(&backend, "values", &values).unwrap();
+        let flags_array = GpuArray::from_slice(&backend, "flags", &flags).unwrap();
+
+        let compacted = block_on(primitives.compact(&values_array, &flags_array)).unwrap();
+        assert_eq!(block_on(compacted.read()).unwrap(), compact_cpu(&values, &flags));
+
+        let too_few_flags = GpuArray::from_slice(&backend, "flags", &flags[1..]).unwrap();
+        assert!(block_on(primitives.compact(&values_array, &too_few_flags)).is_err());
+    }
+
+
+    #[test]
+    fn radix_sort_matches_cpu()
+    {
+        let (backend, primitives) = primitives();
+        // spread out keys, and keys that only differ in a few digits so every pass has to keep the order
+        let spread = (0..40u32).map(|i| i.wrapping_mul(2654435761) >> (i % 20)).collect::<Vec<u32>>();
+        let clustered = (0..70u32).map(|i| (i * 7 % 5) << 28 | (i % 3)).collect::<Vec<u32>>();
+        for keys in [spread, clustered, Vec::new()]
+        {
+            let values = (0..keys.len() as u32).collect::<Vec<u32>>();
+            let keys_array = GpuArray::from_slice(&backend, "keys", &keys).unwrap();
+            let values_array = GpuArray::from_slice(&backend, "values", &values).unwrap();
+
+            let (sorted_keys, sorted_values) = primitives.radix_sort(&keys_array, &values_array).unwrap();
+            let (expected_keys, expected_values) = radix_sort_cpu(&keys, &values);
+            assert_eq!(block_on(sorted_keys.read()).unwrap(), expected_keys);
+            assert_eq!(block_on(sorted_values.read()).unwrap(), expected_values);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use gpu_backend::
+{
+    Backend, ShaderModuleDescriptor, VertexState, ColorTargetState, FragmentState, RenderPipelineDescriptor,
+    RenderPassDescriptor, RenderPassColorAttachment, LoadOp, StoreOp, CommandEncoder, TextureFormat,
+};
+
+use frame_loop::{FrameBinding, FrameUniforms, with_frame_prelude};
+
+use crate::compute::{ComputeKernel, BindingKind};
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    render_pipeline: B::RenderPipeline,
+    frame_binding: FrameBinding<B>,
+    doubling_kernel: ComputeKernel<B>,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    pub fn new(backend: B, texture_format: TextureFormat) -> Self
+    {
+        let render_source = with_frame_prelude(include_str!("../shader/render.wgsl"));
+        let mut render_shader_module_descriptor = ShaderModuleDescriptor::new(&render_source);
+        render_shader_module_descriptor.label("Our hardcoded red triangle shaders");
+        let render_shader_module = backend.create_shader_module(&render_shader_module_descriptor);
+
+        let vertex_state = VertexState::new("vertex_main", &render_shader_module);
+
+        let fragment_state = FragmentState::new(
+            "fragment_main", &render_shader_module, &[ColorTargetState::new(texture_format)],
+        );
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("Our hardcoded red triangle pipeline")
+            .fragment(fragment_state);
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+        let frame_binding = FrameBinding::new(&backend, &render_pipeline, &[&render_source]);
+
+        let mut doubling_kernel = ComputeKernel::create(
+            &backend, "Doubling", include_str!("../shader/compute.wgsl"), "compute_main",
+        );
+        doubling_kernel.add_binding(BindingKind::InputOutput);
+
+        Renderer { backend, render_pipeline, frame_binding, doubling_kernel }
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    pub fn render_frame(&self, target: &B::Texture, frame_uniforms: &FrameUniforms)
+    {
+        self.frame_binding.write(&self.backend, frame_uniforms);
+        self.render(target);
+    }
+
+
+    pub fn render(&self, target: &B::Texture)
+    {
+        let mut color_attachment = RenderPassColorAttachment::new(
+            LoadOp::Clear, StoreOp::Store, &self.backend.create_view(target),
+        );
+        color_attachment.clear_value([1.0, 1.0, 0.0, 0.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor.label("Our basic canvas render pass");
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("Our command encoder");
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+        self.frame_binding.set_bind_group(&mut render_pass_encoder);
+        render_pass_encoder.draw(3, 1);
+        render_pass_encoder.end();
+
+        self.backend.submit(vec![command_encoder.finish()]);
+    }
+
+
+    // doubles every value on the gpu
+    pub async fn compute(&self, input: &[f32]) -> Result<Vec<f32>, String>
+    {
+        let mut outputs = self.doubling_kernel.dispatch(input.len() as u32, &[input]).await?;
+        Ok(outputs.remove(0))
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{Command, TextureDescriptor};
+    use gpu_backend::recording::RecordingBackend;
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use software_gpu::{SoftwareBackend, assert_golden, block_on};
+
+    use super::*;
+
+
+    #[test]
+    fn the_triangle_is_drawn_over_the_clear_color()
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let renderer = Renderer::new(backend.clone(), TextureFormat::Bgra8Unorm);
+        renderer.render(&canvas_texture);
+
+        let commands = backend.commands();
+        assert!(matches!(&commands[0], Command::BeginRenderPass(descriptor)
+            if descriptor.color_attachments[0].clear_value == [1.0, 1.0, 0.0, 0.0]));
+        assert!(matches!(commands[1], Command::SetPipeline(pipeline) if pipeline == renderer.render_pipeline));
+        assert!(matches!(commands.last(), Some(Command::EndRenderPass)));
+        assert!(commands.iter().any(|command| matches!(command, Command::Draw { vertex_count: 3, .. })));
+    }
+
+
+    #[test]
+    fn the_golden_image_renders_through_the_renderer()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        let renderer = Renderer::new(backend.clone(), TextureFormat::Rgba8Unorm);
+        renderer.render_frame(&target, &FrameUniforms { resolution: [96.0, 96.0], ..Default::default() });
+
+        let image = backend.texture_image(&target).unwrap();
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "fundamentals", &image);
+    }
+
+
+    #[test]
+    fn the_doubling_kernel_runs_on_the_device()
+    {
+        let renderer = Renderer::new(SoftwareBackend::new(), TextureFormat::Rgba8Unorm);
+        let values = (0..1000).map(|i| i as f32).collect::<Vec<f32>>();
+        let doubled = block_on(renderer.compute(&values)).unwrap();
+        assert_eq!(doubled, values.iter().map(|value| value * 2.0).collect::<Vec<f32>>());
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use gpu_backend::native::WgpuBackend;
+use gpu_backend::trace::Trace;
+
+
+// cargo run --example replay --features wgpu -- <trace.json or binary trace> [output.png]
+fn main() -> Result<(), String>
+{
+    let mut args = std::env::args().skip(1);
+    let trace_path = args.next().ok_or("usage: replay <trace> [output.png]")?;
+    let output_path = args.next().unwrap_or("replay.png".to_string());
+
+    let bytes = std::fs::read(&trace_path).map_err(|error| format!("{}: {}", trace_path, error))?;
+    let trace = if bytes.starts_with(b"{")
+    {
+        Trace::from_json(&String::from_utf8_lossy(&bytes))?
+    }
+    else
+    {
+        Trace::from_binary(&bytes)?
+    };
+
+    let backend = WgpuBackend::new()?;
+    let replay = trace.replay(&backend)?;
+    let surface = trace.surface.and_then(|surface| replay.texture(&surface)).ok_or("the trace has no surface texture")?;
+    backend.save_png(&surface, Path::new(&output_path))?;
+    println!("saved {}", output_path);
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use web_sys::
+{
+    GpuDevice, GpuCanvasContext, GpuCanvasConfiguration, GpuCanvasAlphaMode, GpuTextureFormat, GpuBlendFactor,
+    GpuBlendOperation,
+};
+
+use crate::
+{
+    Backend, BlendFactor, BlendOperation, BlendComponent, BlendState, ColorTargetState, TextureFormat,
+    RenderPassEncoder,
+};
+
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode
+{
+    Opaque,
+    Alpha,
+    PremultipliedAlpha,
+    Additive,
+    Multiply,
+    Screen,
+    Min,
+    Max,
+    Custom,
+}
+
+
+fn add(src_factor: BlendFactor, dst_factor: BlendFactor) -> BlendComponent
+{
+    BlendComponent::new(src_factor, dst_factor, BlendOperation::Add)
+}
+
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct BlendSettings
+{
+    mode: BlendMode,
+    color: BlendComponent,
+    alpha: BlendComponent,
+    constant: [f64; 4],
+}
+
+
+#[wasm_bindgen]
+impl BlendSettings
+{
+    #[wasm_bindgen(constructor)]
+    pub fn new(mode: BlendMode) -> Self
+    {
+        use BlendFactor::*;
+
+        let (color, alpha) = match mode
+        {
+            BlendMode::Opaque | BlendMode::Custom => (add(One, Zero), add(One, Zero)),
+            BlendMode::Alpha => (
+                add(SrcAlpha, OneMinusSrcAlpha), add(One, OneMinusSrcAlpha),
+            ),
+            BlendMode::PremultipliedAlpha => (
+                add(One, OneMinusSrcAlpha), add(One, OneMinusSrcAlpha),
+            ),
+            BlendMode::Additive => (add(SrcAlpha, One), add(One, One)),
+            BlendMode::Multiply => (add(Dst, Zero), add(Zero, One)),
+            BlendMode::Screen => (
+                add(One, OneMinusSrc), add(One, OneMinusSrcAlpha),
+            ),
+            // min and max ignore the blend factors but WebGPU requires them to be "one"
+            BlendMode::Min => (
+                BlendComponent::new(One, One, BlendOperation::Min),
+                BlendComponent::new(One, One, BlendOperation::Min),
+            ),
+            BlendMode::Max => (
+                BlendComponent::new(One, One, BlendOperation::Max),
+                BlendComponent::new(One, One, BlendOperation::Max),
+            ),
+        };
+
+        BlendSettings { mode, color, alpha, constant: [0.0, 0.0, 0.0, 0.0] }
+    }
+
+
+    pub fn set_color_component(
+        &mut self, src_factor: GpuBlendFactor, dst_factor: GpuBlendFactor, operation: GpuBlendOperation,
+    )
+        -> Result<(), String>
+    {
+        self.mode = BlendMode::Custom;
+        self.color = BlendComponent::new(src_factor.try_into()?, dst_factor.try_into()?, operation.try_into()?);
+        Ok(())
+    }
+
+
+    pub fn set_alpha_component(
+        &mut self, src_factor: GpuBlendFactor, dst_factor: GpuBlendFactor, operation: GpuBlendOperation,
+    )
+        -> Result<(), String>
+    {
+        self.mode = BlendMode::Custom;
+        self.alpha = BlendComponent::new(src_factor.try_into()?, dst_factor.try_into()?, operation.try_into()?);
+        Ok(())
+    }
+
+
+    // used by the "constant" and "one-minus-constant" blend factors
+    pub fn set_constant(&mut self, r: f64, g: f64, b: f64, a: f64)
+    {
+        self.constant = [r, g, b, a];
+    }
+
+
+    pub fn mode(&self) -> BlendMode
+    {
+        self.mode
+    }
+}
+
+
+impl BlendSettings
+{
+    pub fn color_target_state(&self, texture_format: TextureFormat) -> ColorTargetState
+    {
+        let mut color_target_state = ColorTargetState::new(texture_format);
+        if self.mode != BlendMode::Opaque
+        {
+            color_target_state.blend(BlendState::new(self.color, self.alpha));
+        }
+        color_target_state
+    }
+
+
+    pub fn set_blend_constant<B: Backend>(&self, render_pass_encoder: &mut RenderPassEncoder<'_, B>)
+    {
+        render_pass_encoder.set_blend_constant(self.constant);
+    }
+}
+
+
+// every blended mode can leave translucent pixels in the canvas texture, which the page
+// compositor only honours with premultiplied alpha, opaque rendering ignores canvas alpha
+pub fn canvas_alpha_mode(blend_settings: &[BlendSettings]) -> GpuCanvasAlphaMode
+{
+    if blend_settings.iter().all(|settings| settings.mode == BlendMode::Opaque)
+    {
+        GpuCanvasAlphaMode::Opaque
+    }
+    else
+    {
+        GpuCanvasAlphaMode::Premultiplied
+    }
+}
+
+
+pub fn configure_context(
+    gpu_device: &GpuDevice, context: &GpuCanvasContext, gpu_texture_format: GpuTextureFormat,
+    alpha_mode: GpuCanvasAlphaMode,
+)
+{
+    let mut canvas_configuration = GpuCanvasConfiguration::new(gpu_device, gpu_texture_format);
+    canvas_configuration.alpha_mode(alpha_mode);
+    context.configure(&canvas_configuration);
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn only_opaque_rendering_leaves_out_the_blend_state()
+    {
+        let opaque = BlendSettings::new(BlendMode::Opaque);
+        let alpha = BlendSettings::new(BlendMode::Alpha);
+        assert_eq!(opaque.color_target_state(TextureFormat::Bgra8Unorm).blend, None);
+        assert_eq!(
+            alpha.color_target_state(TextureFormat::Bgra8Unorm).blend,
+            Some(BlendState::new(
+                BlendComponent::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha, BlendOperation::Add),
+                BlendComponent::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha, BlendOperation::Add),
+            )),
+        );
+
+        assert_eq!(canvas_alpha_mode(&[opaque, opaque]), GpuCanvasAlphaMode::Opaque);
+        assert_eq!(canvas_alpha_mode(&[opaque, alpha]), GpuCanvasAlphaMode::Premultiplied);
+    }
+
+
+    #[test]
+    fn custom_components_switch_to_the_custom_mode()
+    {
+        let mut settings = BlendSettings::new(BlendMode::Additive);
+        settings.set_color_component(GpuBlendFactor::Constant, GpuBlendFactor::Zero, GpuBlendOperation::Subtract)
+            .unwrap();
+        assert_eq!(settings.mode(), BlendMode::Custom);
+        assert_eq!(
+            settings.color_target_state(TextureFormat::Bgra8Unorm).blend.unwrap().color,
+            BlendComponent::new(BlendFactor::Constant, BlendFactor::Zero, BlendOperation::Subtract),
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use crate::*;
+use crate::recording::{Call, Handle, RecordingBackend};
+use crate::trace::Trace;
+
+
+// an object of the wrapped backend along with its handle in traces
+#[derive(Clone, Debug)]
+pub struct Captured<T>
+{
+    pub handle: Handle,
+    pub inner: T,
+}
+
+
+#[derive(Debug, Default)]
+struct CaptureState
+{
+    capturing: bool,
+    // every view of a texture is the same default view, so a texture keeps one view handle
+    views: HashMap<Handle, Handle>,
+    surface: Option<(TextureDescriptor, Handle)>,
+}
+
+
+// passes everything on to another backend while keeping what a trace of the next frame would need: every
+// object that was created, and the contents of every buffer. uploads and submissions are only kept while
+// a frame is captured, so scenes can run with it all the time. the capture_frame of every scene renders
+// a frame between start_capture and finish_capture and returns the json of the trace, to attach to bug reports
+#[derive(Clone, Debug)]
+pub struct CaptureBackend<B: Backend>
+{
+    inner: B,
+    recording: RecordingBackend,
+    state: Rc<RefCell<CaptureState>>,
+}
+
+
+struct Inner;
+
+
+impl<B: Backend> HandleMap<CaptureBackend<B>, B> for Inner
+{
+    type Error = Infallible;
+
+    fn buffer(&self, buffer: &Captured<B::Buffer>) -> Result<B::Buffer, Infallible> { Ok(buffer.inner.clone()) }
+    fn texture(&self, texture: &Captured<B::Texture>) -> Result<B::Texture, Infallible> { Ok(texture.inner.clone()) }
+    fn texture_view(&self, view: &Captured<B::TextureView>) -> Result<B::TextureView, Infallible> { Ok(view.inner.clone()) }
+    fn sampler(&self, sampler: &Captured<B::Sampler>) -> Result<B::Sampler, Infallible> { Ok(sampler.inner.clone()) }
+
+    fn shader_module(&self, module: &Captured<B::ShaderModule>) -> Result<B::ShaderModule, Infallible>
+    {
+        Ok(module.inner.clone())
+    }
+
+    fn render_pipeline(&self, pipeline: &Captured<B::RenderPipeline>) -> Result<B::RenderPipeline, Infallible>
+    {
+        Ok(pipeline.inner.clone())
+    }
+
+    fn compute_pipeline(&self, pipeline: &Captured<B::ComputePipeline>) -> Result<B::ComputePipeline, Infallible>
+    {
+        Ok(pipeline.inner.clone())
+    }
+
+    fn bind_group_layout(&self, layout: &Captured<B::BindGroupLayout>) -> Result<B::BindGroupLayout, Infallible>
+    {
+        Ok(layout.inner.clone())
+    }
+
+    fn bind_group(&self, bind_group: &Captured<B::BindGroup>) -> Result<B::BindGroup, Infallible>
+    {
+        Ok(bind_group.inner.clone())
+    }
+}
+
+
+struct Handles;
+
+
+impl<B: Backend> HandleMap<CaptureBackend<B>, RecordingBackend> for Handles
+{
+    type Error = Infallible;
+
+    fn buffer(&self, buffer: &Captured<B::Buffer>) -> Result<Handle, Infallible> { Ok(buffer.handle) }
+    fn texture(&self, texture: &Captured<B::Texture>) -> Result<Handle, Infallible> { Ok(texture.handle) }
+    fn texture_view(&self, view: &Captured<B::TextureView>) -> Result<Handle, Infallible> { Ok(view.handle) }
+    fn sampler(&self, sampler: &Captured<B::Sampler>) -> Result<Handle, Infallible> { Ok(sampler.handle) }
+    fn shader_module(&self, module: &Captured<B::ShaderModule>) -> Result<Handle, Infallible> { Ok(module.handle) }
+    fn render_pipeline(&self, pipeline: &Captured<B::RenderPipeline>) -> Result<Handle, Infallible> { Ok(pipeline.handle) }
+    fn compute_pipeline(&self, pipeline: &Captured<B::ComputePipeline>) -> Result<Handle, Infallible> { Ok(pipeline.handle) }
+    fn bind_group_layout(&self, layout: &Captured<B::BindGroupLayout>) -> Result<Handle, Infallible> { Ok(layout.handle) }
+    fn bind_group(&self, bind_group: &Captured<B::BindGroup>) -> Result<Handle, Infallible> { Ok(bind_group.handle) }
+}
+
+
+impl<B: Backend> CaptureBackend<B>
+{
+    pub fn new(inner: B) -> Self
+    {
+        CaptureBackend { inner, recording: RecordingBackend::new(), state: Rc::new(RefCell::new(CaptureState::default())) }
+    }
+
+
+    pub fn inner(&self) -> &B
+    {
+        &self.inner
+    }
+
+
+    // wraps a texture the scene didn't create, like the current texture of a canvas. it keeps its handle
+    // from frame to frame as long as its size and format stay the same
+    pub fn surface_texture(&self, texture: B::Texture, format: TextureFormat) -> Captured<B::Texture>
+    {
+        let mut descriptor = TextureDescriptor::new(
+            format, self.inner.texture_size(&texture), texture_usage::RENDER_ATTACHMENT | texture_usage::COPY_SRC,
+        );
+        descriptor.label("surface");
+
+        let mut state = self.state.borrow_mut();
+        let handle = match &state.surface
+        {
+            Some((surface_descriptor, handle)) if *surface_descriptor == descriptor => *handle,
+            _ =>
+            {
+                let handle = self.recording.create_texture(&descriptor);
+                state.surface = Some((descriptor, handle));
+                handle
+            },
+        };
+        Captured { handle, inner: texture }
+    }
+
+
+    // the trace starts with the contents every buffer has at this point
+    pub fn start_capture(&self)
+    {
+        self.forget_uploads();
+        let calls = self.recording.calls();
+        let destroyed = calls.iter()
+            .filter_map(|call| match call
+            {
+                Call::DestroyBuffer(buffer) => Some(*buffer),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let buffers = calls.iter()
+            .filter_map(|call| match call
+            {
+                Call::CreateBuffer { buffer, .. } if !destroyed.contains(buffer) => Some(*buffer),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        drop(calls);
+
+        for buffer in buffers
+        {
+            let contents = self.recording.buffer_contents(&buffer).unwrap_or_default();
+            if contents.iter().any(|byte| *byte != 0)
+            {
+                self.recording.write_buffer(&buffer, 0, &contents);
+            }
+        }
+        self.state.borrow_mut().capturing = true;
+    }
+
+
+    pub fn finish_capture(&self) -> Trace
+    {
+        self.state.borrow_mut().capturing = false;
+        let surface = self.state.borrow().surface.as_ref().map(|(_, handle)| *handle);
+        let trace = Trace::new(self.recording.calls().to_vec(), surface);
+        self.forget_uploads();
+        trace
+    }
+
+
+    // their effect is already in the buffer contents of the recording
+    fn forget_uploads(&self)
+    {
+        self.recording.retain_calls(|call| !matches!(call, Call::WriteBuffer { .. } | Call::Submit(_)));
+    }
+}
+
+
+impl<B: Backend> Backend for CaptureBackend<B>
+{
+    type Buffer = Captured<B::Buffer>;
+    type Texture = Captured<B::Texture>;
+    type TextureView = Captured<B::TextureView>;
+    type Sampler = Captured<B::Sampler>;
+    type ShaderModule = Captured<B::ShaderModule>;
+    type RenderPipeline = Captured<B::RenderPipeline>;
+    type ComputePipeline = Captured<B::ComputePipeline>;
+    type BindGroupLayout = Captured<B::BindGroupLayout>;
+    type BindGroup = Captured<B::BindGroup>;
+
+
+    fn max_texture_dimension_2d(&self) -> u32
+    {
+        self.inner.max_texture_dimension_2d()
+    }
+
+
+    fn limits(&self) -> Limits
+    {
+        self.inner.limits()
+    }
+
+
+    fn has_feature(&self, feature: &str) -> bool
+    {
+        self.inner.has_feature(feature)
+    }
+
+
+    fn create_buffer(&self, descriptor: &BufferDescriptor) -> Self::Buffer
+    {
+        Captured { handle: self.recording.create_buffer(descriptor), inner: self.inner.create_buffer(descriptor) }
+    }
+
+
+    fn write_buffer(&self, buffer: &Self::Buffer, offset: u64, data: &[u8])
+    {
+        self.recording.write_buffer(&buffer.handle, offset, data);
+        self.inner.write_buffer(&buffer.inner, offset, data);
+    }
+
+
+    fn destroy_buffer(&self, buffer: &Self::Buffer)
+    {
+        self.recording.destroy_buffer(&buffer.handle);
+        self.inner.destroy_buffer(&buffer.inner);
+    }
+
+
+    // reads don't change anything a trace would replay
+    fn read_buffer(&self, buffer: &Self::Buffer, offset: u64, size: u64) -> BufferRead
+    {
+        self.inner.read_buffer(&buffer.inner, offset, size)
+    }
+
+
+    fn create_texture(&self, descriptor: &TextureDescriptor) -> Self::Texture
+    {
+        Captured { handle: self.recording.create_texture(descriptor), inner: self.inner.create_texture(descriptor) }
+    }
+
+
+    fn write_texture(&self, texture: &Self::Texture, mip_level: u32, data: &[u8], bytes_per_row: u32, size: [u32; 2])
+    {
+        self.recording.write_texture(&texture.handle, mip_level, data, bytes_per_row, size);
+        self.inner.write_texture(&texture.inner, mip_level, data, bytes_per_row, size);
+    }
+
+
+    fn texture_size(&self, texture: &Self::Texture) -> [u32; 2]
+    {
+        self.inner.texture_size(&texture.inner)
+    }
+
+
+    fn create_view(&self, texture: &Self::Texture) -> Self::TextureView
+    {
+        let handle = *self.state.borrow_mut().views.entry(texture.handle)
+            .or_insert_with(|| self.recording.create_view(&texture.handle));
+        Captured { handle, inner: self.inner.create_view(&texture.inner) }
+    }
+
+
+    fn destroy_texture(&self, texture: &Self::Texture)
+    {
+        self.state.borrow_mut().views.remove(&texture.handle);
+        self.recording.destroy_texture(&texture.handle);
+        self.inner.destroy_texture(&texture.inner);
+    }
+
+
+    fn create_sampler(&self, descriptor: &SamplerDescriptor) -> Self::Sampler
+    {
+        Captured { handle: self.recording.create_sampler(descriptor), inner: self.inner.create_sampler(descriptor) }
+    }
+
+
+    fn create_shader_module(&self, descriptor: &ShaderModuleDescriptor) -> Self::ShaderModule
+    {
+        Captured
+        {
+            handle: self.recording.create_shader_module(descriptor),
+            inner: self.inner.create_shader_module(descriptor),
+        }
+    }
+
+
+    fn create_render_pipeline(&self, descriptor: &RenderPipelineDescriptor<Self>) -> Self::RenderPipeline
+    {
+        let (Ok(traced), Ok(inner)) = (descriptor.map_handles(&Handles), descriptor.map_handles(&Inner));
+        Captured
+        {
+            handle: self.recording.create_render_pipeline(&traced),
+            inner: self.inner.create_render_pipeline(&inner),
+        }
+    }
+
+
+    fn create_compute_pipeline(&self, descriptor: &ComputePipelineDescriptor<Self>) -> Self::ComputePipeline
+    {
+        let (Ok(traced), Ok(inner)) = (descriptor.map_handles(&Handles), descriptor.map_handles(&Inner));
+        Captured
+        {
+            handle: self.recording.create_compute_pipeline(&traced),
+            inner: self.inner.create_compute_pipeline(&inner),
+        }
+    }
+
+
+    fn render_bind_group_layout(&self, pipeline: &Self::RenderPipeline, index: u32) -> Self::BindGroupLayout
+    {
+        Captured
+        {
+            handle: self.recording.render_bind_group_layout(&pipeline.handle, index),
+            inner: self.inner.render_bind_group_layout(&pipeline.inner, index),
+        }
+    }
+
+
+    fn compute_bind_group_layout(&self, pipeline: &Self::ComputePipeline, index: u32) -> Self::BindGroupLayout
+    {
+        Captured
+        {
+            handle: self.recording.compute_bind_group_layout(&pipeline.handle, index),
+            inner: self.inner.compute_bind_group_layout(&pipeline.inner, index),
+        }
+    }
+
+
+    fn create_bind_group_layout(&self, descriptor: &BindGroupLayoutDescriptor) -> Self::BindGroupLayout
+    {
+        Captured
+        {
+            handle: self.recording.create_bind_group_layout(descriptor),
+            inner: self.inner.create_bind_group_layout(descriptor),
+        }
+    }
+
+
+    fn create_bind_group(&self, descriptor: &BindGroupDescriptor<Self>) -> Self::BindGroup
+    {
+        let (Ok(traced), Ok(inner)) = (descriptor.map_handles(&Handles), descriptor.map_handles(&Inner));
+        Captured { handle: self.recording.create_bind_group(&traced), inner: self.inner.create_bind_group(&inner) }
+    }
+
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer<Self>>)
+    {
+        let (Ok(traced), Ok(inner)) = (
+            command_buffers.iter().map(|command_buffer| command_buffer.map_handles(&Handles)).collect(),
+            command_buffers.iter().map(|command_buffer| command_buffer.map_handles(&Inner)).collect(),
+        );
+        self.recording.submit(traced);
+        self.inner.submit(inner);
+        if !self.state.borrow().capturing
+        {
+            self.forget_uploads();
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use serde::{Serialize, Deserialize};
+
+use crate::Backend;
+use crate::recording::HandleBackend;
+use crate::descriptors::{IndexFormat, RenderPassDescriptor};
+
+
+// encoding only records commands, a backend replays them onto its own encoder when they are submitted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub enum Command<B: Backend>
+{
+    BeginRenderPass(RenderPassDescriptor<B>),
+    SetPipeline(B::RenderPipeline),
+    SetVertexBuffer { slot: u32, buffer: B::Buffer, offset: u64, size: Option<u64> },
+    SetIndexBuffer { buffer: B::Buffer, format: IndexFormat, offset: u64 },
+    SetViewport { x: f32, y: f32, width: f32, height: f32, min_depth: f32, max_depth: f32 },
+    SetScissorRect { x: u32, y: u32, width: u32, height: u32 },
+    SetBlendConstant([f64; 4]),
+    Draw { vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32 },
+    DrawIndexed { index_count: u32, instance_count: u32, first_index: u32, base_vertex: i32, first_instance: u32 },
+    // the arguments are read on the device, laid out like the fields of DrawIndexed
+    DrawIndexedIndirect { buffer: B::Buffer, offset: u64 },
+    EndRenderPass,
+    BeginComputePass { label: Option<String> },
+    SetComputePipeline(B::ComputePipeline),
+    DispatchWorkgroups { x: u32, y: u32, z: u32 },
+    EndComputePass,
+    // used by both kinds of passes
+    SetBindGroup { index: u32, bind_group: B::BindGroup, dynamic_offsets: Vec<u32> },
+    CopyBufferToBuffer { source: B::Buffer, source_offset: u64, destination: B::Buffer, destination_offset: u64, size: u64 },
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct CommandBuffer<B: Backend>
+{
+    pub label: Option<String>,
+    pub commands: Vec<Command<B>>,
+}
+
+
+#[derive(Debug)]
+pub struct CommandEncoder<B: Backend>
+{
+    label: Option<String>,
+    commands: Vec<Command<B>>,
+}
+
+
+impl<B: Backend> Default for CommandEncoder<B>
+{
+    fn default() -> Self
+    {
+        CommandEncoder::new()
+    }
+}
+
+
+impl<B: Backend> CommandEncoder<B>
+{
+    pub fn new() -> Self
+    {
+        CommandEncoder { label: None, commands: Vec::new() }
+    }
+
+
+    pub fn set_label(&mut self, label: &str)
+    {
+        self.label = Some(label.to_owned());
+    }
+
+
+    pub fn begin_render_pass(&mut self, descriptor: &RenderPassDescriptor<B>) -> RenderPassEncoder<'_, B>
+    {
+        self.commands.push(Command::BeginRenderPass(descriptor.clone()));
+        RenderPassEncoder { commands: &mut self.commands }
+    }
+
+
+    pub fn begin_compute_pass(&mut self, label: Option<&str>) -> ComputePassEncoder<'_, B>
+    {
+        self.commands.push(Command::BeginComputePass { label: label.map(str::to_owned) });
+        ComputePassEncoder { commands: &mut self.commands }
+    }
+
+
+    pub fn copy_buffer_to_buffer(
+        &mut self, source: &B::Buffer, source_offset: u64, destination: &B::Buffer, destination_offset: u64, size: u64,
+    )
+    {
+        self.commands.push(Command::CopyBufferToBuffer
+        {
+            source: source.clone(), source_offset, destination: destination.clone(), destination_offset, size,
+        });
+    }
+
+
+    pub fn finish(self) -> CommandBuffer<B>
+    {
+        CommandBuffer { label: self.label, commands: self.commands }
+    }
+}
+
+
+// the pass borrows its encoder, so the encoder can't be finished before the pass is ended
+pub struct RenderPassEncoder<'a, B: Backend>
+{
+    commands: &'a mut Vec<Command<B>>,
+}
+
+
+impl<'a, B: Backend> RenderPassEncoder<'a, B>
+{
+    pub fn set_pipeline(&mut self, pipeline: &B::RenderPipeline)
+    {
+        self.commands.push(Command::SetPipeline(pipeline.clone()));
+    }
+
+
+    pub fn set_bind_group(&mut self, index: u32, bind_group: &B::BindGroup)
+    {
+        self.set_bind_group_with_dynamic_offsets(index, bind_group, &[]);
+    }
+
+
+    pub fn set_bind_group_with_dynamic_offsets(&mut self, index: u32, bind_group: &B::BindGroup, dynamic_offsets: &[u32])
+    {
+        self.commands.push(Command::SetBindGroup
+        {
+            index, bind_group: bind_group.clone(), dynamic_offsets: dynamic_offsets.to_vec(),
+        });
+    }
+
+
+    pub fn set_vertex_buffer(&mut self, slot: u32, buffer: &B::Buffer)
+    {
+        self.commands.push(Command::SetVertexBuffer { slot, buffer: buffer.clone(), offset: 0, size: None });
+    }
+
+
+    pub fn set_vertex_buffer_with_range(&mut self, slot: u32, buffer: &B::Buffer, offset: u64, size: u64)
+    {
+        self.commands.push(Command::SetVertexBuffer { slot, buffer: buffer.clone(), offset, size: Some(size) });
+    }
+
+
+    pub fn set_index_buffer(&mut self, buffer: &B::Buffer, format: IndexFormat)
+    {
+        self.commands.push(Command::SetIndexBuffer { buffer: buffer.clone(), format, offset: 0 });
+    }
+
+
+    pub fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32, min_depth: f32, max_depth: f32)
+    {
+        self.commands.push(Command::SetViewport { x, y, width, height, min_depth, max_depth });
+    }
+
+
+    pub fn set_scissor_rect(&mut self, x: u32, y: u32, width: u32, height: u32)
+    {
+        self.commands.push(Command::SetScissorRect { x, y, width, height });
+    }
+
+
+    pub fn set_blend_constant(&mut self, color: [f64; 4])
+    {
+        self.commands.push(Command::SetBlendConstant(color));
+    }
+
+
+    pub fn draw(&mut self, vertex_count: u32, instance_count: u32)
+    {
+        self.commands.push(Command::Draw { vertex_count, instance_count, first_vertex: 0, first_instance: 0 });
+    }
+
+
+    pub fn draw_indexed(&mut self, index_count: u32, instance_count: u32)
+    {
+        self.commands.push(Command::DrawIndexed
+        {
+            index_count, instance_count, first_index: 0, base_vertex: 0, first_instance: 0,
+        });
+    }
+
+
+    // draws with the five u32 arguments of DrawIndexed found at offset, e.g. written by a compute pass
+    pub fn draw_indexed_indirect(&mut self, indirect_buffer: &B::Buffer, offset: u64)
+    {
+        self.commands.push(Command::DrawIndexedIndirect { buffer: indirect_buffer.clone(), offset });
+    }
+
+
+    pub fn end(self)
+    {
+        self.commands.push(Command::EndRenderPass);
+    }
+}
+
+
+pub struct ComputePassEncoder<'a, B: Backend>
+{
+    commands: &'a mut Vec<Command<B>>,
+}
+
+
+impl<'a, B: Backend> ComputePassEncoder<'a, B>
+{
+    pub fn set_pipeline(&mut self, pipeline: &B::ComputePipeline)
+    {
+        self.commands.push(Command::SetComputePipeline(pipeline.clone()));
+    }
+
+
+    pub fn set_bind_group(&mut self, index: u32, bind_group: &B::BindGroup)
+    {
+        self.commands.push(Command::SetBindGroup { index, bind_group: bind_group.clone(), dynamic_offsets: Vec::new() });
+    }
+
+
+    pub fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32)
+    {
+        self.commands.push(Command::DispatchWorkgroups { x, y, z });
+    }
+
+
+    pub fn end(self)
+    {
+        self.commands.push(Command::EndComputePass);
+    }
+}
@@ -0,0 +1,1107 @@
+use serde::{Serialize, Deserialize};
+
+use crate::Backend;
+use crate::recording::HandleBackend;
+
+
+// the same bit values as GPUBufferUsage, so they can be handed to web-sys as they are
+pub mod buffer_usage
+{
+    pub const MAP_READ: u32 = 0x0001;
+    pub const MAP_WRITE: u32 = 0x0002;
+    pub const COPY_SRC: u32 = 0x0004;
+    pub const COPY_DST: u32 = 0x0008;
+    pub const INDEX: u32 = 0x0010;
+    pub const VERTEX: u32 = 0x0020;
+    pub const UNIFORM: u32 = 0x0040;
+    pub const STORAGE: u32 = 0x0080;
+    pub const INDIRECT: u32 = 0x0100;
+    pub const QUERY_RESOLVE: u32 = 0x0200;
+}
+
+
+// the same bit values as GPUTextureUsage
+pub mod texture_usage
+{
+    pub const COPY_SRC: u32 = 0x01;
+    pub const COPY_DST: u32 = 0x02;
+    pub const TEXTURE_BINDING: u32 = 0x04;
+    pub const STORAGE_BINDING: u32 = 0x08;
+    pub const RENDER_ATTACHMENT: u32 = 0x10;
+}
+
+
+// the same bit values as GPUShaderStage
+pub mod shader_stage
+{
+    pub const VERTEX: u32 = 0x1;
+    pub const FRAGMENT: u32 = 0x2;
+    pub const COMPUTE: u32 = 0x4;
+}
+
+
+// what a device offers beyond the largest texture, the defaults are the limits every WebGPU device has
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limits
+{
+    pub min_uniform_buffer_offset_alignment: u32,
+    pub max_compute_workgroups_per_dimension: u32,
+    pub max_compute_invocations_per_workgroup: u32,
+    pub max_compute_workgroup_size_x: u32,
+}
+
+
+impl Default for Limits
+{
+    fn default() -> Self
+    {
+        Limits
+        {
+            min_uniform_buffer_offset_alignment: 256,
+            max_compute_workgroups_per_dimension: 65535,
+            max_compute_invocations_per_workgroup: 256,
+            max_compute_workgroup_size_x: 256,
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TextureFormat
+{
+    R8Unorm,
+    R32Float,
+    Rgba8Unorm,
+    Rgba8UnormSrgb,
+    Bgra8Unorm,
+    Bgra8UnormSrgb,
+    Rgba16Float,
+    Rgba32Float,
+    Depth24Plus,
+    Depth24PlusStencil8,
+    Depth32Float,
+    // needs the "depth32float-stencil8" feature
+    Depth32FloatStencil8,
+}
+
+
+impl TextureFormat
+{
+    pub fn bytes_per_pixel(&self) -> u32
+    {
+        match self
+        {
+            TextureFormat::R8Unorm => 1,
+            TextureFormat::Rgba16Float => 8,
+            TextureFormat::Rgba32Float => 16,
+            TextureFormat::Depth32FloatStencil8 => 8,
+            _ => 4,
+        }
+    }
+
+
+    pub fn is_depth(&self) -> bool
+    {
+        matches!(
+            self,
+            TextureFormat::Depth24Plus | TextureFormat::Depth24PlusStencil8 | TextureFormat::Depth32Float
+                | TextureFormat::Depth32FloatStencil8
+        )
+    }
+
+
+    pub fn has_stencil(&self) -> bool
+    {
+        matches!(self, TextureFormat::Depth24PlusStencil8 | TextureFormat::Depth32FloatStencil8)
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VertexFormat
+{
+    Uint8x2, Uint8x4, Sint8x2, Sint8x4, Unorm8x2, Unorm8x4, Snorm8x2, Snorm8x4,
+    Uint16x2, Uint16x4, Sint16x2, Sint16x4, Unorm16x2, Unorm16x4, Snorm16x2, Snorm16x4, Float16x2, Float16x4,
+    Float32, Float32x2, Float32x3, Float32x4,
+    Uint32, Uint32x2, Uint32x3, Uint32x4,
+    Sint32, Sint32x2, Sint32x3, Sint32x4,
+}
+
+
+impl VertexFormat
+{
+    pub fn size(&self) -> u64
+    {
+        use VertexFormat::*;
+        match self
+        {
+            Uint8x2 | Sint8x2 | Unorm8x2 | Snorm8x2 => 2,
+            Uint8x4 | Sint8x4 | Unorm8x4 | Snorm8x4 => 4,
+            Uint16x2 | Sint16x2 | Unorm16x2 | Snorm16x2 | Float16x2 => 4,
+            Uint16x4 | Sint16x4 | Unorm16x4 | Snorm16x4 | Float16x4 => 8,
+            Float32 | Uint32 | Sint32 => 4,
+            Float32x2 | Uint32x2 | Sint32x2 => 8,
+            Float32x3 | Uint32x3 | Sint32x3 => 12,
+            Float32x4 | Uint32x4 | Sint32x4 => 16,
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VertexStepMode
+{
+    #[default]
+    Vertex,
+    Instance,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IndexFormat
+{
+    Uint16,
+    Uint32,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PrimitiveTopology
+{
+    PointList,
+    LineList,
+    LineStrip,
+    #[default]
+    TriangleList,
+    TriangleStrip,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FrontFace
+{
+    #[default]
+    Ccw,
+    Cw,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CullMode
+{
+    #[default]
+    None,
+    Front,
+    Back,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CompareFunction
+{
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlendFactor
+{
+    Zero,
+    One,
+    Src,
+    OneMinusSrc,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    Dst,
+    OneMinusDst,
+    DstAlpha,
+    OneMinusDstAlpha,
+    SrcAlphaSaturated,
+    Constant,
+    OneMinusConstant,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BlendOperation
+{
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AddressMode
+{
+    #[default]
+    ClampToEdge,
+    Repeat,
+    MirrorRepeat,
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FilterMode
+{
+    #[default]
+    Nearest,
+    Linear,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LoadOp
+{
+    Load,
+    Clear,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StoreOp
+{
+    Store,
+    Discard,
+}
+
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BufferDescriptor
+{
+    pub label: Option<String>,
+    pub size: u64,
+    pub usage: u32,
+    pub mapped_at_creation: bool,
+}
+
+
+impl BufferDescriptor
+{
+    pub fn new(size: u64, usage: u32) -> Self
+    {
+        BufferDescriptor { label: None, size, usage, mapped_at_creation: false }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+
+    pub fn mapped_at_creation(&mut self, mapped_at_creation: bool) -> &mut Self
+    {
+        self.mapped_at_creation = mapped_at_creation;
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextureDescriptor
+{
+    pub label: Option<String>,
+    pub format: TextureFormat,
+    pub size: [u32; 2],
+    pub usage: u32,
+    pub mip_level_count: u32,
+    pub sample_count: u32,
+}
+
+
+impl TextureDescriptor
+{
+    pub fn new(format: TextureFormat, size: [u32; 2], usage: u32) -> Self
+    {
+        TextureDescriptor { label: None, format, size, usage, mip_level_count: 1, sample_count: 1 }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+
+    pub fn mip_level_count(&mut self, mip_level_count: u32) -> &mut Self
+    {
+        self.mip_level_count = mip_level_count;
+        self
+    }
+
+
+    pub fn sample_count(&mut self, sample_count: u32) -> &mut Self
+    {
+        self.sample_count = sample_count;
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplerDescriptor
+{
+    pub label: Option<String>,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    pub address_mode_w: AddressMode,
+    pub mag_filter: FilterMode,
+    pub min_filter: FilterMode,
+    pub mipmap_filter: FilterMode,
+    pub compare: Option<CompareFunction>,
+}
+
+
+impl SamplerDescriptor
+{
+    pub fn new() -> Self
+    {
+        SamplerDescriptor::default()
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+
+    pub fn address_mode_u(&mut self, address_mode: AddressMode) -> &mut Self
+    {
+        self.address_mode_u = address_mode;
+        self
+    }
+
+
+    pub fn address_mode_v(&mut self, address_mode: AddressMode) -> &mut Self
+    {
+        self.address_mode_v = address_mode;
+        self
+    }
+
+
+    pub fn address_mode_w(&mut self, address_mode: AddressMode) -> &mut Self
+    {
+        self.address_mode_w = address_mode;
+        self
+    }
+
+
+    pub fn mag_filter(&mut self, filter: FilterMode) -> &mut Self
+    {
+        self.mag_filter = filter;
+        self
+    }
+
+
+    pub fn min_filter(&mut self, filter: FilterMode) -> &mut Self
+    {
+        self.min_filter = filter;
+        self
+    }
+
+
+    pub fn mipmap_filter(&mut self, filter: FilterMode) -> &mut Self
+    {
+        self.mipmap_filter = filter;
+        self
+    }
+
+
+    pub fn compare(&mut self, compare: CompareFunction) -> &mut Self
+    {
+        self.compare = Some(compare);
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShaderModuleDescriptor
+{
+    pub label: Option<String>,
+    pub code: String,
+}
+
+
+impl ShaderModuleDescriptor
+{
+    pub fn new(code: &str) -> Self
+    {
+        ShaderModuleDescriptor { label: None, code: code.to_owned() }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VertexAttribute
+{
+    pub format: VertexFormat,
+    pub offset: u64,
+    pub shader_location: u32,
+}
+
+
+impl VertexAttribute
+{
+    pub fn new(format: VertexFormat, offset: u64, shader_location: u32) -> Self
+    {
+        VertexAttribute { format, offset, shader_location }
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VertexBufferLayout
+{
+    pub array_stride: u64,
+    pub step_mode: VertexStepMode,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+
+impl VertexBufferLayout
+{
+    pub fn new(array_stride: u64, attributes: &[VertexAttribute]) -> Self
+    {
+        VertexBufferLayout { array_stride, step_mode: VertexStepMode::Vertex, attributes: attributes.to_vec() }
+    }
+
+
+    pub fn step_mode(&mut self, step_mode: VertexStepMode) -> &mut Self
+    {
+        self.step_mode = step_mode;
+        self
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BlendComponent
+{
+    pub src_factor: BlendFactor,
+    pub dst_factor: BlendFactor,
+    pub operation: BlendOperation,
+}
+
+
+impl BlendComponent
+{
+    pub fn new(src_factor: BlendFactor, dst_factor: BlendFactor, operation: BlendOperation) -> Self
+    {
+        BlendComponent { src_factor, dst_factor, operation }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BlendState
+{
+    pub color: BlendComponent,
+    pub alpha: BlendComponent,
+}
+
+
+impl BlendState
+{
+    pub fn new(color: BlendComponent, alpha: BlendComponent) -> Self
+    {
+        BlendState { color, alpha }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ColorTargetState
+{
+    pub format: TextureFormat,
+    pub blend: Option<BlendState>,
+}
+
+
+impl ColorTargetState
+{
+    pub fn new(format: TextureFormat) -> Self
+    {
+        ColorTargetState { format, blend: None }
+    }
+
+
+    pub fn blend(&mut self, blend: BlendState) -> &mut Self
+    {
+        self.blend = Some(blend);
+        self
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrimitiveState
+{
+    pub topology: PrimitiveTopology,
+    pub front_face: FrontFace,
+    pub cull_mode: CullMode,
+}
+
+
+impl PrimitiveState
+{
+    pub fn new() -> Self
+    {
+        PrimitiveState::default()
+    }
+
+
+    pub fn topology(&mut self, topology: PrimitiveTopology) -> &mut Self
+    {
+        self.topology = topology;
+        self
+    }
+
+
+    pub fn front_face(&mut self, front_face: FrontFace) -> &mut Self
+    {
+        self.front_face = front_face;
+        self
+    }
+
+
+    pub fn cull_mode(&mut self, cull_mode: CullMode) -> &mut Self
+    {
+        self.cull_mode = cull_mode;
+        self
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DepthStencilState
+{
+    pub format: TextureFormat,
+    pub depth_write_enabled: bool,
+    pub depth_compare: CompareFunction,
+}
+
+
+impl DepthStencilState
+{
+    pub fn new(format: TextureFormat, depth_write_enabled: bool, depth_compare: CompareFunction) -> Self
+    {
+        DepthStencilState { format, depth_write_enabled, depth_compare }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MultisampleState
+{
+    pub count: u32,
+    pub alpha_to_coverage_enabled: bool,
+}
+
+
+impl Default for MultisampleState
+{
+    fn default() -> Self
+    {
+        MultisampleState { count: 1, alpha_to_coverage_enabled: false }
+    }
+}
+
+
+impl MultisampleState
+{
+    pub fn new() -> Self
+    {
+        MultisampleState::default()
+    }
+
+
+    pub fn count(&mut self, count: u32) -> &mut Self
+    {
+        self.count = count;
+        self
+    }
+
+
+    pub fn alpha_to_coverage_enabled(&mut self, alpha_to_coverage_enabled: bool) -> &mut Self
+    {
+        self.alpha_to_coverage_enabled = alpha_to_coverage_enabled;
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct VertexState<B: Backend>
+{
+    pub module: B::ShaderModule,
+    pub entry_point: String,
+    pub buffers: Vec<VertexBufferLayout>,
+}
+
+
+impl<B: Backend> VertexState<B>
+{
+    pub fn new(entry_point: &str, module: &B::ShaderModule) -> Self
+    {
+        VertexState { module: module.clone(), entry_point: entry_point.to_owned(), buffers: Vec::new() }
+    }
+
+
+    pub fn buffers(&mut self, buffers: &[VertexBufferLayout]) -> &mut Self
+    {
+        self.buffers = buffers.to_vec();
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct FragmentState<B: Backend>
+{
+    pub module: B::ShaderModule,
+    pub entry_point: String,
+    pub targets: Vec<ColorTargetState>,
+}
+
+
+impl<B: Backend> FragmentState<B>
+{
+    pub fn new(entry_point: &str, module: &B::ShaderModule, targets: &[ColorTargetState]) -> Self
+    {
+        FragmentState { module: module.clone(), entry_point: entry_point.to_owned(), targets: targets.to_vec() }
+    }
+}
+
+
+// without bind group layouts a pipeline uses the "auto" layout, its bind group layouts are then queried from it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct RenderPipelineDescriptor<B: Backend>
+{
+    pub label: Option<String>,
+    pub bind_group_layouts: Option<Vec<B::BindGroupLayout>>,
+    pub vertex: VertexState<B>,
+    pub fragment: Option<FragmentState<B>>,
+    pub primitive: PrimitiveState,
+    pub depth_stencil: Option<DepthStencilState>,
+    pub multisample: MultisampleState,
+}
+
+
+impl<B: Backend> RenderPipelineDescriptor<B>
+{
+    pub fn new(vertex: VertexState<B>) -> Self
+    {
+        RenderPipelineDescriptor
+        {
+            label: None,
+            bind_group_layouts: None,
+            vertex,
+            fragment: None,
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+
+    // the layouts of the bind groups by index, e.g. to share bind groups between pipelines
+    pub fn bind_group_layouts(&mut self, bind_group_layouts: &[B::BindGroupLayout]) -> &mut Self
+    {
+        self.bind_group_layouts = Some(bind_group_layouts.to_vec());
+        self
+    }
+
+
+    pub fn fragment(&mut self, fragment: FragmentState<B>) -> &mut Self
+    {
+        self.fragment = Some(fragment);
+        self
+    }
+
+
+    pub fn primitive(&mut self, primitive: PrimitiveState) -> &mut Self
+    {
+        self.primitive = primitive;
+        self
+    }
+
+
+    pub fn depth_stencil(&mut self, depth_stencil: DepthStencilState) -> &mut Self
+    {
+        self.depth_stencil = Some(depth_stencil);
+        self
+    }
+
+
+    pub fn multisample(&mut self, multisample: MultisampleState) -> &mut Self
+    {
+        self.multisample = multisample;
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct ComputePipelineDescriptor<B: Backend>
+{
+    pub label: Option<String>,
+    pub bind_group_layouts: Option<Vec<B::BindGroupLayout>>,
+    pub module: B::ShaderModule,
+    pub entry_point: String,
+}
+
+
+impl<B: Backend> ComputePipelineDescriptor<B>
+{
+    pub fn new(entry_point: &str, module: &B::ShaderModule) -> Self
+    {
+        ComputePipelineDescriptor
+        {
+            label: None, bind_group_layouts: None, module: module.clone(), entry_point: entry_point.to_owned(),
+        }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+
+    pub fn bind_group_layouts(&mut self, bind_group_layouts: &[B::BindGroupLayout]) -> &mut Self
+    {
+        self.bind_group_layouts = Some(bind_group_layouts.to_vec());
+        self
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BufferBindingType
+{
+    Uniform,
+    Storage,
+    ReadOnlyStorage,
+}
+
+
+// textures are filterable float 2d textures and samplers are filtering ones, the only kinds the chapters bind
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BindingType
+{
+    Buffer { ty: BufferBindingType, has_dynamic_offset: bool },
+    Sampler,
+    Texture,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BindGroupLayoutEntry
+{
+    pub binding: u32,
+    // shader_stage bits
+    pub visibility: u32,
+    pub ty: BindingType,
+}
+
+
+impl BindGroupLayoutEntry
+{
+    pub fn buffer(binding: u32, visibility: u32, ty: BufferBindingType) -> Self
+    {
+        BindGroupLayoutEntry { binding, visibility, ty: BindingType::Buffer { ty, has_dynamic_offset: false } }
+    }
+
+
+    pub fn sampler(binding: u32, visibility: u32) -> Self
+    {
+        BindGroupLayoutEntry { binding, visibility, ty: BindingType::Sampler }
+    }
+
+
+    pub fn texture(binding: u32, visibility: u32) -> Self
+    {
+        BindGroupLayoutEntry { binding, visibility, ty: BindingType::Texture }
+    }
+
+
+    // only buffers take dynamic offsets, anything else is left as it is
+    pub fn has_dynamic_offset(&mut self, has_dynamic_offset: bool) -> &mut Self
+    {
+        if let BindingType::Buffer { has_dynamic_offset: dynamic, .. } = &mut self.ty
+        {
+            *dynamic = has_dynamic_offset;
+        }
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BindGroupLayoutDescriptor
+{
+    pub label: Option<String>,
+    pub entries: Vec<BindGroupLayoutEntry>,
+}
+
+
+impl BindGroupLayoutDescriptor
+{
+    pub fn new(entries: &[BindGroupLayoutEntry]) -> Self
+    {
+        BindGroupLayoutDescriptor { label: None, entries: entries.to_vec() }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub enum BindingResource<B: Backend>
+{
+    // a size of None binds the rest of the buffer after offset
+    Buffer { buffer: B::Buffer, offset: u64, size: Option<u64> },
+    Sampler(B::Sampler),
+    TextureView(B::TextureView),
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct BindGroupEntry<B: Backend>
+{
+    pub binding: u32,
+    pub resource: BindingResource<B>,
+}
+
+
+impl<B: Backend> BindGroupEntry<B>
+{
+    pub fn new(binding: u32, resource: BindingResource<B>) -> Self
+    {
+        BindGroupEntry { binding, resource }
+    }
+
+
+    pub fn buffer(binding: u32, buffer: &B::Buffer) -> Self
+    {
+        BindGroupEntry::new(binding, BindingResource::Buffer { buffer: buffer.clone(), offset: 0, size: None })
+    }
+
+
+    pub fn sampler(binding: u32, sampler: &B::Sampler) -> Self
+    {
+        BindGroupEntry::new(binding, BindingResource::Sampler(sampler.clone()))
+    }
+
+
+    pub fn texture_view(binding: u32, view: &B::TextureView) -> Self
+    {
+        BindGroupEntry::new(binding, BindingResource::TextureView(view.clone()))
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct BindGroupDescriptor<B: Backend>
+{
+    pub label: Option<String>,
+    pub layout: B::BindGroupLayout,
+    pub entries: Vec<BindGroupEntry<B>>,
+}
+
+
+impl<B: Backend> BindGroupDescriptor<B>
+{
+    pub fn new(entries: Vec<BindGroupEntry<B>>, layout: &B::BindGroupLayout) -> Self
+    {
+        BindGroupDescriptor { label: None, layout: layout.clone(), entries }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct RenderPassColorAttachment<B: Backend>
+{
+    pub view: B::TextureView,
+    pub resolve_target: Option<B::TextureView>,
+    pub load_op: LoadOp,
+    pub store_op: StoreOp,
+    pub clear_value: [f64; 4],
+}
+
+
+impl<B: Backend> RenderPassColorAttachment<B>
+{
+    pub fn new(load_op: LoadOp, store_op: StoreOp, view: &B::TextureView) -> Self
+    {
+        RenderPassColorAttachment
+        {
+            view: view.clone(), resolve_target: None, load_op, store_op, clear_value: [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+
+
+    // r, g, b, a
+    pub fn clear_value(&mut self, clear_value: [f64; 4]) -> &mut Self
+    {
+        self.clear_value = clear_value;
+        self
+    }
+
+
+    pub fn resolve_target(&mut self, view: &B::TextureView) -> &mut Self
+    {
+        self.resolve_target = Some(view.clone());
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct RenderPassDepthStencilAttachment<B: Backend>
+{
+    pub view: B::TextureView,
+    pub depth_load_op: LoadOp,
+    pub depth_store_op: StoreOp,
+    pub depth_clear_value: f32,
+    // only for formats with a stencil aspect, which have to say what happens to it
+    pub stencil_ops: Option<(LoadOp, StoreOp)>,
+    pub stencil_clear_value: u32,
+}
+
+
+impl<B: Backend> RenderPassDepthStencilAttachment<B>
+{
+    pub fn new(view: &B::TextureView) -> Self
+    {
+        RenderPassDepthStencilAttachment
+        {
+            view: view.clone(),
+            depth_load_op: LoadOp::Clear,
+            depth_store_op: StoreOp::Store,
+            depth_clear_value: 1.0,
+            stencil_ops: None,
+            stencil_clear_value: 0,
+        }
+    }
+
+
+    pub fn depth_load_op(&mut self, load_op: LoadOp) -> &mut Self
+    {
+        self.depth_load_op = load_op;
+        self
+    }
+
+
+    pub fn depth_store_op(&mut self, store_op: StoreOp) -> &mut Self
+    {
+        self.depth_store_op = store_op;
+        self
+    }
+
+
+    pub fn depth_clear_value(&mut self, depth_clear_value: f32) -> &mut Self
+    {
+        self.depth_clear_value = depth_clear_value;
+        self
+    }
+
+
+    pub fn stencil_ops(&mut self, load_op: LoadOp, store_op: StoreOp) -> &mut Self
+    {
+        self.stencil_ops = Some((load_op, store_op));
+        self
+    }
+
+
+    pub fn stencil_clear_value(&mut self, stencil_clear_value: u32) -> &mut Self
+    {
+        self.stencil_clear_value = stencil_clear_value;
+        self
+    }
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "B: HandleBackend")]
+pub struct RenderPassDescriptor<B: Backend>
+{
+    pub label: Option<String>,
+    pub color_attachments: Vec<RenderPassColorAttachment<B>>,
+    pub depth_stencil_attachment: Option<RenderPassDepthStencilAttachment<B>>,
+}
+
+
+impl<B: Backend> RenderPassDescriptor<B>
+{
+    pub fn new(color_attachments: Vec<RenderPassColorAttachment<B>>) -> Self
+    {
+        RenderPassDescriptor { label: None, color_attachments, depth_stencil_attachment: None }
+    }
+
+
+    pub fn label(&mut self, label: &str) -> &mut Self
+    {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+
+    pub fn depth_stencil_attachment(&mut self, attachment: RenderPassDepthStencilAttachment<B>) -> &mut Self
+    {
+        self.depth_stencil_attachment = Some(attachment);
+        self
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Backend, BufferDescriptor, CommandEncoder};
+use crate::buffer_usage::{COPY_DST, COPY_SRC};
+
+
+// a buffer that is reallocated, at least doubling, when it has to hold more. its contents are copied over
+// to the new buffer, but bind groups referring to it have to be created again
+#[derive(Debug)]
+pub struct GrowableBuffer<B: Backend>
+{
+    buffer: B::Buffer,
+    descriptor: BufferDescriptor,
+}
+
+
+impl<B: Backend> GrowableBuffer<B>
+{
+    // the buffer can always be copied from and to, for growing
+    pub fn new(backend: &B, descriptor: &BufferDescriptor) -> Self
+    {
+        let mut descriptor = descriptor.clone();
+        descriptor.usage |= COPY_SRC | COPY_DST;
+        GrowableBuffer { buffer: backend.create_buffer(&descriptor), descriptor }
+    }
+
+
+    pub fn buffer(&self) -> &B::Buffer
+    {
+        &self.buffer
+    }
+
+
+    pub fn size(&self) -> u64
+    {
+        self.descriptor.size
+    }
+
+
+    // returns whether the buffer was reallocated
+    pub fn reserve(&mut self, backend: &B, size: u64) -> bool
+    {
+        if size <= self.descriptor.size
+        {
+            return false;
+        }
+        let old_size = self.descriptor.size;
+        self.descriptor.size = size.max(old_size.saturating_mul(2));
+        let old_buffer = std::mem::replace(&mut self.buffer, backend.create_buffer(&self.descriptor));
+
+        // destroying the old buffer waits for the copy that was submitted before
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("growing buffer encoder");
+        command_encoder.copy_buffer_to_buffer(&old_buffer, 0, &self.buffer, 0, old_size);
+        backend.submit(vec![command_encoder.finish()]);
+        backend.destroy_buffer(&old_buffer);
+        true
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{AsBytes, Command};
+    use crate::buffer_usage::{COPY_DST, COPY_SRC, STORAGE};
+    use crate::recording::{Call, RecordingBackend};
+
+
+    #[test]
+    fn buffers_grow_geometrically()
+    {
+        let backend = RecordingBackend::new();
+        let mut descriptor = BufferDescriptor::new(8, STORAGE | COPY_DST);
+        descriptor.label("instances");
+        let mut buffer = GrowableBuffer::new(&backend, &descriptor);
+        let first = *buffer.buffer();
+
+        assert!(!buffer.reserve(&backend, 8));
+        let reallocations = (9..=8 * 1024).filter(|size| buffer.reserve(&backend, *size)).count();
+        assert_eq!(reallocations, 10);
+        assert_eq!(buffer.size(), 8 * 1024);
+        assert!(buffer.reserve(&backend, 100_000));
+        assert_eq!(buffer.size(), 100_000);
+
+        // every buffer but the current one is destroyed, and they all keep the label and usage
+        let calls = backend.calls();
+        let destroyed = calls.iter().filter(|call| matches!(call, Call::DestroyBuffer(_))).count();
+        assert_eq!(destroyed, 11);
+        assert!(matches!(&calls[3], Call::DestroyBuffer(handle) if *handle == first));
+        assert!(calls.iter().all(|call| match call
+        {
+            Call::CreateBuffer { descriptor: created, .. } =>
+                created.label == descriptor.label && created.usage == descriptor.usage | COPY_SRC,
+            _ => true,
+        }));
+    }
+
+
+    #[test]
+    fn growing_keeps_the_contents()
+    {
+        let backend = RecordingBackend::new();
+        let mut buffer = GrowableBuffer::new(&backend, &BufferDescriptor::new(8, STORAGE));
+        backend.write_buffer(buffer.buffer(), 0, [1u32, 2].as_bytes());
+        let first = *buffer.buffer();
+
+        assert!(buffer.reserve(&backend, 12));
+        assert_eq!(buffer.size(), 16);
+        backend.write_buffer(buffer.buffer(), 8, [3u32].as_bytes());
+        assert_eq!(backend.buffer_contents(buffer.buffer()).unwrap(), [1u32, 2, 3, 0].as_bytes());
+
+        // the old buffer is copied from before it is destroyed
+        let calls = backend.calls();
+        assert!(matches!(&calls[3..5], [Call::Submit(command_buffers), Call::DestroyBuffer(destroyed)]
+            if *destroyed == first && matches!(command_buffers[0].commands[..], [Command::CopyBufferToBuffer
+                {
+                    source, source_offset: 0, destination, destination_offset: 0, size: 8,
+                }] if source == first && destination == *buffer.buffer())));
+    }
+
+
+    #[test]
+    fn repeated_growth_keeps_the_contents_of_every_buffer()
+    {
+        let backend = RecordingBackend::new();
+        let mut buffer = GrowableBuffer::new(&backend, &BufferDescriptor::new(4, STORAGE));
+        let mut expected = Vec::new();
+        for value in 0..20u32
+        {
+            buffer.reserve(&backend, 4 * (value as u64 + 1));
+            backend.write_buffer(buffer.buffer(), 4 * value as u64, [value].as_bytes());
+            expected.push(value);
+        }
+
+        assert_eq!(buffer.size(), 4 * 32);
+        let contents = backend.buffer_contents(buffer.buffer()).unwrap();
+        assert_eq!(contents[..4 * 20], *expected.as_bytes());
+        assert!(contents[4 * 20..].iter().all(|byte| *byte == 0));
+    }
+
+
+    #[test]
+    fn shrinking_leaves_the_buffer_alone()
+    {
+        let backend = RecordingBackend::new();
+        let mut buffer = GrowableBuffer::new(&backend, &BufferDescriptor::new(64, STORAGE));
+        let first = *buffer.buffer();
+        backend.take_calls();
+
+        assert!(!buffer.reserve(&backend, 16));
+        assert!(!buffer.reserve(&backend, 0));
+        assert_eq!(buffer.size(), 64);
+        assert_eq!(*buffer.buffer(), first);
+        assert!(backend.calls().is_empty());
+    }
+
+
+    #[test]
+    fn buffers_can_be_copied_from_and_to()
+    {
+        let backend = RecordingBackend::new();
+        let buffer = GrowableBuffer::new(&backend, &BufferDescriptor::new(8, STORAGE));
+        let calls = backend.calls();
+        assert!(matches!(&calls[..], [Call::CreateBuffer { buffer: created, descriptor }]
+            if created == buffer.buffer() && descriptor.usage == STORAGE | COPY_SRC | COPY_DST));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::*;
+
+
+// swaps the objects of one backend for those of another, which turns a scene's descriptors and commands into
+// the handles of a trace while capturing, and the handles of a trace into device objects while replaying
+pub trait HandleMap<A: Backend, B: Backend>
+{
+    type Error;
+
+    fn buffer(&self, buffer: &A::Buffer) -> Result<B::Buffer, Self::Error>;
+    fn texture(&self, texture: &A::Texture) -> Result<B::Texture, Self::Error>;
+    fn texture_view(&self, view: &A::TextureView) -> Result<B::TextureView, Self::Error>;
+    fn sampler(&self, sampler: &A::Sampler) -> Result<B::Sampler, Self::Error>;
+    fn shader_module(&self, module: &A::ShaderModule) -> Result<B::ShaderModule, Self::Error>;
+    fn render_pipeline(&self, pipeline: &A::RenderPipeline) -> Result<B::RenderPipeline, Self::Error>;
+    fn compute_pipeline(&self, pipeline: &A::ComputePipeline) -> Result<B::ComputePipeline, Self::Error>;
+    fn bind_group_layout(&self, layout: &A::BindGroupLayout) -> Result<B::BindGroupLayout, Self::Error>;
+    fn bind_group(&self, bind_group: &A::BindGroup) -> Result<B::BindGroup, Self::Error>;
+}
+
+
+impl<A: Backend> VertexState<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<VertexState<B>, M::Error>
+    {
+        Ok(VertexState
+        {
+            module: map.shader_module(&self.module)?,
+            entry_point: self.entry_point.clone(),
+            buffers: self.buffers.clone(),
+        })
+    }
+}
+
+
+impl<A: Backend> FragmentState<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<FragmentState<B>, M::Error>
+    {
+        Ok(FragmentState
+        {
+            module: map.shader_module(&self.module)?,
+            entry_point: self.entry_point.clone(),
+            targets: self.targets.clone(),
+        })
+    }
+}
+
+
+fn map_layouts<A: Backend, B: Backend, M: HandleMap<A, B>>(
+    layouts: &[A::BindGroupLayout], map: &M,
+)
+    -> Result<Vec<B::BindGroupLayout>, M::Error>
+{
+    layouts.iter().map(|layout| map.bind_group_layout(layout)).collect()
+}
+
+
+impl<A: Backend> RenderPipelineDescriptor<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<RenderPipelineDescriptor<B>, M::Error>
+    {
+        Ok(RenderPipelineDescriptor
+        {
+            label: self.label.clone(),
+            bind_group_layouts: self.bind_group_layouts.as_ref().map(|layouts| map_layouts(layouts, map)).transpose()?,
+            vertex: self.vertex.map_handles(map)?,
+            fragment: self.fragment.as_ref().map(|fragment| fragment.map_handles(map)).transpose()?,
+            primitive: self.primitive,
+            depth_stencil: self.depth_stencil,
+            multisample: self.multisample,
+        })
+    }
+}
+
+
+impl<A: Backend> ComputePipelineDescriptor<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<ComputePipelineDescriptor<B>, M::Error>
+    {
+        Ok(ComputePipelineDescriptor
+        {
+            label: self.label.clone(),
+            bind_group_layouts: self.bind_group_layouts.as_ref().map(|layouts| map_layouts(layouts, map)).transpose()?,
+            module: map.shader_module(&self.module)?,
+            entry_point: self.entry_point.clone(),
+        })
+    }
+}
+
+
+impl<A: Backend> BindGroupDescriptor<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<BindGroupDescriptor<B>, M::Error>
+    {
+        let entries = self.entries.iter()
+            .map(|entry| Ok(BindGroupEntry
+            {
+                binding: entry.binding,
+                resource: match &entry.resource
+                {
+                    BindingResource::Buffer { buffer, offset, size } =>
+                    {
+                        BindingResource::Buffer { buffer: map.buffer(buffer)?, offset: *offset, size: *size }
+                    },
+                    BindingResource::Sampler(sampler) => BindingResource::Sampler(map.sampler(sampler)?),
+                    BindingResource::TextureView(view) => BindingResource::TextureView(map.texture_view(view)?),
+                },
+            }))
+            .collect::<Result<_, _>>()?;
+
+        Ok(BindGroupDescriptor { label: self.label.clone(), layout: map.bind_group_layout(&self.layout)?, entries })
+    }
+}
+
+
+impl<A: Backend> RenderPassDescriptor<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<RenderPassDescriptor<B>, M::Error>
+    {
+        let color_attachments = self.color_attachments.iter()
+            .map(|attachment| Ok(RenderPassColorAttachment
+            {
+                view: map.texture_view(&attachment.view)?,
+                resolve_target: attachment.resolve_target.as_ref().map(|view| map.texture_view(view)).transpose()?,
+                load_op: attachment.load_op,
+                store_op: attachment.store_op,
+                clear_value: attachment.clear_value,
+            }))
+            .collect::<Result<_, _>>()?;
+
+        let depth_stencil_attachment = match &self.depth_stencil_attachment
+        {
+            Some(attachment) => Some(RenderPassDepthStencilAttachment
+            {
+                view: map.texture_view(&attachment.view)?,
+                depth_load_op: attachment.depth_load_op,
+                depth_store_op: attachment.depth_store_op,
+                depth_clear_value: attachment.depth_clear_value,
+                stencil_ops: attachment.stencil_ops,
+                stencil_clear_value: attachment.stencil_clear_value,
+            }),
+            None => None,
+        };
+
+        Ok(RenderPassDescriptor { label: self.label.clone(), color_attachments, depth_stencil_attachment })
+    }
+}
+
+
+impl<A: Backend> Command<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<Command<B>, M::Error>
+    {
+        Ok(match self
+        {
+            Command::BeginRenderPass(descriptor) => Command::BeginRenderPass(descriptor.map_handles(map)?),
+            Command::SetPipeline(pipeline) => Command::SetPipeline(map.render_pipeline(pipeline)?),
+            Command::SetVertexBuffer { slot, buffer, offset, size } =>
+            {
+                Command::SetVertexBuffer { slot: *slot, buffer: map.buffer(buffer)?, offset: *offset, size: *size }
+            },
+            Command::SetIndexBuffer { buffer, format, offset } =>
+            {
+                Command::SetIndexBuffer { buffer: map.buffer(buffer)?, format: *format, offset: *offset }
+            },
+            Command::SetViewport { x, y, width, height, min_depth, max_depth } => Command::SetViewport
+            {
+                x: *x, y: *y, width: *width, height: *height, min_depth: *min_depth, max_depth: *max_depth,
+            },
+            Command::SetScissorRect { x, y, width, height } =>
+            {
+                Command::SetScissorRect { x: *x, y: *y, width: *width, height: *height }
+            },
+            Command::SetBlendConstant(color) => Command::SetBlendConstant(*color),
+            Command::Draw { vertex_count, instance_count, first_vertex, first_instance } => Command::Draw
+            {
+                vertex_count: *vertex_count,
+                instance_count: *instance_count,
+                first_vertex: *first_vertex,
+                first_instance: *first_instance,
+            },
+            Command::DrawIndexed { index_count, instance_count, first_index, base_vertex, first_instance } =>
+            {
+                Command::DrawIndexed
+                {
+                    index_count: *index_count,
+                    instance_count: *instance_count,
+                    first_index: *first_index,
+                    base_vertex: *base_vertex,
+                    first_instance: *first_instance,
+                }
+            },
+            Command::DrawIndexedIndirect { buffer, offset } =>
+            {
+                Command::DrawIndexedIndirect { buffer: map.buffer(buffer)?, offset: *offset }
+            },
+            Command::EndRenderPass => Command::EndRenderPass,
+            Command::BeginComputePass { label } => Command::BeginComputePass { label: label.clone() },
+            Command::SetComputePipeline(pipeline) => Command::SetComputePipeline(map.compute_pipeline(pipeline)?),
+            Command::DispatchWorkgroups { x, y, z } => Command::DispatchWorkgroups { x: *x, y: *y, z: *z },
+            Command::EndComputePass => Command::EndComputePass,
+            Command::SetBindGroup { index, bind_group, dynamic_offsets } => Command::SetBindGroup
+            {
+                index: *index,
+                bind_group: map.bind_group(bind_group)?,
+                dynamic_offsets: dynamic_offsets.clone(),
+            },
+            Command::CopyBufferToBuffer { source, source_offset, destination, destination_offset, size } =>
+            {
+                Command::CopyBufferToBuffer
+                {
+                    source: map.buffer(source)?,
+                    source_offset: *source_offset,
+                    destination: map.buffer(destination)?,
+                    destination_offset: *destination_offset,
+                    size: *size,
+                }
+            },
+        })
+    }
+}
+
+
+impl<A: Backend> CommandBuffer<A>
+{
+    pub fn map_handles<B: Backend, M: HandleMap<A, B>>(&self, map: &M) -> Result<CommandBuffer<B>, M::Error>
+    {
+        let commands = self.commands.iter().map(|command| command.map_handles(map)).collect::<Result<_, _>>()?;
+        Ok(CommandBuffer { label: self.label.clone(), commands })
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+mod blend;
+pub use blend::{BlendMode, BlendSettings, canvas_alpha_mode, configure_context};
+
+mod command;
+pub use command::{Command, CommandBuffer, CommandEncoder, RenderPassEncoder, ComputePassEncoder};
+
+mod descriptors;
+pub use descriptors::*;
+
+mod growable_buffer;
+pub use growable_buffer::GrowableBuffer;
+
+mod handle_map;
+pub use handle_map::HandleMap;
+
+mod multisample;
+pub use multisample::{MultisampleTarget, validate_sample_count};
+
+mod viewport;
+pub use viewport::Viewport;
+
+pub mod capture;
+pub mod recording;
+pub mod trace;
+pub mod web;
+#[cfg(feature = "wgpu")]
+pub mod native;
+
+
+// the bytes of a buffer once the device is done with it, see Backend::read_buffer
+pub type BufferRead = Pin<Box<dyn Future<Output = Result<Vec<u8>, String>>>>;
+
+
+// everything a scene needs from a device and its queue. the handles are cheap to clone, like the
+// web-sys objects they mirror, and command encoding is shared by all backends (see CommandEncoder).
+// every chapter keeps what it does with the gpu in a Renderer generic over its backend, so the same
+// code draws to the canvas, renders offscreen and runs in the tests
+pub trait Backend: Clone + Debug
+{
+    type Buffer: Clone + Debug;
+    type Texture: Clone + Debug;
+    type TextureView: Clone + Debug;
+    type Sampler: Clone + Debug;
+    type ShaderModule: Clone + Debug;
+    type RenderPipeline: Clone + Debug;
+    type ComputePipeline: Clone + Debug;
+    type BindGroupLayout: Clone + Debug;
+    type BindGroup: Clone + Debug;
+
+    fn max_texture_dimension_2d(&self) -> u32;
+    fn limits(&self) -> Limits;
+    // a WebGPU feature name, like "shader-f16"
+    fn has_feature(&self, feature: &str) -> bool;
+
+    fn create_buffer(&self, descriptor: &BufferDescriptor) -> Self::Buffer;
+    fn write_buffer(&self, buffer: &Self::Buffer, offset: u64, data: &[u8]);
+    fn destroy_buffer(&self, buffer: &Self::Buffer);
+    // the buffer needs the MAP_READ usage, so results are usually copied into a staging buffer first
+    fn read_buffer(&self, buffer: &Self::Buffer, offset: u64, size: u64) -> BufferRead;
+
+    fn create_texture(&self, descriptor: &TextureDescriptor) -> Self::Texture;
+    // writes a whole mip level, rows are tightly packed unless bytes_per_row says otherwise
+    fn write_texture(&self, texture: &Self::Texture, mip_level: u32, data: &[u8], bytes_per_row: u32, size: [u32; 2]);
+    fn texture_size(&self, texture: &Self::Texture) -> [u32; 2];
+    fn create_view(&self, texture: &Self::Texture) -> Self::TextureView;
+    fn destroy_texture(&self, texture: &Self::Texture);
+
+    fn create_sampler(&self, descriptor: &SamplerDescriptor) -> Self::Sampler;
+    fn create_shader_module(&self, descriptor: &ShaderModuleDescriptor) -> Self::ShaderModule;
+
+    fn create_render_pipeline(&self, descriptor: &RenderPipelineDescriptor<Self>) -> Self::RenderPipeline;
+    fn create_compute_pipeline(&self, descriptor: &ComputePipelineDescriptor<Self>) -> Self::ComputePipeline;
+    fn render_bind_group_layout(&self, pipeline: &Self::RenderPipeline, index: u32) -> Self::BindGroupLayout;
+    fn compute_bind_group_layout(&self, pipeline: &Self::ComputePipeline, index: u32) -> Self::BindGroupLayout;
+    fn create_bind_group_layout(&self, descriptor: &BindGroupLayoutDescriptor) -> Self::BindGroupLayout;
+    fn create_bind_group(&self, descriptor: &BindGroupDescriptor<Self>) -> Self::BindGroup;
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer<Self>>);
+}
+
+
+// views plain values as the bytes uploaded to buffers and textures
+pub trait AsBytes
+{
+    fn as_bytes(&self) -> &[u8];
+}
+
+
+macro_rules! impl_as_bytes
+{
+    ($($ty:ty),*) =>
+    {
+        $(
+            impl AsBytes for [$ty]
+            {
+                fn as_bytes(&self) -> &[u8]
+                {
+                    // plain numbers have no padding and every byte pattern is valid
+                    unsafe { std::slice::from_raw_parts(self.as_ptr() as *const u8, std::mem::size_of_val(self)) }
+                }
+            }
+        )*
+    };
+}
+
+
+impl_as_bytes!(u8, u16, u32, i32, f32);
@@ -0,0 +1,158 @@
+use crate::
+{
+    Backend, TextureDescriptor, TextureFormat, MultisampleState, RenderPassColorAttachment, LoadOp, StoreOp,
+};
+use crate::texture_usage::RENDER_ATTACHMENT;
+
+
+// WebGPU only guarantees sample counts of 1 and 4 for render attachments
+pub fn validate_sample_count(sample_count: u32) -> Result<u32, String>
+{
+    match sample_count
+    {
+        1 | 4 => Ok(sample_count),
+        _ => Err(format!("a sample count of {} isn't supported, only 1 and 4 are", sample_count)),
+    }
+}
+
+
+pub struct MultisampleTarget<B: Backend>
+{
+    format: TextureFormat,
+    sample_count: u32,
+    alpha_to_coverage: bool,
+    texture: Option<B::Texture>,
+    view: Option<B::TextureView>,
+}
+
+
+impl<B: Backend> MultisampleTarget<B>
+{
+    pub fn new(format: TextureFormat, sample_count: u32, alpha_to_coverage: bool) -> Result<Self, String>
+    {
+        let sample_count = validate_sample_count(sample_count)?;
+        // alpha to coverage is only valid for multisampled targets
+        if alpha_to_coverage && sample_count == 1
+        {
+            return Err("alpha to coverage needs a sample count of 4".to_owned());
+        }
+        Ok(MultisampleTarget { format, sample_count, alpha_to_coverage, texture: None, view: None })
+    }
+
+
+    pub fn sample_count(&self) -> u32
+    {
+        self.sample_count
+    }
+
+
+    pub fn multisample_state(&self) -> MultisampleState
+    {
+        let mut multisample_state = MultisampleState::new();
+        multisample_state
+            .count(self.sample_count)
+            .alpha_to_coverage_enabled(self.alpha_to_coverage);
+        multisample_state
+    }
+
+
+    // with a sample count of 1 we render straight into the canvas texture,
+    // otherwise into a multisampled texture that is resolved into the canvas texture
+    pub fn color_attachment(&mut self, backend: &B, canvas_texture: &B::Texture) -> RenderPassColorAttachment<B>
+    {
+        let canvas_view = backend.create_view(canvas_texture);
+        if self.sample_count == 1
+        {
+            return RenderPassColorAttachment::new(LoadOp::Clear, StoreOp::Store, &canvas_view);
+        }
+
+        let view = self.view(backend, backend.texture_size(canvas_texture));
+        let mut color_attachment = RenderPassColorAttachment::new(LoadOp::Clear, StoreOp::Discard, &view);
+        color_attachment.resolve_target(&canvas_view);
+        color_attachment
+    }
+
+
+    fn view(&mut self, backend: &B, size: [u32; 2]) -> B::TextureView
+    {
+        if let Some(texture) = &self.texture
+        {
+            if backend.texture_size(texture) != size
+            {
+                backend.destroy_texture(texture);
+                self.texture = None;
+                self.view = None;
+            }
+        }
+
+        if self.view.is_none()
+        {
+            let mut texture_descriptor = TextureDescriptor::new(self.format, size, RENDER_ATTACHMENT);
+            texture_descriptor
+                .label("multisample color target")
+                .sample_count(self.sample_count);
+            let texture = backend.create_texture(&texture_descriptor);
+            self.view = Some(backend.create_view(&texture));
+            self.texture = Some(texture);
+        }
+
+        self.view.clone().unwrap()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::recording::{Call, RecordingBackend};
+
+
+    #[test]
+    fn only_the_guaranteed_sample_counts_are_valid()
+    {
+        assert_eq!(validate_sample_count(1), Ok(1));
+        assert_eq!(validate_sample_count(4), Ok(4));
+        for sample_count in [0, 2, 3, 8, 16]
+        {
+            assert!(validate_sample_count(sample_count).is_err(), "{}", sample_count);
+            assert!(MultisampleTarget::<RecordingBackend>::new(TextureFormat::Bgra8Unorm, sample_count, false).is_err());
+        }
+    }
+
+
+    #[test]
+    fn alpha_to_coverage_needs_a_multisampled_target()
+    {
+        assert!(MultisampleTarget::<RecordingBackend>::new(TextureFormat::Bgra8Unorm, 1, true).is_err());
+
+        let target = MultisampleTarget::<RecordingBackend>::new(TextureFormat::Bgra8Unorm, 4, true).unwrap();
+        let multisample_state = target.multisample_state();
+        assert_eq!(multisample_state.count, 4);
+        assert!(multisample_state.alpha_to_coverage_enabled);
+    }
+
+
+    #[test]
+    fn multisampled_targets_resolve_into_the_canvas_texture()
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(
+            &TextureDescriptor::new(TextureFormat::Bgra8Unorm, [64, 32], RENDER_ATTACHMENT),
+        );
+        let mut single = MultisampleTarget::new(TextureFormat::Bgra8Unorm, 1, false).unwrap();
+        assert!(single.color_attachment(&backend, &canvas_texture).resolve_target.is_none());
+
+        let mut target = MultisampleTarget::new(TextureFormat::Bgra8Unorm, 4, false).unwrap();
+        let color_attachment = target.color_attachment(&backend, &canvas_texture);
+        assert!(color_attachment.resolve_target.is_some());
+        assert_eq!(color_attachment.store_op, StoreOp::Discard);
+        target.color_attachment(&backend, &canvas_texture);
+
+        // the multisampled texture is created once and reused while the canvas keeps its size
+        let multisampled = backend.calls().iter()
+            .filter(|call| matches!(call, Call::CreateTexture { descriptor, .. } if descriptor.sample_count == 4))
+            .count();
+        assert_eq!(multisampled, 1);
+    }
+}
@@ -0,0 +1,867 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::num::NonZeroU64;
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+
+use crate::*;
+
+
+// renders with wgpu on the machine's own gpu, without a canvas. the scenes draw into textures created
+// through the backend, which can then be read back or saved as png files
+#[derive(Clone, Debug)]
+pub struct WgpuBackend
+{
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+
+impl WgpuBackend
+{
+    // blocks until a device is created, fails if the machine has no usable adapter
+    pub fn new() -> Result<Self, String>
+    {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok_or("no gpu adapter found")?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .map_err(|error| format!("failed to create a device: {}", error))?;
+        Ok(WgpuBackend::from_device(device, queue))
+    }
+
+
+    // for desktop tools that set up their own device, e.g. for a window surface
+    pub fn from_device(device: wgpu::Device, queue: wgpu::Queue) -> Self
+    {
+        WgpuBackend { device: Arc::new(device), queue: Arc::new(queue) }
+    }
+
+
+    pub fn device(&self) -> &wgpu::Device
+    {
+        &self.device
+    }
+
+
+    pub fn queue(&self) -> &wgpu::Queue
+    {
+        &self.queue
+    }
+
+
+    // copies the first mip level of a texture back into memory, rows tightly packed
+    pub fn read_texture(&self, texture: &wgpu::Texture) -> Result<Vec<u8>, String>
+    {
+        let bytes_per_pixel = texture.format().block_copy_size(None)
+            .ok_or_else(|| format!("{:?} textures can't be copied", texture.format()))?;
+        let size = texture.size();
+        let unpadded_bytes_per_row = size.width * bytes_per_pixel;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor
+        {
+            label: Some("texture readback"),
+            size: (bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer
+            {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(bytes_per_row), rows_per_image: None },
+            },
+            wgpu::Extent3d { depth_or_array_layers: 1, ..size },
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| sender.send(result).unwrap_or(()));
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()
+            .map_err(|error| error.to_string())?
+            .map_err(|error| format!("failed to map the readback buffer: {}", error))?;
+
+        let pixels = slice.get_mapped_range()
+            .chunks(bytes_per_row as usize)
+            .flat_map(|row| row[..unpadded_bytes_per_row as usize].to_vec())
+            .collect();
+        buffer.unmap();
+        Ok(pixels)
+    }
+
+
+    // None for the "auto" layout
+    fn pipeline_layout(
+        &self, bind_group_layouts: &Option<Vec<Arc<wgpu::BindGroupLayout>>>,
+    )
+        -> Option<wgpu::PipelineLayout>
+    {
+        bind_group_layouts.as_ref().map(|bind_group_layouts|
+        {
+            let bind_group_layouts = bind_group_layouts.iter().map(|layout| layout.as_ref()).collect::<Vec<_>>();
+            self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor
+            {
+                label: None,
+                bind_group_layouts: &bind_group_layouts,
+                push_constant_ranges: &[],
+            })
+        })
+    }
+
+
+    // only 8 bit rgba and bgra textures can be saved
+    pub fn save_png(&self, texture: &wgpu::Texture, path: &Path) -> Result<(), String>
+    {
+        let mut pixels = self.read_texture(texture)?;
+        match texture.format()
+        {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => (),
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb =>
+            {
+                pixels.chunks_mut(4).for_each(|pixel| pixel.swap(0, 2));
+            },
+            format => return Err(format!("{:?} textures can't be saved as png", format)),
+        }
+
+        write_png(path, texture.width(), texture.height(), &pixels)
+    }
+}
+
+
+// rgba pixels, 8 bits per channel
+pub fn write_png(path: &Path, width: u32, height: u32, pixels: &[u8]) -> Result<(), String>
+{
+    let file = File::create(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+
+fn texture_format(format: TextureFormat) -> wgpu::TextureFormat
+{
+    match format
+    {
+        TextureFormat::R8Unorm => wgpu::TextureFormat::R8Unorm,
+        TextureFormat::R32Float => wgpu::TextureFormat::R32Float,
+        TextureFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+        TextureFormat::Rgba8UnormSrgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Bgra8Unorm => wgpu::TextureFormat::Bgra8Unorm,
+        TextureFormat::Bgra8UnormSrgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+        TextureFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+        TextureFormat::Rgba32Float => wgpu::TextureFormat::Rgba32Float,
+        TextureFormat::Depth24Plus => wgpu::TextureFormat::Depth24Plus,
+        TextureFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+        TextureFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
+        TextureFormat::Depth32FloatStencil8 => wgpu::TextureFormat::Depth32FloatStencil8,
+    }
+}
+
+
+fn vertex_format(format: VertexFormat) -> wgpu::VertexFormat
+{
+    match format
+    {
+        VertexFormat::Uint8x2 => wgpu::VertexFormat::Uint8x2,
+        VertexFormat::Uint8x4 => wgpu::VertexFormat::Uint8x4,
+        VertexFormat::Sint8x2 => wgpu::VertexFormat::Sint8x2,
+        VertexFormat::Sint8x4 => wgpu::VertexFormat::Sint8x4,
+        VertexFormat::Unorm8x2 => wgpu::VertexFormat::Unorm8x2,
+        VertexFormat::Unorm8x4 => wgpu::VertexFormat::Unorm8x4,
+        VertexFormat::Snorm8x2 => wgpu::VertexFormat::Snorm8x2,
+        VertexFormat::Snorm8x4 => wgpu::VertexFormat::Snorm8x4,
+        VertexFormat::Uint16x2 => wgpu::VertexFormat::Uint16x2,
+        VertexFormat::Uint16x4 => wgpu::VertexFormat::Uint16x4,
+        VertexFormat::Sint16x2 => wgpu::VertexFormat::Sint16x2,
+        VertexFormat::Sint16x4 => wgpu::VertexFormat::Sint16x4,
+        VertexFormat::Unorm16x2 => wgpu::VertexFormat::Unorm16x2,
+        VertexFormat::Unorm16x4 => wgpu::VertexFormat::Unorm16x4,
+        VertexFormat::Snorm16x2 => wgpu::VertexFormat::Snorm16x2,
+        VertexFormat::Snorm16x4 => wgpu::VertexFormat::Snorm16x4,
+        VertexFormat::Float16x2 => wgpu::VertexFormat::Float16x2,
+        VertexFormat::Float16x4 => wgpu::VertexFormat::Float16x4,
+        VertexFormat::Float32 => wgpu::VertexFormat::Float32,
+        VertexFormat::Float32x2 => wgpu::VertexFormat::Float32x2,
+        VertexFormat::Float32x3 => wgpu::VertexFormat::Float32x3,
+        VertexFormat::Float32x4 => wgpu::VertexFormat::Float32x4,
+        VertexFormat::Uint32 => wgpu::VertexFormat::Uint32,
+        VertexFormat::Uint32x2 => wgpu::VertexFormat::Uint32x2,
+        VertexFormat::Uint32x3 => wgpu::VertexFormat::Uint32x3,
+        VertexFormat::Uint32x4 => wgpu::VertexFormat::Uint32x4,
+        VertexFormat::Sint32 => wgpu::VertexFormat::Sint32,
+        VertexFormat::Sint32x2 => wgpu::VertexFormat::Sint32x2,
+        VertexFormat::Sint32x3 => wgpu::VertexFormat::Sint32x3,
+        VertexFormat::Sint32x4 => wgpu::VertexFormat::Sint32x4,
+    }
+}
+
+
+fn index_format(format: IndexFormat) -> wgpu::IndexFormat
+{
+    match format
+    {
+        IndexFormat::Uint16 => wgpu::IndexFormat::Uint16,
+        IndexFormat::Uint32 => wgpu::IndexFormat::Uint32,
+    }
+}
+
+
+fn compare_function(compare: CompareFunction) -> wgpu::CompareFunction
+{
+    match compare
+    {
+        CompareFunction::Never => wgpu::CompareFunction::Never,
+        CompareFunction::Less => wgpu::CompareFunction::Less,
+        CompareFunction::Equal => wgpu::CompareFunction::Equal,
+        CompareFunction::LessEqual => wgpu::CompareFunction::LessEqual,
+        CompareFunction::Greater => wgpu::CompareFunction::Greater,
+        CompareFunction::NotEqual => wgpu::CompareFunction::NotEqual,
+        CompareFunction::GreaterEqual => wgpu::CompareFunction::GreaterEqual,
+        CompareFunction::Always => wgpu::CompareFunction::Always,
+    }
+}
+
+
+fn blend_factor(factor: BlendFactor) -> wgpu::BlendFactor
+{
+    match factor
+    {
+        BlendFactor::Zero => wgpu::BlendFactor::Zero,
+        BlendFactor::One => wgpu::BlendFactor::One,
+        BlendFactor::Src => wgpu::BlendFactor::Src,
+        BlendFactor::OneMinusSrc => wgpu::BlendFactor::OneMinusSrc,
+        BlendFactor::SrcAlpha => wgpu::BlendFactor::SrcAlpha,
+        BlendFactor::OneMinusSrcAlpha => wgpu::BlendFactor::OneMinusSrcAlpha,
+        BlendFactor::Dst => wgpu::BlendFactor::Dst,
+        BlendFactor::OneMinusDst => wgpu::BlendFactor::OneMinusDst,
+        BlendFactor::DstAlpha => wgpu::BlendFactor::DstAlpha,
+        BlendFactor::OneMinusDstAlpha => wgpu::BlendFactor::OneMinusDstAlpha,
+        BlendFactor::SrcAlphaSaturated => wgpu::BlendFactor::SrcAlphaSaturated,
+        BlendFactor::Constant => wgpu::BlendFactor::Constant,
+        BlendFactor::OneMinusConstant => wgpu::BlendFactor::OneMinusConstant,
+    }
+}
+
+
+fn blend_component(component: &BlendComponent) -> wgpu::BlendComponent
+{
+    let operation = match component.operation
+    {
+        BlendOperation::Add => wgpu::BlendOperation::Add,
+        BlendOperation::Subtract => wgpu::BlendOperation::Subtract,
+        BlendOperation::ReverseSubtract => wgpu::BlendOperation::ReverseSubtract,
+        BlendOperation::Min => wgpu::BlendOperation::Min,
+        BlendOperation::Max => wgpu::BlendOperation::Max,
+    };
+    wgpu::BlendComponent
+    {
+        src_factor: blend_factor(component.src_factor),
+        dst_factor: blend_factor(component.dst_factor),
+        operation,
+    }
+}
+
+
+fn address_mode(address_mode: AddressMode) -> wgpu::AddressMode
+{
+    match address_mode
+    {
+        AddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        AddressMode::Repeat => wgpu::AddressMode::Repeat,
+        AddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+    }
+}
+
+
+fn filter_mode(filter: FilterMode) -> wgpu::FilterMode
+{
+    match filter
+    {
+        FilterMode::Nearest => wgpu::FilterMode::Nearest,
+        FilterMode::Linear => wgpu::FilterMode::Linear,
+    }
+}
+
+
+fn store_op(store_op: StoreOp) -> wgpu::StoreOp
+{
+    match store_op
+    {
+        StoreOp::Store => wgpu::StoreOp::Store,
+        StoreOp::Discard => wgpu::StoreOp::Discard,
+    }
+}
+
+
+fn bind_group_layout_entry(entry: &BindGroupLayoutEntry) -> wgpu::BindGroupLayoutEntry
+{
+    wgpu::BindGroupLayoutEntry
+    {
+        binding: entry.binding,
+        // the stage bits are the same as WebGPU's
+        visibility: wgpu::ShaderStages::from_bits_truncate(entry.visibility),
+        ty: match entry.ty
+        {
+            BindingType::Buffer { ty, has_dynamic_offset } => wgpu::BindingType::Buffer
+            {
+                ty: match ty
+                {
+                    BufferBindingType::Uniform => wgpu::BufferBindingType::Uniform,
+                    BufferBindingType::Storage => wgpu::BufferBindingType::Storage { read_only: false },
+                    BufferBindingType::ReadOnlyStorage => wgpu::BufferBindingType::Storage { read_only: true },
+                },
+                has_dynamic_offset,
+                min_binding_size: None,
+            },
+            BindingType::Sampler => wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            BindingType::Texture => wgpu::BindingType::Texture
+            {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+        },
+        count: None,
+    }
+}
+
+
+fn primitive_state(primitive: &PrimitiveState) -> wgpu::PrimitiveState
+{
+    wgpu::PrimitiveState
+    {
+        topology: match primitive.topology
+        {
+            PrimitiveTopology::PointList => wgpu::PrimitiveTopology::PointList,
+            PrimitiveTopology::LineList => wgpu::PrimitiveTopology::LineList,
+            PrimitiveTopology::LineStrip => wgpu::PrimitiveTopology::LineStrip,
+            PrimitiveTopology::TriangleList => wgpu::PrimitiveTopology::TriangleList,
+            PrimitiveTopology::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
+        },
+        front_face: match primitive.front_face
+        {
+            FrontFace::Ccw => wgpu::FrontFace::Ccw,
+            FrontFace::Cw => wgpu::FrontFace::Cw,
+        },
+        cull_mode: match primitive.cull_mode
+        {
+            CullMode::None => None,
+            CullMode::Front => Some(wgpu::Face::Front),
+            CullMode::Back => Some(wgpu::Face::Back),
+        },
+        ..Default::default()
+    }
+}
+
+
+impl Backend for WgpuBackend
+{
+    type Buffer = Arc<wgpu::Buffer>;
+    type Texture = Arc<wgpu::Texture>;
+    type TextureView = Arc<wgpu::TextureView>;
+    type Sampler = Arc<wgpu::Sampler>;
+    type ShaderModule = Arc<wgpu::ShaderModule>;
+    type RenderPipeline = Arc<wgpu::RenderPipeline>;
+    type ComputePipeline = Arc<wgpu::ComputePipeline>;
+    type BindGroupLayout = Arc<wgpu::BindGroupLayout>;
+    type BindGroup = Arc<wgpu::BindGroup>;
+
+
+    fn max_texture_dimension_2d(&self) -> u32
+    {
+        self.device.limits().max_texture_dimension_2d
+    }
+
+
+    fn limits(&self) -> Limits
+    {
+        let limits = self.device.limits();
+        Limits
+        {
+            min_uniform_buffer_offset_alignment: limits.min_uniform_buffer_offset_alignment,
+            max_compute_workgroups_per_dimension: limits.max_compute_workgroups_per_dimension,
+            max_compute_invocations_per_workgroup: limits.max_compute_invocations_per_workgroup,
+            max_compute_workgroup_size_x: limits.max_compute_workgroup_size_x,
+        }
+    }
+
+
+    fn has_feature(&self, feature: &str) -> bool
+    {
+        let features = self.device.features();
+        match feature
+        {
+            "shader-f16" => features.contains(wgpu::Features::SHADER_F16),
+            "depth32float-stencil8" => features.contains(wgpu::Features::DEPTH32FLOAT_STENCIL8),
+            _ => false,
+        }
+    }
+
+
+    fn create_buffer(&self, descriptor: &BufferDescriptor) -> Self::Buffer
+    {
+        Arc::new(self.device.create_buffer(&wgpu::BufferDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            size: descriptor.size,
+            // the usage bits are the same as WebGPU's
+            usage: wgpu::BufferUsages::from_bits_truncate(descriptor.usage),
+            mapped_at_creation: descriptor.mapped_at_creation,
+        }))
+    }
+
+
+    fn write_buffer(&self, buffer: &Self::Buffer, offset: u64, data: &[u8])
+    {
+        self.queue.write_buffer(buffer, offset, data);
+    }
+
+
+    fn destroy_buffer(&self, buffer: &Self::Buffer)
+    {
+        buffer.destroy();
+    }
+
+
+    // blocks until the device is done with the buffer, the returned future is ready right away
+    fn read_buffer(&self, buffer: &Self::Buffer, offset: u64, size: u64) -> BufferRead
+    {
+        let slice = buffer.slice(offset..offset + size);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| sender.send(result).unwrap_or(()));
+        self.device.poll(wgpu::Maintain::Wait);
+        let bytes = receiver.recv()
+            .map_err(|error| error.to_string())
+            .and_then(|result| result.map_err(|error| format!("failed to map the buffer: {}", error)))
+            .map(|()|
+            {
+                let bytes = slice.get_mapped_range().to_vec();
+                buffer.unmap();
+                bytes
+            });
+        Box::pin(std::future::ready(bytes))
+    }
+
+
+    fn create_texture(&self, descriptor: &TextureDescriptor) -> Self::Texture
+    {
+        let [width, height] = descriptor.size;
+        Arc::new(self.device.create_texture(&wgpu::TextureDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: descriptor.mip_level_count,
+            sample_count: descriptor.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture_format(descriptor.format),
+            usage: wgpu::TextureUsages::from_bits_truncate(descriptor.usage),
+            view_formats: &[],
+        }))
+    }
+
+
+    fn write_texture(&self, texture: &Self::Texture, mip_level: u32, data: &[u8], bytes_per_row: u32, size: [u32; 2])
+    {
+        let [width, height] = size;
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture { mip_level, ..texture.as_image_copy() },
+            data,
+            wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(bytes_per_row), rows_per_image: None },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+    }
+
+
+    fn texture_size(&self, texture: &Self::Texture) -> [u32; 2]
+    {
+        [texture.width(), texture.height()]
+    }
+
+
+    fn create_view(&self, texture: &Self::Texture) -> Self::TextureView
+    {
+        Arc::new(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+
+    fn destroy_texture(&self, texture: &Self::Texture)
+    {
+        texture.destroy();
+    }
+
+
+    fn create_sampler(&self, descriptor: &SamplerDescriptor) -> Self::Sampler
+    {
+        Arc::new(self.device.create_sampler(&wgpu::SamplerDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            address_mode_u: address_mode(descriptor.address_mode_u),
+            address_mode_v: address_mode(descriptor.address_mode_v),
+            address_mode_w: address_mode(descriptor.address_mode_w),
+            mag_filter: filter_mode(descriptor.mag_filter),
+            min_filter: filter_mode(descriptor.min_filter),
+            mipmap_filter: filter_mode(descriptor.mipmap_filter),
+            compare: descriptor.compare.map(compare_function),
+            ..Default::default()
+        }))
+    }
+
+
+    fn create_shader_module(&self, descriptor: &ShaderModuleDescriptor) -> Self::ShaderModule
+    {
+        Arc::new(self.device.create_shader_module(wgpu::ShaderModuleDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            source: wgpu::ShaderSource::Wgsl(descriptor.code.as_str().into()),
+        }))
+    }
+
+
+    fn create_render_pipeline(&self, descriptor: &RenderPipelineDescriptor<Self>) -> Self::RenderPipeline
+    {
+        let attributes = descriptor.vertex.buffers.iter()
+            .map(|layout| layout.attributes.iter()
+                .map(|attribute| wgpu::VertexAttribute
+                {
+                    format: vertex_format(attribute.format),
+                    offset: attribute.offset,
+                    shader_location: attribute.shader_location,
+                })
+                .collect::<Vec<_>>()
+            )
+            .collect::<Vec<_>>();
+        let buffers = descriptor.vertex.buffers.iter().zip(&attributes)
+            .map(|(layout, attributes)| wgpu::VertexBufferLayout
+            {
+                array_stride: layout.array_stride,
+                step_mode: match layout.step_mode
+                {
+                    VertexStepMode::Vertex => wgpu::VertexStepMode::Vertex,
+                    VertexStepMode::Instance => wgpu::VertexStepMode::Instance,
+                },
+                attributes,
+            })
+            .collect::<Vec<_>>();
+
+        let targets = descriptor.fragment.iter()
+            .flat_map(|fragment| &fragment.targets)
+            .map(|target| Some(wgpu::ColorTargetState
+            {
+                format: texture_format(target.format),
+                blend: target.blend.map(|blend| wgpu::BlendState
+                {
+                    color: blend_component(&blend.color),
+                    alpha: blend_component(&blend.alpha),
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            }))
+            .collect::<Vec<_>>();
+
+        let layout = self.pipeline_layout(&descriptor.bind_group_layouts);
+        Arc::new(self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            layout: layout.as_ref(),
+            vertex: wgpu::VertexState
+            {
+                module: &descriptor.vertex.module,
+                entry_point: &descriptor.vertex.entry_point,
+                buffers: &buffers,
+            },
+            primitive: primitive_state(&descriptor.primitive),
+            depth_stencil: descriptor.depth_stencil.map(|depth_stencil| wgpu::DepthStencilState
+            {
+                format: texture_format(depth_stencil.format),
+                depth_write_enabled: depth_stencil.depth_write_enabled,
+                depth_compare: compare_function(depth_stencil.depth_compare),
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState
+            {
+                count: descriptor.multisample.count,
+                mask: !0,
+                alpha_to_coverage_enabled: descriptor.multisample.alpha_to_coverage_enabled,
+            },
+            fragment: descriptor.fragment.as_ref().map(|fragment| wgpu::FragmentState
+            {
+                module: &fragment.module,
+                entry_point: &fragment.entry_point,
+                targets: &targets,
+            }),
+            multiview: None,
+        }))
+    }
+
+
+    fn create_compute_pipeline(&self, descriptor: &ComputePipelineDescriptor<Self>) -> Self::ComputePipeline
+    {
+        let layout = self.pipeline_layout(&descriptor.bind_group_layouts);
+        Arc::new(self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            layout: layout.as_ref(),
+            module: &descriptor.module,
+            entry_point: &descriptor.entry_point,
+        }))
+    }
+
+
+    fn render_bind_group_layout(&self, pipeline: &Self::RenderPipeline, index: u32) -> Self::BindGroupLayout
+    {
+        Arc::new(pipeline.get_bind_group_layout(index))
+    }
+
+
+    fn compute_bind_group_layout(&self, pipeline: &Self::ComputePipeline, index: u32) -> Self::BindGroupLayout
+    {
+        Arc::new(pipeline.get_bind_group_layout(index))
+    }
+
+
+    fn create_bind_group_layout(&self, descriptor: &BindGroupLayoutDescriptor) -> Self::BindGroupLayout
+    {
+        let entries = descriptor.entries.iter().map(bind_group_layout_entry).collect::<Vec<_>>();
+        Arc::new(self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            entries: &entries,
+        }))
+    }
+
+
+    fn create_bind_group(&self, descriptor: &BindGroupDescriptor<Self>) -> Self::BindGroup
+    {
+        let entries = descriptor.entries.iter()
+            .map(|entry| wgpu::BindGroupEntry
+            {
+                binding: entry.binding,
+                resource: match &entry.resource
+                {
+                    BindingResource::Buffer { buffer, offset, size } => wgpu::BindingResource::Buffer(wgpu::BufferBinding
+                    {
+                        buffer, offset: *offset, size: size.and_then(NonZeroU64::new),
+                    }),
+                    BindingResource::Sampler(sampler) => wgpu::BindingResource::Sampler(sampler),
+                    BindingResource::TextureView(view) => wgpu::BindingResource::TextureView(view),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        Arc::new(self.device.create_bind_group(&wgpu::BindGroupDescriptor
+        {
+            label: descriptor.label.as_deref(),
+            layout: &descriptor.layout,
+            entries: &entries,
+        }))
+    }
+
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer<Self>>)
+    {
+        let command_buffers = command_buffers.iter()
+            .map(|command_buffer|
+            {
+                let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor
+                {
+                    label: command_buffer.label.as_deref(),
+                });
+                encode(&mut encoder, &command_buffer.commands);
+                encoder.finish()
+            })
+            .collect::<Vec<_>>();
+        self.queue.submit(command_buffers);
+    }
+}
+
+
+// wgpu passes borrow everything they use, so each pass is encoded in one go from its slice of commands
+fn encode(encoder: &mut wgpu::CommandEncoder, commands: &[Command<WgpuBackend>])
+{
+    let pass_end = |start: usize, end_command: fn(&Command<WgpuBackend>) -> bool|
+        commands[start..].iter().position(end_command).map(|end| start + end).unwrap_or(commands.len());
+
+    let mut index = 0;
+    while index < commands.len()
+    {
+        match &commands[index]
+        {
+            Command::BeginRenderPass(descriptor) =>
+            {
+                let end = pass_end(index, |command| matches!(command, Command::EndRenderPass));
+                encode_render_pass(encoder, descriptor, &commands[index + 1..end]);
+                index = end;
+            },
+            Command::BeginComputePass { label } =>
+            {
+                let end = pass_end(index, |command| matches!(command, Command::EndComputePass));
+                encode_compute_pass(encoder, label.as_deref(), &commands[index + 1..end]);
+                index = end;
+            },
+            Command::CopyBufferToBuffer { source, source_offset, destination, destination_offset, size } =>
+            {
+                encoder.copy_buffer_to_buffer(source, *source_offset, destination, *destination_offset, *size);
+            },
+            _ => (),
+        }
+        index += 1;
+    }
+}
+
+
+fn encode_render_pass(
+    encoder: &mut wgpu::CommandEncoder, descriptor: &RenderPassDescriptor<WgpuBackend>, commands: &[Command<WgpuBackend>],
+)
+{
+    let color_attachments = descriptor.color_attachments.iter()
+        .map(|attachment|
+        {
+            let [r, g, b, a] = attachment.clear_value;
+            Some(wgpu::RenderPassColorAttachment
+            {
+                view: &attachment.view,
+                resolve_target: attachment.resolve_target.as_deref(),
+                ops: wgpu::Operations
+                {
+                    load: match attachment.load_op
+                    {
+                        LoadOp::Load => wgpu::LoadOp::Load,
+                        LoadOp::Clear => wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a }),
+                    },
+                    store: store_op(attachment.store_op),
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let depth_stencil_attachment = descriptor.depth_stencil_attachment.as_ref()
+        .map(|attachment| wgpu::RenderPassDepthStencilAttachment
+        {
+            view: &attachment.view,
+            depth_ops: Some(wgpu::Operations
+            {
+                load: match attachment.depth_load_op
+                {
+                    LoadOp::Load => wgpu::LoadOp::Load,
+                    LoadOp::Clear => wgpu::LoadOp::Clear(attachment.depth_clear_value),
+                },
+                store: store_op(attachment.depth_store_op),
+            }),
+            stencil_ops: attachment.stencil_ops.map(|(stencil_load_op, stencil_store_op)| wgpu::Operations
+            {
+                load: match stencil_load_op
+                {
+                    LoadOp::Load => wgpu::LoadOp::Load,
+                    LoadOp::Clear => wgpu::LoadOp::Clear(attachment.stencil_clear_value),
+                },
+                store: store_op(stencil_store_op),
+            }),
+        });
+
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor
+    {
+        label: descriptor.label.as_deref(),
+        color_attachments: &color_attachments,
+        depth_stencil_attachment,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    for command in commands
+    {
+        match command
+        {
+            Command::SetPipeline(pipeline) => pass.set_pipeline(pipeline),
+            Command::SetBindGroup { index, bind_group, dynamic_offsets } =>
+            {
+                pass.set_bind_group(*index, bind_group, dynamic_offsets);
+            },
+            Command::SetVertexBuffer { slot, buffer, offset, size } => match size
+            {
+                Some(size) => pass.set_vertex_buffer(*slot, buffer.slice(*offset..*offset + *size)),
+                None => pass.set_vertex_buffer(*slot, buffer.slice(*offset..)),
+            },
+            Command::SetIndexBuffer { buffer, format, offset } =>
+            {
+                pass.set_index_buffer(buffer.slice(*offset..), index_format(*format));
+            },
+            Command::SetViewport { x, y, width, height, min_depth, max_depth } =>
+            {
+                pass.set_viewport(*x, *y, *width, *height, *min_depth, *max_depth);
+            },
+            Command::SetScissorRect { x, y, width, height } => pass.set_scissor_rect(*x, *y, *width, *height),
+            Command::SetBlendConstant([r, g, b, a]) => pass.set_blend_constant(wgpu::Color { r: *r, g: *g, b: *b, a: *a }),
+            Command::Draw { vertex_count, instance_count, first_vertex, first_instance } =>
+            {
+                pass.draw(*first_vertex..first_vertex + vertex_count, *first_instance..first_instance + instance_count);
+            },
+            Command::DrawIndexed { index_count, instance_count, first_index, base_vertex, first_instance } =>
+            {
+                pass.draw_indexed(
+                    *first_index..first_index + index_count, *base_vertex, *first_instance..first_instance + instance_count,
+                );
+            },
+            Command::DrawIndexedIndirect { buffer, offset } => pass.draw_indexed_indirect(buffer, *offset),
+            _ => (),
+        }
+    }
+}
+
+
+fn encode_compute_pass(encoder: &mut wgpu::CommandEncoder, label: Option<&str>, commands: &[Command<WgpuBackend>])
+{
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label, timestamp_writes: None });
+    for command in commands
+    {
+        match command
+        {
+            Command::SetComputePipeline(pipeline) => pass.set_pipeline(pipeline),
+            Command::SetBindGroup { index, bind_group, dynamic_offsets } =>
+            {
+                pass.set_bind_group(*index, bind_group, dynamic_offsets);
+            },
+            Command::DispatchWorkgroups { x, y, z } => pass.dispatch_workgroups(*x, *y, *z),
+            _ => (),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    // run with cargo test --features wgpu -- --ignored on a machine with an adapter
+    #[test]
+    #[ignore = "needs a gpu adapter"]
+    fn clears_an_offscreen_texture()
+    {
+        let backend = WgpuBackend::new().unwrap();
+
+        let texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [8, 4], texture_usage::RENDER_ATTACHMENT | texture_usage::COPY_SRC,
+        ));
+        let mut color_attachment = RenderPassColorAttachment::new(LoadOp::Clear, StoreOp::Store, &backend.create_view(&texture));
+        color_attachment.clear_value([1.0, 0.0, 1.0, 1.0]);
+
+        let mut encoder = CommandEncoder::new();
+        encoder.begin_render_pass(&RenderPassDescriptor::new(vec![color_attachment])).end();
+        backend.submit(vec![encoder.finish()]);
+
+        let pixels = backend.read_texture(&texture).unwrap();
+        assert_eq!(pixels.len(), 8 * 4 * 4);
+        assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 255, 255]));
+    }
+}
@@ -0,0 +1,414 @@
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde::{Serialize, Deserialize};
+
+use crate::*;
+
+
+// every object created by the recording backend is a number, unique across all kinds of objects
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Handle(pub u32);
+
+
+// backends whose objects are all handles, their descriptors and commands can be serialized
+pub trait HandleBackend: Backend<
+    Buffer = Handle, Texture = Handle, TextureView = Handle, Sampler = Handle, ShaderModule = Handle,
+    RenderPipeline = Handle, ComputePipeline = Handle, BindGroupLayout = Handle, BindGroup = Handle,
+>
+{
+}
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Call
+{
+    CreateBuffer { buffer: Handle, descriptor: BufferDescriptor },
+    WriteBuffer { buffer: Handle, offset: u64, data: Vec<u8> },
+    DestroyBuffer(Handle),
+    CreateTexture { texture: Handle, descriptor: TextureDescriptor },
+    WriteTexture { texture: Handle, mip_level: u32, data: Vec<u8>, bytes_per_row: u32, size: [u32; 2] },
+    CreateView { view: Handle, texture: Handle },
+    DestroyTexture(Handle),
+    CreateSampler { sampler: Handle, descriptor: SamplerDescriptor },
+    CreateShaderModule { module: Handle, descriptor: ShaderModuleDescriptor },
+    CreateRenderPipeline { pipeline: Handle, descriptor: RenderPipelineDescriptor<RecordingBackend> },
+    CreateComputePipeline { pipeline: Handle, descriptor: ComputePipelineDescriptor<RecordingBackend> },
+    GetBindGroupLayout { layout: Handle, pipeline: Handle, index: u32 },
+    CreateBindGroupLayout { layout: Handle, descriptor: BindGroupLayoutDescriptor },
+    CreateBindGroup { bind_group: Handle, descriptor: BindGroupDescriptor<RecordingBackend> },
+    Submit(Vec<CommandBuffer<RecordingBackend>>),
+}
+
+
+#[derive(Debug)]
+struct Recording
+{
+    calls: Vec<Call>,
+    next_handle: u32,
+    buffers: HashMap<Handle, Vec<u8>>,
+    textures: HashMap<Handle, TextureDescriptor>,
+    max_texture_dimension_2d: u32,
+    limits: Limits,
+    features: Vec<String>,
+}
+
+
+// keeps every call and the contents every buffer would have on a device, so tests can check what a
+// scene uploads and encodes. clones share the same recording, like clones of a GpuDevice
+#[derive(Clone, Debug)]
+pub struct RecordingBackend
+{
+    recording: Rc<RefCell<Recording>>,
+}
+
+
+impl Default for RecordingBackend
+{
+    fn default() -> Self
+    {
+        RecordingBackend::new()
+    }
+}
+
+
+impl RecordingBackend
+{
+    pub fn new() -> Self
+    {
+        let recording = Recording
+        {
+            calls: Vec::new(),
+            next_handle: 0,
+            buffers: HashMap::new(),
+            textures: HashMap::new(),
+            // the default limit of WebGPU
+            max_texture_dimension_2d: 8192,
+            limits: Limits::default(),
+            features: Vec::new(),
+        };
+        RecordingBackend { recording: Rc::new(RefCell::new(recording)) }
+    }
+
+
+    pub fn set_max_texture_dimension_2d(&self, max_texture_dimension_2d: u32)
+    {
+        self.recording.borrow_mut().max_texture_dimension_2d = max_texture_dimension_2d;
+    }
+
+
+    pub fn set_limits(&self, limits: Limits)
+    {
+        self.recording.borrow_mut().limits = limits;
+    }
+
+
+    // no features are enabled by default, like on a device that wasn't asked for any
+    pub fn enable_feature(&self, feature: &str)
+    {
+        self.recording.borrow_mut().features.push(feature.to_owned());
+    }
+
+
+    pub fn calls(&self) -> Ref<'_, [Call]>
+    {
+        Ref::map(self.recording.borrow(), |recording| recording.calls.as_slice())
+    }
+
+
+    // hands over what was recorded so far, e.g. to look at a single frame after setting a scene up
+    pub fn take_calls(&self) -> Vec<Call>
+    {
+        std::mem::take(&mut self.recording.borrow_mut().calls)
+    }
+
+
+    // drops calls that are no longer needed, e.g. uploads whose effect is already in the buffer contents
+    pub fn retain_calls(&self, keep: impl FnMut(&Call) -> bool)
+    {
+        self.recording.borrow_mut().calls.retain(keep);
+    }
+
+
+    // the commands of every submitted command buffer, in submission order
+    pub fn commands(&self) -> Vec<Command<RecordingBackend>>
+    {
+        self.calls().iter()
+            .filter_map(|call| match call
+            {
+                Call::Submit(command_buffers) => Some(command_buffers),
+                _ => None,
+            })
+            .flatten()
+            .flat_map(|command_buffer| command_buffer.commands.iter().cloned())
+            .collect()
+    }
+
+
+    pub fn buffer_contents(&self, buffer: &Handle) -> Option<Vec<u8>>
+    {
+        self.recording.borrow().buffers.get(buffer).cloned()
+    }
+
+
+    pub fn texture_descriptor(&self, texture: &Handle) -> Option<TextureDescriptor>
+    {
+        self.recording.borrow().textures.get(texture).cloned()
+    }
+
+
+    fn record(&self, create: impl FnOnce(Handle) -> Call) -> Handle
+    {
+        let mut recording = self.recording.borrow_mut();
+        let handle = Handle(recording.next_handle);
+        recording.next_handle += 1;
+        recording.calls.push(create(handle));
+        handle
+    }
+}
+
+
+impl HandleBackend for RecordingBackend
+{
+}
+
+
+impl Backend for RecordingBackend
+{
+    type Buffer = Handle;
+    type Texture = Handle;
+    type TextureView = Handle;
+    type Sampler = Handle;
+    type ShaderModule = Handle;
+    type RenderPipeline = Handle;
+    type ComputePipeline = Handle;
+    type BindGroupLayout = Handle;
+    type BindGroup = Handle;
+
+
+    fn max_texture_dimension_2d(&self) -> u32
+    {
+        self.recording.borrow().max_texture_dimension_2d
+    }
+
+
+    fn limits(&self) -> Limits
+    {
+        self.recording.borrow().limits
+    }
+
+
+    fn has_feature(&self, feature: &str) -> bool
+    {
+        self.recording.borrow().features.iter().any(|enabled| enabled == feature)
+    }
+
+
+    fn create_buffer(&self, descriptor: &BufferDescriptor) -> Handle
+    {
+        let buffer = self.record(|buffer| Call::CreateBuffer { buffer, descriptor: descriptor.clone() });
+        self.recording.borrow_mut().buffers.insert(buffer, vec![0; descriptor.size as usize]);
+        buffer
+    }
+
+
+    // writes past the end of a buffer are recorded but, as on a device, don't change it
+    fn write_buffer(&self, buffer: &Handle, offset: u64, data: &[u8])
+    {
+        let mut recording = self.recording.borrow_mut();
+        recording.calls.push(Call::WriteBuffer { buffer: *buffer, offset, data: data.to_vec() });
+        if let Some(contents) = recording.buffers.get_mut(buffer)
+        {
+            let start = offset as usize;
+            if let Some(destination) = contents.get_mut(start..start + data.len())
+            {
+                destination.copy_from_slice(data);
+            }
+        }
+    }
+
+
+    fn destroy_buffer(&self, buffer: &Handle)
+    {
+        self.recording.borrow_mut().calls.push(Call::DestroyBuffer(*buffer));
+    }
+
+
+    // reads what the recorded writes and copies left in the buffer, right away
+    fn read_buffer(&self, buffer: &Handle, offset: u64, size: u64) -> BufferRead
+    {
+        let contents = self.recording.borrow().buffers.get(buffer)
+            .and_then(|contents| contents.get(offset as usize..(offset + size) as usize))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| format!("{:?} has no bytes {}..{}", buffer, offset, offset + size));
+        Box::pin(std::future::ready(contents))
+    }
+
+
+    fn create_texture(&self, descriptor: &TextureDescriptor) -> Handle
+    {
+        let texture = self.record(|texture| Call::CreateTexture { texture, descriptor: descriptor.clone() });
+        self.recording.borrow_mut().textures.insert(texture, descriptor.clone());
+        texture
+    }
+
+
+    fn write_texture(&self, texture: &Handle, mip_level: u32, data: &[u8], bytes_per_row: u32, size: [u32; 2])
+    {
+        self.recording.borrow_mut().calls.push(Call::WriteTexture
+        {
+            texture: *texture, mip_level, data: data.to_vec(), bytes_per_row, size,
+        });
+    }
+
+
+    fn texture_size(&self, texture: &Handle) -> [u32; 2]
+    {
+        self.recording.borrow().textures.get(texture).map(|descriptor| descriptor.size).unwrap_or([0, 0])
+    }
+
+
+    fn create_view(&self, texture: &Handle) -> Handle
+    {
+        self.record(|view| Call::CreateView { view, texture: *texture })
+    }
+
+
+    fn destroy_texture(&self, texture: &Handle)
+    {
+        self.recording.borrow_mut().calls.push(Call::DestroyTexture(*texture));
+    }
+
+
+    fn create_sampler(&self, descriptor: &SamplerDescriptor) -> Handle
+    {
+        self.record(|sampler| Call::CreateSampler { sampler, descriptor: descriptor.clone() })
+    }
+
+
+    fn create_shader_module(&self, descriptor: &ShaderModuleDescriptor) -> Handle
+    {
+        self.record(|module| Call::CreateShaderModule { module, descriptor: descriptor.clone() })
+    }
+
+
+    fn create_render_pipeline(&self, descriptor: &RenderPipelineDescriptor<Self>) -> Handle
+    {
+        self.record(|pipeline| Call::CreateRenderPipeline { pipeline, descriptor: descriptor.clone() })
+    }
+
+
+    fn create_compute_pipeline(&self, descriptor: &ComputePipelineDescriptor<Self>) -> Handle
+    {
+        self.record(|pipeline| Call::CreateComputePipeline { pipeline, descriptor: descriptor.clone() })
+    }
+
+
+    fn render_bind_group_layout(&self, pipeline: &Handle, index: u32) -> Handle
+    {
+        self.record(|layout| Call::GetBindGroupLayout { layout, pipeline: *pipeline, index })
+    }
+
+
+    fn compute_bind_group_layout(&self, pipeline: &Handle, index: u32) -> Handle
+    {
+        self.record(|layout| Call::GetBindGroupLayout { layout, pipeline: *pipeline, index })
+    }
+
+
+    fn create_bind_group_layout(&self, descriptor: &BindGroupLayoutDescriptor) -> Handle
+    {
+        self.record(|layout| Call::CreateBindGroupLayout { layout, descriptor: descriptor.clone() })
+    }
+
+
+    fn create_bind_group(&self, descriptor: &BindGroupDescriptor<Self>) -> Handle
+    {
+        self.record(|bind_group| Call::CreateBindGroup { bind_group, descriptor: descriptor.clone() })
+    }
+
+
+    // buffer to buffer copies are applied to the recorded contents, nothing else is executed
+    fn submit(&self, command_buffers: Vec<CommandBuffer<Self>>)
+    {
+        let mut recording = self.recording.borrow_mut();
+        for command in command_buffers.iter().flat_map(|command_buffer| &command_buffer.commands)
+        {
+            if let Command::CopyBufferToBuffer { source, source_offset, destination, destination_offset, size } = command
+            {
+                let (source_start, destination_start) = (*source_offset as usize, *destination_offset as usize);
+                let data = recording.buffers.get(source)
+                    .and_then(|contents| contents.get(source_start..source_start + *size as usize))
+                    .map(<[u8]>::to_vec);
+                let destination = recording.buffers.get_mut(destination)
+                    .and_then(|contents| contents.get_mut(destination_start..destination_start + *size as usize));
+                if let (Some(data), Some(destination)) = (data, destination)
+                {
+                    destination.copy_from_slice(&data);
+                }
+            }
+        }
+        recording.calls.push(Call::Submit(command_buffers));
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn writes_and_copies_update_buffer_contents()
+    {
+        let backend = RecordingBackend::new();
+        let source = backend.create_buffer(&BufferDescriptor::new(8, buffer_usage::COPY_SRC | buffer_usage::COPY_DST));
+        let destination = backend.create_buffer(&BufferDescriptor::new(8, buffer_usage::COPY_DST));
+        backend.write_buffer(&source, 0, [1.5f32, -2.0].as_bytes());
+        // out of bounds, ignored
+        backend.write_buffer(&source, 4, [1u32, 2].as_bytes());
+
+        let mut encoder = CommandEncoder::new();
+        encoder.copy_buffer_to_buffer(&source, 4, &destination, 0, 4);
+        backend.submit(vec![encoder.finish()]);
+
+        assert_eq!(backend.buffer_contents(&source).unwrap(), [1.5f32, -2.0].as_bytes());
+        assert_eq!(backend.buffer_contents(&destination).unwrap(), [-2.0f32, 0.0].as_bytes());
+        assert_eq!(backend.calls().len(), 5);
+    }
+
+
+    #[test]
+    fn passes_record_their_commands_in_order()
+    {
+        let backend = RecordingBackend::new();
+        let texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [4, 2], texture_usage::RENDER_ATTACHMENT,
+        ));
+        let view = backend.create_view(&texture);
+        let module = backend.create_shader_module(&ShaderModuleDescriptor::new(""));
+        let pipeline = backend.create_render_pipeline(&RenderPipelineDescriptor::new(VertexState::new("vs", &module)));
+        assert_eq!(backend.texture_size(&texture), [4, 2]);
+
+        let mut encoder = CommandEncoder::new();
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor::new(vec![
+            RenderPassColorAttachment::new(LoadOp::Clear, StoreOp::Store, &view),
+        ]));
+        pass.set_pipeline(&pipeline);
+        pass.draw(3, 2);
+        pass.end();
+        backend.submit(vec![encoder.finish()]);
+
+        let commands = backend.commands();
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(&commands[0], Command::BeginRenderPass(descriptor) if descriptor.color_attachments[0].view == view));
+        assert!(matches!(commands[1], Command::SetPipeline(handle) if handle == pipeline));
+        assert!(matches!(commands[2], Command::Draw { vertex_count: 3, instance_count: 2, .. }));
+        assert!(matches!(commands[3], Command::EndRenderPass));
+
+        assert_eq!(backend.take_calls().len(), 5);
+        assert!(backend.calls().is_empty());
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use crate::*;
+use crate::recording::{Call, Handle, RecordingBackend};
+
+
+// bumped whenever Call, Command or a descriptor changes shape, older traces are rejected instead of misread
+pub const TRACE_VERSION: u32 = 2;
+
+const BINARY_MAGIC: &[u8; 8] = b"GPUTRACE";
+
+
+// everything a frame needs to be rendered again: the objects it uses, the contents of its buffers and textures,
+// and what it submitted. surface is the texture that stood in for the canvas
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trace
+{
+    pub version: u32,
+    pub surface: Option<Handle>,
+    pub calls: Vec<Call>,
+}
+
+
+impl Trace
+{
+    pub fn new(calls: Vec<Call>, surface: Option<Handle>) -> Self
+    {
+        Trace { version: TRACE_VERSION, surface, calls }
+    }
+
+
+    pub fn to_json(&self) -> Result<String, String>
+    {
+        serde_json::to_string(self).map_err(|error| error.to_string())
+    }
+
+
+    pub fn from_json(json: &str) -> Result<Self, String>
+    {
+        let value = serde_json::from_str::<serde_json::Value>(json).map_err(|error| error.to_string())?;
+        match value.get("version").and_then(serde_json::Value::as_u64)
+        {
+            Some(version) if version == TRACE_VERSION as u64 => (),
+            Some(version) => return Err(format!("trace version {} isn't supported, expected {}", version, TRACE_VERSION)),
+            None => return Err("not a trace, the version is missing".to_owned()),
+        }
+        serde_json::from_value(value).map_err(|error| error.to_string())
+    }
+
+
+    // the magic bytes and the version come first, so other versions are told apart before anything is decoded
+    pub fn to_binary(&self) -> Result<Vec<u8>, String>
+    {
+        let mut bytes = BINARY_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bincode::serialize_into(&mut bytes, self).map_err(|error| error.to_string())?;
+        Ok(bytes)
+    }
+
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, String>
+    {
+        let body = bytes.strip_prefix(BINARY_MAGIC.as_slice()).ok_or("not a binary trace")?;
+        let version = body.get(..4).ok_or("the trace is truncated")?;
+        let version = u32::from_le_bytes([version[0], version[1], version[2], version[3]]);
+        if version != TRACE_VERSION
+        {
+            return Err(format!("trace version {} isn't supported, expected {}", version, TRACE_VERSION));
+        }
+        bincode::deserialize(&body[4..]).map_err(|error| error.to_string())
+    }
+
+
+    // issues every call of the trace against the backend, in order
+    pub fn replay<B: Backend>(&self, backend: &B) -> Result<Replay<B>, String>
+    {
+        let mut replay = Replay { objects: HashMap::new() };
+        for call in &self.calls
+        {
+            replay.issue(backend, call)?;
+        }
+        Ok(replay)
+    }
+}
+
+
+#[derive(Clone, Debug)]
+enum Object<B: Backend>
+{
+    Buffer(B::Buffer),
+    Texture(B::Texture),
+    TextureView(B::TextureView),
+    Sampler(B::Sampler),
+    ShaderModule(B::ShaderModule),
+    RenderPipeline(B::RenderPipeline),
+    ComputePipeline(B::ComputePipeline),
+    BindGroupLayout(B::BindGroupLayout),
+    BindGroup(B::BindGroup),
+}
+
+
+// the objects a replay created, by their handles in the trace
+#[derive(Debug)]
+pub struct Replay<B: Backend>
+{
+    objects: HashMap<Handle, Object<B>>,
+}
+
+
+macro_rules! find_object
+{
+    ($objects:expr, $handle:expr, $variant:ident, $kind:literal) =>
+    {
+        match $objects.get($handle)
+        {
+            Some(Object::$variant(object)) => Ok(object.clone()),
+            Some(_) => Err(format!("{:?} isn't {}", $handle, $kind)),
+            None => Err(format!("{:?} is used before it's created", $handle)),
+        }
+    };
+}
+
+
+impl<B: Backend> HandleMap<RecordingBackend, B> for Replay<B>
+{
+    type Error = String;
+
+    fn buffer(&self, buffer: &Handle) -> Result<B::Buffer, String>
+    {
+        find_object!(self.objects, buffer, Buffer, "a buffer")
+    }
+
+    fn texture(&self, texture: &Handle) -> Result<B::Texture, String>
+    {
+        find_object!(self.objects, texture, Texture, "a texture")
+    }
+
+    fn texture_view(&self, view: &Handle) -> Result<B::TextureView, String>
+    {
+        find_object!(self.objects, view, TextureView, "a texture view")
+    }
+
+    fn sampler(&self, sampler: &Handle) -> Result<B::Sampler, String>
+    {
+        find_object!(self.objects, sampler, Sampler, "a sampler")
+    }
+
+    fn shader_module(&self, module: &Handle) -> Result<B::ShaderModule, String>
+    {
+        find_object!(self.objects, module, ShaderModule, "a shader module")
+    }
+
+    fn render_pipeline(&self, pipeline: &Handle) -> Result<B::RenderPipeline, String>
+    {
+        find_object!(self.objects, pipeline, RenderPipeline, "a render pipeline")
+    }
+
+    fn compute_pipeline(&self, pipeline: &Handle) -> Result<B::ComputePipeline, String>
+    {
+        find_object!(self.objects, pipeline, ComputePipeline, "a compute pipeline")
+    }
+
+    fn bind_group_layout(&self, layout: &Handle) -> Result<B::BindGroupLayout, String>
+    {
+        find_object!(self.objects, layout, BindGroupLayout, "a bind group layout")
+    }
+
+    fn bind_group(&self, bind_group: &Handle) -> Result<B::BindGroup, String>
+    {
+        find_object!(self.objects, bind_group, BindGroup, "a bind group")
+    }
+}
+
+
+impl<B: Backend> Replay<B>
+{
+    pub fn buffer(&self, buffer: &Handle) -> Option<B::Buffer>
+    {
+        HandleMap::buffer(self, buffer).ok()
+    }
+
+
+    pub fn texture(&self, texture: &Handle) -> Option<B::Texture>
+    {
+        HandleMap::texture(self, texture).ok()
+    }
+
+
+    fn issue(&mut self, backend: &B, call: &Call) -> Result<(), String>
+    {
+        let (handle, object) = match call
+        {
+            Call::CreateBuffer { buffer, descriptor } => (*buffer, Object::Buffer(backend.create_buffer(descriptor))),
+            Call::WriteBuffer { buffer, offset, data } =>
+            {
+                backend.write_buffer(&HandleMap::buffer(self, buffer)?, *offset, data);
+                return Ok(());
+            },
+            Call::DestroyBuffer(buffer) =>
+            {
+                backend.destroy_buffer(&HandleMap::buffer(self, buffer)?);
+                return Ok(());
+            },
+            Call::CreateTexture { texture, descriptor } =>
+            {
+                (*texture, Object::Texture(backend.create_texture(descriptor)))
+            },
+            Call::WriteTexture { texture, mip_level, data, bytes_per_row, size } =>
+            {
+                backend.write_texture(&HandleMap::texture(self, texture)?, *mip_level, data, *bytes_per_row, *size);
+                return Ok(());
+            },
+            Call::CreateView { view, texture } =>
+            {
+                (*view, Object::TextureView(backend.create_view(&HandleMap::texture(self, texture)?)))
+            },
+            Call::DestroyTexture(texture) =>
+            {
+                backend.destroy_texture(&HandleMap::texture(self, texture)?);
+                return Ok(());
+            },
+            Call::CreateSampler { sampler, descriptor } =>
+            {
+                (*sampler, Object::Sampler(backend.create_sampler(descriptor)))
+            },
+            Call::CreateShaderModule { module, descriptor } =>
+            {
+                (*module, Object::ShaderModule(backend.create_shader_module(descriptor)))
+            },
+            Call::CreateRenderPipeline { pipeline, descriptor } =>
+            {
+                (*pipeline, Object::RenderPipeline(backend.create_render_pipeline(&descriptor.map_handles(self)?)))
+            },
+            Call::CreateComputePipeline { pipeline, descriptor } =>
+            {
+                (*pipeline, Object::ComputePipeline(backend.create_compute_pipeline(&descriptor.map_handles(self)?)))
+            },
+            // the trace doesn't say which kind of pipeline the layout came from
+            Call::GetBindGroupLayout { layout, pipeline, index } => match self.objects.get(pipeline)
+            {
+                Some(Object::RenderPipeline(pipeline)) =>
+                {
+                    (*layout, Object::BindGroupLayout(backend.render_bind_group_layout(pipeline, *index)))
+                },
+                Some(Object::ComputePipeline(pipeline)) =>
+                {
+                    (*layout, Object::BindGroupLayout(backend.compute_bind_group_layout(pipeline, *index)))
+                },
+                _ => return Err(format!("{:?} isn't a pipeline", pipeline)),
+            },
+            Call::CreateBindGroupLayout { layout, descriptor } =>
+            {
+                (*layout, Object::BindGroupLayout(backend.create_bind_group_layout(descriptor)))
+            },
+            Call::CreateBindGroup { bind_group, descriptor } =>
+            {
+                (*bind_group, Object::BindGroup(backend.create_bind_group(&descriptor.map_handles(self)?)))
+            },
+            Call::Submit(command_buffers) =>
+            {
+                let command_buffers = command_buffers.iter()
+                    .map(|command_buffer| command_buffer.map_handles(self))
+                    .collect::<Result<_, _>>()?;
+                backend.submit(command_buffers);
+                return Ok(());
+            },
+        };
+        self.objects.insert(handle, object);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::capture::CaptureBackend;
+
+
+    fn draw_frame<B: Backend>(backend: &B, texture: &B::Texture, pipeline: &B::RenderPipeline, buffer: &B::Buffer)
+    {
+        let mut encoder = CommandEncoder::new();
+        let mut pass = encoder.begin_render_pass(&RenderPassDescriptor::new(vec![
+            RenderPassColorAttachment::new(LoadOp::Clear, StoreOp::Store, &backend.create_view(texture)),
+        ]));
+        pass.set_pipeline(pipeline);
+        pass.set_vertex_buffer(0, buffer);
+        pass.draw(3, 1);
+        pass.end();
+        backend.submit(vec![encoder.finish()]);
+    }
+
+
+    #[test]
+    fn captures_one_frame_and_replays_it()
+    {
+        let device = RecordingBackend::new();
+        let backend = CaptureBackend::new(device.clone());
+        let buffer = backend.create_buffer(&BufferDescriptor::new(16, buffer_usage::VERTEX | buffer_usage::COPY_DST));
+        backend.write_buffer(&buffer, 0, [1.0f32, 2.0, 3.0, 4.0].as_bytes());
+        let module = backend.create_shader_module(&ShaderModuleDescriptor::new(""));
+        let pipeline = backend.create_render_pipeline(&RenderPipelineDescriptor::new(VertexState::new("vs", &module)));
+        let texture = backend.surface_texture(
+            device.create_texture(&TextureDescriptor::new(TextureFormat::Bgra8Unorm, [4, 4], 0)), TextureFormat::Bgra8Unorm,
+        );
+
+        // frames before the capture only leave their uploads behind
+        backend.write_buffer(&buffer, 0, [5.0f32].as_bytes());
+        draw_frame(&backend, &texture, &pipeline, &buffer);
+
+        backend.start_capture();
+        backend.write_buffer(&buffer, 4, [6.0f32].as_bytes());
+        draw_frame(&backend, &texture, &pipeline, &buffer);
+        let trace = backend.finish_capture();
+
+        assert_eq!(trace.surface, Some(texture.handle));
+        assert_eq!(trace.calls.iter().filter(|call| matches!(call, Call::Submit(_))).count(), 1);
+        assert_eq!(trace.calls.iter().filter(|call| matches!(call, Call::CreateView { .. })).count(), 1);
+        let uploads = trace.calls.iter()
+            .filter_map(|call| match call
+            {
+                Call::WriteBuffer { offset, data, .. } => Some((*offset, data.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(uploads, [(0, [5.0f32, 2.0, 3.0, 4.0].as_bytes().to_vec()), (4, [6.0f32].as_bytes().to_vec())]);
+
+        let from_json = Trace::from_json(&trace.to_json().unwrap()).unwrap();
+        let from_binary = Trace::from_binary(&trace.to_binary().unwrap()).unwrap();
+        for trace in [from_json, from_binary]
+        {
+            let target = RecordingBackend::new();
+            let replay = trace.replay(&target).unwrap();
+            let replayed_buffer = replay.buffer(&buffer.handle).unwrap();
+            assert_eq!(target.buffer_contents(&replayed_buffer).unwrap(), [5.0f32, 6.0, 3.0, 4.0].as_bytes());
+            assert!(replay.texture(&texture.handle).is_some());
+
+            let commands = target.commands();
+            assert_eq!(commands.len(), 5);
+            assert!(matches!(commands[2], Command::SetVertexBuffer { buffer, .. } if buffer == replayed_buffer));
+            assert!(matches!(commands[3], Command::Draw { vertex_count: 3, instance_count: 1, .. }));
+        }
+    }
+
+
+    #[test]
+    fn replays_explicit_bind_group_layouts()
+    {
+        let backend = CaptureBackend::new(RecordingBackend::new());
+        backend.start_capture();
+        let mut uniform = BindGroupLayoutEntry::buffer(0, shader_stage::VERTEX, BufferBindingType::Uniform);
+        uniform.has_dynamic_offset(true);
+        let layout = backend.create_bind_group_layout(&BindGroupLayoutDescriptor::new(&[
+            uniform, BindGroupLayoutEntry::sampler(1, shader_stage::FRAGMENT),
+        ]));
+        let module = backend.create_shader_module(&ShaderModuleDescriptor::new(""));
+        let mut descriptor = ComputePipelineDescriptor::new("main", &module);
+        descriptor.bind_group_layouts(std::slice::from_ref(&layout));
+        backend.create_compute_pipeline(&descriptor);
+        let trace = Trace::from_binary(&backend.finish_capture().to_binary().unwrap()).unwrap();
+
+        let target = RecordingBackend::new();
+        trace.replay(&target).unwrap();
+        let calls = target.calls();
+        let replayed_layout = match &calls[0]
+        {
+            Call::CreateBindGroupLayout { layout, descriptor } =>
+            {
+                assert_eq!(descriptor.entries[0].ty, BindingType::Buffer
+                {
+                    ty: BufferBindingType::Uniform, has_dynamic_offset: true,
+                });
+                assert_eq!(descriptor.entries[1].ty, BindingType::Sampler);
+                *layout
+            },
+            call => panic!("unexpected {:?}", call),
+        };
+        assert!(matches!(
+            &calls[2],
+            Call::CreateComputePipeline { descriptor, .. } if descriptor.bind_group_layouts == Some(vec![replayed_layout])
+        ));
+    }
+
+
+    #[test]
+    fn rejects_other_versions_and_dangling_handles()
+    {
+        let mut trace = Trace::new(vec![Call::DestroyBuffer(Handle(7))], None);
+        assert!(trace.replay(&RecordingBackend::new()).unwrap_err().contains("before it's created"));
+
+        trace.version = TRACE_VERSION + 1;
+        assert!(Trace::from_json(&trace.to_json().unwrap()).unwrap_err().contains("isn't supported"));
+        assert!(Trace::from_binary(&trace.to_binary().unwrap()).unwrap_err().contains("isn't supported"));
+        assert!(Trace::from_binary(b"not a trace").is_err());
+    }
+}
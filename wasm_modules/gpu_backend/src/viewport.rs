@@ -0,0 +1,108 @@
+use web_sys::HtmlCanvasElement;
+
+use crate::Backend;
+
+
+// the drawing buffer size of the canvas in physical pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport
+{
+    width: u32,
+    height: u32,
+}
+
+
+impl Viewport
+{
+    pub fn from_canvas(canvas: &HtmlCanvasElement) -> Self
+    {
+        Viewport { width: canvas.width().max(1), height: canvas.height().max(1) }
+    }
+
+
+    // converts a size in css pixels into physical pixels, clamped to what the device can render to.
+    // a ratio that isn't positive, as reported for a detached window, counts as 1
+    pub fn new<B: Backend>(backend: &B, width: f64, height: f64, device_pixel_ratio: f64) -> Self
+    {
+        let device_pixel_ratio = if device_pixel_ratio > 0.0 { device_pixel_ratio } else { 1.0 };
+        let max_dimension = backend.max_texture_dimension_2d();
+        let to_physical = |size: f64| ((size * device_pixel_ratio).round().max(1.0) as u32).min(max_dimension);
+
+        Viewport { width: to_physical(width), height: to_physical(height) }
+    }
+
+
+    pub fn aspect(&self) -> f32
+    {
+        self.width as f32 / self.height as f32
+    }
+
+
+    // resizing the canvas drawing buffer also resizes the textures returned by the context
+    pub fn apply(&self, canvas: &HtmlCanvasElement)
+    {
+        if canvas.width() != self.width
+        {
+            canvas.set_width(self.width);
+        }
+        if canvas.height() != self.height
+        {
+            canvas.set_height(self.height);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::recording::RecordingBackend;
+
+
+    fn size(viewport: Viewport) -> (u32, u32)
+    {
+        (viewport.width, viewport.height)
+    }
+
+
+    #[test]
+    fn css_sizes_are_scaled_and_rounded_to_physical_pixels()
+    {
+        let backend = RecordingBackend::new();
+        assert_eq!(size(Viewport::new(&backend, 300.0, 150.0, 2.0)), (600, 300));
+        assert_eq!(size(Viewport::new(&backend, 100.3, 100.7, 1.5)), (150, 151));
+        assert_eq!(size(Viewport::new(&backend, 101.0, 33.0, 1.25)), (126, 41));
+    }
+
+
+    #[test]
+    fn physical_sizes_are_clamped_to_the_max_texture_dimension()
+    {
+        let backend = RecordingBackend::new();
+        backend.set_max_texture_dimension_2d(2048);
+        assert_eq!(size(Viewport::new(&backend, 1920.0, 1080.0, 2.0)), (2048, 2048));
+        assert_eq!(size(Viewport::new(&backend, 1920.0, 800.0, 1.0)), (1920, 800));
+    }
+
+
+    #[test]
+    fn non_positive_pixel_ratios_count_as_one()
+    {
+        let backend = RecordingBackend::new();
+        assert_eq!(size(Viewport::new(&backend, 300.0, 150.0, 0.0)), (300, 150));
+        assert_eq!(size(Viewport::new(&backend, 300.0, 150.0, -2.0)), (300, 150));
+        assert_eq!(size(Viewport::new(&backend, 300.0, 150.0, f64::NAN)), (300, 150));
+    }
+
+
+    #[test]
+    fn zero_sized_canvases_get_one_pixel()
+    {
+        let backend = RecordingBackend::new();
+        let viewport = Viewport::new(&backend, 0.0, 0.0, 2.0);
+        assert_eq!(size(viewport), (1, 1));
+        assert_eq!(viewport.aspect(), 1.0);
+        assert_eq!(size(Viewport::new(&backend, 0.0, 40.0, 1.0)), (1, 40));
+    }
+}
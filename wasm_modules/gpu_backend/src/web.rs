@@ -0,0 +1,859 @@
+use wasm_bindgen::JsValue;
+
+use web_sys::
+{
+    GpuDevice, GpuQueue, GpuBuffer, GpuBufferDescriptor, GpuTexture, GpuTextureDescriptor, GpuTextureFormat,
+    GpuTextureView, GpuImageCopyTexture, GpuImageDataLayout, GpuSampler, GpuSamplerDescriptor, GpuAddressMode,
+    GpuFilterMode, GpuMipmapFilterMode, GpuCompareFunction, GpuShaderModule, GpuShaderModuleDescriptor,
+    GpuRenderPipeline, GpuRenderPipelineDescriptor, GpuComputePipeline, GpuComputePipelineDescriptor,
+    GpuProgrammableStage, GpuVertexState, GpuVertexBufferLayout, GpuVertexAttribute, GpuVertexFormat,
+    GpuVertexStepMode, GpuFragmentState, GpuColorTargetState, GpuBlendState, GpuBlendComponent, GpuBlendFactor,
+    GpuBlendOperation, GpuPrimitiveState, GpuPrimitiveTopology, GpuFrontFace, GpuCullMode, GpuIndexFormat,
+    GpuDepthStencilState, GpuMultisampleState, GpuBindGroup, GpuBindGroupLayout, GpuBindGroupDescriptor,
+    GpuBindGroupEntry, GpuBufferBinding, GpuCommandEncoder, GpuCommandEncoderDescriptor, GpuRenderPassDescriptor,
+    GpuRenderPassEncoder, GpuRenderPassColorAttachment, GpuRenderPassDepthStencilAttachment,
+    GpuComputePassDescriptor, GpuComputePassEncoder, GpuLoadOp, GpuStoreOp, GpuColorDict, GpuBindGroupLayoutDescriptor,
+    GpuBindGroupLayoutEntry, GpuBufferBindingLayout, GpuBufferBindingType, GpuSamplerBindingLayout,
+    GpuTextureBindingLayout, GpuPipelineLayoutDescriptor, GpuExtent3dDict, GpuImageCopyExternalImage,
+    GpuImageCopyTextureTagged, ImageBitmap,
+};
+
+use web_sys::gpu_map_mode::READ;
+
+use crate::*;
+
+
+#[derive(Clone, Debug)]
+pub struct WebBackend
+{
+    gpu_device: GpuDevice,
+    queue: GpuQueue,
+}
+
+
+impl WebBackend
+{
+    pub fn new(gpu_device: GpuDevice) -> Self
+    {
+        let queue = gpu_device.queue();
+        WebBackend { gpu_device, queue }
+    }
+
+
+    // for what the backend doesn't cover, like copying an ImageBitmap into a texture
+    pub fn gpu_device(&self) -> &GpuDevice
+    {
+        &self.gpu_device
+    }
+
+
+    // the texture needs the COPY_DST and RENDER_ATTACHMENT usages and the size of the image
+    pub fn copy_image_bitmap_to_texture(&self, image_bitmap: &ImageBitmap, texture: &GpuTexture, flip_y: bool)
+    {
+        let mut image_copy_external_image = GpuImageCopyExternalImage::new(image_bitmap);
+        image_copy_external_image.flip_y(flip_y);
+        let mut gpu_extent_3d_dict = GpuExtent3dDict::new(image_bitmap.width());
+        gpu_extent_3d_dict.height(image_bitmap.height());
+
+        self.queue.copy_external_image_to_texture_with_gpu_extent_3d_dict(
+            &image_copy_external_image, &GpuImageCopyTextureTagged::new(texture), &gpu_extent_3d_dict,
+        );
+    }
+
+
+    // the pipeline layout of explicit bind group layouts, or "auto"
+    fn pipeline_layout(&self, bind_group_layouts: &Option<Vec<GpuBindGroupLayout>>) -> JsValue
+    {
+        match bind_group_layouts
+        {
+            Some(bind_group_layouts) =>
+            {
+                let layouts = js_array(bind_group_layouts.iter().cloned());
+                self.gpu_device.create_pipeline_layout(&GpuPipelineLayoutDescriptor::new(&layouts)).into()
+            },
+            None => JsValue::from("auto"),
+        }
+    }
+}
+
+
+fn js_array<T: Into<JsValue>>(values: impl IntoIterator<Item = T>) -> js_sys::Array
+{
+    values.into_iter().map(Into::into).collect()
+}
+
+
+impl TryFrom<GpuTextureFormat> for TextureFormat
+{
+    type Error = String;
+
+    fn try_from(format: GpuTextureFormat) -> Result<Self, String>
+    {
+        Ok(match format
+        {
+            GpuTextureFormat::R8unorm => TextureFormat::R8Unorm,
+            GpuTextureFormat::R32float => TextureFormat::R32Float,
+            GpuTextureFormat::Rgba8unorm => TextureFormat::Rgba8Unorm,
+            GpuTextureFormat::Rgba8unormSrgb => TextureFormat::Rgba8UnormSrgb,
+            GpuTextureFormat::Bgra8unorm => TextureFormat::Bgra8Unorm,
+            GpuTextureFormat::Bgra8unormSrgb => TextureFormat::Bgra8UnormSrgb,
+            GpuTextureFormat::Rgba16float => TextureFormat::Rgba16Float,
+            GpuTextureFormat::Rgba32float => TextureFormat::Rgba32Float,
+            GpuTextureFormat::Depth24plus => TextureFormat::Depth24Plus,
+            GpuTextureFormat::Depth24plusStencil8 => TextureFormat::Depth24PlusStencil8,
+            GpuTextureFormat::Depth32float => TextureFormat::Depth32Float,
+            GpuTextureFormat::Depth32floatStencil8 => TextureFormat::Depth32FloatStencil8,
+            format => return Err(format!("unsupported texture format {:?}", format)),
+        })
+    }
+}
+
+
+impl From<TextureFormat> for GpuTextureFormat
+{
+    fn from(format: TextureFormat) -> Self
+    {
+        match format
+        {
+            TextureFormat::R8Unorm => GpuTextureFormat::R8unorm,
+            TextureFormat::R32Float => GpuTextureFormat::R32float,
+            TextureFormat::Rgba8Unorm => GpuTextureFormat::Rgba8unorm,
+            TextureFormat::Rgba8UnormSrgb => GpuTextureFormat::Rgba8unormSrgb,
+            TextureFormat::Bgra8Unorm => GpuTextureFormat::Bgra8unorm,
+            TextureFormat::Bgra8UnormSrgb => GpuTextureFormat::Bgra8unormSrgb,
+            TextureFormat::Rgba16Float => GpuTextureFormat::Rgba16float,
+            TextureFormat::Rgba32Float => GpuTextureFormat::Rgba32float,
+            TextureFormat::Depth24Plus => GpuTextureFormat::Depth24plus,
+            TextureFormat::Depth24PlusStencil8 => GpuTextureFormat::Depth24plusStencil8,
+            TextureFormat::Depth32Float => GpuTextureFormat::Depth32float,
+            TextureFormat::Depth32FloatStencil8 => GpuTextureFormat::Depth32floatStencil8,
+        }
+    }
+}
+
+
+fn vertex_format(format: VertexFormat) -> GpuVertexFormat
+{
+    match format
+    {
+        VertexFormat::Uint8x2 => GpuVertexFormat::Uint8x2,
+        VertexFormat::Uint8x4 => GpuVertexFormat::Uint8x4,
+        VertexFormat::Sint8x2 => GpuVertexFormat::Sint8x2,
+        VertexFormat::Sint8x4 => GpuVertexFormat::Sint8x4,
+        VertexFormat::Unorm8x2 => GpuVertexFormat::Unorm8x2,
+        VertexFormat::Unorm8x4 => GpuVertexFormat::Unorm8x4,
+        VertexFormat::Snorm8x2 => GpuVertexFormat::Snorm8x2,
+        VertexFormat::Snorm8x4 => GpuVertexFormat::Snorm8x4,
+        VertexFormat::Uint16x2 => GpuVertexFormat::Uint16x2,
+        VertexFormat::Uint16x4 => GpuVertexFormat::Uint16x4,
+        VertexFormat::Sint16x2 => GpuVertexFormat::Sint16x2,
+        VertexFormat::Sint16x4 => GpuVertexFormat::Sint16x4,
+        VertexFormat::Unorm16x2 => GpuVertexFormat::Unorm16x2,
+        VertexFormat::Unorm16x4 => GpuVertexFormat::Unorm16x4,
+        VertexFormat::Snorm16x2 => GpuVertexFormat::Snorm16x2,
+        VertexFormat::Snorm16x4 => GpuVertexFormat::Snorm16x4,
+        VertexFormat::Float16x2 => GpuVertexFormat::Float16x2,
+        VertexFormat::Float16x4 => GpuVertexFormat::Float16x4,
+        VertexFormat::Float32 => GpuVertexFormat::Float32,
+        VertexFormat::Float32x2 => GpuVertexFormat::Float32x2,
+        VertexFormat::Float32x3 => GpuVertexFormat::Float32x3,
+        VertexFormat::Float32x4 => GpuVertexFormat::Float32x4,
+        VertexFormat::Uint32 => GpuVertexFormat::Uint32,
+        VertexFormat::Uint32x2 => GpuVertexFormat::Uint32x2,
+        VertexFormat::Uint32x3 => GpuVertexFormat::Uint32x3,
+        VertexFormat::Uint32x4 => GpuVertexFormat::Uint32x4,
+        VertexFormat::Sint32 => GpuVertexFormat::Sint32,
+        VertexFormat::Sint32x2 => GpuVertexFormat::Sint32x2,
+        VertexFormat::Sint32x3 => GpuVertexFormat::Sint32x3,
+        VertexFormat::Sint32x4 => GpuVertexFormat::Sint32x4,
+    }
+}
+
+
+fn index_format(format: IndexFormat) -> GpuIndexFormat
+{
+    match format
+    {
+        IndexFormat::Uint16 => GpuIndexFormat::Uint16,
+        IndexFormat::Uint32 => GpuIndexFormat::Uint32,
+    }
+}
+
+
+impl TryFrom<GpuCompareFunction> for CompareFunction
+{
+    type Error = String;
+
+    fn try_from(compare: GpuCompareFunction) -> Result<Self, String>
+    {
+        Ok(match compare
+        {
+            GpuCompareFunction::Never => CompareFunction::Never,
+            GpuCompareFunction::Less => CompareFunction::Less,
+            GpuCompareFunction::Equal => CompareFunction::Equal,
+            GpuCompareFunction::LessEqual => CompareFunction::LessEqual,
+            GpuCompareFunction::Greater => CompareFunction::Greater,
+            GpuCompareFunction::NotEqual => CompareFunction::NotEqual,
+            GpuCompareFunction::GreaterEqual => CompareFunction::GreaterEqual,
+            GpuCompareFunction::Always => CompareFunction::Always,
+            compare => return Err(format!("unsupported compare function {:?}", compare)),
+        })
+    }
+}
+
+
+fn compare_function(compare: CompareFunction) -> GpuCompareFunction
+{
+    match compare
+    {
+        CompareFunction::Never => GpuCompareFunction::Never,
+        CompareFunction::Less => GpuCompareFunction::Less,
+        CompareFunction::Equal => GpuCompareFunction::Equal,
+        CompareFunction::LessEqual => GpuCompareFunction::LessEqual,
+        CompareFunction::Greater => GpuCompareFunction::Greater,
+        CompareFunction::NotEqual => GpuCompareFunction::NotEqual,
+        CompareFunction::GreaterEqual => GpuCompareFunction::GreaterEqual,
+        CompareFunction::Always => GpuCompareFunction::Always,
+    }
+}
+
+
+impl TryFrom<GpuBlendFactor> for BlendFactor
+{
+    type Error = String;
+
+    fn try_from(factor: GpuBlendFactor) -> Result<Self, String>
+    {
+        Ok(match factor
+        {
+            GpuBlendFactor::Zero => BlendFactor::Zero,
+            GpuBlendFactor::One => BlendFactor::One,
+            GpuBlendFactor::Src => BlendFactor::Src,
+            GpuBlendFactor::OneMinusSrc => BlendFactor::OneMinusSrc,
+            GpuBlendFactor::SrcAlpha => BlendFactor::SrcAlpha,
+            GpuBlendFactor::OneMinusSrcAlpha => BlendFactor::OneMinusSrcAlpha,
+            GpuBlendFactor::Dst => BlendFactor::Dst,
+            GpuBlendFactor::OneMinusDst => BlendFactor::OneMinusDst,
+            GpuBlendFactor::DstAlpha => BlendFactor::DstAlpha,
+            GpuBlendFactor::OneMinusDstAlpha => BlendFactor::OneMinusDstAlpha,
+            GpuBlendFactor::SrcAlphaSaturated => BlendFactor::SrcAlphaSaturated,
+            GpuBlendFactor::Constant => BlendFactor::Constant,
+            GpuBlendFactor::OneMinusConstant => BlendFactor::OneMinusConstant,
+            factor => return Err(format!("unsupported blend factor {:?}", factor)),
+        })
+    }
+}
+
+
+impl TryFrom<GpuBlendOperation> for BlendOperation
+{
+    type Error = String;
+
+    fn try_from(operation: GpuBlendOperation) -> Result<Self, String>
+    {
+        Ok(match operation
+        {
+            GpuBlendOperation::Add => BlendOperation::Add,
+            GpuBlendOperation::Subtract => BlendOperation::Subtract,
+            GpuBlendOperation::ReverseSubtract => BlendOperation::ReverseSubtract,
+            GpuBlendOperation::Min => BlendOperation::Min,
+            GpuBlendOperation::Max => BlendOperation::Max,
+            operation => return Err(format!("unsupported blend operation {:?}", operation)),
+        })
+    }
+}
+
+
+fn blend_factor(factor: BlendFactor) -> GpuBlendFactor
+{
+    match factor
+    {
+        BlendFactor::Zero => GpuBlendFactor::Zero,
+        BlendFactor::One => GpuBlendFactor::One,
+        BlendFactor::Src => GpuBlendFactor::Src,
+        BlendFactor::OneMinusSrc => GpuBlendFactor::OneMinusSrc,
+        BlendFactor::SrcAlpha => GpuBlendFactor::SrcAlpha,
+        BlendFactor::OneMinusSrcAlpha => GpuBlendFactor::OneMinusSrcAlpha,
+        BlendFactor::Dst => GpuBlendFactor::Dst,
+        BlendFactor::OneMinusDst => GpuBlendFactor::OneMinusDst,
+        BlendFactor::DstAlpha => GpuBlendFactor::DstAlpha,
+        BlendFactor::OneMinusDstAlpha => GpuBlendFactor::OneMinusDstAlpha,
+        BlendFactor::SrcAlphaSaturated => GpuBlendFactor::SrcAlphaSaturated,
+        BlendFactor::Constant => GpuBlendFactor::Constant,
+        BlendFactor::OneMinusConstant => GpuBlendFactor::OneMinusConstant,
+    }
+}
+
+
+fn blend_component(component: &BlendComponent) -> GpuBlendComponent
+{
+    let operation = match component.operation
+    {
+        BlendOperation::Add => GpuBlendOperation::Add,
+        BlendOperation::Subtract => GpuBlendOperation::Subtract,
+        BlendOperation::ReverseSubtract => GpuBlendOperation::ReverseSubtract,
+        BlendOperation::Min => GpuBlendOperation::Min,
+        BlendOperation::Max => GpuBlendOperation::Max,
+    };
+    let mut blend_component = GpuBlendComponent::new();
+    blend_component
+        .src_factor(blend_factor(component.src_factor))
+        .dst_factor(blend_factor(component.dst_factor))
+        .operation(operation);
+    blend_component
+}
+
+
+fn address_mode(address_mode: AddressMode) -> GpuAddressMode
+{
+    match address_mode
+    {
+        AddressMode::ClampToEdge => GpuAddressMode::ClampToEdge,
+        AddressMode::Repeat => GpuAddressMode::Repeat,
+        AddressMode::MirrorRepeat => GpuAddressMode::MirrorRepeat,
+    }
+}
+
+
+fn filter_mode(filter: FilterMode) -> GpuFilterMode
+{
+    match filter
+    {
+        FilterMode::Nearest => GpuFilterMode::Nearest,
+        FilterMode::Linear => GpuFilterMode::Linear,
+    }
+}
+
+
+fn load_op(load_op: LoadOp) -> GpuLoadOp
+{
+    match load_op
+    {
+        LoadOp::Load => GpuLoadOp::Load,
+        LoadOp::Clear => GpuLoadOp::Clear,
+    }
+}
+
+
+fn store_op(store_op: StoreOp) -> GpuStoreOp
+{
+    match store_op
+    {
+        StoreOp::Store => GpuStoreOp::Store,
+        StoreOp::Discard => GpuStoreOp::Discard,
+    }
+}
+
+
+fn bind_group_layout_entry(entry: &BindGroupLayoutEntry) -> GpuBindGroupLayoutEntry
+{
+    let mut layout_entry = GpuBindGroupLayoutEntry::new(entry.binding, entry.visibility);
+    match entry.ty
+    {
+        BindingType::Buffer { ty, has_dynamic_offset } =>
+        {
+            let mut buffer_binding_layout = GpuBufferBindingLayout::new();
+            buffer_binding_layout
+                .type_(match ty
+                {
+                    BufferBindingType::Uniform => GpuBufferBindingType::Uniform,
+                    BufferBindingType::Storage => GpuBufferBindingType::Storage,
+                    BufferBindingType::ReadOnlyStorage => GpuBufferBindingType::ReadOnlyStorage,
+                })
+                .has_dynamic_offset(has_dynamic_offset);
+            layout_entry.buffer(&buffer_binding_layout);
+        },
+        BindingType::Sampler => { layout_entry.sampler(&GpuSamplerBindingLayout::new()); },
+        BindingType::Texture => { layout_entry.texture(&GpuTextureBindingLayout::new()); },
+    }
+    layout_entry
+}
+
+
+fn vertex_buffer_layout(layout: &VertexBufferLayout) -> GpuVertexBufferLayout
+{
+    let attributes = js_array(layout.attributes.iter().map(|attribute|
+        GpuVertexAttribute::new(vertex_format(attribute.format), attribute.offset as f64, attribute.shader_location)
+    ));
+    let mut buffer_layout = GpuVertexBufferLayout::new(layout.array_stride as f64, &attributes);
+    if layout.step_mode == VertexStepMode::Instance
+    {
+        buffer_layout.step_mode(GpuVertexStepMode::Instance);
+    }
+    buffer_layout
+}
+
+
+fn color_target_state(target: &ColorTargetState) -> GpuColorTargetState
+{
+    let mut color_target_state = GpuColorTargetState::new(target.format.into());
+    if let Some(blend) = &target.blend
+    {
+        color_target_state.blend(&GpuBlendState::new(&blend_component(&blend.alpha), &blend_component(&blend.color)));
+    }
+    color_target_state
+}
+
+
+fn primitive_state(primitive: &PrimitiveState) -> GpuPrimitiveState
+{
+    let topology = match primitive.topology
+    {
+        PrimitiveTopology::PointList => GpuPrimitiveTopology::PointList,
+        PrimitiveTopology::LineList => GpuPrimitiveTopology::LineList,
+        PrimitiveTopology::LineStrip => GpuPrimitiveTopology::LineStrip,
+        PrimitiveTopology::TriangleList => GpuPrimitiveTopology::TriangleList,
+        PrimitiveTopology::TriangleStrip => GpuPrimitiveTopology::TriangleStrip,
+    };
+    let front_face = match primitive.front_face
+    {
+        FrontFace::Ccw => GpuFrontFace::Ccw,
+        FrontFace::Cw => GpuFrontFace::Cw,
+    };
+    let cull_mode = match primitive.cull_mode
+    {
+        CullMode::None => GpuCullMode::None,
+        CullMode::Front => GpuCullMode::Front,
+        CullMode::Back => GpuCullMode::Back,
+    };
+    let mut primitive_state = GpuPrimitiveState::new();
+    primitive_state.topology(topology).front_face(front_face).cull_mode(cull_mode);
+    primitive_state
+}
+
+
+fn render_pass_descriptor(descriptor: &RenderPassDescriptor<WebBackend>) -> GpuRenderPassDescriptor
+{
+    let color_attachments = js_array(descriptor.color_attachments.iter().map(|attachment|
+    {
+        let [r, g, b, a] = attachment.clear_value;
+        let mut color_attachment = GpuRenderPassColorAttachment::new(
+            load_op(attachment.load_op), store_op(attachment.store_op), &attachment.view,
+        );
+        color_attachment.clear_value(&GpuColorDict::new(a, b, g, r));
+        if let Some(resolve_target) = &attachment.resolve_target
+        {
+            color_attachment.resolve_target(resolve_target);
+        }
+        color_attachment
+    }));
+
+    let mut render_pass_descriptor = GpuRenderPassDescriptor::new(&color_attachments);
+    if let Some(label) = &descriptor.label
+    {
+        render_pass_descriptor.label(label);
+    }
+    if let Some(attachment) = &descriptor.depth_stencil_attachment
+    {
+        let mut depth_stencil_attachment = GpuRenderPassDepthStencilAttachment::new(&attachment.view);
+        depth_stencil_attachment
+            .depth_clear_value(attachment.depth_clear_value)
+            .depth_load_op(load_op(attachment.depth_load_op))
+            .depth_store_op(store_op(attachment.depth_store_op));
+        if let Some((stencil_load_op, stencil_store_op)) = attachment.stencil_ops
+        {
+            depth_stencil_attachment
+                .stencil_clear_value(attachment.stencil_clear_value)
+                .stencil_load_op(load_op(stencil_load_op))
+                .stencil_store_op(store_op(stencil_store_op));
+        }
+        render_pass_descriptor.depth_stencil_attachment(&depth_stencil_attachment);
+    }
+    render_pass_descriptor
+}
+
+
+impl Backend for WebBackend
+{
+    type Buffer = GpuBuffer;
+    type Texture = GpuTexture;
+    type TextureView = GpuTextureView;
+    type Sampler = GpuSampler;
+    type ShaderModule = GpuShaderModule;
+    type RenderPipeline = GpuRenderPipeline;
+    type ComputePipeline = GpuComputePipeline;
+    type BindGroupLayout = GpuBindGroupLayout;
+    type BindGroup = GpuBindGroup;
+
+
+    fn max_texture_dimension_2d(&self) -> u32
+    {
+        self.gpu_device.limits().max_texture_dimension_2d()
+    }
+
+
+    fn limits(&self) -> Limits
+    {
+        let limits = self.gpu_device.limits();
+        Limits
+        {
+            min_uniform_buffer_offset_alignment: limits.min_uniform_buffer_offset_alignment(),
+            max_compute_workgroups_per_dimension: limits.max_compute_workgroups_per_dimension(),
+            max_compute_invocations_per_workgroup: limits.max_compute_invocations_per_workgroup(),
+            max_compute_workgroup_size_x: limits.max_compute_workgroup_size_x(),
+        }
+    }
+
+
+    fn has_feature(&self, feature: &str) -> bool
+    {
+        self.gpu_device.features().has(feature)
+    }
+
+
+    fn create_buffer(&self, descriptor: &BufferDescriptor) -> GpuBuffer
+    {
+        let mut buffer_descriptor = GpuBufferDescriptor::new(descriptor.size as f64, descriptor.usage);
+        buffer_descriptor.mapped_at_creation(descriptor.mapped_at_creation);
+        if let Some(label) = &descriptor.label
+        {
+            buffer_descriptor.label(label);
+        }
+        self.gpu_device.create_buffer(&buffer_descriptor)
+    }
+
+
+    fn write_buffer(&self, buffer: &GpuBuffer, offset: u64, data: &[u8])
+    {
+        self.queue.write_buffer_with_f64_and_u8_array(buffer, offset as f64, data);
+    }
+
+
+    fn destroy_buffer(&self, buffer: &GpuBuffer)
+    {
+        buffer.destroy();
+    }
+
+
+    fn read_buffer(&self, buffer: &GpuBuffer, offset: u64, size: u64) -> BufferRead
+    {
+        let buffer = buffer.clone();
+        Box::pin(async move
+        {
+            let mapped = buffer.map_async_with_f64_and_f64(READ, offset as f64, size as f64);
+            wasm_bindgen_futures::JsFuture::from(mapped).await
+                .map_err(|error| format!("failed to map the buffer: {:?}", error))?;
+            let bytes = js_sys::Uint8Array::new(&buffer.get_mapped_range_with_f64_and_f64(offset as f64, size as f64))
+                .to_vec();
+            buffer.unmap();
+            Ok(bytes)
+        })
+    }
+
+
+    fn create_texture(&self, descriptor: &TextureDescriptor) -> GpuTexture
+    {
+        let mut texture_descriptor = GpuTextureDescriptor::new(
+            descriptor.format.into(), &js_array(descriptor.size), descriptor.usage,
+        );
+        texture_descriptor
+            .mip_level_count(descriptor.mip_level_count)
+            .sample_count(descriptor.sample_count);
+        if let Some(label) = &descriptor.label
+        {
+            texture_descriptor.label(label);
+        }
+        self.gpu_device.create_texture(&texture_descriptor)
+    }
+
+
+    fn write_texture(&self, texture: &GpuTexture, mip_level: u32, data: &[u8], bytes_per_row: u32, size: [u32; 2])
+    {
+        let mut destination = GpuImageCopyTexture::new(texture);
+        destination.mip_level(mip_level);
+        let mut data_layout = GpuImageDataLayout::new();
+        data_layout.bytes_per_row(bytes_per_row);
+        self.queue.write_texture_with_u8_array_and_u32_sequence(&destination, data, &data_layout, &js_array(size));
+    }
+
+
+    fn texture_size(&self, texture: &GpuTexture) -> [u32; 2]
+    {
+        [texture.width(), texture.height()]
+    }
+
+
+    fn create_view(&self, texture: &GpuTexture) -> GpuTextureView
+    {
+        texture.create_view()
+    }
+
+
+    fn destroy_texture(&self, texture: &GpuTexture)
+    {
+        texture.destroy();
+    }
+
+
+    fn create_sampler(&self, descriptor: &SamplerDescriptor) -> GpuSampler
+    {
+        let mut sampler_descriptor = GpuSamplerDescriptor::new();
+        sampler_descriptor
+            .address_mode_u(address_mode(descriptor.address_mode_u))
+            .address_mode_v(address_mode(descriptor.address_mode_v))
+            .address_mode_w(address_mode(descriptor.address_mode_w))
+            .mag_filter(filter_mode(descriptor.mag_filter))
+            .min_filter(filter_mode(descriptor.min_filter))
+            .mipmap_filter(match descriptor.mipmap_filter
+            {
+                FilterMode::Nearest => GpuMipmapFilterMode::Nearest,
+                FilterMode::Linear => GpuMipmapFilterMode::Linear,
+            });
+        if let Some(compare) = descriptor.compare
+        {
+            sampler_descriptor.compare(compare_function(compare));
+        }
+        if let Some(label) = &descriptor.label
+        {
+            sampler_descriptor.label(label);
+        }
+        self.gpu_device.create_sampler_with_descriptor(&sampler_descriptor)
+    }
+
+
+    fn create_shader_module(&self, descriptor: &ShaderModuleDescriptor) -> GpuShaderModule
+    {
+        let mut shader_module_descriptor = GpuShaderModuleDescriptor::new(&descriptor.code);
+        if let Some(label) = &descriptor.label
+        {
+            shader_module_descriptor.label(label);
+        }
+        self.gpu_device.create_shader_module(&shader_module_descriptor)
+    }
+
+
+    fn create_render_pipeline(&self, descriptor: &RenderPipelineDescriptor<Self>) -> GpuRenderPipeline
+    {
+        let mut vertex_state = GpuVertexState::new(&descriptor.vertex.entry_point, &descriptor.vertex.module);
+        vertex_state.buffers(&js_array(descriptor.vertex.buffers.iter().map(vertex_buffer_layout)));
+
+        let mut multisample_state = GpuMultisampleState::new();
+        multisample_state
+            .count(descriptor.multisample.count)
+            .alpha_to_coverage_enabled(descriptor.multisample.alpha_to_coverage_enabled);
+
+        let mut render_pipeline_descriptor = GpuRenderPipelineDescriptor::new(
+            &self.pipeline_layout(&descriptor.bind_group_layouts), &vertex_state,
+        );
+        render_pipeline_descriptor
+            .primitive(&primitive_state(&descriptor.primitive))
+            .multisample(&multisample_state);
+        if let Some(fragment) = &descriptor.fragment
+        {
+            let targets = js_array(fragment.targets.iter().map(color_target_state));
+            render_pipeline_descriptor.fragment(&GpuFragmentState::new(&fragment.entry_point, &fragment.module, &targets));
+        }
+        if let Some(depth_stencil) = &descriptor.depth_stencil
+        {
+            render_pipeline_descriptor.depth_stencil(&GpuDepthStencilState::new(
+                compare_function(depth_stencil.depth_compare),
+                depth_stencil.depth_write_enabled,
+                depth_stencil.format.into(),
+            ));
+        }
+        if let Some(label) = &descriptor.label
+        {
+            render_pipeline_descriptor.label(label);
+        }
+        self.gpu_device.create_render_pipeline(&render_pipeline_descriptor)
+    }
+
+
+    fn create_compute_pipeline(&self, descriptor: &ComputePipelineDescriptor<Self>) -> GpuComputePipeline
+    {
+        let compute_stage = GpuProgrammableStage::new(&descriptor.entry_point, &descriptor.module);
+        let mut compute_pipeline_descriptor = GpuComputePipelineDescriptor::new(
+            &self.pipeline_layout(&descriptor.bind_group_layouts), &compute_stage,
+        );
+        if let Some(label) = &descriptor.label
+        {
+            compute_pipeline_descriptor.label(label);
+        }
+        self.gpu_device.create_compute_pipeline(&compute_pipeline_descriptor)
+    }
+
+
+    fn render_bind_group_layout(&self, pipeline: &GpuRenderPipeline, index: u32) -> GpuBindGroupLayout
+    {
+        pipeline.get_bind_group_layout(index)
+    }
+
+
+    fn compute_bind_group_layout(&self, pipeline: &GpuComputePipeline, index: u32) -> GpuBindGroupLayout
+    {
+        pipeline.get_bind_group_layout(index)
+    }
+
+
+    fn create_bind_group_layout(&self, descriptor: &BindGroupLayoutDescriptor) -> GpuBindGroupLayout
+    {
+        let entries = js_array(descriptor.entries.iter().map(bind_group_layout_entry));
+        let mut bind_group_layout_descriptor = GpuBindGroupLayoutDescriptor::new(&entries);
+        if let Some(label) = &descriptor.label
+        {
+            bind_group_layout_descriptor.label(label);
+        }
+        self.gpu_device.create_bind_group_layout(&bind_group_layout_descriptor)
+    }
+
+
+    fn create_bind_group(&self, descriptor: &BindGroupDescriptor<Self>) -> GpuBindGroup
+    {
+        let entries = js_array(descriptor.entries.iter().map(|entry|
+        {
+            let resource: JsValue = match &entry.resource
+            {
+                BindingResource::Buffer { buffer, offset, size } =>
+                {
+                    let mut buffer_binding = GpuBufferBinding::new(buffer);
+                    buffer_binding.offset(*offset as f64);
+                    if let Some(size) = size
+                    {
+                        buffer_binding.size(*size as f64);
+                    }
+                    buffer_binding.into()
+                },
+                BindingResource::Sampler(sampler) => sampler.into(),
+                BindingResource::TextureView(view) => view.into(),
+            };
+            GpuBindGroupEntry::new(entry.binding, &resource)
+        }));
+
+        let mut bind_group_descriptor = GpuBindGroupDescriptor::new(&entries, &descriptor.layout);
+        if let Some(label) = &descriptor.label
+        {
+            bind_group_descriptor.label(label);
+        }
+        self.gpu_device.create_bind_group(&bind_group_descriptor)
+    }
+
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer<Self>>)
+    {
+        let command_buffers = js_array(command_buffers.iter().map(|command_buffer|
+        {
+            let mut command_encoder_descriptor = GpuCommandEncoderDescriptor::new();
+            if let Some(label) = &command_buffer.label
+            {
+                command_encoder_descriptor.label(label);
+            }
+            let command_encoder = self.gpu_device.create_command_encoder_with_descriptor(&command_encoder_descriptor);
+            encode(&command_encoder, &command_buffer.commands);
+            command_encoder.finish()
+        }));
+        self.queue.submit(&command_buffers);
+    }
+}
+
+
+fn encode(command_encoder: &GpuCommandEncoder, commands: &[Command<WebBackend>])
+{
+    let mut render_pass: Option<GpuRenderPassEncoder> = None;
+    let mut compute_pass: Option<GpuComputePassEncoder> = None;
+
+    for command in commands
+    {
+        match command
+        {
+            Command::BeginRenderPass(descriptor) =>
+            {
+                render_pass = Some(command_encoder.begin_render_pass(&render_pass_descriptor(descriptor)));
+            },
+            Command::BeginComputePass { label } =>
+            {
+                let mut compute_pass_descriptor = GpuComputePassDescriptor::new();
+                if let Some(label) = label
+                {
+                    compute_pass_descriptor.label(label);
+                }
+                compute_pass = Some(command_encoder.begin_compute_pass_with_descriptor(&compute_pass_descriptor));
+            },
+            Command::EndRenderPass =>
+            {
+                if let Some(pass) = render_pass.take() { pass.end(); }
+            },
+            Command::EndComputePass =>
+            {
+                if let Some(pass) = compute_pass.take() { pass.end(); }
+            },
+            Command::SetBindGroup { index, bind_group, dynamic_offsets } =>
+            {
+                let dynamic_offsets = js_array(dynamic_offsets.iter().copied());
+                if let Some(pass) = &render_pass
+                {
+                    pass.set_bind_group_with_u32_sequence(*index, Some(bind_group), &dynamic_offsets);
+                }
+                if let Some(pass) = &compute_pass
+                {
+                    pass.set_bind_group_with_u32_sequence(*index, Some(bind_group), &dynamic_offsets);
+                }
+            },
+            Command::SetComputePipeline(pipeline) =>
+            {
+                if let Some(pass) = &compute_pass { pass.set_pipeline(pipeline); }
+            },
+            Command::DispatchWorkgroups { x, y, z } =>
+            {
+                if let Some(pass) = &compute_pass
+                {
+                    pass.dispatch_workgroups_with_workgroup_count_y_and_workgroup_count_z(*x, *y, *z);
+                }
+            },
+            Command::CopyBufferToBuffer { source, source_offset, destination, destination_offset, size } =>
+            {
+                command_encoder.copy_buffer_to_buffer_with_f64_and_f64_and_f64(
+                    source, *source_offset as f64, destination, *destination_offset as f64, *size as f64,
+                );
+            },
+            command =>
+            {
+                if let Some(pass) = &render_pass
+                {
+                    encode_render_command(pass, command);
+                }
+            },
+        }
+    }
+
+    // a pass that wasn't ended would make finish() fail
+    if let Some(pass) = render_pass { pass.end(); }
+    if let Some(pass) = compute_pass { pass.end(); }
+}
+
+
+fn encode_render_command(pass: &GpuRenderPassEncoder, command: &Command<WebBackend>)
+{
+    match command
+    {
+        Command::SetPipeline(pipeline) => pass.set_pipeline(pipeline),
+        Command::SetVertexBuffer { slot, buffer, offset, size } => match size
+        {
+            Some(size) => pass.set_vertex_buffer_with_f64_and_f64(*slot, Some(buffer), *offset as f64, *size as f64),
+            None => pass.set_vertex_buffer_with_f64(*slot, Some(buffer), *offset as f64),
+        },
+        Command::SetIndexBuffer { buffer, format, offset } =>
+        {
+            pass.set_index_buffer_with_f64(buffer, index_format(*format), *offset as f64);
+        },
+        Command::SetViewport { x, y, width, height, min_depth, max_depth } =>
+        {
+            pass.set_viewport(*x, *y, *width, *height, *min_depth, *max_depth);
+        },
+        Command::SetScissorRect { x, y, width, height } => pass.set_scissor_rect(*x, *y, *width, *height),
+        Command::SetBlendConstant([r, g, b, a]) => pass.set_blend_constant_with_gpu_color_dict(&GpuColorDict::new(*a, *b, *g, *r)),
+        Command::Draw { vertex_count, instance_count, first_vertex, first_instance } =>
+        {
+            pass.draw_with_instance_count_and_first_vertex_and_first_instance(
+                *vertex_count, *instance_count, *first_vertex, *first_instance,
+            );
+        },
+        Command::DrawIndexed { index_count, instance_count, first_index, base_vertex, first_instance } =>
+        {
+            pass.draw_indexed_with_instance_count_and_first_index_and_base_vertex_and_first_instance(
+                *index_count, *instance_count, *first_index, *base_vertex, *first_instance,
+            );
+        },
+        Command::DrawIndexedIndirect { buffer, offset } => pass.draw_indexed_indirect_with_f64(buffer, *offset as f64),
+        _ => (),
+    }
+}
@@ -4,7 +4,9 @@ fn fragment_main(@builtin(position) pixel_position: vec4f) -> @location(0) vec4f
     let red = vec4(1.0, 0.0, 0.0, 1.0);
     let cyan = vec4(0.0, 1.0, 1.0, 1.0);
 
-    let grid = vec2u(pixel_position.xy) / 8u;
+    // scrolls to the right by two squares a second, the pattern repeats every two squares
+    let scroll = vec2f(16.0 - fract(frame.time) * 16.0, 0.0);
+    let grid = vec2u(pixel_position.xy + scroll) / 8u;
     let checker = (grid.x + grid.y) % 2u == 1u;
 
     return select(red, cyan, checker);
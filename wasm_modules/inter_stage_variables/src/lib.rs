@@ -1,11 +1,16 @@
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use web_sys::{GpuDevice, GpuCanvasContext, GpuTextureFormat};
+
+use frame_loop::Frame;
+
+use gpu_backend::TextureFormat;
+use gpu_backend::capture::CaptureBackend;
+use gpu_backend::web::WebBackend;
+
+mod renderer;
+use renderer::Renderer;
 
-use web_sys::
-{
-    GpuDevice, GpuCanvasContext, GpuTextureFormat, GpuShaderModuleDescriptor, GpuVertexState, GpuColorTargetState, 
-    GpuFragmentState, GpuRenderPipelineDescriptor, GpuRenderPassColorAttachment, GpuLoadOp, GpuStoreOp, GpuColorDict, 
-    GpuRenderPassDescriptor, GpuRenderPipeline,
-};
 
 #[wasm_bindgen]
 extern "C"
@@ -16,11 +21,11 @@ extern "C"
 
 
 #[wasm_bindgen]
-pub struct Scene 
+pub struct Scene
 {
-    gpu_device: GpuDevice,
     context: GpuCanvasContext,
-    render_pipeline: GpuRenderPipeline,
+    texture_format: TextureFormat,
+    renderer: Renderer<CaptureBackend<WebBackend>>,
 }
 
 
@@ -29,55 +34,35 @@ impl Scene
 {
     pub fn create(
         gpu_device: GpuDevice, context: GpuCanvasContext, gpu_texture_format: GpuTextureFormat,
-    ) 
+    )
         -> Self
     {
-        let mut vert_shader_module_descriptor = GpuShaderModuleDescriptor::new(&include_str!("../shader/vert.wgsl"));
-        vert_shader_module_descriptor.label("hardcoded triangle");
-        let vert_shader_module = gpu_device.create_shader_module(&vert_shader_module_descriptor);
-
-        let mut frag_shader_module_descriptor = GpuShaderModuleDescriptor::new(&include_str!("../shader/frag.wgsl"));
-        frag_shader_module_descriptor.label("checkerboard");
-        let frag_shader_module = gpu_device.create_shader_module(&frag_shader_module_descriptor);
-
-        let vertex_state = GpuVertexState::new("vertex_main", &vert_shader_module);
-
-        let color_target_state = GpuColorTargetState::new(gpu_texture_format);
-        let fragment_state_targets = [color_target_state].iter().collect::<js_sys::Array>();
-        let fragment_state = GpuFragmentState::new("fragment_main", &frag_shader_module, &fragment_state_targets);
-
-        let render_layout = JsValue::from("auto");
-        let mut render_pipeline_descriptor = GpuRenderPipelineDescriptor::new(&render_layout, &vertex_state);
-        render_pipeline_descriptor.label("hardcoded checkerboard triangle pipeline");
-        render_pipeline_descriptor.fragment(&fragment_state);
-        let render_pipeline = gpu_device.create_render_pipeline(&render_pipeline_descriptor);
-
-        Scene 
-        {
-            gpu_device, context, render_pipeline,
-        }
+        let texture_format = TextureFormat::try_from(gpu_texture_format).unwrap();
+        let renderer = Renderer::new(CaptureBackend::new(WebBackend::new(gpu_device)), texture_format);
+
+        Scene { context, texture_format, renderer }
+    }
+
+
+    // renders with the time of an animation frame, the checkerboard scrolls with it
+    pub fn render_frame(&self, frame: &Frame)
+    {
+        let texture = self.renderer.backend().surface_texture(self.context.get_current_texture(), self.texture_format);
+        self.renderer.render_frame(&texture, frame.uniforms());
     }
 
 
     pub fn render(&self)
     {
-        let mut color_attachment = GpuRenderPassColorAttachment::new(
-            GpuLoadOp::Clear, GpuStoreOp::Store, &self.context.get_current_texture().create_view(),
-        );
-        color_attachment.clear_value(&GpuColorDict::new(1.0, 1.0, 0.0, 0.0));
-        let color_attachments = [color_attachment].iter().collect::<js_sys::Array>();
-        let mut render_pass_descriptor = GpuRenderPassDescriptor::new(&color_attachments);
-        render_pass_descriptor.label("basic canvas render pass");
-
-        let command_encoder = self.gpu_device.create_command_encoder();
-        command_encoder.set_label("Our command encoder");
-
-        let render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
-        render_pass_encoder.set_pipeline(&self.render_pipeline);
-        render_pass_encoder.draw(3);
-        render_pass_encoder.end();
-
-        let command_buffer = command_encoder.finish();
-        self.gpu_device.queue().submit(&[command_buffer].iter().collect::<js_sys::Array>());
+        let texture = self.renderer.backend().surface_texture(self.context.get_current_texture(), self.texture_format);
+        self.renderer.render(&texture);
+    }
+
+
+    pub fn capture_frame(&self) -> Result<String, String>
+    {
+        self.renderer.backend().start_capture();
+        self.render();
+        self.renderer.backend().finish_capture().to_json()
     }
 }
@@ -0,0 +1,167 @@
+use gpu_backend::
+{
+    Backend, ShaderModuleDescriptor, VertexState, ColorTargetState, FragmentState, RenderPipelineDescriptor,
+    RenderPassDescriptor, RenderPassColorAttachment, LoadOp, StoreOp, CommandEncoder, TextureFormat,
+};
+
+use frame_loop::{FrameBinding, FrameUniforms, with_frame_prelude};
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    render_pipeline: B::RenderPipeline,
+    frame_binding: FrameBinding<B>,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    pub fn new(backend: B, texture_format: TextureFormat) -> Self
+    {
+        let vert_source = with_frame_prelude(include_str!("../shader/vert.wgsl"));
+        let mut vert_shader_module_descriptor = ShaderModuleDescriptor::new(&vert_source);
+        vert_shader_module_descriptor.label("hardcoded triangle");
+        let vert_shader_module = backend.create_shader_module(&vert_shader_module_descriptor);
+
+        let frag_source = with_frame_prelude(include_str!("../shader/frag.wgsl"));
+        let mut frag_shader_module_descriptor = ShaderModuleDescriptor::new(&frag_source);
+        frag_shader_module_descriptor.label("checkerboard");
+        let frag_shader_module = backend.create_shader_module(&frag_shader_module_descriptor);
+
+        let vertex_state = VertexState::new("vertex_main", &vert_shader_module);
+
+        let fragment_state = FragmentState::new(
+            "fragment_main", &frag_shader_module, &[ColorTargetState::new(texture_format)],
+        );
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("hardcoded checkerboard triangle pipeline")
+            .fragment(fragment_state);
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+
+        let frame_binding = FrameBinding::new(&backend, &render_pipeline, &[&vert_source, &frag_source]);
+
+        Renderer { backend, render_pipeline, frame_binding }
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    // renders with the time of an animation frame, the checkerboard scrolls with it
+    pub fn render_frame(&self, target: &B::Texture, frame_uniforms: &FrameUniforms)
+    {
+        self.frame_binding.write(&self.backend, frame_uniforms);
+        self.render(target);
+    }
+
+
+    pub fn render(&self, target: &B::Texture)
+    {
+        let mut color_attachment = RenderPassColorAttachment::new(
+            LoadOp::Clear, StoreOp::Store, &self.backend.create_view(target),
+        );
+        color_attachment.clear_value([0.0, 0.0, 1.0, 1.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor.label("basic canvas render pass");
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("Our command encoder");
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+        self.frame_binding.set_bind_group(&mut render_pass_encoder);
+        render_pass_encoder.draw(3, 1);
+        render_pass_encoder.end();
+
+        self.backend.submit(vec![command_encoder.finish()]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{Command, TextureDescriptor};
+    use gpu_backend::recording::RecordingBackend;
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use frame_loop::FRAME_GROUP;
+
+    use software_gpu::{SoftwareBackend, assert_golden};
+    use software_gpu::rasterizer::Image;
+
+    use super::*;
+
+
+    #[test]
+    fn the_scrolling_checkerboard_binds_the_frame_uniforms()
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let renderer = Renderer::new(backend.clone(), TextureFormat::Bgra8Unorm);
+        let frame_uniforms = FrameUniforms { time: 0.25, ..Default::default() };
+        renderer.render_frame(&canvas_texture, &frame_uniforms);
+
+        assert_eq!(backend.buffer_contents(renderer.frame_binding.buffer()).unwrap(), frame_uniforms.to_bytes());
+        let commands = backend.commands();
+        assert!(matches!(&commands[0], Command::BeginRenderPass(descriptor)
+            if descriptor.color_attachments[0].clear_value == [0.0, 0.0, 1.0, 1.0]));
+        assert!(matches!(commands[1], Command::SetPipeline(pipeline) if pipeline == renderer.render_pipeline));
+        assert!(matches!(commands[2], Command::SetBindGroup { index: FRAME_GROUP, .. }));
+        assert!(matches!(commands[3], Command::Draw { vertex_count: 3, instance_count: 1, .. }));
+    }
+
+
+    // the checkerboard at a time of the animation, rendered on the software gpu
+    fn render_at(time: f32) -> Image
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        let renderer = Renderer::new(backend.clone(), TextureFormat::Rgba8Unorm);
+        renderer.render_frame(&target, &FrameUniforms { resolution: [96.0, 96.0], time, ..Default::default() });
+        backend.texture_image(&target).unwrap()
+    }
+
+
+    #[test]
+    fn the_golden_image_renders_through_the_renderer()
+    {
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "inter_stage_variables", &render_at(0.0));
+    }
+
+
+    #[test]
+    fn the_checkerboard_scrolls_with_the_frame_time()
+    {
+        let start = render_at(0.0);
+        assert_eq!(render_at(1.0), start);
+
+        // half a second later every square moved over by one, swapping the colors
+        let moved = render_at(0.5);
+        let (red, cyan) = ([255, 0, 0, 255], [0, 255, 255, 255]);
+        let mut swapped = 0;
+        for (x, y) in (0..96).flat_map(|y| (0..96).map(move |x| (x, y)))
+        {
+            match (start.pixel(x, y), moved.pixel(x, y))
+            {
+                (before, after) if before == red || before == cyan =>
+                {
+                    assert_eq!(after, if before == red { cyan } else { red });
+                    swapped += 1;
+                },
+                (before, after) => assert_eq!(before, after),
+            }
+        }
+        assert!(swapped > 0);
+    }
+}
@@ -0,0 +1,268 @@
+use gpu_backend::
+{
+    Backend, ShaderModuleDescriptor, VertexState, FragmentState, RenderPipelineDescriptor, RenderPassDescriptor,
+    RenderPassColorAttachment, LoadOp, StoreOp, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, SamplerDescriptor, AddressMode, FilterMode, PrimitiveState, PrimitiveTopology,
+    TextureDescriptor, CommandEncoder, TextureFormat, BlendSettings,
+};
+use gpu_backend::texture_usage::{TEXTURE_BINDING, COPY_DST, RENDER_ATTACHMENT};
+use gpu_backend::shader_stage::FRAGMENT;
+
+use frame_loop::{FrameBinding, FrameUniforms, with_frame_prelude};
+
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    bind_groups: Vec<B::BindGroup>,
+    render_pipeline: B::RenderPipeline,
+    render_pipeline_2: B::RenderPipeline,
+    frame_binding: FrameBinding<B>,
+    blend_settings: BlendSettings,
+    blend_settings_2: BlendSettings,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    // the texture the image is copied into, the copy itself depends on where the image comes from
+    pub fn create_texture(backend: &B, size: [u32; 2]) -> B::Texture
+    {
+        let mut texture_descriptor = TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, size, TEXTURE_BINDING | COPY_DST | RENDER_ATTACHMENT,
+        );
+        texture_descriptor.label("image");
+        backend.create_texture(&texture_descriptor)
+    }
+
+
+    pub fn new(
+        backend: B,
+        texture_format: TextureFormat,
+        texture: &B::Texture,
+        blend_settings: BlendSettings,
+        blend_settings_2: BlendSettings,
+    )
+        -> Self
+    {
+        let render_source = with_frame_prelude(include_str!("../shader/render.wgsl"));
+        let mut render_shader_module_descriptor = ShaderModuleDescriptor::new(&render_source);
+        render_shader_module_descriptor.label("our hardcoded textured quad shaders");
+        let render_shader_module = backend.create_shader_module(&render_shader_module_descriptor);
+
+        let vertex_state = VertexState::new("vertex_main", &render_shader_module);
+        let fragment_state = FragmentState::new(
+            "fragment_main", &render_shader_module, &[blend_settings.color_target_state(texture_format)],
+        );
+
+        let vertex_state_2 = VertexState::new("vertex_main_2", &render_shader_module);
+        let fragment_state_2 = FragmentState::new(
+            "fragment_main_2", &render_shader_module, &[blend_settings_2.color_target_state(texture_format)],
+        );
+
+        let bind_group_layout_0 = backend.create_bind_group_layout(&BindGroupLayoutDescriptor::new(&[
+            BindGroupLayoutEntry::sampler(0, FRAGMENT),
+            BindGroupLayoutEntry::texture(1, FRAGMENT),
+        ]));
+        // the frame uniforms are in group 1 whether the shaders use them or not
+        let frame_bind_group_layout = FrameBinding::bind_group_layout(&backend);
+        let bind_group_layouts = [bind_group_layout_0.clone(), frame_bind_group_layout.clone()];
+
+        let mut primitive_state = PrimitiveState::new();
+        primitive_state.topology(PrimitiveTopology::TriangleStrip);
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("hardcoded textured quad pipeline")
+            .bind_group_layouts(&bind_group_layouts)
+            .fragment(fragment_state)
+            .primitive(primitive_state);
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+
+        let mut render_pipeline_2_descriptor = RenderPipelineDescriptor::new(vertex_state_2);
+        render_pipeline_2_descriptor
+            .label("hardcoded textured quad pipeline 2")
+            .bind_group_layouts(&bind_group_layouts)
+            .fragment(fragment_state_2)
+            .primitive(primitive_state);
+        let render_pipeline_2 = backend.create_render_pipeline(&render_pipeline_2_descriptor);
+
+        let frame_binding = FrameBinding::with_layout(&backend, &frame_bind_group_layout);
+
+        let view = backend.create_view(texture);
+        let mut bind_groups = Vec::new();
+        for i in 0..8
+        {
+            let mut sampler_descriptor = SamplerDescriptor::new();
+            sampler_descriptor
+                .address_mode_u(if (i & 1) == 1 { AddressMode::Repeat } else { AddressMode::ClampToEdge })
+                .address_mode_v(if (i & 2) == 2 { AddressMode::Repeat } else { AddressMode::ClampToEdge })
+                .mag_filter(if (i & 4) == 4 { FilterMode::Linear } else { FilterMode::Nearest });
+            let sampler = backend.create_sampler(&sampler_descriptor);
+
+            let bind_group_0_descriptor = BindGroupDescriptor::new(
+                vec![BindGroupEntry::sampler(0, &sampler), BindGroupEntry::texture_view(1, &view)],
+                &bind_group_layout_0,
+            );
+            bind_groups.push(backend.create_bind_group(&bind_group_0_descriptor));
+        }
+
+        Renderer
+        {
+            backend, bind_groups, render_pipeline, render_pipeline_2, frame_binding, blend_settings,
+            blend_settings_2,
+        }
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    pub fn render_frame(&self, target: &B::Texture, frame_uniforms: &FrameUniforms, ndx: usize)
+    {
+        self.frame_binding.write(&self.backend, frame_uniforms);
+        self.render(target, ndx);
+    }
+
+
+    // ndx picks one of the 8 samplers, bit 0 repeats u, bit 1 repeats v and bit 2 magnifies linearly
+    pub fn render(&self, target: &B::Texture, ndx: usize)
+    {
+        let mut color_attachment = RenderPassColorAttachment::new(
+            LoadOp::Clear, StoreOp::Store, &self.backend.create_view(target),
+        );
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor.label("basic canvas render pass");
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("render quad encoder");
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+
+        render_pass_encoder.set_bind_group(0, &self.bind_groups[ndx]);
+        self.frame_binding.set_bind_group(&mut render_pass_encoder);
+
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+        self.blend_settings.set_blend_constant(&mut render_pass_encoder);
+        render_pass_encoder.draw(4, 1);  // call our vertex shader 4 times
+
+        render_pass_encoder.set_pipeline(&self.render_pipeline_2);
+        self.blend_settings_2.set_blend_constant(&mut render_pass_encoder);
+        render_pass_encoder.draw(4, 1);  // call our vertex shader 4 times
+
+        render_pass_encoder.end();
+
+        self.backend.submit(vec![command_encoder.finish()]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{BlendMode, Command};
+    use gpu_backend::recording::{Call, RecordingBackend};
+
+    use frame_loop::FRAME_GROUP;
+
+    use software_gpu::{SoftwareBackend, assert_golden};
+
+    use super::*;
+
+
+    fn renderer() -> (Renderer<RecordingBackend>, <RecordingBackend as Backend>::Texture)
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let texture = Renderer::create_texture(&backend, [2, 2]);
+        backend.write_texture(&texture, 0, &[255; 16], 8, [2, 2]);
+        let renderer = Renderer::new(
+            backend, TextureFormat::Bgra8Unorm, &texture, BlendSettings::new(BlendMode::Alpha),
+            BlendSettings::new(BlendMode::Opaque),
+        );
+        (renderer, canvas_texture)
+    }
+
+
+    #[test]
+    fn there_is_a_sampler_for_every_combination_of_wrapping_and_filtering()
+    {
+        let (renderer, _) = renderer();
+        let samplers = renderer.backend().calls().iter()
+            .filter_map(|call| match call
+            {
+                Call::CreateSampler { descriptor, .. } => Some(descriptor.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(samplers.len(), 8);
+        assert_eq!(renderer.bind_groups.len(), 8);
+        assert_eq!(samplers[5].address_mode_u, AddressMode::Repeat);
+        assert_eq!(samplers[5].address_mode_v, AddressMode::ClampToEdge);
+        assert_eq!(samplers[5].mag_filter, FilterMode::Linear);
+    }
+
+
+    #[test]
+    fn both_quads_are_drawn_with_the_chosen_sampler_and_their_own_blending()
+    {
+        let (renderer, canvas_texture) = renderer();
+        renderer.render_frame(&canvas_texture, &FrameUniforms::default(), 3);
+
+        let commands = renderer.backend().commands();
+        assert!(matches!(&commands[1..], [
+            Command::SetBindGroup { index: 0, bind_group, .. },
+            Command::SetBindGroup { index: FRAME_GROUP, .. },
+            Command::SetPipeline(pipeline),
+            Command::SetBlendConstant(_),
+            Command::Draw { vertex_count: 4, instance_count: 1, .. },
+            Command::SetPipeline(pipeline_2),
+            Command::SetBlendConstant(_),
+            Command::Draw { vertex_count: 4, instance_count: 1, .. },
+            Command::EndRenderPass,
+        ] if *bind_group == renderer.bind_groups[3] && *pipeline == renderer.render_pipeline
+            && *pipeline_2 == renderer.render_pipeline_2));
+
+        let blends = renderer.backend().calls().iter()
+            .filter_map(|call| match call
+            {
+                Call::CreateRenderPipeline { descriptor, .. } =>
+                    Some(descriptor.fragment.as_ref().unwrap().targets[0].blend),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(blends[0].is_some());
+        assert!(blends[1].is_none());
+    }
+
+
+    #[test]
+    fn the_golden_image_renders_through_the_renderer()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        // a checkerboard of an opaque and a half transparent color stands in for the image
+        let texture = Renderer::create_texture(&backend, [8, 8]);
+        let pixels = (0..8 * 8)
+            .flat_map(|i| if (i % 8 + i / 8) % 2 == 0 { [255, 255, 0, 255] } else { [40, 0, 160, 128] })
+            .collect::<Vec<u8>>();
+        backend.write_texture(&texture, 0, &pixels, 8 * 4, [8, 8]);
+        let renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, &texture, BlendSettings::new(BlendMode::Alpha),
+            BlendSettings::new(BlendMode::Opaque),
+        );
+        renderer.render_frame(&target, &FrameUniforms { resolution: [96.0, 96.0], ..Default::default() }, 5);
+
+        let image = backend.texture_image(&target).unwrap();
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "loading_images", &image);
+    }
+}
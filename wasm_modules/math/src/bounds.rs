@@ -0,0 +1,327 @@
+use crate::{Mat4, Vec3, Vec4};
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb
+{
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere
+{
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+
+// the points p with normal.dot(p) + distance >= 0 are in front of the plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane
+{
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+
+// the six planes of a view volume with their normals pointing inwards
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum
+{
+    pub planes: [Plane; 6],
+}
+
+
+impl Aabb
+{
+    pub fn new(min: Vec3, max: Vec3) -> Self
+    {
+        Aabb { min, max }
+    }
+
+
+    // None without points
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self>
+    {
+        points.into_iter().fold(None, |aabb: Option<Aabb>, point| Some(match aabb
+        {
+            Some(aabb) => Aabb::new(aabb.min.min(point), aabb.max.max(point)),
+            None => Aabb::new(point, point),
+        }))
+    }
+
+
+    pub fn center(&self) -> Vec3
+    {
+        (self.min + self.max) * 0.5
+    }
+
+
+    // half the size along every axis
+    pub fn extents(&self) -> Vec3
+    {
+        (self.max - self.min) * 0.5
+    }
+
+
+    pub fn union(&self, other: &Aabb) -> Aabb
+    {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+
+    pub fn contains_point(&self, point: Vec3) -> bool
+    {
+        point.min(self.min) == self.min && point.max(self.max) == self.max
+    }
+
+
+    // the box around the transformed box, which is larger than the box itself under rotations
+    pub fn transform(&self, matrix: &Mat4) -> Aabb
+    {
+        let center = matrix.transform_point3(self.center());
+        let extents = self.extents();
+        let extents = matrix.cols[0].truncate().abs() * extents.x
+            + matrix.cols[1].truncate().abs() * extents.y
+            + matrix.cols[2].truncate().abs() * extents.z;
+        Aabb::new(center - extents, center + extents)
+    }
+
+
+    // the sphere through the corners
+    pub fn bounding_sphere(&self) -> Sphere
+    {
+        Sphere::new(self.center(), self.extents().length())
+    }
+}
+
+
+impl Sphere
+{
+    pub fn new(center: Vec3, radius: f32) -> Self
+    {
+        Sphere { center, radius }
+    }
+
+
+    // centered on the box around the points, which is close to the smallest sphere for most meshes
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Option<Self>
+    {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points.into_iter().map(|point| point.distance(center)).fold(0.0, f32::max);
+        Some(Sphere::new(center, radius))
+    }
+
+
+    pub fn contains_point(&self, point: Vec3) -> bool
+    {
+        point.distance(self.center) <= self.radius
+    }
+
+
+    // the radius grows by the largest scale of the matrix, so the sphere stays around what it bounded
+    pub fn transform(&self, matrix: &Mat4) -> Sphere
+    {
+        let scale = (0..3).map(|i| matrix.cols[i].truncate().length()).fold(0.0, f32::max);
+        Sphere::new(matrix.transform_point3(self.center), self.radius * scale)
+    }
+}
+
+
+impl Plane
+{
+    // normalized so distance_to returns actual distances
+    pub fn from_vec4(plane: Vec4) -> Self
+    {
+        let length = plane.truncate().length();
+        let plane = if length > 0.0 { plane / length } else { plane };
+        Plane { normal: plane.truncate(), distance: plane.w }
+    }
+
+
+    pub fn distance_to(&self, point: Vec3) -> f32
+    {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+
+impl Frustum
+{
+    // the planes of WebGPU's clip volume, -w <= x, y <= w and 0 <= z <= w, pulled back through the matrix.
+    // a view-projection matrix gives the frustum in world space, a projection matrix in view space
+    pub fn from_matrix(matrix: &Mat4) -> Self
+    {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|i| matrix.row(i));
+        Frustum
+        {
+            planes: [w + x, w - x, w + y, w - y, z, w - z].map(Plane::from_vec4),
+        }
+    }
+
+
+    pub fn contains_point(&self, point: Vec3) -> bool
+    {
+        self.planes.iter().all(|plane| plane.distance_to(point) >= 0.0)
+    }
+
+
+    // conservative: spheres touching a plane count as inside, and spheres just outside a corner
+    // of the frustum but in front of every plane aren't rejected either
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool
+    {
+        self.planes.iter().all(|plane| plane.distance_to(sphere.center) >= -sphere.radius)
+    }
+
+
+    // tests the corner furthest along each plane's normal, with the same caveat for corners as spheres
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool
+    {
+        self.planes.iter().all(|plane|
+        {
+            let corner = Vec3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use super::*;
+
+
+    const EPSILON: f32 = 1e-5;
+
+
+    fn camera_frustum() -> Frustum
+    {
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, Vec3::Y);
+        Frustum::from_matrix(&(Mat4::perspective(FRAC_PI_2, 1.0, 1.0, 100.0) * view))
+    }
+
+
+    #[test]
+    fn boxes_from_points()
+    {
+        assert_eq!(Aabb::from_points([]), None);
+        let aabb = Aabb::from_points([Vec3::new(1.0, -2.0, 3.0), Vec3::new(-1.0, 4.0, 3.0)]).unwrap();
+        assert_eq!(aabb, Aabb::new(Vec3::new(-1.0, -2.0, 3.0), Vec3::new(1.0, 4.0, 3.0)));
+        assert_eq!(aabb.center(), Vec3::new(0.0, 1.0, 3.0));
+        assert_eq!(aabb.extents(), Vec3::new(1.0, 3.0, 0.0));
+        assert!(aabb.contains_point(Vec3::new(1.0, 4.0, 3.0)));
+        assert!(!aabb.contains_point(Vec3::new(0.0, 0.0, 3.1)));
+
+        let point = Aabb::from_points([Vec3::ONE]).unwrap();
+        assert_eq!(point.extents(), Vec3::ZERO);
+        assert_eq!(point.union(&aabb), Aabb::new(Vec3::new(-1.0, -2.0, 1.0), Vec3::new(1.0, 4.0, 3.0)));
+        assert_eq!(point.bounding_sphere(), Sphere::new(Vec3::ONE, 0.0));
+    }
+
+
+    #[test]
+    fn transformed_boxes_still_hold_their_corners()
+    {
+        let aabb = Aabb::new(Vec3::new(-1.0, -2.0, -3.0), Vec3::new(1.0, 2.0, 3.0));
+        let matrix = Mat4::from_translation(Vec3::new(5.0, 0.0, 0.0))
+            * Mat4::from_rotation_z(FRAC_PI_4)
+            * Mat4::from_scale(Vec3::new(2.0, 1.0, 1.0));
+        let transformed = aabb.transform(&matrix);
+        let padded = Aabb::new(transformed.min - Vec3::splat(EPSILON), transformed.max + Vec3::splat(EPSILON));
+        for corner in 0..8
+        {
+            let corner = Vec3::new(
+                if corner & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                if corner & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                if corner & 4 == 0 { aabb.min.z } else { aabb.max.z },
+            );
+            assert!(padded.contains_point(matrix.transform_point3(corner)));
+        }
+        assert!(transformed.center().abs_diff_eq(Vec3::new(5.0, 0.0, 0.0), EPSILON));
+
+        // rotating by a quarter turn swaps the extents exactly
+        let turned = aabb.transform(&Mat4::from_rotation_z(FRAC_PI_2));
+        assert!(turned.extents().abs_diff_eq(Vec3::new(2.0, 1.0, 3.0), EPSILON));
+    }
+
+
+    #[test]
+    fn spheres_from_points_and_transforms()
+    {
+        assert_eq!(Sphere::from_points(Vec::new()), None);
+        let sphere = Sphere::from_points(vec![Vec3::new(-1.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0)]).unwrap();
+        assert_eq!(sphere, Sphere::new(Vec3::new(1.0, 0.5, 0.0), 2.0615528));
+        assert!(sphere.contains_point(Vec3::new(3.0, 0.0, 0.0)));
+        assert!(!sphere.contains_point(Vec3::new(3.5, 0.0, 0.0)));
+
+        // non-uniform scales grow the radius by the largest one
+        let matrix = Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)) * Mat4::from_scale(Vec3::new(1.0, 3.0, 2.0));
+        let moved = Sphere::new(Vec3::X, 1.0).transform(&matrix);
+        assert_eq!(moved, Sphere::new(Vec3::new(1.0, 1.0, 0.0), 3.0));
+    }
+
+
+    #[test]
+    fn planes_are_extracted_normalized_and_facing_inwards()
+    {
+        let frustum = Frustum::from_matrix(&Mat4::IDENTITY);
+        let inside = Vec3::new(0.0, 0.0, 0.5);
+        for plane in frustum.planes
+        {
+            assert!((plane.normal.length() - 1.0).abs() < EPSILON);
+            assert!(plane.distance_to(inside) > 0.0);
+        }
+        // the clip volume of WebGPU has depth from 0 to 1
+        assert!(frustum.contains_point(Vec3::new(1.0, -1.0, 0.0)));
+        assert!(frustum.contains_point(Vec3::new(-1.0, 1.0, 1.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -0.01)));
+        assert!(!frustum.contains_point(Vec3::new(1.01, 0.0, 0.5)));
+
+        let frustum = camera_frustum();
+        assert!((frustum.planes[4].distance_to(Vec3::ZERO) - 9.0).abs() < 1e-4);
+        assert!((frustum.planes[5].distance_to(Vec3::ZERO) - 90.0).abs() < 1e-3);
+    }
+
+
+    #[test]
+    fn culls_what_is_outside_the_view()
+    {
+        let frustum = camera_frustum();
+        let visible = |center: Vec3, radius: f32|
+        {
+            let sphere = Sphere::new(center, radius);
+            let aabb = Aabb::new(center - Vec3::splat(radius), center + Vec3::splat(radius));
+            assert_eq!(frustum.intersects_sphere(&sphere), frustum.intersects_aabb(&aabb));
+            frustum.intersects_sphere(&sphere)
+        };
+
+        assert!(visible(Vec3::ZERO, 1.0));
+        // behind the camera, and beyond the far plane
+        assert!(!visible(Vec3::new(0.0, 0.0, 20.0), 1.0));
+        assert!(!visible(Vec3::new(0.0, 0.0, -100.0), 1.0));
+        // at the origin the view is 10 units wide on either side, objects past it only show when they reach in
+        assert!(!visible(Vec3::new(13.0, 0.0, 0.0), 1.0));
+        assert!(visible(Vec3::new(10.5, 0.0, 0.0), 1.0));
+        assert!(!visible(Vec3::new(0.0, -13.0, 0.0), 1.0));
+        // the corners of a box reach further towards the slanted side planes than the sphere inside it
+        assert!(!frustum.intersects_sphere(&Sphere::new(Vec3::new(12.0, 0.0, 0.0), 1.0)));
+        assert!(frustum.intersects_aabb(&Aabb::new(Vec3::new(11.0, -1.0, -1.0), Vec3::new(13.0, 1.0, 1.0))));
+        // straddling the near plane, and large enough to hold the whole frustum
+        assert!(visible(Vec3::new(0.0, 0.0, 9.0), 0.5));
+        assert!(visible(Vec3::ZERO, 1000.0));
+        // a point object right on the left plane counts as visible
+        let left = frustum.planes[0];
+        let on_plane = Vec3::new(0.0, 0.0, -5.0) - left.normal * left.distance_to(Vec3::new(0.0, 0.0, -5.0));
+        assert!(frustum.intersects_sphere(&Sphere::new(on_plane, 0.0)));
+        assert!(frustum.intersects_aabb(&Aabb::new(on_plane, on_plane)));
+    }
+}
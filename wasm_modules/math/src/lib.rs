@@ -0,0 +1,83 @@
+mod vector;
+pub use vector::{Vec2, Vec3, Vec4};
+
+mod matrix;
+pub use matrix::{Mat3, Mat4};
+
+mod quat;
+pub use quat::Quat;
+
+mod bounds;
+pub use bounds::{Aabb, Sphere, Plane, Frustum};
+
+mod random;
+pub use random::SceneRng;
+
+
+// how a value is laid out in WGSL uniform and storage buffers: its size and alignment in bytes, and its
+// floats with the padding WGSL expects written as zeros, e.g. every mat3x3f column takes 4 floats
+pub trait WgslLayout
+{
+    const SIZE: usize;
+    const ALIGN: usize;
+
+    // out has to hold at least SIZE / 4 floats
+    fn write_wgsl(&self, out: &mut [f32]);
+
+    fn to_wgsl(&self) -> Vec<f32>
+    {
+        let mut out = vec![0.0; Self::SIZE / 4];
+        self.write_wgsl(&mut out);
+        out
+    }
+}
+
+
+impl WgslLayout for f32
+{
+    const SIZE: usize = 4;
+    const ALIGN: usize = 4;
+
+    fn write_wgsl(&self, out: &mut [f32])
+    {
+        out[0] = *self;
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn layouts_follow_the_wgsl_rules()
+    {
+        assert_eq!((f32::SIZE, f32::ALIGN), (4, 4));
+        assert_eq!((Vec2::SIZE, Vec2::ALIGN), (8, 8));
+        assert_eq!((Vec3::SIZE, Vec3::ALIGN), (12, 16));
+        assert_eq!((Vec4::SIZE, Vec4::ALIGN), (16, 16));
+        assert_eq!((Quat::SIZE, Quat::ALIGN), (16, 16));
+        assert_eq!((Mat3::SIZE, Mat3::ALIGN), (48, 16));
+        assert_eq!((Mat4::SIZE, Mat4::ALIGN), (64, 16));
+
+        // the rust types have no padding, so they can be viewed as plain floats
+        assert_eq!(std::mem::size_of::<Vec2>(), 8);
+        assert_eq!(std::mem::size_of::<Vec3>(), 12);
+        assert_eq!(std::mem::size_of::<Vec4>(), 16);
+        assert_eq!(std::mem::size_of::<Quat>(), 16);
+        assert_eq!(std::mem::size_of::<Mat3>(), 36);
+        assert_eq!(std::mem::size_of::<Mat4>(), 64);
+    }
+
+
+    #[test]
+    fn mat3_columns_are_padded_to_four_floats()
+    {
+        let matrix = Mat3::from_cols(Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0), Vec3::new(7.0, 8.0, 9.0));
+        assert_eq!(matrix.to_wgsl(), [1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0, 7.0, 8.0, 9.0, 0.0]);
+        assert_eq!(Vec3::new(1.0, 2.0, 3.0).to_wgsl(), [1.0, 2.0, 3.0]);
+        assert_eq!(Mat4::IDENTITY.to_wgsl(), Mat4::IDENTITY.to_cols_array());
+    }
+}
@@ -0,0 +1,742 @@
+use std::ops::{Mul, MulAssign};
+
+use crate::{Vec2, Vec3, Vec4, Quat, WgslLayout};
+
+
+// column-major like WGSL's mat3x3f, cols[i] is the i-th column
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat3
+{
+    pub cols: [Vec3; 3],
+}
+
+
+// column-major like WGSL's mat4x4f, so its 16 floats can be uploaded as they are
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4
+{
+    pub cols: [Vec4; 4],
+}
+
+
+impl Default for Mat3
+{
+    fn default() -> Self
+    {
+        Mat3::IDENTITY
+    }
+}
+
+
+impl Default for Mat4
+{
+    fn default() -> Self
+    {
+        Mat4::IDENTITY
+    }
+}
+
+
+impl Mat3
+{
+    pub const IDENTITY: Mat3 = Mat3::from_cols(Vec3::X, Vec3::Y, Vec3::Z);
+    pub const ZERO: Mat3 = Mat3::from_cols(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO);
+
+
+    pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self
+    {
+        Mat3 { cols: [x, y, z] }
+    }
+
+
+    pub fn from_cols_array(values: &[f32; 9]) -> Self
+    {
+        Mat3::from_cols(
+            Vec3::new(values[0], values[1], values[2]),
+            Vec3::new(values[3], values[4], values[5]),
+            Vec3::new(values[6], values[7], values[8]),
+        )
+    }
+
+
+    pub fn to_cols_array(&self) -> [f32; 9]
+    {
+        std::array::from_fn(|i| self.cols[i / 3][i % 3])
+    }
+
+
+    // the rotation and scale of a 3d transform
+    pub fn from_mat4(matrix: &Mat4) -> Self
+    {
+        Mat3::from_cols(matrix.cols[0].truncate(), matrix.cols[1].truncate(), matrix.cols[2].truncate())
+    }
+
+
+    // 2d transforms in homogeneous coordinates
+    pub fn from_translation(translation: Vec2) -> Self
+    {
+        Mat3::from_cols(Vec3::X, Vec3::Y, translation.extend(1.0))
+    }
+
+
+    pub fn from_rotation(angle: f32) -> Self
+    {
+        let (sin, cos) = angle.sin_cos();
+        Mat3::from_cols(Vec3::new(cos, sin, 0.0), Vec3::new(-sin, cos, 0.0), Vec3::Z)
+    }
+
+
+    pub fn from_scale(scale: Vec2) -> Self
+    {
+        Mat3::from_cols(Vec3::X * scale.x, Vec3::Y * scale.y, Vec3::Z)
+    }
+
+
+    // transforms normals by the inverse transpose, so they stay perpendicular to non-uniformly scaled surfaces
+    pub fn normal_matrix(matrix: &Mat4) -> Option<Self>
+    {
+        Mat3::from_mat4(matrix).inverse().map(|inverse| inverse.transpose())
+    }
+
+
+    pub fn row(&self, index: usize) -> Vec3
+    {
+        Vec3::new(self.cols[0][index], self.cols[1][index], self.cols[2][index])
+    }
+
+
+    pub fn transpose(&self) -> Self
+    {
+        Mat3::from_cols(self.row(0), self.row(1), self.row(2))
+    }
+
+
+    pub fn determinant(&self) -> f32
+    {
+        let [x, y, z] = self.cols;
+        x.dot(y.cross(z))
+    }
+
+
+    // None for singular matrices
+    pub fn inverse(&self) -> Option<Self>
+    {
+        let determinant = self.determinant();
+        if determinant == 0.0
+        {
+            return None;
+        }
+
+        // the rows of the inverse are the cross products of the columns
+        let [x, y, z] = self.cols;
+        Some(Mat3::from_cols(y.cross(z), z.cross(x), x.cross(y)).transpose() * (1.0 / determinant))
+    }
+
+
+    pub fn abs_diff_eq(&self, other: &Mat3, epsilon: f32) -> bool
+    {
+        self.cols.iter().zip(&other.cols).all(|(a, b)| a.abs_diff_eq(*b, epsilon))
+    }
+}
+
+
+impl Mat4
+{
+    pub const IDENTITY: Mat4 = Mat4::from_cols(
+        Vec4::new(1.0, 0.0, 0.0, 0.0),
+        Vec4::new(0.0, 1.0, 0.0, 0.0),
+        Vec4::new(0.0, 0.0, 1.0, 0.0),
+        Vec4::new(0.0, 0.0, 0.0, 1.0),
+    );
+    pub const ZERO: Mat4 = Mat4::from_cols(Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ZERO);
+
+
+    pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Self
+    {
+        Mat4 { cols: [x, y, z, w] }
+    }
+
+
+    pub fn from_cols_array(values: &[f32; 16]) -> Self
+    {
+        Mat4 { cols: std::array::from_fn(|i| Vec4::new(values[i * 4], values[i * 4 + 1], values[i * 4 + 2], values[i * 4 + 3])) }
+    }
+
+
+    pub fn to_cols_array(&self) -> [f32; 16]
+    {
+        *self.as_array()
+    }
+
+
+    pub fn as_array(&self) -> &[f32; 16]
+    {
+        // repr(C) columns of repr(C) vectors, 16 floats without padding
+        unsafe { &*(self as *const Mat4 as *const [f32; 16]) }
+    }
+
+
+    pub fn from_translation(translation: Vec3) -> Self
+    {
+        let mut matrix = Mat4::IDENTITY;
+        matrix.cols[3] = translation.extend(1.0);
+        matrix
+    }
+
+
+    pub fn from_scale(scale: Vec3) -> Self
+    {
+        Mat4::from_cols(
+            Vec4::new(scale.x, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, scale.y, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, scale.z, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+
+    pub fn from_rotation_x(angle: f32) -> Self
+    {
+        Mat4::from_mat3(&Mat3::from_cols(Vec3::X, Vec3::new(0.0, angle.cos(), angle.sin()), Vec3::new(0.0, -angle.sin(), angle.cos())))
+    }
+
+
+    pub fn from_rotation_y(angle: f32) -> Self
+    {
+        Mat4::from_mat3(&Mat3::from_cols(Vec3::new(angle.cos(), 0.0, -angle.sin()), Vec3::Y, Vec3::new(angle.sin(), 0.0, angle.cos())))
+    }
+
+
+    pub fn from_rotation_z(angle: f32) -> Self
+    {
+        Mat4::from_mat3(&Mat3::from_cols(Vec3::new(angle.cos(), angle.sin(), 0.0), Vec3::new(-angle.sin(), angle.cos(), 0.0), Vec3::Z))
+    }
+
+
+    // counter-clockwise when looking down the axis towards the origin, the axis doesn't need to be normalized
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self
+    {
+        Mat4::from_quat(Quat::from_axis_angle(axis, angle))
+    }
+
+
+    pub fn from_quat(rotation: Quat) -> Self
+    {
+        Mat4::from_mat3(&Mat3::from_cols(rotation * Vec3::X, rotation * Vec3::Y, rotation * Vec3::Z))
+    }
+
+
+    pub fn from_mat3(matrix: &Mat3) -> Self
+    {
+        let [x, y, z] = matrix.cols;
+        Mat4::from_cols(x.extend(0.0), y.extend(0.0), z.extend(0.0), Vec4::new(0.0, 0.0, 0.0, 1.0))
+    }
+
+
+    // scales first, then rotates, then translates
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self
+    {
+        let mut matrix = Mat4::from_quat(rotation);
+        for (col, scale) in matrix.cols.iter_mut().zip(scale.to_array())
+        {
+            *col *= scale;
+        }
+        matrix.cols[3] = translation.extend(1.0);
+        matrix
+    }
+
+
+    // maps view space, looking down -z, to WebGPU's clip space with depth from 0 at z_near to 1 at z_far
+    pub fn perspective(fov_y: f32, aspect: f32, z_near: f32, z_far: f32) -> Self
+    {
+        let f = 1.0 / (fov_y * 0.5).tan();
+        let range_inv = 1.0 / (z_near - z_far);
+        Mat4::from_cols(
+            Vec4::new(f / aspect, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, f, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, z_far * range_inv, -1.0),
+            Vec4::new(0.0, 0.0, z_near * z_far * range_inv, 0.0),
+        )
+    }
+
+
+    // depth goes from 1 at z_near to 0 at z_far, which spreads float precision evenly over the distance
+    pub fn perspective_reversed_z(fov_y: f32, aspect: f32, z_near: f32, z_far: f32) -> Self
+    {
+        let mut matrix = Mat4::perspective(fov_y, aspect, z_near, z_far);
+        let range_inv = 1.0 / (z_near - z_far);
+        matrix.cols[2].z = -z_near * range_inv;
+        matrix.cols[3].z = -z_near * z_far * range_inv;
+        matrix
+    }
+
+
+    // depth from 0 at z_near to 1 at z_far, like perspective
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, z_near: f32, z_far: f32) -> Self
+    {
+        Mat4::from_cols(
+            Vec4::new(2.0 / (right - left), 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / (top - bottom), 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 1.0 / (z_near - z_far), 0.0),
+            Vec4::new(
+                (right + left) / (left - right),
+                (top + bottom) / (bottom - top),
+                z_near / (z_near - z_far),
+                1.0,
+            ),
+        )
+    }
+
+
+    // depth from 1 at z_near to 0 at z_far, like perspective_reversed_z
+    pub fn orthographic_reversed_z(left: f32, right: f32, bottom: f32, top: f32, z_near: f32, z_far: f32) -> Self
+    {
+        let mut matrix = Mat4::orthographic(left, right, bottom, top, z_near, z_far);
+        matrix.cols[2].z = 1.0 / (z_far - z_near);
+        matrix.cols[3].z = z_far / (z_far - z_near);
+        matrix
+    }
+
+
+    // the camera's own transform: placed at eye with its -z axis pointing at target
+    pub fn aim(eye: Vec3, target: Vec3, up: Vec3) -> Self
+    {
+        let z_axis = (eye - target).normalize();
+        let x_axis = up.cross(z_axis).normalize();
+        let y_axis = z_axis.cross(x_axis).normalize();
+        Mat4::from_cols(x_axis.extend(0.0), y_axis.extend(0.0), z_axis.extend(0.0), eye.extend(1.0))
+    }
+
+
+    // the view matrix of a camera at eye looking at target, the inverse of aim
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self
+    {
+        let camera = Mat4::aim(eye, target, up);
+        let rotation = Mat3::from_mat4(&camera).transpose();
+        let mut view = Mat4::from_mat3(&rotation);
+        view.cols[3] = (-(rotation * eye)).extend(1.0);
+        view
+    }
+
+
+    pub fn row(&self, index: usize) -> Vec4
+    {
+        Vec4::new(self.cols[0][index], self.cols[1][index], self.cols[2][index], self.cols[3][index])
+    }
+
+
+    pub fn translation(&self) -> Vec3
+    {
+        self.cols[3].truncate()
+    }
+
+
+    pub fn transpose(&self) -> Self
+    {
+        Mat4::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+    }
+
+
+    pub fn determinant(&self) -> f32
+    {
+        let m = self.as_array();
+        let (b00, b01, b02) = (m[0] * m[5] - m[1] * m[4], m[0] * m[6] - m[2] * m[4], m[0] * m[7] - m[3] * m[4]);
+        let (b03, b04, b05) = (m[1] * m[6] - m[2] * m[5], m[1] * m[7] - m[3] * m[5], m[2] * m[7] - m[3] * m[6]);
+        let (b06, b07, b08) = (m[8] * m[13] - m[9] * m[12], m[8] * m[14] - m[10] * m[12], m[8] * m[15] - m[11] * m[12]);
+        let (b09, b10, b11) = (m[9] * m[14] - m[10] * m[13], m[9] * m[15] - m[11] * m[13], m[10] * m[15] - m[11] * m[14]);
+        b00 * b11 - b01 * b10 + b02 * b09 + b03 * b08 - b04 * b07 + b05 * b06
+    }
+
+
+    // None for singular matrices
+    pub fn inverse(&self) -> Option<Self>
+    {
+        let m = self.as_array();
+        let (b00, b01, b02) = (m[0] * m[5] - m[1] * m[4], m[0] * m[6] - m[2] * m[4], m[0] * m[7] - m[3] * m[4]);
+        let (b03, b04, b05) = (m[1] * m[6] - m[2] * m[5], m[1] * m[7] - m[3] * m[5], m[2] * m[7] - m[3] * m[6]);
+        let (b06, b07, b08) = (m[8] * m[13] - m[9] * m[12], m[8] * m[14] - m[10] * m[12], m[8] * m[15] - m[11] * m[12]);
+        let (b09, b10, b11) = (m[9] * m[14] - m[10] * m[13], m[9] * m[15] - m[11] * m[13], m[10] * m[15] - m[11] * m[14]);
+
+        let determinant = b00 * b11 - b01 * b10 + b02 * b09 + b03 * b08 - b04 * b07 + b05 * b06;
+        if determinant == 0.0
+        {
+            return None;
+        }
+        let d = 1.0 / determinant;
+
+        Some(Mat4::from_cols_array(&[
+            (m[5] * b11 - m[6] * b10 + m[7] * b09) * d,
+            (m[2] * b10 - m[1] * b11 - m[3] * b09) * d,
+            (m[13] * b05 - m[14] * b04 + m[15] * b03) * d,
+            (m[10] * b04 - m[9] * b05 - m[11] * b03) * d,
+            (m[6] * b08 - m[4] * b11 - m[7] * b07) * d,
+            (m[0] * b11 - m[2] * b08 + m[3] * b07) * d,
+            (m[14] * b02 - m[12] * b05 - m[15] * b01) * d,
+            (m[8] * b05 - m[10] * b02 + m[11] * b01) * d,
+            (m[4] * b10 - m[5] * b08 + m[7] * b06) * d,
+            (m[1] * b08 - m[0] * b10 - m[3] * b06) * d,
+            (m[12] * b04 - m[13] * b02 + m[15] * b00) * d,
+            (m[9] * b02 - m[8] * b04 - m[11] * b00) * d,
+            (m[5] * b07 - m[4] * b09 - m[6] * b06) * d,
+            (m[0] * b09 - m[1] * b07 + m[2] * b06) * d,
+            (m[13] * b01 - m[12] * b03 - m[14] * b00) * d,
+            (m[8] * b03 - m[9] * b01 + m[10] * b00) * d,
+        ]))
+    }
+
+
+    // for affine transforms, w is assumed to stay 1
+    pub fn transform_point3(&self, point: Vec3) -> Vec3
+    {
+        (*self * point.extend(1.0)).truncate()
+    }
+
+
+    // divides by w, e.g. to go from view space to normalized device coordinates
+    pub fn project_point3(&self, point: Vec3) -> Vec3
+    {
+        let clip = *self * point.extend(1.0);
+        clip.truncate() / clip.w
+    }
+
+
+    // directions ignore the translation
+    pub fn transform_vector3(&self, vector: Vec3) -> Vec3
+    {
+        (*self * vector.extend(0.0)).truncate()
+    }
+
+
+    pub fn abs_diff_eq(&self, other: &Mat4, epsilon: f32) -> bool
+    {
+        self.cols.iter().zip(&other.cols).all(|(a, b)| a.abs_diff_eq(*b, epsilon))
+    }
+}
+
+
+impl From<[f32; 16]> for Mat4
+{
+    fn from(values: [f32; 16]) -> Self
+    {
+        Mat4::from_cols_array(&values)
+    }
+}
+
+
+impl From<Mat4> for [f32; 16]
+{
+    fn from(matrix: Mat4) -> Self
+    {
+        matrix.to_cols_array()
+    }
+}
+
+
+impl Mul<Vec3> for Mat3
+{
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3
+    {
+        self.cols[0] * vector.x + self.cols[1] * vector.y + self.cols[2] * vector.z
+    }
+}
+
+
+impl Mul for Mat3
+{
+    type Output = Mat3;
+
+    fn mul(self, other: Mat3) -> Mat3
+    {
+        Mat3 { cols: other.cols.map(|col| self * col) }
+    }
+}
+
+
+impl Mul<f32> for Mat3
+{
+    type Output = Mat3;
+
+    fn mul(self, scale: f32) -> Mat3
+    {
+        Mat3 { cols: self.cols.map(|col| col * scale) }
+    }
+}
+
+
+impl MulAssign for Mat3
+{
+    fn mul_assign(&mut self, other: Mat3)
+    {
+        *self = *self * other;
+    }
+}
+
+
+impl Mul<Vec4> for Mat4
+{
+    type Output = Vec4;
+
+    fn mul(self, vector: Vec4) -> Vec4
+    {
+        self.cols[0] * vector.x + self.cols[1] * vector.y + self.cols[2] * vector.z + self.cols[3] * vector.w
+    }
+}
+
+
+// a * b applies b first, like in WGSL
+impl Mul for Mat4
+{
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4
+    {
+        Mat4 { cols: other.cols.map(|col| self * col) }
+    }
+}
+
+
+impl Mul<f32> for Mat4
+{
+    type Output = Mat4;
+
+    fn mul(self, scale: f32) -> Mat4
+    {
+        Mat4 { cols: self.cols.map(|col| col * scale) }
+    }
+}
+
+
+impl MulAssign for Mat4
+{
+    fn mul_assign(&mut self, other: Mat4)
+    {
+        *self = *self * other;
+    }
+}
+
+
+impl WgslLayout for Mat3
+{
+    const SIZE: usize = 48;
+    const ALIGN: usize = 16;
+
+    fn write_wgsl(&self, out: &mut [f32])
+    {
+        for (i, col) in self.cols.iter().enumerate()
+        {
+            col.extend(0.0).write_wgsl(&mut out[i * 4..]);
+        }
+    }
+}
+
+
+impl WgslLayout for Mat4
+{
+    const SIZE: usize = 64;
+    const ALIGN: usize = 16;
+
+    fn write_wgsl(&self, out: &mut [f32])
+    {
+        out[..16].copy_from_slice(self.as_array());
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_3, PI};
+
+    use super::*;
+
+
+    const EPSILON: f32 = 1e-5;
+
+
+    fn some_transform() -> Mat4
+    {
+        Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 0.5, 3.0), Quat::from_axis_angle(Vec3::new(1.0, 2.0, -1.0), 0.7), Vec3::new(4.0, -2.0, 1.0),
+        )
+    }
+
+
+    #[test]
+    fn storage_is_column_major()
+    {
+        let values = std::array::from_fn::<f32, 16, _>(|i| i as f32);
+        let matrix = Mat4::from_cols_array(&values);
+        assert_eq!(matrix.cols[1], Vec4::new(4.0, 5.0, 6.0, 7.0));
+        assert_eq!(matrix.row(1), Vec4::new(1.0, 5.0, 9.0, 13.0));
+        assert_eq!(matrix.to_cols_array(), values);
+        assert_eq!(<[f32; 16]>::from(Mat4::from(values)), values);
+        assert_eq!(Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)).as_array()[12..15], [1.0, 2.0, 3.0]);
+
+        let values = std::array::from_fn::<f32, 9, _>(|i| i as f32);
+        let matrix = Mat3::from_cols_array(&values);
+        assert_eq!(matrix.cols[2], Vec3::new(6.0, 7.0, 8.0));
+        assert_eq!(matrix.to_cols_array(), values);
+        assert_eq!(matrix.transpose().cols[0], Vec3::new(0.0, 3.0, 6.0));
+    }
+
+
+    #[test]
+    fn multiplication_applies_the_right_matrix_first()
+    {
+        let translation = Mat4::from_translation(Vec3::new(1.0, 0.0, 0.0));
+        let rotation = Mat4::from_rotation_z(FRAC_PI_2);
+        let point = Vec3::new(1.0, 0.0, 0.0);
+        // rotated onto y, then moved along x
+        assert!((translation * rotation).transform_point3(point).abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), EPSILON));
+        // moved to 2 on x, then rotated onto y
+        assert!((rotation * translation).transform_point3(point).abs_diff_eq(Vec3::new(0.0, 2.0, 0.0), EPSILON));
+
+        let mut matrix = translation;
+        matrix *= rotation;
+        assert_eq!(matrix, translation * rotation);
+        assert_eq!(Mat4::IDENTITY * some_transform(), some_transform());
+        assert_eq!(some_transform() * Mat4::IDENTITY, some_transform());
+        assert_eq!(Mat4::default(), Mat4::IDENTITY);
+        assert_eq!(Mat3::default(), Mat3::IDENTITY);
+
+        let a = Mat3::from_rotation(0.3);
+        let b = Mat3::from_scale(Vec2::new(2.0, 3.0));
+        let v = Vec3::new(1.0, 2.0, 1.0);
+        assert!(((a * b) * v).abs_diff_eq(a * (b * v), EPSILON));
+        let mut c = a;
+        c *= b;
+        assert_eq!(c, a * b);
+    }
+
+
+    #[test]
+    fn rotations_are_counter_clockwise()
+    {
+        assert!(Mat4::from_rotation_x(FRAC_PI_2).transform_vector3(Vec3::Y).abs_diff_eq(Vec3::Z, EPSILON));
+        assert!(Mat4::from_rotation_y(FRAC_PI_2).transform_vector3(Vec3::Z).abs_diff_eq(Vec3::X, EPSILON));
+        assert!(Mat4::from_rotation_z(FRAC_PI_2).transform_vector3(Vec3::X).abs_diff_eq(Vec3::Y, EPSILON));
+        assert!(Mat4::from_axis_angle(Vec3::X * 5.0, 0.4).abs_diff_eq(&Mat4::from_rotation_x(0.4), EPSILON));
+        assert!(Mat4::from_axis_angle(Vec3::Y, 0.4).abs_diff_eq(&Mat4::from_rotation_y(0.4), EPSILON));
+        assert!(Mat4::from_axis_angle(Vec3::Z, 0.4).abs_diff_eq(&Mat4::from_rotation_z(0.4), EPSILON));
+
+        let point = Mat3::from_rotation(FRAC_PI_2) * Vec3::new(1.0, 0.0, 1.0);
+        assert!(point.abs_diff_eq(Vec3::new(0.0, 1.0, 1.0), EPSILON));
+    }
+
+
+    #[test]
+    fn translation_and_scale()
+    {
+        let matrix = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)) * Mat4::from_scale(Vec3::new(2.0, 3.0, 4.0));
+        assert_eq!(matrix.transform_point3(Vec3::ONE), Vec3::new(3.0, 5.0, 7.0));
+        assert_eq!(matrix.transform_vector3(Vec3::ONE), Vec3::new(2.0, 3.0, 4.0));
+        assert_eq!(matrix.translation(), Vec3::new(1.0, 2.0, 3.0));
+
+        let composed = Mat4::from_translation(Vec3::new(4.0, -2.0, 1.0))
+            * Mat4::from_axis_angle(Vec3::new(1.0, 2.0, -1.0), 0.7)
+            * Mat4::from_scale(Vec3::new(2.0, 0.5, 3.0));
+        assert!(some_transform().abs_diff_eq(&composed, EPSILON));
+
+        let point = Mat3::from_translation(Vec2::new(2.0, 3.0)) * Mat3::from_scale(Vec2::new(2.0, 2.0)) * Vec3::new(1.0, 1.0, 1.0);
+        assert_eq!(point, Vec3::new(4.0, 5.0, 1.0));
+    }
+
+
+    #[test]
+    fn inverses_undo_the_transform()
+    {
+        let matrix = some_transform();
+        let inverse = matrix.inverse().unwrap();
+        assert!((matrix * inverse).abs_diff_eq(&Mat4::IDENTITY, EPSILON));
+        assert!((inverse * matrix).abs_diff_eq(&Mat4::IDENTITY, EPSILON));
+        assert!((matrix.determinant() - 3.0).abs() < 1e-4);
+        assert!((inverse.determinant() - 1.0 / 3.0).abs() < 1e-5);
+        assert_eq!(Mat4::from_scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        assert_eq!(Mat4::ZERO.determinant(), 0.0);
+
+        let matrix = Mat3::from_mat4(&matrix);
+        assert!((matrix * matrix.inverse().unwrap()).abs_diff_eq(&Mat3::IDENTITY, EPSILON));
+        assert!((matrix.determinant() - 3.0).abs() < 1e-4);
+        assert_eq!(Mat3::ZERO.inverse(), None);
+
+        assert!(Mat4::from_rotation_y(1.1).transpose().abs_diff_eq(&Mat4::from_rotation_y(-1.1), EPSILON));
+        assert_eq!(some_transform().transpose().transpose(), some_transform());
+    }
+
+
+    #[test]
+    fn normal_matrices_keep_normals_perpendicular()
+    {
+        let matrix = Mat4::from_scale(Vec3::new(4.0, 1.0, 1.0)) * Mat4::from_rotation_z(0.3);
+        let normal_matrix = Mat3::normal_matrix(&matrix).unwrap();
+        // a plane spanned by two tangents, its normal is their cross product
+        let (tangent, bitangent) = (Vec3::new(1.0, 1.0, 0.0), Vec3::Z);
+        let normal = normal_matrix * tangent.cross(bitangent);
+        assert!(normal.dot(matrix.transform_vector3(tangent)).abs() < EPSILON);
+        assert!(normal.dot(matrix.transform_vector3(bitangent)).abs() < EPSILON);
+    }
+
+
+    #[test]
+    fn perspective_maps_depth_to_webgpu_clip_space()
+    {
+        let projection = Mat4::perspective(FRAC_PI_3, 1.5, 1.0, 100.0);
+        assert!((projection.project_point3(Vec3::new(0.0, 0.0, -1.0)).z - 0.0).abs() < EPSILON);
+        assert!((projection.project_point3(Vec3::new(0.0, 0.0, -100.0)).z - 1.0).abs() < EPSILON);
+        let middle = projection.project_point3(Vec3::new(0.0, 0.0, -10.0)).z;
+        assert!(middle > 0.0 && middle < 1.0);
+
+        // the edges of the field of view land on the edges of clip space
+        let top = (FRAC_PI_3 * 0.5).tan() * 10.0;
+        let corner = projection.project_point3(Vec3::new(top * 1.5, top, -10.0));
+        assert!(corner.truncate().abs_diff_eq(Vec2::ONE, EPSILON));
+        assert_eq!((projection * Vec3::new(0.0, 0.0, -7.0).extend(1.0)).w, 7.0);
+
+        let reversed = Mat4::perspective_reversed_z(FRAC_PI_3, 1.5, 1.0, 100.0);
+        assert!((reversed.project_point3(Vec3::new(0.0, 0.0, -1.0)).z - 1.0).abs() < EPSILON);
+        assert!((reversed.project_point3(Vec3::new(0.0, 0.0, -100.0)).z - 0.0).abs() < EPSILON);
+        assert_eq!(reversed.cols[0], projection.cols[0]);
+        assert_eq!(reversed.cols[1], projection.cols[1]);
+    }
+
+
+    #[test]
+    fn orthographic_maps_the_box_to_clip_space()
+    {
+        let projection = Mat4::orthographic(-2.0, 6.0, -1.0, 3.0, 0.5, 10.5);
+        assert!(projection.project_point3(Vec3::new(-2.0, -1.0, -0.5)).abs_diff_eq(Vec3::new(-1.0, -1.0, 0.0), EPSILON));
+        assert!(projection.project_point3(Vec3::new(6.0, 3.0, -10.5)).abs_diff_eq(Vec3::new(1.0, 1.0, 1.0), EPSILON));
+        assert!(projection.project_point3(Vec3::new(2.0, 1.0, -5.5)).abs_diff_eq(Vec3::new(0.0, 0.0, 0.5), EPSILON));
+
+        let reversed = Mat4::orthographic_reversed_z(-2.0, 6.0, -1.0, 3.0, 0.5, 10.5);
+        assert!(reversed.project_point3(Vec3::new(-2.0, -1.0, -0.5)).abs_diff_eq(Vec3::new(-1.0, -1.0, 1.0), EPSILON));
+        assert!(reversed.project_point3(Vec3::new(6.0, 3.0, -10.5)).abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), EPSILON));
+    }
+
+
+    #[test]
+    fn look_at_is_the_inverse_of_aim()
+    {
+        let (eye, target) = (Vec3::new(3.0, 4.0, 5.0), Vec3::new(-1.0, 0.5, 0.0));
+        let camera = Mat4::aim(eye, target, Vec3::Y);
+        let view = Mat4::look_at(eye, target, Vec3::Y);
+        assert!((camera * view).abs_diff_eq(&Mat4::IDENTITY, EPSILON));
+        assert!(view.abs_diff_eq(&camera.inverse().unwrap(), EPSILON));
+
+        // the camera looks down its -z axis with y up
+        assert!(view.transform_point3(eye).abs_diff_eq(Vec3::ZERO, EPSILON));
+        let in_front = view.transform_point3(target);
+        assert!(in_front.truncate().abs_diff_eq(Vec2::ZERO, EPSILON));
+        assert!((in_front.z + eye.distance(target)).abs() < EPSILON);
+        assert!(view.transform_vector3(Vec3::Y).y > 0.0);
+
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, 2.0), Vec3::ZERO, Vec3::Y);
+        assert!(view.abs_diff_eq(&Mat4::from_translation(Vec3::new(0.0, 0.0, -2.0)), EPSILON));
+        let view = Mat4::look_at(Vec3::new(0.0, 0.0, -2.0), Vec3::ZERO, Vec3::Y);
+        assert!(view.abs_diff_eq(&(Mat4::from_translation(Vec3::new(0.0, 0.0, -2.0)) * Mat4::from_rotation_y(PI)), EPSILON));
+    }
+}
@@ -0,0 +1,291 @@
+use std::ops::{Mul, MulAssign, Neg};
+
+use crate::{Vec3, Vec4, WgslLayout};
+
+
+// a rotation as a unit quaternion, xyz is the vector part and w the scalar part like in glTF
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat
+{
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+
+impl Default for Quat
+{
+    fn default() -> Self
+    {
+        Quat::IDENTITY
+    }
+}
+
+
+impl Quat
+{
+    pub const IDENTITY: Quat = Quat::new(0.0, 0.0, 0.0, 1.0);
+
+
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self
+    {
+        Quat { x, y, z, w }
+    }
+
+
+    // counter-clockwise when looking down the axis towards the origin, the axis doesn't need to be normalized
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self
+    {
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        let axis = axis.normalize() * sin;
+        Quat::new(axis.x, axis.y, axis.z, cos)
+    }
+
+
+    pub fn from_rotation_x(angle: f32) -> Self
+    {
+        Quat::from_axis_angle(Vec3::X, angle)
+    }
+
+
+    pub fn from_rotation_y(angle: f32) -> Self
+    {
+        Quat::from_axis_angle(Vec3::Y, angle)
+    }
+
+
+    pub fn from_rotation_z(angle: f32) -> Self
+    {
+        Quat::from_axis_angle(Vec3::Z, angle)
+    }
+
+
+    pub fn to_vec4(self) -> Vec4
+    {
+        Vec4::new(self.x, self.y, self.z, self.w)
+    }
+
+
+    pub fn from_vec4(vector: Vec4) -> Self
+    {
+        Quat::new(vector.x, vector.y, vector.z, vector.w)
+    }
+
+
+    pub fn to_array(self) -> [f32; 4]
+    {
+        [self.x, self.y, self.z, self.w]
+    }
+
+
+    pub fn dot(self, other: Quat) -> f32
+    {
+        self.to_vec4().dot(other.to_vec4())
+    }
+
+
+    pub fn length(self) -> f32
+    {
+        self.to_vec4().length()
+    }
+
+
+    pub fn normalize(self) -> Self
+    {
+        Quat::from_vec4(self.to_vec4().normalize())
+    }
+
+
+    pub fn conjugate(self) -> Self
+    {
+        Quat::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+
+    // for unit quaternions this is the conjugate
+    pub fn inverse(self) -> Self
+    {
+        Quat::from_vec4(self.conjugate().to_vec4() / self.dot(self))
+    }
+
+
+    // takes the shorter way around, and falls back to a normalized lerp when the rotations are nearly the same
+    pub fn slerp(self, other: Quat, t: f32) -> Self
+    {
+        let mut cos = self.dot(other);
+        let mut other = other;
+        if cos < 0.0
+        {
+            cos = -cos;
+            other = -other;
+        }
+
+        if cos > 0.9995
+        {
+            return Quat::from_vec4(self.to_vec4().lerp(other.to_vec4(), t)).normalize();
+        }
+
+        let angle = cos.acos();
+        let sin = angle.sin();
+        let a = ((1.0 - t) * angle).sin() / sin;
+        let b = (t * angle).sin() / sin;
+        Quat::from_vec4(self.to_vec4() * a + other.to_vec4() * b)
+    }
+
+
+    // q and -q are the same rotation
+    pub fn abs_diff_eq(self, other: Quat, epsilon: f32) -> bool
+    {
+        self.to_vec4().abs_diff_eq(other.to_vec4(), epsilon) || self.to_vec4().abs_diff_eq(-other.to_vec4(), epsilon)
+    }
+}
+
+
+impl Neg for Quat
+{
+    type Output = Quat;
+
+    fn neg(self) -> Quat
+    {
+        Quat::from_vec4(-self.to_vec4())
+    }
+}
+
+
+// a * b rotates by b first, like matrices
+impl Mul for Quat
+{
+    type Output = Quat;
+
+    fn mul(self, other: Quat) -> Quat
+    {
+        let (a, b) = (self, other);
+        Quat::new(
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        )
+    }
+}
+
+
+impl MulAssign for Quat
+{
+    fn mul_assign(&mut self, other: Quat)
+    {
+        *self = *self * other;
+    }
+}
+
+
+impl Mul<Vec3> for Quat
+{
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3
+    {
+        // v + 2w(q x v) + 2q x (q x v), with q the vector part
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = q.cross(vector) * 2.0;
+        vector + t * self.w + q.cross(t)
+    }
+}
+
+
+impl WgslLayout for Quat
+{
+    const SIZE: usize = 16;
+    const ALIGN: usize = 16;
+
+    fn write_wgsl(&self, out: &mut [f32])
+    {
+        out[..4].copy_from_slice(&self.to_array());
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use super::*;
+    use crate::Mat4;
+
+
+    const EPSILON: f32 = 1e-5;
+
+
+    #[test]
+    fn rotates_vectors_like_matrices()
+    {
+        assert!((Quat::from_rotation_x(FRAC_PI_2) * Vec3::Y).abs_diff_eq(Vec3::Z, EPSILON));
+        assert!((Quat::from_rotation_y(FRAC_PI_2) * Vec3::Z).abs_diff_eq(Vec3::X, EPSILON));
+        assert!((Quat::from_rotation_z(FRAC_PI_2) * Vec3::X).abs_diff_eq(Vec3::Y, EPSILON));
+
+        let axis = Vec3::new(1.0, -2.0, 0.5);
+        let rotation = Quat::from_axis_angle(axis, 1.3);
+        let vector = Vec3::new(0.3, 2.0, -1.0);
+        assert!((rotation * vector).abs_diff_eq(Mat4::from_axis_angle(axis, 1.3).transform_vector3(vector), EPSILON));
+        assert!((rotation * axis).abs_diff_eq(axis, EPSILON));
+        assert!(((rotation * vector).length() - vector.length()).abs() < EPSILON);
+        assert_eq!(Quat::IDENTITY * vector, vector);
+        assert_eq!(Quat::default(), Quat::IDENTITY);
+    }
+
+
+    #[test]
+    fn products_compose_rotations()
+    {
+        let a = Quat::from_rotation_x(0.4);
+        let b = Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 2.0);
+        let vector = Vec3::new(1.0, 2.0, 3.0);
+        assert!(((a * b) * vector).abs_diff_eq(a * (b * vector), EPSILON));
+        assert!(Mat4::from_quat(a * b).abs_diff_eq(&(Mat4::from_quat(a) * Mat4::from_quat(b)), EPSILON));
+        assert!((Quat::from_rotation_z(FRAC_PI_4) * Quat::from_rotation_z(FRAC_PI_4)).abs_diff_eq(Quat::from_rotation_z(FRAC_PI_2), EPSILON));
+
+        let mut c = a;
+        c *= b;
+        assert_eq!(c, a * b);
+        assert!((b * b.inverse()).abs_diff_eq(Quat::IDENTITY, EPSILON));
+        assert!(b.inverse().abs_diff_eq(b.conjugate(), EPSILON));
+        let scaled = Quat::from_vec4(b.to_vec4() * 0.5);
+        assert!((scaled.inverse() * scaled).abs_diff_eq(Quat::IDENTITY, EPSILON));
+    }
+
+
+    #[test]
+    fn normalizing_and_negating()
+    {
+        let scaled = Quat::from_vec4(Quat::from_rotation_y(0.8).to_vec4() * 4.0);
+        assert!((scaled.length() - 4.0).abs() < EPSILON);
+        assert!((scaled.normalize().length() - 1.0).abs() < EPSILON);
+        // q and -q are the same rotation
+        let vector = Vec3::new(1.0, 2.0, 3.0);
+        let rotation = Quat::from_rotation_y(0.8);
+        assert!(((-rotation) * vector).abs_diff_eq(rotation * vector, EPSILON));
+        assert!((-rotation).abs_diff_eq(rotation, EPSILON));
+        assert!(!rotation.abs_diff_eq(Quat::IDENTITY, EPSILON));
+    }
+
+
+    #[test]
+    fn slerp_interpolates_the_angle()
+    {
+        let a = Quat::from_rotation_z(0.0);
+        let b = Quat::from_rotation_z(FRAC_PI_2);
+        assert!(a.slerp(b, 0.0).abs_diff_eq(a, EPSILON));
+        assert!(a.slerp(b, 1.0).abs_diff_eq(b, EPSILON));
+        assert!(a.slerp(b, 0.5).abs_diff_eq(Quat::from_rotation_z(FRAC_PI_4), EPSILON));
+        assert!(a.slerp(b, 0.25).abs_diff_eq(Quat::from_rotation_z(FRAC_PI_2 * 0.25), EPSILON));
+
+        // 3/2 pi the long way is pi/2 the short way
+        let c = Quat::from_rotation_z(-FRAC_PI_2 * 3.0);
+        assert!(a.slerp(c, 0.5).abs_diff_eq(Quat::from_rotation_z(FRAC_PI_4), EPSILON));
+        assert!(a.slerp(a, 0.5).abs_diff_eq(a, EPSILON));
+        assert!(((a.slerp(Quat::from_rotation_x(PI * 0.999), 0.3)).length() - 1.0).abs() < EPSILON);
+    }
+}
@@ -0,0 +1,70 @@
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+
+// generates everything random about a scene. chacha gives the same numbers for a seed on every platform and
+// version, so a scene created with the seed of another is the same scene
+pub struct SceneRng
+{
+    seed: u32,
+    rng: ChaCha8Rng,
+}
+
+
+impl SceneRng
+{
+    pub fn new(seed: u32) -> Self
+    {
+        SceneRng { seed, rng: ChaCha8Rng::seed_from_u64(seed.into()) }
+    }
+
+
+    // a different scene every time, its seed still tells how to get it again
+    pub fn from_entropy() -> Self
+    {
+        SceneRng::new(thread_rng().gen())
+    }
+
+
+    pub fn seed(&self) -> u32
+    {
+        self.seed
+    }
+
+
+    // in [0, 1) without bounds, [0, min) with one and [min, max) with both
+    pub fn rand(&mut self, min: Option<f32>, max: Option<f32>) -> f32
+    {
+        if min.is_none() { return self.rng.gen_range(0.0..1.0); };
+        if max.is_none() { return self.rng.gen_range(0.0..min.unwrap()); };
+        self.rng.gen_range(min.unwrap()..max.unwrap())
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    fn numbers(rng: &mut SceneRng) -> Vec<f32>
+    {
+        (0..100).map(|_| rng.rand(Some(-0.9), Some(0.9))).collect()
+    }
+
+
+    #[test]
+    fn a_seed_gives_the_same_numbers()
+    {
+        let numbers_42 = numbers(&mut SceneRng::new(42));
+        assert_eq!(numbers(&mut SceneRng::new(42)), numbers_42);
+        assert_ne!(numbers(&mut SceneRng::new(43)), numbers_42);
+        assert!(numbers_42.iter().all(|number| (-0.9..0.9).contains(number)));
+
+        let mut rng = SceneRng::from_entropy();
+        let seed = rng.seed();
+        assert_eq!(numbers(&mut rng), numbers(&mut SceneRng::new(seed)));
+        assert!((0..100).map(|_| rng.rand(Some(0.5), None)).all(|number| (0.0..0.5).contains(&number)));
+    }
+}
@@ -0,0 +1,450 @@
+use std::ops::{Add, AddAssign, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Neg, Index, IndexMut};
+
+use crate::WgslLayout;
+
+
+macro_rules! vector
+{
+    ($name:ident, $n:literal, $wgsl_size:literal, $wgsl_align:literal, $($field:ident),+) =>
+    {
+        // repr(C) keeps the fields in order without padding, like a WGSL vector
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, Default, PartialEq)]
+        pub struct $name
+        {
+            $(pub $field: f32),+
+        }
+
+
+        impl $name
+        {
+            pub const ZERO: Self = Self { $($field: 0.0),+ };
+            pub const ONE: Self = Self { $($field: 1.0),+ };
+
+
+            pub const fn new($($field: f32),+) -> Self
+            {
+                Self { $($field),+ }
+            }
+
+
+            pub const fn splat(value: f32) -> Self
+            {
+                Self { $($field: value),+ }
+            }
+
+
+            pub fn to_array(self) -> [f32; $n]
+            {
+                [$(self.$field),+]
+            }
+
+
+            pub fn as_array(&self) -> &[f32; $n]
+            {
+                // the struct is repr(C) with only f32 fields, so it has the layout of the array
+                unsafe { &*(self as *const Self as *const [f32; $n]) }
+            }
+
+
+            pub fn as_array_mut(&mut self) -> &mut [f32; $n]
+            {
+                unsafe { &mut *(self as *mut Self as *mut [f32; $n]) }
+            }
+
+
+            pub fn dot(self, other: Self) -> f32
+            {
+                0.0 $(+ self.$field * other.$field)+
+            }
+
+
+            pub fn length_squared(self) -> f32
+            {
+                self.dot(self)
+            }
+
+
+            pub fn length(self) -> f32
+            {
+                self.length_squared().sqrt()
+            }
+
+
+            pub fn distance(self, other: Self) -> f32
+            {
+                (self - other).length()
+            }
+
+
+            // the zero vector stays zero instead of turning into NaNs
+            pub fn normalize(self) -> Self
+            {
+                let length = self.length();
+                if length > 0.0 { self / length } else { self }
+            }
+
+
+            pub fn lerp(self, other: Self, t: f32) -> Self
+            {
+                self + (other - self) * t
+            }
+
+
+            pub fn min(self, other: Self) -> Self
+            {
+                Self { $($field: self.$field.min(other.$field)),+ }
+            }
+
+
+            pub fn max(self, other: Self) -> Self
+            {
+                Self { $($field: self.$field.max(other.$field)),+ }
+            }
+
+
+            pub fn abs(self) -> Self
+            {
+                Self { $($field: self.$field.abs()),+ }
+            }
+
+
+            pub fn min_element(self) -> f32
+            {
+                f32::INFINITY $(.min(self.$field))+
+            }
+
+
+            pub fn max_element(self) -> f32
+            {
+                f32::NEG_INFINITY $(.max(self.$field))+
+            }
+
+
+            pub fn abs_diff_eq(self, other: Self, epsilon: f32) -> bool
+            {
+                true $(&& (self.$field - other.$field).abs() <= epsilon)+
+            }
+        }
+
+
+        impl From<[f32; $n]> for $name
+        {
+            fn from([$($field),+]: [f32; $n]) -> Self
+            {
+                Self { $($field),+ }
+            }
+        }
+
+
+        impl From<$name> for [f32; $n]
+        {
+            fn from(vector: $name) -> Self
+            {
+                vector.to_array()
+            }
+        }
+
+
+        impl Index<usize> for $name
+        {
+            type Output = f32;
+
+            fn index(&self, index: usize) -> &f32
+            {
+                &self.as_array()[index]
+            }
+        }
+
+
+        impl IndexMut<usize> for $name
+        {
+            fn index_mut(&mut self, index: usize) -> &mut f32
+            {
+                &mut self.as_array_mut()[index]
+            }
+        }
+
+
+        impl Add for $name
+        {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self
+            {
+                Self { $($field: self.$field + other.$field),+ }
+            }
+        }
+
+
+        impl Sub for $name
+        {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self
+            {
+                Self { $($field: self.$field - other.$field),+ }
+            }
+        }
+
+
+        // component-wise
+        impl Mul for $name
+        {
+            type Output = Self;
+
+            fn mul(self, other: Self) -> Self
+            {
+                Self { $($field: self.$field * other.$field),+ }
+            }
+        }
+
+
+        impl Mul<f32> for $name
+        {
+            type Output = Self;
+
+            fn mul(self, scale: f32) -> Self
+            {
+                Self { $($field: self.$field * scale),+ }
+            }
+        }
+
+
+        impl Mul<$name> for f32
+        {
+            type Output = $name;
+
+            fn mul(self, vector: $name) -> $name
+            {
+                vector * self
+            }
+        }
+
+
+        impl Div for $name
+        {
+            type Output = Self;
+
+            fn div(self, other: Self) -> Self
+            {
+                Self { $($field: self.$field / other.$field),+ }
+            }
+        }
+
+
+        impl Div<f32> for $name
+        {
+            type Output = Self;
+
+            fn div(self, divisor: f32) -> Self
+            {
+                Self { $($field: self.$field / divisor),+ }
+            }
+        }
+
+
+        impl Neg for $name
+        {
+            type Output = Self;
+
+            fn neg(self) -> Self
+            {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+
+        impl AddAssign for $name
+        {
+            fn add_assign(&mut self, other: Self)
+            {
+                *self = *self + other;
+            }
+        }
+
+
+        impl SubAssign for $name
+        {
+            fn sub_assign(&mut self, other: Self)
+            {
+                *self = *self - other;
+            }
+        }
+
+
+        impl MulAssign<f32> for $name
+        {
+            fn mul_assign(&mut self, scale: f32)
+            {
+                *self = *self * scale;
+            }
+        }
+
+
+        impl DivAssign<f32> for $name
+        {
+            fn div_assign(&mut self, divisor: f32)
+            {
+                *self = *self / divisor;
+            }
+        }
+
+
+        impl WgslLayout for $name
+        {
+            const SIZE: usize = $wgsl_size;
+            const ALIGN: usize = $wgsl_align;
+
+            fn write_wgsl(&self, out: &mut [f32])
+            {
+                out[..$n].copy_from_slice(self.as_array());
+            }
+        }
+    };
+}
+
+
+vector!(Vec2, 2, 8, 8, x, y);
+vector!(Vec3, 3, 12, 16, x, y, z);
+vector!(Vec4, 4, 16, 16, x, y, z, w);
+
+
+impl Vec2
+{
+    pub fn extend(self, z: f32) -> Vec3
+    {
+        Vec3::new(self.x, self.y, z)
+    }
+}
+
+
+impl Vec3
+{
+    pub const X: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+    pub const Y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+    pub const Z: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+
+
+    // right handed, X.cross(Y) is Z
+    pub fn cross(self, other: Vec3) -> Vec3
+    {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+
+    pub fn extend(self, w: f32) -> Vec4
+    {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+
+
+    pub fn truncate(self) -> Vec2
+    {
+        Vec2::new(self.x, self.y)
+    }
+}
+
+
+impl Vec4
+{
+    pub fn truncate(self) -> Vec3
+    {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn arithmetic_is_component_wise()
+    {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, -5.0, 6.0);
+        assert_eq!(a + b, Vec3::new(5.0, -3.0, 9.0));
+        assert_eq!(a - b, Vec3::new(-3.0, 7.0, -3.0));
+        assert_eq!(a * b, Vec3::new(4.0, -10.0, 18.0));
+        assert_eq!(b / a, Vec3::new(4.0, -2.5, 2.0));
+        assert_eq!(a * 2.0, 2.0 * a);
+        assert_eq!(a / 2.0, Vec3::new(0.5, 1.0, 1.5));
+        assert_eq!(-a, Vec3::new(-1.0, -2.0, -3.0));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        c *= 3.0;
+        c /= 2.0;
+        assert_eq!(c, b * 1.5);
+
+        let d = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(d + Vec4::ONE, Vec4::new(2.0, 3.0, 4.0, 5.0));
+        assert_eq!(Vec2::new(1.0, 2.0) - Vec2::splat(1.0), Vec2::new(0.0, 1.0));
+    }
+
+
+    #[test]
+    fn products_and_lengths()
+    {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        let b = Vec3::new(4.0, -5.0, 6.0);
+        assert_eq!(a.dot(b), 12.0);
+        assert_eq!(Vec3::X.cross(Vec3::Y), Vec3::Z);
+        assert_eq!(Vec3::Y.cross(Vec3::Z), Vec3::X);
+        assert_eq!(Vec3::Z.cross(Vec3::X), Vec3::Y);
+        assert_eq!(a.cross(b).dot(a), 0.0);
+        assert_eq!(a.cross(b).dot(b), 0.0);
+
+        assert_eq!(Vec2::new(3.0, 4.0).length(), 5.0);
+        assert_eq!(Vec4::new(1.0, 1.0, 1.0, 1.0).length_squared(), 4.0);
+        assert_eq!(Vec2::new(1.0, 1.0).distance(Vec2::new(4.0, 5.0)), 5.0);
+        assert!((b.normalize().length() - 1.0).abs() < 1e-6);
+        assert_eq!(Vec3::ZERO.normalize(), Vec3::ZERO);
+    }
+
+
+    #[test]
+    fn helpers_and_conversions()
+    {
+        let a = Vec3::new(1.0, -2.0, 3.0);
+        let b = Vec3::new(-1.0, 4.0, 2.0);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Vec3::new(0.0, 1.0, 2.5));
+        assert_eq!(a.min(b), Vec3::new(-1.0, -2.0, 2.0));
+        assert_eq!(a.max(b), Vec3::new(1.0, 4.0, 3.0));
+        assert_eq!(a.abs(), Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(a.min_element(), -2.0);
+        assert_eq!(a.max_element(), 3.0);
+        assert!(a.abs_diff_eq(a + Vec3::splat(1e-4), 1e-3));
+        assert!(!a.abs_diff_eq(b, 1e-3));
+
+        assert_eq!(Vec3::from([1.0, -2.0, 3.0]), a);
+        assert_eq!(<[f32; 3]>::from(a), [1.0, -2.0, 3.0]);
+        assert_eq!(a.as_array(), &[1.0, -2.0, 3.0]);
+        assert_eq!((a[0], a[1], a[2]), (1.0, -2.0, 3.0));
+        let mut c = a;
+        c[2] = 7.0;
+        assert_eq!(c.z, 7.0);
+
+        assert_eq!(a.extend(4.0), Vec4::new(1.0, -2.0, 3.0, 4.0));
+        assert_eq!(a.extend(4.0).truncate(), a);
+        assert_eq!(a.truncate().extend(3.0), a);
+    }
+
+
+    #[test]
+    #[should_panic]
+    fn indexing_past_the_last_component_panics()
+    {
+        let _ = Vec2::ZERO[2];
+    }
+}
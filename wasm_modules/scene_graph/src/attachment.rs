@@ -0,0 +1,80 @@
+use math::Mat4;
+
+
+// identifies the geometry a renderer draws for a node, what it refers to is up to the renderer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MeshId(pub u32);
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraProjection
+{
+    Perspective { fov_y: f32, z_near: f32, z_far: f32 },
+    // half_height is half the height of the view volume, its width follows the aspect
+    Orthographic { half_height: f32, z_near: f32, z_far: f32 },
+}
+
+
+impl CameraProjection
+{
+    pub fn matrix(&self, aspect: f32) -> Mat4
+    {
+        match *self
+        {
+            CameraProjection::Perspective { fov_y, z_near, z_far } => Mat4::perspective(fov_y, aspect, z_near, z_far),
+            CameraProjection::Orthographic { half_height, z_near, z_far } =>
+            {
+                let half_width = half_height * aspect;
+                Mat4::orthographic(-half_width, half_width, -half_height, half_height, z_near, z_far)
+            },
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind
+{
+    // shines down the node's -z axis from infinitely far away
+    Directional,
+    Point,
+    // a cone around the node's -z axis, angles are in radians from the axis
+    Spot { inner_angle: f32, outer_angle: f32 },
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light
+{
+    pub kind: LightKind,
+    pub color: [f32; 3],
+    pub intensity: f32,
+    // the distance the light fades out at, 0 for no limit
+    pub range: f32,
+}
+
+
+impl Light
+{
+    pub fn new(kind: LightKind, color: [f32; 3], intensity: f32) -> Self
+    {
+        Light { kind, color, intensity, range: 0.0 }
+    }
+
+
+    pub fn range(&mut self, range: f32) -> &mut Self
+    {
+        self.range = range;
+        self
+    }
+}
+
+
+// what a node carries besides its transform, a node can have any number of them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attachment
+{
+    Mesh(MeshId),
+    Camera(CameraProjection),
+    Light(Light),
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use math::{Aabb, Frustum, Sphere, Vec3};
+
+use crate::{MeshId, MeshInstance};
+
+
+// the volumes around a mesh in its own space, the sphere rejects quickly and the box more tightly
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshBounds
+{
+    pub aabb: Aabb,
+    pub sphere: Sphere,
+}
+
+
+impl MeshBounds
+{
+    // None for meshes without vertices
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Option<Self>
+    {
+        Some(MeshBounds { aabb: Aabb::from_points(points.clone())?, sphere: Sphere::from_points(points)? })
+    }
+
+
+    pub fn intersects(&self, frustum: &Frustum, instance: &MeshInstance) -> bool
+    {
+        frustum.intersects_sphere(&self.sphere.transform(&instance.world))
+            && frustum.intersects_aabb(&self.aabb.transform(&instance.world))
+    }
+}
+
+
+// the instances that may show in the frustum, in the order they came in. instances of meshes
+// without bounds are always kept
+pub fn cull(instances: &[MeshInstance], frustum: &Frustum, bounds: &HashMap<MeshId, MeshBounds>) -> Vec<MeshInstance>
+{
+    instances.iter()
+        .filter(|instance| bounds.get(&instance.mesh).is_none_or(|bounds| bounds.intersects(frustum, instance)))
+        .copied()
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use math::Mat4;
+
+    use super::*;
+    use crate::{Attachment, SceneGraph, Transform};
+
+
+    // a unit square in the xy plane
+    fn square() -> MeshBounds
+    {
+        let corners = [Vec3::new(-0.5, -0.5, 0.0), Vec3::new(0.5, -0.5, 0.0), Vec3::new(0.5, 0.5, 0.0), Vec3::new(-0.5, 0.5, 0.0)];
+        MeshBounds::from_points(corners).unwrap()
+    }
+
+
+    fn graph_with_squares(translations: &[Vec3]) -> SceneGraph
+    {
+        let mut graph = SceneGraph::new();
+        for translation in translations
+        {
+            let node = graph.add_node("square", None, Transform::from_translation(*translation)).unwrap();
+            graph.attach(node, Attachment::Mesh(MeshId(0))).unwrap();
+        }
+        graph.update_world_matrices();
+        graph
+    }
+
+
+    #[test]
+    fn bounds_of_a_mesh()
+    {
+        let bounds = square();
+        assert_eq!(bounds.aabb, Aabb::new(Vec3::new(-0.5, -0.5, 0.0), Vec3::new(0.5, 0.5, 0.0)));
+        assert_eq!(bounds.sphere, Sphere::new(Vec3::ZERO, 0.5f32.hypot(0.5)));
+        assert_eq!(MeshBounds::from_points(Vec::new()), None);
+    }
+
+
+    #[test]
+    fn keeps_the_order_of_what_is_visible()
+    {
+        // clip space itself, x and y from -1 to 1 and z from 0 to 1
+        let frustum = Frustum::from_matrix(&Mat4::IDENTITY);
+        let graph = graph_with_squares(&[
+            Vec3::new(0.0, 0.0, 0.5),
+            Vec3::new(3.0, 0.0, 0.5),       // right of the view
+            Vec3::new(-1.4, 1.4, 0.5),      // reaching in with its corner
+            Vec3::new(0.0, 0.0, -0.5),      // behind the near plane
+            Vec3::new(0.0, -1.5, 1.0),      // touching the bottom edge
+            Vec3::new(0.0, 0.0, 1.0),       // on the far plane
+        ]);
+        let bounds = HashMap::from([(MeshId(0), square())]);
+
+        let instances = graph.mesh_instances();
+        let visible = cull(&instances, &frustum, &bounds);
+        assert_eq!(visible, [instances[0], instances[2], instances[4], instances[5]]);
+    }
+
+
+    #[test]
+    fn spheres_reject_before_boxes()
+    {
+        let frustum = Frustum::from_matrix(&Mat4::IDENTITY);
+        // the sphere around the square reaches in at the corner of the view, the box doesn't
+        let graph = graph_with_squares(&[Vec3::new(-1.6, 1.6, 0.5)]);
+        let instance = graph.mesh_instances()[0];
+        let bounds = square();
+        assert!(frustum.intersects_sphere(&bounds.sphere.transform(&instance.world)));
+        assert!(!bounds.intersects(&frustum, &instance));
+    }
+
+
+    #[test]
+    fn scaled_and_unbounded_instances()
+    {
+        let frustum = Frustum::from_matrix(&Mat4::IDENTITY);
+        let mut graph = graph_with_squares(&[Vec3::new(3.0, 0.0, 0.5), Vec3::new(3.0, 0.0, 0.5)]);
+        let big = graph.roots()[0];
+        graph.transform_mut(big).unwrap().scale = Vec3::splat(5.0);
+        graph.attach(graph.roots()[1], Attachment::Mesh(MeshId(7))).unwrap();
+        graph.update_world_matrices();
+
+        // the grown square reaches into the view, the mesh without bounds is kept as it is
+        let instances = graph.mesh_instances();
+        let visible = cull(&instances, &frustum, &HashMap::from([(MeshId(0), square())]));
+        assert_eq!(visible.iter().map(|instance| instance.mesh).collect::<Vec<_>>(), [MeshId(0), MeshId(7)]);
+
+        // squashed flat along x it's just a line, still seen when it crosses the view
+        graph.transform_mut(big).unwrap().scale = Vec3::new(0.0, 1.0, 1.0);
+        graph.transform_mut(big).unwrap().translation = Vec3::new(1.0, 0.0, 0.5);
+        graph.update_world_matrices();
+        let visible = cull(&graph.mesh_instances(), &frustum, &HashMap::from([(MeshId(0), square())]));
+        assert_eq!(visible.iter().map(|instance| instance.node).collect::<Vec<_>>(), [big, graph.roots()[1]]);
+        assert_eq!(cull(&[], &frustum, &HashMap::new()), []);
+    }
+}
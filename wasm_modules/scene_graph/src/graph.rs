@@ -0,0 +1,658 @@
+use std::collections::HashMap;
+
+use math::{Frustum, Mat3, Mat4, Vec3, WgslLayout};
+
+use crate::{Attachment, CameraProjection, Light, LightKind, MeshBounds, MeshId, Transform, cull};
+
+
+// the generation tells a removed node from the one that reused its slot later
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId
+{
+    index: u32,
+    generation: u32,
+}
+
+
+#[derive(Clone, Debug)]
+struct Node
+{
+    name: String,
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    attachments: Vec<Attachment>,
+    world: Mat4,
+    // the world matrix of this node and every node below it is out of date
+    dirty: bool,
+}
+
+
+#[derive(Clone, Debug)]
+struct Slot
+{
+    generation: u32,
+    node: Option<Node>,
+}
+
+
+// nodes with local transforms relative to their parents. world matrices are recomputed by
+// update_world_matrices, only for nodes whose transform or ancestors changed since the last update
+#[derive(Clone, Debug, Default)]
+pub struct SceneGraph
+{
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    roots: Vec<NodeId>,
+}
+
+
+// a mesh attached to a node, with the world matrix the node had at the last update
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshInstance
+{
+    pub node: NodeId,
+    pub mesh: MeshId,
+    pub world: Mat4,
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraInstance
+{
+    pub node: NodeId,
+    pub projection: CameraProjection,
+    pub world: Mat4,
+}
+
+
+// a light with the position and -z axis of its node in world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightInstance
+{
+    pub node: NodeId,
+    pub light: Light,
+    pub position: Vec3,
+    pub direction: Vec3,
+}
+
+
+impl MeshInstance
+{
+    pub fn normal_matrix(&self) -> Mat3
+    {
+        Mat3::normal_matrix(&self.world).unwrap_or(Mat3::IDENTITY)
+    }
+}
+
+
+// struct Instance { world: mat4x4f, normal: mat3x3f }
+impl WgslLayout for MeshInstance
+{
+    const SIZE: usize = 112;
+    const ALIGN: usize = 16;
+
+    fn write_wgsl(&self, out: &mut [f32])
+    {
+        self.world.write_wgsl(&mut out[..16]);
+        self.normal_matrix().write_wgsl(&mut out[16..28]);
+    }
+}
+
+
+impl CameraInstance
+{
+    pub fn view(&self) -> Mat4
+    {
+        self.world.inverse().unwrap_or(Mat4::IDENTITY)
+    }
+
+
+    pub fn view_projection(&self, aspect: f32) -> Mat4
+    {
+        self.projection.matrix(aspect) * self.view()
+    }
+}
+
+
+// struct Light { position: vec3f, range: f32, direction: vec3f, intensity: f32, color: vec3f, kind: u32, cone: vec2f }
+// kind is 0 for directional, 1 for point and 2 for spot lights, cone holds the cosines of the spot angles
+impl WgslLayout for LightInstance
+{
+    const SIZE: usize = 64;
+    const ALIGN: usize = 16;
+
+    fn write_wgsl(&self, out: &mut [f32])
+    {
+        let (kind, cone) = match self.light.kind
+        {
+            LightKind::Directional => (0u32, [0.0, 0.0]),
+            LightKind::Point => (1, [0.0, 0.0]),
+            LightKind::Spot { inner_angle, outer_angle } => (2, [inner_angle.cos(), outer_angle.cos()]),
+        };
+        self.position.write_wgsl(&mut out[0..3]);
+        out[3] = self.light.range;
+        self.direction.write_wgsl(&mut out[4..7]);
+        out[7] = self.light.intensity;
+        out[8..11].copy_from_slice(&self.light.color);
+        out[11] = f32::from_bits(kind);
+        out[12..14].copy_from_slice(&cone);
+    }
+}
+
+
+impl SceneGraph
+{
+    pub fn new() -> Self
+    {
+        SceneGraph::default()
+    }
+
+
+    fn node(&self, id: NodeId) -> Option<&Node>
+    {
+        self.slots.get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut Node>
+    {
+        self.slots.get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+    }
+
+
+    fn existing_mut(&mut self, id: NodeId) -> Result<&mut Node, String>
+    {
+        self.node_mut(id).ok_or_else(|| format!("{:?} is not in the scene graph", id))
+    }
+
+
+    fn siblings_mut(&mut self, parent: Option<NodeId>) -> &mut Vec<NodeId>
+    {
+        match parent.filter(|parent| self.contains(*parent))
+        {
+            Some(parent) => &mut self.node_mut(parent).unwrap().children,
+            None => &mut self.roots,
+        }
+    }
+
+
+    pub fn add_node(&mut self, name: &str, parent: Option<NodeId>, transform: Transform) -> Result<NodeId, String>
+    {
+        if let Some(parent) = parent
+        {
+            self.existing_mut(parent)?;
+        }
+
+        let node = Node
+        {
+            name: name.to_string(), transform, parent, children: Vec::new(), attachments: Vec::new(),
+            world: Mat4::IDENTITY, dirty: true,
+        };
+        let id = match self.free.pop()
+        {
+            Some(index) =>
+            {
+                let slot = &mut self.slots[index as usize];
+                slot.node = Some(node);
+                NodeId { index, generation: slot.generation }
+            },
+            None =>
+            {
+                self.slots.push(Slot { generation: 0, node: Some(node) });
+                NodeId { index: self.slots.len() as u32 - 1, generation: 0 }
+            },
+        };
+        self.siblings_mut(parent).push(id);
+        Ok(id)
+    }
+
+
+    // removes the node along with everything below it
+    pub fn remove_node(&mut self, id: NodeId) -> Result<(), String>
+    {
+        let parent = self.existing_mut(id)?.parent;
+        self.siblings_mut(parent).retain(|sibling| *sibling != id);
+
+        let mut removed = vec![id];
+        while let Some(id) = removed.pop()
+        {
+            let slot = &mut self.slots[id.index as usize];
+            if let Some(node) = slot.node.take()
+            {
+                removed.extend(node.children);
+            }
+            slot.generation += 1;
+            self.free.push(id.index);
+        }
+        Ok(())
+    }
+
+
+    // moves the node under another parent, or to the roots for None. its local transform stays the same
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<(), String>
+    {
+        let old_parent = self.existing_mut(id)?.parent;
+        if let Some(parent) = parent
+        {
+            self.existing_mut(parent)?;
+            if self.ancestors(parent).chain([parent]).any(|ancestor| ancestor == id)
+            {
+                return Err(format!("{:?} can't become a child of its descendant {:?}", id, parent));
+            }
+        }
+
+        self.siblings_mut(old_parent).retain(|sibling| *sibling != id);
+        self.siblings_mut(parent).push(id);
+        let node = self.existing_mut(id)?;
+        node.parent = parent;
+        node.dirty = true;
+        Ok(())
+    }
+
+
+    pub fn contains(&self, id: NodeId) -> bool
+    {
+        self.node(id).is_some()
+    }
+
+
+    pub fn len(&self) -> usize
+    {
+        self.slots.len() - self.free.len()
+    }
+
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+
+
+    pub fn roots(&self) -> &[NodeId]
+    {
+        &self.roots
+    }
+
+
+    pub fn name(&self, id: NodeId) -> Option<&str>
+    {
+        self.node(id).map(|node| node.name.as_str())
+    }
+
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId>
+    {
+        self.node(id).and_then(|node| node.parent)
+    }
+
+
+    pub fn children(&self, id: NodeId) -> &[NodeId]
+    {
+        self.node(id).map(|node| node.children.as_slice()).unwrap_or_default()
+    }
+
+
+    // from the parent up to the root
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_
+    {
+        std::iter::successors(self.parent(id), |id| self.parent(*id))
+    }
+
+
+    // parents come before their children, and siblings in the order they were added
+    pub fn depth_first(&self) -> Vec<NodeId>
+    {
+        let mut order = Vec::with_capacity(self.len());
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(id) = stack.pop()
+        {
+            order.push(id);
+            stack.extend(self.children(id).iter().rev());
+        }
+        order
+    }
+
+
+    pub fn find(&self, name: &str) -> Option<NodeId>
+    {
+        self.depth_first().into_iter().find(|id| self.name(*id) == Some(name))
+    }
+
+
+    pub fn transform(&self, id: NodeId) -> Option<&Transform>
+    {
+        self.node(id).map(|node| &node.transform)
+    }
+
+
+    // borrowing the transform mutably marks the node for the next update
+    pub fn transform_mut(&mut self, id: NodeId) -> Option<&mut Transform>
+    {
+        self.node_mut(id).map(|node|
+        {
+            node.dirty = true;
+            &mut node.transform
+        })
+    }
+
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) -> Result<(), String>
+    {
+        let node = self.existing_mut(id)?;
+        if node.transform != transform
+        {
+            node.transform = transform;
+            node.dirty = true;
+        }
+        Ok(())
+    }
+
+
+    pub fn attach(&mut self, id: NodeId, attachment: Attachment) -> Result<(), String>
+    {
+        self.existing_mut(id)?.attachments.push(attachment);
+        Ok(())
+    }
+
+
+    pub fn clear_attachments(&mut self, id: NodeId) -> Result<(), String>
+    {
+        self.existing_mut(id)?.attachments.clear();
+        Ok(())
+    }
+
+
+    pub fn attachments(&self, id: NodeId) -> &[Attachment]
+    {
+        self.node(id).map(|node| node.attachments.as_slice()).unwrap_or_default()
+    }
+
+
+    // the world matrix as of the last update
+    pub fn world_matrix(&self, id: NodeId) -> Option<Mat4>
+    {
+        self.node(id).map(|node| node.world)
+    }
+
+
+    // recomputes the world matrices of dirty nodes and their descendants, returns how many changed
+    pub fn update_world_matrices(&mut self) -> usize
+    {
+        let mut updated = 0;
+        let mut stack = self.roots.iter().map(|root| (*root, Mat4::IDENTITY, false)).collect::<Vec<_>>();
+        while let Some((id, parent_world, parent_changed)) = stack.pop()
+        {
+            let Some(node) = self.node_mut(id)
+            else
+            {
+                continue;
+            };
+            let changed = node.dirty || parent_changed;
+            if changed
+            {
+                node.world = parent_world * node.transform.matrix();
+                node.dirty = false;
+                updated += 1;
+            }
+            let world = node.world;
+            stack.extend(node.children.iter().map(|child| (*child, world, changed)));
+        }
+        updated
+    }
+
+
+    fn attached<T>(&self, mut select: impl FnMut(NodeId, &Node, &Attachment) -> Option<T>) -> Vec<T>
+    {
+        self.depth_first().into_iter()
+            .flat_map(|id|
+            {
+                let node = self.node(id).unwrap();
+                node.attachments.iter().filter_map(|attachment| select(id, node, attachment)).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+
+    // in depth first order, ready to be written into a per-instance buffer
+    pub fn mesh_instances(&self) -> Vec<MeshInstance>
+    {
+        self.attached(|node, Node { world, .. }, attachment| match attachment
+        {
+            Attachment::Mesh(mesh) => Some(MeshInstance { node, mesh: *mesh, world: *world }),
+            _ => None,
+        })
+    }
+
+
+    // the mesh instances that may show in the frustum
+    pub fn visible_mesh_instances(&self, frustum: &Frustum, bounds: &HashMap<MeshId, MeshBounds>) -> Vec<MeshInstance>
+    {
+        cull(&self.mesh_instances(), frustum, bounds)
+    }
+
+
+    pub fn cameras(&self) -> Vec<CameraInstance>
+    {
+        self.attached(|node, Node { world, .. }, attachment| match attachment
+        {
+            Attachment::Camera(projection) => Some(CameraInstance { node, projection: *projection, world: *world }),
+            _ => None,
+        })
+    }
+
+
+    pub fn lights(&self) -> Vec<LightInstance>
+    {
+        self.attached(|node, Node { world, .. }, attachment| match attachment
+        {
+            Attachment::Light(light) => Some(LightInstance
+            {
+                node,
+                light: *light,
+                position: world.translation(),
+                direction: world.transform_vector3(-Vec3::Z).normalize(),
+            }),
+            _ => None,
+        })
+    }
+
+
+    // the instances packed one after another with the WGSL layout, for an array<Instance> storage buffer
+    pub fn write_instances<T: WgslLayout>(instances: &[T]) -> Vec<f32>
+    {
+        let stride = T::SIZE.next_multiple_of(T::ALIGN) / 4;
+        let mut out = vec![0.0; instances.len() * stride];
+        for (instance, chunk) in instances.iter().zip(out.chunks_mut(stride))
+        {
+            instance.write_wgsl(chunk);
+        }
+        out
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use std::f32::consts::FRAC_PI_2;
+
+    use math::Quat;
+
+    use super::*;
+
+
+    const EPSILON: f32 = 1e-5;
+
+
+    // sun > planet > moon, with the planet 10 units along x and the moon 2 units further
+    fn solar_system() -> (SceneGraph, [NodeId; 3])
+    {
+        let mut graph = SceneGraph::new();
+        let sun = graph.add_node("sun", None, Transform::IDENTITY).unwrap();
+        let planet = graph.add_node("planet", Some(sun), Transform::from_translation(Vec3::new(10.0, 0.0, 0.0))).unwrap();
+        let moon = graph.add_node("moon", Some(planet), Transform::from_translation(Vec3::new(2.0, 0.0, 0.0))).unwrap();
+        (graph, [sun, planet, moon])
+    }
+
+
+    #[test]
+    fn world_matrices_compose_down_the_hierarchy()
+    {
+        let (mut graph, [sun, planet, moon]) = solar_system();
+        assert_eq!(graph.update_world_matrices(), 3);
+        assert_eq!(graph.world_matrix(moon).unwrap().translation(), Vec3::new(12.0, 0.0, 0.0));
+
+        // turning the sun carries the planet and the moon around it
+        graph.transform_mut(sun).unwrap().rotation = Quat::from_rotation_y(FRAC_PI_2);
+        assert_eq!(graph.update_world_matrices(), 3);
+        assert!(graph.world_matrix(planet).unwrap().translation().abs_diff_eq(Vec3::new(0.0, 0.0, -10.0), EPSILON));
+        assert!(graph.world_matrix(moon).unwrap().translation().abs_diff_eq(Vec3::new(0.0, 0.0, -12.0), EPSILON));
+
+        let mut transform = *graph.transform(planet).unwrap();
+        transform.scale(Vec3::splat(2.0));
+        graph.set_transform(planet, transform).unwrap();
+        graph.update_world_matrices();
+        assert!(graph.world_matrix(moon).unwrap().translation().abs_diff_eq(Vec3::new(0.0, 0.0, -14.0), EPSILON));
+    }
+
+
+    #[test]
+    fn only_dirty_subtrees_are_updated()
+    {
+        let (mut graph, [sun, planet, moon]) = solar_system();
+        let comet = graph.add_node("comet", Some(sun), Transform::IDENTITY).unwrap();
+        assert_eq!(graph.update_world_matrices(), 4);
+        assert_eq!(graph.update_world_matrices(), 0);
+
+        graph.transform_mut(planet).unwrap().translation.y = 1.0;
+        assert_eq!(graph.update_world_matrices(), 2);
+        graph.transform_mut(moon).unwrap();
+        assert_eq!(graph.update_world_matrices(), 1);
+        // setting the same transform again changes nothing
+        graph.set_transform(comet, Transform::IDENTITY).unwrap();
+        assert_eq!(graph.update_world_matrices(), 0);
+        graph.transform_mut(sun).unwrap();
+        assert_eq!(graph.update_world_matrices(), 4);
+    }
+
+
+    #[test]
+    fn reparenting_keeps_the_local_transform()
+    {
+        let (mut graph, [sun, planet, moon]) = solar_system();
+        graph.set_parent(moon, Some(sun)).unwrap();
+        graph.update_world_matrices();
+        assert_eq!(graph.world_matrix(moon).unwrap().translation(), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(graph.children(sun), [planet, moon]);
+        assert_eq!(graph.children(planet), []);
+
+        graph.set_parent(moon, None).unwrap();
+        assert_eq!(graph.roots(), [sun, moon]);
+        assert_eq!(graph.parent(moon), None);
+
+        // a node can't end up below itself
+        assert!(graph.set_parent(sun, Some(planet)).is_err());
+        assert!(graph.set_parent(sun, Some(sun)).is_err());
+        assert_eq!(graph.parent(planet), Some(sun));
+        assert_eq!(graph.ancestors(planet).collect::<Vec<_>>(), [sun]);
+    }
+
+
+    #[test]
+    fn removing_a_node_removes_its_subtree()
+    {
+        let (mut graph, [sun, planet, moon]) = solar_system();
+        graph.remove_node(planet).unwrap();
+        assert_eq!(graph.len(), 1);
+        assert!(!graph.contains(planet));
+        assert!(!graph.contains(moon));
+        assert_eq!(graph.children(sun), []);
+        assert!(graph.remove_node(moon).is_err());
+        assert!(graph.transform_mut(moon).is_none());
+
+        // the slots are reused, but the old ids stay dangling
+        let comet = graph.add_node("comet", Some(sun), Transform::IDENTITY).unwrap();
+        let asteroid = graph.add_node("asteroid", Some(sun), Transform::IDENTITY).unwrap();
+        assert!(!graph.contains(planet) && !graph.contains(moon));
+        assert_eq!(graph.len(), 3);
+        assert_eq!(graph.find("asteroid"), Some(asteroid));
+        assert_eq!(graph.name(comet), Some("comet"));
+        assert!(graph.add_node("orphan", Some(moon), Transform::IDENTITY).is_err());
+
+        graph.remove_node(sun).unwrap();
+        assert!(graph.is_empty());
+        assert_eq!(graph.roots(), []);
+    }
+
+
+    #[test]
+    fn depth_first_lists_parents_before_children()
+    {
+        let (mut graph, [sun, planet, moon]) = solar_system();
+        let comet = graph.add_node("comet", Some(sun), Transform::IDENTITY).unwrap();
+        let star = graph.add_node("star", None, Transform::IDENTITY).unwrap();
+        assert_eq!(graph.depth_first(), [sun, planet, moon, comet, star]);
+    }
+
+
+    #[test]
+    fn attachments_are_flattened_with_world_space_data()
+    {
+        let (mut graph, [sun, planet, moon]) = solar_system();
+        graph.attach(moon, Attachment::Mesh(MeshId(1))).unwrap();
+        graph.attach(planet, Attachment::Mesh(MeshId(0))).unwrap();
+        graph.attach(planet, Attachment::Light(*Light::new(LightKind::Point, [1.0, 0.5, 0.0], 2.0).range(5.0))).unwrap();
+        let projection = CameraProjection::Perspective { fov_y: 1.0, z_near: 0.1, z_far: 100.0 };
+        graph.attach(sun, Attachment::Camera(projection)).unwrap();
+        graph.update_world_matrices();
+
+        let meshes = graph.mesh_instances();
+        assert_eq!(meshes.iter().map(|instance| (instance.node, instance.mesh)).collect::<Vec<_>>(), [(planet, MeshId(0)), (moon, MeshId(1))]);
+        assert_eq!(meshes[1].world.translation(), Vec3::new(12.0, 0.0, 0.0));
+
+        let lights = graph.lights();
+        assert_eq!(lights.len(), 1);
+        assert_eq!(lights[0].position, Vec3::new(10.0, 0.0, 0.0));
+        assert_eq!(lights[0].direction, -Vec3::Z);
+
+        let cameras = graph.cameras();
+        assert_eq!(cameras.len(), 1);
+        assert_eq!(cameras[0].view_projection(1.5), Mat4::perspective(1.0, 1.5, 0.1, 100.0));
+        assert_eq!(graph.attachments(planet).len(), 2);
+        graph.clear_attachments(planet).unwrap();
+        assert_eq!(graph.mesh_instances().len(), 1);
+    }
+
+
+    #[test]
+    fn instances_are_written_with_the_wgsl_layout()
+    {
+        let (mut graph, [_, planet, moon]) = solar_system();
+        graph.transform_mut(moon).unwrap().scale = Vec3::new(2.0, 4.0, 1.0);
+        graph.attach(planet, Attachment::Mesh(MeshId(0))).unwrap();
+        graph.attach(moon, Attachment::Mesh(MeshId(0))).unwrap();
+        graph.update_world_matrices();
+
+        let instances = graph.mesh_instances();
+        let data = SceneGraph::write_instances(&instances);
+        assert_eq!(data.len(), 2 * 28);
+        assert_eq!(data[28..44], instances[1].world.to_cols_array());
+        // the normal matrix undoes the scale, with every column padded to 4 floats
+        assert_eq!(data[44..56], [0.5, 0.0, 0.0, 0.0, 0.0, 0.25, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+        let spot = graph.add_node("spot", Some(moon), Transform::IDENTITY).unwrap();
+        let cone = LightKind::Spot { inner_angle: 0.0, outer_angle: FRAC_PI_2 };
+        graph.attach(spot, Attachment::Light(Light::new(cone, [1.0; 3], 3.0))).unwrap();
+        graph.update_world_matrices();
+        let light = SceneGraph::write_instances(&graph.lights());
+        assert_eq!(light.len(), 16);
+        assert_eq!(light[0..4], [12.0, 0.0, 0.0, 0.0]);
+        assert_eq!((light[7], light[11].to_bits()), (3.0, 2));
+        assert!((light[12] - 1.0).abs() < EPSILON && light[13].abs() < EPSILON);
+    }
+}
@@ -0,0 +1,11 @@
+mod transform;
+pub use transform::Transform;
+
+mod attachment;
+pub use attachment::{Attachment, MeshId, CameraProjection, Light, LightKind};
+
+mod graph;
+pub use graph::{SceneGraph, NodeId, MeshInstance, CameraInstance, LightInstance};
+
+mod culling;
+pub use culling::{MeshBounds, cull};
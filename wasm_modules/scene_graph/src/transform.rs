@@ -0,0 +1,92 @@
+use math::{Mat4, Quat, Vec3};
+
+
+// a local transform relative to the parent node: scaled first, then rotated, then translated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform
+{
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+
+impl Default for Transform
+{
+    fn default() -> Self
+    {
+        Transform::IDENTITY
+    }
+}
+
+
+impl Transform
+{
+    pub const IDENTITY: Transform = Transform { translation: Vec3::ZERO, rotation: Quat::IDENTITY, scale: Vec3::ONE };
+
+
+    pub fn from_translation(translation: Vec3) -> Self
+    {
+        Transform { translation, ..Transform::IDENTITY }
+    }
+
+
+    pub fn from_rotation(rotation: Quat) -> Self
+    {
+        Transform { rotation, ..Transform::IDENTITY }
+    }
+
+
+    pub fn from_scale(scale: Vec3) -> Self
+    {
+        Transform { scale, ..Transform::IDENTITY }
+    }
+
+
+    pub fn translation(&mut self, translation: Vec3) -> &mut Self
+    {
+        self.translation = translation;
+        self
+    }
+
+
+    pub fn rotation(&mut self, rotation: Quat) -> &mut Self
+    {
+        self.rotation = rotation;
+        self
+    }
+
+
+    pub fn scale(&mut self, scale: Vec3) -> &mut Self
+    {
+        self.scale = scale;
+        self
+    }
+
+
+    pub fn matrix(&self) -> Mat4
+    {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn scales_then_rotates_then_translates()
+    {
+        let mut transform = Transform::from_translation(Vec3::new(1.0, 2.0, 3.0));
+        transform
+            .rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+            .scale(Vec3::new(2.0, 1.0, 1.0));
+        let point = transform.matrix().transform_point3(Vec3::X);
+        assert!(point.abs_diff_eq(Vec3::new(1.0, 4.0, 3.0), 1e-5));
+        assert_eq!(Transform::default().matrix(), Mat4::IDENTITY);
+        assert_eq!(Transform::from_scale(Vec3::splat(2.0)).matrix(), Mat4::from_scale(Vec3::splat(2.0)));
+    }
+}
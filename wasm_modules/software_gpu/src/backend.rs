@@ -0,0 +1,590 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use gpu_backend::*;
+use gpu_backend::recording::Handle;
+
+use crate::interpreter::{Bindings, Interpreter};
+use crate::rasterizer::{self, Image, RenderPass};
+
+
+// color textures keep one image per mip level, depth textures a value per pixel. bgra textures are
+// stored as rgba, the order the shaders see
+struct Texture
+{
+    descriptor: TextureDescriptor,
+    levels: Vec<Image>,
+    depth: Vec<f32>,
+}
+
+
+struct ComputePipeline
+{
+    interpreter: Interpreter,
+    entry_point: String,
+}
+
+
+struct BindGroup
+{
+    layout: Handle,
+    entries: Vec<BindGroupEntry<SoftwareBackend>>,
+}
+
+
+#[derive(Default)]
+struct Device
+{
+    next_handle: u32,
+    buffers: HashMap<Handle, Vec<u8>>,
+    textures: HashMap<Handle, Texture>,
+    views: HashMap<Handle, Handle>,
+    samplers: HashMap<Handle, rasterizer::Sampler>,
+    modules: HashMap<Handle, String>,
+    render_pipelines: HashMap<Handle, Rc<rasterizer::RenderPipeline>>,
+    compute_pipelines: HashMap<Handle, Rc<ComputePipeline>>,
+    // the layouts queried from pipelines with the "auto" layout have no descriptor and no dynamic offsets
+    bind_group_layouts: HashMap<Handle, Option<BindGroupLayoutDescriptor>>,
+    bind_groups: HashMap<Handle, Rc<BindGroup>>,
+    limits: Limits,
+}
+
+
+impl Device
+{
+    fn handle(&mut self) -> Handle
+    {
+        let handle = Handle(self.next_handle);
+        self.next_handle += 1;
+        handle
+    }
+
+
+    fn texture(&self, view: &Handle) -> Handle
+    {
+        *self.views.get(view).unwrap_or_else(|| panic!("{:?} is not a texture view", view))
+    }
+
+
+    fn texture_mut(&mut self, view: &Handle) -> &mut Texture
+    {
+        let texture = self.texture(view);
+        self.textures.get_mut(&texture).unwrap_or_else(|| panic!("{:?} was destroyed", texture))
+    }
+
+
+    fn buffer(&self, buffer: &Handle) -> &Vec<u8>
+    {
+        self.buffers.get(buffer).unwrap_or_else(|| panic!("{:?} is not a live buffer", buffer))
+    }
+
+
+    // the resources of the bound groups, with where each buffer binding starts in its buffer. dynamic offsets
+    // are consumed in the order of the layout's bindings
+    fn bind(&self, bind_groups: &HashMap<u32, (Handle, Vec<u32>)>) -> (Bindings, Vec<(u32, u32, Handle, usize)>)
+    {
+        let mut bindings = Bindings::new();
+        let mut buffer_ranges = Vec::new();
+        for (index, (bind_group, dynamic_offsets)) in bind_groups
+        {
+            let bind_group = &self.bind_groups[bind_group];
+            let dynamic_bindings = match &self.bind_group_layouts[&bind_group.layout]
+            {
+                Some(layout) =>
+                {
+                    let mut entries = layout.entries.iter()
+                        .filter(|entry| matches!(entry.ty, BindingType::Buffer { has_dynamic_offset: true, .. }))
+                        .map(|entry| entry.binding)
+                        .collect::<Vec<u32>>();
+                    entries.sort();
+                    entries
+                },
+                None => Vec::new(),
+            };
+
+            for entry in &bind_group.entries
+            {
+                match &entry.resource
+                {
+                    BindingResource::Buffer { buffer, offset, size } =>
+                    {
+                        let dynamic_offset = dynamic_bindings.iter()
+                            .position(|binding| *binding == entry.binding)
+                            .map(|i| dynamic_offsets[i] as u64)
+                            .unwrap_or(0);
+                        let contents = self.buffer(buffer);
+                        let start = (offset + dynamic_offset) as usize;
+                        let end = size.map(|size| start + size as usize).unwrap_or(contents.len());
+                        let bytes = contents.get(start..end)
+                            .unwrap_or_else(|| panic!("{:?} has no bytes {}..{}", buffer, start, end));
+                        bindings.set(*index, entry.binding, bytes.to_vec());
+                        buffer_ranges.push((*index, entry.binding, *buffer, start));
+                    },
+                    BindingResource::Sampler(sampler) =>
+                    {
+                        bindings.set_sampler(*index, entry.binding, self.samplers[sampler]);
+                    },
+                    BindingResource::TextureView(view) =>
+                    {
+                        let texture = &self.textures[&self.texture(view)];
+                        bindings.set_texture(*index, entry.binding, Arc::new(texture.levels[0].clone()));
+                    },
+                }
+            }
+        }
+        (bindings, buffer_ranges)
+    }
+}
+
+
+fn image_from_bytes(format: TextureFormat, data: &[u8], bytes_per_row: u32, size: [u32; 2]) -> Image
+{
+    let pixels = (0..size[1] as usize)
+        .flat_map(|y| data[y * bytes_per_row as usize..][..4 * size[0] as usize].chunks(4))
+        .map(|texel| match format
+        {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => [texel[0], texel[1], texel[2], texel[3]],
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => [texel[2], texel[1], texel[0], texel[3]],
+            format => panic!("writing {:?} textures isn't supported", format),
+        })
+        .collect();
+    Image::from_pixels(size[0], size[1], pixels).unwrap()
+}
+
+
+// a device on the cpu that runs what is submitted to it, with the interpreter for the shaders and the
+// rasterizer for the draws. multisampled targets are rendered with a single sample and resolved by copying,
+// textures are sampled at their base level. clones share the same device
+#[derive(Clone, Default)]
+pub struct SoftwareBackend
+{
+    device: Rc<RefCell<Device>>,
+}
+
+
+impl fmt::Debug for SoftwareBackend
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        f.debug_struct("SoftwareBackend").finish_non_exhaustive()
+    }
+}
+
+
+impl SoftwareBackend
+{
+    pub fn new() -> Self
+    {
+        SoftwareBackend::default()
+    }
+
+
+    // e.g. a small max_compute_workgroups_per_dimension splits dispatches into several passes
+    pub fn set_limits(&self, limits: Limits)
+    {
+        self.device.borrow_mut().limits = limits;
+    }
+
+
+    pub fn buffer_contents(&self, buffer: &Handle) -> Option<Vec<u8>>
+    {
+        self.device.borrow().buffers.get(buffer).cloned()
+    }
+
+
+    // the base level of a color texture
+    pub fn texture_image(&self, texture: &Handle) -> Option<Image>
+    {
+        self.device.borrow().textures.get(texture).and_then(|texture| texture.levels.first().cloned())
+    }
+
+
+    fn execute(&self, commands: &[Command<SoftwareBackend>])
+    {
+        let mut device = self.device.borrow_mut();
+        let mut color_attachment = None;
+        let mut depth_attachment = None;
+        let mut render_pipeline = None;
+        let mut compute_pipeline = None;
+        let mut vertex_buffers = Vec::new();
+        let mut index_buffer = None;
+        let mut blend_constant = [0.0; 4];
+        let mut bind_groups = HashMap::new();
+
+        for command in commands
+        {
+            match command
+            {
+                Command::BeginRenderPass(descriptor) =>
+                {
+                    let [attachment] = &descriptor.color_attachments[..]
+                    else
+                    {
+                        panic!("render passes need a single color attachment");
+                    };
+                    if attachment.load_op == LoadOp::Clear
+                    {
+                        let texture = device.texture_mut(&attachment.view);
+                        let [width, height] = texture.descriptor.size;
+                        texture.levels[0] = Image::new(width, height, attachment.clear_value.map(|c| c as f32));
+                    }
+                    color_attachment = Some(attachment.clone());
+
+                    if let Some(attachment) = &descriptor.depth_stencil_attachment
+                    {
+                        if attachment.depth_load_op == LoadOp::Clear
+                        {
+                            device.texture_mut(&attachment.view).depth.fill(attachment.depth_clear_value);
+                        }
+                    }
+                    depth_attachment = descriptor.depth_stencil_attachment.clone();
+                    bind_groups.clear();
+                    vertex_buffers.clear();
+                    index_buffer = None;
+                    blend_constant = [0.0; 4];
+                },
+                Command::SetPipeline(pipeline) => render_pipeline = Some(device.render_pipelines[pipeline].clone()),
+                Command::SetVertexBuffer { slot, buffer, offset, size } =>
+                {
+                    let contents = device.buffer(buffer);
+                    let end = size.map(|size| (offset + size) as usize).unwrap_or(contents.len());
+                    if vertex_buffers.len() <= *slot as usize
+                    {
+                        vertex_buffers.resize(*slot as usize + 1, Vec::new());
+                    }
+                    vertex_buffers[*slot as usize] = contents[*offset as usize..end].to_vec();
+                },
+                Command::SetIndexBuffer { buffer, format, offset } =>
+                {
+                    let contents = &device.buffer(buffer)[*offset as usize..];
+                    index_buffer = Some(match format
+                    {
+                        IndexFormat::Uint16 => contents.chunks_exact(2)
+                            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]) as u32)
+                            .collect::<Vec<u32>>(),
+                        IndexFormat::Uint32 => contents.chunks_exact(4)
+                            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+                            .collect(),
+                    });
+                },
+                Command::SetBlendConstant(color) => blend_constant = color.map(|c| c as f32),
+                Command::SetBindGroup { index, bind_group, dynamic_offsets } =>
+                {
+                    bind_groups.insert(*index, (*bind_group, dynamic_offsets.clone()));
+                },
+                Command::Draw { first_vertex: 0, first_instance: 0, .. }
+                    | Command::DrawIndexed { first_index: 0, base_vertex: 0, first_instance: 0, .. }
+                    | Command::DrawIndexedIndirect { .. } =>
+                {
+                    let pipeline = render_pipeline.as_ref().expect("a draw without a pipeline");
+                    let attachment = color_attachment.as_ref().expect("a draw outside of a render pass");
+                    let (bindings, _) = device.bind(&bind_groups);
+                    let args = match command
+                    {
+                        Command::DrawIndexedIndirect { buffer, offset } =>
+                            device.buffer(buffer)[*offset as usize..].to_vec(),
+                        _ => Vec::new(),
+                    };
+
+                    // the target and the depth buffer are taken out of the device while the draw borrows them
+                    let target = &mut device.texture_mut(&attachment.view).levels[0];
+                    let mut target = std::mem::replace(target, Image::new(0, 0, [0.0; 4]));
+                    let mut depth = match &depth_attachment
+                    {
+                        Some(attachment) => std::mem::take(&mut device.texture_mut(&attachment.view).depth),
+                        None => Vec::new(),
+                    };
+
+                    let mut render_pass = RenderPass::new(&mut target, None);
+                    if depth_attachment.is_some()
+                    {
+                        render_pass.set_depth_buffer(&mut depth).unwrap();
+                    }
+                    for (slot, bytes) in vertex_buffers.iter().enumerate()
+                    {
+                        render_pass.set_vertex_buffer(slot, bytes);
+                    }
+                    if let Some(indices) = &index_buffer
+                    {
+                        render_pass.set_index_buffer(indices);
+                    }
+                    render_pass.set_blend_constant(blend_constant);
+                    let result = match *command
+                    {
+                        Command::Draw { vertex_count, instance_count, .. } =>
+                            render_pass.draw(pipeline, &bindings, vertex_count, instance_count),
+                        Command::DrawIndexed { index_count, instance_count, .. } =>
+                            render_pass.draw_indexed(pipeline, &bindings, index_count, instance_count),
+                        _ => render_pass.draw_indexed_indirect(pipeline, &bindings, &args),
+                    };
+                    result.unwrap_or_else(|error| panic!("{}", error));
+
+                    device.texture_mut(&attachment.view).levels[0] = target;
+                    if let Some(attachment) = &depth_attachment
+                    {
+                        device.texture_mut(&attachment.view).depth = depth;
+                    }
+                },
+                Command::EndRenderPass =>
+                {
+                    let attachment = color_attachment.take().expect("a render pass that wasn't begun");
+                    if let Some(resolve_target) = &attachment.resolve_target
+                    {
+                        let image = device.texture_mut(&attachment.view).levels[0].clone();
+                        device.texture_mut(resolve_target).levels[0] = image;
+                    }
+                    depth_attachment = None;
+                },
+                Command::BeginComputePass { .. } => bind_groups.clear(),
+                Command::SetComputePipeline(pipeline) =>
+                {
+                    compute_pipeline = Some(device.compute_pipelines[pipeline].clone());
+                },
+                Command::DispatchWorkgroups { x, y, z } =>
+                {
+                    let pipeline = compute_pipeline.as_ref().expect("a dispatch without a pipeline");
+                    let (bindings, buffer_ranges) = device.bind(&bind_groups);
+                    pipeline.interpreter.dispatch(&pipeline.entry_point, [*x, *y, *z], &bindings)
+                        .unwrap_or_else(|error| panic!("{}", error));
+
+                    // the shaders work on copies, what they stored is written back
+                    for (group, binding, buffer, start) in buffer_ranges
+                    {
+                        let bytes = bindings.bytes(group, binding).unwrap();
+                        device.buffers.get_mut(&buffer).unwrap()[start..start + bytes.len()].copy_from_slice(&bytes);
+                    }
+                },
+                Command::EndComputePass => compute_pipeline = None,
+                Command::CopyBufferToBuffer { source, source_offset, destination, destination_offset, size } =>
+                {
+                    let data = device.buffer(source)[*source_offset as usize..][..*size as usize].to_vec();
+                    device.buffers.get_mut(destination)
+                        .unwrap_or_else(|| panic!("{:?} is not a live buffer", destination))
+                        [*destination_offset as usize..][..*size as usize]
+                        .copy_from_slice(&data);
+                },
+                command => panic!("{:?} isn't supported", command),
+            }
+        }
+    }
+}
+
+
+impl Backend for SoftwareBackend
+{
+    type Buffer = Handle;
+    type Texture = Handle;
+    type TextureView = Handle;
+    type Sampler = Handle;
+    type ShaderModule = Handle;
+    type RenderPipeline = Handle;
+    type ComputePipeline = Handle;
+    type BindGroupLayout = Handle;
+    type BindGroup = Handle;
+
+
+    fn max_texture_dimension_2d(&self) -> u32
+    {
+        8192
+    }
+
+
+    fn limits(&self) -> Limits
+    {
+        self.device.borrow().limits
+    }
+
+
+    fn has_feature(&self, _: &str) -> bool
+    {
+        false
+    }
+
+
+    fn create_buffer(&self, descriptor: &BufferDescriptor) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let buffer = device.handle();
+        device.buffers.insert(buffer, vec![0; descriptor.size as usize]);
+        buffer
+    }
+
+
+    fn write_buffer(&self, buffer: &Handle, offset: u64, data: &[u8])
+    {
+        let mut device = self.device.borrow_mut();
+        let contents = device.buffers.get_mut(buffer).unwrap_or_else(|| panic!("{:?} is not a live buffer", buffer));
+        contents[offset as usize..][..data.len()].copy_from_slice(data);
+    }
+
+
+    fn destroy_buffer(&self, buffer: &Handle)
+    {
+        self.device.borrow_mut().buffers.remove(buffer);
+    }
+
+
+    fn read_buffer(&self, buffer: &Handle, offset: u64, size: u64) -> BufferRead
+    {
+        let contents = self.device.borrow().buffers.get(buffer)
+            .and_then(|contents| contents.get(offset as usize..(offset + size) as usize))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| format!("{:?} has no bytes {}..{}", buffer, offset, offset + size));
+        Box::pin(std::future::ready(contents))
+    }
+
+
+    fn create_texture(&self, descriptor: &TextureDescriptor) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let texture = device.handle();
+        let [width, height] = descriptor.size;
+        let (levels, depth) = if descriptor.format.is_depth()
+        {
+            (Vec::new(), vec![1.0; (width * height) as usize])
+        }
+        else
+        {
+            let levels = (0..descriptor.mip_level_count)
+                .map(|level| Image::new((width >> level).max(1), (height >> level).max(1), [0.0; 4]))
+                .collect();
+            (levels, Vec::new())
+        };
+        device.textures.insert(texture, Texture { descriptor: descriptor.clone(), levels, depth });
+        texture
+    }
+
+
+    fn write_texture(&self, texture: &Handle, mip_level: u32, data: &[u8], bytes_per_row: u32, size: [u32; 2])
+    {
+        let mut device = self.device.borrow_mut();
+        let texture = device.textures.get_mut(texture).unwrap_or_else(|| panic!("{:?} is not a live texture", texture));
+        texture.levels[mip_level as usize] = image_from_bytes(texture.descriptor.format, data, bytes_per_row, size);
+    }
+
+
+    fn texture_size(&self, texture: &Handle) -> [u32; 2]
+    {
+        self.device.borrow().textures.get(texture).map(|texture| texture.descriptor.size).unwrap_or([0, 0])
+    }
+
+
+    fn create_view(&self, texture: &Handle) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let view = device.handle();
+        device.views.insert(view, *texture);
+        view
+    }
+
+
+    fn destroy_texture(&self, texture: &Handle)
+    {
+        self.device.borrow_mut().textures.remove(texture);
+    }
+
+
+    fn create_sampler(&self, descriptor: &SamplerDescriptor) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let sampler = device.handle();
+        device.samplers.insert(sampler, rasterizer::Sampler::new(
+            descriptor.address_mode_u, descriptor.address_mode_v, descriptor.mag_filter,
+        ));
+        sampler
+    }
+
+
+    fn create_shader_module(&self, descriptor: &ShaderModuleDescriptor) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let module = device.handle();
+        device.modules.insert(module, descriptor.code.clone());
+        module
+    }
+
+
+    fn create_render_pipeline(&self, descriptor: &RenderPipelineDescriptor<Self>) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let fragment = descriptor.fragment.as_ref().expect("pipelines without a fragment stage aren't supported");
+        let mut pipeline = rasterizer::RenderPipeline::new(
+            &device.modules[&descriptor.vertex.module], &descriptor.vertex.entry_point,
+            &device.modules[&fragment.module], &fragment.entry_point,
+        ).unwrap_or_else(|error| panic!("{}", error));
+        pipeline
+            .buffers(&descriptor.vertex.buffers)
+            .topology(descriptor.primitive.topology)
+            .front_face(descriptor.primitive.front_face)
+            .cull_mode(descriptor.primitive.cull_mode)
+            .blend(fragment.targets.first().and_then(|target| target.blend))
+            .depth_stencil(descriptor.depth_stencil);
+
+        let handle = device.handle();
+        device.render_pipelines.insert(handle, Rc::new(pipeline));
+        handle
+    }
+
+
+    fn create_compute_pipeline(&self, descriptor: &ComputePipelineDescriptor<Self>) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let interpreter = Interpreter::new(&device.modules[&descriptor.module])
+            .unwrap_or_else(|error| panic!("{}", error));
+        interpreter.workgroup_size(&descriptor.entry_point).unwrap_or_else(|error| panic!("{}", error));
+
+        let handle = device.handle();
+        let pipeline = ComputePipeline { interpreter, entry_point: descriptor.entry_point.clone() };
+        device.compute_pipelines.insert(handle, Rc::new(pipeline));
+        handle
+    }
+
+
+    fn render_bind_group_layout(&self, _: &Handle, _: u32) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let layout = device.handle();
+        device.bind_group_layouts.insert(layout, None);
+        layout
+    }
+
+
+    fn compute_bind_group_layout(&self, _: &Handle, _: u32) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let layout = device.handle();
+        device.bind_group_layouts.insert(layout, None);
+        layout
+    }
+
+
+    fn create_bind_group_layout(&self, descriptor: &BindGroupLayoutDescriptor) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let layout = device.handle();
+        device.bind_group_layouts.insert(layout, Some(descriptor.clone()));
+        layout
+    }
+
+
+    fn create_bind_group(&self, descriptor: &BindGroupDescriptor<Self>) -> Handle
+    {
+        let mut device = self.device.borrow_mut();
+        let bind_group = device.handle();
+        let group = BindGroup { layout: descriptor.layout, entries: descriptor.entries.clone() };
+        device.bind_groups.insert(bind_group, Rc::new(group));
+        bind_group
+    }
+
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer<Self>>)
+    {
+        for command_buffer in command_buffers
+        {
+            self.execute(&command_buffer.commands);
+        }
+    }
+}
@@ -0,0 +1,1818 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use naga::
+{
+    AddressSpace, ArraySize, AtomicFunction, BinaryOperator, Binding, Block, BuiltIn, EntryPoint, Expression, Function,
+    GlobalVariable, Handle, ImageQuery, Literal, MathFunction, Module, RelationalFunction, Scalar, ScalarKind,
+    ShaderStage, Statement, SwitchValue, Type, TypeInner, UnaryOperator, VectorSize,
+};
+use naga::valid::{Capabilities, FunctionInfo, ModuleInfo, ValidationFlags, Validator};
+
+use crate::rasterizer::{Image, Sampler};
+
+
+// the contents of a storage or uniform buffer, shared by every invocation of a dispatch
+pub type Buffer = Arc<Mutex<Vec<u8>>>;
+
+
+#[derive(Clone, Debug)]
+pub enum Value
+{
+    Bool(bool),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    // vectors, matrices as a list of columns, arrays and structs
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+    Texture(Arc<Image>),
+    Sampler(Sampler),
+}
+
+
+impl Value
+{
+    fn as_bool(&self) -> Result<bool, String>
+    {
+        match self
+        {
+            Value::Bool(value) => Ok(*value),
+            _ => Err(format!("expected a bool, got {:?}", self)),
+        }
+    }
+
+
+    // negative indices are out of bounds as well
+    fn as_index(&self) -> Result<usize, String>
+    {
+        match self
+        {
+            Value::U32(value) => Ok(*value as usize),
+            Value::I32(value) => Ok(usize::try_from(*value).unwrap_or(usize::MAX)),
+            _ => Err(format!("expected an index, got {:?}", self)),
+        }
+    }
+
+
+    fn as_pointer(&self) -> Result<&Pointer, String>
+    {
+        match self
+        {
+            Value::Pointer(pointer) => Ok(pointer),
+            _ => Err(format!("expected a pointer, got {:?}", self)),
+        }
+    }
+
+
+    fn as_f32(&self) -> Result<f32, String>
+    {
+        match self
+        {
+            Value::F32(value) => Ok(*value),
+            _ => Err(format!("expected an f32, got {:?}", self)),
+        }
+    }
+
+
+    fn as_texture(&self) -> Result<&Image, String>
+    {
+        match self
+        {
+            Value::Texture(image) => Ok(image),
+            _ => Err(format!("expected a texture, got {:?}", self)),
+        }
+    }
+
+
+    // vectors of i32 or u32, like texel coordinates
+    fn as_i32s(&self) -> Result<Vec<i32>, String>
+    {
+        let components = match self
+        {
+            Value::Composite(components) => components.as_slice(),
+            scalar => std::slice::from_ref(scalar),
+        };
+        components.iter()
+            .map(|component| match component
+            {
+                Value::I32(value) => Ok(*value),
+                Value::U32(value) => Ok(*value as i32),
+                component => Err(format!("expected an integer, got {:?}", component)),
+            })
+            .collect()
+    }
+}
+
+
+#[derive(Clone, Debug)]
+enum Memory
+{
+    Shared(Buffer),
+    // private and function variables live in the memory of their invocation
+    Local(usize),
+}
+
+
+// what a pointer points at, pointers into vectors and matrices have no type handle
+#[derive(Clone, Copy, Debug)]
+enum Pointee
+{
+    Type(Handle<Type>),
+    Vector(VectorSize, Scalar),
+    Scalar(Scalar),
+}
+
+
+#[derive(Clone, Debug)]
+pub struct Pointer
+{
+    memory: Memory,
+    offset: usize,
+    pointee: Pointee,
+}
+
+
+// matrices are laid out as arrays of aligned column vectors
+fn column_stride(rows: VectorSize, scalar: Scalar) -> usize
+{
+    let components = if rows == VectorSize::Bi { 2 } else { 4 };
+    components * scalar.width as usize
+}
+
+
+fn read_scalar(bytes: &[u8], offset: usize, scalar: Scalar) -> Result<Value, String>
+{
+    let width = scalar.width as usize;
+    // like on the gpu, loads outside of the buffer don't fail, they return zero
+    let raw = match bytes.get(offset..offset + width)
+    {
+        Some(raw) => raw,
+        None => return zero_scalar(scalar),
+    };
+
+    match (scalar.kind, width)
+    {
+        (ScalarKind::Bool, _) => Ok(Value::Bool(raw[0] != 0)),
+        (ScalarKind::Uint, 4) => Ok(Value::U32(u32::from_le_bytes(raw.try_into().unwrap()))),
+        (ScalarKind::Sint, 4) => Ok(Value::I32(i32::from_le_bytes(raw.try_into().unwrap()))),
+        (ScalarKind::Float, 4) => Ok(Value::F32(f32::from_le_bytes(raw.try_into().unwrap()))),
+        _ => Err(format!("unsupported scalar type {:?}", scalar)),
+    }
+}
+
+
+fn write_scalar(bytes: &mut [u8], offset: usize, value: &Value) -> Result<(), String>
+{
+    let raw = match value
+    {
+        Value::Bool(value) => vec![*value as u8],
+        Value::U32(value) => value.to_le_bytes().to_vec(),
+        Value::I32(value) => value.to_le_bytes().to_vec(),
+        Value::F32(value) => value.to_le_bytes().to_vec(),
+        _ => return Err(format!("can't store {:?} as a scalar", value)),
+    };
+
+    // stores outside of the buffer are a bug in the kernel even if the gpu silently drops them
+    let length = bytes.len();
+    bytes.get_mut(offset..offset + raw.len())
+        .ok_or_else(|| format!("out of bounds store at byte {} of a {} byte buffer", offset, length))?
+        .copy_from_slice(&raw);
+    Ok(())
+}
+
+
+fn zero_scalar(scalar: Scalar) -> Result<Value, String>
+{
+    match scalar.kind
+    {
+        ScalarKind::Bool => Ok(Value::Bool(false)),
+        ScalarKind::Uint => Ok(Value::U32(0)),
+        ScalarKind::Sint | ScalarKind::AbstractInt => Ok(Value::I32(0)),
+        ScalarKind::Float | ScalarKind::AbstractFloat => Ok(Value::F32(0.0)),
+    }
+}
+
+
+fn literal_value(literal: &Literal) -> Result<Value, String>
+{
+    match *literal
+    {
+        Literal::Bool(value) => Ok(Value::Bool(value)),
+        Literal::U32(value) => Ok(Value::U32(value)),
+        Literal::I32(value) => Ok(Value::I32(value)),
+        Literal::F32(value) => Ok(Value::F32(value)),
+        Literal::AbstractInt(value) => Ok(Value::I32(value as i32)),
+        Literal::AbstractFloat(value) => Ok(Value::F32(value as f32)),
+        _ => Err(format!("unsupported literal {:?}", literal)),
+    }
+}
+
+
+// applies f to every component, scalars are broadcast to the size of the composite arguments
+fn zip_map(args: &[Value], f: &dyn Fn(&[Value]) -> Result<Value, String>) -> Result<Value, String>
+{
+    let size = args.iter().find_map(|arg| match arg
+        {
+            Value::Composite(components) => Some(components.len()),
+            _ => None,
+        });
+
+    match size
+    {
+        Some(size) => (0..size)
+            .map(|i|
+            {
+                let components = args.iter()
+                    .map(|arg| match arg
+                    {
+                        Value::Composite(components) => components[i].clone(),
+                        scalar => scalar.clone(),
+                    })
+                    .collect::<Vec<Value>>();
+                zip_map(&components, f)
+            })
+            .collect::<Result<Vec<Value>, String>>()
+            .map(Value::Composite),
+        None => f(args),
+    }
+}
+
+
+fn float_map(args: &[Value], f: fn(&[f32]) -> f32) -> Result<Value, String>
+{
+    zip_map(args, &|args|
+    {
+        let args = args.iter().map(Value::as_f32).collect::<Result<Vec<f32>, String>>()?;
+        Ok(Value::F32(f(&args)))
+    })
+}
+
+
+fn scalar_binary(op: BinaryOperator, a: &Value, b: &Value) -> Result<Value, String>
+{
+    use BinaryOperator::*;
+
+    macro_rules! compare
+    {
+        ($a:expr, $b:expr) =>
+        {
+            match op
+            {
+                Equal => Some(Value::Bool($a == $b)),
+                NotEqual => Some(Value::Bool($a != $b)),
+                Less => Some(Value::Bool($a < $b)),
+                LessEqual => Some(Value::Bool($a <= $b)),
+                Greater => Some(Value::Bool($a > $b)),
+                GreaterEqual => Some(Value::Bool($a >= $b)),
+                _ => None,
+            }
+        };
+    }
+
+    let result = match (a, b)
+    {
+        (Value::U32(a), Value::U32(b)) => compare!(a, b).or(match op
+            {
+                Add => Some(Value::U32(a.wrapping_add(*b))),
+                Subtract => Some(Value::U32(a.wrapping_sub(*b))),
+                Multiply => Some(Value::U32(a.wrapping_mul(*b))),
+                // integer division by zero gives the dividend in WGSL
+                Divide => Some(Value::U32(if *b == 0 { *a } else { a / b })),
+                Modulo => Some(Value::U32(if *b == 0 { 0 } else { a % b })),
+                And => Some(Value::U32(a & b)),
+                InclusiveOr => Some(Value::U32(a | b)),
+                ExclusiveOr => Some(Value::U32(a ^ b)),
+                ShiftLeft => Some(Value::U32(a.wrapping_shl(*b))),
+                ShiftRight => Some(Value::U32(a.wrapping_shr(*b))),
+                _ => None,
+            }),
+        (Value::I32(a), Value::I32(b)) => compare!(a, b).or(match op
+            {
+                Add => Some(Value::I32(a.wrapping_add(*b))),
+                Subtract => Some(Value::I32(a.wrapping_sub(*b))),
+                Multiply => Some(Value::I32(a.wrapping_mul(*b))),
+                Divide => Some(Value::I32(if *b == 0 { *a } else { a.wrapping_div(*b) })),
+                Modulo => Some(Value::I32(if *b == 0 { 0 } else { a.wrapping_rem(*b) })),
+                And => Some(Value::I32(a & b)),
+                InclusiveOr => Some(Value::I32(a | b)),
+                ExclusiveOr => Some(Value::I32(a ^ b)),
+                _ => None,
+            }),
+        (Value::I32(a), Value::U32(b)) => match op
+            {
+                ShiftLeft => Some(Value::I32(a.wrapping_shl(*b))),
+                ShiftRight => Some(Value::I32(a.wrapping_shr(*b))),
+                _ => None,
+            },
+        (Value::F32(a), Value::F32(b)) => compare!(a, b).or(match op
+            {
+                Add => Some(Value::F32(a + b)),
+                Subtract => Some(Value::F32(a - b)),
+                Multiply => Some(Value::F32(a * b)),
+                Divide => Some(Value::F32(a / b)),
+                Modulo => Some(Value::F32(a % b)),
+                _ => None,
+            }),
+        (Value::Bool(a), Value::Bool(b)) => match op
+            {
+                Equal => Some(Value::Bool(a == b)),
+                NotEqual => Some(Value::Bool(a != b)),
+                And | LogicalAnd => Some(Value::Bool(*a && *b)),
+                InclusiveOr | LogicalOr => Some(Value::Bool(*a || *b)),
+                _ => None,
+            },
+        _ => None,
+    };
+
+    result.ok_or_else(|| format!("unsupported operation {:?} on {:?} and {:?}", op, a, b))
+}
+
+
+fn binary(op: BinaryOperator, a: &Value, b: &Value) -> Result<Value, String>
+{
+    zip_map(&[a.clone(), b.clone()], &|args| scalar_binary(op, &args[0], &args[1]))
+}
+
+
+fn unary(op: UnaryOperator, value: &Value) -> Result<Value, String>
+{
+    zip_map(std::slice::from_ref(value), &|args| match (op, &args[0])
+    {
+        (UnaryOperator::Negate, Value::I32(value)) => Ok(Value::I32(value.wrapping_neg())),
+        (UnaryOperator::Negate, Value::F32(value)) => Ok(Value::F32(-value)),
+        (UnaryOperator::LogicalNot, Value::Bool(value)) => Ok(Value::Bool(!value)),
+        (UnaryOperator::BitwiseNot, Value::U32(value)) => Ok(Value::U32(!value)),
+        (UnaryOperator::BitwiseNot, Value::I32(value)) => Ok(Value::I32(!value)),
+        (op, value) => Err(format!("unsupported operation {:?} on {:?}", op, value)),
+    })
+}
+
+
+// numeric conversions when convert is set, bit casts otherwise
+fn cast(value: &Value, kind: ScalarKind, convert: bool) -> Result<Value, String>
+{
+    zip_map(std::slice::from_ref(value), &|args|
+    {
+        let value = &args[0];
+        let result = match (kind, value, convert)
+        {
+            (ScalarKind::Uint, Value::U32(value), _) => Value::U32(*value),
+            (ScalarKind::Uint, Value::I32(value), _) => Value::U32(*value as u32),
+            (ScalarKind::Uint, Value::F32(value), true) => Value::U32(*value as u32),
+            (ScalarKind::Uint, Value::F32(value), false) => Value::U32(value.to_bits()),
+            (ScalarKind::Uint, Value::Bool(value), true) => Value::U32(*value as u32),
+            (ScalarKind::Sint, Value::I32(value), _) => Value::I32(*value),
+            (ScalarKind::Sint, Value::U32(value), _) => Value::I32(*value as i32),
+            (ScalarKind::Sint, Value::F32(value), true) => Value::I32(*value as i32),
+            (ScalarKind::Sint, Value::F32(value), false) => Value::I32(value.to_bits() as i32),
+            (ScalarKind::Sint, Value::Bool(value), true) => Value::I32(*value as i32),
+            (ScalarKind::Float, Value::F32(value), _) => Value::F32(*value),
+            (ScalarKind::Float, Value::U32(value), true) => Value::F32(*value as f32),
+            (ScalarKind::Float, Value::U32(value), false) => Value::F32(f32::from_bits(*value)),
+            (ScalarKind::Float, Value::I32(value), true) => Value::F32(*value as f32),
+            (ScalarKind::Float, Value::I32(value), false) => Value::F32(f32::from_bits(*value as u32)),
+            (ScalarKind::Float, Value::Bool(value), true) => Value::F32(if *value { 1.0 } else { 0.0 }),
+            (ScalarKind::Bool, Value::Bool(value), _) => Value::Bool(*value),
+            (ScalarKind::Bool, Value::U32(value), true) => Value::Bool(*value != 0),
+            (ScalarKind::Bool, Value::I32(value), true) => Value::Bool(*value != 0),
+            (ScalarKind::Bool, Value::F32(value), true) => Value::Bool(*value != 0.0),
+            _ => return Err(format!("can't convert {:?} to {:?}", value, kind)),
+        };
+        Ok(result)
+    })
+}
+
+
+fn dot(a: &Value, b: &Value) -> Result<Value, String>
+{
+    match (a, b)
+    {
+        (Value::Composite(a), Value::Composite(b)) => a.iter().zip(b)
+            .map(|(a, b)| scalar_binary(BinaryOperator::Multiply, a, b))
+            .reduce(|sum, product| scalar_binary(BinaryOperator::Add, &sum?, &product?))
+            .unwrap_or_else(|| Err("dot of empty vectors".to_owned())),
+        _ => Err(format!("dot needs two vectors, got {:?} and {:?}", a, b)),
+    }
+}
+
+
+fn length(value: &Value) -> Result<f32, String>
+{
+    match value
+    {
+        Value::Composite(_) => Ok(dot(value, value)?.as_f32()?.sqrt()),
+        value => Ok(value.as_f32()?.abs()),
+    }
+}
+
+
+fn math(fun: MathFunction, args: &[Value]) -> Result<Value, String>
+{
+    use MathFunction as M;
+
+    match fun
+    {
+        M::Abs => zip_map(args, &|args| match &args[0]
+            {
+                Value::F32(value) => Ok(Value::F32(value.abs())),
+                Value::I32(value) => Ok(Value::I32(value.wrapping_abs())),
+                value => Ok(value.clone()),
+            }),
+        M::Min => zip_map(args, &|args| match (&args[0], &args[1])
+            {
+                (Value::F32(a), Value::F32(b)) => Ok(Value::F32(a.min(*b))),
+                (Value::U32(a), Value::U32(b)) => Ok(Value::U32(*a.min(b))),
+                (Value::I32(a), Value::I32(b)) => Ok(Value::I32(*a.min(b))),
+                (a, b) => Err(format!("min of {:?} and {:?}", a, b)),
+            }),
+        M::Max => zip_map(args, &|args| match (&args[0], &args[1])
+            {
+                (Value::F32(a), Value::F32(b)) => Ok(Value::F32(a.max(*b))),
+                (Value::U32(a), Value::U32(b)) => Ok(Value::U32(*a.max(b))),
+                (Value::I32(a), Value::I32(b)) => Ok(Value::I32(*a.max(b))),
+                (a, b) => Err(format!("max of {:?} and {:?}", a, b)),
+            }),
+        M::Clamp => math(M::Min, &[math(M::Max, &args[0..2])?, args[2].clone()]),
+        M::Saturate => float_map(args, |x| x[0].clamp(0.0, 1.0)),
+        M::Cos => float_map(args, |x| x[0].cos()),
+        M::Cosh => float_map(args, |x| x[0].cosh()),
+        M::Sin => float_map(args, |x| x[0].sin()),
+        M::Sinh => float_map(args, |x| x[0].sinh()),
+        M::Tan => float_map(args, |x| x[0].tan()),
+        M::Tanh => float_map(args, |x| x[0].tanh()),
+        M::Acos => float_map(args, |x| x[0].acos()),
+        M::Asin => float_map(args, |x| x[0].asin()),
+        M::Atan => float_map(args, |x| x[0].atan()),
+        M::Atan2 => float_map(args, |x| x[0].atan2(x[1])),
+        M::Asinh => float_map(args, |x| x[0].asinh()),
+        M::Acosh => float_map(args, |x| x[0].acosh()),
+        M::Atanh => float_map(args, |x| x[0].atanh()),
+        M::Radians => float_map(args, |x| x[0].to_radians()),
+        M::Degrees => float_map(args, |x| x[0].to_degrees()),
+        M::Ceil => float_map(args, |x| x[0].ceil()),
+        M::Floor => float_map(args, |x| x[0].floor()),
+        M::Round => float_map(args, |x| x[0].round_ties_even()),
+        M::Fract => float_map(args, |x| x[0] - x[0].floor()),
+        M::Trunc => float_map(args, |x| x[0].trunc()),
+        M::Exp => float_map(args, |x| x[0].exp()),
+        M::Exp2 => float_map(args, |x| x[0].exp2()),
+        M::Log => float_map(args, |x| x[0].ln()),
+        M::Log2 => float_map(args, |x| x[0].log2()),
+        M::Pow => float_map(args, |x| x[0].powf(x[1])),
+        M::Sqrt => float_map(args, |x| x[0].sqrt()),
+        M::InverseSqrt => float_map(args, |x| 1.0 / x[0].sqrt()),
+        M::Fma => float_map(args, |x| x[0].mul_add(x[1], x[2])),
+        M::Mix => float_map(args, |x| x[0] * (1.0 - x[2]) + x[1] * x[2]),
+        M::Step => float_map(args, |x| if x[1] >= x[0] { 1.0 } else { 0.0 }),
+        M::SmoothStep => float_map(args, |x|
+            {
+                let t = ((x[2] - x[0]) / (x[1] - x[0])).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }),
+        M::Sign => zip_map(args, &|args| match &args[0]
+            {
+                Value::F32(value) => Ok(Value::F32(if *value == 0.0 { 0.0 } else { value.signum() })),
+                Value::I32(value) => Ok(Value::I32(value.signum())),
+                value => Err(format!("sign of {:?}", value)),
+            }),
+        M::Dot => dot(&args[0], &args[1]),
+        M::Cross => match (&args[0], &args[1])
+            {
+                (Value::Composite(a), Value::Composite(b)) if a.len() == 3 && b.len() == 3 =>
+                {
+                    let a = a.iter().map(Value::as_f32).collect::<Result<Vec<f32>, String>>()?;
+                    let b = b.iter().map(Value::as_f32).collect::<Result<Vec<f32>, String>>()?;
+                    Ok(Value::Composite(vec![
+                        Value::F32(a[1] * b[2] - a[2] * b[1]),
+                        Value::F32(a[2] * b[0] - a[0] * b[2]),
+                        Value::F32(a[0] * b[1] - a[1] * b[0]),
+                    ]))
+                },
+                _ => Err("cross needs two vec3f".to_owned()),
+            },
+        M::Length => Ok(Value::F32(length(&args[0])?)),
+        M::Distance => Ok(Value::F32(length(&binary(BinaryOperator::Subtract, &args[0], &args[1])?)?)),
+        M::Normalize =>
+        {
+            let length = Value::F32(length(&args[0])?);
+            binary(BinaryOperator::Divide, &args[0], &length)
+        },
+        M::CountOneBits | M::CountLeadingZeros | M::CountTrailingZeros | M::ReverseBits | M::FindLsb | M::FindMsb =>
+            zip_map(args, &|args|
+            {
+                let (bits, signed) = match &args[0]
+                {
+                    Value::U32(value) => (*value, false),
+                    Value::I32(value) => (*value as u32, true),
+                    value => return Err(format!("{:?} of {:?}", fun, value)),
+                };
+                let result = match fun
+                {
+                    M::CountOneBits => bits.count_ones(),
+                    M::CountLeadingZeros => bits.leading_zeros(),
+                    M::CountTrailingZeros => bits.trailing_zeros(),
+                    M::ReverseBits => bits.reverse_bits(),
+                    M::FindLsb => if bits == 0 { u32::MAX } else { bits.trailing_zeros() },
+                    // for negative signed values the most significant 0 bit is searched
+                    _ =>
+                    {
+                        let bits = if signed && (bits as i32) < 0 { !bits } else { bits };
+                        if bits == 0 { u32::MAX } else { 31 - bits.leading_zeros() }
+                    },
+                };
+                Ok(if signed { Value::I32(result as i32) } else { Value::U32(result) })
+            }),
+        M::ExtractBits => zip_map(args, &|args|
+            {
+                let offset = args[1].as_index()?.min(32) as u32;
+                let count = args[2].as_index()?.min(32 - offset as usize) as u32;
+                if count == 0
+                {
+                    return zero_like(&args[0]);
+                }
+                let shift = 32 - count;
+                match &args[0]
+                {
+                    Value::U32(value) => Ok(Value::U32((value >> offset) << shift >> shift)),
+                    Value::I32(value) => Ok(Value::I32((value >> offset) << shift >> shift)),
+                    value => Err(format!("extractBits of {:?}", value)),
+                }
+            }),
+        M::InsertBits => zip_map(args, &|args|
+            {
+                let offset = args[2].as_index()?.min(32) as u32;
+                let count = args[3].as_index()?.min(32 - offset as usize) as u32;
+                let mask = if count == 0 { 0 } else { (u32::MAX >> (32 - count)) << offset };
+                match (&args[0], &args[1])
+                {
+                    (Value::U32(e), Value::U32(new_bits)) => Ok(Value::U32((e & !mask) | ((new_bits << offset) & mask))),
+                    (Value::I32(e), Value::I32(new_bits)) =>
+                    {
+                        let (e, new_bits) = (*e as u32, *new_bits as u32);
+                        Ok(Value::I32(((e & !mask) | (new_bits.wrapping_shl(offset) & mask)) as i32))
+                    },
+                    (e, new_bits) => Err(format!("insertBits of {:?} and {:?}", e, new_bits)),
+                }
+            }),
+        fun => Err(format!("unsupported math function {:?}", fun)),
+    }
+}
+
+
+fn zero_like(value: &Value) -> Result<Value, String>
+{
+    match value
+    {
+        Value::Bool(_) => Ok(Value::Bool(false)),
+        Value::U32(_) => Ok(Value::U32(0)),
+        Value::I32(_) => Ok(Value::I32(0)),
+        Value::F32(_) => Ok(Value::F32(0.0)),
+        Value::Composite(components) => components.iter().map(zero_like).collect::<Result<_, _>>().map(Value::Composite),
+        value => Err(format!("{:?} has no zero value", value)),
+    }
+}
+
+
+// a barrier that invocations leaving the entry point early don't block, so a failing
+// invocation reports its error instead of hanging the rest of its workgroup
+struct WorkgroupBarrier
+{
+    state: Mutex<BarrierState>,
+    condvar: Condvar,
+}
+
+
+struct BarrierState
+{
+    active: usize,
+    waiting: usize,
+    generation: u64,
+}
+
+
+impl WorkgroupBarrier
+{
+    fn new(invocations: usize) -> Self
+    {
+        WorkgroupBarrier
+        {
+            state: Mutex::new(BarrierState { active: invocations, waiting: 0, generation: 0 }),
+            condvar: Condvar::new(),
+        }
+    }
+
+
+    fn release_if_complete(&self, state: &mut BarrierState)
+    {
+        if state.waiting > 0 && state.waiting == state.active
+        {
+            state.waiting = 0;
+            state.generation += 1;
+            self.condvar.notify_all();
+        }
+    }
+
+
+    fn wait(&self)
+    {
+        let mut state = self.state.lock().unwrap();
+        let generation = state.generation;
+        state.waiting += 1;
+        self.release_if_complete(&mut state);
+        while state.generation == generation
+        {
+            state = self.condvar.wait(state).unwrap();
+        }
+    }
+
+
+    fn leave(&self)
+    {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        self.release_if_complete(&mut state);
+    }
+}
+
+
+// the buffers, textures and samplers bound to a dispatch or draw, by @group and @binding
+#[derive(Default)]
+pub struct Bindings
+{
+    buffers: HashMap<(u32, u32), Buffer>,
+    textures: HashMap<(u32, u32), Arc<Image>>,
+    samplers: HashMap<(u32, u32), Sampler>,
+}
+
+
+impl Bindings
+{
+    pub fn new() -> Self
+    {
+        Bindings::default()
+    }
+
+
+    pub fn set(&mut self, group: u32, binding: u32, bytes: Vec<u8>)
+    {
+        self.buffers.insert((group, binding), Arc::new(Mutex::new(bytes)));
+    }
+
+
+    pub fn bytes(&self, group: u32, binding: u32) -> Option<Vec<u8>>
+    {
+        self.buffers.get(&(group, binding)).map(|buffer| buffer.lock().unwrap().clone())
+    }
+
+
+    pub fn set_texture(&mut self, group: u32, binding: u32, image: Arc<Image>)
+    {
+        self.textures.insert((group, binding), image);
+    }
+
+
+    pub fn set_sampler(&mut self, group: u32, binding: u32, sampler: Sampler)
+    {
+        self.samplers.insert((group, binding), sampler);
+    }
+}
+
+
+#[derive(Clone, Copy)]
+struct Builtins
+{
+    global_invocation_id: [u32; 3],
+    local_invocation_id: [u32; 3],
+    local_invocation_index: u32,
+    workgroup_id: [u32; 3],
+    num_workgroups: [u32; 3],
+}
+
+
+impl Builtins
+{
+    fn value(&self, builtin: BuiltIn) -> Result<Value, String>
+    {
+        let vector = |v: [u32; 3]| Value::Composite(v.iter().map(|c| Value::U32(*c)).collect());
+        match builtin
+        {
+            BuiltIn::GlobalInvocationId => Ok(vector(self.global_invocation_id)),
+            BuiltIn::LocalInvocationId => Ok(vector(self.local_invocation_id)),
+            BuiltIn::LocalInvocationIndex => Ok(Value::U32(self.local_invocation_index)),
+            BuiltIn::WorkGroupId => Ok(vector(self.workgroup_id)),
+            BuiltIn::NumWorkGroups => Ok(vector(self.num_workgroups)),
+            builtin => Err(format!("unsupported builtin {:?} in a compute shader", builtin)),
+        }
+    }
+}
+
+
+// runs the entry points of a WGSL module on the cpu by walking its naga IR, every
+// invocation of a compute workgroup that uses barriers runs on its own thread
+pub struct Interpreter
+{
+    module: Module,
+    info: ModuleInfo,
+    constants: Vec<Result<Value, String>>,
+}
+
+
+impl Interpreter
+{
+    pub fn new(source: &str) -> Result<Self, String>
+    {
+        let module = naga::front::wgsl::parse_str(source).map_err(|error| error.emit_to_string(source))?;
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|error| error.emit_to_string(source))?;
+
+        let mut interpreter = Interpreter { module, info, constants: Vec::new() };
+        for (_, expression) in interpreter.module.const_expressions.iter()
+        {
+            let value = interpreter.const_value(expression);
+            interpreter.constants.push(value);
+        }
+        Ok(interpreter)
+    }
+
+
+    fn const_value(&self, expression: &Expression) -> Result<Value, String>
+    {
+        let constant = |handle: &Handle<Expression>| self.constants[handle.index()].clone();
+        match expression
+        {
+            Expression::Literal(literal) => literal_value(literal),
+            Expression::Constant(handle) => constant(&self.module.constants[*handle].init),
+            Expression::ZeroValue(ty) => self.zero_value(&self.module.types[*ty].inner),
+            Expression::Compose { ty, components } =>
+                Ok(self.compose(*ty, components.iter().map(constant).collect::<Result<_, _>>()?)),
+            Expression::Splat { size, value } => Ok(Value::Composite(vec![constant(value)?; *size as usize])),
+            expression => Err(format!("unsupported constant expression {:?}", expression)),
+        }
+    }
+
+
+    fn with_inner<R>(&self, pointee: Pointee, f: impl FnOnce(&TypeInner) -> R) -> R
+    {
+        match pointee
+        {
+            Pointee::Type(ty) => f(&self.module.types[ty].inner),
+            Pointee::Vector(size, scalar) => f(&TypeInner::Vector { size, scalar }),
+            Pointee::Scalar(scalar) => f(&TypeInner::Scalar(scalar)),
+        }
+    }
+
+
+    fn size_of(&self, ty: Handle<Type>) -> usize
+    {
+        self.module.types[ty].inner.size(self.module.to_ctx()) as usize
+    }
+
+
+    fn zero_value(&self, inner: &TypeInner) -> Result<Value, String>
+    {
+        match *inner
+        {
+            TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => zero_scalar(scalar),
+            TypeInner::Vector { size, scalar } => Ok(Value::Composite(vec![zero_scalar(scalar)?; size as usize])),
+            TypeInner::Matrix { columns, rows, scalar } => Ok(Value::Composite(vec![
+                Value::Composite(vec![zero_scalar(scalar)?; rows as usize]); columns as usize
+            ])),
+            TypeInner::Array { base, size: ArraySize::Constant(count), .. } => Ok(Value::Composite(vec![
+                self.zero_value(&self.module.types[base].inner)?; count.get() as usize
+            ])),
+            TypeInner::Struct { ref members, .. } => members.iter()
+                .map(|member| self.zero_value(&self.module.types[member.ty].inner))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            ref inner => Err(format!("{:?} has no zero value", inner)),
+        }
+    }
+
+
+    // vector constructors can take smaller vectors as components, they are flattened
+    fn compose(&self, ty: Handle<Type>, components: Vec<Value>) -> Value
+    {
+        match self.module.types[ty].inner
+        {
+            TypeInner::Vector { .. } => Value::Composite(components.into_iter()
+                .flat_map(|component| match component
+                {
+                    Value::Composite(components) => components,
+                    scalar => vec![scalar],
+                })
+                .collect()),
+            _ => Value::Composite(components),
+        }
+    }
+
+
+    fn read(&self, bytes: &[u8], offset: usize, inner: &TypeInner) -> Result<Value, String>
+    {
+        match *inner
+        {
+            TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => read_scalar(bytes, offset, scalar),
+            TypeInner::Vector { size, scalar } => (0..size as usize)
+                .map(|i| read_scalar(bytes, offset + i * scalar.width as usize, scalar))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            TypeInner::Matrix { columns, rows, scalar } => (0..columns as usize)
+                .map(|i| self.read(bytes, offset + i * column_stride(rows, scalar), &TypeInner::Vector { size: rows, scalar }))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            TypeInner::Array { base, size: ArraySize::Constant(count), stride } => (0..count.get() as usize)
+                .map(|i| self.read(bytes, offset + i * stride as usize, &self.module.types[base].inner))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            TypeInner::Struct { ref members, .. } => members.iter()
+                .map(|member| self.read(bytes, offset + member.offset as usize, &self.module.types[member.ty].inner))
+                .collect::<Result<_, _>>()
+                .map(Value::Composite),
+            ref inner => Err(format!("can't load a value of type {:?}", inner)),
+        }
+    }
+
+
+    fn write(&self, bytes: &mut [u8], offset: usize, inner: &TypeInner, value: &Value) -> Result<(), String>
+    {
+        let components = match value
+        {
+            Value::Composite(components) => components.as_slice(),
+            scalar => return write_scalar(bytes, offset, scalar),
+        };
+
+        for (i, component) in components.iter().enumerate()
+        {
+            match *inner
+            {
+                TypeInner::Vector { scalar, .. } => write_scalar(bytes, offset + i * scalar.width as usize, component)?,
+                TypeInner::Matrix { rows, scalar, .. } => self.write(
+                    bytes, offset + i * column_stride(rows, scalar), &TypeInner::Vector { size: rows, scalar }, component,
+                )?,
+                TypeInner::Array { base, stride, .. } => self.write(
+                    bytes, offset + i * stride as usize, &self.module.types[base].inner, component,
+                )?,
+                TypeInner::Struct { ref members, .. } => self.write(
+                    bytes, offset + members[i].offset as usize, &self.module.types[members[i].ty].inner, component,
+                )?,
+                ref inner => return Err(format!("can't store {:?} as {:?}", value, inner)),
+            }
+        }
+        Ok(())
+    }
+
+
+    fn entry_point(&self, name: &str, stage: ShaderStage) -> Result<(usize, &EntryPoint), String>
+    {
+        self.module.entry_points.iter().enumerate()
+            .find(|(_, entry_point)| entry_point.name == name && entry_point.stage == stage)
+            .ok_or_else(|| format!("no {:?} entry point named {}", stage, name))
+    }
+
+
+    fn uses_barriers(&self, block: &Block) -> bool
+    {
+        block.iter().any(|statement| match statement
+        {
+            Statement::Barrier(_) | Statement::WorkGroupUniformLoad { .. } => true,
+            Statement::Block(block) => self.uses_barriers(block),
+            Statement::If { accept, reject, .. } => self.uses_barriers(accept) || self.uses_barriers(reject),
+            Statement::Switch { cases, .. } => cases.iter().any(|case| self.uses_barriers(&case.body)),
+            Statement::Loop { body, continuing, .. } => self.uses_barriers(body) || self.uses_barriers(continuing),
+            Statement::Call { function, .. } => self.uses_barriers(&self.module.functions[*function].body),
+            _ => false,
+        })
+    }
+
+
+    // the size naga gives a named type, for checking the layouts written on the rust side
+    pub fn type_size(&self, name: &str) -> Option<usize>
+    {
+        self.module.types.iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .map(|(_, ty)| ty.inner.size(self.module.to_ctx()) as usize)
+    }
+
+
+    pub fn workgroup_size(&self, entry_point: &str) -> Result<[u32; 3], String>
+    {
+        Ok(self.entry_point(entry_point, ShaderStage::Compute)?.1.workgroup_size)
+    }
+
+
+    // runs every invocation of workgroups, the buffers are updated in place
+    pub fn dispatch(&self, entry_point: &str, workgroups: [u32; 3], bindings: &Bindings) -> Result<(), String>
+    {
+        let (index, entry_point) = self.entry_point(entry_point, ShaderStage::Compute)?;
+        let info = self.info.get_entry_point(index);
+
+        let [size_x, size_y, size_z] = entry_point.workgroup_size;
+        let local_ids = (0..size_z)
+            .flat_map(|z| (0..size_y).flat_map(move |y| (0..size_x).map(move |x| [x, y, z])))
+            .collect::<Vec<[u32; 3]>>();
+        let threaded = local_ids.len() > 1 && self.uses_barriers(&entry_point.function.body);
+
+        for workgroup_z in 0..workgroups[2]
+        {
+            for workgroup_y in 0..workgroups[1]
+            {
+                for workgroup_x in 0..workgroups[0]
+                {
+                    let workgroup_id = [workgroup_x, workgroup_y, workgroup_z];
+
+                    // workgroup variables start out zeroed for every workgroup
+                    let workgroup_memory = self.module.global_variables.iter()
+                        .filter(|(_, global)| global.space == AddressSpace::WorkGroup)
+                        .map(|(handle, global)| (handle, Arc::new(Mutex::new(vec![0u8; self.size_of(global.ty)]))))
+                        .collect::<HashMap<Handle<GlobalVariable>, Buffer>>();
+
+                    let run = |local_invocation_index: usize, local_invocation_id: [u32; 3], barrier: Option<&WorkgroupBarrier>|
+                    {
+                        let builtins = Builtins
+                        {
+                            global_invocation_id: std::array::from_fn(|i|
+                                workgroup_id[i] * entry_point.workgroup_size[i] + local_invocation_id[i]
+                            ),
+                            local_invocation_id,
+                            local_invocation_index: local_invocation_index as u32,
+                            workgroup_id,
+                            num_workgroups: workgroups,
+                        };
+                        let mut invocation = Invocation::new(self, bindings, &workgroup_memory, barrier);
+                        let inputs = |binding: &Binding, _: &TypeInner| match binding
+                        {
+                            Binding::BuiltIn(builtin) => builtins.value(*builtin),
+                            _ => Err("compute shader inputs have to be builtins".to_owned()),
+                        };
+                        let result = invocation.run_entry_point(&entry_point.function, info, &inputs);
+                        if let Some(barrier) = barrier
+                        {
+                            barrier.leave();
+                        }
+                        result.map(|_| ())
+                    };
+
+                    if threaded
+                    {
+                        let barrier = &WorkgroupBarrier::new(local_ids.len());
+                        let run = &run;
+                        thread::scope(|scope|
+                        {
+                            let invocations = local_ids.iter().enumerate()
+                                .map(|(i, local_id)| scope.spawn(move || run(i, *local_id, Some(barrier))))
+                                .collect::<Vec<_>>();
+                            invocations.into_iter()
+                                .map(|invocation| invocation.join().unwrap_or_else(|_| Err("invocation panicked".to_owned())))
+                                .collect::<Result<Vec<()>, String>>()
+                        })?;
+                    }
+                    else
+                    {
+                        for (i, local_id) in local_ids.iter().enumerate()
+                        {
+                            run(i, *local_id, None)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+
+    // runs a single vertex or fragment invocation, inputs provides the value of every
+    // builtin and location argument, the outputs come back flattened out of their structs,
+    // a fragment invocation that discards returns None
+    pub(crate) fn run_stage(
+        &self,
+        stage: ShaderStage,
+        entry_point: &str,
+        bindings: &Bindings,
+        inputs: &dyn Fn(&Binding, &TypeInner) -> Result<Value, String>,
+    )
+        -> Result<Option<Vec<(Binding, Value)>>, String>
+    {
+        let (index, entry_point) = self.entry_point(entry_point, stage)?;
+        let workgroup_memory = HashMap::new();
+        let mut invocation = Invocation::new(self, bindings, &workgroup_memory, None);
+        let value = match invocation.run_entry_point(&entry_point.function, self.info.get_entry_point(index), inputs)?
+        {
+            Some(value) => value,
+            None if invocation.killed => return Ok(None),
+            None => return Ok(Some(Vec::new())),
+        };
+
+        let result = entry_point.function.result.as_ref().ok_or("entry point result without a type")?;
+        match (&result.binding, &self.module.types[result.ty].inner, value)
+        {
+            (Some(binding), _, value) => Ok(Some(vec![(binding.clone(), value)])),
+            (None, TypeInner::Struct { members, .. }, Value::Composite(values)) => Ok(Some(
+                members.iter().zip(values).filter_map(|(member, value)| Some((member.binding.clone()?, value))).collect()
+            )),
+            _ => Err("entry point outputs need bindings".to_owned()),
+        }
+    }
+
+
+    pub(crate) fn has_entry_point(&self, entry_point: &str, stage: ShaderStage) -> bool
+    {
+        self.entry_point(entry_point, stage).is_ok()
+    }
+}
+
+
+enum Flow
+{
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+}
+
+
+struct Frame<'a>
+{
+    function: &'a Function,
+    info: &'a FunctionInfo,
+    arguments: Vec<Value>,
+    values: Vec<Option<Value>>,
+    locals: Vec<usize>,
+}
+
+
+struct Invocation<'a>
+{
+    interpreter: &'a Interpreter,
+    bindings: &'a Bindings,
+    workgroup_memory: &'a HashMap<Handle<GlobalVariable>, Buffer>,
+    barrier: Option<&'a WorkgroupBarrier>,
+    local_memory: Vec<Vec<u8>>,
+    private_memory: HashMap<Handle<GlobalVariable>, usize>,
+    // set by discard, which ends the invocation from any function
+    killed: bool,
+}
+
+
+impl<'a> Invocation<'a>
+{
+    fn new(
+        interpreter: &'a Interpreter,
+        bindings: &'a Bindings,
+        workgroup_memory: &'a HashMap<Handle<GlobalVariable>, Buffer>,
+        barrier: Option<&'a WorkgroupBarrier>,
+    )
+        -> Self
+    {
+        Invocation
+        {
+            interpreter, bindings, workgroup_memory, barrier,
+            local_memory: Vec::new(),
+            private_memory: HashMap::new(),
+            killed: false,
+        }
+    }
+
+
+    fn run_entry_point(
+        &mut self,
+        function: &'a Function,
+        info: &'a FunctionInfo,
+        inputs: &dyn Fn(&Binding, &TypeInner) -> Result<Value, String>,
+    )
+        -> Result<Option<Value>, String>
+    {
+        // private variables live below the variables of every function call
+        let interpreter = self.interpreter;
+        for (handle, global) in interpreter.module.global_variables.iter()
+            .filter(|(_, global)| global.space == AddressSpace::Private)
+        {
+            let mut bytes = vec![0u8; interpreter.size_of(global.ty)];
+            if let Some(init) = global.init
+            {
+                let value = interpreter.constants[init.index()].clone()?;
+                interpreter.write(&mut bytes, 0, &interpreter.module.types[global.ty].inner, &value)?;
+            }
+            self.private_memory.insert(handle, self.local_memory.len());
+            self.local_memory.push(bytes);
+        }
+
+        let types = &interpreter.module.types;
+        let arguments = function.arguments.iter()
+            .map(|argument| match (&argument.binding, &types[argument.ty].inner)
+            {
+                (Some(binding), inner) => inputs(binding, inner),
+                (None, TypeInner::Struct { members, .. }) => members.iter()
+                    .map(|member| inputs(member.binding.as_ref().ok_or("struct input without a binding")?, &types[member.ty].inner))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Composite),
+                _ => Err("entry point input without a binding".to_owned()),
+            })
+            .collect::<Result<Vec<Value>, String>>()?;
+        self.call_function(function, info, arguments)
+    }
+
+
+    fn call_function(&mut self, function: &'a Function, info: &'a FunctionInfo, arguments: Vec<Value>)
+        -> Result<Option<Value>, String>
+    {
+        let memory_base = self.local_memory.len();
+        let mut frame = Frame
+        {
+            function,
+            info,
+            arguments,
+            values: vec![None; function.expressions.len()],
+            locals: Vec::new(),
+        };
+
+        for (_, local) in function.local_variables.iter()
+        {
+            frame.locals.push(self.local_memory.len());
+            self.local_memory.push(vec![0u8; self.interpreter.size_of(local.ty)]);
+        }
+        for (handle, local) in function.local_variables.iter()
+        {
+            if let Some(init) = local.init
+            {
+                let value = self.eval(&mut frame, init)?;
+                let pointer = Pointer
+                {
+                    memory: Memory::Local(frame.locals[handle.index()]), offset: 0, pointee: Pointee::Type(local.ty),
+                };
+                self.store(&pointer, &value)?;
+            }
+        }
+
+        let flow = self.exec_block(&mut frame, &function.body);
+        self.local_memory.truncate(memory_base);
+
+        match flow?
+        {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(None),
+        }
+    }
+
+
+    fn load(&self, pointer: &Pointer) -> Result<Value, String>
+    {
+        self.interpreter.with_inner(pointer.pointee, |inner| match &pointer.memory
+        {
+            Memory::Shared(buffer) => self.interpreter.read(&buffer.lock().unwrap(), pointer.offset, inner),
+            Memory::Local(index) => self.interpreter.read(&self.local_memory[*index], pointer.offset, inner),
+        })
+    }
+
+
+    fn store(&mut self, pointer: &Pointer, value: &Value) -> Result<(), String>
+    {
+        let interpreter = self.interpreter;
+        let local_memory = &mut self.local_memory;
+        interpreter.with_inner(pointer.pointee, |inner| match &pointer.memory
+        {
+            Memory::Shared(buffer) => interpreter.write(&mut buffer.lock().unwrap(), pointer.offset, inner, value),
+            Memory::Local(index) => interpreter.write(&mut local_memory[*index], pointer.offset, inner, value),
+        })
+    }
+
+
+    // the whole read-modify-write happens under the lock of the buffer
+    fn atomic(&mut self, pointer: &Pointer, fun: AtomicFunction, value: Value, compare: Option<Value>)
+        -> Result<Value, String>
+    {
+        let scalar = self.interpreter.with_inner(pointer.pointee, |inner| match *inner
+        {
+            TypeInner::Atomic(scalar) => Ok(scalar),
+            ref inner => Err(format!("atomic operation on {:?}", inner)),
+        })?;
+        let buffer = match &pointer.memory
+        {
+            Memory::Shared(buffer) => buffer,
+            Memory::Local(_) => return Err("atomics have to be in storage or workgroup memory".to_owned()),
+        };
+
+        let mut bytes = buffer.lock().unwrap();
+        let old = read_scalar(&bytes, pointer.offset, scalar)?;
+        let (new, result) = match fun
+        {
+            AtomicFunction::Add => (binary(BinaryOperator::Add, &old, &value)?, old.clone()),
+            AtomicFunction::Subtract => (binary(BinaryOperator::Subtract, &old, &value)?, old.clone()),
+            AtomicFunction::And => (binary(BinaryOperator::And, &old, &value)?, old.clone()),
+            AtomicFunction::ExclusiveOr => (binary(BinaryOperator::ExclusiveOr, &old, &value)?, old.clone()),
+            AtomicFunction::InclusiveOr => (binary(BinaryOperator::InclusiveOr, &old, &value)?, old.clone()),
+            AtomicFunction::Min => (math(MathFunction::Min, &[old.clone(), value])?, old.clone()),
+            AtomicFunction::Max => (math(MathFunction::Max, &[old.clone(), value])?, old.clone()),
+            AtomicFunction::Exchange { compare: None } => (value, old.clone()),
+            AtomicFunction::Exchange { compare: Some(_) } =>
+            {
+                let exchanged = binary(BinaryOperator::Equal, &old, compare.as_ref().unwrap())?.as_bool()?;
+                let new = if exchanged { value } else { old.clone() };
+                (new, Value::Composite(vec![old.clone(), Value::Bool(exchanged)]))
+            },
+        };
+        write_scalar(&mut bytes, pointer.offset, &new)?;
+        Ok(result)
+    }
+
+
+    fn barrier(&self)
+    {
+        if let Some(barrier) = self.barrier
+        {
+            barrier.wait();
+        }
+    }
+
+
+    // textures and samplers are values, every other global is a pointer to its memory
+    fn global(&self, handle: Handle<GlobalVariable>) -> Result<Value, String>
+    {
+        let global = &self.interpreter.module.global_variables[handle];
+        let memory = match global.space
+        {
+            AddressSpace::Handle =>
+            {
+                let binding = global.binding.as_ref().ok_or("resource without a binding")?;
+                let key = (binding.group, binding.binding);
+                let missing = || format!("nothing bound to @group({}) @binding({})", binding.group, binding.binding);
+                return match self.interpreter.module.types[global.ty].inner
+                {
+                    TypeInner::Image { .. } => Ok(Value::Texture(self.bindings.textures.get(&key).ok_or_else(missing)?.clone())),
+                    TypeInner::Sampler { .. } => Ok(Value::Sampler(*self.bindings.samplers.get(&key).ok_or_else(missing)?)),
+                    ref inner => Err(format!("unsupported resource {:?}", inner)),
+                };
+            },
+            AddressSpace::Storage { .. } | AddressSpace::Uniform =>
+            {
+                let binding = global.binding.as_ref().ok_or("resource without a binding")?;
+                let buffer = self.bindings.buffers.get(&(binding.group, binding.binding)).ok_or_else(||
+                    format!("nothing bound to @group({}) @binding({})", binding.group, binding.binding)
+                )?;
+                Memory::Shared(buffer.clone())
+            },
+            AddressSpace::WorkGroup => Memory::Shared(self.workgroup_memory[&handle].clone()),
+            AddressSpace::Private => Memory::Local(self.private_memory[&handle]),
+            space => return Err(format!("unsupported address space {:?}", space)),
+        };
+
+        Ok(Value::Pointer(Pointer { memory, offset: 0, pointee: Pointee::Type(global.ty) }))
+    }
+
+
+    fn access(&self, base: Value, index: usize) -> Result<Value, String>
+    {
+        match base
+        {
+            Value::Composite(components) =>
+            {
+                // dynamic indices into values are clamped like on the gpu
+                let index = index.min(components.len() - 1);
+                Ok(components[index].clone())
+            },
+            Value::Pointer(pointer) => self.interpreter.with_inner(pointer.pointee, |inner|
+            {
+                let (offset, pointee) = match *inner
+                {
+                    TypeInner::Array { base, size, stride } =>
+                    {
+                        let index = match size
+                        {
+                            ArraySize::Constant(count) => index.min(count.get() as usize - 1),
+                            // runtime sized arrays are checked by the loads and stores
+                            ArraySize::Dynamic => index,
+                        };
+                        (index.saturating_mul(stride as usize), Pointee::Type(base))
+                    },
+                    TypeInner::Struct { ref members, .. } =>
+                        (members[index].offset as usize, Pointee::Type(members[index].ty)),
+                    TypeInner::Vector { size, scalar } =>
+                        (index.min(size as usize - 1) * scalar.width as usize, Pointee::Scalar(scalar)),
+                    TypeInner::Matrix { columns, rows, scalar } =>
+                        (index.min(columns as usize - 1) * column_stride(rows, scalar), Pointee::Vector(rows, scalar)),
+                    ref inner => return Err(format!("can't index into {:?}", inner)),
+                };
+                Ok(Value::Pointer(Pointer
+                {
+                    memory: pointer.memory.clone(), offset: pointer.offset.saturating_add(offset), pointee,
+                }))
+            }),
+            base => Err(format!("can't index into {:?}", base)),
+        }
+    }
+
+
+    fn multiply(&self, frame: &Frame, left: Handle<Expression>, right: Handle<Expression>, a: &Value, b: &Value)
+        -> Result<Value, String>
+    {
+        let types = &self.interpreter.module.types;
+        let is_matrix = |handle: Handle<Expression>| matches!(frame.info[handle].ty.inner_with(types), TypeInner::Matrix { .. });
+        let columns = |value: &Value| match value
+        {
+            Value::Composite(columns) => Ok(columns.clone()),
+            value => Err(format!("expected a matrix, got {:?}", value)),
+        };
+        let matrix_times_vector = |matrix: &[Value], vector: &Value| -> Result<Value, String>
+        {
+            let vector = columns(vector)?;
+            matrix.iter().zip(&vector)
+                .map(|(column, factor)| binary(BinaryOperator::Multiply, column, factor))
+                .reduce(|sum, product| binary(BinaryOperator::Add, &sum?, &product?))
+                .unwrap_or_else(|| Err("empty matrix".to_owned()))
+        };
+
+        match (is_matrix(left), is_matrix(right), frame.info[right].ty.inner_with(types))
+        {
+            (true, false, TypeInner::Vector { .. }) => matrix_times_vector(&columns(a)?, b),
+            (false, true, _) if matches!(frame.info[left].ty.inner_with(types), TypeInner::Vector { .. }) =>
+                columns(b)?.iter().map(|column| dot(a, column)).collect::<Result<_, _>>().map(Value::Composite),
+            (true, true, _) =>
+            {
+                let matrix = columns(a)?;
+                columns(b)?.iter()
+                    .map(|column| matrix_times_vector(&matrix, column))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Composite)
+            },
+            _ => binary(BinaryOperator::Multiply, a, b),
+        }
+    }
+
+
+    fn eval(&mut self, frame: &mut Frame<'a>, handle: Handle<Expression>) -> Result<Value, String>
+    {
+        if let Some(value) = &frame.values[handle.index()]
+        {
+            return Ok(value.clone());
+        }
+        let value = self.compute(frame, handle)?;
+        frame.values[handle.index()] = Some(value.clone());
+        Ok(value)
+    }
+
+
+    fn compute(&mut self, frame: &mut Frame<'a>, handle: Handle<Expression>) -> Result<Value, String>
+    {
+        let interpreter = self.interpreter;
+        match frame.function.expressions[handle]
+        {
+            Expression::Literal(ref literal) => literal_value(literal),
+            Expression::Constant(constant) =>
+                interpreter.constants[interpreter.module.constants[constant].init.index()].clone(),
+            Expression::ZeroValue(ty) => interpreter.zero_value(&interpreter.module.types[ty].inner),
+            Expression::Compose { ty, ref components } =>
+            {
+                let components = components.iter()
+                    .map(|component| self.eval(frame, *component))
+                    .collect::<Result<Vec<Value>, String>>()?;
+                Ok(interpreter.compose(ty, components))
+            },
+            Expression::Access { base, index } =>
+            {
+                let base = self.eval(frame, base)?;
+                let index = self.eval(frame, index)?.as_index()?;
+                self.access(base, index)
+            },
+            Expression::AccessIndex { base, index } =>
+            {
+                let base = self.eval(frame, base)?;
+                self.access(base, index as usize)
+            },
+            Expression::Splat { size, value } => Ok(Value::Composite(vec![self.eval(frame, value)?; size as usize])),
+            Expression::Swizzle { size, vector, pattern } =>
+            {
+                let vector = self.eval(frame, vector)?;
+                pattern[..size as usize].iter().map(|component| self.access(vector.clone(), *component as usize))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Composite)
+            },
+            Expression::FunctionArgument(index) => Ok(frame.arguments[index as usize].clone()),
+            Expression::GlobalVariable(global) => self.global(global),
+            Expression::LocalVariable(local) => Ok(Value::Pointer(Pointer
+                {
+                    memory: Memory::Local(frame.locals[local.index()]),
+                    offset: 0,
+                    pointee: Pointee::Type(frame.function.local_variables[local].ty),
+                })),
+            Expression::Load { pointer } =>
+            {
+                let pointer = self.eval(frame, pointer)?;
+                self.load(pointer.as_pointer()?)
+            },
+            Expression::Unary { op, expr } => unary(op, &self.eval(frame, expr)?),
+            Expression::Binary { op, left, right } =>
+            {
+                let a = self.eval(frame, left)?;
+                let b = self.eval(frame, right)?;
+                match op
+                {
+                    BinaryOperator::Multiply => self.multiply(frame, left, right, &a, &b),
+                    op => binary(op, &a, &b),
+                }
+            },
+            Expression::Select { condition, accept, reject } =>
+            {
+                let condition = self.eval(frame, condition)?;
+                let accept = self.eval(frame, accept)?;
+                let reject = self.eval(frame, reject)?;
+                zip_map(&[condition, accept, reject], &|args|
+                    Ok(if args[0].as_bool()? { args[1].clone() } else { args[2].clone() })
+                )
+            },
+            Expression::Relational { fun, argument } =>
+            {
+                let argument = self.eval(frame, argument)?;
+                let components = match &argument
+                {
+                    Value::Composite(components) => components.clone(),
+                    scalar => vec![scalar.clone()],
+                };
+                match fun
+                {
+                    RelationalFunction::All => Ok(Value::Bool(
+                        components.iter().map(Value::as_bool).collect::<Result<Vec<bool>, String>>()?.iter().all(|b| *b)
+                    )),
+                    RelationalFunction::Any => Ok(Value::Bool(
+                        components.iter().map(Value::as_bool).collect::<Result<Vec<bool>, String>>()?.iter().any(|b| *b)
+                    )),
+                    RelationalFunction::IsNan => zip_map(&[argument], &|args| Ok(Value::Bool(args[0].as_f32()?.is_nan()))),
+                    RelationalFunction::IsInf => zip_map(&[argument], &|args| Ok(Value::Bool(args[0].as_f32()?.is_infinite()))),
+                }
+            },
+            Expression::Math { fun, arg, arg1, arg2, arg3 } =>
+            {
+                let args = [Some(arg), arg1, arg2, arg3].into_iter().flatten()
+                    .map(|arg| self.eval(frame, arg))
+                    .collect::<Result<Vec<Value>, String>>()?;
+                math(fun, &args)
+            },
+            Expression::As { expr, kind, convert } => cast(&self.eval(frame, expr)?, kind, convert.is_some()),
+            Expression::ArrayLength(expr) =>
+            {
+                let pointer = self.eval(frame, expr)?;
+                let pointer = pointer.as_pointer()?;
+                let stride = interpreter.with_inner(pointer.pointee, |inner| match *inner
+                {
+                    TypeInner::Array { stride, .. } => Ok(stride as usize),
+                    ref inner => Err(format!("arrayLength of {:?}", inner)),
+                })?;
+                let length = match &pointer.memory
+                {
+                    Memory::Shared(buffer) => buffer.lock().unwrap().len(),
+                    Memory::Local(index) => self.local_memory[*index].len(),
+                };
+                Ok(Value::U32((length.saturating_sub(pointer.offset) / stride) as u32))
+            },
+            // textures are sampled at their only mip level, see Sampler::sample
+            Expression::ImageSample { image, sampler, gather: None, coordinate, array_index: None, offset, depth_ref: None, .. } =>
+            {
+                let image = self.eval(frame, image)?;
+                let sampler = match self.eval(frame, sampler)?
+                {
+                    Value::Sampler(sampler) => sampler,
+                    value => return Err(format!("expected a sampler, got {:?}", value)),
+                };
+                let coordinate = match self.eval(frame, coordinate)?
+                {
+                    Value::Composite(components) if components.len() == 2 => [components[0].as_f32()?, components[1].as_f32()?],
+                    value => return Err(format!("only 2d textures can be sampled, got coordinate {:?}", value)),
+                };
+                let offset = match offset
+                {
+                    Some(offset) => interpreter.constants[offset.index()].clone()?.as_i32s()?,
+                    None => vec![0, 0],
+                };
+                let texel = sampler.sample(image.as_texture()?, coordinate, [offset[0], offset[1]]);
+                Ok(Value::Composite(texel.iter().map(|channel| Value::F32(*channel)).collect()))
+            },
+            Expression::ImageLoad { image, coordinate, array_index: None, sample: None, .. } =>
+            {
+                let image = self.eval(frame, image)?;
+                let coordinate = self.eval(frame, coordinate)?.as_i32s()?;
+                let texel = image.as_texture()?.load(coordinate[0], coordinate[1]);
+                Ok(Value::Composite(texel.iter().map(|channel| Value::F32(*channel)).collect()))
+            },
+            Expression::ImageQuery { image, query } =>
+            {
+                let image = self.eval(frame, image)?;
+                let image = image.as_texture()?;
+                match query
+                {
+                    ImageQuery::Size { .. } => Ok(Value::Composite(vec![Value::U32(image.width()), Value::U32(image.height())])),
+                    ImageQuery::NumLevels | ImageQuery::NumLayers | ImageQuery::NumSamples => Ok(Value::U32(1)),
+                }
+            },
+            ref expression => Err(format!("unsupported expression {:?}", expression)),
+        }
+    }
+
+
+    fn exec_block(&mut self, frame: &mut Frame<'a>, block: &'a Block) -> Result<Flow, String>
+    {
+        for statement in block.iter()
+        {
+            match *statement
+            {
+                Statement::Emit(ref range) =>
+                {
+                    for handle in range.clone()
+                    {
+                        let value = self.compute(frame, handle)?;
+                        frame.values[handle.index()] = Some(value);
+                    }
+                },
+                Statement::Block(ref block) => match self.exec_block(frame, block)?
+                {
+                    Flow::Next => {},
+                    flow => return Ok(flow),
+                },
+                Statement::If { condition, ref accept, ref reject } =>
+                {
+                    let block = if self.eval(frame, condition)?.as_bool()? { accept } else { reject };
+                    match self.exec_block(frame, block)?
+                    {
+                        Flow::Next => {},
+                        flow => return Ok(flow),
+                    }
+                },
+                Statement::Switch { selector, ref cases } =>
+                {
+                    let selector = self.eval(frame, selector)?;
+                    let start = cases.iter()
+                        .position(|case| match (case.value, &selector)
+                        {
+                            (SwitchValue::I32(value), Value::I32(selector)) => value == *selector,
+                            (SwitchValue::U32(value), Value::U32(selector)) => value == *selector,
+                            _ => false,
+                        })
+                        .or_else(|| cases.iter().position(|case| case.value == SwitchValue::Default))
+                        .ok_or("switch without a default case")?;
+
+                    for case in &cases[start..]
+                    {
+                        match self.exec_block(frame, &case.body)?
+                        {
+                            Flow::Next if case.fall_through => continue,
+                            Flow::Next | Flow::Break => break,
+                            flow => return Ok(flow),
+                        }
+                    }
+                },
+                Statement::Loop { ref body, ref continuing, break_if } =>
+                {
+                    loop
+                    {
+                        match self.exec_block(frame, body)?
+                        {
+                            Flow::Break => break,
+                            Flow::Return(value) => return Ok(Flow::Return(value)),
+                            Flow::Next | Flow::Continue => {},
+                        }
+                        if let Flow::Return(value) = self.exec_block(frame, continuing)?
+                        {
+                            return Ok(Flow::Return(value));
+                        }
+                        if let Some(break_if) = break_if
+                        {
+                            if self.eval(frame, break_if)?.as_bool()?
+                            {
+                                break;
+                            }
+                        }
+                    }
+                },
+                Statement::Break => return Ok(Flow::Break),
+                Statement::Continue => return Ok(Flow::Continue),
+                Statement::Return { value } =>
+                {
+                    let value = value.map(|value| self.eval(frame, value)).transpose()?;
+                    return Ok(Flow::Return(value));
+                },
+                Statement::Barrier(_) => self.barrier(),
+                Statement::Kill =>
+                {
+                    self.killed = true;
+                    return Ok(Flow::Return(None));
+                },
+                Statement::Store { pointer, value } =>
+                {
+                    let pointer = self.eval(frame, pointer)?;
+                    let value = self.eval(frame, value)?;
+                    self.store(pointer.as_pointer()?, &value)?;
+                },
+                Statement::Atomic { pointer, ref fun, value, result } =>
+                {
+                    let pointer = self.eval(frame, pointer)?;
+                    let value = self.eval(frame, value)?;
+                    let compare = match fun
+                    {
+                        AtomicFunction::Exchange { compare: Some(compare) } => Some(self.eval(frame, *compare)?),
+                        _ => None,
+                    };
+                    let old = self.atomic(pointer.as_pointer()?, *fun, value, compare)?;
+                    frame.values[result.index()] = Some(old);
+                },
+                Statement::WorkGroupUniformLoad { pointer, result } =>
+                {
+                    let pointer = self.eval(frame, pointer)?;
+                    self.barrier();
+                    let value = self.load(pointer.as_pointer()?)?;
+                    self.barrier();
+                    frame.values[result.index()] = Some(value);
+                },
+                Statement::Call { function, ref arguments, result } =>
+                {
+                    let arguments = arguments.iter()
+                        .map(|argument| self.eval(frame, *argument))
+                        .collect::<Result<Vec<Value>, String>>()?;
+                    let interpreter = self.interpreter;
+                    let value = self.call_function(
+                        &interpreter.module.functions[function], &interpreter.info[function], arguments,
+                    )?;
+                    if self.killed
+                    {
+                        return Ok(Flow::Return(None));
+                    }
+                    if let (Some(result), Some(value)) = (result, value)
+                    {
+                        frame.values[result.index()] = Some(value);
+                    }
+                },
+                ref statement => return Err(format!("unsupported statement {:?}", statement)),
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use gpu_backend::AsBytes;
+
+
+    fn run(source: &str, entry_point: &str, workgroups: [u32; 3], buffers: &[Vec<u8>]) -> Vec<Vec<u8>>
+    {
+        let interpreter = Interpreter::new(source).unwrap_or_else(|error| panic!("{}", error));
+        let mut bindings = Bindings::new();
+        for (binding, bytes) in buffers.iter().enumerate()
+        {
+            bindings.set(0, binding as u32, bytes.clone());
+        }
+        interpreter.dispatch(entry_point, workgroups, &bindings).unwrap_or_else(|error| panic!("{}", error));
+
+        (0..buffers.len()).map(|binding| bindings.bytes(0, binding as u32).unwrap()).collect()
+    }
+
+
+    fn decode<T>(bytes: &[u8], from_le_bytes: fn([u8; 4]) -> T) -> Vec<T>
+    {
+        bytes.chunks(4).map(|chunk| from_le_bytes(chunk.try_into().unwrap())).collect()
+    }
+
+
+    #[test]
+    fn structs_loops_switches_and_calls()
+    {
+        let source = r#"
+            struct Particle
+            {
+                position: vec4f,
+                weight: f32,
+                steps: u32,
+            }
+
+            @group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+
+            fn scale(m: mat2x2f, v: vec2f) -> vec2f
+            {
+                return m * v;
+            }
+
+            @compute @workgroup_size(2)
+            fn main(@builtin(global_invocation_id) id: vec3u)
+            {
+                if (id.x >= arrayLength(&particles)) { return; }
+                var particle = particles[id.x];
+
+                var total = 0.0;
+                for (var i = 0u; i < particle.steps; i++)
+                {
+                    if (i == 2u) { continue; }
+                    total += f32(i);
+                }
+
+                switch (id.x % 3u)
+                {
+                    case 0u: { particle.weight = total; }
+                    case 1u, 2u: { particle.weight = -total; }
+                    default: {}
+                }
+
+                let m = mat2x2f(vec2f(2.0, 0.0), vec2f(0.0, 3.0));
+                particle.position = vec4f(scale(m, particle.position.xy), particle.position.zw);
+                particles[id.x] = particle;
+            }
+        "#;
+
+        // a particle takes 32 bytes, the struct is padded to the 16 byte alignment of its vec4f
+        let particles = (0..5u32)
+            .flat_map(|i| [1.0f32, 1.0, i as f32, 7.0, 0.0].map(f32::to_bits).into_iter().chain([i, 0, 0]))
+            .collect::<Vec<u32>>();
+        let result = run(source, "main", [3, 1, 1], &[particles.as_bytes().to_vec()]);
+        let result = decode(&result[0], u32::from_le_bytes);
+
+        let expected_totals = [0.0, 0.0, 1.0, 1.0, 4.0];
+        for (i, particle) in result.chunks(8).enumerate()
+        {
+            let sign = if i % 3 == 0 { 1.0 } else { -1.0 };
+            let floats = particle[..5].iter().map(|bits| f32::from_bits(*bits)).collect::<Vec<f32>>();
+            assert_eq!(floats[4], sign * expected_totals[i]);
+            assert_eq!(&floats[..4], &[2.0, 3.0, i as f32, 7.0]);
+            assert_eq!(particle[5], i as u32);
+        }
+    }
+
+
+    #[test]
+    fn atomics_count_into_a_histogram()
+    {
+        let source = r#"
+            @group(0) @binding(0) var<storage, read> values: array<u32>;
+            @group(0) @binding(1) var<storage, read_write> histogram: array<atomic<u32>, 4>;
+
+            var<workgroup> local_count: atomic<u32>;
+
+            @compute @workgroup_size(8)
+            fn main(@builtin(global_invocation_id) id: vec3u, @builtin(local_invocation_index) index: u32)
+            {
+                if (id.x < arrayLength(&values))
+                {
+                    atomicAdd(&histogram[values[id.x] % 4u], 1u);
+                    atomicAdd(&local_count, 1u);
+                }
+                workgroupBarrier();
+                if (index == 0u)
+                {
+                    atomicMax(&histogram[3], atomicLoad(&local_count) * 100u);
+                }
+            }
+        "#;
+
+        let values = (0..20u32).map(|i| i * 7 % 3).collect::<Vec<u32>>();
+        let result = run(source, "main", [3, 1, 1], &[values.as_bytes().to_vec(), vec![0; 16]]);
+        let histogram = decode(&result[1], u32::from_le_bytes);
+
+        let count = |bucket: u32| values.iter().filter(|value| **value % 4 == bucket).count() as u32;
+        assert_eq!(&histogram[..3], &[count(0), count(1), count(2)]);
+        // every full workgroup counts 8 values, workgroup memory starts at zero for each
+        assert_eq!(histogram[3], 800);
+    }
+
+
+    #[test]
+    fn barriers_order_workgroup_memory()
+    {
+        let source = r#"
+            @group(0) @binding(0) var<storage, read_write> data: array<i32>;
+
+            var<workgroup> shared_data: array<i32, 16>;
+
+            @compute @workgroup_size(16)
+            fn main(@builtin(global_invocation_id) id: vec3u, @builtin(local_invocation_id) local_id: vec3u)
+            {
+                shared_data[local_id.x] = data[id.x];
+                workgroupBarrier();
+                data[id.x] = shared_data[15u - local_id.x];
+            }
+        "#;
+
+        let values = (0..32).collect::<Vec<i32>>();
+        let result = run(source, "main", [2, 1, 1], &[values.as_bytes().to_vec()]);
+        let reversed = decode(&result[0], i32::from_le_bytes);
+
+        let expected = values.chunks(16).flat_map(|chunk| chunk.iter().rev().copied()).collect::<Vec<i32>>();
+        assert_eq!(reversed, expected);
+    }
+
+
+    #[test]
+    fn out_of_bounds_stores_are_reported()
+    {
+        let interpreter = Interpreter::new(r#"
+            @group(0) @binding(0) var<storage, read_write> data: array<u32>;
+
+            @compute @workgroup_size(1)
+            fn main(@builtin(global_invocation_id) id: vec3u) { data[id.x + 1u] = 1u; }
+        "#).unwrap();
+
+        let mut bindings = Bindings::new();
+        bindings.set(0, 0, vec![0; 4]);
+        assert!(interpreter.dispatch("main", [1, 1, 1], &bindings).is_err());
+    }
+}
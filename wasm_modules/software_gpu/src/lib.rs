@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::path::Path;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+pub mod interpreter;
+pub mod rasterizer;
+use rasterizer::Image;
+mod backend;
+pub use backend::SoftwareBackend;
+
+
+// compares a rendered image with golden/<name>.png of the crate at manifest_dir, set UPDATE_GOLDEN=1 to
+// rewrite the reference images after an intended change
+pub fn assert_golden(manifest_dir: &str, name: &str, image: &Image)
+{
+    let path = Path::new(manifest_dir).join("golden").join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some()
+    {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        image.save_png(&path).unwrap();
+        return;
+    }
+
+    let reference = Image::load_png(&path).unwrap();
+    if let Err(error) = image.compare(&reference, 2)
+    {
+        let actual = std::env::temp_dir().join(format!("{}.actual.png", name));
+        image.save_png(&actual).unwrap();
+        panic!("{} doesn't match {}: {}, the rendered image is at {}", name, path.display(), error, actual.display());
+    }
+}
+
+
+// the software backend finishes its work on submit, so its futures are ready the first time they are polled
+pub fn block_on<T>(future: impl Future<Output = T>) -> T
+{
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop()))
+    {
+        Poll::Ready(value) => value,
+        Poll::Pending => panic!("the future isn't ready, it waits on something else than the software backend"),
+    }
+}
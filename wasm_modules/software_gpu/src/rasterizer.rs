@@ -0,0 +1,999 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use gpu_backend::{
+    AddressMode, BlendComponent, BlendFactor, BlendOperation, BlendState, CompareFunction, CullMode, DepthStencilState,
+    FilterMode, FrontFace, PrimitiveTopology, VertexBufferLayout, VertexFormat, VertexStepMode,
+};
+use naga::{Binding, BuiltIn, Interpolation, ScalarKind, ShaderStage, TypeInner};
+
+use crate::interpreter::{Bindings, Interpreter, Value};
+
+
+// an rgba8unorm image, used as render target and as texture
+#[derive(Clone, PartialEq)]
+pub struct Image
+{
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 4]>,
+}
+
+
+impl fmt::Debug for Image
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "Image({}x{})", self.width, self.height)
+    }
+}
+
+
+fn to_unorm8(value: f32) -> u8
+{
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+
+impl Image
+{
+    pub fn new(width: u32, height: u32, color: [f32; 4]) -> Self
+    {
+        Image { width, height, pixels: vec![color.map(to_unorm8); (width * height) as usize] }
+    }
+
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<[u8; 4]>) -> Result<Self, String>
+    {
+        if pixels.len() != (width * height) as usize
+        {
+            return Err(format!("{} pixels don't make a {}x{} image", pixels.len(), width, height));
+        }
+        Ok(Image { width, height, pixels })
+    }
+
+
+    pub fn width(&self) -> u32
+    {
+        self.width
+    }
+
+
+    pub fn height(&self) -> u32
+    {
+        self.height
+    }
+
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4]
+    {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+
+    // texels outside of the image read as transparent black
+    pub fn load(&self, x: i32, y: i32) -> [f32; 4]
+    {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32
+        {
+            return [0.0; 4];
+        }
+        self.pixel(x as u32, y as u32).map(|channel| channel as f32 / 255.0)
+    }
+
+
+    pub fn load_png(path: &Path) -> Result<Self, String>
+    {
+        let file = File::open(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().map_err(|error| format!("{}: {}", path.display(), error))?;
+        let mut bytes = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut bytes).map_err(|error| format!("{}: {}", path.display(), error))?;
+
+        let channels = match info.color_type
+        {
+            png::ColorType::Rgba if info.bit_depth == png::BitDepth::Eight => 4,
+            png::ColorType::Rgb if info.bit_depth == png::BitDepth::Eight => 3,
+            color_type => return Err(format!("{}: unsupported {:?} {:?} png", path.display(), color_type, info.bit_depth)),
+        };
+        let pixels = bytes[..info.buffer_size()].chunks(channels)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], if channels == 4 { pixel[3] } else { 255 }])
+            .collect();
+        Image::from_pixels(info.width, info.height, pixels)
+    }
+
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String>
+    {
+        let file = File::create(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels.concat()))
+            .map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+
+    // fails when any channel of any pixel is further than tolerance away from the reference
+    pub fn compare(&self, reference: &Image, tolerance: u8) -> Result<(), String>
+    {
+        if (self.width, self.height) != (reference.width, reference.height)
+        {
+            return Err(format!(
+                "the image is {}x{}, the reference {}x{}", self.width, self.height, reference.width, reference.height,
+            ));
+        }
+
+        let differences = self.pixels.iter().zip(&reference.pixels)
+            .map(|(a, b)| a.iter().zip(b).map(|(a, b)| a.abs_diff(*b)).max().unwrap())
+            .filter(|difference| *difference > tolerance)
+            .collect::<Vec<u8>>();
+        match differences.iter().max()
+        {
+            Some(max) => Err(format!(
+                "{} of {} pixels differ by up to {}, the tolerance is {}",
+                differences.len(), self.pixels.len(), max, tolerance,
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sampler
+{
+    address_mode_u: AddressMode,
+    address_mode_v: AddressMode,
+    mag_filter: FilterMode,
+}
+
+
+fn address(mode: AddressMode, coordinate: i32, size: u32) -> i32
+{
+    let size = size as i32;
+    match mode
+    {
+        AddressMode::ClampToEdge => coordinate.clamp(0, size - 1),
+        AddressMode::Repeat => coordinate.rem_euclid(size),
+        AddressMode::MirrorRepeat =>
+        {
+            let coordinate = coordinate.rem_euclid(2 * size);
+            if coordinate < size { coordinate } else { 2 * size - 1 - coordinate }
+        },
+    }
+}
+
+
+impl Sampler
+{
+    pub fn new(address_mode_u: AddressMode, address_mode_v: AddressMode, mag_filter: FilterMode) -> Self
+    {
+        Sampler { address_mode_u, address_mode_v, mag_filter }
+    }
+
+
+    // there are no derivatives and no mip levels on the cpu, the base level is
+    // always sampled with the magnification filter
+    pub fn sample(&self, image: &Image, coordinate: [f32; 2], offset: [i32; 2]) -> [f32; 4]
+    {
+        let texel = |x: i32, y: i32| image.load(
+            address(self.address_mode_u, x + offset[0], image.width),
+            address(self.address_mode_v, y + offset[1], image.height),
+        );
+        let x = coordinate[0] * image.width as f32;
+        let y = coordinate[1] * image.height as f32;
+
+        match self.mag_filter
+        {
+            FilterMode::Nearest => texel(x.floor() as i32, y.floor() as i32),
+            FilterMode::Linear =>
+            {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+                let (fx, fy) = (x - x.floor(), y - y.floor());
+                let [a, b, c, d] = [texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1)];
+                std::array::from_fn(|i|
+                    (a[i] * (1.0 - fx) + b[i] * fx) * (1.0 - fy) + (c[i] * (1.0 - fx) + d[i] * fx) * fy
+                )
+            },
+        }
+    }
+}
+
+
+// the components of a vertex attribute, the formats the chapters don't use aren't supported
+fn decode(format: VertexFormat, bytes: &[u8]) -> Result<Vec<Value>, String>
+{
+    use VertexFormat::*;
+    let word = |chunk: &[u8]| <[u8; 4]>::try_from(chunk).unwrap();
+    Ok(match format
+    {
+        Float32 | Float32x2 | Float32x3 | Float32x4 =>
+            bytes.chunks(4).map(|chunk| Value::F32(f32::from_le_bytes(word(chunk)))).collect(),
+        Uint32 | Uint32x2 | Uint32x3 | Uint32x4 =>
+            bytes.chunks(4).map(|chunk| Value::U32(u32::from_le_bytes(word(chunk)))).collect(),
+        Sint32 | Sint32x2 | Sint32x3 | Sint32x4 =>
+            bytes.chunks(4).map(|chunk| Value::I32(i32::from_le_bytes(word(chunk)))).collect(),
+        Unorm8x2 | Unorm8x4 => bytes.iter().map(|byte| Value::F32(*byte as f32 / 255.0)).collect(),
+        Snorm8x2 | Snorm8x4 => bytes.iter().map(|byte| Value::F32((*byte as i8 as f32 / 127.0).max(-1.0))).collect(),
+        Uint8x2 | Uint8x4 => bytes.iter().map(|byte| Value::U32(*byte as u32)).collect(),
+        format => return Err(format!("unsupported vertex format {:?}", format)),
+    })
+}
+
+
+fn blend_factor(factor: BlendFactor, channel: usize, src: &[f32; 4], dst: &[f32; 4], constant: &[f32; 4]) -> f32
+{
+    match factor
+    {
+        BlendFactor::Zero => 0.0,
+        BlendFactor::One => 1.0,
+        BlendFactor::Src => src[channel],
+        BlendFactor::OneMinusSrc => 1.0 - src[channel],
+        BlendFactor::SrcAlpha => src[3],
+        BlendFactor::OneMinusSrcAlpha => 1.0 - src[3],
+        BlendFactor::Dst => dst[channel],
+        BlendFactor::OneMinusDst => 1.0 - dst[channel],
+        BlendFactor::DstAlpha => dst[3],
+        BlendFactor::OneMinusDstAlpha => 1.0 - dst[3],
+        BlendFactor::SrcAlphaSaturated if channel == 3 => 1.0,
+        BlendFactor::SrcAlphaSaturated => src[3].min(1.0 - dst[3]),
+        BlendFactor::Constant => constant[channel],
+        BlendFactor::OneMinusConstant => 1.0 - constant[channel],
+    }
+}
+
+
+fn blend(component: &BlendComponent, channel: usize, src: &[f32; 4], dst: &[f32; 4], constant: &[f32; 4]) -> f32
+{
+    let src_factor = blend_factor(component.src_factor, channel, src, dst, constant);
+    let dst_factor = blend_factor(component.dst_factor, channel, src, dst, constant);
+    match component.operation
+    {
+        BlendOperation::Add => src[channel] * src_factor + dst[channel] * dst_factor,
+        BlendOperation::Subtract => src[channel] * src_factor - dst[channel] * dst_factor,
+        BlendOperation::ReverseSubtract => dst[channel] * dst_factor - src[channel] * src_factor,
+        BlendOperation::Min => src[channel].min(dst[channel]),
+        BlendOperation::Max => src[channel].max(dst[channel]),
+    }
+}
+
+
+fn passes(compare: CompareFunction, value: f32, reference: f32) -> bool
+{
+    match compare
+    {
+        CompareFunction::Never => false,
+        CompareFunction::Less => value < reference,
+        CompareFunction::Equal => value == reference,
+        CompareFunction::LessEqual => value <= reference,
+        CompareFunction::Greater => value > reference,
+        CompareFunction::NotEqual => value != reference,
+        CompareFunction::GreaterEqual => value >= reference,
+        CompareFunction::Always => true,
+    }
+}
+
+
+// the cpu counterpart of a render pipeline with a single color target, vertex and
+// fragment entry points may live in the same or in different WGSL sources
+pub struct RenderPipeline
+{
+    vertex_module: Interpreter,
+    vertex_entry_point: String,
+    fragment_module: Interpreter,
+    fragment_entry_point: String,
+    buffers: Vec<VertexBufferLayout>,
+    topology: PrimitiveTopology,
+    front_face: FrontFace,
+    cull_mode: CullMode,
+    blend: Option<BlendState>,
+    depth_stencil: Option<DepthStencilState>,
+}
+
+
+impl RenderPipeline
+{
+    pub fn new(vertex_source: &str, vertex_entry_point: &str, fragment_source: &str, fragment_entry_point: &str)
+        -> Result<Self, String>
+    {
+        let vertex_module = Interpreter::new(vertex_source)?;
+        let fragment_module = Interpreter::new(fragment_source)?;
+        if !vertex_module.has_entry_point(vertex_entry_point, ShaderStage::Vertex)
+        {
+            return Err(format!("no vertex entry point named {}", vertex_entry_point));
+        }
+        if !fragment_module.has_entry_point(fragment_entry_point, ShaderStage::Fragment)
+        {
+            return Err(format!("no fragment entry point named {}", fragment_entry_point));
+        }
+
+        Ok(RenderPipeline
+        {
+            vertex_module,
+            vertex_entry_point: vertex_entry_point.to_owned(),
+            fragment_module,
+            fragment_entry_point: fragment_entry_point.to_owned(),
+            buffers: Vec::new(),
+            topology: PrimitiveTopology::TriangleList,
+            front_face: FrontFace::Ccw,
+            cull_mode: CullMode::None,
+            blend: None,
+            depth_stencil: None,
+        })
+    }
+
+
+    pub fn buffers(&mut self, buffers: &[VertexBufferLayout]) -> &mut Self
+    {
+        self.buffers = buffers.to_vec();
+        self
+    }
+
+
+    pub fn topology(&mut self, topology: PrimitiveTopology) -> &mut Self
+    {
+        self.topology = topology;
+        self
+    }
+
+
+    pub fn front_face(&mut self, front_face: FrontFace) -> &mut Self
+    {
+        self.front_face = front_face;
+        self
+    }
+
+
+    pub fn cull_mode(&mut self, cull_mode: CullMode) -> &mut Self
+    {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+
+    pub fn blend(&mut self, blend: Option<BlendState>) -> &mut Self
+    {
+        self.blend = blend;
+        self
+    }
+
+
+    // the depth test needs a depth buffer on the pass, see RenderPass::set_depth_buffer
+    pub fn depth_stencil(&mut self, depth_stencil: Option<DepthStencilState>) -> &mut Self
+    {
+        self.depth_stencil = depth_stencil;
+        self
+    }
+}
+
+
+#[derive(Clone, Debug)]
+struct Varying
+{
+    location: u32,
+    interpolation: Interpolation,
+    value: Value,
+}
+
+
+#[derive(Clone, Debug)]
+struct ClipVertex
+{
+    position: [f32; 4],
+    varyings: Vec<Varying>,
+}
+
+
+// weighted sum of the float components, everything else is taken from the first value
+fn mix_values(values: &[&Value], weights: &[f32]) -> Value
+{
+    match values[0]
+    {
+        Value::F32(_) => Value::F32(values.iter().zip(weights)
+            .map(|(value, weight)| match value
+            {
+                Value::F32(value) => value * weight,
+                _ => 0.0,
+            })
+            .sum()),
+        Value::Composite(components) => Value::Composite((0..components.len())
+            .map(|i|
+            {
+                let components = values.iter()
+                    .map(|value| match value
+                    {
+                        Value::Composite(components) => &components[i],
+                        value => value,
+                    })
+                    .collect::<Vec<&Value>>();
+                mix_values(&components, weights)
+            })
+            .collect()),
+        value => value.clone(),
+    }
+}
+
+
+fn mix_vertices(a: &ClipVertex, b: &ClipVertex, t: f32) -> ClipVertex
+{
+    ClipVertex
+    {
+        position: std::array::from_fn(|i| a.position[i] * (1.0 - t) + b.position[i] * t),
+        varyings: a.varyings.iter().zip(&b.varyings)
+            .map(|(varying_a, varying_b)| Varying
+            {
+                value: mix_values(&[&varying_a.value, &varying_b.value], &[1.0 - t, t]),
+                ..varying_a.clone()
+            })
+            .collect(),
+    }
+}
+
+
+// clips a triangle against the near and far planes, 0 <= z <= w, the result is a convex polygon,
+// x and y need no clipping since only pixels inside of the target are visited
+fn clip_triangle(triangle: [ClipVertex; 3]) -> Vec<ClipVertex>
+{
+    let planes: [fn(&[f32; 4]) -> f32; 2] = [|p| p[2], |p| p[3] - p[2]];
+    let mut polygon = triangle.to_vec();
+
+    for distance in planes
+    {
+        let mut clipped = Vec::new();
+        for (i, current) in polygon.iter().enumerate()
+        {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let (d_current, d_next) = (distance(&current.position), distance(&next.position));
+            if d_current >= 0.0
+            {
+                clipped.push(current.clone());
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0)
+            {
+                clipped.push(mix_vertices(current, next, d_current / (d_current - d_next)));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+
+fn as_color(value: &Value) -> Result<[f32; 4], String>
+{
+    match value
+    {
+        Value::Composite(components) if components.len() == 4 =>
+        {
+            let mut color = [0.0; 4];
+            for (channel, component) in color.iter_mut().zip(components)
+            {
+                *channel = match component
+                {
+                    Value::F32(value) => *value,
+                    component => return Err(format!("color targets hold floats, got {:?}", component)),
+                };
+            }
+            Ok(color)
+        },
+        value => Err(format!("expected a vec4f color, got {:?}", value)),
+    }
+}
+
+
+// records draws into an image, like a render pass with a single color attachment and an optional
+// depth attachment
+pub struct RenderPass<'a>
+{
+    target: &'a mut Image,
+    depth_buffer: Option<&'a mut [f32]>,
+    vertex_buffers: Vec<&'a [u8]>,
+    index_buffer: &'a [u32],
+    blend_constant: [f32; 4],
+}
+
+
+impl<'a> RenderPass<'a>
+{
+    pub fn new(target: &'a mut Image, clear_value: Option<[f32; 4]>) -> Self
+    {
+        if let Some(color) = clear_value
+        {
+            target.pixels.fill(color.map(to_unorm8));
+        }
+        RenderPass
+        {
+            target, depth_buffer: None, vertex_buffers: Vec::new(), index_buffer: &[], blend_constant: [0.0; 4],
+        }
+    }
+
+
+    // one value per pixel of the target, row by row
+    pub fn set_depth_buffer(&mut self, depth: &'a mut [f32]) -> Result<(), String>
+    {
+        if depth.len() != self.target.pixels.len()
+        {
+            return Err(format!("a depth buffer of {} values for {} pixels", depth.len(), self.target.pixels.len()));
+        }
+        self.depth_buffer = Some(depth);
+        Ok(())
+    }
+
+
+    pub fn set_vertex_buffer(&mut self, slot: usize, bytes: &'a [u8])
+    {
+        if self.vertex_buffers.len() <= slot
+        {
+            self.vertex_buffers.resize(slot + 1, &[]);
+        }
+        self.vertex_buffers[slot] = bytes;
+    }
+
+
+    pub fn set_index_buffer(&mut self, indices: &'a [u32])
+    {
+        self.index_buffer = indices;
+    }
+
+
+    pub fn set_blend_constant(&mut self, color: [f32; 4])
+    {
+        self.blend_constant = color;
+    }
+
+
+    pub fn draw(&mut self, pipeline: &RenderPipeline, bindings: &Bindings, vertex_count: u32, instance_count: u32)
+        -> Result<(), String>
+    {
+        let indices = (0..vertex_count).collect::<Vec<u32>>();
+        self.draw_vertices(pipeline, bindings, &indices, instance_count)
+    }
+
+
+    pub fn draw_indexed(&mut self, pipeline: &RenderPipeline, bindings: &Bindings, index_count: u32, instance_count: u32)
+        -> Result<(), String>
+    {
+        let indices = self.index_buffer.get(..index_count as usize)
+            .ok_or_else(|| format!("{} indices drawn from an index buffer of {}", index_count, self.index_buffer.len()))?
+            .to_vec();
+        self.draw_vertices(pipeline, bindings, &indices, instance_count)
+    }
+
+
+    // reads the arguments of drawIndexedIndirect from their bytes, only draws from the start of both
+    // the index buffer and the instances are supported
+    pub fn draw_indexed_indirect(&mut self, pipeline: &RenderPipeline, bindings: &Bindings, args: &[u8])
+        -> Result<(), String>
+    {
+        let args = args.get(..20)
+            .ok_or_else(|| format!("drawIndexedIndirect needs 20 bytes of arguments, got {}", args.len()))?
+            .chunks(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        match args[..]
+        {
+            [index_count, instance_count, 0, 0, 0] => self.draw_indexed(pipeline, bindings, index_count, instance_count),
+            _ => Err(format!("unsupported first index, base vertex or first instance in {:?}", args)),
+        }
+    }
+
+
+    fn draw_vertices(&mut self, pipeline: &RenderPipeline, bindings: &Bindings, indices: &[u32], instance_count: u32)
+        -> Result<(), String>
+    {
+        for instance_index in 0..instance_count
+        {
+            let vertices = indices.iter()
+                .map(|vertex_index| self.run_vertex(pipeline, bindings, *vertex_index, instance_index))
+                .collect::<Result<Vec<ClipVertex>, String>>()?;
+
+            let triangles = match pipeline.topology
+            {
+                PrimitiveTopology::TriangleList => (0..vertices.len() / 3).map(|i| [3 * i, 3 * i + 1, 3 * i + 2]).collect(),
+                // every other triangle of a strip is flipped to keep the winding of the first one
+                PrimitiveTopology::TriangleStrip => (0..vertices.len().saturating_sub(2))
+                    .map(|i| if i % 2 == 0 { [i, i + 1, i + 2] } else { [i + 1, i, i + 2] })
+                    .collect::<Vec<[usize; 3]>>(),
+                topology => return Err(format!("unsupported primitive topology {:?}", topology)),
+            };
+
+            for [a, b, c] in triangles
+            {
+                self.draw_triangle(pipeline, bindings, [vertices[a].clone(), vertices[b].clone(), vertices[c].clone()])?;
+            }
+        }
+        Ok(())
+    }
+
+
+    fn run_vertex(&self, pipeline: &RenderPipeline, bindings: &Bindings, vertex_index: u32, instance_index: u32)
+        -> Result<ClipVertex, String>
+    {
+        let inputs = |binding: &Binding, inner: &TypeInner| match binding
+        {
+            Binding::BuiltIn(BuiltIn::VertexIndex) => Ok(Value::U32(vertex_index)),
+            Binding::BuiltIn(BuiltIn::InstanceIndex) => Ok(Value::U32(instance_index)),
+            Binding::Location { location, .. } => self.fetch(pipeline, *location, inner, vertex_index, instance_index),
+            binding => Err(format!("unsupported vertex input {:?}", binding)),
+        };
+        let outputs = pipeline.vertex_module
+            .run_stage(ShaderStage::Vertex, &pipeline.vertex_entry_point, bindings, &inputs)?
+            .ok_or("vertex shaders can't discard")?;
+
+        let mut vertex = ClipVertex { position: [0.0; 4], varyings: Vec::new() };
+        for (binding, value) in outputs
+        {
+            match binding
+            {
+                Binding::BuiltIn(BuiltIn::Position { .. }) => vertex.position = as_color(&value)?,
+                Binding::Location { location, interpolation, .. } =>
+                {
+                    let interpolation = interpolation.unwrap_or(Interpolation::Perspective);
+                    vertex.varyings.push(Varying { location, interpolation, value });
+                },
+                binding => return Err(format!("unsupported vertex output {:?}", binding)),
+            }
+        }
+        Ok(vertex)
+    }
+
+
+    // reads a vertex attribute, missing components are filled in from (0, 0, 0, 1)
+    fn fetch(&self, pipeline: &RenderPipeline, location: u32, inner: &TypeInner, vertex_index: u32, instance_index: u32)
+        -> Result<Value, String>
+    {
+        let (slot, layout, attribute) = pipeline.buffers.iter().enumerate()
+            .find_map(|(slot, layout)| layout.attributes.iter()
+                .find(|attribute| attribute.shader_location == location)
+                .map(|attribute| (slot, layout, attribute))
+            )
+            .ok_or_else(|| format!("no vertex attribute for @location({})", location))?;
+
+        let index = match layout.step_mode
+        {
+            VertexStepMode::Vertex => vertex_index,
+            VertexStepMode::Instance => instance_index,
+        };
+        let start = index as usize * layout.array_stride as usize + attribute.offset as usize;
+        let buffer = self.vertex_buffers.get(slot).copied().unwrap_or(&[]);
+        let size = attribute.format.size() as usize;
+        let mut components = match buffer.get(start..start + size)
+        {
+            Some(bytes) => decode(attribute.format, bytes)?,
+            // like on the gpu, reads past the end of a vertex buffer don't fail
+            None => decode(attribute.format, &vec![0; size])?,
+        };
+
+        let (size, kind) = match *inner
+        {
+            TypeInner::Scalar(scalar) => (1, scalar.kind),
+            TypeInner::Vector { size, scalar } => (size as usize, scalar.kind),
+            ref inner => return Err(format!("unsupported vertex input type {:?}", inner)),
+        };
+        let default = |i: usize| match (kind, i)
+        {
+            (ScalarKind::Float, 3) => Value::F32(1.0),
+            (ScalarKind::Float, _) => Value::F32(0.0),
+            (ScalarKind::Sint, 3) => Value::I32(1),
+            (ScalarKind::Sint, _) => Value::I32(0),
+            (_, 3) => Value::U32(1),
+            _ => Value::U32(0),
+        };
+        components.truncate(size);
+        while components.len() < size
+        {
+            components.push(default(components.len()));
+        }
+
+        Ok(if size == 1 { components.remove(0) } else { Value::Composite(components) })
+    }
+
+
+    fn draw_triangle(&mut self, pipeline: &RenderPipeline, bindings: &Bindings, triangle: [ClipVertex; 3])
+        -> Result<(), String>
+    {
+        // flat varyings come from the first vertex of the triangle
+        let provoking = triangle[0].varyings.clone();
+        let polygon = clip_triangle(triangle);
+        if polygon.len() < 3
+        {
+            return Ok(());
+        }
+
+        let (width, height) = (self.target.width as f32, self.target.height as f32);
+        let window = polygon.iter()
+            .map(|vertex|
+            {
+                let [x, y, z, w] = vertex.position;
+                [(x / w * 0.5 + 0.5) * width, (0.5 - y / w * 0.5) * height, z / w, 1.0 / w]
+            })
+            .collect::<Vec<[f32; 4]>>();
+
+        for i in 1..polygon.len() - 1
+        {
+            let corners = [0, i, i + 1];
+            self.rasterize(pipeline, bindings, corners.map(|c| &window[c]), corners.map(|c| &polygon[c]), &provoking)?;
+        }
+        Ok(())
+    }
+
+
+    fn rasterize(
+        &mut self,
+        pipeline: &RenderPipeline,
+        bindings: &Bindings,
+        window: [&[f32; 4]; 3],
+        vertices: [&ClipVertex; 3],
+        provoking: &[Varying],
+    )
+        -> Result<(), String>
+    {
+        let edge = |a: &[f32; 4], b: &[f32; 4], x: f32, y: f32| (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0]);
+        let area = edge(window[0], window[1], window[2][0], window[2][1]);
+        if area == 0.0
+        {
+            return Ok(());
+        }
+
+        // the window y axis points down, which turns counter clockwise triangles clockwise
+        let front_facing = (area < 0.0) == (pipeline.front_face == FrontFace::Ccw);
+        match (pipeline.cull_mode, front_facing)
+        {
+            (CullMode::Front, true) | (CullMode::Back, false) => return Ok(()),
+            _ => {},
+        }
+
+        // with a positive area a pixel is inside when all edge functions are positive
+        let (window, vertices) = if area > 0.0
+        {
+            (window, vertices)
+        }
+        else
+        {
+            ([window[0], window[2], window[1]], [vertices[0], vertices[2], vertices[1]])
+        };
+        let area = area.abs();
+
+        // pixels exactly on an edge belong to the triangle if the edge is a top or a left edge
+        let edges = [(1, 2), (2, 0), (0, 1)];
+        let top_left = edges.map(|(a, b)|
+        {
+            let (dx, dy) = (window[b][0] - window[a][0], window[b][1] - window[a][1]);
+            dy < 0.0 || (dy == 0.0 && dx > 0.0)
+        });
+
+        let min = |i: usize| window.iter().map(|v| v[i]).fold(f32::INFINITY, f32::min);
+        let max = |i: usize| window.iter().map(|v| v[i]).fold(f32::NEG_INFINITY, f32::max);
+        let x_range = (min(0).floor().max(0.0) as u32)..(max(0).ceil().min(self.target.width as f32) as u32);
+        let y_range = (min(1).floor().max(0.0) as u32)..(max(1).ceil().min(self.target.height as f32) as u32);
+
+        for y in y_range
+        {
+            for x in x_range.clone()
+            {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = edges.map(|(a, b)| edge(window[a], window[b], px, py));
+                let inside = weights.iter().zip(&top_left).all(|(weight, top_left)| *weight > 0.0 || (*weight == 0.0 && *top_left));
+                if !inside
+                {
+                    continue;
+                }
+
+                let linear = weights.map(|weight| weight / area);
+                let inverse_w = (0..3).map(|i| linear[i] * window[i][3]).sum::<f32>();
+                let perspective = std::array::from_fn::<f32, 3, _>(|i| linear[i] * window[i][3] / inverse_w);
+                let position = [px, py, (0..3).map(|i| linear[i] * window[i][2]).sum(), inverse_w];
+
+                let depth_index = (y * self.target.width + x) as usize;
+                if let (Some(state), Some(depth)) = (pipeline.depth_stencil, self.depth_buffer.as_deref())
+                {
+                    if !passes(state.depth_compare, position[2], depth[depth_index])
+                    {
+                        continue;
+                    }
+                }
+
+                let varyings = (0..vertices[0].varyings.len())
+                    .map(|v|
+                    {
+                        let varying = &vertices[0].varyings[v];
+                        let values = vertices.map(|vertex| &vertex.varyings[v].value);
+                        let value = match varying.interpolation
+                        {
+                            Interpolation::Perspective => mix_values(&values, &perspective),
+                            Interpolation::Linear => mix_values(&values, &linear),
+                            Interpolation::Flat => provoking[v].value.clone(),
+                        };
+                        (varying.location, value)
+                    })
+                    .collect::<Vec<(u32, Value)>>();
+
+                if let Some(color) = self.run_fragment(pipeline, bindings, position, front_facing, &varyings)?
+                {
+                    self.write(pipeline, x, y, color);
+                    if let (Some(state), Some(depth)) = (pipeline.depth_stencil, self.depth_buffer.as_deref_mut())
+                    {
+                        if state.depth_write_enabled
+                        {
+                            depth[depth_index] = position[2];
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+
+    fn run_fragment(
+        &self,
+        pipeline: &RenderPipeline,
+        bindings: &Bindings,
+        position: [f32; 4],
+        front_facing: bool,
+        varyings: &[(u32, Value)],
+    )
+        -> Result<Option<[f32; 4]>, String>
+    {
+        let inputs = |binding: &Binding, _: &TypeInner| match binding
+        {
+            Binding::BuiltIn(BuiltIn::Position { .. }) =>
+                Ok(Value::Composite(position.iter().map(|c| Value::F32(*c)).collect())),
+            Binding::BuiltIn(BuiltIn::FrontFacing) => Ok(Value::Bool(front_facing)),
+            Binding::Location { location, .. } => varyings.iter()
+                .find(|(varying_location, _)| varying_location == location)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| format!("the vertex shader has no output at @location({})", location)),
+            binding => Err(format!("unsupported fragment input {:?}", binding)),
+        };
+
+        let outputs = match pipeline.fragment_module
+            .run_stage(ShaderStage::Fragment, &pipeline.fragment_entry_point, bindings, &inputs)?
+        {
+            Some(outputs) => outputs,
+            None => return Ok(None),
+        };
+        outputs.iter()
+            .find(|(binding, _)| matches!(binding, Binding::Location { location: 0, .. }))
+            .map(|(_, value)| as_color(value))
+            .transpose()
+    }
+
+
+    // blending happens in floats, the result is stored with 8 bits per channel like in an rgba8unorm target
+    fn write(&mut self, pipeline: &RenderPipeline, x: u32, y: u32, color: [f32; 4])
+    {
+        let pixel = &mut self.target.pixels[(y * self.target.width + x) as usize];
+        let src = color.map(|channel| channel.clamp(0.0, 1.0));
+        let dst = pixel.map(|channel| channel as f32 / 255.0);
+
+        let result = match pipeline.blend
+        {
+            Some(state) => std::array::from_fn(|channel|
+            {
+                let component = if channel == 3 { &state.alpha } else { &state.color };
+                blend(component, channel, &src, &dst, &self.blend_constant)
+            }),
+            None => src,
+        };
+        *pixel = result.map(to_unorm8);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::interpreter::Bindings;
+
+
+    #[test]
+    fn shared_edges_are_covered_once()
+    {
+        let source = r#"
+            @vertex
+            fn vertex_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4f
+            {
+                var pos = array(vec2f(-0.7, -0.9), vec2f(0.8, -0.3), vec2f(-0.2, 0.9), vec2f(0.6, 0.7));
+                return vec4f(pos[vertex_index], 0.0, 1.0);
+            }
+
+            @fragment
+            fn fragment_main() -> @location(0) vec4f
+            {
+                return vec4f(0.25, 0.25, 0.25, 1.0);
+            }
+        "#;
+        let mut pipeline = RenderPipeline::new(source, "vertex_main", source, "fragment_main").unwrap();
+        pipeline.topology(PrimitiveTopology::TriangleStrip).blend(Some(BlendState::new(
+            BlendComponent::new(BlendFactor::One, BlendFactor::One, BlendOperation::Add),
+            BlendComponent::new(BlendFactor::One, BlendFactor::Zero, BlendOperation::Add),
+        )));
+
+        let mut image = Image::new(32, 32, [0.0; 4]);
+        RenderPass::new(&mut image, Some([0.0, 0.0, 0.0, 1.0])).draw(&pipeline, &Bindings::new(), 4, 1).unwrap();
+        let covered = image.pixels.iter().filter(|pixel| pixel[0] > 0).count();
+        assert!(covered > 300);
+        assert!(image.pixels.iter().all(|pixel| pixel[0] == 0 || pixel[0] == 64));
+    }
+
+
+    #[test]
+    fn culling_follows_the_front_face()
+    {
+        let source = r#"
+            @vertex
+            fn vertex_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4f
+            {
+                // counter clockwise
+                var pos = array(vec2f(-1.0, -1.0), vec2f(1.0, -1.0), vec2f(-1.0, 1.0));
+                return vec4f(pos[vertex_index], 0.5, 1.0);
+            }
+
+            @fragment
+            fn fragment_main(@builtin(front_facing) front_facing: bool) -> @location(0) vec4f
+            {
+                if (!front_facing) { discard; }
+                return vec4f(1.0);
+            }
+        "#;
+        let mut pipeline = RenderPipeline::new(source, "vertex_main", source, "fragment_main").unwrap();
+        let draw = |pipeline: &RenderPipeline|
+        {
+            let mut image = Image::new(8, 8, [0.0; 4]);
+            RenderPass::new(&mut image, None).draw(pipeline, &Bindings::new(), 3, 1).unwrap();
+            image.pixel(0, 7)
+        };
+
+        assert_eq!(draw(&pipeline), [255; 4]);
+        pipeline.cull_mode(CullMode::Back);
+        assert_eq!(draw(&pipeline), [255; 4]);
+        pipeline.cull_mode(CullMode::Front);
+        assert_eq!(draw(&pipeline), [0; 4]);
+        // a clockwise front face turns the triangle around, and the shader discards back faces
+        pipeline.cull_mode(CullMode::None).front_face(FrontFace::Cw);
+        assert_eq!(draw(&pipeline), [0; 4]);
+    }
+
+
+    #[test]
+    fn the_depth_test_keeps_the_nearest_triangle()
+    {
+        let source = r#"
+            @vertex
+            fn vertex_main(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance: u32)
+                -> @builtin(position) vec4f
+            {
+                // the second instance is drawn behind the first one
+                var pos = array(vec2f(-1.0, -1.0), vec2f(3.0, -1.0), vec2f(-1.0, 3.0));
+                return vec4f(pos[vertex_index], 0.25 + 0.5 * f32(instance), 1.0);
+            }
+
+            @fragment
+            fn fragment_main(@builtin(position) position: vec4f) -> @location(0) vec4f
+            {
+                return vec4f(position.z, 0.0, 0.0, 1.0);
+            }
+        "#;
+        let mut pipeline = RenderPipeline::new(source, "vertex_main", source, "fragment_main").unwrap();
+        pipeline.depth_stencil(Some(DepthStencilState::new(
+            gpu_backend::TextureFormat::Depth24Plus, true, CompareFunction::Less,
+        )));
+
+        let mut image = Image::new(4, 4, [0.0; 4]);
+        let mut depth = vec![1.0; 16];
+        let mut render_pass = RenderPass::new(&mut image, None);
+        render_pass.set_depth_buffer(&mut depth).unwrap();
+        render_pass.draw(&pipeline, &Bindings::new(), 3, 2).unwrap();
+
+        assert_eq!(image.pixel(1, 1), [64, 0, 0, 255]);
+        assert!(depth.iter().all(|depth| *depth == 0.25));
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use animation::{Easing, Interpolation, Repeat, Timeline, Track};
+use math::{Vec2, Vec4};
+
+use crate::instances::{Instance, Instances};
+
+
+// what a track of an instance animates, color takes 4 floats, offset 2 and scale 1
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InstanceProperty
+{
+    Color,
+    Offset,
+    Scale,
+}
+
+
+// the properties without a track keep the value the instance was given
+#[derive(Clone, Debug, Default)]
+struct InstanceTracks
+{
+    color: Option<Track<Vec4>>,
+    offset: Option<Track<Vec2>>,
+    scale: Option<Track<f32>>,
+}
+
+
+impl InstanceTracks
+{
+    fn end_time(&self) -> f32
+    {
+        [
+            self.color.as_ref().map(Track::end_time),
+            self.offset.as_ref().map(Track::end_time),
+            self.scale.as_ref().map(Track::end_time),
+        ]
+            .into_iter()
+            .flatten()
+            .fold(0.0, f32::max)
+    }
+
+
+    fn sample(&self, time: f32, instance: &Instance) -> Instance
+    {
+        Instance
+        {
+            color: self.color.as_ref().map_or(instance.color, |track| track.sample(time).to_array()),
+            offset: self.offset.as_ref().map_or(instance.offset, |track| track.sample(time).to_array()),
+            scale: self.scale.as_ref().map_or(instance.scale, |track| track.sample(time)),
+        }
+    }
+}
+
+
+// the keyframe animations of the instances, all on one timeline as long as the longest track
+pub struct InstanceAnimations
+{
+    tracks: HashMap<u32, InstanceTracks>,
+    timeline: Timeline,
+}
+
+
+impl InstanceAnimations
+{
+    pub fn new() -> Self
+    {
+        InstanceAnimations { tracks: HashMap::new(), timeline: Timeline::new(0.0, Repeat::Loop) }
+    }
+
+
+    pub fn timeline(&self) -> &Timeline
+    {
+        &self.timeline
+    }
+
+
+    pub fn timeline_mut(&mut self) -> &mut Timeline
+    {
+        &mut self.timeline
+    }
+
+
+    // replaces the track of a property of the instance id, see Track::from_components for the components
+    pub fn set_track(
+        &mut self,
+        id: u32,
+        property: InstanceProperty,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        components: &[f32],
+    )
+        -> Result<(), String>
+    {
+        let tracks = self.tracks.entry(id).or_default();
+        match property
+        {
+            InstanceProperty::Color =>
+            {
+                let mut track = Track::from_components(interpolation, times, components)?;
+                tracks.color = Some(track.easing(easing).clone());
+            },
+            InstanceProperty::Offset =>
+            {
+                let mut track = Track::from_components(interpolation, times, components)?;
+                tracks.offset = Some(track.easing(easing).clone());
+            },
+            InstanceProperty::Scale =>
+            {
+                let mut track = Track::from_components(interpolation, times, components)?;
+                tracks.scale = Some(track.easing(easing).clone());
+            },
+        }
+        self.update_duration();
+        Ok(())
+    }
+
+
+    // the instance keeps the values it was last animated to
+    pub fn remove(&mut self, id: u32)
+    {
+        self.tracks.remove(&id);
+        self.update_duration();
+    }
+
+
+    pub fn clear(&mut self)
+    {
+        self.tracks.clear();
+        self.update_duration();
+    }
+
+
+    fn update_duration(&mut self)
+    {
+        let duration = self.tracks.values().map(InstanceTracks::end_time).fold(0.0, f32::max);
+        self.timeline.set_duration(duration);
+    }
+
+
+    // sets the animated properties of the instances to their values at the time of the timeline
+    pub fn apply(&self, instances: &mut Instances)
+    {
+        let time = self.timeline.local_time();
+        for (id, tracks) in &self.tracks
+        {
+            if let Some(instance) = instances.get(*id)
+            {
+                instances.update(*id, tracks.sample(time, instance)).unwrap();
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn animated_properties_follow_the_timeline()
+    {
+        let mut instances = Instances::new();
+        let instance = Instance { color: [1.0, 0.0, 0.0, 1.0], offset: [0.5, 0.5], scale: 0.2 };
+        let id = instances.add(instance);
+
+        let mut animations = InstanceAnimations::new();
+        animations.set_track(
+            id, InstanceProperty::Offset, Interpolation::Linear, Easing::Linear, vec![0.0, 2.0], &[0.0, 0.0, 1.0, -1.0],
+        ).unwrap();
+        animations.set_track(
+            id, InstanceProperty::Scale, Interpolation::Step, Easing::Linear, vec![0.0, 1.0, 4.0], &[0.1, 0.3, 0.1],
+        ).unwrap();
+        assert_eq!(animations.timeline().duration(), 4.0);
+
+        animations.timeline_mut().set_time(1.0);
+        animations.apply(&mut instances);
+        assert_eq!(*instances.get(id).unwrap(), Instance { offset: [0.5, -0.5], scale: 0.3, ..instance });
+
+        // looping around to the start
+        animations.timeline_mut().advance(3.0);
+        animations.apply(&mut instances);
+        assert_eq!(*instances.get(id).unwrap(), Instance { offset: [0.0, 0.0], scale: 0.1, ..instance });
+
+        // malformed tracks leave the animation as it was
+        let color = animations.set_track(
+            id, InstanceProperty::Color, Interpolation::Linear, Easing::Linear, vec![0.0], &[1.0, 1.0, 1.0],
+        );
+        assert!(color.is_err());
+
+        // removed instances are skipped, removing their animation shortens the timeline
+        let other = instances.add(instance);
+        animations.set_track(
+            other, InstanceProperty::Scale, Interpolation::Linear, Easing::Linear, vec![0.0, 8.0], &[0.1, 0.9],
+        ).unwrap();
+        assert_eq!(animations.timeline().duration(), 8.0);
+        instances.remove(other).unwrap();
+        animations.apply(&mut instances);
+        animations.remove(other);
+        assert_eq!(animations.timeline().duration(), 4.0);
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instance
+{
+    pub color: [f32; 4],
+    pub offset: [f32; 2],
+    pub scale: f32,
+}
+
+
+// the instances are kept packed in the order they were drawn, removing one moves the last instance into
+// its place. ids stay valid until their instance is removed and are never reused
+#[derive(Debug, Default)]
+pub struct Instances
+{
+    instances: Vec<Instance>,
+    ids: Vec<u32>,
+    slots: HashMap<u32, usize>,
+    next_id: u32,
+    // colors or offsets changed since the last take_changed
+    changed: bool,
+}
+
+
+impl Instances
+{
+    pub fn new() -> Self
+    {
+        Instances::default()
+    }
+
+
+    pub fn len(&self) -> usize
+    {
+        self.instances.len()
+    }
+
+
+    pub fn as_slice(&self) -> &[Instance]
+    {
+        &self.instances
+    }
+
+
+    pub fn get(&self, id: u32) -> Option<&Instance>
+    {
+        self.slots.get(&id).map(|slot| &self.instances[*slot])
+    }
+
+
+    pub fn add(&mut self, instance: Instance) -> u32
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots.insert(id, self.instances.len());
+        self.instances.push(instance);
+        self.ids.push(id);
+        self.changed = true;
+        id
+    }
+
+
+    pub fn update(&mut self, id: u32, instance: Instance) -> Result<(), String>
+    {
+        let slot = *self.slots.get(&id).ok_or_else(|| format!("there is no instance {}", id))?;
+        let current = &mut self.instances[slot];
+        self.changed |= current.color != instance.color || current.offset != instance.offset;
+        *current = instance;
+        Ok(())
+    }
+
+
+    pub fn remove(&mut self, id: u32) -> Result<(), String>
+    {
+        let slot = self.slots.remove(&id).ok_or_else(|| format!("there is no instance {}", id))?;
+        self.instances.swap_remove(slot);
+        self.ids.swap_remove(slot);
+        if let Some(moved) = self.ids.get(slot)
+        {
+            self.slots.insert(*moved, slot);
+        }
+        self.changed = true;
+        Ok(())
+    }
+
+
+    pub fn clear(&mut self)
+    {
+        self.instances.clear();
+        self.ids.clear();
+        self.slots.clear();
+        self.changed = true;
+    }
+
+
+    // whether the colors or offsets have to be uploaded again, scales are uploaded every frame anyway
+    pub fn take_changed(&mut self) -> bool
+    {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    fn instance(scale: f32) -> Instance
+    {
+        Instance { color: [1.0; 4], offset: [0.0; 2], scale }
+    }
+
+
+    #[test]
+    fn removing_moves_the_last_instance_into_the_gap()
+    {
+        let mut instances = Instances::new();
+        let ids = (0..4).map(|i| instances.add(instance(i as f32))).collect::<Vec<_>>();
+        assert!(instances.take_changed());
+
+        instances.remove(ids[1]).unwrap();
+        assert_eq!(instances.as_slice().iter().map(|instance| instance.scale).collect::<Vec<_>>(), [0.0, 3.0, 2.0]);
+        assert_eq!(instances.get(ids[3]), Some(&instance(3.0)));
+        assert_eq!(instances.get(ids[1]), None);
+        assert!(instances.remove(ids[1]).is_err());
+
+        // the last one goes without moving anything
+        instances.remove(ids[2]).unwrap();
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances.get(ids[3]), Some(&instance(3.0)));
+        assert_ne!(instances.add(instance(4.0)), ids[3]);
+    }
+
+
+    #[test]
+    fn only_colors_and_offsets_count_as_changes()
+    {
+        let mut instances = Instances::new();
+        let id = instances.add(instance(1.0));
+        assert!(instances.take_changed());
+        assert!(!instances.take_changed());
+
+        instances.update(id, instance(2.0)).unwrap();
+        assert!(!instances.take_changed());
+        instances.update(id, Instance { offset: [0.5, 0.0], ..instance(2.0) }).unwrap();
+        assert!(instances.take_changed());
+        assert!(instances.update(id + 1, instance(1.0)).is_err());
+
+        instances.clear();
+        assert!(instances.take_changed());
+        assert_eq!(instances.len(), 0);
+    }
+}
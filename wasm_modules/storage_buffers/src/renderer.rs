@@ -0,0 +1,566 @@
+use gpu_backend::
+{
+    Backend, AsBytes, BufferDescriptor, GrowableBuffer, ShaderModuleDescriptor, VertexState, ColorTargetState,
+    FragmentState, RenderPipelineDescriptor, BindGroupDescriptor, BindGroupEntry, RenderPassDescriptor,
+    CommandEncoder, TextureFormat, Viewport, MultisampleTarget,
+};
+use gpu_backend::buffer_usage::{COPY_DST, STORAGE};
+
+use animation::{Easing, Interpolation, Timeline};
+
+use frame_loop::{FrameBinding, FrameUniforms, with_frame_prelude};
+
+use math::{SceneRng, Vec3};
+
+use scene_graph::{SceneGraph, NodeId, Transform, Attachment, MeshId};
+
+use crate::instances::{Instance, Instances};
+use crate::instance_animation::{InstanceAnimations, InstanceProperty};
+
+
+fn random_instance(rng: &mut SceneRng) -> Instance
+{
+    Instance
+    {
+        color: [rng.rand(None, None), rng.rand(None, None), rng.rand(None, None), 1.0],
+        offset: [rng.rand(Some(-0.9), Some(0.9)), rng.rand(Some(-0.9), Some(0.9))],
+        scale: rng.rand(Some(0.2), Some(0.5)),
+    }
+}
+
+
+// the one mesh of the scene
+const CIRCLE: MeshId = MeshId(0);
+
+
+const STATIC_STORAGE_UNIT_SIZE: u64 =
+    4 * 4 + // color is 4 32bit floats (4bytes each)
+    2 * 4 + // offset is 2 32bit floats (4bytes each)
+    2 * 4;  // padding
+
+const STORAGE_UNIT_SIZE: u64 =
+    2 * 4;  // scale is 2 32bit floats (4bytes each)
+
+
+fn create_circle_vertices(radius: Option<f32>, inner_radius: Option<f32>) -> (Vec<f32>, u32)
+{
+    let radius = radius.unwrap_or(1f32);
+    let num_subdivisions = 24;
+    let inner_radius = inner_radius.unwrap_or(0f32);
+    let start_angle = 0f32;
+    let end_angle = std::f32::consts::PI * 2.0;
+
+    // 2 triangles per subdivision, 3 verts per tri, 2 values (xy) each.
+    let num_vertices = num_subdivisions * 3 * 2;
+    let mut vertex_data = Vec::with_capacity(num_vertices as usize * 2);
+
+    let mut add_vertex = |x, y|
+        {
+            vertex_data.push(x);
+            vertex_data.push(y);
+        };
+
+    // 2 vertices per subdivision
+    //
+    // 0--1 4
+    // | / /|
+    // |/ / |
+    // 2 3--5
+    for i in 0..num_subdivisions
+    {
+      let angle1 = start_angle + i as f32 * (end_angle - start_angle) / num_subdivisions as f32;
+      let angle2 = start_angle + (i + 1) as f32 * (end_angle - start_angle) / num_subdivisions as f32;
+
+      let c1 = angle1.cos();
+      let s1 = angle1.sin();
+      let c2 = angle2.cos();
+      let s2 = angle2.sin();
+
+      // first triangle
+      add_vertex(c1 * radius, s1 * radius);
+      add_vertex(c2 * radius, s2 * radius);
+      add_vertex(c1 * inner_radius, s1 * inner_radius);
+
+      // second triangle
+      add_vertex(c1 * inner_radius, s1 * inner_radius);
+      add_vertex(c2 * radius, s2 * radius);
+      add_vertex(c2 * inner_radius, s2 * inner_radius);
+    }
+
+    (vertex_data, num_vertices)
+}
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    // random instances added later come from it too, so a seed reproduces them as well
+    rng: SceneRng,
+    instances: Instances,
+    animations: InstanceAnimations,
+    // every instance is an object node placed at its offset, with a child node drawing the circle at its scale.
+    // the object and circle nodes by slot of the instance, the roots are in the same order
+    scene_graph: SceneGraph,
+    nodes: Vec<(NodeId, NodeId)>,
+    num_vertices: u32,
+    bind_group_0: B::BindGroup,
+    static_storage_buffer: GrowableBuffer<B>,
+    changing_storage_buffer: GrowableBuffer<B>,
+    vertex_storage_buffer: B::Buffer,
+    render_pipeline: B::RenderPipeline,
+    frame_binding: FrameBinding<B>,
+    multisample_target: MultisampleTarget<B>,
+    viewport: Viewport,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    pub fn new(
+        backend: B,
+        texture_format: TextureFormat,
+        sample_count: u32,
+        alpha_to_coverage: bool,
+        viewport: Viewport,
+        mut rng: SceneRng,
+    )
+        -> Result<Self, String>
+    {
+        let multisample_target = MultisampleTarget::new(texture_format, sample_count, alpha_to_coverage)?;
+
+        let render_source = with_frame_prelude(include_str!("../shader/render.wgsl"));
+        let mut render_shader_module_descriptor = ShaderModuleDescriptor::new(&render_source);
+        render_shader_module_descriptor.label("triangle shaders with storage buffers");
+        let render_shader_module = backend.create_shader_module(&render_shader_module_descriptor);
+
+        let vertex_state = VertexState::new("vertex_main", &render_shader_module);
+
+        let fragment_state = FragmentState::new(
+            "fragment_main", &render_shader_module, &[ColorTargetState::new(texture_format)],
+        );
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("triangle with storage buffers")
+            .fragment(fragment_state)
+            .multisample(multisample_target.multisample_state());
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+        let frame_binding = FrameBinding::new(&backend, &render_pipeline, &[&render_source]);
+
+        let k_num_objects = 100;
+        let mut instances = Instances::new();
+        for _ in 0..k_num_objects
+        {
+            instances.add(random_instance(&mut rng));
+        }
+
+        // the storage buffers grow with the instances, see render
+        let mut static_storage_descriptor = BufferDescriptor::new(
+            STATIC_STORAGE_UNIT_SIZE * k_num_objects, STORAGE | COPY_DST,
+        );
+        static_storage_descriptor.label("static storage for objects");
+        let static_storage_buffer = GrowableBuffer::new(&backend, &static_storage_descriptor);
+        let mut changing_storage_descriptor = BufferDescriptor::new(
+            STORAGE_UNIT_SIZE * k_num_objects, STORAGE | COPY_DST,
+        );
+        changing_storage_descriptor.label("changing storage for objects");
+        let changing_storage_buffer = GrowableBuffer::new(&backend, &changing_storage_descriptor);
+
+        // setup a storage buffer with vertex data
+        let (vertex_data, num_vertices) = create_circle_vertices(Some(0.5), Some(0.25));
+
+        let mut vertex_storage_buffer_descriptor = BufferDescriptor::new(
+            (vertex_data.len() * 4) as u64, STORAGE | COPY_DST,
+        );
+        vertex_storage_buffer_descriptor.label("storage buffer vertices");
+        let vertex_storage_buffer = backend.create_buffer(&vertex_storage_buffer_descriptor);
+        backend.write_buffer(&vertex_storage_buffer, 0, vertex_data.as_bytes());
+
+        let bind_group_0 = Renderer::create_bind_group_0(
+            &backend, &render_pipeline, &static_storage_buffer, &changing_storage_buffer, &vertex_storage_buffer,
+        );
+
+        Ok(Renderer
+        {
+            backend, rng, instances, animations: InstanceAnimations::new(), scene_graph: SceneGraph::new(),
+            nodes: Vec::new(), num_vertices, bind_group_0,
+            static_storage_buffer, changing_storage_buffer, vertex_storage_buffer, render_pipeline, frame_binding,
+            multisample_target, viewport,
+        })
+    }
+
+
+    fn create_bind_group_0(
+        backend: &B,
+        render_pipeline: &B::RenderPipeline,
+        static_storage_buffer: &GrowableBuffer<B>,
+        changing_storage_buffer: &GrowableBuffer<B>,
+        vertex_storage_buffer: &B::Buffer,
+    )
+        -> B::BindGroup
+    {
+        let mut bind_group_0_descriptor = BindGroupDescriptor::new(
+            vec![
+                BindGroupEntry::buffer(0, static_storage_buffer.buffer()),
+                BindGroupEntry::buffer(1, changing_storage_buffer.buffer()),
+                BindGroupEntry::buffer(2, vertex_storage_buffer),
+            ],
+            &backend.render_bind_group_layout(render_pipeline, 0),
+        );
+        bind_group_0_descriptor.label("bind group for objects");
+        backend.create_bind_group(&bind_group_0_descriptor)
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    pub fn set_viewport(&mut self, viewport: Viewport)
+    {
+        self.viewport = viewport;
+    }
+
+
+    // the seed the instances were generated with, to create the scene again with the same instances
+    pub fn seed(&self) -> u32
+    {
+        self.rng.seed()
+    }
+
+
+    pub fn instances(&self) -> &Instances
+    {
+        &self.instances
+    }
+
+
+    pub fn add_instance(&mut self, instance: Instance) -> u32
+    {
+        self.instances.add(instance)
+    }
+
+
+    // adds instances with random colors, offsets and scales, e.g. to find out how many a device can draw
+    pub fn add_random_instances(&mut self, count: u32) -> Vec<u32>
+    {
+        (0..count).map(|_| self.instances.add(random_instance(&mut self.rng))).collect()
+    }
+
+
+    pub fn update_instance(&mut self, id: u32, instance: Instance) -> Result<(), String>
+    {
+        self.instances.update(id, instance)
+    }
+
+
+    pub fn remove_instance(&mut self, id: u32) -> Result<(), String>
+    {
+        self.instances.remove(id)?;
+        self.animations.remove(id);
+        Ok(())
+    }
+
+
+    pub fn clear_instances(&mut self)
+    {
+        self.instances.clear();
+        self.animations.clear();
+    }
+
+
+    // replaces the track of a property of an instance, see InstanceAnimations::set_track
+    pub fn animate_instance(
+        &mut self,
+        id: u32,
+        property: InstanceProperty,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        values: Vec<f32>,
+    )
+        -> Result<(), String>
+    {
+        self.instances.get(id).ok_or_else(|| format!("there is no instance {}", id))?;
+        self.animations.set_track(id, property, interpolation, easing, times, &values)
+    }
+
+
+    // the instance keeps the values it was last animated to
+    pub fn stop_instance_animation(&mut self, id: u32)
+    {
+        self.animations.remove(id);
+    }
+
+
+    pub fn timeline(&self) -> &Timeline
+    {
+        self.animations.timeline()
+    }
+
+
+    pub fn timeline_mut(&mut self) -> &mut Timeline
+    {
+        self.animations.timeline_mut()
+    }
+
+
+    // moves the nodes to the instances, with a node pair for every instance, returns whether any world matrix
+    // changed. the circles are scaled down along x by the aspect, so they stay round on wide canvases
+    fn update_scene_graph(&mut self) -> bool
+    {
+        while self.nodes.len() > self.instances.len()
+        {
+            let (object, _) = self.nodes.pop().unwrap();
+            self.scene_graph.remove_node(object).unwrap();
+        }
+        while self.nodes.len() < self.instances.len()
+        {
+            let object = self.scene_graph.add_node("object", None, Transform::IDENTITY).unwrap();
+            let circle = self.scene_graph.add_node("circle", Some(object), Transform::IDENTITY).unwrap();
+            self.scene_graph.attach(circle, Attachment::Mesh(CIRCLE)).unwrap();
+            self.nodes.push((object, circle));
+        }
+
+        let aspect = self.viewport.aspect();
+        for (instance, (object, circle)) in self.instances.as_slice().iter().zip(&self.nodes)
+        {
+            let translation = Vec3::new(instance.offset[0], instance.offset[1], 0.0);
+            let scale = Vec3::new(instance.scale / aspect, instance.scale, 1.0);
+            self.scene_graph.set_transform(*object, Transform::from_translation(translation)).unwrap();
+            self.scene_graph.set_transform(*circle, Transform::from_scale(scale)).unwrap();
+        }
+        self.scene_graph.update_world_matrices() > 0
+    }
+
+
+    // makes room for every instance and flattens the circles of the scene graph into the storage buffers. the
+    // colors and offsets are uploaded if they changed, the scales if any world matrix did. the buffers are never
+    // empty, storage bindings can't be
+    fn write_instances(&mut self)
+    {
+        let moved = self.update_scene_graph();
+
+        let instance_count = (self.instances.len() as u64).max(1);
+        let static_size = STATIC_STORAGE_UNIT_SIZE * instance_count;
+        let static_grown = self.static_storage_buffer.reserve(&self.backend, static_size);
+        let grown = self.changing_storage_buffer.reserve(&self.backend, STORAGE_UNIT_SIZE * instance_count);
+        if static_grown || grown
+        {
+            self.bind_group_0 = Renderer::create_bind_group_0(
+                &self.backend, &self.render_pipeline, &self.static_storage_buffer, &self.changing_storage_buffer,
+                &self.vertex_storage_buffer,
+            );
+        }
+
+        let k_color_offset = 0;
+        let k_offset_offset = 4;
+
+        // in slot order, like the roots
+        let mesh_instances = self.scene_graph.mesh_instances();
+
+        // the buffers only grow for added instances, which are uploaded anyway
+        if self.instances.take_changed()
+        {
+            let static_unit_length = (STATIC_STORAGE_UNIT_SIZE / 4) as usize;
+            let mut static_storage_values = vec![0f32; self.instances.len() * static_unit_length];
+            let units = static_storage_values.chunks_mut(static_unit_length);
+            for ((instance, mesh_instance), values) in self.instances.as_slice().iter().zip(&mesh_instances).zip(units)
+            {
+                let offset = [mesh_instance.world.cols[3].x, mesh_instance.world.cols[3].y];
+                values[k_color_offset..k_color_offset + 4].copy_from_slice(&instance.color);      // set the color
+                values[k_offset_offset..k_offset_offset + 2].copy_from_slice(&offset);            // set the offset
+            }
+            self.backend.write_buffer(self.static_storage_buffer.buffer(), 0, static_storage_values.as_bytes());
+        }
+
+        if moved
+        {
+            let storage_values = mesh_instances.iter()
+                .flat_map(|mesh_instance| [mesh_instance.world.cols[0].x, mesh_instance.world.cols[1].y])   // the scale
+                .collect::<Vec<f32>>();
+            self.backend.write_buffer(self.changing_storage_buffer.buffer(), 0, storage_values.as_bytes());
+        }
+    }
+
+
+    // advances the animation by the time of the frame
+    pub fn render_frame(&mut self, target: &B::Texture, frame_uniforms: &FrameUniforms)
+    {
+        self.frame_binding.write(&self.backend, frame_uniforms);
+        self.animations.timeline_mut().advance(frame_uniforms.delta_time);
+        self.render(target);
+    }
+
+
+    pub fn render(&mut self, target: &B::Texture)
+    {
+        self.animations.apply(&mut self.instances);
+        self.write_instances();
+
+        let mut color_attachment = self.multisample_target.color_attachment(&self.backend, target);
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor.label("basic canvas render pass");
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("command encoder");
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+
+        render_pass_encoder.set_bind_group(0, &self.bind_group_0);
+        self.frame_binding.set_bind_group(&mut render_pass_encoder);
+        render_pass_encoder.draw(self.num_vertices, self.instances.len() as u32);
+
+        render_pass_encoder.end();
+
+        self.backend.submit(vec![command_encoder.finish()]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{Command, TextureDescriptor};
+    use gpu_backend::recording::{Call, RecordingBackend};
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use software_gpu::{SoftwareBackend, assert_golden};
+
+    use super::*;
+
+
+    fn renderer() -> (Renderer<RecordingBackend>, <RecordingBackend as Backend>::Texture)
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 300.0, 150.0, 1.0);
+        let renderer = Renderer::new(
+            backend.clone(), TextureFormat::Bgra8Unorm, 1, false, viewport, SceneRng::new(3),
+        ).unwrap();
+        (renderer, canvas_texture)
+    }
+
+
+    fn floats(data: &[u8]) -> Vec<f32>
+    {
+        data.chunks(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect()
+    }
+
+
+    #[test]
+    fn every_instance_is_drawn_with_one_call()
+    {
+        let (mut renderer, canvas_texture) = renderer();
+        renderer.render(&canvas_texture);
+
+        let commands = renderer.backend().commands();
+        assert!(commands.iter().any(|command| matches!(
+            command, Command::SetBindGroup { index: 0, bind_group, .. } if *bind_group == renderer.bind_group_0
+        )));
+        assert!(commands.iter().any(|command| matches!(
+            command, Command::Draw { vertex_count: 144, instance_count: 100, .. }
+        )));
+
+        let scales = floats(&renderer.backend().buffer_contents(renderer.changing_storage_buffer.buffer()).unwrap());
+        let instance = &renderer.instances().as_slice()[7];
+        assert_eq!(scales[14..16], [instance.scale / 2.0, instance.scale]);
+    }
+
+
+    #[test]
+    fn colors_and_offsets_are_only_uploaded_when_they_change()
+    {
+        let (mut renderer, canvas_texture) = renderer();
+        renderer.render(&canvas_texture);
+        let static_buffer = *renderer.static_storage_buffer.buffer();
+        let static_writes = |backend: &RecordingBackend| backend.take_calls().iter()
+            .filter(|call| matches!(call, Call::WriteBuffer { buffer, .. } if *buffer == static_buffer))
+            .count();
+        static_writes(renderer.backend());
+
+        renderer.render(&canvas_texture);
+        assert_eq!(static_writes(renderer.backend()), 0);
+
+        let id = renderer.add_instance(Instance { color: [1.0, 0.0, 0.0, 1.0], offset: [0.5, 0.5], scale: 0.3 });
+        renderer.update_instance(id, Instance { color: [0.0, 1.0, 0.0, 1.0], offset: [0.5, 0.5], scale: 0.3 })
+            .unwrap();
+        renderer.render(&canvas_texture);
+        // the buffers grew past 100 instances, so they were reallocated and rebound
+        assert_ne!(*renderer.static_storage_buffer.buffer(), static_buffer);
+        let contents = floats(&renderer.backend().buffer_contents(renderer.static_storage_buffer.buffer()).unwrap());
+        assert_eq!(contents[100 * 8..100 * 8 + 6], [0.0, 1.0, 0.0, 1.0, 0.5, 0.5]);
+    }
+
+
+    #[test]
+    fn the_scene_graph_follows_the_instances()
+    {
+        let (mut renderer, canvas_texture) = renderer();
+        renderer.render(&canvas_texture);
+        assert_eq!(renderer.scene_graph.len(), 2 * 100);
+
+        // nothing moved, so the scales stay as they are
+        renderer.backend().take_calls();
+        renderer.render(&canvas_texture);
+        assert!(!renderer.backend().calls().iter().any(|call| matches!(call, Call::WriteBuffer { .. })));
+
+        let id = renderer.add_instance(Instance { color: [1.0; 4], offset: [0.0, 0.0], scale: 0.2 });
+        renderer.update_instance(id, Instance { color: [1.0; 4], offset: [0.25, -0.5], scale: 0.4 }).unwrap();
+        renderer.remove_instance(0).unwrap();
+        renderer.render(&canvas_texture);
+
+        // the new instance was moved into the slot of the removed one
+        assert_eq!(renderer.scene_graph.len(), 2 * 100);
+        let (object, circle) = renderer.nodes[0];
+        assert_eq!(renderer.scene_graph.world_matrix(object).unwrap().translation(), Vec3::new(0.25, -0.5, 0.0));
+        assert_eq!(renderer.scene_graph.world_matrix(circle).unwrap().cols[0].x, 0.2);
+        let contents = floats(&renderer.backend().buffer_contents(renderer.static_storage_buffer.buffer()).unwrap());
+        assert_eq!(contents[4..6], [0.25, -0.5]);
+        let scales = floats(&renderer.backend().buffer_contents(renderer.changing_storage_buffer.buffer()).unwrap());
+        assert_eq!(scales[0..2], [0.2, 0.4]);
+    }
+
+
+    #[test]
+    fn a_seed_generates_the_same_instances()
+    {
+        let backend = RecordingBackend::new();
+        let viewport = Viewport::new(&backend, 300.0, 150.0, 1.0);
+        let seeded = |seed| Renderer::new(
+            backend.clone(), TextureFormat::Bgra8Unorm, 1, false, viewport, SceneRng::new(seed),
+        ).unwrap();
+        let (mut first, mut second) = (seeded(11), seeded(11));
+        first.add_random_instances(3);
+        second.add_random_instances(3);
+        let colors = |renderer: &Renderer<RecordingBackend>| renderer.instances().as_slice().iter()
+            .map(|instance| instance.color)
+            .collect::<Vec<_>>();
+        assert_eq!(colors(&first), colors(&second));
+        assert_eq!(first.seed(), 11);
+    }
+
+
+    #[test]
+    fn the_golden_image_renders_through_the_renderer()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 96.0, 96.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, 1, false, viewport, SceneRng::new(3),
+        ).unwrap();
+        renderer.render(&target);
+
+        let image = backend.texture_image(&target).unwrap();
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "storage_buffers", &image);
+    }
+}
@@ -0,0 +1,160 @@
+struct VertexShaderOutput
+{
+    @builtin(position) position: vec4f,
+    @location(0) texcoord: vec2f,
+};
+
+struct EffectUniforms
+{
+    texel_size: vec2f,
+    direction: vec2f,
+    params: vec4f,
+};
+
+@group(0) @binding(0) var our_sampler: sampler;
+@group(0) @binding(1) var our_texture: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> uni: EffectUniforms;
+
+
+@vertex
+fn vertex_main(@builtin(vertex_index) vertex_index : u32) -> VertexShaderOutput
+{
+    // a single triangle covering the whole clip space
+    var pos = array(
+        vec2f(-1.0, -1.0),
+        vec2f(3.0, -1.0),
+        vec2f(-1.0, 3.0),
+    );
+
+    var vs_output: VertexShaderOutput;
+    let xy = pos[vertex_index];
+    vs_output.position = vec4f(xy, 0.0, 1.0);
+    vs_output.texcoord = xy * vec2f(0.5, -0.5) + vec2f(0.5);
+    return vs_output;
+}
+
+
+fn luminance(color: vec3f) -> f32
+{
+    return dot(color, vec3f(0.2126, 0.7152, 0.0722));
+}
+
+
+// separable gaussian blur, run once horizontally and once vertically
+// params.x - spread in texels
+@fragment
+fn blur_main(fs_input: VertexShaderOutput) -> @location(0) vec4f
+{
+    var weights = array(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    let spread = uni.direction * uni.texel_size * uni.params.x;
+
+    var color = textureSample(our_texture, our_sampler, fs_input.texcoord) * weights[0];
+    for (var i = 1; i < 5; i++)
+    {
+        let offset = spread * f32(i);
+        color += textureSample(our_texture, our_sampler, fs_input.texcoord + offset) * weights[i];
+        color += textureSample(our_texture, our_sampler, fs_input.texcoord - offset) * weights[i];
+    }
+    return color;
+}
+
+
+// params.x - brightness threshold, params.y - intensity, params.z - spread in texels
+@fragment
+fn bloom_main(fs_input: VertexShaderOutput) -> @location(0) vec4f
+{
+    let color = textureSample(our_texture, our_sampler, fs_input.texcoord);
+    let spread = uni.texel_size * uni.params.z;
+
+    var glow = vec3f(0.0);
+    for (var y = -2; y <= 2; y++)
+    {
+        for (var x = -2; x <= 2; x++)
+        {
+            let texcoord = fs_input.texcoord + vec2f(f32(x), f32(y)) * spread;
+            let sample = textureSample(our_texture, our_sampler, texcoord).rgb;
+            glow += sample * step(uni.params.x, luminance(sample));
+        }
+    }
+    return vec4f(color.rgb + glow / 25.0 * uni.params.y, color.a);
+}
+
+
+// params.x - exposure, params.y - contrast, params.z - saturation, params.w - brightness
+@fragment
+fn color_grading_main(fs_input: VertexShaderOutput) -> @location(0) vec4f
+{
+    let color = textureSample(our_texture, our_sampler, fs_input.texcoord);
+    var rgb = color.rgb * uni.params.x;
+    rgb = (rgb - 0.5) * uni.params.y + 0.5 + uni.params.w;
+    rgb = mix(vec3f(luminance(rgb)), rgb, uni.params.z);
+    return vec4f(clamp(rgb, vec3f(0.0), vec3f(1.0)), color.a);
+}
+
+
+// params.x - intensity, params.y - radius, params.z - softness
+@fragment
+fn vignette_main(fs_input: VertexShaderOutput) -> @location(0) vec4f
+{
+    let color = textureSample(our_texture, our_sampler, fs_input.texcoord);
+    let dist = distance(fs_input.texcoord, vec2f(0.5));
+    let vignette = smoothstep(uni.params.y, uni.params.y - uni.params.z, dist);
+    return vec4f(color.rgb * mix(1.0, vignette, uni.params.x), color.a);
+}
+
+
+// params.x - maximum span in texels, params.y - reduce multiplier, params.z - minimum reduce
+@fragment
+fn fxaa_main(fs_input: VertexShaderOutput) -> @location(0) vec4f
+{
+    let uv = fs_input.texcoord;
+    let texel = uni.texel_size;
+
+    let color = textureSample(our_texture, our_sampler, uv);
+    let luma_nw = luminance(textureSample(our_texture, our_sampler, uv + vec2f(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luminance(textureSample(our_texture, our_sampler, uv + vec2f(1.0, -1.0) * texel).rgb);
+    let luma_sw = luminance(textureSample(our_texture, our_sampler, uv + vec2f(-1.0, 1.0) * texel).rgb);
+    let luma_se = luminance(textureSample(our_texture, our_sampler, uv + vec2f(1.0, 1.0) * texel).rgb);
+    let luma_m = luminance(color.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var dir = vec2f(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * uni.params.y, uni.params.z);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2f(-uni.params.x), vec2f(uni.params.x)) * texel;
+
+    let rgb_a = 0.5 * (
+        textureSample(our_texture, our_sampler, uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+        textureSample(our_texture, our_sampler, uv + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        textureSample(our_texture, our_sampler, uv + dir * -0.5).rgb +
+        textureSample(our_texture, our_sampler, uv + dir * 0.5).rgb
+    );
+    let luma_b = luminance(rgb_b);
+
+    return vec4f(select(rgb_b, rgb_a, luma_b < luma_min || luma_b > luma_max), color.a);
+}
+
+
+// params.x - amount
+@fragment
+fn sharpen_main(fs_input: VertexShaderOutput) -> @location(0) vec4f
+{
+    let uv = fs_input.texcoord;
+    let texel = uni.texel_size;
+
+    let color = textureSample(our_texture, our_sampler, uv);
+    let neighbours =
+        textureSample(our_texture, our_sampler, uv + vec2f(texel.x, 0.0)).rgb +
+        textureSample(our_texture, our_sampler, uv - vec2f(texel.x, 0.0)).rgb +
+        textureSample(our_texture, our_sampler, uv + vec2f(0.0, texel.y)).rgb +
+        textureSample(our_texture, our_sampler, uv - vec2f(0.0, texel.y)).rgb;
+    let rgb = color.rgb * (1.0 + 4.0 * uni.params.x) - neighbours * uni.params.x;
+    return vec4f(clamp(rgb, vec3f(0.0), vec3f(1.0)), color.a);
+}
@@ -0,0 +1,575 @@
+use std::collections::HashSet;
+use std::f32::consts::FRAC_PI_2;
+
+use math::{Mat4, Vec3};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::depth::perspective;
+
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection
+{
+    Perspective,
+    Orthographic,
+}
+
+
+// orbit rotates around the target with the primary button and pans with the others,
+// pan_zoom pans with any button, fly looks around from the eye and moves with wasd, q and e
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraController
+{
+    Orbit,
+    PanZoom,
+    Fly,
+}
+
+
+// the eye sits at distance from the target, in the direction given by yaw around y and pitch above the xz plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pose
+{
+    target: Vec3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+
+impl Pose
+{
+    fn offset(&self) -> Vec3
+    {
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+        Vec3::new(yaw_sin * pitch_cos, pitch_sin, yaw_cos * pitch_cos) * self.distance
+    }
+
+
+    fn eye(&self) -> Vec3
+    {
+        self.target + self.offset()
+    }
+
+
+    fn forward(&self) -> Vec3
+    {
+        -self.offset().normalize()
+    }
+
+
+    fn right(&self) -> Vec3
+    {
+        self.forward().cross(Vec3::Y).normalize()
+    }
+
+
+    fn up(&self) -> Vec3
+    {
+        self.right().cross(self.forward())
+    }
+
+
+    fn lerp(&self, other: &Pose, t: f32) -> Pose
+    {
+        Pose
+        {
+            target: self.target.lerp(other.target, t),
+            yaw: self.yaw + (other.yaw - self.yaw) * t,
+            pitch: self.pitch + (other.pitch - self.pitch) * t,
+            distance: self.distance + (other.distance - self.distance) * t,
+        }
+    }
+
+
+    fn abs_diff_eq(&self, other: &Pose, epsilon: f32) -> bool
+    {
+        self.target.abs_diff_eq(other.target, epsilon)
+            && (self.yaw - other.yaw).abs() <= epsilon
+            && (self.pitch - other.pitch).abs() <= epsilon
+            && (self.distance - other.distance).abs() <= epsilon
+    }
+}
+
+
+// radians per css pixel of pointer movement
+const ROTATE_SPEED: f32 = 0.005;
+// the distance is multiplied by e^(delta * ZOOM_SPEED) for a wheel delta in pixels
+const ZOOM_SPEED: f32 = 0.001;
+// keeps the up vector from flipping at the poles
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+
+// input moves the goal pose and update eases the current pose towards it
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Camera
+{
+    projection: Projection,
+    controller: CameraController,
+    fov: f32,
+    z_near: f32,
+    z_far: f32,
+    min_distance: f32,
+    max_distance: f32,
+    damping: f32,
+    speed: f32,
+    size: (f32, f32),
+    current: Pose,
+    goal: Pose,
+    drag: Option<(f32, f32, i16)>,
+    keys: HashSet<String>,
+}
+
+
+impl Default for Camera
+{
+    fn default() -> Self
+    {
+        Camera::new()
+    }
+}
+
+
+#[wasm_bindgen]
+impl Camera
+{
+    // at [0, 0, 2] looking at the origin with a 60 degree field of view
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self
+    {
+        let pose = Pose { target: Vec3::ZERO, yaw: 0.0, pitch: 0.0, distance: 2.0 };
+        Camera
+        {
+            projection: Projection::Perspective,
+            controller: CameraController::Orbit,
+            fov: 60f32.to_radians(),
+            z_near: 1.0,
+            z_far: 2000.0,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            damping: 0.1,
+            speed: 5.0,
+            size: (1.0, 1.0),
+            current: pose,
+            goal: pose,
+            drag: None,
+            keys: HashSet::new(),
+        }
+    }
+
+
+    pub fn set_projection(&mut self, projection: Projection)
+    {
+        self.projection = projection;
+    }
+
+
+    pub fn set_controller(&mut self, controller: CameraController)
+    {
+        self.controller = controller;
+        self.drag = None;
+        self.keys.clear();
+    }
+
+
+    pub fn set_fov_degrees(&mut self, fov: f32)
+    {
+        self.fov = fov.clamp(1.0, 179.0).to_radians();
+    }
+
+
+    pub fn set_clip_planes(&mut self, z_near: f32, z_far: f32)
+    {
+        self.z_near = z_near;
+        self.z_far = z_far;
+    }
+
+
+    pub fn set_distance_limits(&mut self, min_distance: f32, max_distance: f32)
+    {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self.goal.distance = self.goal.distance.clamp(min_distance, max_distance);
+    }
+
+
+    // the time in seconds it takes to cover about 63% of the way to the goal, 0 follows input immediately
+    pub fn set_damping(&mut self, damping: f32)
+    {
+        self.damping = damping.max(0.0);
+    }
+
+
+    // units per second the fly controller moves at
+    pub fn set_speed(&mut self, speed: f32)
+    {
+        self.speed = speed;
+    }
+
+
+    // jumps there without easing
+    pub fn look_at(&mut self, eye_x: f32, eye_y: f32, eye_z: f32, target_x: f32, target_y: f32, target_z: f32)
+    {
+        let target = Vec3::new(target_x, target_y, target_z);
+        let offset = Vec3::new(eye_x, eye_y, eye_z) - target;
+        let distance = offset.length().max(self.min_distance);
+        let direction = offset.normalize();
+        let pose = Pose
+        {
+            target,
+            yaw: direction.x.atan2(direction.z),
+            pitch: direction.y.clamp(-1.0, 1.0).asin().clamp(-MAX_PITCH, MAX_PITCH),
+            distance,
+        };
+        self.current = pose;
+        self.goal = pose;
+    }
+
+
+    // the canvas size in css pixels, pointer positions are relative to it
+    pub fn resize(&mut self, width: f32, height: f32)
+    {
+        self.size = (width.max(1.0), height.max(1.0));
+    }
+
+
+    // button as in PointerEvent.button
+    pub fn pointer_down(&mut self, x: f32, y: f32, button: i16)
+    {
+        self.drag = Some((x, y, button));
+    }
+
+
+    pub fn pointer_move(&mut self, x: f32, y: f32)
+    {
+        let Some((last_x, last_y, button)) = self.drag
+        else
+        {
+            return;
+        };
+        self.drag = Some((x, y, button));
+        let (dx, dy) = (x - last_x, y - last_y);
+
+        match (self.controller, button)
+        {
+            (CameraController::Orbit, 0) => self.rotate(dx, dy),
+            (CameraController::Orbit, _) | (CameraController::PanZoom, _) => self.pan(dx, dy),
+            (CameraController::Fly, _) =>
+            {
+                // turns the head, so the eye stays where it is
+                let eye = self.goal.eye();
+                self.rotate(dx, dy);
+                self.goal.target = eye - self.goal.offset();
+            },
+        }
+    }
+
+
+    pub fn pointer_up(&mut self)
+    {
+        self.drag = None;
+    }
+
+
+    // delta as in WheelEvent.deltaY, positive zooms out
+    pub fn wheel(&mut self, delta: f32)
+    {
+        match self.controller
+        {
+            CameraController::Fly =>
+            {
+                let step = self.goal.forward() * (-delta * ZOOM_SPEED * self.speed);
+                self.goal.target += step;
+            },
+            _ =>
+            {
+                self.goal.distance = (self.goal.distance * (delta * ZOOM_SPEED).exp())
+                    .clamp(self.min_distance, self.max_distance);
+            },
+        }
+    }
+
+
+    // code as in KeyboardEvent.code, held keys move the fly controller on update
+    pub fn key_down(&mut self, code: &str)
+    {
+        self.keys.insert(code.to_string());
+    }
+
+
+    pub fn key_up(&mut self, code: &str)
+    {
+        self.keys.remove(code);
+    }
+
+
+    // advances by dt seconds, returns whether the camera is still moving so the caller knows to keep rendering
+    pub fn update(&mut self, dt: f32) -> bool
+    {
+        if self.controller == CameraController::Fly
+        {
+            let pressed = |codes: [&str; 2]| codes.iter().any(|code| self.keys.contains(*code)) as i32 as f32;
+            let direction = self.goal.forward() * (pressed(["KeyW", "ArrowUp"]) - pressed(["KeyS", "ArrowDown"]))
+                + self.goal.right() * (pressed(["KeyD", "ArrowRight"]) - pressed(["KeyA", "ArrowLeft"]))
+                + Vec3::Y * (pressed(["KeyE", "Space"]) - pressed(["KeyQ", "ShiftLeft"]));
+            self.goal.target += direction.normalize() * (self.speed * dt);
+        }
+
+        let t = if self.damping > 0.0 { 1.0 - (-dt / self.damping).exp() } else { 1.0 };
+        self.current = self.current.lerp(&self.goal, t);
+        if self.current.abs_diff_eq(&self.goal, 1e-4)
+        {
+            self.current = self.goal;
+        }
+        self.current != self.goal || (self.controller == CameraController::Fly && !self.keys.is_empty())
+    }
+
+
+    pub fn projection(&self) -> Projection
+    {
+        self.projection
+    }
+
+
+    pub fn controller(&self) -> CameraController
+    {
+        self.controller
+    }
+
+
+    pub fn eye(&self) -> Vec<f32>
+    {
+        self.current.eye().to_array().to_vec()
+    }
+
+
+    pub fn target(&self) -> Vec<f32>
+    {
+        self.current.target.to_array().to_vec()
+    }
+
+
+    pub fn yaw(&self) -> f32
+    {
+        self.current.yaw
+    }
+
+
+    pub fn pitch(&self) -> f32
+    {
+        self.current.pitch
+    }
+
+
+    pub fn distance(&self) -> f32
+    {
+        self.current.distance
+    }
+
+
+    pub fn fov_degrees(&self) -> f32
+    {
+        self.fov.to_degrees()
+    }
+}
+
+
+impl Camera
+{
+    fn rotate(&mut self, dx: f32, dy: f32)
+    {
+        self.goal.yaw -= dx * ROTATE_SPEED;
+        self.goal.pitch = (self.goal.pitch + dy * ROTATE_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+
+    // moves the target so the point under the pointer stays under it, at the target's depth
+    fn pan(&mut self, dx: f32, dy: f32)
+    {
+        let units_per_pixel = self.half_height() * 2.0 / self.size.1;
+        self.goal.target += (self.goal.right() * -dx + self.goal.up() * dy) * units_per_pixel;
+    }
+
+
+    // half the height of the view at the target, orthographic projections show the same area
+    fn half_height(&self) -> f32
+    {
+        self.goal.distance * (self.fov * 0.5).tan()
+    }
+
+
+    pub fn view_matrix(&self) -> Mat4
+    {
+        Mat4::look_at(self.current.eye(), self.current.target, Vec3::Y)
+    }
+
+
+    pub fn projection_matrix(&self, aspect: f32, reversed_z: bool) -> Mat4
+    {
+        match self.projection
+        {
+            Projection::Perspective => perspective(self.fov, aspect, self.z_near, self.z_far, reversed_z),
+            Projection::Orthographic =>
+            {
+                let half_height = self.current.distance * (self.fov * 0.5).tan();
+                let half_width = half_height * aspect;
+                if reversed_z
+                {
+                    Mat4::orthographic_reversed_z(-half_width, half_width, -half_height, half_height, self.z_near, self.z_far)
+                }
+                else
+                {
+                    Mat4::orthographic(-half_width, half_width, -half_height, half_height, self.z_near, self.z_far)
+                }
+            },
+        }
+    }
+
+
+    pub fn view_projection_matrix(&self, aspect: f32, reversed_z: bool) -> Mat4
+    {
+        self.projection_matrix(aspect, reversed_z) * self.view_matrix()
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    const EPSILON: f32 = 1e-4;
+
+
+    fn settle(camera: &mut Camera)
+    {
+        for _ in 0..1000
+        {
+            if !camera.update(1.0 / 60.0)
+            {
+                return;
+            }
+        }
+        panic!("the camera never came to rest");
+    }
+
+
+    #[test]
+    fn starts_where_the_fixed_camera_was()
+    {
+        let camera = Camera::new();
+        assert_eq!(camera.eye(), [0.0, 0.0, 2.0]);
+        assert_eq!(camera.target(), [0.0, 0.0, 0.0]);
+        assert!(camera.view_matrix().abs_diff_eq(&Mat4::look_at(Vec3::new(0.0, 0.0, 2.0), Vec3::ZERO, Vec3::Y), EPSILON));
+        assert_eq!(camera.projection_matrix(1.5, false), perspective(60f32.to_radians(), 1.5, 1.0, 2000.0, false));
+    }
+
+
+    #[test]
+    fn orbiting_keeps_the_distance_to_the_target()
+    {
+        let mut camera = Camera::new();
+        camera.set_damping(0.0);
+        camera.pointer_down(100.0, 100.0, 0);
+        camera.pointer_move(400.0, 0.0);
+        camera.pointer_up();
+        camera.pointer_move(0.0, 0.0);
+        camera.update(0.0);
+
+        let eye = Vec3::from([camera.eye()[0], camera.eye()[1], camera.eye()[2]]);
+        assert!((eye.length() - 2.0).abs() < EPSILON);
+        assert!((camera.yaw() + 300.0 * ROTATE_SPEED).abs() < EPSILON);
+        assert!((camera.pitch() + 100.0 * ROTATE_SPEED).abs() < EPSILON);
+        assert_eq!(camera.target(), [0.0, 0.0, 0.0]);
+
+        // the pitch stops short of the poles
+        camera.pointer_down(0.0, 0.0, 0);
+        camera.pointer_move(0.0, 10000.0);
+        camera.update(0.0);
+        assert_eq!(camera.pitch(), MAX_PITCH);
+    }
+
+
+    #[test]
+    fn panning_moves_the_target_with_the_pointer()
+    {
+        let mut camera = Camera::new();
+        camera.set_damping(0.0);
+        camera.resize(200.0, 100.0);
+        camera.pointer_down(0.0, 0.0, 2);
+        camera.pointer_move(-50.0, 0.0);
+        camera.update(0.0);
+        // half the canvas height to the left is half the visible height to the right
+        let half_height = 2.0 * 30f32.to_radians().tan();
+        assert!(Vec3::from([camera.target()[0], camera.target()[1], camera.target()[2]])
+            .abs_diff_eq(Vec3::new(half_height, 0.0, 0.0), EPSILON));
+        assert!((camera.eye()[0] - half_height).abs() < EPSILON);
+
+        camera.wheel(-1000.0);
+        camera.update(0.0);
+        assert!((camera.distance() - 2.0 * (-1.0f32).exp()).abs() < EPSILON);
+        camera.set_distance_limits(1.0, 10.0);
+        camera.wheel(-1000.0);
+        camera.update(0.0);
+        assert_eq!(camera.distance(), 1.0);
+    }
+
+
+    #[test]
+    fn flying_turns_around_the_eye_and_moves_with_keys()
+    {
+        let mut camera = Camera::new();
+        camera.set_controller(CameraController::Fly);
+        camera.set_damping(0.0);
+        camera.pointer_down(0.0, 0.0, 0);
+        camera.pointer_move(300.0, 0.0);
+        camera.update(0.0);
+        assert!(Vec3::from([camera.eye()[0], camera.eye()[1], camera.eye()[2]]).abs_diff_eq(Vec3::new(0.0, 0.0, 2.0), EPSILON));
+        assert!(camera.target()[0] > 0.0);
+
+        camera.pointer_up();
+        camera.look_at(0.0, 0.0, 2.0, 0.0, 0.0, 0.0);
+        camera.set_speed(2.0);
+        camera.key_down("KeyW");
+        assert!(camera.update(0.5));
+        camera.key_up("KeyW");
+        assert!(!camera.update(0.5));
+        assert!(Vec3::from([camera.eye()[0], camera.eye()[1], camera.eye()[2]]).abs_diff_eq(Vec3::new(0.0, 0.0, 1.0), EPSILON));
+    }
+
+
+    #[test]
+    fn damping_eases_towards_the_goal()
+    {
+        let mut camera = Camera::new();
+        camera.set_damping(0.1);
+        camera.wheel(1000.0);
+        assert!(camera.update(0.1));
+        let goal = 2.0 * 1f32.exp();
+        // one time constant covers 1 - 1/e of the way
+        assert!((camera.distance() - (2.0 + (goal - 2.0) * (1.0 - (-1f32).exp()))).abs() < EPSILON);
+        settle(&mut camera);
+        assert_eq!(camera.distance(), goal);
+    }
+
+
+    #[test]
+    fn orthographic_shows_what_perspective_shows_at_the_target()
+    {
+        let mut camera = Camera::new();
+        camera.set_projection(Projection::Orthographic);
+        let corner = Vec3::new(2.0 * 30f32.to_radians().tan() * 1.5, 2.0 * 30f32.to_radians().tan(), 0.0);
+        let projected = camera.view_projection_matrix(1.5, false).project_point3(corner);
+        assert!(projected.abs_diff_eq(Vec3::new(1.0, 1.0, 1.0 / 1999.0), EPSILON));
+
+        // reversed-Z puts the near plane at 1 and the far plane at 0
+        let reversed = camera.projection_matrix(1.5, true);
+        assert!((reversed.project_point3(Vec3::new(0.0, 0.0, -1.0)).z - 1.0).abs() < EPSILON);
+        assert!((reversed.project_point3(Vec3::new(0.0, 0.0, -2000.0)).z).abs() < EPSILON);
+    }
+}
@@ -0,0 +1,199 @@
+use math::Mat4;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use web_sys::GpuCompareFunction;
+
+use gpu_backend::
+{
+    Backend, TextureDescriptor, TextureFormat, CompareFunction, DepthStencilState, RenderPassDepthStencilAttachment,
+    LoadOp, StoreOp,
+};
+use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthFormat
+{
+    Depth24Plus,
+    Depth32Float,
+    Depth24PlusStencil8,
+    Depth32FloatStencil8,   // requires the "depth32float-stencil8" device feature
+}
+
+
+impl DepthFormat
+{
+    pub fn texture_format(&self) -> TextureFormat
+    {
+        match self
+        {
+            DepthFormat::Depth24Plus => TextureFormat::Depth24Plus,
+            DepthFormat::Depth32Float => TextureFormat::Depth32Float,
+            DepthFormat::Depth24PlusStencil8 => TextureFormat::Depth24PlusStencil8,
+            DepthFormat::Depth32FloatStencil8 => TextureFormat::Depth32FloatStencil8,
+        }
+    }
+
+
+    pub fn has_stencil(&self) -> bool
+    {
+        self.texture_format().has_stencil()
+    }
+}
+
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct DepthSettings
+{
+    format: DepthFormat,
+    compare: CompareFunction,
+    write_enabled: bool,
+    reversed_z: bool,
+}
+
+
+#[wasm_bindgen]
+impl DepthSettings
+{
+    #[wasm_bindgen(constructor)]
+    pub fn new(format: DepthFormat, reversed_z: bool) -> Self
+    {
+        // with reversed-Z the near plane maps to 1.0 and the far plane to 0.0,
+        // so closer fragments have the greater depth value
+        let compare = if reversed_z { CompareFunction::Greater } else { CompareFunction::Less };
+        DepthSettings { format, compare, write_enabled: true, reversed_z }
+    }
+
+
+    pub fn set_compare(&mut self, compare: GpuCompareFunction) -> Result<(), String>
+    {
+        self.compare = CompareFunction::try_from(compare)?;
+        Ok(())
+    }
+
+
+    pub fn set_write_enabled(&mut self, write_enabled: bool)
+    {
+        self.write_enabled = write_enabled;
+    }
+
+
+    pub fn format(&self) -> DepthFormat
+    {
+        self.format
+    }
+
+
+    pub fn reversed_z(&self) -> bool
+    {
+        self.reversed_z
+    }
+}
+
+
+impl Default for DepthSettings
+{
+    fn default() -> Self
+    {
+        DepthSettings::new(DepthFormat::Depth24Plus, false)
+    }
+}
+
+
+impl DepthSettings
+{
+    pub fn depth_stencil_state(&self) -> DepthStencilState
+    {
+        DepthStencilState::new(self.format.texture_format(), self.write_enabled, self.compare)
+    }
+
+
+    pub fn clear_value(&self) -> f32
+    {
+        if self.reversed_z { 0.0 } else { 1.0 }
+    }
+}
+
+
+pub struct DepthTexture<B: Backend>
+{
+    settings: DepthSettings,
+    sample_count: u32,
+    texture: Option<B::Texture>,
+    view: Option<B::TextureView>,
+}
+
+
+impl<B: Backend> DepthTexture<B>
+{
+    // the sample count has to match the sample count of the color targets of the render pass
+    pub fn new(settings: DepthSettings, sample_count: u32) -> Self
+    {
+        DepthTexture { settings, sample_count, texture: None, view: None }
+    }
+
+
+    // returns a view of a depth texture matching the given size, creating the texture
+    // on first use and recreating it whenever the canvas size changes
+    pub fn view(&mut self, backend: &B, size: [u32; 2]) -> B::TextureView
+    {
+        if let Some(texture) = &self.texture
+        {
+            if backend.texture_size(texture) != size
+            {
+                backend.destroy_texture(texture);
+                self.texture = None;
+                self.view = None;
+            }
+        }
+
+        if self.view.is_none()
+        {
+            let mut texture_descriptor = TextureDescriptor::new(
+                self.settings.format.texture_format(), size, RENDER_ATTACHMENT,
+            );
+            texture_descriptor
+                .label("depth texture")
+                .sample_count(self.sample_count);
+            let texture = backend.create_texture(&texture_descriptor);
+            self.view = Some(backend.create_view(&texture));
+            self.texture = Some(texture);
+        }
+
+        self.view.clone().unwrap()
+    }
+
+
+    pub fn attachment(&mut self, backend: &B, size: [u32; 2]) -> RenderPassDepthStencilAttachment<B>
+    {
+        let view = self.view(backend, size);
+        let mut depth_stencil_attachment = RenderPassDepthStencilAttachment::new(&view);
+        depth_stencil_attachment
+            .depth_clear_value(self.settings.clear_value())
+            .depth_load_op(LoadOp::Clear)
+            .depth_store_op(StoreOp::Store);
+        if self.settings.format.has_stencil()
+        {
+            depth_stencil_attachment
+                .stencil_clear_value(0)
+                .stencil_ops(LoadOp::Clear, StoreOp::Store);
+        }
+        depth_stencil_attachment
+    }
+}
+
+
+// reversed-Z maps the near plane to 1.0 and the far plane to 0.0 for better depth precision
+pub fn perspective(fov: f32, aspect: f32, z_near: f32, z_far: f32, reversed_z: bool) -> Mat4
+{
+    if reversed_z
+    {
+        Mat4::perspective_reversed_z(fov, aspect, z_near, z_far)
+    }
+    else
+    {
+        Mat4::perspective(fov, aspect, z_near, z_far)
+    }
+}
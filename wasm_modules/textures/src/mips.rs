@@ -0,0 +1,217 @@
+// one level of a mipmap, rgba8unorm texels
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mip
+{
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+
+fn lerp(a: f32, b: f32, t: f32) -> f32
+{
+    a + (b - a) * t
+}
+
+
+fn mix(a: &[f32], b: &[f32], t: f32) -> Vec<f32>
+{
+    a.iter().enumerate().map(|(i, v)| lerp(*v, b[i], t)).collect::<Vec<f32>>()
+}
+
+
+fn bilinear_filter(tl: &[f32], tr: &[f32], bl: &[f32], br: &[f32], t1: f32, t2: f32) -> Vec<f32>
+{
+    let t = mix(tl, tr, t1);
+    let b = mix(bl, br, t1);
+    mix(&t, &b, t2)
+}
+
+
+fn create_next_mip_level_rgba8_unorm(mip: &Mip) -> Mip
+{
+    // compute the size of the next mip
+    let dst_width = 1.max(mip.width / 2);    // const dstWidth = Math.max(1, srcWidth / 2 | 0);
+    let dst_height = 1.max(mip.height / 2);  // const dstHeight = Math.max(1, srcHeight / 2 | 0);
+    // const dst = new Uint8Array(dstWidth * dstHeight * 4);
+    let mut dst = vec![0u8; (dst_width * dst_height * 4) as usize];
+
+    // texels past the edge are clamped, a 1 texel wide source has no right neighbour
+    let get_src_pixel = |x: f32, y: f32|
+        {
+            let x = (x as u32).min(mip.width - 1);
+            let y = (y as u32).min(mip.height - 1);
+            let offset = ((y * mip.width + x) * 4) as usize;   // const offset = (y * srcWidth + x) * 4;
+            // return src.subarray(offset, offset + 4);
+            mip.data[offset..offset + 4].iter().map(|v| *v as f32).collect::<Vec<f32>>()
+        };
+
+    for y in 0..dst_height  // for (let y = 0; y < dstHeight; ++y) {
+    {
+        for x in 0..dst_width   // for (let x = 0; x < dstWidth; ++x) {
+        {
+            // compute texcoord of the center of the destination texel
+            let u = (x as f32 + 0.5) / dst_width as f32;    // const u = (x + 0.5) / dstWidth;
+            let v = (y as f32 + 0.5) / dst_height as f32;   // const v = (y + 0.5) / dstHeight;
+
+            // compute the same texcoord in the source - 0.5 a pixel
+            let au = u * mip.width as f32 - 0.5;    // const au = (u * srcWidth - 0.5);
+            let av = v * mip.height as f32 - 0.5;   // const av = (v * srcHeight - 0.5);
+
+            // compute the src top left texel coord (not texcoord)
+            let tx = au.floor();    // const tx = au | 0;
+            let ty = av.floor();    // const ty = av | 0;
+
+            // compute the mix amounts between pixels
+            let t1 = au.fract();    // const t1 = au % 1;
+            let t2 = av.fract();    // const t2 = av % 1;
+
+            // get the 4 pixels
+            let tl = get_src_pixel(tx, ty); // const tl = getSrcPixel(tx, ty);
+            let tr = get_src_pixel(tx + 1.0, ty);   // const tr = getSrcPixel(tx + 1, ty);
+            let bl = get_src_pixel(tx, ty + 1.0);   // const bl = getSrcPixel(tx, ty + 1);
+            let br = get_src_pixel(tx + 1.0, ty + 1.0); // const br = getSrcPixel(tx + 1, ty + 1);
+
+            // copy the "sampled" result into the dest.
+            let dst_offset = ((y * dst_width + x) * 4) as usize; // const dstOffset = (y * dstWidth + x) * 4;
+            let texel = bilinear_filter(&tl, &tr, &bl, &br, t1, t2).iter().map(|v| *v as u8).collect::<Vec<u8>>();
+            dst[dst_offset..dst_offset + 4].copy_from_slice(&texel);  // dst.set(bilinearFilter(tl, tr, bl, br, t1, t2), dstOffset);
+        }
+    }
+
+    Mip { data: dst, width: dst_width, height: dst_height }  // return { data: dst, width: dstWidth, height: dstHeight };
+}
+
+
+pub fn generate_mips(src: &[u8], src_width: u32) -> Vec<Mip>
+{
+    let src_height = src.len() as u32 / 4 / src_width; // const srcHeight = src.length / 4 / srcWidth;
+
+    // populate with first mip level (base level)
+    let mut mip = Mip { data: src.to_vec(), width: src_width, height: src_height };  // let mip = { data: src, width: srcWidth, height: srcHeight, };
+    let mut mips = vec![mip.clone()];   // const mips = [mip];
+
+    while mip.width > 1 || mip.height > 1  // while (mip.width > 1 || mip.height > 1) {
+    {
+        mip = create_next_mip_level_rgba8_unorm(&mip); // mip = createNextMipLevelRgba8Unorm(mip);
+        mips.push(mip.clone()); // mips.push(mip);
+    }
+
+    mips
+}
+
+
+pub fn create_blended_mipmap() -> Vec<Mip>
+{
+    let w = [255, 255, 255, 255];
+    let r = [255, 0, 0, 255];
+    let b = [0, 28, 116, 255];
+    let y = [255, 231, 0, 255];
+    let g = [58, 181, 75, 255];
+    let a = [38, 123, 167, 255];
+    let data = [
+        w, r, r, r, r, r, r, a, a, r, r, r, r, r, r, w,
+        w, w, r, r, r, r, r, a, a, r, r, r, r, r, w, w,
+        w, w, w, r, r, r, r, a, a, r, r, r, r, w, w, w,
+        w, w, w, w, r, r, r, a, a, r, r, r, w, w, w, w,
+        w, w, w, w, w, r, r, a, a, r, r, w, w, w, w, w,
+        w, w, w, w, w, w, r, a, a, r, w, w, w, w, w, w,
+        w, w, w, w, w, w, w, a, a, w, w, w, w, w, w, w,
+        b, b, b, b, b, b, b, b, a, y, y, y, y, y, y, y,
+        b, b, b, b, b, b, b, g, y, y, y, y, y, y, y, y,
+        w, w, w, w, w, w, w, g, g, w, w, w, w, w, w, w,
+        w, w, w, w, w, w, r, g, g, r, w, w, w, w, w, w,
+        w, w, w, w, w, r, r, g, g, r, r, w, w, w, w, w,
+        w, w, w, w, r, r, r, g, g, r, r, r, w, w, w, w,
+        w, w, w, r, r, r, r, g, g, r, r, r, r, w, w, w,
+        w, w, r, r, r, r, r, g, g, r, r, r, r, r, w, w,
+        w, r, r, r, r, r, r, g, g, r, r, r, r, r, r, w,
+    ].into_iter().flatten().collect::<Vec<u8>>();
+    generate_mips(&data, 16)
+}
+
+
+// every level gets its own color so it is obvious which level is sampled. the top left and bottom right
+// quarters have the color, the rest alternates between white and black from level to level
+pub fn create_checked_mipmap() -> Vec<Mip>
+{
+    let levels = [
+        (64, [128, 0, 255]),
+        (32, [0, 255, 0]),
+        (16, [255, 0, 0]),
+        (8, [255, 255, 0]),
+        (4, [0, 0, 255]),
+        (2, [0, 255, 255]),
+        (1, [255, 0, 255]),
+    ];
+
+    levels.into_iter().enumerate().map(|(i, (size, color)): (usize, (u32, [u8; 3]))|
+        {
+            let background = if (i & 1) == 1 { [0, 0, 0] } else { [255, 255, 255] };
+            let half = size / 2;
+            let data = (0..size * size)
+                .flat_map(|texel|
+                {
+                    let (x, y) = (texel % size, texel / size);
+                    let [r, g, b] = if half == 0
+                        {
+                            // the quarters cover half of a single texel, as a 2d canvas would blend them
+                            [0, 1, 2].map(|c| (color[c] as u32 + background[c] as u32).div_ceil(2) as u8)
+                        }
+                        else if (x < half) == (y < half)
+                        {
+                            color
+                        }
+                        else
+                        {
+                            background
+                        };
+                    [r, g, b, 255]
+                })
+                .collect::<Vec<u8>>();
+            Mip { data, width: size, height: size }
+        }).collect::<Vec<Mip>>()
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+
+    #[test]
+    fn the_mips_halve_down_to_one_texel()
+    {
+        let mips = generate_mips(&[0; 4 * 4 * 2], 4);
+        let sizes = mips.iter().map(|mip| (mip.width, mip.height, mip.data.len())).collect::<Vec<_>>();
+        assert_eq!(sizes, [(4, 2, 32), (2, 1, 8), (1, 1, 4)]);
+    }
+
+
+    #[test]
+    fn a_level_averages_the_texels_under_it()
+    {
+        let mips = generate_mips(&[0, 0, 0, 255, 200, 100, 50, 255], 2);
+        assert_eq!(mips[1].data, [100, 50, 25, 255]);
+
+        let blended = create_blended_mipmap();
+        assert_eq!(blended.len(), 5);
+        assert_eq!(blended[0].data[..4], [255, 255, 255, 255]);
+    }
+
+
+    #[test]
+    fn every_checked_level_has_its_own_color()
+    {
+        let mips = create_checked_mipmap();
+        assert_eq!(mips.iter().map(|mip| mip.width).collect::<Vec<_>>(), [64, 32, 16, 8, 4, 2, 1]);
+
+        let texel = |mip: &Mip, x: u32, y: u32| mip.data[((y * mip.width + x) * 4) as usize..][..4].to_vec();
+        assert_eq!(texel(&mips[0], 0, 0), [128, 0, 255, 255]);
+        assert_eq!(texel(&mips[0], 63, 63), [128, 0, 255, 255]);
+        assert_eq!(texel(&mips[0], 63, 0), [255, 255, 255, 255]);
+        assert_eq!(texel(&mips[1], 0, 31), [0, 0, 0, 255]);
+        assert_eq!(texel(&mips[6], 0, 0), [255, 128, 255, 255]);
+    }
+}
@@ -0,0 +1,620 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use gpu_backend::
+{
+    Backend, TextureDescriptor, TextureFormat, ShaderModuleDescriptor, VertexState, FragmentState,
+    ColorTargetState, RenderPipelineDescriptor, SamplerDescriptor, AddressMode, FilterMode, BufferDescriptor,
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BufferBindingType,
+    CommandEncoder, RenderPassColorAttachment, RenderPassDescriptor, LoadOp, StoreOp, AsBytes,
+};
+use gpu_backend::texture_usage::{RENDER_ATTACHMENT, TEXTURE_BINDING};
+use gpu_backend::buffer_usage::{UNIFORM, COPY_DST};
+use gpu_backend::shader_stage::FRAGMENT;
+
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EffectKind
+{
+    Blur,
+    Bloom,
+    ColorGrading,
+    Vignette,
+    Fxaa,
+    Sharpen,
+}
+
+
+impl EffectKind
+{
+    fn entry_point(&self) -> &'static str
+    {
+        match self
+        {
+            EffectKind::Blur => "blur_main",
+            EffectKind::Bloom => "bloom_main",
+            EffectKind::ColorGrading => "color_grading_main",
+            EffectKind::Vignette => "vignette_main",
+            EffectKind::Fxaa => "fxaa_main",
+            EffectKind::Sharpen => "sharpen_main",
+        }
+    }
+
+
+    // see shader/post_processing.wgsl for the meaning of every parameter
+    fn default_params(&self) -> [f32; 4]
+    {
+        match self
+        {
+            EffectKind::Blur => [1.0, 0.0, 0.0, 0.0],
+            EffectKind::Bloom => [0.7, 1.0, 2.0, 0.0],
+            EffectKind::ColorGrading => [1.0, 1.0, 1.0, 0.0],
+            EffectKind::Vignette => [0.8, 0.75, 0.45, 0.0],
+            EffectKind::Fxaa => [8.0, 1.0 / 8.0, 1.0 / 128.0, 0.0],
+            EffectKind::Sharpen => [0.5, 0.0, 0.0, 0.0],
+        }
+    }
+
+
+    // the blur is separable, so it runs as a horizontal and a vertical pass
+    fn directions(&self) -> &'static [[f32; 2]]
+    {
+        match self
+        {
+            EffectKind::Blur => &[[1.0, 0.0], [0.0, 1.0]],
+            _ => &[[0.0, 0.0]],
+        }
+    }
+}
+
+
+struct Effect
+{
+    kind: EffectKind,
+    params: [f32; 4],
+    enabled: bool,
+}
+
+
+pub struct RenderTarget<B: Backend>
+{
+    format: TextureFormat,
+    label: String,
+    texture: Option<B::Texture>,
+    view: Option<B::TextureView>,
+}
+
+
+impl<B: Backend> RenderTarget<B>
+{
+    pub fn new(format: TextureFormat, label: &str) -> Self
+    {
+        RenderTarget { format, label: label.to_owned(), texture: None, view: None }
+    }
+
+
+    // returns a texture that can be rendered to and sampled from, recreating it
+    // whenever the requested size changes
+    pub fn texture(&mut self, backend: &B, size: [u32; 2]) -> B::Texture
+    {
+        if let Some(texture) = &self.texture
+        {
+            if backend.texture_size(texture) != size
+            {
+                backend.destroy_texture(texture);
+                self.texture = None;
+                self.view = None;
+            }
+        }
+
+        if self.texture.is_none()
+        {
+            let mut texture_descriptor = TextureDescriptor::new(self.format, size, RENDER_ATTACHMENT | TEXTURE_BINDING);
+            texture_descriptor.label(&self.label);
+            let texture = backend.create_texture(&texture_descriptor);
+            self.view = Some(backend.create_view(&texture));
+            self.texture = Some(texture);
+        }
+
+        self.texture.clone().unwrap()
+    }
+
+
+    // the view of the texture, kept as long as the texture
+    pub fn view(&mut self, backend: &B, size: [u32; 2]) -> B::TextureView
+    {
+        self.texture(backend, size);
+        self.view.clone().unwrap()
+    }
+}
+
+
+pub struct PostProcessingStack<B: Backend>
+{
+    backend: B,
+    shader_module: B::ShaderModule,
+    texture_format: TextureFormat,
+    // shared by the pipelines of every effect, so a bind group works with any of them
+    bind_group_layout: B::BindGroupLayout,
+    pipelines: Vec<(EffectKind, B::RenderPipeline)>,
+    sampler: B::Sampler,
+    scene_target: RenderTarget<B>,
+    ping_pong_targets: [RenderTarget<B>; 2],
+    uniform_buffers: Vec<B::Buffer>,
+    // by pass index. the first pass reads the scene target and the others the ping-pong target the pass
+    // before wrote, so they only have to be created again when the targets are resized to another size
+    bind_groups: Vec<B::BindGroup>,
+    bind_groups_size: [u32; 2],
+    effects: Vec<Effect>,
+}
+
+
+impl<B: Backend> PostProcessingStack<B>
+{
+    pub fn new(backend: &B, texture_format: TextureFormat) -> Self
+    {
+        let mut shader_module_descriptor = ShaderModuleDescriptor::new(include_str!("../shader/post_processing.wgsl"));
+        shader_module_descriptor.label("post processing shaders");
+        let shader_module = backend.create_shader_module(&shader_module_descriptor);
+
+        let mut sampler_descriptor = SamplerDescriptor::new();
+        sampler_descriptor
+            .address_mode_u(AddressMode::ClampToEdge)
+            .address_mode_v(AddressMode::ClampToEdge)
+            .mag_filter(FilterMode::Linear)
+            .min_filter(FilterMode::Linear);
+        let sampler = backend.create_sampler(&sampler_descriptor);
+
+        let mut bind_group_layout_descriptor = BindGroupLayoutDescriptor::new(&[
+            BindGroupLayoutEntry::sampler(0, FRAGMENT),
+            BindGroupLayoutEntry::texture(1, FRAGMENT),
+            BindGroupLayoutEntry::buffer(2, FRAGMENT, BufferBindingType::Uniform),
+        ]);
+        bind_group_layout_descriptor.label("bind group layout for post processing");
+        let bind_group_layout = backend.create_bind_group_layout(&bind_group_layout_descriptor);
+
+        PostProcessingStack
+        {
+            backend: backend.clone(),
+            shader_module,
+            texture_format,
+            bind_group_layout,
+            pipelines: Vec::new(),
+            sampler,
+            scene_target: RenderTarget::new(texture_format, "post processing scene target"),
+            ping_pong_targets: [
+                RenderTarget::new(texture_format, "post processing ping target"),
+                RenderTarget::new(texture_format, "post processing pong target"),
+            ],
+            uniform_buffers: Vec::new(),
+            bind_groups: Vec::new(),
+            bind_groups_size: [0, 0],
+            effects: Vec::new(),
+        }
+    }
+
+
+    pub fn is_active(&self) -> bool
+    {
+        self.effects.iter().any(|effect| effect.enabled)
+    }
+
+
+    pub fn add_effect(&mut self, kind: EffectKind) -> usize
+    {
+        self.effects.push(Effect { kind, params: kind.default_params(), enabled: true });
+        self.effects.len() - 1
+    }
+
+
+    pub fn insert_effect(&mut self, index: usize, kind: EffectKind)
+    {
+        let index = index.min(self.effects.len());
+        self.effects.insert(index, Effect { kind, params: kind.default_params(), enabled: true });
+    }
+
+
+    pub fn remove_effect(&mut self, index: usize)
+    {
+        if index < self.effects.len()
+        {
+            self.effects.remove(index);
+        }
+    }
+
+
+    pub fn move_effect(&mut self, from: usize, to: usize)
+    {
+        if from < self.effects.len() && to < self.effects.len()
+        {
+            let effect = self.effects.remove(from);
+            self.effects.insert(to, effect);
+        }
+    }
+
+
+    pub fn clear_effects(&mut self)
+    {
+        self.effects.clear();
+    }
+
+
+    pub fn effects_count(&self) -> usize
+    {
+        self.effects.len()
+    }
+
+
+    pub fn set_effect_params(&mut self, index: usize, params: [f32; 4])
+    {
+        if let Some(effect) = self.effects.get_mut(index)
+        {
+            effect.params = params;
+        }
+    }
+
+
+    pub fn set_effect_enabled(&mut self, index: usize, enabled: bool)
+    {
+        if let Some(effect) = self.effects.get_mut(index)
+        {
+            effect.enabled = enabled;
+        }
+    }
+
+
+    // the texture the scene has to be rendered into when the stack is active
+    pub fn scene_texture(&mut self, size: [u32; 2]) -> B::Texture
+    {
+        self.scene_target.texture(&self.backend, size)
+    }
+
+
+    fn pipeline(&mut self, kind: EffectKind) -> B::RenderPipeline
+    {
+        if let Some((_, pipeline)) = self.pipelines.iter().find(|(k, _)| *k == kind)
+        {
+            return pipeline.clone();
+        }
+
+        let vertex_state = VertexState::new("vertex_main", &self.shader_module);
+        let fragment_state = FragmentState::new(
+            kind.entry_point(), &self.shader_module, &[ColorTargetState::new(self.texture_format)],
+        );
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label(&format!("post processing pipeline: {}", kind.entry_point()))
+            .fragment(fragment_state)
+            .bind_group_layouts(std::slice::from_ref(&self.bind_group_layout));
+        let pipeline = self.backend.create_render_pipeline(&render_pipeline_descriptor);
+        self.pipelines.push((kind, pipeline.clone()));
+        pipeline
+    }
+
+
+    fn uniform_buffer(&mut self, pass_index: usize) -> B::Buffer
+    {
+        while self.uniform_buffers.len() <= pass_index
+        {
+            let uniform_buffer_size =
+                2 * 4 + // texel size is 2 32bit floats (4bytes each)
+                2 * 4 + // direction is 2 32bit floats (4bytes each)
+                4 * 4;  // params are 4 32bit floats (4bytes each)
+            let mut buffer_descriptor = BufferDescriptor::new(uniform_buffer_size, UNIFORM | COPY_DST);
+            buffer_descriptor.label(&format!("post processing uniforms for pass: {}", self.uniform_buffers.len()));
+            self.uniform_buffers.push(self.backend.create_buffer(&buffer_descriptor));
+        }
+        self.uniform_buffers[pass_index].clone()
+    }
+
+
+    // the input view has to be the one the pass always reads, see bind_groups
+    fn bind_group(&mut self, pass_index: usize, input_view: &B::TextureView) -> B::BindGroup
+    {
+        while self.bind_groups.len() <= pass_index
+        {
+            let uniform_buffer = self.uniform_buffer(self.bind_groups.len());
+            let mut bind_group_0_descriptor = BindGroupDescriptor::new(
+                vec![
+                    BindGroupEntry::sampler(0, &self.sampler),
+                    BindGroupEntry::texture_view(1, input_view),
+                    BindGroupEntry::buffer(2, &uniform_buffer),
+                ],
+                &self.bind_group_layout,
+            );
+            bind_group_0_descriptor.label(&format!("post processing bind group for pass: {}", self.bind_groups.len()));
+            self.bind_groups.push(self.backend.create_bind_group(&bind_group_0_descriptor));
+        }
+        self.bind_groups[pass_index].clone()
+    }
+
+
+    // runs every enabled effect in order, ping-ponging between two intermediate textures,
+    // reading the scene texture first and writing the last pass into the destination view
+    pub fn run(&mut self, command_encoder: &mut CommandEncoder<B>, destination: &B::TextureView)
+    {
+        let (source, mut input_view) = match (&self.scene_target.texture, &self.scene_target.view)
+        {
+            (Some(texture), Some(view)) => (texture.clone(), view.clone()),
+            _ => return,
+        };
+        let size = self.backend.texture_size(&source);
+
+        // resizing recreates the targets the bind groups refer to
+        if size != self.bind_groups_size
+        {
+            self.bind_groups.clear();
+            self.bind_groups_size = size;
+        }
+
+        let passes = self.effects.iter()
+            .filter(|effect| effect.enabled)
+            .flat_map(|effect| effect.kind.directions().iter().map(|direction| (effect.kind, *direction, effect.params)))
+            .collect::<Vec<_>>();
+
+        for (pass_index, (kind, direction, params)) in passes.iter().enumerate()
+        {
+            let is_last = pass_index == passes.len() - 1;
+            let output_view = if is_last
+                {
+                    destination.clone()
+                }
+                else
+                {
+                    self.ping_pong_targets[pass_index % 2].view(&self.backend, size)
+                };
+
+            let pipeline = self.pipeline(*kind);
+            let uniform_buffer = self.uniform_buffer(pass_index);
+
+            let uniform_values = [
+                1.0 / size[0] as f32, 1.0 / size[1] as f32,
+                direction[0], direction[1],
+                params[0], params[1], params[2], params[3],
+            ];
+            self.backend.write_buffer(&uniform_buffer, 0, uniform_values.as_bytes());
+
+            let bind_group_0 = self.bind_group(pass_index, &input_view);
+
+            let color_attachment = RenderPassColorAttachment::new(LoadOp::Clear, StoreOp::Store, &output_view);
+            let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+            render_pass_descriptor.label(&format!("post processing pass: {}", kind.entry_point()));
+
+            let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+            render_pass_encoder.set_pipeline(&pipeline);
+            render_pass_encoder.set_bind_group(0, &bind_group_0);
+            render_pass_encoder.draw(3, 1);  // one full screen triangle
+            render_pass_encoder.end();
+
+            input_view = output_view;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{BindingResource, Command};
+    use gpu_backend::recording::{Call, Handle, RecordingBackend};
+
+    use super::*;
+
+
+    #[test]
+    fn the_blur_runs_twice_and_the_last_pass_writes_the_destination()
+    {
+        let backend = RecordingBackend::new();
+        let mut stack = PostProcessingStack::new(&backend, TextureFormat::Bgra8Unorm);
+        stack.add_effect(EffectKind::Blur);
+        let vignette = stack.add_effect(EffectKind::Vignette);
+        let sharpen = stack.add_effect(EffectKind::Sharpen);
+        stack.set_effect_enabled(sharpen, false);
+        stack.set_effect_params(vignette, [0.5, 0.5, 0.5, 0.0]);
+        assert!(stack.is_active());
+
+        let destination_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let destination = backend.create_view(&destination_texture);
+        stack.scene_texture([300, 150]);
+        let mut command_encoder = CommandEncoder::new();
+        stack.run(&mut command_encoder, &destination);
+        backend.submit(vec![command_encoder.finish()]);
+
+        let passes = backend.commands().into_iter()
+            .filter_map(|command| match command
+            {
+                Command::BeginRenderPass(descriptor) => Some(descriptor),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(passes.len(), 3);
+        assert_eq!(passes[0].label.as_deref(), Some("post processing pass: blur_main"));
+        assert_eq!(passes[2].label.as_deref(), Some("post processing pass: vignette_main"));
+        assert_eq!(passes[2].color_attachments[0].view, destination);
+        assert_ne!(passes[1].color_attachments[0].view, destination);
+
+        // a pipeline per kind of effect and a uniform buffer per pass
+        let calls = backend.calls();
+        let pipelines = calls.iter().filter(|call| matches!(call, Call::CreateRenderPipeline { .. }));
+        assert_eq!(pipelines.count(), 2);
+        assert_eq!(stack.uniform_buffers.len(), 3);
+        let params = backend.buffer_contents(&stack.uniform_buffers[2]).unwrap();
+        assert_eq!(params[16..20], 0.5f32.to_le_bytes());
+    }
+
+
+    // runs the stack over a scene of the size into a new destination, returns the destination view
+    fn run(backend: &RecordingBackend, stack: &mut PostProcessingStack<RecordingBackend>, size: [u32; 2])
+        -> <RecordingBackend as Backend>::TextureView
+    {
+        let destination_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, size, RENDER_ATTACHMENT,
+        ));
+        let destination = backend.create_view(&destination_texture);
+        stack.scene_texture(size);
+        let mut command_encoder = CommandEncoder::new();
+        stack.run(&mut command_encoder, &destination);
+        backend.submit(vec![command_encoder.finish()]);
+        destination
+    }
+
+
+    // the effect, input view and output view of every pass of the last submit
+    fn passes(backend: &RecordingBackend) -> Vec<(String, Handle, Handle)>
+    {
+        let calls = backend.calls();
+        let input_view = |bind_group: &Handle| calls.iter()
+            .find_map(|call| match call
+            {
+                Call::CreateBindGroup { bind_group: created, descriptor } if created == bind_group =>
+                {
+                    match descriptor.entries[1].resource
+                    {
+                        BindingResource::TextureView(view) => Some(view),
+                        _ => None,
+                    }
+                },
+                _ => None,
+            })
+            .unwrap();
+        let command_buffers = calls.iter().rev()
+            .find_map(|call| match call
+            {
+                Call::Submit(command_buffers) => Some(command_buffers),
+                _ => None,
+            })
+            .unwrap();
+
+        let mut passes = Vec::new();
+        let mut pass = None;
+        for command in command_buffers.iter().flat_map(|command_buffer| &command_buffer.commands)
+        {
+            match command
+            {
+                Command::BeginRenderPass(descriptor) =>
+                {
+                    let label = descriptor.label.as_deref().unwrap().trim_start_matches("post processing pass: ");
+                    pass = Some((label.to_owned(), descriptor.color_attachments[0].view));
+                },
+                Command::SetBindGroup { bind_group, .. } =>
+                {
+                    let (label, output) = pass.take().unwrap();
+                    passes.push((label, input_view(bind_group), output));
+                },
+                _ => {},
+            }
+        }
+        passes
+    }
+
+
+    fn bind_group_count(backend: &RecordingBackend) -> usize
+    {
+        backend.calls().iter().filter(|call| matches!(call, Call::CreateBindGroup { .. })).count()
+    }
+
+
+    #[test]
+    fn reordered_effects_reuse_the_bind_groups_of_their_passes()
+    {
+        let backend = RecordingBackend::new();
+        let mut stack = PostProcessingStack::new(&backend, TextureFormat::Bgra8Unorm);
+        stack.add_effect(EffectKind::Vignette);
+        stack.add_effect(EffectKind::Blur);
+        stack.add_effect(EffectKind::Sharpen);
+        let destination = run(&backend, &mut stack, [300, 150]);
+        let first = passes(&backend);
+        assert_eq!(bind_group_count(&backend), 4);
+
+        // every pass reads what the one before wrote, starting from the scene
+        let scene = stack.scene_target.view.unwrap();
+        let ping = stack.ping_pong_targets[0].view.unwrap();
+        let pong = stack.ping_pong_targets[1].view.unwrap();
+        let effects = |passes: &[(String, Handle, Handle)]| passes.iter()
+            .map(|(effect, _, _)| effect.trim_end_matches("_main").to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(effects(&first), ["vignette", "blur", "blur", "sharpen"]);
+        assert_eq!(first.iter().map(|(_, input, output)| (*input, *output)).collect::<Vec<_>>(), [
+            (scene, ping), (ping, pong), (pong, ping), (ping, destination),
+        ]);
+
+        stack.move_effect(2, 0);
+        let destination = run(&backend, &mut stack, [300, 150]);
+        let moved = passes(&backend);
+        assert_eq!(effects(&moved), ["sharpen", "vignette", "blur", "blur"]);
+        assert_eq!(moved.last().unwrap().2, destination);
+        assert_eq!(bind_group_count(&backend), 4);
+    }
+
+
+    #[test]
+    fn removed_effects_leave_the_remaining_passes()
+    {
+        let backend = RecordingBackend::new();
+        let mut stack = PostProcessingStack::new(&backend, TextureFormat::Bgra8Unorm);
+        stack.add_effect(EffectKind::Blur);
+        stack.add_effect(EffectKind::ColorGrading);
+        stack.add_effect(EffectKind::Fxaa);
+        run(&backend, &mut stack, [64, 64]);
+
+        stack.remove_effect(0);
+        // out of range, ignored
+        stack.remove_effect(5);
+        assert_eq!(stack.effects_count(), 2);
+        let destination = run(&backend, &mut stack, [64, 64]);
+        let scene = stack.scene_target.view.unwrap();
+        let ping = stack.ping_pong_targets[0].view.unwrap();
+        assert_eq!(passes(&backend), [
+            ("color_grading_main".to_owned(), scene, ping),
+            ("fxaa_main".to_owned(), ping, destination),
+        ]);
+        assert_eq!(bind_group_count(&backend), 4);
+    }
+
+
+    #[test]
+    fn resizing_creates_the_bind_groups_again()
+    {
+        let backend = RecordingBackend::new();
+        let mut stack = PostProcessingStack::new(&backend, TextureFormat::Bgra8Unorm);
+        stack.add_effect(EffectKind::Blur);
+        run(&backend, &mut stack, [300, 150]);
+        let old_views = (stack.scene_target.view.unwrap(), stack.ping_pong_targets[0].view.unwrap());
+
+        run(&backend, &mut stack, [150, 300]);
+        let scene = stack.scene_target.view.unwrap();
+        let ping = stack.ping_pong_targets[0].view.unwrap();
+        assert_ne!((scene, ping), old_views);
+        assert_eq!(passes(&backend).iter().map(|(_, input, _)| *input).collect::<Vec<_>>(), [scene, ping]);
+        assert_eq!(bind_group_count(&backend), 4);
+    }
+
+
+    #[test]
+    fn an_empty_stack_runs_no_passes()
+    {
+        let backend = RecordingBackend::new();
+        let mut stack = PostProcessingStack::new(&backend, TextureFormat::Bgra8Unorm);
+        stack.add_effect(EffectKind::Bloom);
+        stack.clear_effects();
+        assert_eq!(stack.effects_count(), 0);
+        run(&backend, &mut stack, [300, 150]);
+        assert!(backend.commands().is_empty());
+        assert_eq!(bind_group_count(&backend), 0);
+    }
+
+
+    #[test]
+    fn an_empty_or_disabled_stack_is_inactive()
+    {
+        let backend = RecordingBackend::new();
+        let mut stack = PostProcessingStack::new(&backend, TextureFormat::Bgra8Unorm);
+        assert!(!stack.is_active());
+        let blur = stack.add_effect(EffectKind::Blur);
+        stack.set_effect_enabled(blur, false);
+        assert!(!stack.is_active());
+    }
+}
@@ -0,0 +1,482 @@
+use std::f32::consts::PI;
+
+use math::{Mat4, Vec3};
+use animation::{Easing, Interpolation, Repeat, Timeline, TransformProperty, TransformTracks};
+use scene_graph::Transform;
+
+use gpu_backend::
+{
+    Backend, ShaderModuleDescriptor, VertexState, FragmentState, ColorTargetState, RenderPipelineDescriptor,
+    RenderPassDescriptor, TextureDescriptor, SamplerDescriptor, AddressMode, FilterMode, BufferDescriptor,
+    BindGroupDescriptor, BindGroupEntry, CommandEncoder, TextureFormat, MultisampleTarget, Viewport, AsBytes,
+};
+use gpu_backend::texture_usage::{TEXTURE_BINDING, COPY_DST as TEXTURE_COPY_DST};
+use gpu_backend::buffer_usage::{UNIFORM, COPY_DST as BUFFER_COPY_DST};
+
+use frame_loop::{FrameBinding, FrameUniforms, with_frame_prelude};
+
+use crate::camera::Camera;
+use crate::depth::{DepthSettings, DepthTexture};
+use crate::mips::{Mip, create_blended_mipmap, create_checked_mipmap};
+use crate::post_processing::PostProcessingStack;
+
+
+// a bind group per texture and the buffer for the matrix of a quad
+struct ObjectInfo<B: Backend>
+{
+    bind_groups: Vec<B::BindGroup>,
+    uniform_buffer: B::Buffer,
+}
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    object_infos: Vec<ObjectInfo<B>>,
+    // by quad, applied between placing a quad and laying it down
+    quad_animations: Vec<TransformTracks>,
+    timeline: Timeline,
+    render_pipeline: B::RenderPipeline,
+    frame_binding: FrameBinding<B>,
+    depth_settings: DepthSettings,
+    depth_texture: DepthTexture<B>,
+    multisample_target: MultisampleTarget<B>,
+    post_processing: PostProcessingStack<B>,
+    viewport: Viewport,
+    camera: Camera,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    fn create_texture_with_mips(backend: &B, mips: &[Mip], label: &str) -> B::Texture
+    {
+        let mut texture_descriptor = TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [mips[0].width, mips[0].height], TEXTURE_BINDING | TEXTURE_COPY_DST,
+        );
+        texture_descriptor
+            .label(label)
+            .mip_level_count(mips.len() as u32);
+        let texture = backend.create_texture(&texture_descriptor);
+
+        mips.iter().enumerate().for_each(|(mip_level, mip)|
+        {
+            backend.write_texture(&texture, mip_level as u32, &mip.data, mip.width * 4, [mip.width, mip.height]);
+        });
+
+        texture
+    }
+
+
+    pub fn new(
+        backend: B,
+        texture_format: TextureFormat,
+        depth_settings: DepthSettings,
+        sample_count: u32,
+        alpha_to_coverage: bool,
+        viewport: Viewport,
+    )
+        -> Result<Self, String>
+    {
+        let multisample_target = MultisampleTarget::new(texture_format, sample_count, alpha_to_coverage)?;
+
+        let render_source = with_frame_prelude(include_str!("../shader/render.wgsl"));
+        let mut render_shader_module_descriptor = ShaderModuleDescriptor::new(&render_source);
+        render_shader_module_descriptor.label("our hardcoded textured quad shaders");
+        let render_shader_module = backend.create_shader_module(&render_shader_module_descriptor);
+
+        let vertex_state = VertexState::new("vertex_main", &render_shader_module);
+        let fragment_state = FragmentState::new(
+            "fragment_main", &render_shader_module, &[ColorTargetState::new(texture_format)],
+        );
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("hardcoded textured quad pipeline")
+            .fragment(fragment_state)
+            .depth_stencil(depth_settings.depth_stencil_state())
+            .multisample(multisample_target.multisample_state());
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+        let frame_binding = FrameBinding::new(&backend, &render_pipeline, &[&render_source]);
+
+        let textures = [
+            Renderer::create_texture_with_mips(&backend, &create_blended_mipmap(), "blended"),
+            Renderer::create_texture_with_mips(&backend, &create_checked_mipmap(), "checker"),
+        ];
+        let views = textures.iter().map(|texture| backend.create_view(texture)).collect::<Vec<_>>();
+        let bind_group_layout_0 = backend.render_bind_group_layout(&render_pipeline, 0);
+
+        let mut object_infos = Vec::new();
+
+        for i in 0..8
+        {
+            let mut sampler_descriptor = SamplerDescriptor::new();
+            sampler_descriptor
+                .address_mode_u(AddressMode::Repeat)
+                .address_mode_v(AddressMode::Repeat)
+                .mag_filter(if (i & 1) == 1 { FilterMode::Linear } else { FilterMode::Nearest })
+                .min_filter(if (i & 2) == 2 { FilterMode::Linear } else { FilterMode::Nearest })
+                .mipmap_filter(if (i & 4) == 4 { FilterMode::Linear } else { FilterMode::Nearest });
+            let sampler = backend.create_sampler(&sampler_descriptor);
+
+            // create a buffer for the uniform values
+            let uniform_buffer_size =
+                16 * 4; // matrix is 16 32bit floats (4bytes each)
+            let mut buffer_descriptor = BufferDescriptor::new(uniform_buffer_size, UNIFORM | BUFFER_COPY_DST);
+            buffer_descriptor.label("uniforms for quad");
+            let uniform_buffer = backend.create_buffer(&buffer_descriptor);
+
+            let bind_groups = views.iter().map(|view|
+                {
+                    let bind_group_0_descriptor = BindGroupDescriptor::new(
+                        vec![
+                            BindGroupEntry::sampler(0, &sampler),
+                            BindGroupEntry::texture_view(1, view),
+                            BindGroupEntry::buffer(2, &uniform_buffer),
+                        ],
+                        &bind_group_layout_0,
+                    );
+                    backend.create_bind_group(&bind_group_0_descriptor)
+                }).collect::<Vec<B::BindGroup>>();
+
+            object_infos.push(ObjectInfo { bind_groups, uniform_buffer });
+        }
+
+        let depth_texture = DepthTexture::new(depth_settings, multisample_target.sample_count());
+
+        let post_processing = PostProcessingStack::new(&backend, texture_format);
+
+        Ok(Renderer
+        {
+            backend, object_infos, quad_animations: vec![TransformTracks::new(); 8],
+            timeline: Timeline::new(0.0, Repeat::Loop), render_pipeline, frame_binding, depth_settings, depth_texture,
+            multisample_target, post_processing, viewport, camera: Camera::new(),
+        })
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    pub fn set_viewport(&mut self, viewport: Viewport)
+    {
+        self.viewport = viewport;
+    }
+
+
+    pub fn camera(&self) -> &Camera
+    {
+        &self.camera
+    }
+
+
+    pub fn set_camera(&mut self, camera: Camera)
+    {
+        self.camera = camera;
+    }
+
+
+    pub fn timeline(&self) -> &Timeline
+    {
+        &self.timeline
+    }
+
+
+    pub fn timeline_mut(&mut self) -> &mut Timeline
+    {
+        &mut self.timeline
+    }
+
+
+    pub fn post_processing(&self) -> &PostProcessingStack<B>
+    {
+        &self.post_processing
+    }
+
+
+    pub fn post_processing_mut(&mut self) -> &mut PostProcessingStack<B>
+    {
+        &mut self.post_processing
+    }
+
+
+    // replaces the track of one of the 8 quads for a property, see Scene::animate_quad
+    pub fn animate_quad(
+        &mut self,
+        index: usize,
+        property: TransformProperty,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        values: Vec<f32>,
+    )
+        -> Result<(), String>
+    {
+        let tracks = self.quad_animations.get_mut(index).ok_or_else(|| format!("there is no quad {}", index))?;
+        tracks.set_track(property, interpolation, easing, times, &values)?;
+        self.update_animation_duration();
+        Ok(())
+    }
+
+
+    pub fn stop_quad_animation(&mut self, index: usize)
+    {
+        if let Some(tracks) = self.quad_animations.get_mut(index)
+        {
+            *tracks = TransformTracks::new();
+            self.update_animation_duration();
+        }
+    }
+
+
+    fn update_animation_duration(&mut self)
+    {
+        let duration = self.quad_animations.iter().map(TransformTracks::end_time).fold(0.0, f32::max);
+        self.timeline.set_duration(duration);
+    }
+
+
+    // advances the animation by the time of the frame
+    pub fn render_frame(&mut self, target: &B::Texture, frame_uniforms: &FrameUniforms, tex_ndx: usize)
+    {
+        self.frame_binding.write(&self.backend, frame_uniforms);
+        self.timeline.advance(frame_uniforms.delta_time);
+        self.render(target, tex_ndx);
+    }
+
+
+    // tex_ndx picks the blended (0) or the checked (1) mipmap
+    pub fn render(&mut self, target: &B::Texture, tex_ndx: usize)
+    {
+        let view_projection_matrix = self.camera.view_projection_matrix(
+            self.viewport.aspect(), self.depth_settings.reversed_z(),
+        );
+
+        let size = self.backend.texture_size(target);
+        // with post processing enabled the scene is rendered offscreen and the effects write to the target
+        let scene_texture = if self.post_processing.is_active()
+            {
+                self.post_processing.scene_texture(size)
+            }
+            else
+            {
+                target.clone()
+            };
+        let mut color_attachment = self.multisample_target.color_attachment(&self.backend, &scene_texture);
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor
+            .label("basic canvas render pass")
+            .depth_stencil_attachment(self.depth_texture.attachment(&self.backend, size));
+
+        let animation_time = self.timeline.local_time();
+
+        for (i, object_info) in self.object_infos.iter().enumerate()
+        {
+            let x_spacing = 1.2;
+            let y_spacing = 0.7;
+            let z_depth = 50.0;
+
+            let x = i as f32 % 4.0 - 1.5;
+            let y = if i < 4 { 1.0 } else { -1.0 };
+
+            let animated = self.quad_animations[i].sample(animation_time, &Transform::IDENTITY);
+            let matrix = view_projection_matrix
+                * Mat4::from_translation(Vec3::new(x * x_spacing, y * y_spacing, -z_depth * 0.5))
+                * animated.matrix()
+                * Mat4::from_rotation_x(PI * 0.5)
+                * Mat4::from_scale(Vec3::new(1.0, z_depth * 2.0, 1.0))
+                * Mat4::from_translation(Vec3::new(-0.5, -0.5, 0.0));
+
+            // copy the values from JavaScript to the GPU
+            self.backend.write_buffer(&object_info.uniform_buffer, 0, matrix.as_array().as_bytes());
+        }
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("render quad encoder");
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+        self.frame_binding.set_bind_group(&mut render_pass_encoder);
+
+        for object_info in self.object_infos.iter()
+        {
+            render_pass_encoder.set_bind_group(0, &object_info.bind_groups[tex_ndx]);
+            render_pass_encoder.draw(6, 1);  // call our vertex shader 6 times
+        }
+
+        render_pass_encoder.end();
+
+        if self.post_processing.is_active()
+        {
+            self.post_processing.run(&mut command_encoder, &self.backend.create_view(target));
+        }
+
+        self.backend.submit(vec![command_encoder.finish()]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{Command, CompareFunction, LoadOp};
+    use gpu_backend::recording::{Call, RecordingBackend};
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use crate::depth::DepthFormat;
+    use crate::post_processing::EffectKind;
+
+    use software_gpu::{SoftwareBackend, assert_golden};
+    use software_gpu::rasterizer::Image;
+
+    use super::*;
+
+
+    fn renderer(depth_settings: DepthSettings) -> (Renderer<RecordingBackend>, <RecordingBackend as Backend>::Texture)
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 300.0, 150.0, 1.0);
+        let renderer = Renderer::new(backend, TextureFormat::Bgra8Unorm, depth_settings, 1, false, viewport).unwrap();
+        (renderer, canvas_texture)
+    }
+
+
+    #[test]
+    fn every_mip_level_of_both_textures_is_uploaded()
+    {
+        let (renderer, _) = renderer(DepthSettings::default());
+        let calls = renderer.backend().calls();
+        let mip_level_counts = calls.iter()
+            .filter_map(|call| match call
+            {
+                Call::CreateTexture { descriptor, .. } if descriptor.format == TextureFormat::Rgba8Unorm =>
+                    Some(descriptor.mip_level_count),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(mip_level_counts, [5, 7]);
+        let writes = calls.iter().filter(|call| matches!(call, Call::WriteTexture { .. }));
+        assert_eq!(writes.count(), 5 + 7);
+    }
+
+
+    #[test]
+    fn every_quad_is_drawn_with_the_chosen_texture_and_a_cleared_depth()
+    {
+        let (mut renderer, canvas_texture) = renderer(DepthSettings::new(DepthFormat::Depth24PlusStencil8, true));
+        renderer.render_frame(&canvas_texture, &FrameUniforms::default(), 1);
+
+        let commands = renderer.backend().commands();
+        let depth_stencil_attachment = match &commands[0]
+        {
+            Command::BeginRenderPass(descriptor) => descriptor.depth_stencil_attachment.clone().unwrap(),
+            command => panic!("expected a render pass, not {:?}", command),
+        };
+        // reversed-z clears to the far plane at 0.0, and a stencil aspect has to be cleared too
+        assert_eq!(depth_stencil_attachment.depth_clear_value, 0.0);
+        assert!(matches!(depth_stencil_attachment.stencil_ops, Some((LoadOp::Clear, _))));
+
+        let bind_groups = commands.iter()
+            .filter_map(|command| match command
+            {
+                Command::SetBindGroup { index: 0, bind_group, .. } => Some(*bind_group),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let checked = renderer.object_infos.iter().map(|object_info| object_info.bind_groups[1]).collect::<Vec<_>>();
+        assert_eq!(bind_groups, checked);
+        let draws = commands.iter().filter(|command| matches!(command, Command::Draw { vertex_count: 6, .. }));
+        assert_eq!(draws.count(), 8);
+
+        let depth_stencil = renderer.backend().calls().iter()
+            .find_map(|call| match call
+            {
+                Call::CreateRenderPipeline { descriptor, .. } => descriptor.depth_stencil,
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(depth_stencil.depth_compare, CompareFunction::Greater);
+    }
+
+
+    #[test]
+    fn with_effects_the_scene_is_rendered_offscreen_first()
+    {
+        let (mut renderer, canvas_texture) = renderer(DepthSettings::default());
+        renderer.post_processing_mut().add_effect(EffectKind::Vignette);
+        renderer.render(&canvas_texture, 0);
+
+        let commands = renderer.backend().commands();
+        let passes = commands.iter()
+            .filter_map(|command| match command
+            {
+                Command::BeginRenderPass(descriptor) => Some(descriptor),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(passes.len(), 2);
+        assert_eq!(passes[1].label.as_deref(), Some("post processing pass: vignette_main"));
+        assert_ne!(passes[0].color_attachments[0].view, passes[1].color_attachments[0].view);
+    }
+
+
+    #[test]
+    fn an_animated_quad_moves_with_the_timeline()
+    {
+        let (mut renderer, canvas_texture) = renderer(DepthSettings::default());
+        renderer.animate_quad(
+            2, TransformProperty::Translation, Interpolation::Linear, Easing::Linear, vec![0.0, 1.0],
+            vec![0.0, 0.0, 0.0, 0.0, 5.0, 0.0],
+        ).unwrap();
+        assert_eq!(renderer.timeline().duration(), 1.0);
+        assert!(renderer.animate_quad(
+            8, TransformProperty::Scale, Interpolation::Step, Easing::Linear, vec![0.0], vec![1.0, 1.0, 1.0],
+        ).is_err());
+
+        let matrix = |renderer: &Renderer<RecordingBackend>|
+            renderer.backend().buffer_contents(&renderer.object_infos[2].uniform_buffer).unwrap();
+        renderer.render(&canvas_texture, 0);
+        let before = matrix(&renderer);
+        renderer.timeline_mut().set_time(0.5);
+        renderer.render(&canvas_texture, 0);
+        assert_ne!(matrix(&renderer), before);
+
+        renderer.stop_quad_animation(2);
+        assert_eq!(renderer.timeline().duration(), 0.0);
+    }
+
+
+    fn render_golden(effects: &[EffectKind]) -> Image
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 96.0, 96.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, DepthSettings::default(), 1, false, viewport,
+        ).unwrap();
+        for effect in effects
+        {
+            renderer.post_processing_mut().add_effect(*effect);
+        }
+        renderer.render(&target, 1);
+        backend.texture_image(&target).unwrap()
+    }
+
+
+    #[test]
+    fn the_golden_images_render_through_the_renderer()
+    {
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "textures", &render_golden(&[]));
+        let effects = [EffectKind::Blur, EffectKind::Vignette];
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "textures_post_processing", &render_golden(&effects));
+    }
+}
@@ -1,19 +1,19 @@
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue, JsCast};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
 
-use web_sys::
-{
-    GpuDevice, GpuCanvasContext, GpuTextureFormat, GpuShaderModuleDescriptor, GpuVertexState, GpuColorTargetState, 
-    GpuFragmentState, GpuRenderPipelineDescriptor, GpuRenderPassColorAttachment, GpuLoadOp, GpuStoreOp, GpuColorDict, 
-    GpuRenderPassDescriptor, GpuBufferDescriptor,  GpuBindGroupDescriptor, GpuBindGroupEntry, GpuBufferBinding,
-    HtmlCanvasElement, GpuRenderPipeline, GpuBuffer, GpuBindGroup, GpuBlendState, GpuBlendOperation, GpuBlendFactor,
-    GpuBlendComponent,
+use web_sys::{GpuDevice, GpuCanvasContext, GpuTextureFormat, HtmlCanvasElement};
+
+use frame_loop::Frame;
+
+use math::SceneRng;
 
-};
-use web_sys::gpu_buffer_usage::{COPY_DST, UNIFORM};
+use gpu_backend::{TextureFormat, Viewport, BlendMode, BlendSettings, configure_context, canvas_alpha_mode};
+use gpu_backend::capture::CaptureBackend;
+use gpu_backend::web::WebBackend;
 
-use js_sys::Float32Array;
+mod renderer;
+use renderer::Renderer;
 
-use rand::{thread_rng, Rng};
+mod uniform_arena;
 
 
 #[wasm_bindgen]
@@ -27,10 +27,9 @@ extern "C"
 #[wasm_bindgen]
 pub struct Scene 
 {
-    gpu_device: GpuDevice,
     context: GpuCanvasContext,
-    object_infos: Vec<(f32, GpuBuffer, Float32Array, GpuBindGroup)>,
-    render_pipeline: GpuRenderPipeline,
+    texture_format: TextureFormat,
+    renderer: Renderer<CaptureBackend<WebBackend>>,
 }
 
 
@@ -40,151 +39,139 @@ impl Scene
     pub fn create(
         gpu_device: GpuDevice, context: GpuCanvasContext, gpu_texture_format: GpuTextureFormat,
     ) 
-        -> Self
+        -> Result<Scene, JsValue>
+    {
+        Scene::create_with_options(
+            gpu_device, context, gpu_texture_format, BlendSettings::new(BlendMode::Alpha), 1, false,
+        )
+    }
+
+
+    pub fn create_with_multisample(
+        gpu_device: GpuDevice, 
+        context: GpuCanvasContext, 
+        gpu_texture_format: GpuTextureFormat, 
+        sample_count: u32, 
+        alpha_to_coverage: bool,
+    ) 
+        -> Result<Scene, JsValue>
+    {
+        Scene::create_with_options(
+            gpu_device, context, gpu_texture_format, BlendSettings::new(BlendMode::Alpha), sample_count, 
+            alpha_to_coverage,
+        )
+    }
+
+
+    pub fn create_with_blend(
+        gpu_device: GpuDevice, 
+        context: GpuCanvasContext, 
+        gpu_texture_format: GpuTextureFormat, 
+        blend_settings: BlendSettings,
+    ) 
+        -> Result<Scene, JsValue>
+    {
+        Scene::create_with_options(gpu_device, context, gpu_texture_format, blend_settings, 1, false)
+    }
+
+
+    // the same seed always creates the same objects, see object_parameters
+    pub fn create_with_seed(
+        gpu_device: GpuDevice, context: GpuCanvasContext, gpu_texture_format: GpuTextureFormat, seed: u32,
+    )
+        -> Result<Scene, JsValue>
+    {
+        Scene::create_with_rng(
+            gpu_device, context, gpu_texture_format, BlendSettings::new(BlendMode::Alpha), 1, false,
+            SceneRng::new(seed),
+        )
+    }
+
+
+    pub fn create_with_options(
+        gpu_device: GpuDevice, 
+        context: GpuCanvasContext, 
+        gpu_texture_format: GpuTextureFormat, 
+        blend_settings: BlendSettings,
+        sample_count: u32, 
+        alpha_to_coverage: bool,
+    ) 
+        -> Result<Scene, JsValue>
+    {
+        Scene::create_with_rng(
+            gpu_device, context, gpu_texture_format, blend_settings, sample_count, alpha_to_coverage,
+            SceneRng::from_entropy(),
+        )
+    }
+
+
+    fn create_with_rng(
+        gpu_device: GpuDevice, 
+        context: GpuCanvasContext, 
+        gpu_texture_format: GpuTextureFormat, 
+        blend_settings: BlendSettings,
+        sample_count: u32, 
+        alpha_to_coverage: bool,
+        rng: SceneRng,
+    ) 
+        -> Result<Scene, JsValue>
     {
-        let mut render_shader_module_descriptor = GpuShaderModuleDescriptor::new(&include_str!("../shader/render.wgsl"));
-        render_shader_module_descriptor.label("triangle shaders with uniforms");
-        let render_shader_module = gpu_device.create_shader_module(&render_shader_module_descriptor);
-
-        let vertex_state = GpuVertexState::new("vertex_main", &render_shader_module);
-
-        let mut color_target_state = GpuColorTargetState::new(gpu_texture_format);
-
-        let alpha = GpuBlendComponent::new();
-        let mut color = GpuBlendComponent::new();
-        color.src_factor(GpuBlendFactor::SrcAlpha);
-        color.dst_factor(GpuBlendFactor::OneMinusSrcAlpha);
-        color.operation(GpuBlendOperation::Add);
-        let blend_state = GpuBlendState::new(&alpha, &color);
-        color_target_state.blend(&blend_state);
-
-        let fragment_state_targets = [color_target_state].iter().collect::<js_sys::Array>();
-        let fragment_state = GpuFragmentState::new("fragment_main", &render_shader_module, &fragment_state_targets);
-
-        let render_layout = JsValue::from("auto");
-        let mut render_pipeline_descriptor = GpuRenderPipelineDescriptor::new(&render_layout, &vertex_state);
-        render_pipeline_descriptor.label("triangle with uniforms");
-        render_pipeline_descriptor.fragment(&fragment_state);
-        let render_pipeline = gpu_device.create_render_pipeline(&render_pipeline_descriptor);
-
-        let rand = |min: Option<f32>, max: Option<f32>| 
-            {
-                let mut rng = thread_rng();
-                if min.is_none() { return rng.gen_range(0.0..1.0); };
-                if max.is_none() { return rng.gen_range(0.0..min.unwrap()); };
-                rng.gen_range(min.unwrap()..max.unwrap())
-            };
-
-        let static_uniform_buffer_size =
-            4 * 4 + // color is 4 32bit floats (4bytes each)
-            2 * 4 + // offset is 2 32bit floats (4bytes each)
-            2 * 4;  // padding
-
-        let uniform_buffer_size =
-            2 * 4;  // scale is 2 32bit floats (4bytes each)
-
-        let k_color_offset = 0u32;
-        let k_offset_offset = 4u32;
-
-        let k_num_objects = 100;
-        let mut object_infos = Vec::new();
-
-        for i in 0..k_num_objects
-        {
-            let mut static_uniform_buffer_descriptor = GpuBufferDescriptor::new(
-                static_uniform_buffer_size.into(),
-                UNIFORM | COPY_DST,
-            );
-            static_uniform_buffer_descriptor.label(&format!("static uniforms for obj: {}", i));
-            let static_uniform_buffer = gpu_device.create_buffer(&static_uniform_buffer_descriptor);   
-
-            let static_uniform_values = Float32Array::new_with_length(static_uniform_buffer_size / 4);
-
-            let color = [rand(None, None), rand(None, None), rand(None, None), 0.67];
-            let color_array = Float32Array::new_with_length(color.len() as u32);
-            color_array.copy_from(&color);
-            static_uniform_values.set(&color_array, k_color_offset);       // set the color
-
-            let offset = [rand(Some(-0.9), Some(0.9)), rand(Some(-0.9), Some(0.9))];
-            let offset_array = Float32Array::new_with_length(offset.len() as u32);
-            offset_array.copy_from(&offset);
-            static_uniform_values.set(&offset_array, k_offset_offset);     // set the offset
-
-            // copy these values to the GPU
-            gpu_device.queue().write_buffer_with_u32_and_buffer_source(
-                &static_uniform_buffer, 0, &static_uniform_values,
-            );
-
-            // create a typedarray to hold the values for the uniforms in JavaScript
-            let mut uniform_buffer_descriptor = GpuBufferDescriptor::new(
-                uniform_buffer_size.into(),
-                UNIFORM | COPY_DST,
-            );
-            uniform_buffer_descriptor.label(&format!("uniforms for obj: {}", i));
-            let uniform_buffer = gpu_device.create_buffer(&uniform_buffer_descriptor);
-
-            let uniform_values = Float32Array::new_with_length(uniform_buffer_size / 4);
-
-            let bind_group_0_entry_0_resource = GpuBufferBinding::new(&static_uniform_buffer);
-            let bind_group_0_entry_0 = GpuBindGroupEntry::new(0, &bind_group_0_entry_0_resource);
-
-            let bind_group_0_entry_1_resource = GpuBufferBinding::new(&uniform_buffer);
-            let bind_group_0_entry_1 = GpuBindGroupEntry::new(1, &bind_group_0_entry_1_resource);
-        
-            let bind_group_0_entries = [bind_group_0_entry_0, bind_group_0_entry_1].iter().collect::<js_sys::Array>();
-            let mut bind_group_0_descriptor = GpuBindGroupDescriptor::new(
-                &bind_group_0_entries, &render_pipeline.get_bind_group_layout(0),
-            );
-            bind_group_0_descriptor.label(&format!("bind group 0 for obj: {}", i));
-            let bind_group_0 = gpu_device.create_bind_group(&bind_group_0_descriptor);
-
-            object_infos.push((rand(Some(0.2), Some(0.5)), uniform_buffer, uniform_values, bind_group_0));
-        } 
-
-        Scene 
-        {
-            gpu_device, context, object_infos, render_pipeline,
-        }
+        configure_context(&gpu_device, &context, gpu_texture_format, canvas_alpha_mode(&[blend_settings]));
+
+        let texture_format = TextureFormat::try_from(gpu_texture_format).unwrap();
+        let canvas = context.canvas().dyn_into::<HtmlCanvasElement>().unwrap();
+        let viewport = Viewport::from_canvas(&canvas);
+        let renderer = Renderer::new(
+            CaptureBackend::new(WebBackend::new(gpu_device)), texture_format, blend_settings, sample_count,
+            alpha_to_coverage, viewport, rng,
+        )?;
+
+        Ok(Scene { context, texture_format, renderer })
     }
 
 
-    pub fn render(&self)
+    // the seed the objects were generated with, to create the scene again with create_with_seed
+    pub fn seed(&self) -> u32
     {
-        let mut color_attachment = GpuRenderPassColorAttachment::new(
-            GpuLoadOp::Clear, GpuStoreOp::Store, &self.context.get_current_texture().create_view(),
-        );
-        color_attachment.clear_value(&GpuColorDict::new(1.0, 0.3, 0.3, 0.3));
-        let color_attachments = [color_attachment].iter().collect::<js_sys::Array>();
-        let mut render_pass_descriptor = GpuRenderPassDescriptor::new(&color_attachments);
-        render_pass_descriptor.label("basic canvas render pass");
+        self.renderer.seed()
+    }
 
-        let command_encoder = self.gpu_device.create_command_encoder();
-        command_encoder.set_label("command encoder");
 
-        let render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
-        render_pass_encoder.set_pipeline(&self.render_pipeline);
+    // the color, offset and scale of every object, 7 floats each
+    pub fn object_parameters(&self) -> Vec<f32>
+    {
+        self.renderer.object_parameters()
+    }
+
 
+    // width and height are in css pixels, the canvas drawing buffer is sized in physical pixels
+    pub fn resize(&mut self, width: f64, height: f64, device_pixel_ratio: f64)
+    {
+        let viewport = Viewport::new(self.renderer.backend(), width, height, device_pixel_ratio);
         let canvas = self.context.canvas().dyn_into::<HtmlCanvasElement>().unwrap();
-        let aspect = canvas.width() / canvas.height();
+        viewport.apply(&canvas);
+        self.renderer.set_viewport(viewport);
+    }
 
-        let k_scale_offset = 0u32;
 
-        for (scale, uniform_buffer, uniform_values, bind_group_0) in self.object_infos.iter() 
-        {
-            let scale = [scale / aspect as f32, *scale];
-            let scale_array = Float32Array::new_with_length(scale.len() as u32);
-            scale_array.copy_from(&scale);
-            uniform_values.set(&scale_array, k_scale_offset);       // set the scale
+    pub fn render_frame(&mut self, frame: &Frame)
+    {
+        let texture = self.renderer.backend().surface_texture(self.context.get_current_texture(), self.texture_format);
+        self.renderer.render_frame(&texture, frame.uniforms());
+    }
 
-            self.gpu_device.queue().write_buffer_with_u32_and_buffer_source(&uniform_buffer, 0, &uniform_values);
 
-            render_pass_encoder.set_bind_group(0, Some(&bind_group_0));
-            render_pass_encoder.draw(3);
-        }
+    pub fn render(&mut self)
+    {
+        let texture = self.renderer.backend().surface_texture(self.context.get_current_texture(), self.texture_format);
+        self.renderer.render(&texture);
+    }
 
-        render_pass_encoder.end();
 
-        let command_buffer = command_encoder.finish();
-        self.gpu_device.queue().submit(&[command_buffer].iter().collect::<js_sys::Array>());
+    pub fn capture_frame(&mut self) -> Result<String, String>
+    {
+        self.renderer.backend().start_capture();
+        self.render();
+        self.renderer.backend().finish_capture().to_json()
     }
 }
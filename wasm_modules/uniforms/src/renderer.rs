@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+
+use gpu_backend::
+{
+    Backend, ShaderModuleDescriptor, VertexState, FragmentState, RenderPipelineDescriptor, RenderPassDescriptor,
+    BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BufferBindingType,
+    CommandEncoder, TextureFormat, BlendSettings, MultisampleTarget, Viewport,
+};
+use gpu_backend::shader_stage::{VERTEX, FRAGMENT};
+
+use frame_loop::{FrameBinding, FrameUniforms, with_frame_prelude};
+
+use math::{SceneRng, Vec3};
+
+use scene_graph::{SceneGraph, NodeId, Transform, Attachment, MeshId};
+
+use crate::uniform_arena::UniformArena;
+
+
+// the one mesh of the scene
+const TRIANGLE: MeshId = MeshId(0);
+
+// where the color and the offset are in the static uniforms, in floats
+const K_COLOR_OFFSET: usize = 0;
+const K_OFFSET_OFFSET: usize = 4;
+
+
+// every object is a node placed at its offset, with a child node drawing the triangle at its scale.
+// this is what was generated for the triangle node and the offsets of its uniforms in the arena
+struct ObjectInfo
+{
+    color: [f32; 4],
+    scale: f32,
+    static_uniform_offset: u32,
+    uniform_offset: u32,
+}
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    seed: u32,
+    scene_graph: SceneGraph,
+    // by triangle node
+    object_infos: HashMap<NodeId, ObjectInfo>,
+    // the aspect the triangles are scaled for
+    scaled_aspect: f32,
+    uniform_arena: UniformArena<B>,
+    render_pipeline: B::RenderPipeline,
+    bind_group_0: B::BindGroup,
+    frame_binding: FrameBinding<B>,
+    multisample_target: MultisampleTarget<B>,
+    blend_settings: BlendSettings,
+    viewport: Viewport,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    pub fn new(
+        backend: B,
+        texture_format: TextureFormat,
+        blend_settings: BlendSettings,
+        sample_count: u32,
+        alpha_to_coverage: bool,
+        viewport: Viewport,
+        mut rng: SceneRng,
+    )
+        -> Result<Self, String>
+    {
+        let multisample_target = MultisampleTarget::new(texture_format, sample_count, alpha_to_coverage)?;
+
+        let render_source = with_frame_prelude(include_str!("../shader/render.wgsl"));
+        let mut render_shader_module_descriptor = ShaderModuleDescriptor::new(&render_source);
+        render_shader_module_descriptor.label("triangle shaders with uniforms");
+        let render_shader_module = backend.create_shader_module(&render_shader_module_descriptor);
+
+        let vertex_state = VertexState::new("vertex_main", &render_shader_module);
+
+        let fragment_state = FragmentState::new(
+            "fragment_main", &render_shader_module, &[blend_settings.color_target_state(texture_format)],
+        );
+
+        let static_uniform_buffer_size =
+            4 * 4 + // color is 4 32bit floats (4bytes each)
+            2 * 4 + // offset is 2 32bit floats (4bytes each)
+            2 * 4;  // padding
+
+        let uniform_buffer_size =
+            2 * 4;  // scale is 2 32bit floats (4bytes each)
+
+        // both uniforms of every object live in one buffer, the dynamic offsets pick an object's
+        let bind_group_layout_0 = backend.create_bind_group_layout(&BindGroupLayoutDescriptor::new(&[
+            *BindGroupLayoutEntry::buffer(0, VERTEX | FRAGMENT, BufferBindingType::Uniform).has_dynamic_offset(true),
+            *BindGroupLayoutEntry::buffer(1, VERTEX, BufferBindingType::Uniform).has_dynamic_offset(true),
+        ]));
+        // the frame uniforms are in group 1 whether the shader uses them or not
+        let frame_bind_group_layout = FrameBinding::bind_group_layout(&backend);
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("triangle with uniforms")
+            .bind_group_layouts(&[bind_group_layout_0.clone(), frame_bind_group_layout.clone()])
+            .fragment(fragment_state)
+            .multisample(multisample_target.multisample_state());
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+
+        let k_num_objects = 100;
+        let mut uniform_arena = UniformArena::new(
+            &backend, "uniforms for all objects", static_uniform_buffer_size, 2 * k_num_objects,
+        );
+        let mut scene_graph = SceneGraph::new();
+        let mut object_infos = HashMap::new();
+        let aspect = viewport.aspect();
+
+        for _ in 0..k_num_objects
+        {
+            let static_uniform_offset = uniform_arena.allocate(static_uniform_buffer_size).unwrap();
+            let mut static_uniform_values = vec![0f32; (static_uniform_buffer_size / 4) as usize];
+
+            let color = [rng.rand(None, None), rng.rand(None, None), rng.rand(None, None), 0.67];
+            // set the color, the offset follows the node, see write_uniforms
+            static_uniform_values[K_COLOR_OFFSET..K_COLOR_OFFSET + color.len()].copy_from_slice(&color);
+
+            // staged now, uploaded along with the first frame
+            uniform_arena.write(static_uniform_offset, &static_uniform_values);
+
+            let uniform_offset = uniform_arena.allocate(uniform_buffer_size).unwrap();
+
+            let offset = Vec3::new(rng.rand(Some(-0.9), Some(0.9)), rng.rand(Some(-0.9), Some(0.9)), 0.0);
+            let scale = rng.rand(Some(0.2), Some(0.5));
+            let object = scene_graph.add_node("object", None, Transform::from_translation(offset)).unwrap();
+            let triangle = scene_graph
+                .add_node("triangle", Some(object), Renderer::<B>::triangle_transform(scale, aspect))
+                .unwrap();
+            scene_graph.attach(triangle, Attachment::Mesh(TRIANGLE)).unwrap();
+            object_infos.insert(triangle, ObjectInfo { color, scale, static_uniform_offset, uniform_offset });
+        }
+
+        let mut bind_group_0_descriptor = BindGroupDescriptor::new(
+            vec![
+                BindGroupEntry::new(0, uniform_arena.binding(static_uniform_buffer_size)),
+                BindGroupEntry::new(1, uniform_arena.binding(uniform_buffer_size)),
+            ],
+            &bind_group_layout_0,
+        );
+        bind_group_0_descriptor.label("bind group 0 for all objects");
+        let bind_group_0 = backend.create_bind_group(&bind_group_0_descriptor);
+
+        let frame_binding = FrameBinding::with_layout(&backend, &frame_bind_group_layout);
+
+        Ok(Renderer
+        {
+            backend, seed: rng.seed(), scene_graph, object_infos, scaled_aspect: aspect, uniform_arena, render_pipeline,
+            bind_group_0, frame_binding, multisample_target, blend_settings, viewport,
+        })
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    // the seed the objects were generated with, to create the scene again with the same objects
+    pub fn seed(&self) -> u32
+    {
+        self.seed
+    }
+
+
+    // the color, offset and scale of every object in the order they are drawn, 7 floats each
+    pub fn object_parameters(&self) -> Vec<f32>
+    {
+        self.scene_graph.roots().iter()
+            .flat_map(|object|
+            {
+                let translation = self.scene_graph.transform(*object).unwrap().translation;
+                let object_info = &self.object_infos[&self.scene_graph.children(*object)[0]];
+                [object_info.color.as_slice(), &[translation.x, translation.y, object_info.scale]].concat()
+            })
+            .collect()
+    }
+
+
+    pub fn set_viewport(&mut self, viewport: Viewport)
+    {
+        self.viewport = viewport;
+    }
+
+
+    // the triangles are scaled down along x by the aspect, so they keep their shape on wide canvases
+    fn triangle_transform(scale: f32, aspect: f32) -> Transform
+    {
+        Transform::from_scale(Vec3::new(scale / aspect, scale, 1.0))
+    }
+
+
+    // rescales the triangles if the aspect changed, then stages the offset and scale of every triangle from the
+    // world matrices, unless none of them changed since the last frame
+    fn write_uniforms(&mut self)
+    {
+        let aspect = self.viewport.aspect();
+        if aspect != self.scaled_aspect
+        {
+            self.scaled_aspect = aspect;
+            for (triangle, object_info) in &self.object_infos
+            {
+                let transform = Renderer::<B>::triangle_transform(object_info.scale, aspect);
+                self.scene_graph.set_transform(*triangle, transform).unwrap();
+            }
+        }
+        if self.scene_graph.update_world_matrices() == 0
+        {
+            return;
+        }
+
+        for instance in self.scene_graph.mesh_instances()
+        {
+            let world = instance.world;
+            let object_info = &self.object_infos[&instance.node];
+            let offset_offset = object_info.static_uniform_offset + 4 * K_OFFSET_OFFSET as u32;
+            self.uniform_arena.write(offset_offset, &[world.cols[3].x, world.cols[3].y]);     // set the offset
+            self.uniform_arena.write(object_info.uniform_offset, &[world.cols[0].x, world.cols[1].y]);  // set the scale
+        }
+    }
+
+
+    pub fn render_frame(&mut self, target: &B::Texture, frame_uniforms: &FrameUniforms)
+    {
+        self.frame_binding.write(&self.backend, frame_uniforms);
+        self.render(target);
+    }
+
+
+    pub fn render(&mut self, target: &B::Texture)
+    {
+        self.write_uniforms();
+        // every object's uniforms in a single write
+        self.uniform_arena.upload(&self.backend);
+
+        let mut color_attachment = self.multisample_target.color_attachment(&self.backend, target);
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor.label("basic canvas render pass");
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("command encoder");
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+        self.blend_settings.set_blend_constant(&mut render_pass_encoder);
+        self.frame_binding.set_bind_group(&mut render_pass_encoder);
+
+        for instance in self.scene_graph.mesh_instances()
+        {
+            let object_info = &self.object_infos[&instance.node];
+            let dynamic_offsets = [object_info.static_uniform_offset, object_info.uniform_offset];
+            render_pass_encoder.set_bind_group_with_dynamic_offsets(0, &self.bind_group_0, &dynamic_offsets);
+            render_pass_encoder.draw(3, 1);
+        }
+
+        render_pass_encoder.end();
+
+        self.backend.submit(vec![command_encoder.finish()]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{BlendMode, Command, TextureDescriptor, BindingType};
+    use gpu_backend::recording::{Call, RecordingBackend};
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use software_gpu::{SoftwareBackend, assert_golden};
+
+    use super::*;
+
+
+    fn renderer() -> (Renderer<RecordingBackend>, <RecordingBackend as Backend>::Texture)
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 300.0, 150.0, 1.0);
+        let renderer = Renderer::new(
+            backend.clone(), TextureFormat::Bgra8Unorm, BlendSettings::new(BlendMode::Alpha), 1, false, viewport,
+            SceneRng::new(7),
+        ).unwrap();
+        (renderer, canvas_texture)
+    }
+
+
+    // found among the calls, so before they are taken
+    fn arena_buffer(renderer: &Renderer<RecordingBackend>) -> <RecordingBackend as Backend>::Buffer
+    {
+        renderer.backend().calls().iter()
+            .find_map(|call| match call
+            {
+                Call::CreateBuffer { buffer, descriptor }
+                    if descriptor.label.as_deref() == Some("uniforms for all objects") => Some(*buffer),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+
+    #[test]
+    fn every_object_is_drawn_with_its_own_dynamic_offsets()
+    {
+        let (mut renderer, canvas_texture) = renderer();
+        renderer.render(&canvas_texture);
+
+        let commands = renderer.backend().commands();
+        let offsets = commands.iter()
+            .filter_map(|command| match command
+            {
+                Command::SetBindGroup { index: 0, bind_group, dynamic_offsets }
+                    if *bind_group == renderer.bind_group_0 => Some(dynamic_offsets.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(offsets.len(), 100);
+        assert_eq!(offsets[0], [0, 256]);
+        assert_eq!(offsets[99], [99 * 512, 99 * 512 + 256]);
+        let draws = commands.iter().filter(|command| matches!(command, Command::Draw { vertex_count: 3, .. }));
+        assert_eq!(draws.count(), 100);
+        assert!(commands.iter().any(|command| matches!(command, Command::SetBlendConstant(_))));
+    }
+
+
+    #[test]
+    fn the_scale_is_corrected_for_the_aspect_in_a_single_upload()
+    {
+        let (mut renderer, canvas_texture) = renderer();
+        let arena_buffer = arena_buffer(&renderer);
+        renderer.backend().take_calls();
+        renderer.set_viewport(Viewport::new(renderer.backend(), 200.0, 100.0, 1.0));
+        renderer.render(&canvas_texture);
+
+        let writes = renderer.backend().calls().iter()
+            .filter(|call| matches!(call, Call::WriteBuffer { .. }))
+            .count();
+        assert_eq!(writes, 1);
+
+        let contents = renderer.backend().buffer_contents(&arena_buffer).unwrap();
+        let triangle = renderer.scene_graph.mesh_instances()[3].node;
+        let object_info = &renderer.object_infos[&triangle];
+        let at = object_info.uniform_offset as usize;
+        let scale = [&contents[at..at + 4], &contents[at + 4..at + 8]]
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()));
+        assert_eq!(scale, [object_info.scale / 2.0, object_info.scale]);
+
+        // nothing moved, so there is nothing to upload
+        renderer.backend().take_calls();
+        renderer.render(&canvas_texture);
+        assert!(!renderer.backend().calls().iter().any(|call| matches!(call, Call::WriteBuffer { .. })));
+    }
+
+
+    #[test]
+    fn the_offsets_follow_the_object_nodes()
+    {
+        let (mut renderer, canvas_texture) = renderer();
+        let object = renderer.scene_graph.roots()[5];
+        renderer.scene_graph.transform_mut(object).unwrap().translation = Vec3::new(0.25, -0.5, 0.0);
+        renderer.render(&canvas_texture);
+
+        let triangle = renderer.scene_graph.children(object)[0];
+        let at = renderer.object_infos[&triangle].static_uniform_offset as usize + 4 * K_OFFSET_OFFSET;
+        let arena_buffer = arena_buffer(&renderer);
+        let contents = renderer.backend().buffer_contents(&arena_buffer).unwrap();
+        let offset = [&contents[at..at + 4], &contents[at + 4..at + 8]]
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()));
+        assert_eq!(offset, [0.25, -0.5]);
+        assert_eq!(renderer.object_parameters()[5 * 7 + 4..5 * 7 + 6], [0.25, -0.5]);
+    }
+
+
+    #[test]
+    fn the_pipeline_has_the_object_and_the_frame_layouts()
+    {
+        let (renderer, _) = renderer();
+        let calls = renderer.backend().calls();
+        let layouts = calls.iter()
+            .find_map(|call| match call
+            {
+                Call::CreateRenderPipeline { descriptor, .. } => descriptor.bind_group_layouts.clone(),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(layouts.len(), 2);
+        assert!(calls.iter().any(|call| matches!(
+            call, Call::CreateBindGroupLayout { layout, descriptor }
+                if *layout == layouts[0] && descriptor.entries.iter()
+                    .all(|entry| matches!(entry.ty, BindingType::Buffer { has_dynamic_offset: true, .. }))
+        )));
+    }
+
+
+    #[test]
+    fn the_golden_image_renders_through_the_renderer()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 96.0, 96.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, BlendSettings::new(BlendMode::Alpha), 1, false, viewport,
+            SceneRng::new(7),
+        ).unwrap();
+        renderer.render(&target);
+
+        let image = backend.texture_image(&target).unwrap();
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "uniforms", &image);
+    }
+}
@@ -0,0 +1,189 @@
+use std::ops::Range;
+
+use gpu_backend::{Backend, BufferDescriptor, BindingResource};
+use gpu_backend::buffer_usage::{COPY_DST, UNIFORM};
+
+
+fn align_to(value: u32, alignment: u32) -> u32
+{
+    value.next_multiple_of(alignment)
+}
+
+
+// the bookkeeping of an arena apart from its buffer: where the allocations are, the values staged for them
+// and the range that changed since the last upload
+#[derive(Debug)]
+struct Suballocator
+{
+    alignment: u32,
+    values: Vec<u8>,
+    size: u32,
+    dirty: Option<Range<u32>>,
+}
+
+
+impl Suballocator
+{
+    fn new(alignment: u32, capacity: u32) -> Self
+    {
+        Suballocator { alignment, values: vec![0; capacity as usize], size: 0, dirty: None }
+    }
+
+
+    fn allocate(&mut self, size: u32) -> Result<u32, String>
+    {
+        let offset = align_to(self.size, self.alignment);
+        let capacity = self.values.len() as u32;
+        if offset + size > capacity
+        {
+            return Err(format!("no room for {} more bytes in an arena of {}", size, capacity));
+        }
+        self.size = offset + size;
+        Ok(offset)
+    }
+
+
+    fn write(&mut self, offset: u32, data: &[u8])
+    {
+        let end = offset + data.len() as u32;
+        self.values[offset as usize..end as usize].copy_from_slice(data);
+        self.dirty = Some(match self.dirty.take()
+        {
+            Some(dirty) => dirty.start.min(offset)..dirty.end.max(end),
+            None => offset..end,
+        });
+    }
+
+
+    // the range to upload, the values are considered uploaded from here on
+    fn take_dirty(&mut self) -> Option<Range<u32>>
+    {
+        self.dirty.take()
+    }
+}
+
+
+// suballocates per object uniforms from one buffer. every allocation starts at a multiple of
+// minUniformBufferOffsetAlignment, so a single bind group with dynamic offsets serves all of them
+pub struct UniformArena<B: Backend>
+{
+    buffer: B::Buffer,
+    suballocator: Suballocator,
+}
+
+
+impl<B: Backend> UniformArena<B>
+{
+    // room for max_allocations allocations of up to max_allocation_size bytes each
+    pub fn new(backend: &B, label: &str, max_allocation_size: u32, max_allocations: u32) -> Self
+    {
+        let alignment = backend.limits().min_uniform_buffer_offset_alignment;
+        let capacity = align_to(max_allocation_size, alignment) * max_allocations;
+
+        let mut buffer_descriptor = BufferDescriptor::new(capacity.into(), UNIFORM | COPY_DST);
+        buffer_descriptor.label(label);
+        let buffer = backend.create_buffer(&buffer_descriptor);
+
+        UniformArena { buffer, suballocator: Suballocator::new(alignment, capacity) }
+    }
+
+
+    // the offset of the allocation, to be passed as its dynamic offset
+    pub fn allocate(&mut self, size: u32) -> Result<u32, String>
+    {
+        self.suballocator.allocate(size)
+    }
+
+
+    // stages values at an offset, they reach the gpu with the next upload
+    pub fn write(&mut self, offset: u32, values: &[f32])
+    {
+        let data = values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+        self.suballocator.write(offset, &data);
+    }
+
+
+    // one write of everything staged since the last upload
+    pub fn upload(&mut self, backend: &B)
+    {
+        if let Some(dirty) = self.suballocator.take_dirty()
+        {
+            backend.write_buffer(
+                &self.buffer, dirty.start.into(), &self.suballocator.values[dirty.start as usize..dirty.end as usize],
+            );
+        }
+    }
+
+
+    // binds size bytes, the dynamic offset chooses which allocation
+    pub fn binding(&self, size: u32) -> BindingResource<B>
+    {
+        BindingResource::Buffer { buffer: self.buffer.clone(), offset: 0, size: Some(size.into()) }
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::Limits;
+    use gpu_backend::recording::{Call, RecordingBackend};
+
+    use super::*;
+
+
+    #[test]
+    fn allocations_are_aligned_and_bounded()
+    {
+        let mut suballocator = Suballocator::new(256, 3 * 256);
+        assert_eq!(suballocator.allocate(32), Ok(0));
+        assert_eq!(suballocator.allocate(8), Ok(256));
+        assert_eq!(suballocator.allocate(256), Ok(512));
+        assert!(suballocator.allocate(4).is_err());
+
+        let mut suballocator = Suballocator::new(256, 260);
+        assert_eq!(suballocator.allocate(16), Ok(0));
+        assert!(suballocator.allocate(16).is_err());
+    }
+
+
+    #[test]
+    fn uploads_cover_every_write_since_the_last_one()
+    {
+        let mut suballocator = Suballocator::new(256, 4 * 256);
+        let offsets = (0..4).map(|_| suballocator.allocate(8).unwrap()).collect::<Vec<_>>();
+        assert_eq!(suballocator.take_dirty(), None);
+
+        suballocator.write(offsets[2], &[1; 8]);
+        suballocator.write(offsets[1], &[2; 4]);
+        assert_eq!(suballocator.take_dirty(), Some(256..520));
+        assert_eq!(suballocator.take_dirty(), None);
+        assert_eq!(suballocator.values[256..260], [2; 4]);
+        assert_eq!(suballocator.values[512..520], [1; 8]);
+
+        suballocator.write(offsets[3], &[3; 8]);
+        assert_eq!(suballocator.take_dirty(), Some(768..776));
+    }
+
+
+    #[test]
+    fn the_arena_aligns_to_the_device_limit_and_uploads_once()
+    {
+        let backend = RecordingBackend::new();
+        backend.set_limits(Limits { min_uniform_buffer_offset_alignment: 64, ..Default::default() });
+        let mut arena = UniformArena::new(&backend, "arena", 8, 3);
+        let offsets = (0..3).map(|_| arena.allocate(8).unwrap()).collect::<Vec<_>>();
+        assert_eq!(offsets, [0, 64, 128]);
+
+        arena.write(offsets[0], &[1.0, 2.0]);
+        arena.write(offsets[2], &[3.0, 4.0]);
+        backend.take_calls();
+        arena.upload(&backend);
+        arena.upload(&backend);
+
+        let calls = backend.take_calls();
+        assert!(matches!(&calls[..], [Call::WriteBuffer { offset: 0, data, .. }] if data.len() == 136));
+        let contents = backend.buffer_contents(&arena.buffer).unwrap();
+        assert_eq!(contents[128..132], 3f32.to_le_bytes());
+    }
+}
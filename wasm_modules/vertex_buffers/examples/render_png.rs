@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use vertex_buffers::offscreen::render_to_png;
+
+
+// cargo run --example render_png --features wgpu -- [path] [width] [height] [sample count] [seed]
+fn main() -> Result<(), String>
+{
+    let mut args = std::env::args().skip(1);
+    let path = PathBuf::from(args.next().unwrap_or("vertex_buffers.png".to_string()));
+    let mut next_number = |default: u32| args.next()
+        .map(|arg| arg.parse().map_err(|_| format!("not a number: {}", arg)))
+        .unwrap_or(Ok(default));
+    let width = next_number(640)?;
+    let height = next_number(480)?;
+    let sample_count = next_number(4)?;
+    let seed = next_number(0)?;
+
+    render_to_png(&path, width, height, sample_count, seed)?;
+    println!("saved {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,181 @@
+struct Instance {
+  color: vec4f,
+  offset: vec2f,
+  scale: vec2f,
+}
+
+
+// the bounds of the mesh in its own space and the planes of the view, normal and distance
+struct Culling {
+  planes: array<vec4f, 6>,
+  // center and radius
+  sphere: vec4f,
+  aabb_center: vec4f,
+  aabb_extents: vec4f,
+  instance_count: u32,
+}
+
+
+// laid out the way drawIndexedIndirect reads its arguments
+struct DrawIndexedIndirectArgs {
+  index_count: u32,
+  instance_count: u32,
+  first_index: u32,
+  base_vertex: i32,
+  first_instance: u32,
+}
+
+
+const WORKGROUP_SIZE = 64u;
+
+
+@group(0) @binding(0) var<uniform> culling: Culling;
+@group(0) @binding(1) var<storage, read> instances: array<Instance>;
+@group(0) @binding(2) var<storage, read_write> visible_instances: array<u32>;
+@group(0) @binding(3) var<storage, read_write> draw_args: DrawIndexedIndirectArgs;
+// how many of the instances of every workgroup are visible, then where they start after scan_main
+@group(0) @binding(4) var<storage, read_write> workgroup_counts: array<u32>;
+
+var<workgroup> visible_counts: array<u32, WORKGROUP_SIZE>;
+
+
+
+// the same tests as MeshBounds::intersects, the sphere first and then the box.
+// the instances only scale and move the mesh in the xy plane
+fn is_visible(instance: Instance) -> bool
+{
+    let scale = vec3f(instance.scale, 1.0);
+    let offset = vec3f(instance.offset, 0.0);
+
+    let center = culling.sphere.xyz * scale + offset;
+    let radius = culling.sphere.w * max(max(abs(scale.x), abs(scale.y)), abs(scale.z));
+    let aabb_center = culling.aabb_center.xyz * scale + offset;
+    let aabb_extents = culling.aabb_extents.xyz * abs(scale);
+
+    for (var i = 0; i < 6; i++)
+    {
+        let plane = culling.planes[i];
+        if (dot(plane.xyz, center) + plane.w < -radius)
+        {
+            return false;
+        }
+        // the distance of the corner furthest along the normal
+        if (dot(plane.xyz, aabb_center) + dot(abs(plane.xyz), aabb_extents) + plane.w < 0.0)
+        {
+            return false;
+        }
+    }
+    return true;
+}
+
+
+// inclusive scan of what the invocations of the workgroup put into visible_counts
+fn scan(local_index: u32)
+{
+    workgroupBarrier();
+    for (var stride = 1u; stride < WORKGROUP_SIZE; stride *= 2u)
+    {
+        var count = visible_counts[local_index];
+        if (local_index >= stride)
+        {
+            count += visible_counts[local_index - stride];
+        }
+        workgroupBarrier();
+        visible_counts[local_index] = count;
+        workgroupBarrier();
+    }
+}
+
+
+fn is_visible_at(index: u32) -> bool
+{
+    return index < culling.instance_count && is_visible(instances[index]);
+}
+
+
+// there is always one, so that scan_main has a count to write
+fn cull_workgroup_count() -> u32
+{
+    return max((culling.instance_count + WORKGROUP_SIZE - 1u) / WORKGROUP_SIZE, 1u);
+}
+
+
+// more workgroups than fit in x are dispatched in rows, the last row can have some past the end
+fn workgroup_index(workgroup_id: vec3u, num_workgroups: vec3u) -> u32
+{
+    return workgroup_id.y * num_workgroups.x + workgroup_id.x;
+}
+
+
+// every workgroup counts how many of its instances are visible, for compact_main to know where its own go
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn count_main(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(num_workgroups) num_workgroups: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+)
+{
+    let workgroup = workgroup_index(workgroup_id, num_workgroups);
+    if (workgroup >= cull_workgroup_count()) { return; }
+
+    visible_counts[local_index] = u32(is_visible_at(workgroup * WORKGROUP_SIZE + local_index));
+    scan(local_index);
+    if (local_index == WORKGROUP_SIZE - 1u)
+    {
+        workgroup_counts[workgroup] = visible_counts[local_index];
+    }
+}
+
+
+// a single workgroup turns the counts into where the visible instances of every workgroup start, an exclusive
+// scan a chunk of WORKGROUP_SIZE counts at a time, carrying the total of the chunks before along
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn scan_main(@builtin(local_invocation_index) local_index: u32)
+{
+    let workgroup_count = cull_workgroup_count();
+    var carry = 0u;
+    for (var chunk = 0u; chunk < workgroup_count; chunk += WORKGROUP_SIZE)
+    {
+        let workgroup = chunk + local_index;
+        var count = 0u;
+        if (workgroup < workgroup_count) { count = workgroup_counts[workgroup]; }
+        visible_counts[local_index] = count;
+        scan(local_index);
+        if (workgroup < workgroup_count)
+        {
+            workgroup_counts[workgroup] = carry + visible_counts[local_index] - count;
+        }
+        carry += visible_counts[WORKGROUP_SIZE - 1u];
+        // every invocation has read the total before the next chunk is written
+        workgroupBarrier();
+    }
+
+    if (local_index == 0u)
+    {
+        draw_args.instance_count = carry;
+    }
+}
+
+
+// the visible instances of a workgroup go after those of the workgroups before it, so the survivors keep their
+// order and overlapping circles are drawn the same way every frame
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn compact_main(
+    @builtin(workgroup_id) workgroup_id: vec3u,
+    @builtin(num_workgroups) num_workgroups: vec3u,
+    @builtin(local_invocation_index) local_index: u32,
+)
+{
+    let workgroup = workgroup_index(workgroup_id, num_workgroups);
+    if (workgroup >= cull_workgroup_count()) { return; }
+
+    let first = workgroup_counts[workgroup];
+    let index = workgroup * WORKGROUP_SIZE + local_index;
+    let visible = is_visible_at(index);
+    visible_counts[local_index] = u32(visible);
+    scan(local_index);
+    if (visible)
+    {
+        visible_instances[first + visible_counts[local_index] - 1u] = index;
+    }
+}
@@ -0,0 +1,70 @@
+struct Uniforms {
+  scale: vec2f,
+  target_count: u32,
+  vertex_count: u32,
+}
+
+
+struct Instance {
+  color: vec4f,
+  offset: vec2f,
+}
+
+
+struct MorphDelta {
+  position: vec3f,
+  normal: vec3f,
+}
+
+
+struct VSOutput {
+  @builtin(position) position: vec4f,
+  @location(0) normal: vec3f,
+  @location(1) color: vec4f,
+}
+
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var<storage, read> instances: array<Instance>;
+// the deltas of every vertex, the first target's then the next one's
+@group(0) @binding(2) var<storage, read> deltas: array<MorphDelta>;
+// target_count weights for every instance
+@group(0) @binding(3) var<storage, read> weights: array<f32>;
+
+
+@vertex
+fn vertex_main(
+    @location(0) position: vec3f,
+    @location(1) normal: vec3f,
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+)
+    -> VSOutput
+{
+    var morphed_position = position;
+    var morphed_normal = normal;
+    for (var morph = 0u; morph < uniforms.target_count; morph++)
+    {
+        let weight = weights[instance_index * uniforms.target_count + morph];
+        let delta = deltas[morph * uniforms.vertex_count + vertex_index];
+        morphed_position += delta.position * weight;
+        morphed_normal += delta.normal * weight;
+    }
+
+    let instance = instances[instance_index];
+
+    var vs_out: VSOutput;
+    vs_out.position = vec4f(morphed_position.xy * uniforms.scale + instance.offset, 0.0, 1.0);
+    vs_out.normal = normalize(morphed_normal);
+    vs_out.color = instance.color;
+    return vs_out;
+}
+
+
+@fragment
+fn fragment_main(vs_out: VSOutput) -> @location(0) vec4f
+{
+    let light = normalize(vec3f(-0.5, 0.5, 1.0));
+    let diffuse = max(dot(normalize(vs_out.normal), light), 0.0);
+    return vec4f(vs_out.color.rgb * (0.3 + 0.7 * diffuse), vs_out.color.a);
+}
@@ -1,22 +1,36 @@
+struct Instance {
+  color: vec4f,
+  offset: vec2f,
+  scale: vec2f,
+}
+
+
 struct VSOutput {
   @builtin(position) position: vec4f,
   @location(0) color: vec4f,
 }
 
 
+@group(0) @binding(0) var<storage, read> instances: array<Instance>;
+// the instances that survived culling, written by cull.wgsl
+@group(0) @binding(1) var<storage, read> visible_instances: array<u32>;
+// the cull pass culls against the same view
+@group(0) @binding(2) var<uniform> view: mat4x4f;
+
+
 @vertex 
 fn vertex_main(
     @location(0) position: vec2f,
-    @location(1) color: vec4f,
-    @location(2) offset: vec2f,
-    @location(3) scale: vec2f,
-    @location(4) per_vertex_color: vec4f,
+    @location(1) per_vertex_color: vec4f,
+    @builtin(instance_index) instance_index: u32,
 ) 
     -> VSOutput
 {
+    let instance = instances[visible_instances[instance_index]];
+
     var vs_out: VSOutput;
-    vs_out.position = vec4f(position * scale + offset, 0.0, 1.0);
-    vs_out.color = color * per_vertex_color;
+    vs_out.position = view * vec4f(position * instance.scale + instance.offset, 0.0, 1.0);
+    vs_out.color = instance.color * per_vertex_color;
     return vs_out;
 }
 
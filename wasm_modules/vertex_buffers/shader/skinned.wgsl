@@ -0,0 +1,56 @@
+struct Uniforms {
+  color: vec4f,
+  offset: vec2f,
+  scale: vec2f,
+}
+
+
+struct VSOutput {
+  @builtin(position) position: vec4f,
+  @location(0) normal: vec3f,
+}
+
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+// the world matrix of every joint times its inverse bind matrix, for the pose of the frame
+@group(0) @binding(1) var<storage, read> joint_matrices: array<mat4x4f>;
+
+
+// blends the matrices of up to four joints, the weights add up to 1 and unused joints have weight 0
+fn skin_matrix(joints: vec4u, weights: vec4f) -> mat4x4f
+{
+    return joint_matrices[joints.x] * weights.x
+        + joint_matrices[joints.y] * weights.y
+        + joint_matrices[joints.z] * weights.z
+        + joint_matrices[joints.w] * weights.w;
+}
+
+
+@vertex
+fn vertex_main(
+    @location(0) position: vec3f,
+    @location(1) normal: vec3f,
+    @location(2) joints: vec4u,
+    @location(3) weights: vec4f,
+)
+    -> VSOutput
+{
+    let skin = skin_matrix(joints, weights);
+    let skinned_position = skin * vec4f(position, 1.0);
+
+    var vs_out: VSOutput;
+    vs_out.position = vec4f(skinned_position.xy * uniforms.scale + uniforms.offset, 0.0, 1.0);
+    // the joints don't scale, so the normals turn with the same matrix. blending shortens them, they are normalized
+    // before they are interpolated like the cpu reference does
+    vs_out.normal = normalize((skin * vec4f(normal, 0.0)).xyz);
+    return vs_out;
+}
+
+
+@fragment
+fn fragment_main(vs_out: VSOutput) -> @location(0) vec4f
+{
+    let light = normalize(vec3f(-0.5, 0.5, 1.0));
+    let diffuse = max(dot(normalize(vs_out.normal), light), 0.0);
+    return vec4f(uniforms.color.rgb * (0.3 + 0.7 * diffuse), uniforms.color.a);
+}
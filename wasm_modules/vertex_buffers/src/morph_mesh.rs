@@ -0,0 +1,501 @@
+use gpu_backend::
+{
+    Backend, AsBytes, BufferDescriptor, ShaderModuleDescriptor, VertexState, VertexAttribute, VertexFormat,
+    VertexBufferLayout, ColorTargetState, FragmentState, RenderPipelineDescriptor, BindGroupDescriptor, BindGroupEntry,
+    RenderPassEncoder, IndexFormat, MultisampleState, TextureFormat,
+};
+use gpu_backend::buffer_usage::{COPY_DST, VERTEX, INDEX, UNIFORM, STORAGE};
+
+use frame_loop::with_frame_prelude;
+
+use animation::{Easing, Interpolation, MorphTarget, MorphTargets, MorphWeightTracks, Repeat, Timeline};
+
+use math::Vec3;
+
+
+// vertices around the rim of a blob, the center is one more
+const RIM: u32 = 32;
+
+// position and normal are vec3fs
+const VERTEX_SIZE: u64 = 3 * 4 + 3 * 4;
+
+// scale is a vec2f, then the target and vertex counts
+const UNIFORMS_SIZE: u64 = 2 * 4 + 4 + 4;
+
+// color is a vec4f and offset a vec2f, padded to the alignment of the color
+const INSTANCE_SIZE: u64 = 4 * 4 + 4 * 4;
+
+// a position and a normal delta, vec3fs aligned like vec4fs
+const DELTA_SIZE: u64 = 2 * 4 * 4;
+
+const SCALE: f32 = 0.15;
+
+const STAR: usize = 0;
+const SQUARE: usize = 1;
+
+
+// a lit dome seen from above: the center faces the viewer, the rim leans outwards
+fn create_blob_vertices() -> (Vec<Vec3>, Vec<Vec3>, Vec<u32>)
+{
+    let mut positions = vec![Vec3::ZERO];
+    let mut normals = vec![Vec3::Z];
+    for i in 0..RIM
+    {
+        let angle = i as f32 * 2.0 * std::f32::consts::PI / RIM as f32;
+        let (sin, cos) = angle.sin_cos();
+        positions.push(Vec3::new(cos, sin, 0.0));
+        normals.push(Vec3::new(cos * 0.8, sin * 0.8, 0.6));
+    }
+    let indices = (0..RIM).flat_map(|i| [0, 1 + i, 1 + (i + 1) % RIM]).collect();
+    (positions, normals, indices)
+}
+
+
+// a star pushing every other rim vertex out and the ones between in, and a square the rim is stretched onto
+fn create_morph_targets(positions: &[Vec3], normals: &[Vec3]) -> MorphTargets
+{
+    let rim = positions.iter().zip(normals).enumerate().skip(1);
+
+    let mut star = MorphTarget
+    {
+        name: "star".to_owned(),
+        position_deltas: vec![Vec3::ZERO; positions.len()],
+        normal_deltas: vec![Vec3::ZERO; positions.len()],
+    };
+    let mut square = MorphTarget { name: "square".to_owned(), ..star.clone() };
+    for (vertex, (position, normal)) in rim
+    {
+        let push = if vertex % 2 == 1 { 0.6 } else { -0.3 };
+        star.position_deltas[vertex] = *position * push;
+        star.normal_deltas[vertex] = *position * (push * 0.5);
+
+        let stretch = 1.0 / position.x.abs().max(position.y.abs());
+        let side = if position.x.abs() > position.y.abs()
+        {
+            Vec3::new(position.x.signum(), 0.0, 0.0)
+        }
+        else
+        {
+            Vec3::new(0.0, position.y.signum(), 0.0)
+        };
+        square.position_deltas[vertex] = *position * (stretch - 1.0);
+        square.normal_deltas[vertex] = (side * 0.8 + Vec3::Z * 0.6) - *normal;
+    }
+
+    let mut targets = MorphTargets::new(positions.len());
+    assert_eq!(targets.add(star).unwrap(), STAR);
+    assert_eq!(targets.add(square).unwrap(), SQUARE);
+    targets
+}
+
+
+fn encode_vertices(positions: &[Vec3], normals: &[Vec3]) -> Vec<u8>
+{
+    let mut vertex_data = Vec::with_capacity(positions.len() * VERTEX_SIZE as usize);
+    for (position, normal) in positions.iter().zip(normals)
+    {
+        vertex_data.extend_from_slice(position.as_array().as_bytes());
+        vertex_data.extend_from_slice(normal.as_array().as_bytes());
+    }
+    vertex_data
+}
+
+
+struct MorphInstance
+{
+    color: [f32; 4],
+    offset: [f32; 2],
+    // the weights the tracks don't animate
+    weights: Vec<f32>,
+    tracks: MorphWeightTracks,
+}
+
+
+// the first blob pulses into a star, the second into a square and the third turns from a star into a square
+fn create_instances(target_count: usize) -> Vec<MorphInstance>
+{
+    let pulse = |tracks: &mut MorphWeightTracks, target, values: [f32; 3]|
+    {
+        tracks.set_track(target, Interpolation::Linear, Easing::SineInOut, vec![0.0, 1.0, 2.0], values.to_vec())
+            .unwrap();
+    };
+    let colors = [[0.2, 0.7, 0.7, 1.0], [0.9, 0.6, 0.2, 1.0], [0.6, 0.5, 0.9, 1.0]];
+    colors.into_iter()
+        .enumerate()
+        .map(|(i, color)|
+        {
+            let mut tracks = MorphWeightTracks::new(target_count);
+            match i
+            {
+                0 => pulse(&mut tracks, STAR, [0.0, 1.0, 0.0]),
+                1 => pulse(&mut tracks, SQUARE, [0.0, 1.0, 0.0]),
+                _ =>
+                {
+                    pulse(&mut tracks, STAR, [1.0, 0.0, 1.0]);
+                    pulse(&mut tracks, SQUARE, [0.0, 1.0, 0.0]);
+                },
+            }
+            let offset = [(i as f32 - 1.0) * 0.6, 0.7];
+            MorphInstance { color, offset, weights: vec![0.0; target_count], tracks }
+        })
+        .collect()
+}
+
+
+// blobs along the top of the canvas that morph between a circle, a star and a square, blended in the vertex
+// shader with the weights of every instance, see morph.wgsl
+pub struct MorphMesh<B: Backend>
+{
+    targets: MorphTargets,
+    instances: Vec<MorphInstance>,
+    timeline: Timeline,
+    index_count: u32,
+    vertex_buffer: B::Buffer,
+    index_buffer: B::Buffer,
+    uniform_buffer: B::Buffer,
+    weight_buffer: B::Buffer,
+    pipeline: B::RenderPipeline,
+    bind_group: B::BindGroup,
+}
+
+
+impl<B: Backend> MorphMesh<B>
+{
+    pub fn new(backend: &B, texture_format: TextureFormat, multisample_state: MultisampleState) -> Self
+    {
+        let source = with_frame_prelude(include_str!("../shader/morph.wgsl"));
+        let mut shader_module_descriptor = ShaderModuleDescriptor::new(&source);
+        shader_module_descriptor.label("morph target shaders");
+        let shader_module = backend.create_shader_module(&shader_module_descriptor);
+
+        let mut vertex_state = VertexState::new("vertex_main", &shader_module);
+        vertex_state.buffers(&[VertexBufferLayout::new(
+            VERTEX_SIZE,
+            &[
+                VertexAttribute::new(VertexFormat::Float32x3, 0, 0),     // position
+                VertexAttribute::new(VertexFormat::Float32x3, 12, 1),    // normal
+            ],
+        )]);
+
+        let fragment_state = FragmentState::new("fragment_main", &shader_module, &[ColorTargetState::new(texture_format)]);
+
+        let mut pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        pipeline_descriptor
+            .label("morphing blobs")
+            .fragment(fragment_state)
+            .multisample(multisample_state);
+        let pipeline = backend.create_render_pipeline(&pipeline_descriptor);
+
+        let (positions, normals, indices) = create_blob_vertices();
+        let targets = create_morph_targets(&positions, &normals);
+
+        let vertex_data = encode_vertices(&positions, &normals);
+
+        let mut vertex_buffer_descriptor = BufferDescriptor::new(vertex_data.len() as u64, VERTEX | COPY_DST);
+        vertex_buffer_descriptor.label("morph base vertices");
+        let vertex_buffer = backend.create_buffer(&vertex_buffer_descriptor);
+        backend.write_buffer(&vertex_buffer, 0, &vertex_data);
+
+        let mut index_buffer_descriptor = BufferDescriptor::new(indices.as_bytes().len() as u64, INDEX | COPY_DST);
+        index_buffer_descriptor.label("morph indices");
+        let index_buffer = backend.create_buffer(&index_buffer_descriptor);
+        backend.write_buffer(&index_buffer, 0, indices.as_bytes());
+
+        // the deltas never change, target after target
+        let mut delta_data = Vec::with_capacity(targets.len() * targets.vertex_count() * DELTA_SIZE as usize);
+        for target in targets.targets()
+        {
+            for (position, normal) in target.position_deltas.iter().zip(&target.normal_deltas)
+            {
+                delta_data.extend_from_slice(position.extend(0.0).as_array().as_bytes());
+                delta_data.extend_from_slice(normal.extend(0.0).as_array().as_bytes());
+            }
+        }
+        let mut delta_buffer_descriptor = BufferDescriptor::new(delta_data.len() as u64, STORAGE | COPY_DST);
+        delta_buffer_descriptor.label("morph target deltas");
+        let delta_buffer = backend.create_buffer(&delta_buffer_descriptor);
+        backend.write_buffer(&delta_buffer, 0, &delta_data);
+
+        let instances = create_instances(targets.len());
+
+        let mut instance_data = Vec::with_capacity(instances.len() * INSTANCE_SIZE as usize);
+        for instance in &instances
+        {
+            instance_data.extend_from_slice([instance.color.as_slice(), &instance.offset, &[0.0; 2]].concat().as_bytes());
+        }
+        let mut instance_buffer_descriptor = BufferDescriptor::new(instance_data.len() as u64, STORAGE | COPY_DST);
+        instance_buffer_descriptor.label("morph instances");
+        let instance_buffer = backend.create_buffer(&instance_buffer_descriptor);
+        backend.write_buffer(&instance_buffer, 0, &instance_data);
+
+        let mut uniform_buffer_descriptor = BufferDescriptor::new(UNIFORMS_SIZE, UNIFORM | COPY_DST);
+        uniform_buffer_descriptor.label("uniforms for morphing");
+        let uniform_buffer = backend.create_buffer(&uniform_buffer_descriptor);
+
+        let mut weight_buffer_descriptor = BufferDescriptor::new(
+            (instances.len() * targets.len() * 4) as u64, STORAGE | COPY_DST,
+        );
+        weight_buffer_descriptor.label("morph weights");
+        let weight_buffer = backend.create_buffer(&weight_buffer_descriptor);
+
+        let mut bind_group_descriptor = BindGroupDescriptor::new(
+            vec![
+                BindGroupEntry::buffer(0, &uniform_buffer),
+                BindGroupEntry::buffer(1, &instance_buffer),
+                BindGroupEntry::buffer(2, &delta_buffer),
+                BindGroupEntry::buffer(3, &weight_buffer),
+            ],
+            &backend.render_bind_group_layout(&pipeline, 0),
+        );
+        bind_group_descriptor.label("bind group for morphing");
+        let bind_group = backend.create_bind_group(&bind_group_descriptor);
+
+        let mut morph_mesh = MorphMesh
+        {
+            targets, instances, timeline: Timeline::new(0.0, Repeat::Loop), index_count: indices.len() as u32,
+            vertex_buffer, index_buffer, uniform_buffer, weight_buffer, pipeline, bind_group,
+        };
+        morph_mesh.update_duration();
+        morph_mesh
+    }
+
+
+    fn update_duration(&mut self)
+    {
+        let duration = self.instances.iter().map(|instance| instance.tracks.end_time()).fold(0.0, f32::max);
+        self.timeline.set_duration(duration);
+    }
+
+
+    pub fn timeline_mut(&mut self) -> &mut Timeline
+    {
+        &mut self.timeline
+    }
+
+
+    fn instance_mut(&mut self, instance: usize) -> Result<&mut MorphInstance, String>
+    {
+        let instance_count = self.instances.len();
+        self.instances.get_mut(instance)
+            .ok_or_else(|| format!("instance {} is out of the {} morphing instances", instance, instance_count))
+    }
+
+
+    // the weights of the targets the instance doesn't animate, one for every target
+    pub fn set_weights(&mut self, instance: usize, weights: &[f32]) -> Result<(), String>
+    {
+        let target_count = self.targets.len();
+        if weights.len() != target_count
+        {
+            return Err(format!("{} weights for {} targets", weights.len(), target_count));
+        }
+        self.instance_mut(instance)?.weights = weights.to_vec();
+        Ok(())
+    }
+
+
+    // replaces the track of the weight of a target of an instance
+    pub fn animate_weight(
+        &mut self,
+        instance: usize,
+        target: usize,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        values: Vec<f32>,
+    )
+        -> Result<(), String>
+    {
+        self.instance_mut(instance)?.tracks.set_track(target, interpolation, easing, times, values)?;
+        self.update_duration();
+        Ok(())
+    }
+
+
+    // the instance keeps the weights it was last animated to
+    pub fn stop_animation(&mut self, instance: usize) -> Result<(), String>
+    {
+        let (time, target_count) = (self.timeline.local_time(), self.targets.len());
+        let instance = self.instance_mut(instance)?;
+        instance.weights = instance.tracks.sample(time, &instance.weights);
+        instance.tracks = MorphWeightTracks::new(target_count);
+        self.update_duration();
+        Ok(())
+    }
+
+
+    // the weights of every instance at the time of the timeline, target_count for each
+    pub fn weights(&self) -> Vec<f32>
+    {
+        let time = self.timeline.local_time();
+        self.instances.iter().flat_map(|instance| instance.tracks.sample(time, &instance.weights)).collect()
+    }
+
+
+    // uploads the weights and the scale, scaled down along x by the aspect like the circles
+    pub fn write(&self, backend: &B, aspect: f32)
+    {
+        backend.write_buffer(&self.weight_buffer, 0, self.weights().as_bytes());
+
+        let mut uniform_values = Vec::with_capacity(UNIFORMS_SIZE as usize);
+        uniform_values.extend_from_slice([SCALE / aspect, SCALE].as_bytes());
+        uniform_values.extend_from_slice([self.targets.len() as u32, self.targets.vertex_count() as u32].as_bytes());
+        backend.write_buffer(&self.uniform_buffer, 0, &uniform_values);
+    }
+
+
+    pub fn draw(&self, render_pass_encoder: &mut RenderPassEncoder<'_, B>)
+    {
+        render_pass_encoder.set_pipeline(&self.pipeline);
+        render_pass_encoder.set_bind_group(0, &self.bind_group);
+        render_pass_encoder.set_vertex_buffer(0, &self.vertex_buffer);
+        render_pass_encoder.set_index_buffer(&self.index_buffer, IndexFormat::Uint32);
+        render_pass_encoder.draw_indexed(self.index_count, self.instances.len() as u32);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::
+    {
+        Command, CommandEncoder, LoadOp, RenderPassColorAttachment, RenderPassDescriptor, StoreOp, TextureDescriptor,
+    };
+    use gpu_backend::recording::RecordingBackend;
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use software_gpu::SoftwareBackend;
+    use software_gpu::rasterizer::Image;
+
+    use super::*;
+
+
+    fn floats(data: &[u8]) -> Vec<f32>
+    {
+        data.chunks(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect()
+    }
+
+
+    #[test]
+    fn targets_reshape_the_rim()
+    {
+        let (positions, normals, indices) = create_blob_vertices();
+        assert_eq!((positions.len(), indices.len()), (33, 96));
+        let targets = create_morph_targets(&positions, &normals);
+
+        // the center stays where it is, the rim reaches the corners of the square
+        assert_eq!(targets.morph(0, positions[0], normals[0], &[1.0, 1.0]), (Vec3::ZERO, Vec3::Z));
+        let (corner, corner_normal) = targets.morph(5, positions[5], normals[5], &[0.0, 1.0]);
+        assert!(corner.abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-5));
+        assert!((corner_normal.length() - 1.0).abs() < 1e-5);
+        let (side, side_normal) = targets.morph(1, positions[1], normals[1], &[0.0, 1.0]);
+        assert!(side.abs_diff_eq(Vec3::X, 1e-6));
+        assert!(side_normal.abs_diff_eq(Vec3::new(0.8, 0.0, 0.6), 1e-6));
+
+        let (tip, _) = targets.morph(1, positions[1], normals[1], &[1.0, 0.0]);
+        let (notch, _) = targets.morph(2, positions[2], normals[2], &[1.0, 0.0]);
+        assert!(tip.length() > 1.5 && notch.length() < 0.75);
+    }
+
+
+    #[test]
+    fn weights_follow_the_tracks_of_every_instance()
+    {
+        let backend = RecordingBackend::new();
+        let mut mesh = MorphMesh::new(&backend, TextureFormat::Bgra8Unorm, MultisampleState::new());
+        mesh.timeline_mut().advance(3.0);
+        mesh.write(&backend, 2.0);
+        assert_eq!(floats(&backend.buffer_contents(&mesh.weight_buffer).unwrap()), [
+            1.0, 0.0, 0.0, 1.0, 0.0, 1.0,
+        ]);
+        let uniforms = backend.buffer_contents(&mesh.uniform_buffer).unwrap();
+        assert_eq!(floats(&uniforms[..8]), [0.075, 0.15]);
+        assert_eq!(&uniforms[8..], [2u32, 33].as_bytes());
+
+        // stopped instances keep their weights, untracked weights are set directly
+        mesh.stop_animation(2).unwrap();
+        mesh.set_weights(0, &[0.0, 0.5]).unwrap();
+        assert!(mesh.set_weights(0, &[1.0]).is_err());
+        assert!(mesh.set_weights(3, &[0.0, 0.0]).is_err());
+        mesh.timeline_mut().set_time(0.0);
+        assert_eq!(mesh.weights(), [0.0, 0.5, 0.0, 0.0, 0.0, 1.0]);
+
+        mesh.animate_weight(1, STAR, Interpolation::Step, Easing::Linear, vec![0.0, 4.0], vec![0.25, 1.0]).unwrap();
+        assert!(mesh.animate_weight(1, 2, Interpolation::Step, Easing::Linear, vec![0.0], vec![1.0]).is_err());
+        assert_eq!(mesh.timeline.duration(), 4.0);
+        assert_eq!(mesh.weights()[2..4], [0.25, 0.0]);
+
+        let mut command_encoder = CommandEncoder::new();
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&RenderPassDescriptor::new(vec![]));
+        mesh.draw(&mut render_pass_encoder);
+        render_pass_encoder.end();
+        backend.submit(vec![command_encoder.finish()]);
+        assert!(backend.commands().iter().any(|command| matches!(
+            command, Command::DrawIndexed { index_count: 96, instance_count: 3, .. },
+        )));
+    }
+
+
+    const SIZE: u32 = 128;
+
+
+    // draws just the blobs on the software gpu, with the vertices and weights the mesh holds
+    fn render(backend: &SoftwareBackend, mesh: &MorphMesh<SoftwareBackend>) -> Image
+    {
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [SIZE, SIZE], RENDER_ATTACHMENT,
+        ));
+        let mut color_attachment = RenderPassColorAttachment::new(
+            LoadOp::Clear, StoreOp::Store, &backend.create_view(&target),
+        );
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+
+        let mut command_encoder = CommandEncoder::new();
+        let render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        mesh.draw(&mut render_pass_encoder);
+        render_pass_encoder.end();
+        backend.submit(vec![command_encoder.finish()]);
+        backend.texture_image(&target).unwrap()
+    }
+
+
+    #[test]
+    fn morphing_matches_the_cpu_reference()
+    {
+        let backend = SoftwareBackend::new();
+        let mut mesh = MorphMesh::new(&backend, TextureFormat::Rgba8Unorm, MultisampleState::new());
+        // halfway through the pulses the third blob is part star and part square
+        mesh.timeline_mut().advance(0.5);
+        mesh.write(&backend, 1.0);
+        let morphed = render(&backend, &mesh);
+
+        // every blob morphed on the cpu, drawn with weights that leave it as it is
+        let (positions, normals, _) = create_blob_vertices();
+        let weights = mesh.weights();
+        backend.write_buffer(&mesh.weight_buffer, 0, vec![0.0f32; weights.len()].as_bytes());
+        let images = weights.chunks(mesh.targets.len())
+            .map(|weights|
+            {
+                let (positions, normals) = (0..positions.len())
+                    .map(|vertex| mesh.targets.morph(vertex, positions[vertex], normals[vertex], weights))
+                    .unzip::<Vec3, Vec3, Vec<_>, Vec<_>>();
+                backend.write_buffer(&mesh.vertex_buffer, 0, &encode_vertices(&positions, &normals));
+                render(&backend, &mesh)
+            })
+            .collect::<Vec<Image>>();
+
+        // every instance draws the same vertices, each one is taken from the third of the image around its blob
+        let pixels = (0..SIZE * SIZE)
+            .map(|i| (i % SIZE, i / SIZE))
+            .map(|(x, y)| images[(x * 3 / SIZE) as usize].pixel(x, y))
+            .collect();
+        let reference = Image::from_pixels(SIZE, SIZE, pixels).unwrap();
+        morphed.compare(&reference, 2).unwrap();
+
+        // and the weights did change the shapes
+        backend.write_buffer(&mesh.vertex_buffer, 0, &encode_vertices(&positions, &normals));
+        assert!(morphed.compare(&render(&backend, &mesh), 2).is_err());
+    }
+}
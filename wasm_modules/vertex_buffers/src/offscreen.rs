@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use gpu_backend::{Backend, TextureDescriptor, TextureFormat, Viewport};
+use gpu_backend::native::{WgpuBackend, write_png};
+use gpu_backend::texture_usage::{RENDER_ATTACHMENT, COPY_SRC};
+
+use math::SceneRng;
+
+use crate::renderer::Renderer;
+
+
+// renders one frame of the scene a seed generates without a browser and saves it as a png, fails if the machine
+// has no gpu adapter
+pub fn render_to_png(path: &Path, width: u32, height: u32, sample_count: u32, seed: u32) -> Result<(), String>
+{
+    let backend = WgpuBackend::new()?;
+    let viewport = Viewport::new(&backend, width as f64, height as f64, 1.0);
+    let mut renderer = Renderer::new(
+        backend.clone(), TextureFormat::Rgba8Unorm, sample_count, false, viewport, SceneRng::new(seed),
+    )?;
+
+    let texture = backend.create_texture(&TextureDescriptor::new(
+        TextureFormat::Rgba8Unorm, [width, height], RENDER_ATTACHMENT | COPY_SRC,
+    ));
+    renderer.render(&texture);
+
+    // the canvas is configured with the default opaque alpha mode, so alpha never shows in the browser
+    let mut pixels = backend.read_texture(&texture)?;
+    pixels.chunks_mut(4).for_each(|pixel| pixel[3] = 255);
+    write_png(path, width, height, &pixels)
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::capture::CaptureBackend;
+    use gpu_backend::trace::Trace;
+
+    use super::*;
+
+
+    // run with cargo test --features wgpu -- --ignored on a machine with an adapter
+    #[test]
+    #[ignore = "needs a gpu adapter"]
+    fn a_replayed_trace_renders_the_captured_frame()
+    {
+        let device = WgpuBackend::new().unwrap();
+
+        let backend = CaptureBackend::new(device.clone());
+        let viewport = Viewport::new(&backend, 64.0, 48.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, 4, false, viewport, SceneRng::new(1),
+        ).unwrap();
+        let texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [64, 48], RENDER_ATTACHMENT | COPY_SRC,
+        ));
+        renderer.render(&texture);
+        backend.start_capture();
+        renderer.render(&texture);
+        let trace = Trace::from_json(&backend.finish_capture().to_json().unwrap()).unwrap();
+
+        // the replay creates all of its objects again, nothing is shared with the captured frame
+        let replay = trace.replay(&device).unwrap();
+        let replayed = device.read_texture(&replay.texture(&texture.handle).unwrap()).unwrap();
+        assert_eq!(replayed, device.read_texture(&texture.inner).unwrap());
+        assert!(replayed.chunks(4).any(|pixel| pixel != &replayed[..4]));
+    }
+}
@@ -0,0 +1,1205 @@
+use std::collections::HashMap;
+
+use gpu_backend::
+{
+    Backend, AsBytes, BufferDescriptor, GrowableBuffer, ShaderModuleDescriptor, VertexState, VertexAttribute,
+    VertexFormat, VertexBufferLayout, ColorTargetState, FragmentState, RenderPipelineDescriptor,
+    ComputePipelineDescriptor, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor, BindGroupLayoutEntry,
+    BufferBindingType, RenderPassDescriptor, CommandEncoder, IndexFormat, TextureFormat, Viewport, MultisampleTarget,
+};
+use gpu_backend::buffer_usage::{COPY_DST, VERTEX, INDEX, UNIFORM, STORAGE, INDIRECT};
+use gpu_backend::shader_stage::COMPUTE;
+
+use animation::{Easing, Interpolation};
+
+use frame_loop::{FrameUniforms, FRAME_GROUP, FRAME_UNIFORMS_SIZE, with_frame_prelude, uses_frame};
+
+use math::{Frustum, Mat4, SceneRng, Vec3};
+
+use scene_graph::{SceneGraph, NodeId, Transform, Attachment, MeshId, MeshBounds};
+
+use crate::morph_mesh::MorphMesh;
+use crate::skinned_mesh::SkinnedMesh;
+
+
+fn create_circle_vertices(radius: Option<f32>, inner_radius: Option<f32>) -> (Vec<u8>, Vec<u32>, u32)
+{
+    let radius = radius.unwrap_or(1f32);
+    let num_subdivisions = 24;
+    let inner_radius = inner_radius.unwrap_or(0f32);
+    let start_angle = 0f32;
+    let end_angle = std::f32::consts::PI * 2.0;
+
+    // 2 vertices at each subdivision, + 1 to wrap around the circle.
+    let num_vertices = (num_subdivisions + 1) * 2;
+
+    // 2 32-bit values for position (xy) and 1 32-bit value for color (rgb_)
+    // The 32-bit color value will be written/read as 4 8-bit values
+    let mut vertex_data = Vec::with_capacity(num_vertices as usize * (2 + 1) * 4);
+
+    let mut add_vertex = |x: f32, y: f32, r: f32, g: f32, b: f32|
+        {
+            vertex_data.extend_from_slice([x, y].as_bytes());
+            vertex_data.extend_from_slice(&[(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 0]);
+        };
+
+    let inner_color = [1.0, 1.0, 1.0];
+    let outer_color = [0.1, 0.1, 0.1];
+
+    // 2 vertices per subdivision
+    //
+    // 0  2  4  6  8 ...
+    //
+    // 1  3  5  7  9 ...
+    for i in 0..=num_subdivisions
+    {
+        let angle = start_angle + i as f32 * (end_angle - start_angle) / num_subdivisions as f32;
+
+        let c1 = angle.cos();
+        let s1 = angle.sin();
+
+        add_vertex(c1 * radius, s1 * radius, outer_color[0], outer_color[1], outer_color[2]);
+        add_vertex(c1 * inner_radius, s1 * inner_radius, inner_color[0], inner_color[1], inner_color[2]);
+    }
+
+    let mut index_data = Vec::with_capacity(num_subdivisions as usize * 6);
+
+    // 0---2---4---...
+    // | //| //|
+    // |// |// |//
+    // 1---3-- 5---...
+    for i in 0..num_subdivisions
+    {
+        let ndx_offset = i * 2;
+
+        // first triangle
+        index_data.extend_from_slice(&[ndx_offset, ndx_offset + 1, ndx_offset + 2]);
+
+        // second triangle
+        index_data.extend_from_slice(&[ndx_offset + 2, ndx_offset + 1, ndx_offset + 3]);
+    }
+
+    let num_indexes = index_data.len() as u32;
+
+    (vertex_data, index_data, num_indexes)
+}
+
+
+// the one mesh of the scene
+const CIRCLE: MeshId = MeshId(0);
+
+
+// color is a vec4f, offset and scale are vec2fs
+const INSTANCE_SIZE: u64 = 4 * 4 + 2 * 4 + 2 * 4;
+
+// the 6 planes of the view, the bounding sphere, the center and extents of the bounding box, all vec4fs,
+// then the instance count padded to a vec4
+const CULLING_SIZE: u64 = (6 + 3 + 1) * 4 * 4;
+
+// index_count, instance_count, first_index, base_vertex and first_instance
+const DRAW_ARGS_SIZE: u64 = 5 * 4;
+
+// the view matrix, a mat4x4f
+const VIEW_SIZE: u64 = 16 * 4;
+
+// the instances every workgroup of the cull passes takes, as in cull.wgsl
+const CULL_WORKGROUP_SIZE: u64 = 64;
+
+
+// every circle is an object node placed at its offset, with a child node drawing the circle at its scale
+struct ObjectInfo
+{
+    color: [f32; 4],
+    scale: f32,
+}
+
+
+pub struct Renderer<B: Backend>
+{
+    backend: B,
+    vertex_buffer: B::Buffer,
+    instance_buffer: GrowableBuffer<B>,
+    visible_instance_buffer: GrowableBuffer<B>,
+    // a count for every workgroup of the cull passes
+    workgroup_count_buffer: GrowableBuffer<B>,
+    culling_uniform_buffer: B::Buffer,
+    draw_args_buffer: B::Buffer,
+    index_buffer: B::Buffer,
+    // moves and zooms the circles, the cull pass culls against it as well
+    view: Mat4,
+    view_uniform_buffer: B::Buffer,
+    // the pipelines of the cull passes share the bind group of its layout
+    cull_bind_group_layout: B::BindGroupLayout,
+    count_pipeline: B::ComputePipeline,
+    scan_pipeline: B::ComputePipeline,
+    compact_pipeline: B::ComputePipeline,
+    max_workgroups_per_dimension: u32,
+    cull_bind_group: B::BindGroup,
+    render_pipeline: B::RenderPipeline,
+    render_bind_group: B::BindGroup,
+    frame_uniform_buffer: B::Buffer,
+    // only when the render shader uses the frame uniforms, the "auto" layout has no group for them otherwise
+    frame_bind_group: Option<B::BindGroup>,
+    // random instances added later come from it too, so a seed reproduces them as well
+    rng: SceneRng,
+    scene_graph: SceneGraph,
+    // the object node of every instance, by the id handed out for it
+    instances: HashMap<u32, NodeId>,
+    next_instance_id: u32,
+    // by circle node
+    object_infos: HashMap<NodeId, ObjectInfo>,
+    // instances were added or removed or changed their color since the last upload
+    instances_changed: bool,
+    // the aspect the circles are scaled for
+    scaled_aspect: f32,
+    mesh_bounds: HashMap<MeshId, MeshBounds>,
+    instance_values: Vec<u8>,
+    multisample_target: MultisampleTarget<B>,
+    viewport: Viewport,
+    // both drawn over the circles
+    morph_mesh: MorphMesh<B>,
+    skinned_mesh: SkinnedMesh<B>,
+}
+
+
+impl<B: Backend> Renderer<B>
+{
+    pub fn new(
+        backend: B,
+        texture_format: TextureFormat,
+        sample_count: u32,
+        alpha_to_coverage: bool,
+        viewport: Viewport,
+        rng: SceneRng,
+    )
+        -> Result<Self, String>
+    {
+        let multisample_target = MultisampleTarget::new(texture_format, sample_count, alpha_to_coverage)?;
+
+        let render_source = with_frame_prelude(include_str!("../shader/render.wgsl"));
+        let mut render_shader_module_descriptor = ShaderModuleDescriptor::new(&render_source);
+        render_shader_module_descriptor.label("triangle shaders with vertex buffers");
+        let render_shader_module = backend.create_shader_module(&render_shader_module_descriptor);
+
+        let mut vertex_state = VertexState::new("vertex_main", &render_shader_module);
+
+        // the instances are read from storage, through the indices the cull pass leaves of them
+        let vertex_position_buffer_layout = VertexBufferLayout::new(
+            2 * 4 + 4,    // 2 floats, 4 bytes each + 4 bytes
+            &[
+                VertexAttribute::new(VertexFormat::Float32x2, 0, 0),    // position
+                VertexAttribute::new(VertexFormat::Unorm8x4, 8, 1),     // per vertex color
+            ],
+        );
+
+        vertex_state.buffers(&[vertex_position_buffer_layout]);
+
+        let fragment_state = FragmentState::new(
+            "fragment_main", &render_shader_module, &[ColorTargetState::new(texture_format)],
+        );
+
+        let mut render_pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        render_pipeline_descriptor
+            .label("triangle with vertex buffers")
+            .fragment(fragment_state)
+            .multisample(multisample_target.multisample_state());
+        let render_pipeline = backend.create_render_pipeline(&render_pipeline_descriptor);
+
+        let mut frame_uniform_buffer_descriptor = BufferDescriptor::new(FRAME_UNIFORMS_SIZE, UNIFORM | COPY_DST);
+        frame_uniform_buffer_descriptor.label("frame uniforms");
+        let frame_uniform_buffer = backend.create_buffer(&frame_uniform_buffer_descriptor);
+
+        let frame_bind_group = uses_frame(&render_source).then(||
+            {
+                let mut frame_bind_group_descriptor = BindGroupDescriptor::new(
+                    vec![BindGroupEntry::buffer(0, &frame_uniform_buffer)],
+                    &backend.render_bind_group_layout(&render_pipeline, FRAME_GROUP),
+                );
+                frame_bind_group_descriptor.label("bind group for the frame uniforms");
+                backend.create_bind_group(&frame_bind_group_descriptor)
+            });
+
+        let mut cull_shader_module_descriptor = ShaderModuleDescriptor::new(include_str!("../shader/cull.wgsl"));
+        cull_shader_module_descriptor.label("frustum culling of the instances");
+        let cull_shader_module = backend.create_shader_module(&cull_shader_module_descriptor);
+
+        let mut cull_bind_group_layout_descriptor = BindGroupLayoutDescriptor::new(&[
+            BindGroupLayoutEntry::buffer(0, COMPUTE, BufferBindingType::Uniform),
+            BindGroupLayoutEntry::buffer(1, COMPUTE, BufferBindingType::ReadOnlyStorage),
+            BindGroupLayoutEntry::buffer(2, COMPUTE, BufferBindingType::Storage),
+            BindGroupLayoutEntry::buffer(3, COMPUTE, BufferBindingType::Storage),
+            BindGroupLayoutEntry::buffer(4, COMPUTE, BufferBindingType::Storage),
+        ]);
+        cull_bind_group_layout_descriptor.label("bind group layout for culling");
+        let cull_bind_group_layout = backend.create_bind_group_layout(&cull_bind_group_layout_descriptor);
+
+        let mut count_pipeline_descriptor = ComputePipelineDescriptor::new("count_main", &cull_shader_module);
+        count_pipeline_descriptor
+            .label("counting the visible instances")
+            .bind_group_layouts(std::slice::from_ref(&cull_bind_group_layout));
+        let count_pipeline = backend.create_compute_pipeline(&count_pipeline_descriptor);
+
+        let mut scan_pipeline_descriptor = ComputePipelineDescriptor::new("scan_main", &cull_shader_module);
+        scan_pipeline_descriptor
+            .label("scanning the visible instance counts")
+            .bind_group_layouts(std::slice::from_ref(&cull_bind_group_layout));
+        let scan_pipeline = backend.create_compute_pipeline(&scan_pipeline_descriptor);
+
+        let mut compact_pipeline_descriptor = ComputePipelineDescriptor::new("compact_main", &cull_shader_module);
+        compact_pipeline_descriptor
+            .label("compacting the visible instances")
+            .bind_group_layouts(std::slice::from_ref(&cull_bind_group_layout));
+        let compact_pipeline = backend.create_compute_pipeline(&compact_pipeline_descriptor);
+        let max_workgroups_per_dimension = backend.limits().max_compute_workgroups_per_dimension;
+
+        let k_num_objects: u64 = 100;
+
+        // both grow with the instances, see reserve_instances
+        let mut instance_buffer_descriptor = BufferDescriptor::new(INSTANCE_SIZE * k_num_objects, STORAGE | COPY_DST);
+        instance_buffer_descriptor.label("per instance storage for objects");
+        let instance_buffer = GrowableBuffer::new(&backend, &instance_buffer_descriptor);
+
+        let mut visible_instance_buffer_descriptor = BufferDescriptor::new(4 * k_num_objects, STORAGE);
+        visible_instance_buffer_descriptor.label("indices of the visible instances");
+        let visible_instance_buffer = GrowableBuffer::new(&backend, &visible_instance_buffer_descriptor);
+
+        let mut workgroup_count_buffer_descriptor = BufferDescriptor::new(
+            4 * k_num_objects.div_ceil(CULL_WORKGROUP_SIZE), STORAGE,
+        );
+        workgroup_count_buffer_descriptor.label("visible instances of every cull workgroup");
+        let workgroup_count_buffer = GrowableBuffer::new(&backend, &workgroup_count_buffer_descriptor);
+
+        let mut culling_uniform_buffer_descriptor = BufferDescriptor::new(CULLING_SIZE, UNIFORM | COPY_DST);
+        culling_uniform_buffer_descriptor.label("uniforms for culling");
+        let culling_uniform_buffer = backend.create_buffer(&culling_uniform_buffer_descriptor);
+
+        let mut view_uniform_buffer_descriptor = BufferDescriptor::new(VIEW_SIZE, UNIFORM | COPY_DST);
+        view_uniform_buffer_descriptor.label("view matrix");
+        let view_uniform_buffer = backend.create_buffer(&view_uniform_buffer_descriptor);
+
+        // setup a storage buffer with vertex data
+        let (vertex_data, index_data, num_indexes) =
+            create_circle_vertices(Some(0.5), Some(0.25));
+
+        let positions = vertex_data.chunks(12)
+            .map(|vertex| Vec3::new(
+                f32::from_le_bytes(vertex[0..4].try_into().unwrap()),
+                f32::from_le_bytes(vertex[4..8].try_into().unwrap()),
+                0.0,
+            ))
+            .collect::<Vec<_>>();
+        let mesh_bounds = HashMap::from([(CIRCLE, MeshBounds::from_points(positions).unwrap())]);
+
+        let mut vertex_buffer_descriptor = BufferDescriptor::new(
+            vertex_data.len() as u64,
+            VERTEX | COPY_DST,
+        );
+        vertex_buffer_descriptor.label("vertex buffer vertices");
+        let vertex_buffer = backend.create_buffer(&vertex_buffer_descriptor);
+        backend.write_buffer(&vertex_buffer, 0, &vertex_data);
+
+        let mut index_buffer_descriptor = BufferDescriptor::new(
+            index_data.as_bytes().len() as u64,
+            INDEX | COPY_DST,
+        );
+        index_buffer_descriptor.label("index buffer");
+        let index_buffer = backend.create_buffer(&index_buffer_descriptor);
+        backend.write_buffer(&index_buffer, 0, index_data.as_bytes());
+
+        // the cull pass only ever writes the instance count, the rest of the arguments stay as they are
+        let mut draw_args_buffer_descriptor = BufferDescriptor::new(DRAW_ARGS_SIZE, STORAGE | INDIRECT | COPY_DST);
+        draw_args_buffer_descriptor.label("draw indexed indirect arguments");
+        let draw_args_buffer = backend.create_buffer(&draw_args_buffer_descriptor);
+        backend.write_buffer(&draw_args_buffer, 0, [num_indexes, 0, 0, 0, 0].as_bytes());
+
+        let (cull_bind_group, render_bind_group) = Renderer::create_bind_groups(
+            &backend, &cull_bind_group_layout, &render_pipeline, &culling_uniform_buffer, instance_buffer.buffer(),
+            visible_instance_buffer.buffer(), &draw_args_buffer, workgroup_count_buffer.buffer(), &view_uniform_buffer,
+        );
+
+        let morph_mesh = MorphMesh::new(&backend, texture_format, multisample_target.multisample_state());
+        let skinned_mesh = SkinnedMesh::new(&backend, texture_format, multisample_target.multisample_state());
+
+        let scaled_aspect = viewport.aspect();
+        let mut renderer = Renderer
+        {
+            backend, vertex_buffer, instance_buffer, visible_instance_buffer, workgroup_count_buffer,
+            culling_uniform_buffer, draw_args_buffer, index_buffer, view: Mat4::IDENTITY, view_uniform_buffer,
+            cull_bind_group_layout, count_pipeline, scan_pipeline, compact_pipeline, max_workgroups_per_dimension,
+            cull_bind_group, render_pipeline, render_bind_group, frame_uniform_buffer, frame_bind_group, rng,
+            scene_graph: SceneGraph::new(), instances: HashMap::new(), next_instance_id: 0,
+            object_infos: HashMap::new(), instances_changed: true, scaled_aspect, mesh_bounds,
+            instance_values: Vec::new(), multisample_target, viewport, morph_mesh,
+            skinned_mesh,
+        };
+        renderer.add_random_instances(k_num_objects as u32);
+        Ok(renderer)
+    }
+
+
+    // the bind groups have to be created again whenever the instance buffers are reallocated
+    #[allow(clippy::too_many_arguments)]
+    fn create_bind_groups(
+        backend: &B,
+        cull_bind_group_layout: &B::BindGroupLayout,
+        render_pipeline: &B::RenderPipeline,
+        culling_uniform_buffer: &B::Buffer,
+        instance_buffer: &B::Buffer,
+        visible_instance_buffer: &B::Buffer,
+        draw_args_buffer: &B::Buffer,
+        workgroup_count_buffer: &B::Buffer,
+        view_uniform_buffer: &B::Buffer,
+    )
+        -> (B::BindGroup, B::BindGroup)
+    {
+        let mut cull_bind_group_descriptor = BindGroupDescriptor::new(
+            vec![
+                BindGroupEntry::buffer(0, culling_uniform_buffer),
+                BindGroupEntry::buffer(1, instance_buffer),
+                BindGroupEntry::buffer(2, visible_instance_buffer),
+                BindGroupEntry::buffer(3, draw_args_buffer),
+                BindGroupEntry::buffer(4, workgroup_count_buffer),
+            ],
+            cull_bind_group_layout,
+        );
+        cull_bind_group_descriptor.label("bind group for culling");
+        let cull_bind_group = backend.create_bind_group(&cull_bind_group_descriptor);
+
+        let mut render_bind_group_descriptor = BindGroupDescriptor::new(
+            vec![
+                BindGroupEntry::buffer(0, instance_buffer),
+                BindGroupEntry::buffer(1, visible_instance_buffer),
+                BindGroupEntry::buffer(2, view_uniform_buffer),
+            ],
+            &backend.render_bind_group_layout(render_pipeline, 0),
+        );
+        render_bind_group_descriptor.label("bind group for instances");
+        let render_bind_group = backend.create_bind_group(&render_bind_group_descriptor);
+
+        (cull_bind_group, render_bind_group)
+    }
+
+
+    pub fn backend(&self) -> &B
+    {
+        &self.backend
+    }
+
+
+    pub fn set_viewport(&mut self, viewport: Viewport)
+    {
+        self.viewport = viewport;
+    }
+
+
+    // applied to the positions of the circles, they are in clip space with the identity view
+    pub fn set_view(&mut self, view: Mat4)
+    {
+        self.view = view;
+    }
+
+
+    // the seed the instances were generated with
+    pub fn seed(&self) -> u32
+    {
+        self.rng.seed()
+    }
+
+
+    // the color, offset and scale of every instance in the order they are drawn, 7 floats each
+    pub fn object_parameters(&self) -> Vec<f32>
+    {
+        self.scene_graph.roots().iter()
+            .flat_map(|object|
+            {
+                let translation = self.scene_graph.transform(*object).unwrap().translation;
+                let object_info = &self.object_infos[&self.scene_graph.children(*object)[0]];
+                [object_info.color.as_slice(), &[translation.x, translation.y, object_info.scale]].concat()
+            })
+            .collect()
+    }
+
+
+    pub fn instance_count(&self) -> usize
+    {
+        self.instances.len()
+    }
+
+
+    // returns the id of the instance, to update or remove it with
+    pub fn add_instance(&mut self, color: [f32; 4], offset: [f32; 2], scale: f32) -> u32
+    {
+        let id = self.next_instance_id;
+        self.next_instance_id += 1;
+
+        let translation = Vec3::new(offset[0], offset[1], 0.0);
+        let object = self.scene_graph
+            .add_node(&format!("object {}", id), None, Transform::from_translation(translation))
+            .unwrap();
+        let circle = self.scene_graph.add_node("circle", Some(object), self.circle_transform(scale)).unwrap();
+        self.scene_graph.attach(circle, Attachment::Mesh(CIRCLE)).unwrap();
+
+        self.object_infos.insert(circle, ObjectInfo { color, scale });
+        self.instances.insert(id, object);
+        self.instances_changed = true;
+        id
+    }
+
+
+    // adds instances with random colors, offsets and scales, e.g. to find out how many a device can draw
+    pub fn add_random_instances(&mut self, count: u32) -> Vec<u32>
+    {
+        (0..count)
+            .map(|_|
+            {
+                let rng = &mut self.rng;
+                let color = [rng.rand(None, None), rng.rand(None, None), rng.rand(None, None), 1.0];
+                let offset = [rng.rand(Some(-0.9), Some(0.9)), rng.rand(Some(-0.9), Some(0.9))];
+                let scale = rng.rand(Some(0.2), Some(0.5));
+                self.add_instance(color, offset, scale)
+            })
+            .collect()
+    }
+
+
+    fn object(&self, id: u32) -> Result<(NodeId, NodeId), String>
+    {
+        let object = *self.instances.get(&id).ok_or_else(|| format!("there is no instance {}", id))?;
+        Ok((object, self.scene_graph.children(object)[0]))
+    }
+
+
+    pub fn update_instance(&mut self, id: u32, color: [f32; 4], offset: [f32; 2], scale: f32) -> Result<(), String>
+    {
+        let (object, circle) = self.object(id)?;
+        self.scene_graph.transform_mut(object).unwrap().translation = Vec3::new(offset[0], offset[1], 0.0);
+        self.scene_graph.set_transform(circle, self.circle_transform(scale))?;
+
+        let object_info = self.object_infos.get_mut(&circle).unwrap();
+        self.instances_changed |= object_info.color != color;
+        *object_info = ObjectInfo { color, scale };
+        Ok(())
+    }
+
+
+    // the color, offset and scale of an instance
+    pub fn instance(&self, id: u32) -> Result<([f32; 4], [f32; 2], f32), String>
+    {
+        let (object, circle) = self.object(id)?;
+        let translation = self.scene_graph.transform(object).unwrap().translation;
+        let object_info = &self.object_infos[&circle];
+        Ok((object_info.color, [translation.x, translation.y], object_info.scale))
+    }
+
+
+    pub fn remove_instance(&mut self, id: u32) -> Result<(), String>
+    {
+        let (object, circle) = self.object(id)?;
+        self.scene_graph.remove_node(object)?;
+        self.object_infos.remove(&circle);
+        self.instances.remove(&id);
+        self.instances_changed = true;
+        Ok(())
+    }
+
+
+    pub fn clear_instances(&mut self)
+    {
+        self.scene_graph = SceneGraph::new();
+        self.object_infos.clear();
+        self.instances.clear();
+        self.instances_changed = true;
+    }
+
+
+    // the weights of the morph targets of a blob that aren't animated, one for every target
+    pub fn set_morph_weights(&mut self, blob: usize, weights: &[f32]) -> Result<(), String>
+    {
+        self.morph_mesh.set_weights(blob, weights)
+    }
+
+
+    pub fn animate_morph_weight(
+        &mut self,
+        blob: usize,
+        target: usize,
+        interpolation: Interpolation,
+        easing: Easing,
+        times: Vec<f32>,
+        values: Vec<f32>,
+    )
+        -> Result<(), String>
+    {
+        self.morph_mesh.animate_weight(blob, target, interpolation, easing, times, values)
+    }
+
+
+    pub fn stop_morph_animation(&mut self, blob: usize) -> Result<(), String>
+    {
+        self.morph_mesh.stop_animation(blob)
+    }
+
+
+    // the circles are scaled down along x by the aspect, so they stay round on wide canvases
+    fn circle_transform(&self, scale: f32) -> Transform
+    {
+        Transform::from_scale(Vec3::new(scale / self.scaled_aspect, scale, 1.0))
+    }
+
+
+    // rescales the circles if the aspect changed, returns whether any world matrix changed
+    fn update_scene_graph(&mut self) -> bool
+    {
+        let aspect = self.viewport.aspect();
+        if aspect != self.scaled_aspect
+        {
+            self.scaled_aspect = aspect;
+            for (circle, object_info) in &self.object_infos
+            {
+                let transform = Transform::from_scale(Vec3::new(object_info.scale / aspect, object_info.scale, 1.0));
+                self.scene_graph.set_transform(*circle, transform).unwrap();
+            }
+        }
+        self.scene_graph.update_world_matrices() > 0
+    }
+
+
+    // makes room for every instance, returns whether a buffer was reallocated. the buffers are never empty,
+    // storage bindings can't be
+    fn reserve_instances(&mut self) -> bool
+    {
+        let instance_count = (self.instances.len() as u64).max(1);
+        let instances_grown = self.instance_buffer.reserve(&self.backend, INSTANCE_SIZE * instance_count);
+        let visible_grown = self.visible_instance_buffer.reserve(&self.backend, 4 * instance_count);
+        // sized for every instance there is room for, so it grows along with the instance buffer
+        let workgroup_count = (self.instance_buffer.size() / INSTANCE_SIZE).div_ceil(CULL_WORKGROUP_SIZE);
+        let counts_grown = self.workgroup_count_buffer.reserve(&self.backend, 4 * workgroup_count);
+        if instances_grown || visible_grown || counts_grown
+        {
+            (self.cull_bind_group, self.render_bind_group) = Renderer::create_bind_groups(
+                &self.backend, &self.cull_bind_group_layout, &self.render_pipeline, &self.culling_uniform_buffer,
+                self.instance_buffer.buffer(), self.visible_instance_buffer.buffer(), &self.draw_args_buffer,
+                self.workgroup_count_buffer.buffer(), &self.view_uniform_buffer,
+            );
+        }
+        instances_grown
+    }
+
+
+    // flattens every circle of the scene graph into the instance storage buffer
+    fn write_instances(&mut self)
+    {
+        let instances = self.scene_graph.mesh_instances();
+        self.instance_values.clear();
+        for instance in &instances
+        {
+            let world = instance.world;
+            let color = self.object_infos[&instance.node].color;
+            let offset = [world.cols[3].x, world.cols[3].y];
+            let scale = [world.cols[0].x, world.cols[1].y];
+            self.instance_values.extend_from_slice(color.as_bytes());
+            self.instance_values.extend_from_slice(offset.as_bytes());
+            self.instance_values.extend_from_slice(scale.as_bytes());
+        }
+        self.backend.write_buffer(self.instance_buffer.buffer(), 0, &self.instance_values);
+    }
+
+
+    fn write_culling_uniforms(&self, instance_count: u32)
+    {
+        let frustum = Frustum::from_matrix(&self.view);
+        let bounds = self.mesh_bounds[&CIRCLE];
+
+        let mut culling_values = Vec::with_capacity(CULLING_SIZE as usize);
+        for plane in &frustum.planes
+        {
+            culling_values.extend_from_slice(plane.normal.extend(plane.distance).as_array().as_bytes());
+        }
+        culling_values.extend_from_slice(bounds.sphere.center.extend(bounds.sphere.radius).as_array().as_bytes());
+        culling_values.extend_from_slice(bounds.aabb.center().extend(0.0).as_array().as_bytes());
+        culling_values.extend_from_slice(bounds.aabb.extents().extend(0.0).as_array().as_bytes());
+        culling_values.extend_from_slice([instance_count, 0, 0, 0].as_bytes());
+        self.backend.write_buffer(&self.culling_uniform_buffer, 0, &culling_values);
+    }
+
+
+    // renders with the time, resolution and mouse of an animation frame
+    pub fn render_frame(&mut self, target: &B::Texture, frame_uniforms: &FrameUniforms)
+    {
+        self.backend.write_buffer(&self.frame_uniform_buffer, 0, &frame_uniforms.to_bytes());
+        self.morph_mesh.timeline_mut().advance(frame_uniforms.delta_time);
+        self.skinned_mesh.timeline_mut().advance(frame_uniforms.delta_time);
+        self.render(target);
+    }
+
+
+    pub fn render(&mut self, target: &B::Texture)
+    {
+        // with many instances flattening them is the expensive part, it's skipped while nothing changed
+        let moved = self.update_scene_graph();
+        let grown = self.reserve_instances();
+        if std::mem::take(&mut self.instances_changed) || moved || grown
+        {
+            self.write_instances();
+        }
+        self.write_culling_uniforms(self.instances.len() as u32);
+        self.backend.write_buffer(&self.view_uniform_buffer, 0, self.view.as_array().as_bytes());
+        self.morph_mesh.write(&self.backend, self.viewport.aspect());
+        self.skinned_mesh.write(&self.backend, self.viewport.aspect());
+
+        let mut color_attachment = self.multisample_target.color_attachment(&self.backend, target);
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+        let mut render_pass_descriptor = RenderPassDescriptor::new(vec![color_attachment]);
+        render_pass_descriptor.label("basic canvas render pass");
+
+        let mut command_encoder = CommandEncoder::new();
+        command_encoder.set_label("command encoder");
+
+        // the workgroups count their visible instances, one workgroup scans the counts into where the instances of
+        // every workgroup start and writes the instance count, then the workgroups compact them, see cull.wgsl.
+        // there is always one, and more than fit in x are dispatched in rows of them
+        let workgroup_count = (self.instances.len() as u64).div_ceil(CULL_WORKGROUP_SIZE).max(1) as u32;
+        let x = workgroup_count.min(self.max_workgroups_per_dimension);
+        let y = workgroup_count.div_ceil(x);
+        let mut compute_pass_encoder = command_encoder.begin_compute_pass(Some("cull pass"));
+        compute_pass_encoder.set_bind_group(0, &self.cull_bind_group);
+        compute_pass_encoder.set_pipeline(&self.count_pipeline);
+        compute_pass_encoder.dispatch_workgroups(x, y, 1);
+        compute_pass_encoder.set_pipeline(&self.scan_pipeline);
+        compute_pass_encoder.dispatch_workgroups(1, 1, 1);
+        compute_pass_encoder.set_pipeline(&self.compact_pipeline);
+        compute_pass_encoder.dispatch_workgroups(x, y, 1);
+        compute_pass_encoder.end();
+
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&render_pass_descriptor);
+        render_pass_encoder.set_pipeline(&self.render_pipeline);
+        render_pass_encoder.set_bind_group(0, &self.render_bind_group);
+        if let Some(frame_bind_group) = &self.frame_bind_group
+        {
+            render_pass_encoder.set_bind_group(FRAME_GROUP, frame_bind_group);
+        }
+        render_pass_encoder.set_vertex_buffer(0, &self.vertex_buffer);
+        render_pass_encoder.set_index_buffer(&self.index_buffer, IndexFormat::Uint32);
+        render_pass_encoder.draw_indexed_indirect(&self.draw_args_buffer, 0);
+        self.morph_mesh.draw(&mut render_pass_encoder);
+        self.skinned_mesh.draw(&mut render_pass_encoder);
+
+        render_pass_encoder.end();
+
+        let command_buffer = command_encoder.finish();
+        self.backend.submit(vec![command_buffer]);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::{Command, Limits, TextureDescriptor};
+    use gpu_backend::recording::{Call, RecordingBackend};
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use software_gpu::{SoftwareBackend, assert_golden};
+
+    use super::*;
+
+
+    fn renderer(sample_count: u32) -> (Renderer<RecordingBackend>, <RecordingBackend as Backend>::Texture)
+    {
+        let backend = RecordingBackend::new();
+        let canvas_texture = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 300.0, 150.0, 1.0);
+        let renderer = Renderer::new(
+            backend.clone(), TextureFormat::Bgra8Unorm, sample_count, false, viewport, SceneRng::new(1),
+        ).unwrap();
+        (renderer, canvas_texture)
+    }
+
+
+    fn floats(data: &[u8]) -> Vec<f32>
+    {
+        data.chunks(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect()
+    }
+
+
+    #[test]
+    fn render_culls_on_the_gpu_then_draws_indirectly()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        renderer.render(&canvas_texture);
+
+        let commands = renderer.backend().commands();
+        // a workgroup for every 64 of the 100 instances
+        assert!(matches!(&commands[..9], [
+            Command::BeginComputePass { .. },
+            Command::SetBindGroup { index: 0, bind_group, .. },
+            Command::SetComputePipeline(count_pipeline),
+            Command::DispatchWorkgroups { x: 2, y: 1, z: 1 },
+            Command::SetComputePipeline(scan_pipeline),
+            Command::DispatchWorkgroups { x: 1, y: 1, z: 1 },
+            Command::SetComputePipeline(compact_pipeline),
+            Command::DispatchWorkgroups { x: 2, y: 1, z: 1 },
+            Command::EndComputePass,
+        ] if *bind_group == renderer.cull_bind_group && *count_pipeline == renderer.count_pipeline
+            && *scan_pipeline == renderer.scan_pipeline && *compact_pipeline == renderer.compact_pipeline));
+
+        let draws = commands.iter()
+            .filter(|command| matches!(
+                command, Command::Draw { .. } | Command::DrawIndexed { .. } | Command::DrawIndexedIndirect { .. },
+            ))
+            .collect::<Vec<_>>();
+        // the circles, then the morphing blobs and the skinned tentacle over them
+        assert!(matches!(draws[..], [
+            Command::DrawIndexedIndirect { buffer, offset: 0 },
+            Command::DrawIndexed { index_count: 96, instance_count: 3, .. },
+            Command::DrawIndexed { index_count: 144, instance_count: 1, .. },
+        ] if *buffer == renderer.draw_args_buffer));
+
+        // the cull pass fills in the instance count
+        let draw_args = renderer.backend().buffer_contents(&renderer.draw_args_buffer).unwrap();
+        assert_eq!(draw_args, [144u32, 0, 0, 0, 0].as_bytes());
+    }
+
+
+    fn instances(data: &[u8]) -> Vec<([f32; 4], [f32; 2], [f32; 2])>
+    {
+        data.chunks(INSTANCE_SIZE as usize)
+            .map(floats)
+            .map(|unit| ([unit[0], unit[1], unit[2], unit[3]], [unit[4], unit[5]], [unit[6], unit[7]]))
+            .collect()
+    }
+
+
+    // the uploads of instances since the calls were last taken
+    fn instance_writes(renderer: &Renderer<RecordingBackend>) -> Vec<Vec<u8>>
+    {
+        renderer.backend().take_calls().into_iter()
+            .filter_map(|call| match call
+            {
+                Call::WriteBuffer { buffer, offset: 0, data } if buffer == *renderer.instance_buffer.buffer() =>
+                {
+                    Some(data)
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+
+    #[test]
+    fn render_uploads_scales_corrected_for_the_aspect()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        renderer.backend().take_calls();
+        renderer.render(&canvas_texture);
+
+        let writes = instance_writes(&renderer);
+        assert_eq!(writes.len(), 1);
+
+        let instances = instances(&writes[0]);
+        assert_eq!(instances.len(), 100);
+        let nodes = renderer.scene_graph.mesh_instances().into_iter().map(|instance| instance.node).collect::<Vec<_>>();
+        for (node, (color, _, scale)) in nodes.iter().zip(&instances)
+        {
+            let object_info = &renderer.object_infos[node];
+            assert_eq!(*color, object_info.color);
+            assert_eq!(*scale, [object_info.scale / 2.0, object_info.scale]);
+        }
+    }
+
+
+    #[test]
+    fn objects_are_placed_by_their_nodes()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        renderer.render(&canvas_texture);
+        let backend = renderer.backend();
+
+        // every circle hangs below an object node holding its offset
+        let graph = &renderer.scene_graph;
+        assert_eq!(graph.roots().len(), 100);
+        assert_eq!(graph.len(), 200);
+        let instances = instances(&backend.buffer_contents(renderer.instance_buffer.buffer()).unwrap());
+        for (root, (color, offset, _)) in graph.roots().iter().zip(&instances)
+        {
+            let translation = graph.transform(*root).unwrap().translation;
+            assert_eq!(*offset, [translation.x, translation.y]);
+            assert!(offset.iter().all(|value| (-0.9..0.9).contains(value)));
+            assert_eq!(color[3], 1.0);
+        }
+
+        let indices = backend.buffer_contents(&renderer.index_buffer).unwrap();
+        assert_eq!(indices.len(), 144 * 4);
+        assert_eq!(&indices[..24], [0u32, 1, 2, 2, 1, 3].as_bytes());
+        assert_eq!(backend.buffer_contents(&renderer.vertex_buffer).unwrap().len(), 50 * 12);
+    }
+
+
+    #[test]
+    fn circles_outside_the_canvas_are_left_to_the_cull_pass()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        let roots = renderer.scene_graph.roots().to_vec();
+        renderer.scene_graph.transform_mut(roots[0]).unwrap().translation = Vec3::new(3.0, 0.0, 0.0);
+        renderer.render(&canvas_texture);
+        let backend = renderer.backend();
+
+        // every circle is uploaded, in the order of the scene graph
+        let instances = instances(&backend.buffer_contents(renderer.instance_buffer.buffer()).unwrap());
+        assert_eq!(instances.len(), 100);
+        assert_eq!(instances[0].1, [3.0, 0.0]);
+
+        // the planes of clip space, then the sphere and box around the circle
+        let culling = floats(&backend.buffer_contents(&renderer.culling_uniform_buffer).unwrap());
+        assert_eq!(culling[..8], [1.0, 0.0, 0.0, 1.0, -1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(culling[16..24], [0.0, 0.0, 1.0, 0.0, 0.0, 0.0, -1.0, 1.0]);
+        assert_eq!(culling[24..28], [0.0, 0.0, 0.0, 0.5]);
+        assert_eq!(culling[32..35], [0.5, 0.5, 0.0]);
+        assert_eq!(culling[36].to_bits(), 100);
+    }
+
+
+    #[test]
+    fn instances_are_only_uploaded_when_they_change()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        renderer.render(&canvas_texture);
+        renderer.backend().take_calls();
+
+        renderer.render(&canvas_texture);
+        assert_eq!(instance_writes(&renderer).len(), 0);
+
+        // the color alone doesn't move anything, but has to be uploaded all the same
+        let id = *renderer.instances.keys().next().unwrap();
+        let (color, offset, scale) = renderer.instance(id).unwrap();
+        renderer.update_instance(id, color, offset, scale).unwrap();
+        renderer.render(&canvas_texture);
+        renderer.render(&canvas_texture);
+        assert_eq!(instance_writes(&renderer).len(), 1);
+        renderer.update_instance(id, [1.0, 0.0, 0.0, 1.0], offset, scale).unwrap();
+        renderer.render(&canvas_texture);
+        assert_eq!(instance_writes(&renderer).len(), 1);
+
+        // the circles are scaled for the new aspect
+        let viewport = Viewport::new(renderer.backend(), 100.0, 100.0, 1.0);
+        renderer.set_viewport(viewport);
+        renderer.render(&canvas_texture);
+        let writes = instance_writes(&renderer);
+        assert_eq!(writes.len(), 1);
+        assert!(instances(&writes[0]).iter().all(|(_, _, scale)| scale[0] == scale[1]));
+    }
+
+
+    #[test]
+    fn instances_can_be_added_updated_and_removed()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        renderer.clear_instances();
+        assert_eq!(renderer.instance_count(), 0);
+        // nothing to draw still binds a buffer
+        renderer.render(&canvas_texture);
+        let culling = floats(&renderer.backend().buffer_contents(&renderer.culling_uniform_buffer).unwrap());
+        assert_eq!(culling[36].to_bits(), 0);
+
+        let ids = (0..3)
+            .map(|i| renderer.add_instance([1.0, 0.0, 0.0, 1.0], [i as f32 * 0.5, 0.0], 0.5))
+            .collect::<Vec<_>>();
+        renderer.update_instance(ids[2], [0.0, 0.0, 1.0, 1.0], [0.0, 0.5], 0.25).unwrap();
+        renderer.remove_instance(ids[1]).unwrap();
+        assert!(renderer.remove_instance(ids[1]).is_err());
+        assert!(renderer.update_instance(ids[1], [1.0; 4], [0.0; 2], 1.0).is_err());
+        assert!(renderer.instance(ids[1]).is_err());
+        assert_eq!(renderer.instance(ids[2]), Ok(([0.0, 0.0, 1.0, 1.0], [0.0, 0.5], 0.25)));
+        assert_eq!(renderer.instance_count(), 2);
+        assert_eq!(renderer.scene_graph.len(), 4);
+
+        renderer.render(&canvas_texture);
+        let backend = renderer.backend();
+        let instances = instances(&backend.buffer_contents(renderer.instance_buffer.buffer()).unwrap());
+        assert_eq!(instances[..2], [
+            ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0], [0.25, 0.5]),
+            ([0.0, 0.0, 1.0, 1.0], [0.0, 0.5], [0.125, 0.25]),
+        ]);
+        let culling = floats(&backend.buffer_contents(&renderer.culling_uniform_buffer).unwrap());
+        assert_eq!(culling[36].to_bits(), 2);
+    }
+
+
+    #[test]
+    fn instance_buffers_grow_and_are_rebound()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        renderer.render(&canvas_texture);
+        let bind_groups = (renderer.cull_bind_group, renderer.render_bind_group);
+        let buffers = (*renderer.instance_buffer.buffer(), *renderer.visible_instance_buffer.buffer());
+
+        renderer.add_random_instances(1);
+        renderer.render(&canvas_texture);
+        assert_eq!(renderer.instance_buffer.size(), 200 * INSTANCE_SIZE);
+        assert_eq!(renderer.visible_instance_buffer.size(), 200 * 4);
+        assert_ne!((renderer.cull_bind_group, renderer.render_bind_group), bind_groups);
+
+        // the old buffers are gone, the new ones hold every instance again
+        let backend = renderer.backend();
+        assert!(backend.calls().iter().any(|call| matches!(call, Call::DestroyBuffer(buffer) if *buffer == buffers.0)));
+        assert!(backend.calls().iter().any(|call| matches!(call, Call::DestroyBuffer(buffer) if *buffer == buffers.1)));
+        let uploaded = instances(&backend.buffer_contents(renderer.instance_buffer.buffer()).unwrap());
+        assert!(uploaded[..101].iter().all(|(color, _, _)| color[3] == 1.0));
+        assert_eq!(uploaded[101], ([0.0; 4], [0.0; 2], [0.0; 2]));
+
+        let bind_groups = (renderer.cull_bind_group, renderer.render_bind_group);
+        renderer.add_random_instances(99);
+        renderer.render(&canvas_texture);
+        assert_eq!((renderer.cull_bind_group, renderer.render_bind_group), bind_groups);
+
+        renderer.add_random_instances(10_000);
+        renderer.render(&canvas_texture);
+        assert_eq!(renderer.instance_buffer.size(), 10_200 * INSTANCE_SIZE);
+        assert_eq!(renderer.instance_count(), 10_200);
+    }
+
+
+    #[test]
+    fn a_seed_generates_the_same_scene()
+    {
+        let seeded = |seed|
+        {
+            let backend = RecordingBackend::new();
+            let viewport = Viewport::new(&backend, 300.0, 150.0, 1.0);
+            Renderer::new(backend, TextureFormat::Bgra8Unorm, 1, false, viewport, SceneRng::new(seed)).unwrap()
+        };
+        let mut renderer = seeded(7);
+        let parameters = renderer.object_parameters();
+        assert_eq!(renderer.seed(), 7);
+        assert_eq!(parameters.len(), 100 * 7);
+        assert_eq!(seeded(7).object_parameters(), parameters);
+        assert_ne!(seeded(8).object_parameters(), parameters);
+
+        // the parameters are what is drawn, in the same order
+        let canvas_texture = renderer.backend().create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        renderer.render(&canvas_texture);
+        let instances = instances(&renderer.backend().buffer_contents(renderer.instance_buffer.buffer()).unwrap());
+        for (object, (color, offset, scale)) in parameters.chunks(7).zip(&instances)
+        {
+            assert_eq!(object[..4], *color);
+            assert_eq!(object[4..6], *offset);
+            assert_eq!([object[6] / 2.0, object[6]], *scale);
+        }
+
+        // and so are instances added later
+        let mut other = seeded(7);
+        renderer.add_random_instances(3);
+        other.add_random_instances(3);
+        assert_eq!(other.object_parameters(), renderer.object_parameters());
+    }
+
+
+    #[test]
+    fn render_frame_uploads_the_frame_uniforms()
+    {
+        let (mut renderer, canvas_texture) = renderer(1);
+        let frame_uniforms = FrameUniforms
+        {
+            resolution: [300.0, 150.0], mouse: [10.0, 20.0], time: 1.5, delta_time: 0.016, frame_index: 90,
+        };
+        renderer.render_frame(&canvas_texture, &frame_uniforms);
+        let frame_values = renderer.backend().buffer_contents(&renderer.frame_uniform_buffer).unwrap();
+        assert_eq!(frame_values, frame_uniforms.to_bytes());
+
+        // render.wgsl doesn't animate, so its pipeline has no frame group to bind
+        assert!(renderer.frame_bind_group.is_none());
+        let frame_bind_groups = renderer.backend().commands().iter()
+            .filter(|command| matches!(command, Command::SetBindGroup { index: FRAME_GROUP, .. }))
+            .count();
+        assert_eq!(frame_bind_groups, 0);
+    }
+
+
+    #[test]
+    fn multisampled_render_resolves_into_the_canvas_texture()
+    {
+        let (mut renderer, canvas_texture) = renderer(4);
+        renderer.render(&canvas_texture);
+        renderer.render(&canvas_texture);
+
+        let backend = renderer.backend();
+        let calls = backend.calls();
+        let multisample_textures = calls.iter()
+            .filter(|call| matches!(call, Call::CreateTexture { descriptor, .. } if descriptor.sample_count == 4))
+            .count();
+        // the multisampled texture is reused while the canvas keeps its size
+        assert_eq!(multisample_textures, 1);
+
+        let canvas_views = calls.iter()
+            .filter_map(|call| match call
+            {
+                Call::CreateView { view, texture } if *texture == canvas_texture => Some(*view),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        drop(calls);
+        let resolve_targets = backend.commands().into_iter()
+            .filter_map(|command| match command
+            {
+                Command::BeginRenderPass(descriptor) => descriptor.color_attachments[0].resolve_target,
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(resolve_targets, canvas_views);
+    }
+
+
+    // the indices of the instances the cpu culls against the view keeps, in the order they are drawn
+    fn cpu_visible_instances<B: Backend>(renderer: &Renderer<B>, view: &Mat4) -> Vec<u32>
+    {
+        let nodes = renderer.scene_graph.mesh_instances().iter().map(|instance| instance.node).collect::<Vec<_>>();
+        renderer.scene_graph
+            .visible_mesh_instances(&Frustum::from_matrix(view), &renderer.mesh_bounds)
+            .iter()
+            .map(|instance| nodes.iter().position(|node| *node == instance.node).unwrap() as u32)
+            .collect()
+    }
+
+
+    // the instances are culled on the device before the survivors are drawn with the arguments the cull wrote
+    #[test]
+    fn the_gpu_keeps_the_instances_the_cpu_culling_keeps()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [32, 32], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 32.0, 32.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, 1, false, viewport, SceneRng::new(11),
+        ).unwrap();
+        // 200 instances take four workgroups, some of the added ones are moved out of the view or onto its edge
+        let ids = renderer.add_random_instances(100);
+        for id in ids.into_iter().step_by(7)
+        {
+            let (color, [_, y], scale) = renderer.instance(id).unwrap();
+            let x = if id % 2 == 0 { 1.6 } else { -1.0 - scale * 0.4 };
+            renderer.update_instance(id, color, [x, y], scale).unwrap();
+        }
+        renderer.render(&target);
+
+        let expected = cpu_visible_instances(&renderer, &Mat4::IDENTITY);
+        assert!(expected.len() > 150 && expected.len() < 200);
+
+        let draw_args = backend.buffer_contents(&renderer.draw_args_buffer).unwrap();
+        assert_eq!(draw_args, [144, expected.len() as u32, 0, 0, 0].as_bytes());
+        let visible = backend.buffer_contents(renderer.visible_instance_buffer.buffer()).unwrap();
+        assert_eq!(visible[..4 * expected.len()], *expected.as_bytes());
+
+        // without instances the scan still writes the count
+        renderer.clear_instances();
+        renderer.render(&target);
+        assert_eq!(backend.buffer_contents(&renderer.draw_args_buffer).unwrap(), [144u32, 0, 0, 0, 0].as_bytes());
+    }
+
+
+    // more workgroups than fit in a dimension are dispatched in rows, and more than one workgroup of counts
+    // takes the scan several chunks
+    #[test]
+    fn the_cull_passes_split_many_workgroups()
+    {
+        // 69 workgroups in 14 rows of 5, the last workgroup of the last row has nothing to do
+        let limits = Limits { max_compute_workgroups_per_dimension: 5, ..Limits::default() };
+        let view = Mat4::from_scale(Vec3::new(8.0, 8.0, 1.0));
+
+        let recording = RecordingBackend::new();
+        recording.set_limits(limits);
+        let canvas_texture = recording.create_texture(&TextureDescriptor::new(
+            TextureFormat::Bgra8Unorm, [300, 150], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&recording, 300.0, 150.0, 1.0);
+        let mut recorded = Renderer::new(
+            recording.clone(), TextureFormat::Bgra8Unorm, 1, false, viewport, SceneRng::new(17),
+        ).unwrap();
+        recorded.add_random_instances(4300);
+        recorded.render(&canvas_texture);
+        let dispatches = recording.commands().into_iter()
+            .filter_map(|command| match command
+            {
+                Command::DispatchWorkgroups { x, y, z } => Some([x, y, z]),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(dispatches, [[5, 14, 1], [1, 1, 1], [5, 14, 1]]);
+
+        let backend = SoftwareBackend::new();
+        backend.set_limits(limits);
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [8, 8], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 8.0, 8.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, 1, false, viewport, SceneRng::new(17),
+        ).unwrap();
+        renderer.add_random_instances(4300);
+        renderer.set_view(view);
+        renderer.render(&target);
+
+        let expected = cpu_visible_instances(&renderer, &view);
+        assert!(!expected.is_empty() && expected.len() < 1000);
+        let draw_args = backend.buffer_contents(&renderer.draw_args_buffer).unwrap();
+        assert_eq!(draw_args, [144, expected.len() as u32, 0, 0, 0].as_bytes());
+        let visible = backend.buffer_contents(renderer.visible_instance_buffer.buffer()).unwrap();
+        assert_eq!(visible[..4 * expected.len()], *expected.as_bytes());
+    }
+
+
+    #[test]
+    fn zoomed_in_views_cull_the_instances_out_of_sight()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [32, 32], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 32.0, 32.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, 1, false, viewport, SceneRng::new(13),
+        ).unwrap();
+        let view = Mat4::from_scale(Vec3::new(4.0, 4.0, 1.0)) * Mat4::from_translation(Vec3::new(-0.4, 0.3, 0.0));
+        renderer.set_view(view);
+        renderer.render(&target);
+
+        let expected = cpu_visible_instances(&renderer, &view);
+        assert!(!expected.is_empty() && expected.len() < 50, "{} of 100 visible", expected.len());
+
+        let draw_args = backend.buffer_contents(&renderer.draw_args_buffer).unwrap();
+        assert_eq!(draw_args, [144, expected.len() as u32, 0, 0, 0].as_bytes());
+        let visible = backend.buffer_contents(renderer.visible_instance_buffer.buffer()).unwrap();
+        assert_eq!(visible[..4 * expected.len()], *expected.as_bytes());
+
+        // the circles are drawn through the same view
+        let views = floats(&backend.buffer_contents(&renderer.view_uniform_buffer).unwrap());
+        assert_eq!(views, view.to_cols_array());
+    }
+
+
+    #[test]
+    fn the_golden_image_renders_through_the_renderer()
+    {
+        let backend = SoftwareBackend::new();
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [96, 96], RENDER_ATTACHMENT,
+        ));
+        let viewport = Viewport::new(&backend, 96.0, 96.0, 1.0);
+        let mut renderer = Renderer::new(
+            backend.clone(), TextureFormat::Rgba8Unorm, 1, false, viewport, SceneRng::new(5),
+        ).unwrap();
+        let frame_uniforms = FrameUniforms
+        {
+            resolution: [96.0, 96.0], time: 0.5, delta_time: 0.5, ..Default::default()
+        };
+        renderer.render_frame(&target, &frame_uniforms);
+
+        let image = backend.texture_image(&target).unwrap();
+        assert_golden(env!("CARGO_MANIFEST_DIR"), "vertex_buffers", &image);
+    }
+}
@@ -0,0 +1,375 @@
+use gpu_backend::
+{
+    Backend, AsBytes, BufferDescriptor, ShaderModuleDescriptor, VertexState, VertexAttribute, VertexFormat,
+    VertexBufferLayout, ColorTargetState, FragmentState, RenderPipelineDescriptor, BindGroupDescriptor, BindGroupEntry,
+    RenderPassEncoder, IndexFormat, MultisampleState, TextureFormat,
+};
+use gpu_backend::buffer_usage::{COPY_DST, VERTEX, INDEX, UNIFORM, STORAGE};
+
+use frame_loop::with_frame_prelude;
+
+use animation::{Clip, Easing, Interpolation, Joint, Repeat, Skeleton, SkinnedVertex, Timeline, TransformProperty};
+
+use math::{Mat4, Quat, Vec3};
+
+use scene_graph::Transform;
+
+
+// the joints of the chain from the base to the tip of the tentacle
+const JOINT_COUNT: usize = 4;
+
+// rows of vertices along the tentacle, without the one at the base
+const SEGMENTS: u32 = 12;
+
+const LENGTH: f32 = 1.2;
+
+// position and normal are vec3fs, then 4 joint indices of a byte each and 4 weights
+const VERTEX_SIZE: u64 = 3 * 4 + 3 * 4 + 4 + 4 * 4;
+
+// color is a vec4f, offset and scale are vec2fs
+const UNIFORMS_SIZE: u64 = 4 * 4 + 2 * 4 + 2 * 4;
+
+const COLOR: [f32; 4] = [0.9, 0.5, 0.7, 1.0];
+
+// where the base of the tentacle sits in clip space
+const OFFSET: [f32; 2] = [0.7, -0.9];
+
+const SCALE: f32 = 0.5;
+
+
+// a chain of joints standing up from the base, bound in this rest pose
+fn create_skeleton() -> Skeleton
+{
+    let spacing = LENGTH / (JOINT_COUNT - 1) as f32;
+    let joints = (0..JOINT_COUNT)
+        .map(|index| match index
+        {
+            0 => Joint::new("base", None, Transform::IDENTITY),
+            _ => Joint::new(
+                &format!("segment {}", index), Some(index - 1), Transform::from_translation(Vec3::new(0.0, spacing, 0.0)),
+            ),
+        })
+        .collect();
+    let mut skeleton = Skeleton::new(joints).unwrap();
+    skeleton.bind_rest_pose().unwrap();
+    skeleton
+}
+
+
+// the tentacle sways to either side and back, the joints further up bend more
+fn create_clip(skeleton: &Skeleton) -> Clip
+{
+    let mut clip = Clip::new("sway", skeleton);
+    for joint in 0..JOINT_COUNT
+    {
+        let angle = if joint == 0 { 0.1 } else { 0.3 };
+        let rotations = [angle, -angle, angle].map(|angle| Quat::from_rotation_z(angle).to_array()).concat();
+        clip.set_track(
+            joint, TransformProperty::Rotation, Interpolation::Linear, Easing::SineInOut, vec![0.0, 1.0, 2.0], &rotations,
+        ).unwrap();
+    }
+    clip
+}
+
+
+// a tapering strip of three vertices per row, the normals of the outer ones point sideways so it shades like a tube.
+// every vertex is bound to the two joints it lies between
+fn create_tentacle_vertices() -> (Vec<SkinnedVertex>, Vec<u32>)
+{
+    let spacing = LENGTH / (JOINT_COUNT - 1) as f32;
+    let mut vertices = Vec::with_capacity((SEGMENTS as usize + 1) * 3);
+    for row in 0..=SEGMENTS
+    {
+        let along = row as f32 / SEGMENTS as f32;
+        let y = along * LENGTH;
+        let half_width = 0.1 * (1.0 - 0.8 * along);
+
+        let joint = ((y / spacing) as usize).min(JOINT_COUNT - 2);
+        let weight = y / spacing - joint as f32;
+        let joints = [joint as u32, joint as u32 + 1, 0, 0];
+        let weights = [1.0 - weight, weight, 0.0, 0.0];
+
+        for (x, normal) in [(-half_width, -Vec3::X), (0.0, Vec3::Z), (half_width, Vec3::X)]
+        {
+            vertices.push(SkinnedVertex { position: Vec3::new(x, y, 0.0), normal, joints, weights });
+        }
+    }
+
+    // row r holds the vertices 3r, 3r + 1 and 3r + 2, from left to right
+    let mut indices = Vec::with_capacity(SEGMENTS as usize * 12);
+    for row in 0..SEGMENTS
+    {
+        for column in 0..2
+        {
+            let bottom = row * 3 + column;
+            let top = bottom + 3;
+            indices.extend_from_slice(&[bottom, bottom + 1, top, top, bottom + 1, top + 1]);
+        }
+    }
+    (vertices, indices)
+}
+
+
+fn encode_vertices(vertices: &[SkinnedVertex]) -> Vec<u8>
+{
+    let mut vertex_data = Vec::with_capacity(vertices.len() * VERTEX_SIZE as usize);
+    for vertex in vertices
+    {
+        vertex_data.extend_from_slice(vertex.position.as_array().as_bytes());
+        vertex_data.extend_from_slice(vertex.normal.as_array().as_bytes());
+        vertex_data.extend(vertex.joints.map(|joint| joint as u8));
+        vertex_data.extend_from_slice(vertex.weights.as_bytes());
+    }
+    vertex_data
+}
+
+
+// a tentacle in the corner of the canvas, skinned in the vertex shader with the joint matrices of the pose at the
+// time of its timeline, see skinned.wgsl
+pub struct SkinnedMesh<B: Backend>
+{
+    skeleton: Skeleton,
+    clip: Clip,
+    timeline: Timeline,
+    index_count: u32,
+    vertex_buffer: B::Buffer,
+    index_buffer: B::Buffer,
+    uniform_buffer: B::Buffer,
+    joint_buffer: B::Buffer,
+    pipeline: B::RenderPipeline,
+    bind_group: B::BindGroup,
+}
+
+
+impl<B: Backend> SkinnedMesh<B>
+{
+    pub fn new(backend: &B, texture_format: TextureFormat, multisample_state: MultisampleState) -> Self
+    {
+        let source = with_frame_prelude(include_str!("../shader/skinned.wgsl"));
+        let mut shader_module_descriptor = ShaderModuleDescriptor::new(&source);
+        shader_module_descriptor.label("skinning shaders");
+        let shader_module = backend.create_shader_module(&shader_module_descriptor);
+
+        let mut vertex_state = VertexState::new("vertex_main", &shader_module);
+        vertex_state.buffers(&[VertexBufferLayout::new(
+            VERTEX_SIZE,
+            &[
+                VertexAttribute::new(VertexFormat::Float32x3, 0, 0),     // position
+                VertexAttribute::new(VertexFormat::Float32x3, 12, 1),    // normal
+                VertexAttribute::new(VertexFormat::Uint8x4, 24, 2),      // joints
+                VertexAttribute::new(VertexFormat::Float32x4, 28, 3),    // weights
+            ],
+        )]);
+
+        let fragment_state = FragmentState::new("fragment_main", &shader_module, &[ColorTargetState::new(texture_format)]);
+
+        let mut pipeline_descriptor = RenderPipelineDescriptor::new(vertex_state);
+        pipeline_descriptor
+            .label("skinned tentacle")
+            .fragment(fragment_state)
+            .multisample(multisample_state);
+        let pipeline = backend.create_render_pipeline(&pipeline_descriptor);
+
+        let (vertices, indices) = create_tentacle_vertices();
+        let vertex_data = encode_vertices(&vertices);
+
+        let mut vertex_buffer_descriptor = BufferDescriptor::new(vertex_data.len() as u64, VERTEX | COPY_DST);
+        vertex_buffer_descriptor.label("skinned vertices");
+        let vertex_buffer = backend.create_buffer(&vertex_buffer_descriptor);
+        backend.write_buffer(&vertex_buffer, 0, &vertex_data);
+
+        let mut index_buffer_descriptor = BufferDescriptor::new(indices.as_bytes().len() as u64, INDEX | COPY_DST);
+        index_buffer_descriptor.label("skinned indices");
+        let index_buffer = backend.create_buffer(&index_buffer_descriptor);
+        backend.write_buffer(&index_buffer, 0, indices.as_bytes());
+
+        let mut uniform_buffer_descriptor = BufferDescriptor::new(UNIFORMS_SIZE, UNIFORM | COPY_DST);
+        uniform_buffer_descriptor.label("uniforms for the skinned tentacle");
+        let uniform_buffer = backend.create_buffer(&uniform_buffer_descriptor);
+
+        // a mat4x4f per joint
+        let mut joint_buffer_descriptor = BufferDescriptor::new(JOINT_COUNT as u64 * 16 * 4, STORAGE | COPY_DST);
+        joint_buffer_descriptor.label("joint matrices");
+        let joint_buffer = backend.create_buffer(&joint_buffer_descriptor);
+
+        let mut bind_group_descriptor = BindGroupDescriptor::new(
+            vec![BindGroupEntry::buffer(0, &uniform_buffer), BindGroupEntry::buffer(1, &joint_buffer)],
+            &backend.render_bind_group_layout(&pipeline, 0),
+        );
+        bind_group_descriptor.label("bind group for skinning");
+        let bind_group = backend.create_bind_group(&bind_group_descriptor);
+
+        let skeleton = create_skeleton();
+        let clip = create_clip(&skeleton);
+        let timeline = Timeline::new(clip.duration(), Repeat::Loop);
+
+        SkinnedMesh
+        {
+            skeleton, clip, timeline, index_count: indices.len() as u32, vertex_buffer, index_buffer,
+            uniform_buffer, joint_buffer, pipeline, bind_group,
+        }
+    }
+
+
+    pub fn timeline_mut(&mut self) -> &mut Timeline
+    {
+        &mut self.timeline
+    }
+
+
+    // the joint matrices of the pose at the time of the timeline
+    pub fn joint_matrices(&self) -> Vec<Mat4>
+    {
+        let pose = self.clip.sample(self.timeline.local_time(), &self.skeleton);
+        self.skeleton.joint_matrices(&pose)
+    }
+
+
+    // uploads the pose and the placement, scaled down along x by the aspect like the circles
+    pub fn write(&self, backend: &B, aspect: f32)
+    {
+        let joint_values = self.joint_matrices().iter().flat_map(Mat4::to_cols_array).collect::<Vec<f32>>();
+        backend.write_buffer(&self.joint_buffer, 0, joint_values.as_bytes());
+
+        let uniform_values = [COLOR.as_slice(), &OFFSET, &[SCALE / aspect, SCALE]].concat();
+        backend.write_buffer(&self.uniform_buffer, 0, uniform_values.as_bytes());
+    }
+
+
+    pub fn draw(&self, render_pass_encoder: &mut RenderPassEncoder<'_, B>)
+    {
+        render_pass_encoder.set_pipeline(&self.pipeline);
+        render_pass_encoder.set_bind_group(0, &self.bind_group);
+        render_pass_encoder.set_vertex_buffer(0, &self.vertex_buffer);
+        render_pass_encoder.set_index_buffer(&self.index_buffer, IndexFormat::Uint32);
+        render_pass_encoder.draw_indexed(self.index_count, 1);
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use gpu_backend::
+    {
+        Command, CommandEncoder, LoadOp, RenderPassColorAttachment, RenderPassDescriptor, StoreOp, TextureDescriptor,
+    };
+    use gpu_backend::recording::RecordingBackend;
+    use gpu_backend::texture_usage::RENDER_ATTACHMENT;
+
+    use software_gpu::SoftwareBackend;
+    use software_gpu::rasterizer::Image;
+
+    use animation::skin;
+
+    use super::*;
+
+
+    fn floats(data: &[u8]) -> Vec<f32>
+    {
+        data.chunks(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect()
+    }
+
+
+    #[test]
+    fn vertices_are_bound_to_the_joints_around_them()
+    {
+        let (vertices, indices) = create_tentacle_vertices();
+        assert_eq!(vertices.len(), 39);
+        assert_eq!(indices.len(), 144);
+        assert!(vertices.iter().all(|vertex| (vertex.weights.iter().sum::<f32>() - 1.0).abs() < 1e-6));
+        assert_eq!((vertices[0].joints, vertices[0].weights), ([0, 1, 0, 0], [1.0, 0.0, 0.0, 0.0]));
+        assert_eq!((vertices[38].joints, vertices[38].weights), ([2, 3, 0, 0], [0.0, 1.0, 0.0, 0.0]));
+
+        // in the bind pose nothing moves, swaying bends the tip aside
+        let skeleton = create_skeleton();
+        let rest = skeleton.joint_matrices(&skeleton.rest_pose());
+        assert!(vertices.iter().all(|vertex| skin(vertex, &rest).0.abs_diff_eq(vertex.position, 1e-5)));
+
+        let clip = create_clip(&skeleton);
+        let swayed = skeleton.joint_matrices(&clip.sample(0.0, &skeleton));
+        let (tip, tip_normal) = skin(&vertices[37], &swayed);
+        assert!(tip.x < -0.4 && tip.y < LENGTH);
+        assert!(tip_normal.abs_diff_eq(Vec3::Z, 1e-5));
+        let (side, side_normal) = skin(&vertices[38], &swayed);
+        assert!(side.y > tip.y && side_normal.y > 0.5);
+    }
+
+
+    #[test]
+    fn write_uploads_the_pose_of_the_timeline()
+    {
+        let backend = RecordingBackend::new();
+        let mut mesh = SkinnedMesh::new(&backend, TextureFormat::Bgra8Unorm, MultisampleState::new());
+        mesh.timeline_mut().advance(2.5);
+        mesh.write(&backend, 2.0);
+
+        let joint_values = floats(&backend.buffer_contents(&mesh.joint_buffer).unwrap());
+        let pose = mesh.clip.sample(0.5, &mesh.skeleton);
+        let expected = mesh.skeleton.joint_matrices(&pose).iter().flat_map(Mat4::to_cols_array).collect::<Vec<_>>();
+        assert_eq!(joint_values, expected);
+        assert_eq!(floats(&backend.buffer_contents(&mesh.uniform_buffer).unwrap()), [
+            0.9, 0.5, 0.7, 1.0, 0.7, -0.9, 0.25, 0.5,
+        ]);
+
+        let mut command_encoder = CommandEncoder::new();
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&gpu_backend::RenderPassDescriptor::new(vec![]));
+        mesh.draw(&mut render_pass_encoder);
+        render_pass_encoder.end();
+        backend.submit(vec![command_encoder.finish()]);
+        assert!(backend.commands().iter().any(|command| matches!(
+            command, Command::DrawIndexed { index_count: 144, instance_count: 1, .. },
+        )));
+    }
+
+
+    // draws just the mesh on the software gpu, with the joint matrices and vertices it holds
+    fn render(backend: &SoftwareBackend, mesh: &SkinnedMesh<SoftwareBackend>) -> Image
+    {
+        let target = backend.create_texture(&TextureDescriptor::new(
+            TextureFormat::Rgba8Unorm, [128, 128], RENDER_ATTACHMENT,
+        ));
+        let mut color_attachment = RenderPassColorAttachment::new(
+            LoadOp::Clear, StoreOp::Store, &backend.create_view(&target),
+        );
+        color_attachment.clear_value([0.3, 0.3, 0.3, 1.0]);
+
+        let mut command_encoder = CommandEncoder::new();
+        let mut render_pass_encoder = command_encoder.begin_render_pass(&RenderPassDescriptor::new(vec![color_attachment]));
+        mesh.draw(&mut render_pass_encoder);
+        render_pass_encoder.end();
+        backend.submit(vec![command_encoder.finish()]);
+        backend.texture_image(&target).unwrap()
+    }
+
+
+    #[test]
+    fn skinning_matches_the_cpu_reference()
+    {
+        let backend = SoftwareBackend::new();
+        let mut mesh = SkinnedMesh::new(&backend, TextureFormat::Rgba8Unorm, MultisampleState::new());
+        // a second in the tentacle sways all the way to the other side
+        mesh.timeline_mut().advance(1.0);
+        mesh.write(&backend, 1.0);
+        let skinned = render(&backend, &mesh);
+
+        // the same tentacle skinned on the cpu, drawn with joint matrices that leave it as it is
+        let (vertices, _) = create_tentacle_vertices();
+        let joint_matrices = mesh.joint_matrices();
+        let skinned_on_the_cpu = vertices.iter()
+            .map(|vertex|
+            {
+                let (position, normal) = skin(vertex, &joint_matrices);
+                SkinnedVertex { position, normal, ..*vertex }
+            })
+            .collect::<Vec<_>>();
+        let identity = [Mat4::IDENTITY; JOINT_COUNT].iter().flat_map(Mat4::to_cols_array).collect::<Vec<f32>>();
+        backend.write_buffer(&mesh.joint_buffer, 0, identity.as_bytes());
+        backend.write_buffer(&mesh.vertex_buffer, 0, &encode_vertices(&skinned_on_the_cpu));
+        skinned.compare(&render(&backend, &mesh), 2).unwrap();
+
+        // and the pose did bend it
+        backend.write_buffer(&mesh.vertex_buffer, 0, &encode_vertices(&vertices));
+        assert!(skinned.compare(&render(&backend, &mesh), 2).is_err());
+    }
+}
@@ -1,4 +1,6 @@
-import init, { Scene } from "../wasm/fundamentals.js";
+import init, { Scene, FrameLoop } from "../wasm/fundamentals.js";
+
+export { FrameLoop };
 
 
 export async function initFundamentals(device, context, gpuTextureFormat) {
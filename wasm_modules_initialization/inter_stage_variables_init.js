@@ -1,4 +1,6 @@
-import init, { Scene } from "../wasm/inter_stage_variables.js";
+import init, { Scene, FrameLoop } from "../wasm/inter_stage_variables.js";
+
+export { FrameLoop };
 
 
 export async function initInterStageVariables(device, context, gpuTextureFormat) {
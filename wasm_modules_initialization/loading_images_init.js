@@ -1,4 +1,6 @@
-import init, { Scene } from "../wasm/loading_images.js";
+import init, { Scene, FrameLoop } from "../wasm/loading_images.js";
+
+export { FrameLoop };
 
 
 export async function initLoadingImages(device, context, gpuTextureFormat, imageBitmap) {
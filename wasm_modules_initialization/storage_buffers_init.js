@@ -1,8 +1,13 @@
-import init, { Scene } from "../wasm/storage_buffers.js";
+import init, { Scene, FrameLoop, InstanceProperty, Interpolation, Easing, Repeat } from "../wasm/storage_buffers.js";
 
+export { FrameLoop, InstanceProperty, Interpolation, Easing, Repeat };
 
-export async function initStorageBuffers(device, context, gpuTextureFormat) {
+
+// a seed of null generates a different scene every time
+export async function initStorageBuffers(device, context, gpuTextureFormat, seed = null) {
     await init();
-    const scene = Scene.create(device, context, gpuTextureFormat);
+    const scene = seed === null
+        ? Scene.create(device, context, gpuTextureFormat)
+        : Scene.create_with_seed(device, context, gpuTextureFormat, seed);
     return scene;    
 }
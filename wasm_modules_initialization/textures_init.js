@@ -1,4 +1,8 @@
-import init, { Scene } from "../wasm/textures.js";
+import init, {
+    Scene, Camera, CameraController, Projection, FrameLoop, TransformProperty, Interpolation, Easing, Repeat,
+} from "../wasm/textures.js";
+
+export { Camera, CameraController, Projection, FrameLoop, TransformProperty, Interpolation, Easing, Repeat };
 
 
 export async function initTextures(device, context, gpuTextureFormat) {
@@ -1,8 +1,13 @@
-import init, { Scene } from "../wasm/vertex_buffers.js";
+import init, { Scene, FrameLoop, Interpolation, Easing } from "../wasm/vertex_buffers.js";
 
+export { FrameLoop, Interpolation, Easing };
 
-export async function initVertexBuffers(device, context, gpuTextureFormat) {
+
+// a seed of null generates a different scene every time
+export async function initVertexBuffers(device, context, gpuTextureFormat, seed = null) {
     await init();
-    const scene = Scene.create(device, context, gpuTextureFormat);
+    const scene = seed === null
+        ? Scene.create(device, context, gpuTextureFormat)
+        : Scene.create_with_seed(device, context, gpuTextureFormat, seed);
     return scene;    
 }